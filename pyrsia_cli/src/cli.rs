@@ -14,5 +14,9 @@
    limitations under the License.
 */
 
+pub mod daemon;
+pub mod doctor;
 pub mod handlers;
+pub mod output;
 pub mod parser;
+pub mod progress;
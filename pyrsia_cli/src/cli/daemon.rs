@@ -0,0 +1,153 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use pyrsia::cli_commands::config;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const NODE_EXECUTABLE: &str = "pyrsia_node";
+const PID_FILE: &str = "pyrsia_node.pid";
+/// How long to wait for the node to stop, which includes the time it gives
+/// in-flight requests to finish.
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Whether the node that was started with `pyrsia node start` is running.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DaemonStatus {
+    pub pid_file: PathBuf,
+    pub pid: Option<u32>,
+    pub running: bool,
+}
+
+/// The PID file of the node that is started by the CLI, next to its config file.
+pub fn pid_file_path() -> anyhow::Result<PathBuf> {
+    let config_file_path = config::get_config_file_path()?;
+    let config_dir = config_file_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid config file path"))?;
+    Ok(config_dir.join(PID_FILE))
+}
+
+pub fn daemon_status() -> anyhow::Result<DaemonStatus> {
+    let pid_file = pid_file_path()?;
+    let pid = fs::read_to_string(&pid_file)
+        .ok()
+        .and_then(|pid| pid.trim().parse().ok());
+    Ok(DaemonStatus {
+        running: pid.map(is_running).unwrap_or(false),
+        pid_file,
+        pid,
+    })
+}
+
+/// Starts pyrsia_node in the background with the specified extra arguments
+/// and returns its process id. The node executable is looked up next to the
+/// CLI executable first and on the PATH otherwise.
+pub fn start_daemon(node_args: &[String]) -> anyhow::Result<u32> {
+    let status = daemon_status()?;
+    if let (Some(pid), true) = (status.pid, status.running) {
+        anyhow::bail!("Pyrsia Node is already running with process id {}", pid);
+    }
+
+    let exit_status = Command::new(node_executable())
+        .arg("--daemon")
+        .arg("--pid-file")
+        .arg(&status.pid_file)
+        .args(node_args)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Unable to run {}: {}", NODE_EXECUTABLE, e))?;
+    if !exit_status.success() {
+        anyhow::bail!("{} exited with {}", NODE_EXECUTABLE, exit_status);
+    }
+    daemon_status()?
+        .pid
+        .ok_or_else(|| anyhow::anyhow!("Pyrsia Node did not write its PID file"))
+}
+
+/// Asks the node to shut down gracefully and waits until it stopped.
+/// Returns the process id of the stopped node.
+pub fn stop_daemon() -> anyhow::Result<u32> {
+    let status = daemon_status()?;
+    let pid = match (status.pid, status.running) {
+        (Some(pid), true) => pid,
+        (Some(_), false) => {
+            // The node didn't get to clean up its PID file.
+            let _ = fs::remove_file(&status.pid_file);
+            anyhow::bail!("Pyrsia Node is not running")
+        }
+        (None, _) => anyhow::bail!("Pyrsia Node is not running"),
+    };
+
+    terminate(pid)?;
+    let started_at = Instant::now();
+    while is_running(pid) {
+        if started_at.elapsed() > STOP_TIMEOUT {
+            anyhow::bail!(
+                "Pyrsia Node with process id {} did not stop within {} seconds",
+                pid,
+                STOP_TIMEOUT.as_secs()
+            );
+        }
+        thread::sleep(STOP_POLL_INTERVAL);
+    }
+    Ok(pid)
+}
+
+fn node_executable() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|cli_executable| {
+            let node_executable = cli_executable
+                .with_file_name(NODE_EXECUTABLE)
+                .with_extension(env::consts::EXE_EXTENSION);
+            node_executable.is_file().then_some(node_executable)
+        })
+        .unwrap_or_else(|| PathBuf::from(NODE_EXECUTABLE))
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // Signal 0 only checks whether the process exists.
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32) -> anyhow::Result<()> {
+    // The node shuts down gracefully on SIGTERM.
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        anyhow::bail!(
+            "Unable to stop Pyrsia Node with process id {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: u32) -> anyhow::Result<()> {
+    anyhow::bail!("Stopping Pyrsia Node is only supported on unix")
+}
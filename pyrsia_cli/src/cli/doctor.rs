@@ -0,0 +1,332 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use pyrsia::cli_commands::node;
+use pyrsia::node_api::model::cli::{DiskUsage, Status};
+use pyrsia::util::disk_space_util::format_disk_space;
+use pyrsia::util::keystore::Keystore;
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+/// The upstream registries the node and its clients fall back to when an
+/// artifact is not available on the Pyrsia network.
+const UPSTREAM_REGISTRIES: [(&str, &str); 3] = [
+    ("Docker Hub", "https://registry-1.docker.io/v2/"),
+    ("Maven Central", "https://repo1.maven.org/maven2/"),
+    (
+        "Pyrsia mapping service",
+        "https://mapping-service.pyrsia.io/",
+    ),
+];
+
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Signatures and API tokens are time sensitive, so a clock that is off by
+/// more than this is reported.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The outcome of a single diagnostic check, with an actionable message
+/// when something is wrong.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub check: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(check: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Finding {
+            check: check.to_owned(),
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Runs all diagnostic checks of the node and the CLI. The checks of the
+/// node are skipped when it cannot be reached.
+pub async fn run_checks(keystore: anyhow::Result<Keystore>) -> Vec<Finding> {
+    let mut findings = vec![];
+    match node::status().await {
+        Ok(status) => {
+            findings.push(Finding::new(
+                "node",
+                Severity::Ok,
+                format!("Node {} is reachable at {}", status.version, node::get_url()),
+            ));
+            findings.push(check_p2p_listening(&status));
+            findings.push(check_peers(&status));
+            findings.push(check_nat(&status));
+            findings.push(check_disk_headroom(&status.disk_usage));
+        }
+        Err(error) => findings.push(Finding::new(
+            "node",
+            Severity::Error,
+            format!(
+                "Node is not reachable at {}: {}. Check that the node is running and 'pyrsia config --show'",
+                node::get_url(),
+                error
+            ),
+        )),
+    }
+
+    let mut upstream_date = None;
+    let client = reqwest::Client::builder()
+        .timeout(UPSTREAM_TIMEOUT)
+        .build()
+        .unwrap();
+    for (name, url) in UPSTREAM_REGISTRIES {
+        // any response, including an authentication challenge, means the
+        // registry is reachable
+        match client.get(url).send().await {
+            Ok(response) => {
+                upstream_date = upstream_date.or_else(|| {
+                    response
+                        .headers()
+                        .get(reqwest::header::DATE)
+                        .and_then(|date| date.to_str().ok())
+                        .and_then(|date| httpdate::parse_http_date(date).ok())
+                });
+                findings.push(Finding::new(
+                    "upstream",
+                    Severity::Ok,
+                    format!("{} is reachable", name),
+                ));
+            }
+            Err(error) => findings.push(Finding::new(
+                "upstream",
+                Severity::Warning,
+                format!(
+                    "{} is not reachable at {}: {}. Check the proxy and firewall settings of this host",
+                    name, url, error
+                ),
+            )),
+        }
+    }
+    findings.push(check_clock_skew(SystemTime::now(), upstream_date));
+
+    findings.push(check_keys(keystore));
+    findings
+}
+
+fn check_p2p_listening(status: &Status) -> Finding {
+    if status.peer_addrs.is_empty() {
+        Finding::new(
+            "p2p",
+            Severity::Error,
+            "The node does not listen on any p2p address. Check the --listen option of the node",
+        )
+    } else {
+        Finding::new(
+            "p2p",
+            Severity::Ok,
+            format!("Listening on {}", status.peer_addrs.join(", ")),
+        )
+    }
+}
+
+fn check_peers(status: &Status) -> Finding {
+    if status.peers_count == 0 {
+        Finding::new(
+            "peers",
+            Severity::Warning,
+            "The node is not connected to any peer. Connect to a peer with 'pyrsia connect <multiaddr>'",
+        )
+    } else {
+        Finding::new(
+            "peers",
+            Severity::Ok,
+            format!("Connected to {} peers", status.peers_count),
+        )
+    }
+}
+
+fn check_nat(status: &Status) -> Finding {
+    match status.nat_status.as_str() {
+        "public" => Finding::new("nat", Severity::Ok, "The node is publicly reachable"),
+        "private" => Finding::new(
+            "nat",
+            Severity::Warning,
+            "The node is behind a NAT and other peers cannot dial it. Forward the p2p port and set PYRSIA_EXTERNAL_IP",
+        ),
+        _ => Finding::new(
+            "nat",
+            Severity::Warning,
+            "The reachability of the node is not known yet, it is probed by connected peers",
+        ),
+    }
+}
+
+fn check_disk_headroom(disk_usage: &DiskUsage) -> Finding {
+    let used = format_disk_space(disk_usage.used_bytes);
+    match disk_usage.allocated_bytes {
+        Some(allocated_bytes) if disk_usage.used_bytes >= allocated_bytes => Finding::new(
+            "disk",
+            Severity::Error,
+            format!(
+                "The disk allocation of {} is used up, new artifacts are rejected. Run 'pyrsia admin gc' or raise the allocation",
+                format_disk_space(allocated_bytes)
+            ),
+        ),
+        Some(allocated_bytes) if disk_usage.used_bytes >= allocated_bytes / 10 * 9 => {
+            Finding::new(
+                "disk",
+                Severity::Warning,
+                format!(
+                    "{} of {} is used. Run 'pyrsia admin gc' or raise the allocation",
+                    used,
+                    format_disk_space(allocated_bytes)
+                ),
+            )
+        }
+        Some(allocated_bytes) => Finding::new(
+            "disk",
+            Severity::Ok,
+            format!("{} of {} is used", used, format_disk_space(allocated_bytes)),
+        ),
+        None => Finding::new(
+            "disk",
+            Severity::Ok,
+            format!("{} is used, without allocation", used),
+        ),
+    }
+}
+
+fn check_clock_skew(now: SystemTime, upstream_date: Option<SystemTime>) -> Finding {
+    let upstream_date = match upstream_date {
+        Some(upstream_date) => upstream_date,
+        None => {
+            return Finding::new(
+                "clock",
+                Severity::Warning,
+                "The clock could not be compared, no upstream registry is reachable",
+            )
+        }
+    };
+    // the date header only has a precision of a second
+    let skew = now
+        .duration_since(upstream_date)
+        .or_else(|_| upstream_date.duration_since(now))
+        .unwrap_or_default();
+    if skew > MAX_CLOCK_SKEW {
+        Finding::new(
+            "clock",
+            Severity::Warning,
+            format!(
+                "The clock is off by {} s. Synchronize it with NTP, signatures and API tokens are time sensitive",
+                skew.as_secs()
+            ),
+        )
+    } else {
+        Finding::new("clock", Severity::Ok, "The clock is synchronized")
+    }
+}
+
+fn check_keys(keystore: anyhow::Result<Keystore>) -> Finding {
+    match keystore.and_then(|keystore| Ok(keystore.entries()?)) {
+        Ok(entries) if entries.iter().any(|entry| entry.private) => Finding::new(
+            "keys",
+            Severity::Ok,
+            format!("The keystore holds {} keys", entries.len()),
+        ),
+        Ok(_) => Finding::new(
+            "keys",
+            Severity::Warning,
+            "The keystore holds no keypair. Generate one with 'pyrsia keys generate <name>'",
+        ),
+        Err(error) => Finding::new(
+            "keys",
+            Severity::Error,
+            format!("The keystore cannot be read: {}", error),
+        ),
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_p2p_and_peers() {
+        let mut status = Status::default();
+        assert_eq!(check_p2p_listening(&status).severity, Severity::Error);
+        assert_eq!(check_peers(&status).severity, Severity::Warning);
+
+        status.peer_addrs = vec![String::from("/ip4/127.0.0.1/tcp/44000")];
+        status.peers_count = 2;
+        assert_eq!(check_p2p_listening(&status).severity, Severity::Ok);
+        assert_eq!(check_peers(&status).severity, Severity::Ok);
+    }
+
+    #[test]
+    fn test_check_nat() {
+        let mut status = Status::default();
+        assert_eq!(check_nat(&status).severity, Severity::Warning);
+        status.nat_status = String::from("private");
+        assert_eq!(check_nat(&status).severity, Severity::Warning);
+        status.nat_status = String::from("public");
+        assert_eq!(check_nat(&status).severity, Severity::Ok);
+    }
+
+    #[test]
+    fn test_check_disk_headroom() {
+        let disk_usage = |used_bytes, allocated_bytes| DiskUsage {
+            used_bytes,
+            allocated_bytes,
+            ..Default::default()
+        };
+        assert_eq!(
+            check_disk_headroom(&disk_usage(100, None)).severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_disk_headroom(&disk_usage(100, Some(1000))).severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_disk_headroom(&disk_usage(950, Some(1000))).severity,
+            Severity::Warning
+        );
+        assert_eq!(
+            check_disk_headroom(&disk_usage(1000, Some(1000))).severity,
+            Severity::Error
+        );
+    }
+
+    #[test]
+    fn test_check_clock_skew() {
+        let now = SystemTime::now();
+        assert_eq!(check_clock_skew(now, None).severity, Severity::Warning);
+        assert_eq!(
+            check_clock_skew(now, Some(now - Duration::from_secs(5))).severity,
+            Severity::Ok
+        );
+        assert_eq!(
+            check_clock_skew(now, Some(now + Duration::from_secs(120))).severity,
+            Severity::Warning
+        );
+    }
+}
@@ -14,56 +14,138 @@
    limitations under the License.
 */
 
+use crate::cli::daemon;
+use crate::cli::doctor::{self, Severity};
+use crate::cli::output::{
+    output_format, print_error, print_message, print_output, set_output_format, OutputFormat,
+};
+use crate::cli::parser::cli_command;
+use crate::cli::progress::{ProgressBar, WithTransfer};
 use crate::CONF_FILE_PATH_MSG_STARTER;
+use clap_complete::Shell;
+use pyrsia::artifact_service::events::NodeEventKind;
+use pyrsia::artifact_service::hashing::{ArtifactDigest, HashAlgorithm};
+use pyrsia::artifact_service::model::PackageType;
+use pyrsia::artifact_service::trust_store::{fingerprint, fingerprint_of, TrustRole};
 use pyrsia::cli_commands::config;
 use pyrsia::cli_commands::node;
+use pyrsia::logging::buffer::LogEntry;
+use pyrsia::network::peer_identity::ConnectionDirection;
+use pyrsia::network::public_keys::Jwk;
+use pyrsia::network::signed_document::SignedDocument;
+use pyrsia::network::transfer_metrics::TransferCounters;
 use pyrsia::node_api::handlers::swarm;
 use pyrsia::node_api::model::cli::{
-    RequestAddAuthorizedNode, RequestBuildStatus, RequestDockerBuild, RequestDockerLog,
-    RequestMavenBuild, RequestMavenLog, TransparencyLogOutputParams,
+    LogQuery, RequestAddAuthorizedNode, RequestBench, RequestBuildStatus, RequestDockerBuild,
+    RequestDockerLog, RequestMavenBuild, RequestMavenLog, RequestTrustKey, RequestVerify,
+    SearchQuery, Status, TransparencyLogOutputParams,
 };
-use std::collections::HashSet;
+use pyrsia::node_api::pagination::ListQuery;
+use pyrsia::transparency_log::key_rotation::KeyRotationCertificate;
+use pyrsia::util::disk_space_util::format_disk_space;
+use pyrsia::util::env_util::read_var;
+use pyrsia::util::keystore::Keystore;
+use serde_json::json;
+use std::fs;
 use std::io;
 use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const KEYSTORE_PASSPHRASE_VAR: &str = "PYRSIA_KEYSTORE_PASSPHRASE";
 
 const CONF_REMINDER_MESSAGE: &str = "Please make sure the pyrsia CLI config is up to date and matches the node configuration. For more information, run 'pyrsia config --show'";
 
 pub fn config_add() -> anyhow::Result<()> {
-    let default_config = config::CliConfig {
-        ..Default::default()
-    };
+    // the current values are the defaults, so pressing enter keeps them
+    let current_config = config::get_config().unwrap_or_default();
+    println!("Current configuration:\n{}", current_config);
 
     let mut new_cfg = config::CliConfig {
         host: read_interactive_input(
-            &format!("Enter host: [{}]", default_config.host),
-            &default_config.host,
+            &format!("Enter host: [{}]", current_config.host),
+            &current_config.host,
             &config::valid_host_name,
         ),
         ..Default::default()
     };
     new_cfg.port = read_interactive_input(
-        &format!("Enter port: [{}]", default_config.port),
-        &default_config.port,
+        &format!("Enter port: [{}]", current_config.port),
+        &current_config.port,
         &config::valid_port,
     );
     new_cfg.disk_allocated = read_interactive_input(
         &format!(
-            "Enter disk space to be allocated to pyrsia(Please enter with units ex: 10 GB): [{}]",
-            default_config.disk_allocated
+            "Enter disk space to be allocated to pyrsia(Please enter with units ex: 512 MB, 10 GB or 1 TB): [{}]",
+            current_config.disk_allocated
         ),
-        &default_config.disk_allocated,
+        &current_config.disk_allocated,
         &config::valid_disk_space,
     );
+    new_cfg.scheme = read_interactive_input(
+        &format!("Enter scheme, http or https: [{}]", current_config.scheme),
+        &current_config.scheme,
+        &config::valid_scheme,
+    );
+    new_cfg.ca_cert = read_interactive_input(
+        &format!(
+            "Enter the path of a PEM file with the CA certificate of the node, if it is not signed by a public CA: [{}]",
+            current_config.ca_cert
+        ),
+        &current_config.ca_cert,
+        &config::valid_ca_cert,
+    );
 
     config::add_config(new_cfg)
 }
 
+pub fn completion(shell: Shell) {
+    clap_complete::generate(shell, &mut cli_command(), "pyrsia", &mut io::stdout());
+}
+
+pub fn select_output_format(output_format: &str) {
+    set_output_format(OutputFormat::from_str(output_format).unwrap_or_default());
+}
+
+pub fn select_profile(profile: Option<&String>) {
+    if profile.is_some() {
+        config::set_profile(profile.cloned());
+    }
+}
+
 pub fn config_edit(
     host_name: Option<String>,
     port: Option<String>,
     disk_space: Option<String>,
+    scheme: Option<String>,
+    ca_cert: Option<String>,
 ) -> anyhow::Result<()> {
-    config::config_edit(host_name, port, disk_space)
+    config::config_edit(host_name, port, disk_space, scheme, ca_cert)
+}
+
+pub fn login(token: Option<&String>) {
+    let token = match token {
+        Some(token) => token.to_owned(),
+        None => read_interactive_input("Enter API token:", "", &|token| {
+            if token.is_empty() {
+                Err(String::from("The API token must not be empty"))
+            } else {
+                Ok(token)
+            }
+        }),
+    };
+    match config::store_api_token(&token) {
+        Ok(()) => print_message("API token stored, the CLI presents it to the node"),
+        Err(error) => print_error(&format!("Error storing API token: {}", error)),
+    }
+}
+
+pub fn logout() {
+    match config::remove_api_token() {
+        Ok(()) => print_message("API token removed"),
+        Err(error) => print_error(&format!("Error removing API token: {}", error)),
+    }
 }
 
 pub fn config_remove() -> anyhow::Result<()> {
@@ -71,6 +153,24 @@ pub fn config_remove() -> anyhow::Result<()> {
 }
 
 pub fn config_show() {
+    if output_format() != OutputFormat::Text {
+        let config_file_path = config::get_config_file_path()
+            .map(|path_buf| path_buf.into_os_string().into_string().unwrap())
+            .ok();
+        let config = config::get_config().ok();
+        print_output(
+            &json!({
+                "config_file_path": config_file_path,
+                "profile": config::active_profile(),
+                "profiles": config::get_profiles().unwrap_or_default(),
+                "config": config,
+                "api_token_stored": config::api_token().is_some(),
+            }),
+            |_| {},
+        );
+        return;
+    }
+
     match config::get_config_file_path() {
         Ok(path_buf) => {
             println!(
@@ -83,10 +183,21 @@ pub fn config_show() {
             println!("Error retrieving config file path: {}", error);
         }
     }
+    if let Some(profile) = config::active_profile() {
+        println!("Profile: {}", profile);
+    }
+    if let Ok(profiles) = config::get_profiles() {
+        if !profiles.is_empty() {
+            println!("Available profiles: {}", profiles.join(", "));
+        }
+    }
     let result = config::get_config();
     match result {
         Ok(config) => {
-            println!("{}", config)
+            println!("{}", config);
+            if config::api_token().is_some() {
+                println!("An API token is stored, remove it with 'pyrsia logout'");
+            }
         }
         Err(error) => {
             println!("No Node Configured: {}", error);
@@ -100,8 +211,8 @@ pub async fn authorize(peer_id: &str) {
     })
     .await
     {
-        Ok(()) => println!("Authorize request successfully handled."),
-        Err(error) => println!("Authorize request failed with error: {}", error),
+        Ok(()) => print_message("Authorize request successfully handled."),
+        Err(error) => print_error(&format!("Authorize request failed with error: {}", error)),
     };
 }
 
@@ -128,13 +239,16 @@ pub async fn request_build_status(build_id: &str) {
     .await;
 
     match result {
-        Ok(build_status) => println!("Build status for '{}' is '{}'", build_id, build_status),
+        Ok(build_status) => print_output(
+            &json!({ "build_id": build_id, "status": build_status }),
+            |_| println!("Build status for '{}' is '{}'", build_id, build_status),
+        ),
         Err(e) => {
-            println!(
+            print_error(&format!(
                 "Build status for '{}' was not found: {}
 Additional info related to the build might be available via 'pyrsia inspect-log' command",
                 build_id, e
-            );
+            ));
         }
     }
 }
@@ -142,55 +256,781 @@ Additional info related to the build might be available via 'pyrsia inspect-log'
 fn handle_request_build_result(build_result: Result<String, anyhow::Error>) {
     match build_result {
         Ok(build_id) => {
-            println!(
-                "Build request successfully handled. Build with ID '{}' has been started.",
-                build_id
-            );
+            print_output(&json!({ "build_id": build_id }), |_| {
+                println!(
+                    "Build request successfully handled. Build with ID '{}' has been started.",
+                    build_id
+                )
+            });
         }
         Err(error_message) => {
-            println!("Build request failed with error: {}", error_message);
+            print_error(&format!(
+                "Build request failed with error: {}",
+                error_message
+            ));
         }
     }
 }
 
 pub async fn node_ping() {
+    let started_at = Instant::now();
     let result = node::ping().await;
     match result {
-        Ok(_resp) => {
-            println!("Connection Successful !!")
+        Ok(ping) => {
+            let round_trip_ms = started_at.elapsed().as_millis();
+            print_output(
+                &json!({
+                    "node": node::get_url(),
+                    "round_trip_ms": round_trip_ms,
+                    "version": ping.version,
+                    "peer_id": ping.peer_id,
+                }),
+                |_| {
+                    println!("Connection Successful !!");
+                    println!("Node:        {}", node::get_url());
+                    println!("Round trip:  {} ms", round_trip_ms);
+                    println!("Version:     {}", ping.version);
+                    println!("Peer ID:     {}", ping.peer_id);
+                },
+            );
         }
+        Err(error) => print_node_error(error),
+    };
+}
+
+pub async fn node_events() {
+    let result = node::follow_events(|event| {
+        if output_format() != OutputFormat::Text {
+            print_output(&event, |_| {});
+            return;
+        }
+        let name = event.kind.name();
+        let details = match event.kind {
+            NodeEventKind::ArtifactStored { artifact_id }
+            | NodeEventKind::ArtifactServed { artifact_id }
+            | NodeEventKind::ArtifactEvicted { artifact_id } => artifact_id,
+            NodeEventKind::ArtifactVerified { artifact_id, valid } => {
+                format!(
+                    "{} ({})",
+                    artifact_id,
+                    if valid { "valid" } else { "corrupted" }
+                )
+            }
+            NodeEventKind::PeerConnected { peer_id }
+            | NodeEventKind::PeerDisconnected { peer_id } => peer_id,
+        };
+        println!("{} {:<20} {}", event.timestamp, name, details);
+    })
+    .await;
+    if let Err(error) = result {
+        print_node_error(error);
+    }
+}
+
+pub async fn node_logs(level: Option<String>, lines: usize, follow: bool) {
+    let query = LogQuery {
+        level,
+        lines: Some(lines),
+    };
+    let result = if follow {
+        node::follow_logs(&query, |entry| {
+            print_output(&entry, |entry| println!("{}", format_log_entry(entry)))
+        })
+        .await
+    } else {
+        node::node_logs(&query).await.map(|entries| {
+            print_output(&entries, |entries| {
+                for entry in entries {
+                    println!("{}", format_log_entry(entry));
+                }
+            })
+        })
+    };
+    if let Err(error) = result {
+        print_node_error(error);
+    }
+}
+
+fn format_log_entry(entry: &LogEntry) -> String {
+    format!(
+        "{} {:<5} {} > {}",
+        entry.timestamp, entry.level, entry.target, entry.message
+    )
+}
+
+pub async fn prefetch_image(image: &str) {
+    let prefetched_image = match node::prefetch_image(image).await {
+        Ok(prefetched_image) => prefetched_image,
         Err(error) => {
-            println!("Error: {}. {}", error, CONF_REMINDER_MESSAGE);
+            print_node_error(error);
+            return;
         }
     };
+    print_output(&prefetched_image, |prefetched_image| {
+        let rows: Vec<Vec<String>> = prefetched_image
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                vec![
+                    artifact.part.to_string(),
+                    artifact.reference.clone(),
+                    format_disk_space(artifact.size),
+                    String::from(if artifact.cached { "cached" } else { "fetched" }),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            format_table(&["PART", "REFERENCE", "SIZE", "STATUS"], &rows)
+        );
+        let (cached, fetched): (Vec<_>, Vec<_>) = prefetched_image
+            .artifacts
+            .iter()
+            .partition(|artifact| artifact.cached);
+        println!(
+            "Prefetched {}: {} artifacts fetched ({}), {} already cached",
+            prefetched_image.image,
+            fetched.len(),
+            format_disk_space(fetched.iter().map(|artifact| artifact.size).sum()),
+            cached.len()
+        );
+    });
+}
+
+pub async fn search_artifacts(name: &str, package_type: Option<&String>, network: bool) {
+    let package_type = package_type.map(|package_type| match package_type.as_str() {
+        "maven2" => PackageType::Maven2,
+        "generic" => PackageType::Generic,
+        _ => PackageType::Docker,
+    });
+    let query = SearchQuery {
+        q: name.to_owned(),
+        package_type,
+        network,
+    };
+    match node::search_artifacts(&query).await {
+        Ok(results) => print_output(&results, |results| {
+            if results.is_empty() {
+                println!("No artifacts found for {}", name);
+                return;
+            }
+            let rows = results
+                .iter()
+                .map(|result| {
+                    let mut locations = vec![];
+                    if result.stored_locally {
+                        locations.push(String::from("local"));
+                    }
+                    locations.extend(result.providers.iter().cloned());
+                    vec![
+                        result.name.clone(),
+                        result.package_type.to_string(),
+                        result
+                            .artifact_hash
+                            .clone()
+                            .unwrap_or_else(|| String::from("-")),
+                        locations.join(", "),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            println!(
+                "{}",
+                format_table(&["NAME", "TYPE", "HASH", "AVAILABLE FROM"], &rows)
+            );
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub fn daemon_start(node_args: &[String]) {
+    match daemon::start_daemon(node_args) {
+        Ok(pid) => print_output(&json!({ "pid": pid }), |_| {
+            println!("Pyrsia Node started with process id {}", pid)
+        }),
+        Err(error) => print_error(&format!("Error: {}", error)),
+    }
+}
+
+pub fn daemon_stop() {
+    match daemon::stop_daemon() {
+        Ok(pid) => print_output(&json!({ "pid": pid }), |_| {
+            println!("Pyrsia Node with process id {} stopped", pid)
+        }),
+        Err(error) => print_error(&format!("Error: {}", error)),
+    }
+}
+
+pub async fn daemon_status() {
+    match daemon::daemon_status() {
+        Ok(status) => {
+            let node_api_reachable = node::ping().await.is_ok();
+            print_output(
+                &json!({
+                    "pid_file": status.pid_file,
+                    "pid": status.pid,
+                    "running": status.running,
+                    "node_api": node::get_url(),
+                    "node_api_reachable": node_api_reachable,
+                }),
+                |_| {
+                    match (status.pid, status.running) {
+                        (Some(pid), true) => {
+                            println!("Pyrsia Node is running with process id {}", pid)
+                        }
+                        _ => println!(
+                            "Pyrsia Node is not running in the background (no process in {})",
+                            status.pid_file.display()
+                        ),
+                    }
+                    let reachability = if node_api_reachable {
+                        "reachable"
+                    } else {
+                        "not reachable"
+                    };
+                    println!("Node API {} is {}", node::get_url(), reachability);
+                },
+            )
+        }
+        Err(error) => print_error(&format!("Error: {}", error)),
+    }
 }
 
 pub async fn node_status() {
     let result = node::status().await;
     match result {
-        Ok(resp) => {
-            println!("Connected Peers Count:       {}", resp.peers_count);
+        Ok(resp) => print_output(&resp, print_status),
+        Err(error) => print_node_error(error),
+    }
+}
+
+/// Poll the status of the node every `interval_secs` seconds and redraw it
+/// in place, with the transfer rates since the previous poll, until the
+/// user interrupts the command. In the structured output formats every
+/// poll is printed as a separate document instead.
+pub async fn doctor() {
+    let findings = doctor::run_checks(cli_keystore()).await;
+    print_output(&findings, |findings| {
+        for finding in findings {
+            let label = match finding.severity {
+                Severity::Ok => "OK",
+                Severity::Warning => "WARN",
+                Severity::Error => "FAIL",
+            };
+            println!("[{:<4}] {:<8} {}", label, finding.check, finding.message);
         }
-        Err(error) => {
-            println!("Error: {}. {}", error, CONF_REMINDER_MESSAGE);
+        let problems = findings
+            .iter()
+            .filter(|finding| finding.severity != Severity::Ok)
+            .count();
+        println!();
+        match problems {
+            0 => println!("No problems found"),
+            1 => println!("1 problem found"),
+            _ => println!("{} problems found", problems),
         }
+    });
+}
+
+pub async fn node_status_watch(interval_secs: u64) {
+    let interval = Duration::from_secs(interval_secs);
+    let mut previous: Option<(Instant, TransferCounters)> = None;
+    loop {
+        let result = node::status().await;
+        let polled_at = Instant::now();
+        if output_format() == OutputFormat::Text {
+            // clear the terminal and move the cursor to the top left
+            print!("\x1B[2J\x1B[H");
+        }
+        match result {
+            Ok(resp) => {
+                print_output(&resp, |resp| {
+                    print_status(resp);
+                    if let Some((previous_at, previous_total)) = &previous {
+                        let elapsed = polled_at.duration_since(*previous_at);
+                        let total = &resp.transfer_metrics.total;
+                        println!(
+                            "Transfer Rate:               {}/s in, {}/s out",
+                            format_disk_space(transfer_rate(
+                                previous_total.bytes_in,
+                                total.bytes_in,
+                                elapsed
+                            )),
+                            format_disk_space(transfer_rate(
+                                previous_total.bytes_out,
+                                total.bytes_out,
+                                elapsed
+                            ))
+                        );
+                    }
+                    println!();
+                    println!("Refreshing every {} s, press Ctrl-C to stop", interval_secs);
+                });
+                previous = Some((polled_at, resp.transfer_metrics.total));
+            }
+            // keep polling, the node may just be restarting
+            Err(error) => {
+                print_node_error(error);
+                previous = None;
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// The number of bytes per second transferred between two readings of a
+// counter. A counter that went down means the node was restarted in between.
+fn transfer_rate(previous: u64, current: u64, elapsed: Duration) -> u64 {
+    let millis = elapsed.as_millis() as u64;
+    if millis == 0 {
+        return 0;
+    }
+    current.saturating_sub(previous) * 1000 / millis
+}
+
+fn print_status(resp: &Status) {
+    println!("Node Version:                {}", resp.version);
+    println!("Peer ID:                     {}", resp.peer_id);
+    println!("Uptime:                      {} s", resp.uptime_secs);
+    println!("Connected Peers Count:       {}", resp.peers_count);
+    if !resp.nat_status.is_empty() {
+        println!("NAT Status:                  {}", resp.nat_status);
+    }
+    println!("Build Fallbacks:             {}", resp.build_fallbacks);
+    let transfers = &resp.transfer_metrics.total;
+    println!(
+        "Artifacts Served:            {} ({} bytes)",
+        transfers.artifacts_served, transfers.bytes_out
+    );
+    println!(
+        "Artifacts Fetched:           {} ({} bytes)",
+        transfers.artifacts_fetched, transfers.bytes_in
+    );
+    println!(
+        "Transfer Failures:           {} served, {} fetched",
+        transfers.serve_failures, transfers.fetch_failures
+    );
+    let disk_used = format_disk_space(resp.disk_usage.used_bytes);
+    match resp.disk_usage.allocated_bytes {
+        Some(allocated_bytes) => println!(
+            "Disk Usage:                  {} of {}",
+            disk_used,
+            format_disk_space(allocated_bytes)
+        ),
+        None => println!("Disk Usage:                  {}", disk_used),
+    }
+    println!(
+        "Evicted Artifacts:           {} ({})",
+        resp.disk_usage.evictions.evicted_artifacts,
+        format_disk_space(resp.disk_usage.evictions.evicted_bytes)
+    );
+    let rate_limits = &resp.rate_limits;
+    if let Some(requests_per_minute) = rate_limits.requests_per_minute {
+        println!(
+            "Rate Limit:                  {} requests per minute per client",
+            requests_per_minute
+        );
+    }
+    if let Some(max_concurrent_downloads) = rate_limits.max_concurrent_downloads {
+        println!(
+            "Downloads:                   {} of {} concurrent",
+            rate_limits.active_downloads, max_concurrent_downloads
+        );
+    }
+    if rate_limits.rejected_requests > 0 {
+        println!(
+            "Rejected Requests:           {}",
+            rate_limits.rejected_requests
+        );
+    }
+    if let Some(scrub) = &resp.last_scrub {
+        println!(
+            "Last Scrub:                  {} checked, {} corrupted, {} fetched again, {} unverifiable",
+            scrub.artifacts_checked,
+            scrub.artifacts_corrupted,
+            scrub.artifacts_refetched,
+            scrub.artifacts_unverifiable
+        );
+    }
+    for usage in &resp.repository_usage {
+        let repository = match (usage.package_type, &usage.repository) {
+            (Some(package_type), Some(repository)) => format!("{} {}", package_type, repository),
+            (Some(package_type), None) => package_type.to_string(),
+            _ => "Unknown".to_owned(),
+        };
+        println!(
+            "{:<29}{} artifacts ({}), {} local pulls, {} served to peers",
+            format!("Usage {}:", repository),
+            usage.artifacts,
+            format_disk_space(usage.size_bytes),
+            usage.local_pulls,
+            usage.peer_serves
+        );
     }
 }
 
 pub async fn node_list() {
     let result = node::peers_connected().await;
     match result {
-        Ok(resp) => {
+        Ok(resp) => print_output(&resp, |resp| {
             println!("Connected Peers:");
-            let peers_split = resp.split(',');
-            let mut unique_peers = HashSet::new();
-            for peer in peers_split {
-                unique_peers.insert(peer);
+            resp.iter().for_each(|p| {
+                let latency = match p.latency_ms {
+                    Some(latency_ms) => format!("{} ms", latency_ms),
+                    None => String::from("-"),
+                };
+                let direction = match p.direction {
+                    Some(ConnectionDirection::Inbound) => "inbound",
+                    Some(ConnectionDirection::Outbound) => "outbound",
+                    None => "-",
+                };
+                let quality_metric = match p.quality_metric {
+                    Some(quality_metric) => format!("{:.2}", quality_metric),
+                    None => String::from("-"),
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    p.peer_id,
+                    latency,
+                    direction,
+                    quality_metric,
+                    p.agent_version.as_deref().unwrap_or("-")
+                );
+            });
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn list_artifacts(
+    offset: usize,
+    limit: usize,
+    sort: Option<String>,
+    filter: Option<String>,
+) {
+    let result = node::list_artifacts(ListQuery {
+        offset,
+        limit,
+        sort,
+        filter,
+    })
+    .await;
+    match result {
+        Ok(resp) => print_output(&resp, |resp| {
+            println!(
+                "Stored Artifacts ({} to {} of {}):",
+                (resp.offset + 1).min(resp.total),
+                resp.offset + resp.items.len(),
+                resp.total
+            );
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let rows = resp
+                .items
+                .iter()
+                .map(|a| {
+                    vec![
+                        a.artifact_hash.clone().unwrap_or_else(|| String::from("-")),
+                        format_disk_space(a.size),
+                        a.package_type
+                            .map(|package_type| package_type.to_string())
+                            .unwrap_or_else(|| String::from("-")),
+                        a.package_specific_artifact_id
+                            .clone()
+                            .unwrap_or_else(|| String::from("-")),
+                        format_age(now, a.last_access),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            println!(
+                "{}",
+                format_table(
+                    &["HASH", "SIZE", "TYPE", "REPOSITORY", "LAST ACCESS"],
+                    &rows
+                )
+            );
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn inspect_artifact(reference: &str) {
+    let result = node::inspect_artifact(reference).await;
+    match result {
+        Ok(inspections) => print_output(&inspections, |inspections| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            for inspection in inspections {
+                println!("Artifact {}", inspection.artifact_hash);
+                if let Some(revocation) = &inspection.revocation {
+                    println!("  REVOKED: {}", revocation.reason);
+                }
+
+                println!("  Stored copies:");
+                if inspection.stored.is_empty() {
+                    println!("    none");
+                }
+                for a in &inspection.stored {
+                    println!(
+                        "    {}  {}  {}  {} pulls, {} serves, last access {}{}",
+                        a.artifact_id,
+                        format_disk_space(a.size),
+                        a.media_type.as_deref().unwrap_or("-"),
+                        a.local_pulls,
+                        a.peer_serves,
+                        format_age(now, a.last_access),
+                        if a.pinned { ", pinned" } else { "" }
+                    );
+                }
+
+                println!("  Signatures:");
+                if inspection.attestations.is_empty() {
+                    println!("    none");
+                }
+                for attestation in &inspection.attestations {
+                    println!(
+                        "    {} signed by {} ({})",
+                        attestation.predicate_type,
+                        attestation.signers.join(", "),
+                        if attestation.valid {
+                            "valid"
+                        } else {
+                            "INVALID"
+                        }
+                    );
+                }
+
+                println!("  Transparency log:");
+                for transparency_log in &inspection.transparency_logs {
+                    println!(
+                        "    {}  {}  {}  by {}",
+                        transparency_log.id,
+                        transparency_log.operation,
+                        transparency_log.package_specific_artifact_id,
+                        transparency_log.node_id
+                    );
+                }
+
+                println!("  Provided by:");
+                if inspection.providers.is_empty() {
+                    println!("    no peers");
+                }
+                for peer_id in &inspection.providers {
+                    println!("    {}", peer_id);
+                }
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn push_artifact(file: &str, name: Option<&String>) {
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None => match Path::new(file).file_name() {
+            Some(file_name) => file_name.to_string_lossy().into_owned(),
+            None => {
+                print_error(&format!(
+                    "Error: {} has no file name, specify one with --name",
+                    file
+                ));
+                return;
+            }
+        },
+    };
+    let artifact = match fs::read(file) {
+        Ok(artifact) => artifact,
+        Err(error) => {
+            print_error(&format!("Error reading {}: {}", file, error));
+            return;
+        }
+    };
+    let artifact_hash = ArtifactDigest {
+        algorithm: HashAlgorithm::Sha256,
+        hex: HashAlgorithm::Sha256.hash(&artifact),
+    }
+    .to_string();
+
+    let progress_bar = ProgressBar::new("Pushing");
+    let result = node::push_artifact(&name, artifact, progress_bar.on_progress()).await;
+    let transfer = progress_bar.finish(&format!("to the node at {}", node::get_url()));
+    match result {
+        Ok(pushed) if pushed.artifact_hash != artifact_hash => {
+            print_error(&format!(
+                "Error: the node recorded {} as {} while its content hashes to {}",
+                name, pushed.artifact_hash, artifact_hash
+            ));
+        }
+        Ok(pushed) => {
+            let output = WithTransfer {
+                result: &pushed,
+                transfer: &transfer,
+            };
+            print_output(&output, |output| {
+                println!(
+                    "Pushed {} as {}",
+                    output.result.name, output.result.artifact_hash
+                );
+                println!("Artifact ID: {}", output.result.artifact_id);
+                output.transfer.print("Pushed");
+            });
+        }
+        Err(error) => {
+            print_error(&format!("Push request failed with error: {}", error));
+        }
+    }
+}
+
+pub async fn verify(target: &str) {
+    // A target that is an existing file is a signed document, anything else
+    // is the hash of a stored artifact.
+    let request = if Path::new(target).is_file() {
+        let document = fs::read_to_string(target)
+            .map_err(|error| error.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<SignedDocument>(&content).map_err(|error| error.to_string())
+            });
+        match document {
+            Ok(document) => RequestVerify {
+                document: Some(document),
+                ..Default::default()
+            },
+            Err(error) => {
+                print_error(&format!(
+                    "Error reading signed document {}: {}",
+                    target, error
+                ));
+                return;
             }
-            unique_peers.iter().for_each(|p| println!("{}", p));
+        }
+    } else {
+        RequestVerify {
+            artifact_hash: Some(target.to_owned()),
+            ..Default::default()
+        }
+    };
+
+    match node::verify(request).await {
+        Ok(report) => print_output(&report, |report| {
+            println!("Signers:");
+            if report.signers.is_empty() {
+                println!("  none");
+            }
+            for signer in &report.signers {
+                let roles = signer
+                    .trusted_roles
+                    .iter()
+                    .map(|role| role.to_string())
+                    .collect::<Vec<_>>();
+                println!(
+                    "  {} signed the {} ({}), trusted for: {}",
+                    signer.peer_id,
+                    signer.signed,
+                    if signer.valid { "valid" } else { "INVALID" },
+                    if roles.is_empty() {
+                        String::from("nothing")
+                    } else {
+                        roles.join(", ")
+                    }
+                );
+            }
+            if report.transparency_logs > 0 {
+                println!("Transparency log entries: {}", report.transparency_logs);
+            }
+            if let Some(reason) = &report.revocation {
+                println!("REVOKED: {}", reason);
+            }
+            match &report.policy_violation {
+                Some(violation) => println!("Policy: rejected, {}", violation),
+                None => println!("Policy: accepted"),
+            }
+            println!(
+                "Verification {}",
+                if report.valid { "succeeded" } else { "FAILED" }
+            );
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn export_artifacts(file: &str) {
+    let archive = match fs::File::create(file) {
+        Ok(archive) => archive,
+        Err(error) => {
+            print_error(&format!("Error writing {}: {}", file, error));
+            return;
+        }
+    };
+    let progress_bar = ProgressBar::new("Exporting");
+    let result =
+        node::export_artifacts(io::BufWriter::new(archive), progress_bar.on_progress()).await;
+    let transfer = progress_bar.finish(&format!("from the node at {}", node::get_url()));
+    match result {
+        Ok(_) => {
+            let output = WithTransfer {
+                result: &json!({ "file": file }),
+                transfer: &transfer,
+            };
+            print_output(&output, |output| {
+                println!("Artifacts exported to {}", file);
+                output.transfer.print("Exported");
+            });
         }
         Err(error) => {
-            println!("Error: {}. {}", error, CONF_REMINDER_MESSAGE);
+            // don't leave an incomplete archive behind
+            let _ = fs::remove_file(file);
+            print_node_error(error);
+        }
+    }
+}
+
+pub async fn import_artifacts(file: &str) {
+    let (archive, size) = match fs::File::open(file)
+        .and_then(|archive| archive.metadata().map(|metadata| (archive, metadata.len())))
+    {
+        Ok(archive) => archive,
+        Err(error) => {
+            print_error(&format!("Error reading {}: {}", file, error));
+            return;
+        }
+    };
+    let progress_bar = ProgressBar::new("Importing");
+    let result = node::import_artifacts(
+        io::BufReader::new(archive),
+        size,
+        progress_bar.on_progress(),
+    )
+    .await;
+    let transfer = progress_bar.finish(&format!("to the node at {}", node::get_url()));
+    match result {
+        Ok(resp) => {
+            let output = WithTransfer {
+                result: &resp,
+                transfer: &transfer,
+            };
+            print_output(&output, |output| {
+                println!(
+                    "Imported {} artifacts from {}",
+                    output.result.artifact_ids.len(),
+                    file
+                );
+                if !output.result.rejected_artifact_ids.is_empty() {
+                    println!(
+                        "Rejected {} artifacts that have no transparency log on the node:",
+                        output.result.rejected_artifact_ids.len()
+                    );
+                    for artifact_id in output.result.rejected_artifact_ids.iter() {
+                        println!("  {}", artifact_id);
+                    }
+                }
+                output.transfer.print("Uploaded");
+            });
+        }
+        Err(error) => {
+            print_error(&format!("Import request failed with error: {}", error));
         }
     }
 }
@@ -210,7 +1050,10 @@ pub async fn inspect_docker_transparency_log(image: &str, format: Option<String>
             content_type.print_logs(logs);
         }
         Err(error) => {
-            println!("Inspect log request failed with error: {:?}", error);
+            print_error(&format!(
+                "Inspect log request failed with error: {:?}",
+                error
+            ));
         }
     };
 }
@@ -230,9 +1073,398 @@ pub async fn inspect_maven_transparency_log(gav: &str, format: Option<String>) {
             content_type.print_logs(logs);
         }
         Err(error) => {
-            println!("Inspect log request failed with error: {:?}", error);
+            print_error(&format!(
+                "Inspect log request failed with error: {:?}",
+                error
+            ));
+        }
+    };
+}
+
+pub fn keys_generate(name: &str) {
+    let result = cli_keystore()
+        .and_then(|keystore| Ok(keystore.generate(name, &read_keystore_passphrase())?));
+    match result {
+        Ok(keypair) => {
+            let peer_id = keypair.public().to_peer_id().to_string();
+            print_output(&json!({ "name": name, "peer_id": peer_id }), |_| {
+                println!("Generated key {} with peer ID {}", name, peer_id)
+            });
+        }
+        Err(error) => print_error(&format!("Error generating key {}: {}", name, error)),
+    }
+}
+
+pub fn keys_list() {
+    match cli_keystore().and_then(|keystore| Ok(keystore.entries()?)) {
+        Ok(entries) => {
+            let keys = entries
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "name": entry.name,
+                        "type": if entry.private { "keypair" } else { "public" },
+                        "peer_id": entry
+                            .public_key
+                            .as_ref()
+                            .map(|public_key| public_key.to_peer_id().to_string()),
+                        "fingerprint": entry.public_key.as_ref().map(fingerprint),
+                    })
+                })
+                .collect::<Vec<_>>();
+            print_output(&keys, |keys| {
+                if keys.is_empty() {
+                    println!("The keystore is empty");
+                    return;
+                }
+                let rows = keys
+                    .iter()
+                    .map(|key| {
+                        ["name", "type", "peer_id", "fingerprint"]
+                            .iter()
+                            .map(|field| key[field].as_str().unwrap_or("-").to_owned())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    format_table(&["NAME", "TYPE", "PEER ID", "FINGERPRINT"], &rows)
+                );
+            });
+        }
+        Err(error) => print_error(&format!("Error listing keys: {}", error)),
+    }
+}
+
+pub fn keys_export(name: &str, file: Option<&String>) {
+    let result = cli_keystore().and_then(|keystore| {
+        let public_key = match keystore.public_key(name)? {
+            Some(public_key) => public_key,
+            // Key files of older versions only hold the encrypted keypair.
+            None => keystore.load(name, &read_keystore_passphrase())?.public(),
+        };
+        Jwk::from_public_key(&public_key)
+            .ok_or_else(|| anyhow::anyhow!("Only Ed25519 keys can be exported"))
+    });
+    match (result, file) {
+        (Ok(jwk), Some(file)) => match fs::write(file, serde_json::to_string_pretty(&jwk).unwrap())
+        {
+            Ok(()) => print_output(&json!({ "name": name, "file": file }), |_| {
+                println!("Public key {} exported to {}", name, file)
+            }),
+            Err(error) => print_error(&format!("Error writing {}: {}", file, error)),
+        },
+        (Ok(jwk), None) => print_output(&jwk, |jwk| {
+            println!("{}", serde_json::to_string_pretty(jwk).unwrap())
+        }),
+        (Err(error), _) => print_error(&format!("Error exporting key {}: {}", name, error)),
+    }
+}
+
+pub fn keys_import(name: &str, file: &str) {
+    let result = cli_keystore().and_then(|keystore| {
+        let jwk: Jwk = serde_json::from_slice(&fs::read(file)?)?;
+        let public_key = jwk
+            .to_public_key()
+            .ok_or_else(|| anyhow::anyhow!("{} does not hold a valid Ed25519 public key", file))?;
+        keystore.import_public_key(name, &public_key)?;
+        Ok(public_key)
+    });
+    match result {
+        Ok(public_key) => {
+            let peer_id = public_key.to_peer_id().to_string();
+            print_output(&json!({ "name": name, "peer_id": peer_id }), |_| {
+                println!("Imported public key {} with peer ID {}", name, peer_id)
+            });
+        }
+        Err(error) => print_error(&format!("Error importing key {}: {}", name, error)),
+    }
+}
+
+pub fn keys_show(name: &str) {
+    let result =
+        cli_keystore().and_then(|keystore| Ok(keystore.load(name, &read_keystore_passphrase())?));
+    match result {
+        Ok(keypair) => {
+            let peer_id = keypair.public().to_peer_id().to_string();
+            let fingerprint = fingerprint(&keypair.public());
+            print_output(
+                &json!({ "name": name, "peer_id": peer_id, "fingerprint": fingerprint }),
+                |_| {
+                    println!("{}", peer_id);
+                    println!("fingerprint: {}", fingerprint);
+                },
+            );
+        }
+        Err(error) => print_error(&format!("Error loading key {}: {}", name, error)),
+    }
+}
+
+pub async fn keys_rotate(name: &str, new_name: &str) {
+    let result = cli_keystore().and_then(|keystore| {
+        let passphrase = read_keystore_passphrase();
+        let previous_keypair = keystore.load(name, &passphrase)?;
+        let next_keypair = keystore.generate(new_name, &passphrase)?;
+        KeyRotationCertificate::new(&previous_keypair, &next_keypair)
+    });
+    let certificate = match result {
+        Ok(certificate) => certificate,
+        Err(error) => {
+            print_error(&format!("Error rotating key {}: {}", name, error));
+            return;
         }
     };
+    match node::add_key_rotation(certificate).await {
+        Ok(()) => print_output(&json!({ "name": name, "new_name": new_name }), |_| {
+            println!("Key {} rotated to {}", name, new_name)
+        }),
+        Err(error) => {
+            print_error(&format!(
+                "Key {} was created, but publishing the rotation failed with error: {}. {}",
+                new_name, error, CONF_REMINDER_MESSAGE
+            ));
+        }
+    }
+}
+
+pub async fn trust_list() {
+    match node::trusted_keys().await {
+        Ok(trusted_keys) => print_output(&trusted_keys, |trusted_keys| {
+            if trusted_keys.is_empty() {
+                println!("No trusted keys");
+            }
+            for trusted_key in trusted_keys {
+                let roles: Vec<String> = trusted_key
+                    .roles
+                    .iter()
+                    .map(|role| role.to_string())
+                    .collect();
+                println!(
+                    "{} {} [{}]",
+                    trusted_key.fingerprint,
+                    trusted_key.peer_id,
+                    roles.join(", ")
+                );
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn trust_add(peer_id: &str, roles: Vec<&String>) {
+    let roles = match roles
+        .into_iter()
+        .map(|role| TrustRole::from_str(role))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(roles) => roles,
+        Err(error) => {
+            print_error(&format!("Error: invalid role: {}", error));
+            return;
+        }
+    };
+    let request = RequestTrustKey {
+        peer_id: peer_id.to_owned(),
+        roles,
+    };
+    match node::add_trusted_key(request).await {
+        Ok(trusted_key) => print_output(&trusted_key, |trusted_key| {
+            println!(
+                "Trusted key {} of peer {}",
+                trusted_key.fingerprint, trusted_key.peer_id
+            )
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn trust_remove(fingerprint: &str) {
+    match node::remove_trusted_key(fingerprint).await {
+        Ok(()) => print_output(&json!({ "fingerprint": fingerprint }), |_| {
+            println!("Removed trusted key {}", fingerprint)
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub fn trust_fingerprint(peer_id: &str) {
+    match peer_id
+        .parse()
+        .ok()
+        .and_then(|peer_id| fingerprint_of(&peer_id))
+    {
+        Some(fingerprint) => print_output(
+            &json!({ "peer_id": peer_id, "fingerprint": fingerprint }),
+            |_| println!("{}", fingerprint),
+        ),
+        None => print_error(&format!(
+            "Error: no public key can be derived from peer ID {}",
+            peer_id
+        )),
+    }
+}
+
+pub async fn admin_evict(artifact_hash: &str) {
+    match node::admin_evict_artifact(artifact_hash).await {
+        Ok(artifact_ids) => print_output(&json!({ "evicted": artifact_ids }), |_| {
+            for artifact_id in &artifact_ids {
+                println!("Evicted artifact {}", artifact_id);
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn node_bench(peer_id: Option<String>, size_mib: u64, rounds: u32) {
+    let request = RequestBench {
+        peer_id,
+        size: Some(size_mib * 1024 * 1024),
+        rounds: Some(rounds),
+    };
+    match node::admin_bench(&request).await {
+        Ok(report) => print_output(&report, |report| {
+            println!(
+                "Benchmark against peer {} ({} rounds of {})",
+                report.peer_id,
+                report.rounds,
+                format_disk_space(report.size)
+            );
+            println!("Latency:  {:.1} ms", report.latency_ms);
+            println!(
+                "Upload:   {}/s",
+                format_disk_space(report.upload_bytes_per_sec)
+            );
+            println!(
+                "Download: {}/s",
+                format_disk_space(report.download_bytes_per_sec)
+            );
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn node_connect(address: &str) {
+    match node::admin_connect_peer(address).await {
+        Ok(peer_id) => print_output(&json!({ "peer_id": peer_id, "address": address }), |_| {
+            println!("Connected to peer {}", peer_id)
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_disconnect(peer_id: &str, ban: bool) {
+    match node::admin_disconnect_peer(peer_id, ban).await {
+        Ok(()) => print_output(&json!({ "peer_id": peer_id, "banned": ban }), |_| {
+            if ban {
+                println!("Banned peer {}", peer_id)
+            } else {
+                println!("Disconnected peer {}", peer_id)
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_gc() {
+    match node::admin_collect_garbage().await {
+        Ok(artifact_ids) => print_output(&json!({ "evicted": artifact_ids }), |_| {
+            if artifact_ids.is_empty() {
+                println!("Disk usage is below the high watermark, no artifacts evicted")
+            }
+            for artifact_id in &artifact_ids {
+                println!("Evicted artifact {}", artifact_id);
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_scrub() {
+    match node::admin_scrub().await {
+        Ok(()) => {
+            print_message("Scrub of stored artifacts started, see 'pyrsia status' for the results")
+        }
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_token_issue() {
+    match node::admin_issue_api_token().await {
+        Ok(new_token) => print_output(&new_token, |new_token| {
+            println!("Issued API token {}:", new_token.id);
+            println!("{}", new_token.token);
+            println!(
+                "The token is not shown again. Set it in PYRSIA_API_TOKEN to use it with the CLI."
+            );
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_token_list() {
+    match node::admin_api_tokens().await {
+        Ok(issued_tokens) => print_output(&issued_tokens, |issued_tokens| {
+            if issued_tokens.is_empty() {
+                println!("No API tokens issued");
+            }
+            for issued_token in issued_tokens {
+                println!("{} issued at {}", issued_token.id, issued_token.issued_at);
+            }
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub async fn admin_token_revoke(id: &str) {
+    match node::admin_revoke_api_token(id).await {
+        Ok(()) => print_output(&json!({ "id": id }), |_| {
+            println!("Revoked API token {}", id)
+        }),
+        Err(error) => print_node_error(error),
+    }
+}
+
+pub fn keys_remove(name: &str) {
+    match cli_keystore().and_then(|keystore| Ok(keystore.remove(name)?)) {
+        Ok(()) => print_output(&json!({ "name": name }), |_| {
+            println!("Key {} removed", name)
+        }),
+        Err(error) => print_error(&format!("Error removing key {}: {}", name, error)),
+    }
+}
+
+// Print the error of a request to the node, with a reminder to check the
+// configuration in the text format.
+fn print_node_error(error: anyhow::Error) {
+    match output_format() {
+        OutputFormat::Text => println!("Error: {}. {}", error, CONF_REMINDER_MESSAGE),
+        _ => print_error(&error.to_string()),
+    }
+}
+
+/// The keystore of the CLI, next to its config file
+fn cli_keystore() -> anyhow::Result<Keystore> {
+    let config_file_path = config::get_config_file_path()?;
+    let config_dir = config_file_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid config file path"))?;
+    Ok(Keystore::new(config_dir.join("keystore")))
+}
+
+/// Read the keystore passphrase from PYRSIA_KEYSTORE_PASSPHRASE, or interactively
+fn read_keystore_passphrase() -> String {
+    match read_var(KEYSTORE_PASSPHRASE_VAR, "") {
+        passphrase if passphrase.is_empty() => {
+            read_interactive_input("Enter keystore passphrase:", "", &|passphrase| {
+                if passphrase.is_empty() {
+                    Err(String::from("The passphrase must not be empty"))
+                } else {
+                    Ok(passphrase)
+                }
+            })
+        }
+        passphrase => passphrase,
+    }
 }
 
 /// Read user input interactively until the validation passed
@@ -250,21 +1482,81 @@ fn read_interactive_input(
                 if input.is_empty() {
                     input = default_val;
                 }
-                if let Ok(r) = validation_func(input.to_owned()) {
-                    break r;
+                match validation_func(input.to_owned()) {
+                    Ok(r) => break r,
+                    Err(description) => println!("{}", description),
                 }
             }
         }
     }
 }
 
+// Format rows as a table with left aligned columns that are as wide as their
+// widest cell.
+fn format_table<S: AsRef<str>>(headers: &[&str], rows: &[Vec<S>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.as_ref().chars().count());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+    std::iter::once(format_row(headers.to_vec()))
+        .chain(
+            rows.iter()
+                .map(|row| format_row(row.iter().map(|cell| cell.as_ref()).collect())),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Format a time in seconds since the epoch relative to now, e.g. "5m ago".
+fn format_age(now: u64, time: u64) -> String {
+    let age = now.saturating_sub(time);
+    match age {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{}m ago", age / 60),
+        3600..=86399 => format!("{}h ago", age / 3600),
+        _ => format!("{}d ago", age / 86400),
+    }
+}
+
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
-    use crate::cli::handlers::config_show;
+    use crate::cli::handlers::{config_show, format_age, format_table, transfer_rate};
+    use std::time::Duration;
 
     #[test]
     fn test_config_show() {
         config_show();
     }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![vec!["sha256:abc", "1.2 MB"], vec!["-", "10 B"]];
+        assert_eq!(
+            format_table(&["HASH", "SIZE"], &rows),
+            "HASH        SIZE\nsha256:abc  1.2 MB\n-           10 B"
+        );
+        assert_eq!(format_age(1000, 990), "just now");
+        assert_eq!(format_age(1000, 400), "10m ago");
+        assert_eq!(format_age(100_000, 0), "1d ago");
+    }
+
+    #[test]
+    fn test_transfer_rate() {
+        assert_eq!(transfer_rate(1000, 5000, Duration::from_secs(2)), 2000);
+        assert_eq!(transfer_rate(5000, 1000, Duration::from_secs(2)), 0);
+        assert_eq!(transfer_rate(0, 1000, Duration::ZERO), 0);
+    }
 }
@@ -0,0 +1,100 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// The names of the output formats, as accepted by `--output`.
+pub const OUTPUT_FORMATS: [&str; 3] = ["text", "json", "yaml"];
+
+/// The format in which commands print their results: text for humans, or
+/// JSON or YAML with stable field names for scripts and CI pipelines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(format!("Unknown output format: {}", s)),
+        }
+    }
+}
+
+lazy_static! {
+    static ref SELECTED_OUTPUT_FORMAT: Mutex<OutputFormat> = Mutex::new(OutputFormat::Text);
+}
+
+/// Selects the format in which all commands print their results.
+pub fn set_output_format(output_format: OutputFormat) {
+    *SELECTED_OUTPUT_FORMAT.lock().unwrap() = output_format;
+}
+
+pub fn output_format() -> OutputFormat {
+    *SELECTED_OUTPUT_FORMAT.lock().unwrap()
+}
+
+/// Prints the result of a command: with `print_text` in the text format,
+/// otherwise serialized in the selected format.
+pub fn print_output<T: Serialize>(value: &T, print_text: impl FnOnce(&T)) {
+    match output_format() {
+        OutputFormat::Text => print_text(value),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value).unwrap()),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value).unwrap()),
+    }
+}
+
+/// Prints the confirmation of a command that has no other result, in the
+/// `message` field of an object in the structured formats.
+pub fn print_message(message: &str) {
+    print_output(&serde_json::json!({ "message": message }), |_| {
+        println!("{}", message)
+    });
+}
+
+/// Prints the reason a command failed, in the `error` field of an object
+/// in the structured formats.
+pub fn print_error(message: &str) {
+    print_output(&serde_json::json!({ "error": message }), |_| {
+        println!("{}", message)
+    });
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_format_from_str() {
+        for name in OUTPUT_FORMATS {
+            assert!(OutputFormat::from_str(name).is_ok());
+        }
+        assert_eq!(OutputFormat::from_str("yaml"), Ok(OutputFormat::Yaml));
+        assert!(OutputFormat::from_str("xml").is_err());
+    }
+}
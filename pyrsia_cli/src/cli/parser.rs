@@ -15,13 +15,33 @@
 */
 
 use clap::{arg, command, crate_version, ArgGroup, ArgMatches, Command};
+use clap_complete::Shell;
+
+use super::output::OUTPUT_FORMATS;
 use const_format::formatcp;
 
 pub fn cli_parser() -> ArgMatches {
+    cli_command().get_matches()
+}
+
+/// The definition of all pyrsia commands, also used to generate shell
+/// completions.
+pub fn cli_command() -> Command {
     let version_string: &str = formatcp!("{} ({})", crate_version!(), env!("VERGEN_GIT_SHA"));
     command!()
         .arg_required_else_help(true)
         .propagate_version(false)
+        .arg(
+            arg!(--profile <PROFILE> "The configuration profile to use, overrides the PYRSIA_PROFILE environment variable")
+                .global(true)
+                .required(false),
+        )
+        .arg(
+            arg!(-o --output <FORMAT> "The output format, json and yaml print the results with stable field names for scripting")
+                .value_parser(OUTPUT_FORMATS)
+                .default_value("text")
+                .global(true),
+        )
         // Config subcommand
         .subcommands(vec![
             Command::new("authorize")
@@ -55,6 +75,13 @@ pub fn cli_parser() -> ArgMatches {
                             arg!(--id <ID> "The build ID"),
                         ]),
                 ]),
+            Command::new("completion")
+                .about("Generate a shell completion script, e.g. 'pyrsia completion bash > /etc/bash_completion.d/pyrsia'")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<SHELL> "The shell to generate the completion script for")
+                        .value_parser(clap::value_parser!(Shell)),
+                ]),
             Command::new("config")
                 .short_flag('c')
                 .about("Configure Pyrsia")
@@ -62,16 +89,57 @@ pub fn cli_parser() -> ArgMatches {
                 .subcommands(vec![
                     Command::new("edit")
                         .short_flag('e')
-                        .about("Edits a node configuration")
+                        .long_flag("edit")
+                        .about("Edits a node configuration, interactively when no values are specified")
                         .arg(arg!(-H --host <HOST> "Hostname").required(false))
                         .arg(arg!(-p --port <PORT> "Port number").required(false))
                         .arg(arg!(-d --diskspace <DISK_SPACE> "Disk space to be allocated to Pyrsia node").required(false))
-                        .group(ArgGroup::new("node_config").args(["host", "port", "diskspace"]).required(false).multiple(true))
+                        .arg(arg!(-S --scheme <SCHEME> "Scheme of the node API").value_parser(["http", "https"]).required(false))
+                        .arg(arg!(--"ca-cert" <FILE> "PEM file with the CA certificate of the node, empty to only trust public CAs").required(false))
+                        .group(ArgGroup::new("node_config").args(["host", "port", "diskspace", "scheme", "ca-cert"]).required(false).multiple(true))
                 ])
                 .args(&[
                     arg!(-r --remove   "Removes the stored node configuration").visible_alias("rm"),
                     arg!(-s --show     "Shows the stored node configuration"),
                 ]),
+            Command::new("login")
+                .about("Store the API token that the CLI presents to the node of the active profile")
+                .args(&[
+                    arg!(--token <TOKEN> "The API token, read interactively when not specified"),
+                ]),
+            Command::new("logout")
+                .about("Remove the stored API token of the active profile"),
+            Command::new("export")
+                .about("Export the artifacts stored on the node to a tar archive")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<FILE> "The archive file to write"),
+                ]),
+            Command::new("import")
+                .about("Import the artifacts of a tar archive into the node")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<FILE> "The archive file to read"),
+                ]),
+            Command::new("push")
+                .about("Publish a local file to the node: record it in the transparency log, store it and announce it to the network")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<FILE> "The file to publish"),
+                    arg!(-n --name <NAME> "The name to publish the file under, defaults to its file name"),
+                ]),
+            Command::new("inspect")
+                .about("Show everything the node knows about an artifact: copies, signatures, transparency logs and providers")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<REFERENCE> "The hash of the artifact (e.g. sha256:...) or a docker image (e.g. alpine:3.16)"),
+                ]),
+            Command::new("verify")
+                .about("Verify the signatures of a signed JSON document or the signatures, transparency log and policy verdict of a stored artifact")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<TARGET> "A file with a signed document, or the hash of a stored artifact"),
+                ]),
             Command::new("inspect-log")
                 .about("Show transparency logs")
                 .subcommand_required(true)
@@ -96,14 +164,206 @@ pub fn cli_parser() -> ArgMatches {
                                 .default_value("json"),
                         ]),
                 ]),
+            Command::new("keys")
+                .about("Manage the keys in the encrypted keystore")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommands(vec![
+                    Command::new("generate")
+                        .about("Generate a new key and store it in the keystore")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name of the key"),
+                        ]),
+                    Command::new("list")
+                        .about("Show the keys in the keystore with their peer IDs and fingerprints"),
+                    Command::new("export")
+                        .about("Export the public key of a key in the keystore as a JSON Web Key, to share it with other parties")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name of the key"),
+                            arg!(-f --file <FILE> "The file to write the public key to, instead of the standard output"),
+                        ]),
+                    Command::new("import")
+                        .about("Import the public key of another party from a JSON Web Key file")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name to store the public key under"),
+                            arg!(<FILE> "The JSON Web Key file, as written by 'pyrsia keys export'"),
+                        ]),
+                    Command::new("show")
+                        .about("Show the peer ID and fingerprint of a key in the keystore")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name of the key"),
+                        ]),
+                    Command::new("rotate")
+                        .about("Replace a key with a new key that is certified by it, and publish the rotation on the node")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name of the key to retire"),
+                            arg!(<NEW_NAME> "The name of the new key"),
+                        ]),
+                    Command::new("remove")
+                        .about("Remove a key from the keystore")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<NAME> "The name of the key"),
+                        ]),
+                ]),
+            Command::new("trust")
+                .about("Manage the keys the node trusts to sign attestations and verify artifacts")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommands(vec![
+                    Command::new("list")
+                        .about("Show the trusted keys with their roles"),
+                    Command::new("add")
+                        .about("Trust the key of a peer for one or more roles")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<PEER_ID> "The peer ID of the key to trust"),
+                            arg!(-r --role <ROLE> "The role the key is trusted for, can be repeated")
+                                .value_parser(["attestation", "verification"])
+                                .action(clap::ArgAction::Append)
+                                .required(true),
+                        ]),
+                    Command::new("remove")
+                        .about("Stop trusting the key with the specified fingerprint")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<FINGERPRINT> "The fingerprint of the trusted key"),
+                        ]),
+                    Command::new("fingerprint")
+                        .about("Show the fingerprint of the key of a peer")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<PEER_ID> "The peer ID of the key"),
+                        ]),
+                ]),
+            Command::new("admin")
+                .about("Intervene on the Pyrsia node, only available on the host of the node")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommands(vec![
+                    Command::new("evict")
+                        .about("Remove the stored artifacts with the specified hash from the node")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<HASH> "The hash of the artifact"),
+                        ]),
+                    Command::new("disconnect")
+                        .about("Disconnect a peer from the node")
+                        .arg_required_else_help(true)
+                        .args(&[
+                            arg!(<PEER_ID> "The peer ID of the peer"),
+                            arg!(--ban "Refuse new connections of the peer until the node restarts"),
+                        ]),
+                    Command::new("gc")
+                        .about("Evict the least recently used artifacts when the disk usage is high"),
+                    Command::new("scrub")
+                        .about("Verify all stored artifacts in the background"),
+                    Command::new("token")
+                        .about("Manage the API tokens that grant access to the node API. The CLI presents the token in PYRSIA_API_TOKEN")
+                        .subcommand_required(true)
+                        .arg_required_else_help(true)
+                        .subcommands(vec![
+                            Command::new("issue")
+                                .about("Issue a new API token, which is shown only once"),
+                            Command::new("list")
+                                .about("Show the ids of the issued API tokens"),
+                            Command::new("revoke")
+                                .about("Revoke an issued API token")
+                                .arg_required_else_help(true)
+                                .args(&[
+                                    arg!(<ID> "The id of the API token"),
+                                ]),
+                        ]),
+                ]),
             Command::new("list")
                 .short_flag('l')
-                .about("Show a list of connected peers"),
-            Command::new("ping").about("Pings configured pyrsia node"),
+                .about("Show a list of connected peers or stored artifacts")
+                .subcommands(vec![
+                    Command::new("peers")
+                        .about("Show a list of connected peers"),
+                    Command::new("artifacts")
+                        .about("Show a list of the artifacts stored on the node")
+                        .args(&[
+                            arg!(--offset <OFFSET> "The number of artifacts to skip")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("0"),
+                            arg!(--limit <LIMIT> "The maximum number of artifacts to show")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("100"),
+                            arg!(--sort <FIELD> "The field to sort by, e.g. size, prefixed with - to sort in descending order")
+                                .allow_hyphen_values(true),
+                            arg!(--filter <FILTER> "Comma separated field:value filters, e.g. package_type:Docker"),
+                        ]),
+                ]),
+            Command::new("bench")
+                .about("Measures the latency and throughput between the Pyrsia node and a peer, by default the connected peer with the lowest latency")
+                .args(&[
+                    arg!([PEER_ID] "The peer to benchmark against"),
+                    arg!(-s --size <MEBIBYTES> "The amount of synthetic data to upload and download in every round")
+                        .value_parser(clap::value_parser!(u64).range(1..=16))
+                        .default_value("4"),
+                    arg!(-r --rounds <ROUNDS> "The number of rounds")
+                        .value_parser(clap::value_parser!(u32).range(1..=100))
+                        .default_value("3"),
+                ]),
+            Command::new("connect")
+                .about("Instruct the Pyrsia node to dial a peer, e.g. 'pyrsia connect /ip4/1.2.3.4/tcp/44000/p2p/<peer id>'")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<MULTIADDR> "The multiaddress of the peer, ending with its peer ID"),
+                ]),
+            Command::new("doctor")
+                .about("Diagnose common problems of the Pyrsia node and the CLI setup"),
+            Command::new("events").about("Follow the activity of the Pyrsia node"),
+            Command::new("logs")
+                .about("Shows the most recent log entries of the Pyrsia node")
+                .args(&[
+                    arg!(-l --level <LEVEL> "Only show entries of this level or more severe")
+                        .value_parser(["error", "warn", "info", "debug", "trace"]),
+                    arg!(-n --lines <LINES> "The number of recent entries to show")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("100"),
+                    arg!(-f --follow "Keep streaming new log entries"),
+                ]),
+            Command::new("node")
+                .about("Run the Pyrsia node on this host in the background")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommands(vec![
+                    Command::new("start")
+                        .about("Start pyrsia_node in the background, passing on any arguments after --")
+                        .arg(arg!([NODE_ARGS]... "The arguments for pyrsia_node, e.g. -- --port 7889").last(true)),
+                    Command::new("stop").about("Stop the node that was started in the background"),
+                    Command::new("status").about("Show whether the node that was started in the background is running and its API is reachable"),
+                ]),
+            Command::new("ping").about("Pings configured pyrsia node and shows its round-trip time, version and peer id"),
+            Command::new("prefetch")
+                .about("Lets the Pyrsia node fetch a Docker image and all its layers ahead of time")
+                .arg_required_else_help(true)
+                .arg(arg!(<IMAGE> "The image reference, e.g. alpine:3.16 or docker.io/library/alpine@sha256:...")),
+            Command::new("search")
+                .about("Search artifacts by name on the Pyrsia node, and optionally on the p2p network")
+                .arg_required_else_help(true)
+                .args(&[
+                    arg!(<NAME> "A part of the name of the artifact, e.g. alpine"),
+                    arg!(-t --type <TYPE> "Only search artifacts of this package type")
+                        .value_parser(["docker", "maven2", "generic"]),
+                    arg!(--network "Also resolve the exact name, e.g. library/alpine:3.16, on the p2p network and show the peers that provide the matches"),
+                ]),
             Command::new("status")
                 .short_flag('s')
-                .about("Show information about the Pyrsia node"),
+                .about("Show information about the Pyrsia node")
+                .args(&[
+                    arg!(-w --watch "Keep refreshing the status in place until interrupted"),
+                    arg!(--interval <SECONDS> "The number of seconds between refreshes in watch mode")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("2"),
+                ]),
         ])
         .version(version_string)
-        .get_matches()
 }
@@ -0,0 +1,171 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::output::{output_format, OutputFormat};
+use pyrsia::cli_commands::node::OnProgress;
+use pyrsia::util::disk_space_util::format_disk_space;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 30;
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A progress bar on stderr for commands that move artifacts to or from the
+/// node. It is only drawn in the text output format.
+pub struct ProgressBar {
+    label: String,
+    started: Instant,
+    state: Arc<Mutex<ProgressState>>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    transferred: u64,
+    total: Option<u64>,
+    last_drawn: Option<Instant>,
+}
+
+impl ProgressBar {
+    pub fn new(label: &str) -> Self {
+        ProgressBar {
+            label: label.to_owned(),
+            started: Instant::now(),
+            state: Arc::new(Mutex::new(ProgressState::default())),
+        }
+    }
+
+    /// The callback that updates the bar while the request transfers data.
+    pub fn on_progress(&self) -> OnProgress {
+        let label = self.label.clone();
+        let started = self.started;
+        let state = self.state.clone();
+        let visible = output_format() == OutputFormat::Text;
+        Box::new(move |transferred, total| {
+            let mut state = state.lock().unwrap();
+            state.transferred = transferred;
+            state.total = total;
+            let now = Instant::now();
+            let finished = total == Some(transferred);
+            let due = state
+                .last_drawn
+                .map_or(true, |last_drawn| now - last_drawn >= REDRAW_INTERVAL);
+            if visible && (finished || due) {
+                state.last_drawn = Some(now);
+                eprint!(
+                    "\r{} {}",
+                    label,
+                    format_progress(transferred, total, now - started)
+                );
+                let _ = std::io::stderr().flush();
+            }
+        })
+    }
+
+    /// Ends the bar and returns the summary of the transfer to or from
+    /// `source`.
+    pub fn finish(self, source: &str) -> TransferSummary {
+        let state = self.state.lock().unwrap();
+        if state.last_drawn.is_some() {
+            eprintln!();
+        }
+        TransferSummary {
+            bytes: state.transferred,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            source: source.to_owned(),
+        }
+    }
+}
+
+/// The amount of data a command moved, how long it took and where the data
+/// came from or went to.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct TransferSummary {
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub source: String,
+}
+
+impl TransferSummary {
+    /// Prints the summary after `action`, e.g. "Exported", in the text
+    /// output format.
+    pub fn print(&self, action: &str) {
+        if output_format() == OutputFormat::Text {
+            let duration = Duration::from_millis(self.duration_ms);
+            println!(
+                "{} {} in {:.1} s ({}/s), {}",
+                action,
+                format_disk_space(self.bytes),
+                duration.as_secs_f64(),
+                format_disk_space(rate(self.bytes, duration)),
+                self.source
+            );
+        }
+    }
+}
+
+/// The result of a command together with the summary of its transfer, for
+/// the structured output formats.
+#[derive(Serialize)]
+pub struct WithTransfer<'a, T: Serialize> {
+    #[serde(flatten)]
+    pub result: &'a T,
+    pub transfer: &'a TransferSummary,
+}
+
+fn format_progress(transferred: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let speed = format_disk_space(rate(transferred, elapsed));
+    match total {
+        Some(total) if total > 0 => {
+            let filled = (transferred.min(total) * BAR_WIDTH as u64 / total) as usize;
+            format!(
+                "[{}{}] {:>3}% {}/{} {}/s",
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                transferred.min(total) * 100 / total,
+                format_disk_space(transferred),
+                format_disk_space(total),
+                speed
+            )
+        }
+        _ => format!("{} {}/s", format_disk_space(transferred), speed),
+    }
+}
+
+fn rate(bytes: u64, elapsed: Duration) -> u64 {
+    match elapsed.as_millis() as u64 {
+        0 => 0,
+        millis => bytes * 1000 / millis,
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_progress() {
+        let progress = format_progress(50, Some(100), Duration::from_secs(1));
+        assert!(progress.starts_with(&format!("[{}{}]  50%", "#".repeat(15), "-".repeat(15))));
+
+        let progress = format_progress(100, None, Duration::from_secs(2));
+        assert!(!progress.contains('['));
+        assert!(progress.ends_with(&format!("{}/s", format_disk_space(50))));
+    }
+}
@@ -16,7 +16,9 @@
 
 pub mod cli;
 
+use clap_complete::Shell;
 use cli::handlers::*;
+use cli::output::{print_error, print_message};
 use cli::parser::*;
 
 const CONF_FILE_PATH_MSG_STARTER: &str = "Config file path:";
@@ -25,34 +27,44 @@ const CONF_FILE_PATH_MSG_STARTER: &str = "Config file path:";
 async fn main() {
     // parsing command line arguments
     let matches = cli_parser();
+    select_profile(matches.get_one::<String>("profile"));
+    select_output_format(matches.get_one::<String>("output").unwrap());
 
     // checking and preparing responses for each command and its arguments if applicable
 
     match matches.subcommand() {
         Some(("config", config_matches)) => {
             if let Some(("edit", edit_config_matches)) = config_matches.subcommand() {
-                if vec!["host", "port", "diskspace"]
+                if vec!["host", "port", "diskspace", "scheme", "ca-cert"]
                     .into_iter()
                     .any(|opt_str| edit_config_matches.contains_id(opt_str))
                 {
                     let host_name = edit_config_matches.get_one::<String>("host");
                     let port = edit_config_matches.get_one::<String>("port");
                     let diskspace = edit_config_matches.get_one::<String>("diskspace");
-                    match config_edit(host_name.cloned(), port.cloned(), diskspace.cloned()) {
+                    let scheme = edit_config_matches.get_one::<String>("scheme");
+                    let ca_cert = edit_config_matches.get_one::<String>("ca-cert");
+                    match config_edit(
+                        host_name.cloned(),
+                        port.cloned(),
+                        diskspace.cloned(),
+                        scheme.cloned(),
+                        ca_cert.cloned(),
+                    ) {
                         Ok(_) => {
-                            println!("Node configuration saved !!");
+                            print_message("Node configuration saved !!");
                         }
                         Err(error) => {
-                            eprintln!("Error saving node configuration: {}", error);
+                            print_error(&format!("Error saving node configuration: {}", error));
                         }
                     }
                 } else {
                     match config_add() {
                         Ok(_) => {
-                            println!("Node configuration saved !!");
+                            print_message("Node configuration saved !!");
                         }
                         Err(error) => {
-                            eprintln!("Error saving node configuration: {}", error);
+                            print_error(&format!("Error saving node configuration: {}", error));
                         }
                     }
                 }
@@ -60,10 +72,10 @@ async fn main() {
             if *config_matches.get_one::<bool>("remove").unwrap_or(&false) {
                 match config_remove() {
                     Ok(_) => {
-                        println!("Node configuration removed !!");
+                        print_message("Node configuration removed !!");
                     }
                     Err(error) => {
-                        eprintln!("Error removing node configuration: {}", error);
+                        print_error(&format!("Error removing node configuration: {}", error));
                     }
                 }
             }
@@ -71,6 +83,9 @@ async fn main() {
                 config_show();
             }
         }
+        Some(("completion", completion_matches)) => {
+            completion(*completion_matches.get_one::<Shell>("SHELL").unwrap());
+        }
         Some(("authorize", authorize_matches)) => {
             authorize(authorize_matches.get_one::<String>("peer").unwrap()).await;
         }
@@ -86,14 +101,193 @@ async fn main() {
             }
             _ => {}
         },
-        Some(("list", _config_matches)) => {
-            node_list().await;
+        Some(("export", export_matches)) => {
+            export_artifacts(export_matches.get_one::<String>("FILE").unwrap()).await;
+        }
+        Some(("import", import_matches)) => {
+            import_artifacts(import_matches.get_one::<String>("FILE").unwrap()).await;
         }
+        Some(("push", push_matches)) => {
+            push_artifact(
+                push_matches.get_one::<String>("FILE").unwrap(),
+                push_matches.get_one::<String>("name"),
+            )
+            .await;
+        }
+        Some(("keys", keys_matches)) => match keys_matches.subcommand() {
+            Some(("generate", generate_matches)) => {
+                keys_generate(generate_matches.get_one::<String>("NAME").unwrap());
+            }
+            Some(("list", _list_matches)) => {
+                keys_list();
+            }
+            Some(("export", export_matches)) => {
+                keys_export(
+                    export_matches.get_one::<String>("NAME").unwrap(),
+                    export_matches.get_one::<String>("file"),
+                );
+            }
+            Some(("import", import_matches)) => {
+                keys_import(
+                    import_matches.get_one::<String>("NAME").unwrap(),
+                    import_matches.get_one::<String>("FILE").unwrap(),
+                );
+            }
+            Some(("show", show_matches)) => {
+                keys_show(show_matches.get_one::<String>("NAME").unwrap());
+            }
+            Some(("rotate", rotate_matches)) => {
+                keys_rotate(
+                    rotate_matches.get_one::<String>("NAME").unwrap(),
+                    rotate_matches.get_one::<String>("NEW_NAME").unwrap(),
+                )
+                .await;
+            }
+            Some(("remove", remove_matches)) => {
+                keys_remove(remove_matches.get_one::<String>("NAME").unwrap());
+            }
+            _ => {}
+        },
+        Some(("trust", trust_matches)) => match trust_matches.subcommand() {
+            Some(("list", _list_matches)) => {
+                trust_list().await;
+            }
+            Some(("add", add_matches)) => {
+                trust_add(
+                    add_matches.get_one::<String>("PEER_ID").unwrap(),
+                    add_matches
+                        .get_many::<String>("role")
+                        .unwrap_or_default()
+                        .collect(),
+                )
+                .await;
+            }
+            Some(("remove", remove_matches)) => {
+                trust_remove(remove_matches.get_one::<String>("FINGERPRINT").unwrap()).await;
+            }
+            Some(("fingerprint", fingerprint_matches)) => {
+                trust_fingerprint(fingerprint_matches.get_one::<String>("PEER_ID").unwrap());
+            }
+            _ => {}
+        },
+        Some(("admin", admin_matches)) => match admin_matches.subcommand() {
+            Some(("evict", evict_matches)) => {
+                admin_evict(evict_matches.get_one::<String>("HASH").unwrap()).await;
+            }
+            Some(("disconnect", disconnect_matches)) => {
+                admin_disconnect(
+                    disconnect_matches.get_one::<String>("PEER_ID").unwrap(),
+                    disconnect_matches.get_flag("ban"),
+                )
+                .await;
+            }
+            Some(("gc", _gc_matches)) => {
+                admin_gc().await;
+            }
+            Some(("scrub", _scrub_matches)) => {
+                admin_scrub().await;
+            }
+            Some(("token", token_matches)) => match token_matches.subcommand() {
+                Some(("issue", _issue_matches)) => {
+                    admin_token_issue().await;
+                }
+                Some(("list", _list_matches)) => {
+                    admin_token_list().await;
+                }
+                Some(("revoke", revoke_matches)) => {
+                    admin_token_revoke(revoke_matches.get_one::<String>("ID").unwrap()).await;
+                }
+                _ => {}
+            },
+            _ => {}
+        },
+        Some(("list", list_matches)) => match list_matches.subcommand() {
+            Some(("artifacts", artifacts_matches)) => {
+                list_artifacts(
+                    *artifacts_matches.get_one::<usize>("offset").unwrap(),
+                    *artifacts_matches.get_one::<usize>("limit").unwrap(),
+                    artifacts_matches.get_one::<String>("sort").cloned(),
+                    artifacts_matches.get_one::<String>("filter").cloned(),
+                )
+                .await;
+            }
+            _ => {
+                node_list().await;
+            }
+        },
+        Some(("events", _events_matches)) => {
+            node_events().await;
+        }
+        Some(("logs", logs_matches)) => {
+            node_logs(
+                logs_matches.get_one::<String>("level").cloned(),
+                *logs_matches.get_one::<usize>("lines").unwrap(),
+                logs_matches.get_flag("follow"),
+            )
+            .await;
+        }
+        Some(("bench", bench_matches)) => {
+            node_bench(
+                bench_matches.get_one::<String>("PEER_ID").cloned(),
+                *bench_matches.get_one::<u64>("size").unwrap(),
+                *bench_matches.get_one::<u32>("rounds").unwrap(),
+            )
+            .await;
+        }
+        Some(("connect", connect_matches)) => {
+            node_connect(connect_matches.get_one::<String>("MULTIADDR").unwrap()).await;
+        }
+        Some(("login", login_matches)) => {
+            login(login_matches.get_one::<String>("token"));
+        }
+        Some(("logout", _logout_matches)) => {
+            logout();
+        }
+        Some(("doctor", _doctor_matches)) => {
+            doctor().await;
+        }
+        Some(("node", node_matches)) => match node_matches.subcommand() {
+            Some(("start", start_matches)) => {
+                let node_args: Vec<String> = start_matches
+                    .get_many::<String>("NODE_ARGS")
+                    .map(|node_args| node_args.cloned().collect())
+                    .unwrap_or_default();
+                daemon_start(&node_args);
+            }
+            Some(("stop", _stop_matches)) => {
+                daemon_stop();
+            }
+            Some(("status", _status_matches)) => {
+                daemon_status().await;
+            }
+            _ => {}
+        },
         Some(("ping", _config_matches)) => {
             node_ping().await;
         }
-        Some(("status", _config_matches)) => {
-            node_status().await;
+        Some(("prefetch", prefetch_matches)) => {
+            prefetch_image(prefetch_matches.get_one::<String>("IMAGE").unwrap()).await;
+        }
+        Some(("search", search_matches)) => {
+            search_artifacts(
+                search_matches.get_one::<String>("NAME").unwrap(),
+                search_matches.get_one::<String>("type"),
+                search_matches.get_flag("network"),
+            )
+            .await;
+        }
+        Some(("status", status_matches)) => {
+            if status_matches.get_flag("watch") {
+                node_status_watch(*status_matches.get_one::<u64>("interval").unwrap()).await;
+            } else {
+                node_status().await;
+            }
+        }
+        Some(("inspect", inspect_matches)) => {
+            inspect_artifact(inspect_matches.get_one::<String>("REFERENCE").unwrap()).await;
+        }
+        Some(("verify", verify_matches)) => {
+            verify(verify_matches.get_one::<String>("TARGET").unwrap()).await;
         }
         Some(("inspect-log", build_matches)) => match build_matches.subcommand() {
             Some(("docker", docker_matches)) => {
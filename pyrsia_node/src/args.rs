@@ -14,4 +14,5 @@
    limitations under the License.
 */
 
+pub mod config_file;
 pub mod parser;
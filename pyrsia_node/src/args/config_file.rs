@@ -0,0 +1,260 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::parser::PyrsiaNodeArgs;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command, CommandFactory, FromArgMatches};
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+/// Parse the command line arguments and complete them with the settings of
+/// the configuration file given with --config, or the default configuration
+/// file in the data directory. Command line arguments and environment
+/// variables take precedence over the configuration file.
+pub fn parse_args_with_config_file<I, T>(args: I) -> Result<PyrsiaNodeArgs, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    PyrsiaNodeArgs::from_arg_matches(&matches_with_config_file(args)?)
+}
+
+/// The matches of the command line arguments completed with the settings of
+/// the configuration file, like `parse_args_with_config_file`.
+pub fn matches_with_config_file<I, T>(args: I) -> Result<ArgMatches, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let mut command = PyrsiaNodeArgs::command();
+    let matches = command.clone().try_get_matches_from(&args)?;
+    let config_file = match PyrsiaNodeArgs::from_arg_matches(&matches)?.config_file() {
+        Some(config_file) => config_file,
+        None => return Ok(matches),
+    };
+
+    let config = fs::read_to_string(&config_file).map_err(|e| {
+        command.error(
+            ErrorKind::Io,
+            format!(
+                "Unable to read the configuration file {}: {}",
+                config_file.display(),
+                e
+            ),
+        )
+    })?;
+    let config_args = args_from_config(&mut command, &matches, &config)
+        .map_err(|message| config_error(&mut command, &config_file, &message))?;
+
+    let mut all_args = args.clone();
+    all_args.splice(1..1, config_args.into_iter().map(OsString::from));
+    command.try_get_matches_from(all_args)
+}
+
+fn config_error(command: &mut Command, config_file: &Path, message: &str) -> clap::Error {
+    command.error(
+        ErrorKind::ValueValidation,
+        format!(
+            "Invalid configuration file {}: {}",
+            config_file.display(),
+            message
+        ),
+    )
+}
+
+// Translates the settings of a TOML configuration into command line
+// arguments, skipping the settings that were given on the command line or
+// in an environment variable already. Settings are named like the long
+// options, with either dashes or underscores, and may be grouped in tables
+// like [storage].
+fn args_from_config(
+    command: &mut Command,
+    matches: &ArgMatches,
+    config: &str,
+) -> Result<Vec<String>, String> {
+    let config: toml::Value = toml::from_str(config).map_err(|e| e.to_string())?;
+    let mut settings = vec![];
+    for (key, value) in config.as_table().into_iter().flatten() {
+        match value {
+            toml::Value::Table(table) => settings.extend(
+                table
+                    .iter()
+                    .map(|(name, value)| (format!("{}.{}", key, name), name, value)),
+            ),
+            value => settings.push((key.clone(), key, value)),
+        }
+    }
+
+    let mut config_args = vec![];
+    for (key, name, value) in settings {
+        let arg = find_arg(command, name).ok_or_else(|| format!("unknown setting '{}'", key))?;
+        let id = arg.get_id().to_string();
+        if matches!(
+            matches.value_source(&id),
+            Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+        let setting_args = setting_args(arg, value).map_err(|e| format!("'{}' {}", key, e))?;
+        validate_setting(command, &setting_args)
+            .map_err(|e| format!("'{}' is invalid: {}", key, e))?;
+        config_args.extend(setting_args);
+    }
+    Ok(config_args)
+}
+
+fn find_arg<'a>(command: &'a Command, name: &str) -> Option<&'a Arg> {
+    let id = name.replace('-', "_");
+    let long = name.replace('_', "-");
+    command.get_arguments().find(|arg| {
+        arg.get_id().as_str() != "config"
+            && arg.get_long().is_some()
+            && (arg.get_id().as_str() == id || arg.get_long() == Some(long.as_str()))
+    })
+}
+
+// The command line arguments for a single setting: a flag for `true`,
+// nothing for `false` and the option with a value for every value.
+fn setting_args(arg: &Arg, value: &toml::Value) -> Result<Vec<String>, String> {
+    let long = format!("--{}", arg.get_long().unwrap_or_default());
+    if !arg.get_action().takes_values() {
+        return match value {
+            toml::Value::Boolean(true) => Ok(vec![long]),
+            toml::Value::Boolean(false) => Ok(vec![]),
+            _ => Err(String::from("must be true or false")),
+        };
+    }
+    let values = match value {
+        toml::Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    let mut args = vec![];
+    for value in values {
+        let value = match value {
+            toml::Value::String(value) => value.clone(),
+            toml::Value::Integer(value) => value.to_string(),
+            toml::Value::Float(value) => value.to_string(),
+            toml::Value::Boolean(value) => value.to_string(),
+            _ => return Err(String::from("must be a string, number or boolean")),
+        };
+        args.push(long.clone());
+        args.push(value);
+    }
+    Ok(args)
+}
+
+// Parse the arguments of a single setting on their own, so an invalid
+// value can be reported with the name of the setting.
+fn validate_setting(command: &Command, setting_args: &[String]) -> Result<(), String> {
+    match command.clone().try_get_matches_from(
+        std::iter::once("pyrsia_node").chain(setting_args.iter().map(String::as_str)),
+    ) {
+        Err(e)
+            if !matches!(
+                e.kind(),
+                ErrorKind::MissingRequiredArgument | ErrorKind::ArgumentConflict
+            ) =>
+        {
+            let message = e.to_string();
+            Err(message
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_owned())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_from(command_line: &[&str], config: &str) -> Result<Vec<String>, String> {
+        let mut command = PyrsiaNodeArgs::command();
+        let matches = command.clone().try_get_matches_from(command_line).unwrap();
+        args_from_config(&mut command, &matches, config)
+    }
+
+    #[test]
+    fn test_args_from_config() {
+        let config = r#"
+            port = 7889
+
+            [network]
+            listen = "/ip4/0.0.0.0/tcp/44000"
+            listen_only = true
+
+            [storage]
+            disk-allocated = "20 GB"
+            compress_artifacts = false
+
+            [auth]
+            api_token = ["first", "second"]
+        "#;
+
+        assert_eq!(
+            args_from(&["pyrsia_node"], config).unwrap(),
+            vec![
+                "--port",
+                "7889",
+                "--listen",
+                "/ip4/0.0.0.0/tcp/44000",
+                "--listen-only",
+                "--disk-allocated",
+                "20 GB",
+                "--api-token",
+                "first",
+                "--api-token",
+                "second",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_line_takes_precedence() {
+        let config = r#"
+            port = 7889
+            host = "0.0.0.0"
+        "#;
+
+        assert_eq!(
+            args_from(&["pyrsia_node", "--port", "7999"], config).unwrap(),
+            vec!["--host", "0.0.0.0"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_setting() {
+        assert_eq!(
+            args_from(&["pyrsia_node"], "[storage]\ndisk_alocated = \"1 GB\"").unwrap_err(),
+            "unknown setting 'storage.disk_alocated'"
+        );
+    }
+
+    #[test]
+    fn test_invalid_setting() {
+        let error = args_from(&["pyrsia_node"], "[storage]\ngc_high_watermark = 150").unwrap_err();
+        assert!(error.starts_with("'storage.gc_high_watermark' is invalid"));
+
+        let error = args_from(&["pyrsia_node"], "listen_only = \"yes\"").unwrap_err();
+        assert_eq!(error, "'listen_only' must be true or false");
+    }
+}
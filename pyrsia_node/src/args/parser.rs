@@ -14,52 +14,339 @@
    limitations under the License.
 */
 
-use clap::Parser;
-use libp2p::Multiaddr;
+use super::config_file::parse_args_with_config_file;
+use clap::{Parser, Subcommand};
+use libp2p::{Multiaddr, PeerId};
+use pyrsia::util::disk_space_util::parse_disk_space;
+use pyrsia::util::env_util::read_var;
+use std::path::PathBuf;
 
+const DEFAULT_DATA_DIR: &str = "pyrsia";
+const DEFAULT_DISK_ALLOCATED: &str = "10 GB";
+const DEFAULT_GC_HIGH_WATERMARK: &str = "90";
+const DEFAULT_GC_LOW_WATERMARK: &str = "75";
 const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_LOG_FORMAT: &str = "text";
+const DEFAULT_LOG_MAX_FILES: &str = "5";
+const DEFAULT_LOG_MAX_SIZE: &str = "100 MB";
+const DEFAULT_LOG_ROTATION: &str = "daily";
 const DEFAULT_LISTEN_ADDRESS: &str = "/ip4/0.0.0.0/tcp/0";
 const DEFAULT_MAX_PROVIDED_KEYS: &str = "32768";
 const DEFAULT_MAPPING_SERVICE_ENDPOINT: &str =
     "https://raw.githubusercontent.com/pyrsia/pyrsia-mappings/main/";
 const DEFAULT_PIPELINE_SERVICE_ENDPOINT: &str = "http://localhost:8080";
 const DEFAULT_PORT: &str = "7888";
+/// The configuration file in the data directory that is used when --config is not set.
+pub const DEFAULT_CONFIG_FILE_NAME: &str = "pyrsia_node.toml";
+const DEFAULT_CPU_STRESS_WEIGHT: &str = "2";
+const DEFAULT_DISK_STRESS_WEIGHT: &str = "1";
+const DEFAULT_FAIR_SHARE_FACTOR: &str = "4";
+const DEFAULT_MEMORY_STRESS_WEIGHT: &str = "1";
+const DEFAULT_METRIC_UNITS: &str = "calibrated";
+const DEFAULT_METRICS_INTERVAL: &str = "5";
+const DEFAULT_NETWORK_STRESS_WEIGHT: &str = "1";
+const DEFAULT_QUOTA_STRESS_WEIGHT: &str = "1";
 const DEFAULT_BOOTSTRAP_URL: &str = "http://boot.pyrsia.link/status";
+const DEFAULT_REPLICATION_FACTOR: &str = "0";
+const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30;
+const DEFAULT_S3_REGION: &str = "us-east-1";
+const DEFAULT_STORAGE_BACKEND: &str = "filesystem";
 
 /// Application to connect to and participate in the Pyrsia network
 #[derive(Clone, Debug, Parser)]
-#[clap(name = "Pyrsia Node")]
+#[clap(
+    name = "Pyrsia Node",
+    after_help = "Every option can also be set with the environment variable shown in brackets, where flags take true or false and options that can be specified multiple times take a comma separated list."
+)]
 pub struct PyrsiaNodeArgs {
-    /// The host address to bind to for the Docker API
-    #[clap(long, short = 'H', default_value = DEFAULT_HOST)]
-    pub host: String,
+    /// The host name or IP address to bind the HTTP APIs to. Host names are resolved and bound on each of their addresses. Can be specified multiple times.
+    #[clap(long, short = 'H', env = "PYRSIA_HOST", default_value = DEFAULT_HOST, value_delimiter = ',')]
+    pub host: Vec<String>,
     /// The address to listen to for incoming requests from other pyrsia nodes
-    #[clap(long = "listen", short = 'L', default_value = DEFAULT_LISTEN_ADDRESS)]
+    #[clap(long = "listen", short = 'L', env = "PYRSIA_LISTEN_ADDRESS", default_value = DEFAULT_LISTEN_ADDRESS)]
     pub listen_address: Multiaddr,
     /// the port to listen to for the Docker API
-    #[clap(long, short, default_value = DEFAULT_PORT)]
+    #[clap(long, short, env = "PYRSIA_PORT", default_value = DEFAULT_PORT)]
     pub port: String,
     /// An address to connect with another Pyrsia Node (eg /ip4/127.0.0.1/tcp/45153/p2p/12D3KooWKsHbKbcVgyiRRgeXGCK4bp3MngnSU7ioeKTfQzd18B2v)
-    #[clap(long, short = 'P')]
+    #[clap(long, short = 'P', env = "PYRSIA_PEER")]
     pub peer: Option<Multiaddr>,
     /// Initialization mode, used only for the first authorized node in the Pyrsia network to initialize the Pyrsia network
-    #[clap(long)]
+    #[clap(long, env = "PYRSIA_INIT_BLOCKCHAIN")]
     pub init_blockchain: bool,
     /// An address to use for probing AutoNAT connections
-    #[clap(long, short = 'R')]
+    #[clap(long, short = 'R', env = "PYRSIA_PROBE")]
     pub probe: Option<Multiaddr>,
     /// listen_only mode - don't try to connect to any peers at startup
-    #[clap(long)]
+    #[clap(long, env = "PYRSIA_LISTEN_ONLY")]
     pub listen_only: bool,
-    #[clap(long, short = 'B', default_value = DEFAULT_BOOTSTRAP_URL)]
+    #[clap(long, short = 'B', env = "PYRSIA_BOOTSTRAP_URL", default_value = DEFAULT_BOOTSTRAP_URL)]
     pub bootstrap_url: String,
     /// The maximum number of keys that can be provided on the network by this Pyrsia Node.
-    #[clap(long, default_value = DEFAULT_MAX_PROVIDED_KEYS)]
+    #[clap(long, env = "PYRSIA_MAX_PROVIDED_KEYS", default_value = DEFAULT_MAX_PROVIDED_KEYS)]
     pub max_provided_keys: usize,
     /// The http endpoint where the mapping service will fetch mapping info from.
-    #[clap(long, default_value = DEFAULT_MAPPING_SERVICE_ENDPOINT)]
+    #[clap(long, env = "PYRSIA_MAPPING_SERVICE_ENDPOINT", default_value = DEFAULT_MAPPING_SERVICE_ENDPOINT)]
     pub mapping_service_endpoint: String,
     /// The http endpoint of the external build pipeline that the pipeline service will use to communicate with.
-    #[clap(long, default_value = DEFAULT_PIPELINE_SERVICE_ENDPOINT)]
+    #[clap(long, env = "PYRSIA_PIPELINE_SERVICE_ENDPOINT", default_value = DEFAULT_PIPELINE_SERVICE_ENDPOINT)]
     pub pipeline_service_endpoint: String,
+    /// The disk space that can be used to store artifacts (eg 10 GB, 512 MiB)
+    #[clap(long, env = "PYRSIA_DISK_ALLOCATED", default_value = DEFAULT_DISK_ALLOCATED, value_parser = parse_disk_space)]
+    pub disk_allocated: u64,
+    /// The percentage of the allocated disk space above which least recently used artifacts are evicted.
+    #[clap(long, env = "PYRSIA_GC_HIGH_WATERMARK", default_value = DEFAULT_GC_HIGH_WATERMARK, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub gc_high_watermark: u8,
+    /// The percentage of the allocated disk space at which the eviction of artifacts stops.
+    #[clap(long, env = "PYRSIA_GC_LOW_WATERMARK", default_value = DEFAULT_GC_LOW_WATERMARK, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub gc_low_watermark: u8,
+    /// Compress stored artifacts with zstd, except for artifacts in formats that are compressed already.
+    #[clap(long, env = "PYRSIA_COMPRESS_ARTIFACTS")]
+    pub compress_artifacts: bool,
+    /// The number of other peers that an artifact built on this node is pushed to. Set to 0 to disable replication.
+    #[clap(long, env = "PYRSIA_REPLICATION_FACTOR", default_value = DEFAULT_REPLICATION_FACTOR)]
+    pub replication_factor: usize,
+    /// The weight of the CPU usage in the stress score from 0 to 1 that the node advertises to its peers, which is the weighted average of the CPU usage, the network and disk throughput, the used part of the allocated disk space and the memory pressure. Only the ratio between the weights matters.
+    #[clap(long, env = "PYRSIA_CPU_STRESS_WEIGHT", default_value = DEFAULT_CPU_STRESS_WEIGHT, value_parser = parse_metric_weight)]
+    pub cpu_stress_weight: f64,
+    /// The weight of the network throughput, relative to 1 Gbit/s, in the stress score.
+    #[clap(long, env = "PYRSIA_NETWORK_STRESS_WEIGHT", default_value = DEFAULT_NETWORK_STRESS_WEIGHT, value_parser = parse_metric_weight)]
+    pub network_stress_weight: f64,
+    /// The weight of the artifact I/O of the node, relative to 500 MB/s, in the stress score.
+    #[clap(long, env = "PYRSIA_DISK_STRESS_WEIGHT", default_value = DEFAULT_DISK_STRESS_WEIGHT, value_parser = parse_metric_weight)]
+    pub disk_stress_weight: f64,
+    /// The weight of the used part of the disk space allocated to artifacts (--disk-allocated) in the stress score.
+    #[clap(long, env = "PYRSIA_QUOTA_STRESS_WEIGHT", default_value = DEFAULT_QUOTA_STRESS_WEIGHT, value_parser = parse_metric_weight)]
+    pub quota_stress_weight: f64,
+    /// The weight of the memory pressure, the used part of the memory or of the swap space, whichever is higher, in the stress score.
+    #[clap(long, env = "PYRSIA_MEMORY_STRESS_WEIGHT", default_value = DEFAULT_MEMORY_STRESS_WEIGHT, value_parser = parse_metric_weight)]
+    pub memory_stress_weight: f64,
+    /// Include the utilization of the volume that holds the artifacts in the disk stress. It also counts the I/O of other processes on that volume, so by default only the artifact I/O of the node itself is measured. Only supported on Linux.
+    #[clap(long, env = "PYRSIA_MEASURE_VOLUME_UTILIZATION")]
+    pub measure_volume_utilization: bool,
+    /// The units of the advertised quality metric: raw is relative to the hardware of the node itself, calibrated is relative to a reference node, based on a CPU and disk benchmark that runs once and is cached in the data directory, so that the metrics of peers on different hardware are comparable.
+    #[clap(long, env = "PYRSIA_METRIC_UNITS", default_value = DEFAULT_METRIC_UNITS, value_parser = ["raw", "calibrated"])]
+    pub metric_units: String,
+    /// Run the benchmark for calibrated metric units again, e.g. after the hardware of the node changed, instead of using the cached calibration.
+    #[clap(long, env = "PYRSIA_RECALIBRATE")]
+    pub recalibrate: bool,
+    /// The number of seconds between two samples of the components of the stress score, which are smoothed into the stress score.
+    #[clap(long, env = "PYRSIA_METRICS_INTERVAL", default_value = DEFAULT_METRICS_INTERVAL, value_parser = clap::value_parser!(u64).range(1..))]
+    pub metrics_interval: u64,
+    /// The stress score from 0 to 1 above which the node refuses artifact requests, asking peers to request the artifact elsewhere and HTTP clients to retry later. Requests are never refused when not set.
+    #[clap(long, env = "PYRSIA_LOAD_SHEDDING_THRESHOLD", value_parser = parse_load_shedding_threshold)]
+    pub load_shedding_threshold: Option<f64>,
+    /// Refuse artifact requests of a peer that was served more than this many times the average of the other peers in the last 10 minutes, so that one greedy peer cannot starve the others. Set to 0 to serve every peer regardless of its share.
+    #[clap(long, env = "PYRSIA_FAIR_SHARE_FACTOR", default_value = DEFAULT_FAIR_SHARE_FACTOR, value_parser = parse_fair_share_factor)]
+    pub fair_share_factor: f64,
+    /// The file that the metrics history of the last day is persisted in, so that it survives restarts. The history is kept in memory only when not set.
+    #[clap(long, env = "PYRSIA_METRICS_HISTORY_FILE")]
+    pub metrics_history_file: Option<PathBuf>,
+    /// Skip the hash verification of artifacts read from local storage. Artifacts served to other peers are always verified.
+    #[clap(long, env = "PYRSIA_SKIP_READ_VERIFICATION")]
+    pub skip_read_verification: bool,
+    /// The peer id of a key that is trusted to sign in-toto attestations, like build provenance. Can be specified multiple times.
+    #[clap(long, env = "PYRSIA_TRUSTED_ATTESTATION_KEY", value_delimiter = ',')]
+    pub trusted_attestation_key: Vec<PeerId>,
+    /// A TOML file with the admission policy that decides which artifacts are served, e.g. rules that require a transparency log or a number of independent verifications.
+    #[clap(long, env = "PYRSIA_POLICY_FILE")]
+    pub policy_file: Option<PathBuf>,
+    /// The role of the node: regular nodes serve artifacts, build nodes also build and publish them, authority nodes also authorize other nodes. Build and authority nodes must be in the authority list on the blockchain. Defaults to authority with --init-blockchain and to regular otherwise.
+    #[clap(long, env = "PYRSIA_ROLE", value_parser = ["regular", "build", "authority"])]
+    pub role: Option<String>,
+    /// The file that the audit log of all artifact and administrative operations is appended to. Defaults to audit.log in the artifact directory.
+    #[clap(long, env = "PYRSIA_AUDIT_LOG")]
+    pub audit_log: Option<PathBuf>,
+    /// Sign every entry of the audit log with the key pair of the node.
+    #[clap(long, env = "PYRSIA_SIGN_AUDIT_LOG")]
+    pub sign_audit_log: bool,
+    /// Sign the responses of the node API with the key pair of the node, in a detached signature header.
+    #[clap(long, env = "PYRSIA_SIGN_RESPONSES")]
+    pub sign_responses: bool,
+    /// The PEM file with the TLS certificate chain to serve the HTTP APIs over HTTPS, which Docker requires for registries that are not on localhost.
+    #[clap(long, env = "PYRSIA_TLS_CERT", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// The PEM file with the private key of the TLS certificate.
+    #[clap(long, env = "PYRSIA_TLS_KEY", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Serve the HTTP APIs over HTTPS with a self-signed certificate for localhost and the host address, which is generated in the data directory. Only meant for testing.
+    #[clap(long, env = "PYRSIA_TLS_SELF_SIGNED", conflicts_with = "tls_cert")]
+    pub tls_self_signed: bool,
+    /// The number of HTTP requests per minute that each client may send. Clients that exceed it receive 429 Too Many Requests.
+    #[clap(long, env = "PYRSIA_RATE_LIMIT")]
+    pub rate_limit: Option<u32>,
+    /// The number of Docker and Maven artifact downloads that the node serves concurrently.
+    #[clap(long, env = "PYRSIA_MAX_CONCURRENT_DOWNLOADS")]
+    pub max_concurrent_downloads: Option<usize>,
+    /// A bearer token that grants access to the node API. Can be specified multiple times. When a token is configured or issued with 'pyrsia admin token issue', the node API requires one; the Docker and Maven APIs are not affected.
+    #[clap(long, env = "PYRSIA_API_TOKEN", value_delimiter = ',')]
+    pub api_token: Vec<String>,
+    /// Allow clients on other hosts to use the admin endpoints of the node API while no API token is configured or issued, eg the CLI on the host when the node runs in a container. Only use this when the node API can't be reached by others.
+    #[clap(long, env = "PYRSIA_ALLOW_REMOTE_ADMIN")]
+    pub allow_remote_admin: bool,
+    /// An origin (eg https://dashboard.example.com) from which browsers may call the node API, or * to allow any origin. Can be specified multiple times. The Docker and Maven APIs are not affected.
+    #[clap(long, env = "PYRSIA_CORS_ALLOWED_ORIGIN", value_delimiter = ',')]
+    pub cors_allowed_origin: Vec<String>,
+    /// An HTTP method that browsers on the allowed origins may use. Can be specified multiple times. Defaults to GET, POST, PUT and DELETE.
+    #[clap(long, env = "PYRSIA_CORS_ALLOWED_METHOD", value_delimiter = ',')]
+    pub cors_allowed_method: Vec<String>,
+    /// The URL of the node API of another node (eg https://node.example.com:7888) to periodically pull transparency logs and signed package metadata from over HTTPS, for networks that block the libp2p ports. Can be specified multiple times. Artifacts are still retrieved over libp2p.
+    #[clap(long, env = "PYRSIA_FEDERATION_PEER", value_delimiter = ',')]
+    pub federation_peer: Vec<String>,
+    /// Allow federation peers with an http URL. The bearer token and the pulled entries are then sent unencrypted, so this is only meant for testing.
+    #[clap(long, env = "PYRSIA_FEDERATION_ALLOW_HTTP")]
+    pub federation_allow_http: bool,
+    /// The bearer token to send to the federation peers, when their node API requires one.
+    #[clap(long, env = "PYRSIA_FEDERATION_TOKEN")]
+    pub federation_token: Option<String>,
+    /// The number of seconds between two pulls from the federation peers.
+    #[clap(long, env = "PYRSIA_FEDERATION_INTERVAL", default_value_t = 300)]
+    pub federation_interval: u64,
+    /// The backend that stores the artifacts: the local filesystem or an S3 compatible object store.
+    #[clap(long, env = "PYRSIA_STORAGE_BACKEND", default_value = DEFAULT_STORAGE_BACKEND, value_parser = ["filesystem", "s3"])]
+    pub storage_backend: String,
+    /// The endpoint of the S3 compatible object store (eg https://s3.us-east-1.amazonaws.com). The credentials are read from PYRSIA_S3_ACCESS_KEY_ID and PYRSIA_S3_SECRET_ACCESS_KEY.
+    #[clap(long, env = "PYRSIA_S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+    /// The bucket of the S3 compatible object store that stores the artifacts.
+    #[clap(long, env = "PYRSIA_S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+    /// The region of the S3 compatible object store.
+    #[clap(long, env = "PYRSIA_S3_REGION", default_value = DEFAULT_S3_REGION)]
+    pub s3_region: String,
+    /// Keep the key pair of the node encrypted in the keystore of the data directory. The passphrase is read from PYRSIA_KEYSTORE_PASSPHRASE. An existing key pair file is imported into the keystore.
+    #[clap(long, env = "PYRSIA_USE_KEYSTORE")]
+    pub use_keystore: bool,
+    /// The directory that holds all data of the node: artifacts, indexes, the blockchain and the key pair. Defaults to "pyrsia".
+    #[clap(long, env = "PYRSIA_DATA_DIR")]
+    pub data_dir: Option<PathBuf>,
+    /// The number of seconds that in-flight HTTP requests, like artifact transfers, may take to finish when the node is stopped with SIGTERM or SIGINT.
+    #[clap(long, env = "PYRSIA_SHUTDOWN_TIMEOUT", default_value_t = DEFAULT_SHUTDOWN_TIMEOUT)]
+    pub shutdown_timeout: u64,
+    /// The format of the log: human readable text or one JSON object per line. The level of detail is set with RUST_LOG.
+    #[clap(long, env = "PYRSIA_LOG_FORMAT", default_value = DEFAULT_LOG_FORMAT, value_parser = ["text", "json"])]
+    pub log_format: String,
+    /// The file to write the log to instead of standard error.
+    #[clap(long, env = "PYRSIA_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+    /// How often the log file is rotated, besides when it reaches --log-max-size.
+    #[clap(long, env = "PYRSIA_LOG_ROTATION", default_value = DEFAULT_LOG_ROTATION, value_parser = ["never", "hourly", "daily"])]
+    pub log_rotation: String,
+    /// The size at which the log file is rotated (eg 100 MB).
+    #[clap(long, env = "PYRSIA_LOG_MAX_SIZE", default_value = DEFAULT_LOG_MAX_SIZE, value_parser = parse_disk_space)]
+    pub log_max_size: u64,
+    /// The number of rotated log files that are kept.
+    #[clap(long, env = "PYRSIA_LOG_MAX_FILES", default_value = DEFAULT_LOG_MAX_FILES, value_parser = clap::value_parser!(u32).range(1..=1000))]
+    pub log_max_files: u32,
+    /// The endpoint of an OpenTelemetry collector (eg http://localhost:4317) to export traces to over OTLP/gRPC, so that pulls can be followed from the HTTP request to the p2p network and the storage in e.g. Jaeger or Tempo.
+    #[clap(long, env = "PYRSIA_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+    /// Run the node in the background, detached from the terminal. The process id is written to --pid-file and the log to --log-file, which default to pyrsia_node.pid and pyrsia_node.log in the data directory.
+    #[clap(long, env = "PYRSIA_DAEMON")]
+    pub daemon: bool,
+    /// The file that the process id of the node is written to while it runs.
+    #[clap(long, env = "PYRSIA_PID_FILE")]
+    pub pid_file: Option<PathBuf>,
+    /// A TOML file with settings for the node. Settings are named like the long options (eg disk_allocated = "20 GB", api_token = ["..."]) and may be grouped in tables like [network] or [storage]. Command line options and environment variables take precedence. Defaults to pyrsia_node.toml in the data directory, when it exists. The rate limits, API tokens, policy file and federation token are reloaded when the file changes or the node receives SIGHUP.
+    #[clap(long, env = "PYRSIA_CONFIG")]
+    pub config: Option<PathBuf>,
+    #[clap(subcommand)]
+    pub command: Option<NodeCommand>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+pub enum NodeCommand {
+    /// Manage the Windows service that runs the node
+    #[clap(subcommand)]
+    Service(ServiceCommand),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+pub enum ServiceCommand {
+    /// Install the node as a Windows service that starts automatically, with the options that precede the subcommand (eg pyrsia_node --host 0.0.0.0 service install)
+    Install,
+    /// Stop the Windows service and remove it
+    Uninstall,
+    /// Run the node under the Windows service control manager, which the installed service does
+    #[clap(hide = true)]
+    Run,
+}
+
+impl PyrsiaNodeArgs {
+    /// Parse the command line arguments, completed with the settings of
+    /// the configuration file given with --config. Exits on errors.
+    pub fn parse_with_config_file() -> Self {
+        parse_args_with_config_file(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Whether the node runs as a Windows service.
+    pub fn is_windows_service(&self) -> bool {
+        self.command == Some(NodeCommand::Service(ServiceCommand::Run))
+    }
+
+    /// The configuration file of the node: the file given with --config or
+    /// the default configuration file in the data directory, if it exists.
+    pub fn config_file(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(|| {
+            let default_config_file = self.data_dir().join(DEFAULT_CONFIG_FILE_NAME);
+            default_config_file.is_file().then_some(default_config_file)
+        })
+    }
+
+    /// The directory that holds all data of the node.
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.data_dir {
+            Some(data_dir) => data_dir.clone(),
+            None => PathBuf::from(DEFAULT_DATA_DIR),
+        }
+    }
+
+    /// The directory of the artifact storage and its indexes, unless
+    /// overridden by PYRSIA_ARTIFACT_PATH.
+    pub fn artifact_path(&self) -> PathBuf {
+        path_from_var("PYRSIA_ARTIFACT_PATH", self.data_dir())
+    }
+
+    /// The directory of the blockchain, unless overridden by PYRSIA_BLOCKCHAIN_PATH.
+    pub fn blockchain_path(&self) -> PathBuf {
+        path_from_var("PYRSIA_BLOCKCHAIN_PATH", self.data_dir().join("blockchain"))
+    }
+
+    /// The directory of the encrypted keystore.
+    pub fn keystore_path(&self) -> PathBuf {
+        self.data_dir().join("keystore")
+    }
+
+    /// The file of the key pair of the node, unless overridden by PYRSIA_KEYPAIR.
+    pub fn keypair_path(&self) -> PathBuf {
+        path_from_var("PYRSIA_KEYPAIR", self.data_dir().join("p2p_keypair.ser"))
+    }
+}
+
+fn parse_metric_weight(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight >= 0_f64 => Ok(weight),
+        _ => Err(String::from("expected a number that is 0 or more")),
+    }
+}
+
+fn parse_fair_share_factor(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(factor) if factor.is_finite() && factor >= 0_f64 => Ok(factor),
+        _ => Err(String::from("expected a number that is 0 or more")),
+    }
+}
+
+fn parse_load_shedding_threshold(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(threshold) if (0_f64..=1_f64).contains(&threshold) => Ok(threshold),
+        _ => Err(String::from("expected a number from 0 to 1")),
+    }
+}
+
+fn path_from_var(variable_name: &str, default_path: PathBuf) -> PathBuf {
+    match read_var(variable_name, "") {
+        path if path.is_empty() => default_path,
+        path => PathBuf::from(path),
+    }
 }
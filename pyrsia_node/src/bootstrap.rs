@@ -0,0 +1,264 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! Provisions what the node needs before it starts: the data directory, the
+//! key pair that is both the p2p identity and the signing key of the node,
+//! and on the first run a default configuration file.
+
+use crate::args::parser::{PyrsiaNodeArgs, DEFAULT_CONFIG_FILE_NAME};
+use crate::startup::{self, StartupError, StartupErrorKind};
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use libp2p::identity::Keypair;
+use libp2p::PeerId;
+use log::{info, warn};
+use pyrsia::artifact_service::trust_store::fingerprint_of;
+use pyrsia::util::env_util::read_var;
+use pyrsia::util::keypair_util;
+use pyrsia::util::keystore::Keystore;
+use std::fmt::Write;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+const KEYSTORE_PASSPHRASE_VAR: &str = "PYRSIA_KEYSTORE_PASSPHRASE";
+const NODE_KEY_NAME: &str = "node";
+
+/// The key pair of the node and what was created for it.
+pub struct Provisioned {
+    pub keypair: Keypair,
+    /// Whether the key pair of the node was generated, which only happens
+    /// on the first run.
+    pub first_run: bool,
+    /// The default configuration file, when it was written on this run.
+    pub config_file: Option<PathBuf>,
+}
+
+/// Create the data directories, load or generate the key pair of the node
+/// and, on the first run, write the default configuration file to the data
+/// directory, unless a configuration file is used already.
+pub fn provision(args: &PyrsiaNodeArgs) -> Result<Provisioned, StartupError> {
+    let first_run = !keypair_exists(args);
+
+    create_data_directories(args)
+        .map_err(|e| StartupError::new(StartupErrorKind::DataDirectory, format!("{:#}", e)))?;
+    startup::check_data_dir_writable(&args.data_dir())?;
+
+    let keypair = load_local_keypair(args).map_err(|e| {
+        StartupError::new(
+            StartupErrorKind::KeyPair,
+            format!("Failed to load the key pair of the node: {:#}", e),
+        )
+    })?;
+
+    let default_config_file = args.data_dir().join(DEFAULT_CONFIG_FILE_NAME);
+    let config_file = if first_run && args.config_file().is_none() {
+        fs::write(&default_config_file, default_config())
+            .map_err(|e| {
+                StartupError::new(
+                    StartupErrorKind::DataDirectory,
+                    format!(
+                        "Failed to write the configuration file {}: {}",
+                        default_config_file.display(),
+                        e
+                    ),
+                )
+            })
+            .map(|_| Some(default_config_file))?
+    } else {
+        None
+    };
+
+    Ok(Provisioned {
+        keypair,
+        first_run,
+        config_file,
+    })
+}
+
+/// Print what was provisioned on the first run and where the node can be
+/// reached.
+pub fn print_summary(
+    args: &PyrsiaNodeArgs,
+    provisioned: &Provisioned,
+    p2p_addresses: &[String],
+    http_addresses: &[SocketAddr],
+) {
+    let peer_id = PeerId::from(provisioned.keypair.public());
+    let mut summary = String::from("Pyrsia Node was set up for the first time\n");
+    let mut line = |label: &str, value: &dyn std::fmt::Display| {
+        let _ = writeln!(summary, "  {:<17} {}", format!("{}:", label), value);
+    };
+    line("Data directory", &args.data_dir().display());
+    line("Peer id", &peer_id);
+    line(
+        "Key fingerprint",
+        &fingerprint_of(&peer_id).unwrap_or_default(),
+    );
+    if args.use_keystore {
+        line("Keystore", &args.keystore_path().display());
+    } else {
+        line("Key pair file", &args.keypair_path().display());
+    }
+    if let Some(config_file) = &provisioned.config_file {
+        line("Config file", &config_file.display());
+    }
+    for address in p2p_addresses {
+        line("p2p address", address);
+    }
+    for address in http_addresses {
+        line("HTTP address", address);
+    }
+    println!("{}", summary.trim_end());
+}
+
+// A configuration file with every setting that has a default, commented out
+// and preceded by its description.
+fn default_config() -> String {
+    let mut config = String::from(
+        "# The settings of the Pyrsia Node. Uncomment a setting to change it, command line\n\
+         # options and environment variables take precedence. See pyrsia_node --help.\n",
+    );
+    for arg in PyrsiaNodeArgs::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        if arg.get_long().is_none() || id == "config" {
+            continue;
+        }
+        let defaults = arg
+            .get_default_values()
+            .iter()
+            .map(|value| format!("{:?}", value.to_string_lossy()))
+            .collect::<Vec<_>>();
+        let value = if !arg.get_action().takes_values() {
+            String::from("false")
+        } else if defaults.is_empty() {
+            continue;
+        } else if arg.get_value_delimiter().is_some() {
+            format!("[{}]", defaults.join(", "))
+        } else {
+            defaults.join(" ")
+        };
+        config.push('\n');
+        if let Some(help) = arg.get_help() {
+            let _ = writeln!(config, "# {}", help);
+        }
+        let _ = writeln!(config, "# {} = {}", id, value);
+    }
+    config
+}
+
+fn keypair_exists(args: &PyrsiaNodeArgs) -> bool {
+    args.keypair_path().exists()
+        || (args.use_keystore && Keystore::new(args.keystore_path()).contains(NODE_KEY_NAME))
+}
+
+fn create_data_directories(args: &PyrsiaNodeArgs) -> Result<()> {
+    let data_dir = args.data_dir();
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    let keypair_path = args.keypair_path();
+    let keypair_dir = keypair_path.parent().unwrap_or(&data_dir);
+    for dir in [
+        args.artifact_path(),
+        args.blockchain_path(),
+        keypair_dir.to_path_buf(),
+    ] {
+        fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create directory {:?}: {}", dir, e))?;
+    }
+    Ok(())
+}
+
+// Load the key pair of the node from the keystore when it is enabled, and
+// from the plain key pair file otherwise. Either way, a new key pair is
+// generated when none exists yet.
+fn load_local_keypair(args: &PyrsiaNodeArgs) -> Result<Keypair> {
+    if !args.use_keystore {
+        let keypair_path = args.keypair_path();
+        if keypair_path.exists() {
+            return keypair_util::load_ed25519_keypair(&keypair_path)
+                .with_context(|| format!("Invalid key pair file {}", keypair_path.display()));
+        }
+        info!("Generating the key pair {}", keypair_path.display());
+        return Ok(keypair_util::load_or_generate_ed25519(keypair_path));
+    }
+
+    let passphrase = read_var(KEYSTORE_PASSPHRASE_VAR, "");
+    if passphrase.is_empty() {
+        anyhow::bail!(
+            "{} must be set to use the keystore",
+            KEYSTORE_PASSPHRASE_VAR
+        );
+    }
+    let keystore = Keystore::new(args.keystore_path());
+    if !keystore.contains(NODE_KEY_NAME) && args.keypair_path().exists() {
+        info!(
+            "Importing the key pair {} into the keystore",
+            args.keypair_path().display()
+        );
+        keystore.store(
+            NODE_KEY_NAME,
+            &keypair_util::load_or_generate_ed25519(args.keypair_path()),
+            &passphrase,
+        )?;
+        warn!(
+            "The unencrypted key pair {} can be removed now",
+            args.keypair_path().display()
+        );
+    }
+    Ok(keystore.load_or_generate(NODE_KEY_NAME, &passphrase)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::config_file::parse_args_with_config_file;
+    use std::env;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let config = default_config();
+        let uncommented = config
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(uncommented.contains("port = \"7888\""));
+        assert!(uncommented.contains("host = [\"127.0.0.1\"]"));
+
+        let config_file = env::temp_dir().join(format!(
+            "pyrsia_node_default_config_{}.toml",
+            std::process::id()
+        ));
+        fs::write(&config_file, uncommented).unwrap();
+        let args =
+            parse_args_with_config_file(["pyrsia_node", "--config", config_file.to_str().unwrap()]);
+        fs::remove_file(&config_file).unwrap();
+
+        let args = args.unwrap();
+        assert_eq!(args.port, "7888");
+        assert_eq!(args.host, vec![String::from("127.0.0.1")]);
+        assert!(!args.listen_only);
+    }
+}
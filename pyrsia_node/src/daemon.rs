@@ -0,0 +1,139 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::args::parser::PyrsiaNodeArgs;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The environment variable of the --daemon flag, which is set to false for
+/// the background process so it doesn't detach again.
+const DAEMON_VAR: &str = "PYRSIA_DAEMON";
+const DEFAULT_LOG_FILE: &str = "pyrsia_node.log";
+const DEFAULT_PID_FILE: &str = "pyrsia_node.pid";
+/// How long to wait for the background process to start serving.
+const DAEMON_START_TIMEOUT: Duration = Duration::from_secs(30);
+const DAEMON_START_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The PID file of the node, which holds the process id while the node runs
+/// and is removed when it stops.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\n", process::id()))?;
+        Ok(PidFile {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// The PID file of the node when it runs in the background.
+fn default_pid_file(args: &PyrsiaNodeArgs) -> PathBuf {
+    args.data_dir().join(DEFAULT_PID_FILE)
+}
+
+/// Starts the node again as a background process that is detached from the
+/// terminal, with its output appended to the log file. Returns the process
+/// id of the background process once it serves the HTTP APIs, which it
+/// signals by writing its PID file.
+pub fn spawn_daemon(args: &PyrsiaNodeArgs) -> anyhow::Result<u32> {
+    let mut daemon_args: Vec<OsString> = env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--daemon")
+        .collect();
+    let pid_file = match &args.pid_file {
+        Some(pid_file) => pid_file.clone(),
+        None => {
+            let pid_file = default_pid_file(args);
+            daemon_args.extend([OsString::from("--pid-file"), pid_file.clone().into()]);
+            pid_file
+        }
+    };
+    let log_file = match &args.log_file {
+        Some(log_file) => log_file.clone(),
+        None => {
+            let log_file = args.data_dir().join(DEFAULT_LOG_FILE);
+            daemon_args.extend([OsString::from("--log-file"), log_file.clone().into()]);
+            log_file
+        }
+    };
+    if let Some(parent) = log_file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    // Anything the node writes outside of the log, like panics, ends up in
+    // the log file too.
+    let output = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file)?;
+    let _ = fs::remove_file(&pid_file);
+
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(daemon_args)
+        .env(DAEMON_VAR, "false")
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Keeps signals of the terminal, like ctrl-c, away from the node.
+        command.process_group(0);
+    }
+    let mut child = command.spawn()?;
+
+    let started_at = Instant::now();
+    while started_at.elapsed() < DAEMON_START_TIMEOUT {
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!(
+                "The node stopped with {}, see {} for details",
+                status,
+                log_file.display()
+            );
+        }
+        if read_pid(&pid_file) == Some(child.id()) {
+            return Ok(child.id());
+        }
+        thread::sleep(DAEMON_START_POLL_INTERVAL);
+    }
+    anyhow::bail!(
+        "The node did not start within {} seconds, see {} for details",
+        DAEMON_START_TIMEOUT.as_secs(),
+        log_file.display()
+    )
+}
+
+fn read_pid(pid_file: &Path) -> Option<u32> {
+    fs::read_to_string(pid_file).ok()?.trim().parse().ok()
+}
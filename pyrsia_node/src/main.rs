@@ -15,15 +15,30 @@
 */
 
 pub mod args;
+pub mod bootstrap;
+pub mod daemon;
 pub mod network;
+pub mod reload;
+#[cfg(windows)]
+pub mod service;
+pub mod startup;
+pub mod systemd;
+pub mod telemetry;
 
-use anyhow::Result;
-use args::parser::PyrsiaNodeArgs;
+use anyhow::{Context, Result};
+use args::parser::{NodeCommand, PyrsiaNodeArgs, ServiceCommand};
+use daemon::PidFile;
 use libp2p::identity::Keypair;
 use libp2p::PeerId;
 use network::handlers;
+use pyrsia::artifact_service::audit_log::{AuditAction, AuditLog};
+use pyrsia::artifact_service::events::NodeEventKind;
+use pyrsia::artifact_service::federation::FederationPeer;
+use pyrsia::artifact_service::model::NodeRole;
+use pyrsia::artifact_service::policy::Policy;
 use pyrsia::artifact_service::service::ArtifactService;
-use pyrsia::artifact_service::storage::ARTIFACTS_DIR;
+use pyrsia::artifact_service::storage::{ArtifactStorage, GcWatermarks};
+use pyrsia::artifact_service::storage_backend::s3::S3Backend;
 use pyrsia::blockchain_service::event::{BlockchainEventClient, BlockchainEventLoop};
 use pyrsia::blockchain_service::service::BlockchainService;
 use pyrsia::build_service::event::{BuildEventClient, BuildEventLoop};
@@ -33,44 +48,236 @@ use pyrsia::docker::v2::routes::make_docker_routes;
 use pyrsia::java::maven2::routes::make_maven_routes;
 use pyrsia::logging::*;
 use pyrsia::network::client::Client;
+use pyrsia::network::event_loop::METRIC_ADVERTISEMENT_INTERVAL;
+use pyrsia::network::fair_share::FairShare;
 use pyrsia::network::p2p;
+use pyrsia::network::signer::Signer;
+use pyrsia::network::transfer_metrics::TransferCounters;
+use pyrsia::node_api::cors::node_cors;
+use pyrsia::node_api::response_signing::sign_response;
 use pyrsia::node_api::routes::make_node_routes;
-use pyrsia::peer_metrics::metrics::PeerMetrics;
+use pyrsia::peer_metrics::calibration::{Calibration, MetricUnits, CALIBRATION_FILE_NAME};
+use pyrsia::peer_metrics::history::{
+    now_millis, MetricsHistory, MetricsSample, HISTORY_CAPACITY, HISTORY_SAMPLE_INTERVAL,
+};
+use pyrsia::peer_metrics::load_shedding::LoadShedding;
+use pyrsia::peer_metrics::metrics::{MetricWeights, PeerMetrics, StorageProbes};
 use pyrsia::util::env_util::read_var;
-use pyrsia::util::keypair_util::{self, KEYPAIR_FILENAME};
+use pyrsia::util::rate_limit::{rate_limit, RateLimits};
 use pyrsia::verification_service::service::VerificationService;
+use reload::ConfigReloader;
 
-use clap::Parser;
-use log::{debug, info, warn};
+use futures::future::{self, BoxFuture, FutureExt};
+use log::{debug, error, info, warn};
+use std::env;
 use std::error::Error;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use tokio::sync::mpsc;
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
-use warp::Filter;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+const GARBAGE_COLLECTION_INTERVAL: Duration = Duration::from_secs(60);
+const ARTIFACT_SCRUB_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const PUBLIC_KEY_PUBLICATION_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    pretty_env_logger::init_timed();
+async fn main() {
+    let args = PyrsiaNodeArgs::parse_with_config_file();
+
+    match &args.command {
+        Some(NodeCommand::Service(ServiceCommand::Run)) | None => {}
+        Some(NodeCommand::Service(service_command)) => {
+            manage_windows_service(service_command, &args);
+            return;
+        }
+    }
+
+    if args.daemon {
+        match daemon::spawn_daemon(&args) {
+            Ok(pid) => {
+                println!(
+                    "Pyrsia Node is running in the background with process id {}",
+                    pid
+                );
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to start Pyrsia Node in the background: {:#}", e);
+                process::exit(startup::EXIT_FAILURE);
+            }
+        }
+    }
+
+    if let Err(e) = telemetry::init_tracing(&args) {
+        eprintln!("Failed to initialize the logger: {}", e);
+        process::exit(startup::EXIT_FAILURE);
+    }
+
+    let result = if args.is_windows_service() {
+        run_windows_service(args).await
+    } else {
+        run(args, shutdown_signal().boxed()).await
+    };
+    telemetry::shutdown_tracing();
+    if let Err(e) = result {
+        error!("{}", e);
+        process::exit(startup::exit_code(e.as_ref()));
+    }
+}
+
+#[cfg(windows)]
+fn manage_windows_service(service_command: &ServiceCommand, args: &PyrsiaNodeArgs) {
+    let (result, action) = match service_command {
+        ServiceCommand::Install => (service::install(args), "installed"),
+        ServiceCommand::Uninstall => (service::uninstall(), "uninstalled"),
+        ServiceCommand::Run => unreachable!("run is dispatched to the service entry point"),
+    };
+    match result {
+        Ok(()) => println!("The {} service was {}", service::SERVICE_NAME, action),
+        Err(e) => {
+            eprintln!(
+                "Failed to manage the {} service: {:#}",
+                service::SERVICE_NAME,
+                e
+            );
+            process::exit(startup::EXIT_FAILURE);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn manage_windows_service(_service_command: &ServiceCommand, _args: &PyrsiaNodeArgs) {
+    eprintln!("Windows services are only supported on Windows");
+    process::exit(startup::EXIT_FAILURE);
+}
 
-    debug!("Parse CLI arguments");
-    let args = PyrsiaNodeArgs::parse();
+// Runs the node until the service control manager stops the service.
+#[cfg(windows)]
+async fn run_windows_service(args: PyrsiaNodeArgs) -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || service::run(args, runtime)).await??;
+    Ok(())
+}
 
-    let mut peer_metrics = PeerMetrics::new();
+#[cfg(not(windows))]
+async fn run_windows_service(_args: PyrsiaNodeArgs) -> Result<(), Box<dyn Error>> {
+    Err("Windows services are only supported on Windows".into())
+}
+
+async fn run(
+    args: PyrsiaNodeArgs,
+    mut shutdown_signal: BoxFuture<'static, ()>,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Validate the HTTP addresses");
+    let http_addresses = startup::http_addresses(&args.host, &args.port)?;
+    for http_address in &http_addresses {
+        startup::check_port_available(*http_address)?;
+    }
+
+    debug!("Provision the data directory and the key pair of the node");
+    let provisioned = bootstrap::provision(&args)?;
 
     debug!("Create p2p components");
-    let (mut p2p_client, local_keypair, mut p2p_events, event_loop) =
-        p2p::setup_libp2p_swarm(args.max_provided_keys)?;
+    let signer: Arc<dyn Signer> = Arc::new(provisioned.keypair.clone());
+    let (mut p2p_client, local_keypair, mut p2p_events, event_loop) = p2p::setup_libp2p_swarm(
+        args.max_provided_keys,
+        provisioned.keypair.clone(),
+        signer.clone(),
+    )?;
 
     debug!("Start p2p event loop");
     tokio::spawn(event_loop.run());
 
     debug!("Create pyrsia services");
-    let (blockchain_event_client, build_event_client, artifact_service) =
-        setup_pyrsia_services(p2p_client.clone(), local_keypair, &args).await?;
+    let (blockchain_event_client, build_event_client, mut artifact_service) =
+        setup_pyrsia_services(p2p_client.clone(), local_keypair, signer, &args).await?;
+
+    debug!("Start sampling the peer metrics");
+    let artifact_storage = artifact_service.artifact_storage.clone();
+    let quota_storage = artifact_storage.clone();
+    let peer_metrics = PeerMetrics::start(
+        MetricWeights {
+            cpu: args.cpu_stress_weight,
+            network: args.network_stress_weight,
+            disk: args.disk_stress_weight,
+            quota: args.quota_stress_weight,
+            memory: args.memory_stress_weight,
+        },
+        Duration::from_secs(args.metrics_interval),
+        StorageProbes {
+            quota_usage: Some(Box::new(move || {
+                let disk_allocated = quota_storage.disk_allocated?;
+                let disk_usage = quota_storage.disk_usage().ok()?;
+                Some(disk_usage as f64 / disk_allocated.max(1) as f64)
+            })),
+            storage_io: Some(Box::new(move || artifact_storage.io_bytes())),
+            volume: args.measure_volume_utilization.then(|| {
+                artifact_service
+                    .artifact_storage
+                    .repository_path()
+                    .to_path_buf()
+            }),
+        },
+    );
+    let peer_metrics = match MetricUnits::from_str(&args.metric_units)? {
+        MetricUnits::Calibrated => {
+            let calibration_file = args.data_dir().join(CALIBRATION_FILE_NAME);
+            let volume = artifact_service
+                .artifact_storage
+                .repository_path()
+                .to_path_buf();
+            let recalibrate = args.recalibrate;
+            peer_metrics.calibrated(
+                tokio::task::spawn_blocking(move || {
+                    Calibration::load_or_measure(&calibration_file, &volume, recalibrate)
+                })
+                .await?,
+            )
+        }
+        MetricUnits::Raw => peer_metrics,
+    };
+    artifact_service.load_shedding =
+        LoadShedding::new(peer_metrics.clone(), args.load_shedding_threshold);
 
     debug!("Setup HTTP server");
-    setup_http(&args, artifact_service.clone(), p2p_client.clone());
+    let (http_shutdown_sender, http_server) = setup_http(
+        &args,
+        &http_addresses,
+        artifact_service.clone(),
+        p2p_client.clone(),
+    )?;
+
+    debug!("Listen for p2p connections");
+    p2p_client.listen(&args.listen_address).await?;
+
+    if provisioned.first_run {
+        let mut p2p_addresses = p2p_client.status().await?.peer_addrs;
+        if p2p_addresses.is_empty() {
+            p2p_addresses.push(args.listen_address.to_string());
+        }
+        bootstrap::print_summary(&args, &provisioned, &p2p_addresses, &http_addresses);
+    }
+
+    // The PID file is removed again when it is dropped at shutdown.
+    let _pid_file = match &args.pid_file {
+        Some(pid_file) => {
+            debug!("Write the PID file {}", pid_file.display());
+            Some(PidFile::create(pid_file)?)
+        }
+        None => None,
+    };
+    systemd::notify_ready();
+    systemd::start_watchdog(p2p_client.clone());
 
     debug!("Establishing connection with p2p network");
     establish_connection_with_p2p_network(
@@ -84,18 +291,54 @@ async fn main() -> Result<(), Box<dyn Error>> {
     debug!("Provide local artifacts");
     artifact_service.clone().provide_local_artifacts().await?;
 
+    debug!("Start garbage collection of artifacts");
+    start_garbage_collection(artifact_service.clone());
+
+    debug!("Start scrub of stored artifacts");
+    start_artifact_scrub(artifact_service.clone());
+
+    debug!("Start publication of public keys");
+    start_public_key_publication(artifact_service.clone());
+
+    debug!("Start advertisement of the quality metric");
+    start_metric_advertisement(p2p_client.clone(), peer_metrics.clone());
+
+    debug!("Start recording the metrics history");
+    start_metrics_history(
+        p2p_client.clone(),
+        peer_metrics.clone(),
+        artifact_service.metrics_history.clone(),
+    );
+
+    debug!("Start reloading the configuration on changes");
+    let config_reloader = ConfigReloader::new(env::args_os().collect(), artifact_service.clone())?;
+    let federation_token = config_reloader.federation_token();
+    reload::start_config_reload(config_reloader, args.config_file());
+
+    if !args.federation_peer.is_empty() {
+        debug!("Start metadata sync with federation peers");
+        start_federation_sync(artifact_service.clone(), &args, federation_token)?;
+    }
+
     debug!("Listen for p2p events");
     loop {
-        if let Some(event) = p2p_events.next().await {
+        let event = tokio::select! {
+            event = p2p_events.next() => event,
+            _ = &mut shutdown_signal => break,
+        };
+
+        if let Some(event) = event {
             match event {
                 // Reply with the content of the artifact on incoming requests.
                 pyrsia::network::event_loop::PyrsiaEvent::RequestArtifact {
                     artifact_id,
+                    peer,
                     channel,
                 } => {
                     if let Err(error) = handlers::handle_request_artifact(
                         artifact_service.clone(),
                         &artifact_id,
+                        &peer,
                         channel,
                     )
                     .await
@@ -106,6 +349,49 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         );
                     }
                 }
+                pyrsia::network::event_loop::PyrsiaEvent::RequestArtifactAvailability {
+                    artifact_id,
+                    channel,
+                } => {
+                    if let Err(error) = handlers::handle_request_artifact_availability(
+                        artifact_service.clone(),
+                        &artifact_id,
+                        channel,
+                    )
+                    .await
+                    {
+                        warn!(
+                            "This node failed to report availability of artifact with id {}. Error: {:?}",
+                            artifact_id, error
+                        );
+                    }
+                }
+                pyrsia::network::event_loop::PyrsiaEvent::RequestArtifactReplication {
+                    peer,
+                    package_type,
+                    package_specific_artifact_id,
+                    artifact,
+                    channel,
+                } => {
+                    debug!(
+                        "Peer {} pushed a replica of artifact {}",
+                        peer, package_specific_artifact_id
+                    );
+                    if let Err(error) = handlers::handle_request_artifact_replication(
+                        artifact_service.clone(),
+                        package_type,
+                        &package_specific_artifact_id,
+                        artifact,
+                        channel,
+                    )
+                    .await
+                    {
+                        warn!(
+                            "This node failed to store replica of artifact {}. Error: {:?}",
+                            package_specific_artifact_id, error
+                        );
+                    }
+                }
                 pyrsia::network::event_loop::PyrsiaEvent::RequestBuild {
                     package_type,
                     package_specific_id,
@@ -117,6 +403,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     );
                     if let Err(error) = handlers::handle_request_build(
                         p2p_client.clone(),
+                        artifact_service.clone(),
                         build_event_client.clone(),
                         package_type,
                         &package_specific_id,
@@ -133,7 +420,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 pyrsia::network::event_loop::PyrsiaEvent::IdleMetricRequest { channel } => {
                     if let Err(error) = handlers::handle_request_idle_metric(
                         p2p_client.clone(),
-                        &mut peer_metrics,
+                        &peer_metrics,
                         channel,
                     )
                     .await
@@ -168,6 +455,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         }
                     }
                 }
+                pyrsia::network::event_loop::PyrsiaEvent::PeerConnected { peer } => {
+                    artifact_service
+                        .events
+                        .publish(NodeEventKind::PeerConnected {
+                            peer_id: peer.to_string(),
+                        });
+                }
+                pyrsia::network::event_loop::PyrsiaEvent::PeerDisconnected { peer } => {
+                    artifact_service
+                        .events
+                        .publish(NodeEventKind::PeerDisconnected {
+                            peer_id: peer.to_string(),
+                        });
+                }
                 pyrsia::network::event_loop::PyrsiaEvent::RequestBuildStatus {
                     build_id,
                     channel,
@@ -193,6 +494,77 @@ async fn main() -> Result<(), Box<dyn Error>> {
             }
         }
     }
+
+    info!("Shutting down Pyrsia Node");
+    systemd::notify_stopping();
+    drain_http_server(
+        http_shutdown_sender,
+        http_server,
+        Duration::from_secs(args.shutdown_timeout),
+    )
+    .await;
+
+    debug!("Flush the artifact index");
+    if let Err(e) = artifact_service.artifact_storage.flush_index() {
+        warn!("Failed to flush the artifact index: {:?}", e);
+    }
+
+    debug!("Shut down the p2p event loop");
+    p2p_client.shutdown().await?;
+
+    info!("Pyrsia Node stopped");
+    Ok(())
+}
+
+// Completes when the node is asked to stop, with SIGINT (ctrl-c) or with the
+// SIGTERM that e.g. `docker stop` sends.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = signal::ctrl_c().await {
+            warn!("Unable to listen for SIGINT: {:?}", e);
+            future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("Unable to listen for SIGTERM: {:?}", e);
+                future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+// Stops accepting new HTTP connections and waits for the in-flight requests
+// to finish, aborting the ones that are still running after the timeout.
+async fn drain_http_server(
+    shutdown_sender: oneshot::Sender<()>,
+    mut http_server: JoinHandle<()>,
+    timeout: Duration,
+) {
+    let _ = shutdown_sender.send(());
+    match tokio::time::timeout(timeout, &mut http_server).await {
+        Ok(Ok(())) => debug!("All in-flight HTTP requests finished"),
+        Ok(Err(e)) => warn!("HTTP server did not shut down cleanly: {:?}", e),
+        Err(_) => {
+            warn!(
+                "In-flight HTTP requests did not finish within {} seconds, aborting them",
+                timeout.as_secs()
+            );
+            http_server.abort();
+        }
+    }
 }
 
 async fn establish_connection_with_p2p_network(
@@ -205,7 +577,10 @@ async fn establish_connection_with_p2p_network(
         if let Some(other_peer_id) = connect_to_p2p_network(p2p_client, &args)
             .await
             .unwrap_or_else(|err| {
-                warn!("Failed to establish connection with p2p network: {:?}", err);
+                warn!(
+                    "Failed to establish connection with p2p network, retrying in the background: {:?}",
+                    err
+                );
                 None
             })
         {
@@ -225,10 +600,9 @@ async fn establish_connection_with_p2p_network(
 }
 
 async fn connect_to_p2p_network(
-    mut p2p_client: Client,
+    p2p_client: Client,
     args: &PyrsiaNodeArgs,
 ) -> anyhow::Result<Option<PeerId>> {
-    p2p_client.listen(&args.listen_address).await?;
     let mut other_peer_id: Option<PeerId> = None;
     if let Some(to_probe) = &args.probe {
         info!("Invoking probe");
@@ -298,20 +672,18 @@ async fn load_peer_addrs(peer_url: &str) -> anyhow::Result<String> {
 async fn setup_pyrsia_services(
     p2p_client: Client,
     local_keypair: Keypair,
+    signer: Arc<dyn Signer>,
     args: &PyrsiaNodeArgs,
 ) -> Result<(BlockchainEventClient, BuildEventClient, ArtifactService)> {
-    let Keypair::Ed25519(local_ed25519_keypair) = local_keypair;
+    let Keypair::Ed25519(local_ed25519_keypair) = local_keypair.clone();
 
-    let artifact_path = PathBuf::from(ARTIFACTS_DIR.as_str());
+    let artifact_path = args.artifact_path();
 
-    let pyrsia_blockchain_path = read_var("PYRSIA_BLOCKCHAIN_PATH", "pyrsia/blockchain");
+    let pyrsia_blockchain_path = args.blockchain_path().to_string_lossy().into_owned();
 
     debug!("Create blockchain service");
     let blockchain_service = if args.init_blockchain {
-        let blockchain_keypair =
-            keypair_util::load_or_generate_ed25519(PathBuf::from(KEYPAIR_FILENAME.as_str()));
-
-        let Keypair::Ed25519(blockchain_ed25519_keypair) = blockchain_keypair;
+        let Keypair::Ed25519(blockchain_ed25519_keypair) = local_keypair.clone();
 
         // Refactor to overloading(trait) later
         BlockchainService::init_first_blockchain_node(
@@ -343,6 +715,8 @@ async fn setup_pyrsia_services(
         blockchain_event_client.clone(),
         build_event_client.clone(),
         p2p_client,
+        signer,
+        args,
     )?;
 
     debug!("Create build service");
@@ -380,17 +754,230 @@ fn setup_artifact_service(
     blockchain_event_client: BlockchainEventClient,
     build_event_client: BuildEventClient,
     p2p_client: Client,
+    signer: Arc<dyn Signer>,
+    args: &PyrsiaNodeArgs,
 ) -> Result<ArtifactService> {
-    let artifact_service = ArtifactService::new(
+    let artifact_storage = if args.storage_backend == "s3" {
+        ArtifactStorage::new_with_backend(artifact_path, Arc::new(setup_s3_backend(args)?))?
+    } else {
+        ArtifactStorage::new(artifact_path)?
+    };
+    let mut artifact_service = ArtifactService::new_with_storage(
         artifact_path,
+        artifact_storage,
         blockchain_event_client,
         build_event_client,
         p2p_client,
     )?;
+    artifact_service.artifact_storage.disk_allocated = Some(args.disk_allocated);
+    if args.gc_low_watermark >= args.gc_high_watermark {
+        anyhow::bail!(
+            "The garbage collection low watermark ({}%) must be lower than the high watermark ({}%)",
+            args.gc_low_watermark,
+            args.gc_high_watermark
+        );
+    }
+    artifact_service.artifact_storage.gc_watermarks = GcWatermarks {
+        high: args.gc_high_watermark,
+        low: args.gc_low_watermark,
+    };
+    artifact_service.artifact_storage.compress_artifacts = args.compress_artifacts;
+    artifact_service.replication_factor = args.replication_factor;
+    artifact_service.verify_on_read = !args.skip_read_verification;
+    artifact_service.trusted_attestation_keys = args.trusted_attestation_key.clone();
+    artifact_service.api_tokens.configure(&args.api_token);
+    artifact_service
+        .api_tokens
+        .allow_remote_admin(args.allow_remote_admin);
+    artifact_service.rate_limits = RateLimits::new(args.rate_limit, args.max_concurrent_downloads);
+    artifact_service.fair_share = FairShare::new(args.fair_share_factor);
+    artifact_service.role = match &args.role {
+        Some(role) => NodeRole::from_str(role)?,
+        None if args.init_blockchain => NodeRole::Authority,
+        None => NodeRole::Regular,
+    };
+    if let Some(policy_file) = &args.policy_file {
+        artifact_service.set_policy(Policy::from_file(policy_file).with_context(|| {
+            format!("Unable to read the policy file {}", policy_file.display())
+        })?);
+    }
+    if let Some(metrics_history_file) = &args.metrics_history_file {
+        artifact_service.metrics_history =
+            MetricsHistory::open(metrics_history_file, HISTORY_CAPACITY).with_context(|| {
+                format!(
+                    "Unable to read the metrics history {}",
+                    metrics_history_file.display()
+                )
+            })?;
+    }
+    let audit_log = AuditLog::new(
+        args.audit_log
+            .clone()
+            .unwrap_or_else(|| artifact_path.join("audit.log")),
+    );
+    artifact_service.audit_log = if args.sign_audit_log {
+        audit_log.with_signer(signer)
+    } else {
+        audit_log
+    };
+    artifact_service.audit_log.record(
+        AuditAction::ConfigChange,
+        "node",
+        Some(format!(
+            "started with role {}, storage backend {}, replication factor {}, policy file {:?}",
+            artifact_service.role, args.storage_backend, args.replication_factor, args.policy_file
+        )),
+    );
 
     Ok(artifact_service)
 }
 
+fn setup_s3_backend(args: &PyrsiaNodeArgs) -> Result<S3Backend> {
+    let endpoint = args
+        .s3_endpoint
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--s3-endpoint is required for the s3 storage backend"))?;
+    let bucket = args
+        .s3_bucket
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--s3-bucket is required for the s3 storage backend"))?;
+
+    S3Backend::new(
+        endpoint,
+        bucket,
+        &args.s3_region,
+        &read_var("PYRSIA_S3_ACCESS_KEY_ID", ""),
+        &read_var("PYRSIA_S3_SECRET_ACCESS_KEY", ""),
+    )
+}
+
+fn start_garbage_collection(mut artifact_service: ArtifactService) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GARBAGE_COLLECTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = artifact_service.collect_garbage().await {
+                warn!("Garbage collection of artifacts failed: {:?}", e);
+            }
+        }
+    });
+}
+
+fn start_public_key_publication(mut artifact_service: ArtifactService) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PUBLIC_KEY_PUBLICATION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = artifact_service.publish_public_keys().await {
+                warn!("Publication of public keys in the DHT failed: {:?}", e);
+            }
+        }
+    });
+}
+
+fn start_metric_advertisement(mut p2p_client: Client, peer_metrics: PeerMetrics) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(METRIC_ADVERTISEMENT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let quality_metric = peer_metrics.get_quality_metric();
+            // fails as long as no peer subscribed to the advertisements
+            if let Err(e) = p2p_client.advertise_quality_metric(quality_metric).await {
+                debug!("Advertisement of the quality metric failed: {:?}", e);
+            }
+        }
+    });
+}
+
+fn start_metrics_history(
+    mut p2p_client: Client,
+    peer_metrics: PeerMetrics,
+    metrics_history: MetricsHistory,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HISTORY_SAMPLE_INTERVAL);
+        let mut previous_transfer = TransferCounters::default();
+        loop {
+            interval.tick().await;
+            let status = match p2p_client.status().await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Unable to sample the metrics history: {:?}", e);
+                    continue;
+                }
+            };
+            let transfer = status.transfer_metrics.total;
+            let stress = peer_metrics.stress().unwrap_or_default();
+            let sample = MetricsSample {
+                timestamp: now_millis(),
+                cpu: stress.cpu,
+                network: stress.network,
+                disk: stress.disk,
+                quota: stress.quota,
+                memory: stress.memory,
+                quality_metric: peer_metrics.get_quality_metric(),
+                bytes_served: transfer
+                    .bytes_out
+                    .saturating_sub(previous_transfer.bytes_out),
+                bytes_fetched: transfer.bytes_in.saturating_sub(previous_transfer.bytes_in),
+                peers_count: status.peers_count,
+            };
+            previous_transfer = transfer;
+            if let Err(e) = metrics_history.push(sample) {
+                warn!("Unable to persist the metrics history: {}", e);
+            }
+        }
+    });
+}
+
+fn start_federation_sync(
+    artifact_service: ArtifactService,
+    args: &PyrsiaNodeArgs,
+    federation_token: watch::Receiver<Option<String>>,
+) -> Result<()> {
+    let mut peers = args
+        .federation_peer
+        .iter()
+        .map(|url| {
+            FederationPeer::new(
+                url,
+                args.federation_token.clone(),
+                args.federation_allow_http,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let period = Duration::from_secs(args.federation_interval.max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let token = federation_token.borrow().clone();
+            for peer in peers.iter_mut() {
+                peer.set_token(token.clone());
+                if let Err(e) = peer.sync(&artifact_service).await {
+                    warn!("Metadata sync with {} failed: {:?}", peer.url(), e);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn start_artifact_scrub(mut artifact_service: ArtifactService) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + ARTIFACT_SCRUB_INTERVAL,
+            ARTIFACT_SCRUB_INTERVAL,
+        );
+        loop {
+            interval.tick().await;
+            if let Err(e) = artifact_service.scrub_artifacts().await {
+                warn!("Scrub of stored artifacts failed: {:?}", e);
+            }
+        }
+    });
+}
+
 fn setup_build_service(
     artifact_path: &Path,
     build_event_client: BuildEventClient,
@@ -406,40 +993,133 @@ fn setup_build_service(
     Ok(build_service)
 }
 
-fn setup_http(args: &PyrsiaNodeArgs, artifact_service: ArtifactService, p2p_client: Client) {
+fn setup_http(
+    args: &PyrsiaNodeArgs,
+    addresses: &[SocketAddr],
+    artifact_service: ArtifactService,
+    p2p_client: Client,
+) -> Result<(oneshot::Sender<()>, JoinHandle<()>)> {
     // Get host and port from the settings. Defaults to DEFAULT_HOST and DEFAULT_PORT
     debug!(
         "Pyrsia Node will bind to host = {}, port = {}",
-        args.host, args.port
-    );
-
-    let address = SocketAddr::new(
-        IpAddr::V4(args.host.parse::<Ipv4Addr>().unwrap()),
-        args.port.parse::<u16>().unwrap(),
+        args.host.join(", "),
+        args.port
     );
 
     debug!("Setup HTTP routing");
+    let rate_limits = artifact_service.rate_limits.clone();
     let docker_routes = make_docker_routes(artifact_service.clone());
     let maven_routes = make_maven_routes(artifact_service.clone());
-    let node_api_routes = make_node_routes(artifact_service, p2p_client);
-    let all_routes = docker_routes.or(maven_routes).or(node_api_routes);
+    let response_signer = args.sign_responses.then(|| p2p_client.clone());
+    let node_api_routes = make_node_routes(artifact_service, p2p_client)
+        .and(warp::any().map(move || response_signer.clone()))
+        .and_then(sign_response);
+    // CORS only applies to the node API, browsers have no business with the
+    // Docker and Maven APIs.
+    let node_api_routes: BoxedFilter<(Box<dyn Reply>,)> =
+        match node_cors(&args.cors_allowed_origin, &args.cors_allowed_method)? {
+            Some(cors) => {
+                info!(
+                    "Allowing cross-origin requests to the node API from {}",
+                    args.cors_allowed_origin.join(", ")
+                );
+                node_api_routes
+                    .with(cors)
+                    .map(|reply| Box::new(reply) as Box<dyn Reply>)
+                    .boxed()
+            }
+            None => node_api_routes
+                .map(|reply| Box::new(reply) as Box<dyn Reply>)
+                .boxed(),
+        };
+    let all_routes =
+        rate_limit(rate_limits).and(docker_routes.or(maven_routes).or(node_api_routes));
 
     debug!("Setup HTTP server");
-    let (addr, server) = warp::serve(
-        all_routes
-            .and(http::log_headers())
-            .recover(custom_recover)
-            .with(warp::log("pyrsia_registry")),
-    )
-    .bind_ephemeral(address);
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let routes = all_routes
+        .and(http::log_headers())
+        .recover(custom_recover)
+        .with(warp::log("pyrsia_registry"))
+        .with(http::trace_requests());
+    // Every bound address shuts down on the same signal.
+    let shutdown_signal = shutdown_receiver.shared();
+    let tls_files = tls_files(args)?;
+    let mut servers: Vec<BoxFuture<'static, ()>> = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        let shutdown_signal = shutdown_signal.clone().map(|_| ());
+        let (scheme, addr, server): (&str, SocketAddr, BoxFuture<'static, ()>) = match &tls_files {
+            Some((cert_path, key_path)) => {
+                let (addr, server) = warp::serve(routes.clone())
+                    .tls()
+                    .cert_path(cert_path)
+                    .key_path(key_path)
+                    .bind_with_graceful_shutdown(*address, shutdown_signal);
+                ("https", addr, server.boxed())
+            }
+            None => {
+                let (addr, server) = warp::serve(routes.clone())
+                    .bind_with_graceful_shutdown(*address, shutdown_signal);
+                ("http", addr, server.boxed())
+            }
+        };
+        info!("Pyrsia Node will start running on {}://{}", scheme, addr);
+        servers.push(server);
+    }
+
+    let server = future::join_all(servers).map(|_| ());
+    Ok((shutdown_sender, tokio::spawn(server)))
+}
 
-    info!(
-        "Pyrsia Node will start running on {}:{}",
-        addr.ip(),
-        addr.port()
+// The certificate and private key files to serve HTTPS with, if any.
+fn tls_files(args: &PyrsiaNodeArgs) -> Result<Option<(PathBuf, PathBuf)>> {
+    match (&args.tls_cert, &args.tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            for path in [cert_path, key_path] {
+                if !path.is_file() {
+                    anyhow::bail!("TLS file {:?} does not exist", path);
+                }
+            }
+            Ok(Some((cert_path.clone(), key_path.clone())))
+        }
+        _ if args.tls_self_signed => generate_self_signed_certificate(args).map(Some),
+        _ => Ok(None),
+    }
+}
+
+// Generate a self-signed certificate for localhost and the host address in
+// the data directory, unless it was generated before.
+fn generate_self_signed_certificate(args: &PyrsiaNodeArgs) -> Result<(PathBuf, PathBuf)> {
+    let tls_dir = args.data_dir().join("tls");
+    let cert_path = tls_dir.join("self_signed_cert.pem");
+    let key_path = tls_dir.join("self_signed_key.pem");
+    if !cert_path.is_file() || !key_path.is_file() {
+        fs::create_dir_all(&tls_dir)?;
+        let mut subject_alt_names = vec![String::from("localhost")];
+        subject_alt_names.extend(args.host.iter().cloned());
+        let certificate = rcgen::generate_simple_self_signed(subject_alt_names)
+            .context("Failed to generate a self-signed TLS certificate")?;
+        fs::write(&cert_path, certificate.serialize_pem()?)?;
+        // The private key is only readable by the user running the node. A
+        // key that is left over is removed, so it is not written with its
+        // previous permissions.
+        if key_path.exists() {
+            fs::remove_file(&key_path)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&key_path)?
+            .write_all(certificate.serialize_private_key_pem().as_bytes())?;
+    }
+    warn!(
+        "Serving HTTPS with the self-signed certificate {:?}, which is only meant for testing",
+        cert_path
     );
 
-    tokio::spawn(server);
+    Ok((cert_path, key_path))
 }
 
 async fn pull_block_from_other_nodes(
@@ -20,12 +20,14 @@ use libp2p::request_response::ResponseChannel;
 use libp2p::{Multiaddr, PeerId};
 use log::debug;
 
-use pyrsia::artifact_service::model::PackageType;
+use pyrsia::artifact_service::model::{NodeRole, PackageType};
 use pyrsia::artifact_service::service::ArtifactService;
 use pyrsia::blockchain_service::event::BlockchainEventClient;
 use pyrsia::blockchain_service::service::BlockchainCommand;
 use pyrsia::build_service::event::BuildEventClient;
+use pyrsia::network::artifact_availability_protocol::ArtifactAvailabilityResponse;
 use pyrsia::network::artifact_protocol::ArtifactResponse;
+use pyrsia::network::artifact_replication_protocol::ArtifactReplicationResponse;
 use pyrsia::network::build_protocol::BuildResponse;
 use pyrsia::network::build_status_protocol::BuildStatusResponse;
 use pyrsia::network::client::Client;
@@ -34,22 +36,30 @@ use pyrsia::peer_metrics::metrics;
 use pyrsia_blockchain_network::structures::block::Block;
 use pyrsia_blockchain_network::structures::header::Ordinal;
 
-/// Reach out to another node with the specified address
+/// Reach out to another node with the specified address. The node will
+/// try to reconnect when the dial fails or the connection is lost later.
 pub async fn dial_other_peer(mut p2p_client: Client, to_dial: &Multiaddr) -> anyhow::Result<()> {
     match to_dial.iter().last() {
         Some(Protocol::P2p(hash)) => match PeerId::from_multihash(hash) {
-            Ok(peer_id) => p2p_client.dial(&peer_id, to_dial).await,
+            Ok(peer_id) => {
+                p2p_client.keep_connected(&peer_id, to_dial).await?;
+                p2p_client.dial(&peer_id, to_dial).await
+            }
             Err(_) => anyhow::bail!("Invalid hash provided for Peer ID."),
         },
         _ => anyhow::bail!("Expect peer address to contain Peer ID."),
     }
 }
 
-/// AutoNAT probe another node with the specified address
+/// AutoNAT probe another node with the specified address. The node will
+/// try to reconnect when the probe fails or the connection is lost later.
 pub async fn probe_other_peer(mut p2p_client: Client, to_probe: &Multiaddr) -> anyhow::Result<()> {
     match to_probe.iter().last() {
         Some(Protocol::P2p(hash)) => match PeerId::from_multihash(hash) {
-            Ok(peer_id) => p2p_client.add_probe_address(&peer_id, to_probe).await,
+            Ok(peer_id) => {
+                p2p_client.keep_connected(&peer_id, to_probe).await?;
+                p2p_client.add_probe_address(&peer_id, to_probe).await
+            }
             Err(_) => anyhow::bail!("Invalid hash provided for Peer ID."),
         },
         _ => anyhow::bail!("Expect peer address to contain Peer ID."),
@@ -57,26 +67,103 @@ pub async fn probe_other_peer(mut p2p_client: Client, to_probe: &Multiaddr) -> a
 }
 
 /// Respond to a RequestArtifact event by getting the artifact
-/// based on the provided artifact id.
+/// based on the provided artifact id. Only artifacts that match
+/// their transparency log are served. An overloaded node, or a
+/// node that served the peer more than its fair share, asks the
+/// peer to request the artifact elsewhere.
 pub async fn handle_request_artifact(
     mut artifact_service: ArtifactService,
     artifact_id: &str,
+    peer: &PeerId,
     channel: ResponseChannel<ArtifactResponse>,
 ) -> anyhow::Result<()> {
     debug!("Handling request artifact: {:?}", artifact_id);
 
-    let content = artifact_service.get_artifact_locally(artifact_id).await?;
+    if let Err(overloaded) = artifact_service.load_shedding.check() {
+        debug!(
+            "Refusing request for artifact {} from peer {}, quality metric {} is too high",
+            artifact_id, peer, overloaded.quality_metric
+        );
+        return artifact_service
+            .p2p_client
+            .respond_artifact_busy(channel)
+            .await;
+    }
+    if !artifact_service.fair_share.admit(peer) {
+        debug!(
+            "Refusing request for artifact {} from peer {}, it exceeds its fair share",
+            artifact_id, peer
+        );
+        return artifact_service
+            .p2p_client
+            .respond_artifact_busy(channel)
+            .await;
+    }
+
+    let content = artifact_service
+        .get_verified_artifact_locally(artifact_id)
+        .await?;
+    artifact_service
+        .fair_share
+        .record_served(peer, content.len() as u64);
+
+    artifact_service
+        .p2p_client
+        .respond_artifact(peer, content, channel)
+        .await
+}
+
+/// Respond to a RequestArtifactAvailability event with the size of the
+/// artifact when it is available in the local storage.
+pub async fn handle_request_artifact_availability(
+    mut artifact_service: ArtifactService,
+    artifact_id: &str,
+    channel: ResponseChannel<ArtifactAvailabilityResponse>,
+) -> anyhow::Result<()> {
+    debug!("Handling request artifact availability: {:?}", artifact_id);
+
+    let size = artifact_service
+        .artifact_storage
+        .artifact_size(artifact_id)
+        .ok();
 
     artifact_service
         .p2p_client
-        .respond_artifact(content, channel)
+        .respond_artifact_availability(size, channel)
+        .await
+}
+
+/// Respond to a RequestArtifactReplication event by storing the pushed
+/// artifact when it matches the local transparency log.
+pub async fn handle_request_artifact_replication(
+    mut artifact_service: ArtifactService,
+    package_type: PackageType,
+    package_specific_artifact_id: &str,
+    artifact: Vec<u8>,
+    channel: ResponseChannel<ArtifactReplicationResponse>,
+) -> anyhow::Result<()> {
+    debug!(
+        "Handling request artifact replication: {:?} : {}",
+        package_type, package_specific_artifact_id
+    );
+
+    let accepted = artifact_service
+        .store_replicated_artifact(package_type, package_specific_artifact_id, &artifact)
+        .await
+        .unwrap_or(false);
+
+    artifact_service
+        .p2p_client
+        .respond_artifact_replication(accepted, channel)
         .await
 }
 
 /// Respond to a RequestBuild event by getting the build
 /// based on the provided package_type and package_specific_id.
+/// Only nodes with the build role accept build requests.
 pub async fn handle_request_build(
     mut p2p_client: Client,
+    artifact_service: ArtifactService,
     build_event_client: BuildEventClient,
     package_type: PackageType,
     package_specific_id: &str,
@@ -87,6 +174,13 @@ pub async fn handle_request_build(
         package_type, package_specific_id
     );
 
+    if !artifact_service.has_role(NodeRole::Build) {
+        anyhow::bail!(
+            "This node is not authorized to build (configured role: {})",
+            artifact_service.role
+        );
+    }
+
     let build_id = build_event_client
         .start_build(package_type, package_specific_id.to_string())
         .await?;
@@ -97,7 +191,7 @@ pub async fn handle_request_build(
 //Respond to the IdleMetricRequest event
 pub async fn handle_request_idle_metric(
     mut p2p_client: Client,
-    peer_metrics: &mut metrics::PeerMetrics,
+    peer_metrics: &metrics::PeerMetrics,
     channel: ResponseChannel<IdleMetricResponse>,
 ) -> anyhow::Result<()> {
     let metric = peer_metrics.get_quality_metric();
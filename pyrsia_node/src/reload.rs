@@ -0,0 +1,262 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! Reloads the settings that can change while the node runs, when the node
+//! receives SIGHUP or when the configuration file given with --config
+//! changes, so that operators don't lose the connected peers over a tweak.
+//! Changes to all other settings are reported as requiring a restart.
+
+use crate::args::config_file::matches_with_config_file;
+use crate::args::parser::PyrsiaNodeArgs;
+use anyhow::Context;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use log::{info, warn};
+use pyrsia::artifact_service::audit_log::AuditAction;
+use pyrsia::artifact_service::policy::Policy;
+use pyrsia::artifact_service::service::ArtifactService;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, watch};
+
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The settings that are applied without a restart.
+pub const RELOADABLE_SETTINGS: &[&str] = &[
+    "rate_limit",
+    "max_concurrent_downloads",
+    "api_token",
+    "allow_remote_admin",
+    "policy_file",
+    "federation_token",
+];
+
+/// The settings that changed in a reload.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// The reloadable settings that changed since the previous reload.
+    pub applied: Vec<String>,
+    /// The settings that changed since the node started, but only take
+    /// effect after a restart.
+    pub requires_restart: Vec<String>,
+}
+
+impl ReloadReport {
+    fn new(started: &ArgMatches, current: &ArgMatches, reloaded: &ArgMatches) -> Self {
+        let (restart_only, reloadable): (Vec<String>, Vec<String>) = PyrsiaNodeArgs::command()
+            .get_arguments()
+            .map(|arg| arg.get_id().to_string())
+            .partition(|id| !RELOADABLE_SETTINGS.contains(&id.as_str()));
+        ReloadReport {
+            applied: reloadable
+                .into_iter()
+                .filter(|id| raw_values(current, id) != raw_values(reloaded, id))
+                .collect(),
+            requires_restart: restart_only
+                .into_iter()
+                .filter(|id| raw_values(started, id) != raw_values(reloaded, id))
+                .collect(),
+        }
+    }
+}
+
+fn raw_values<'a>(matches: &'a ArgMatches, id: &str) -> Vec<&'a OsStr> {
+    matches
+        .try_get_raw(id)
+        .ok()
+        .flatten()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Parses the command line arguments and the configuration file again and
+/// applies the reloadable settings to the running services.
+pub struct ConfigReloader {
+    args: Vec<OsString>,
+    started: ArgMatches,
+    current: ArgMatches,
+    artifact_service: ArtifactService,
+    federation_token: watch::Sender<Option<String>>,
+}
+
+impl ConfigReloader {
+    pub fn new(args: Vec<OsString>, artifact_service: ArtifactService) -> anyhow::Result<Self> {
+        let started = matches_with_config_file(&args)?;
+        let federation_token = PyrsiaNodeArgs::from_arg_matches(&started)?.federation_token;
+        Ok(ConfigReloader {
+            args,
+            current: started.clone(),
+            started,
+            artifact_service,
+            federation_token: watch::channel(federation_token).0,
+        })
+    }
+
+    /// The bearer token to send to the federation peers, as it is reloaded.
+    pub fn federation_token(&self) -> watch::Receiver<Option<String>> {
+        self.federation_token.subscribe()
+    }
+
+    /// Apply the reloadable settings. Nothing is applied when the settings
+    /// or the policy file are invalid.
+    pub fn reload(&mut self) -> anyhow::Result<ReloadReport> {
+        let reloaded = matches_with_config_file(&self.args)?;
+        let args = PyrsiaNodeArgs::from_arg_matches(&reloaded)?;
+        // The policy file is read again even when its path didn't change.
+        let policy = match &args.policy_file {
+            Some(policy_file) => Policy::from_file(policy_file).with_context(|| {
+                format!("Unable to read the policy file {}", policy_file.display())
+            })?,
+            None => Policy::default(),
+        };
+
+        self.artifact_service
+            .rate_limits
+            .reconfigure(args.rate_limit, args.max_concurrent_downloads);
+        self.artifact_service.api_tokens.configure(&args.api_token);
+        self.artifact_service
+            .api_tokens
+            .allow_remote_admin(args.allow_remote_admin);
+        self.artifact_service.set_policy(policy);
+        self.federation_token.send_replace(args.federation_token);
+
+        let report = ReloadReport::new(&self.started, &self.current, &reloaded);
+        self.current = reloaded;
+        if !report.applied.is_empty() {
+            self.artifact_service.audit_log.record(
+                AuditAction::ConfigChange,
+                "node",
+                Some(format!("reloaded {}", report.applied.join(", "))),
+            );
+        }
+        Ok(report)
+    }
+}
+
+/// Reload the settings whenever the node receives SIGHUP or the
+/// configuration file changes.
+pub fn start_config_reload(mut reloader: ConfigReloader, config_file: Option<PathBuf>) {
+    let (reload_sender, mut reload_receiver) = mpsc::channel(1);
+    #[cfg(unix)]
+    watch_hangup(reload_sender.clone());
+    if let Some(config_file) = config_file {
+        watch_config_file(config_file, reload_sender);
+    }
+
+    tokio::spawn(async move {
+        while let Some(reason) = reload_receiver.recv().await {
+            info!("Reloading the configuration: {}", reason);
+            match reloader.reload() {
+                Ok(report) => {
+                    if report.applied.is_empty() {
+                        info!("No reloadable settings changed");
+                    } else {
+                        info!("Applied the changed settings {}", report.applied.join(", "));
+                    }
+                    if !report.requires_restart.is_empty() {
+                        warn!(
+                            "The changed settings {} only take effect after a restart",
+                            report.requires_restart.join(", ")
+                        );
+                    }
+                }
+                Err(e) => warn!(
+                    "Unable to reload the configuration, keeping the current settings: {:#}",
+                    e
+                ),
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+fn watch_hangup(reload_sender: mpsc::Sender<&'static str>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            warn!("Unable to listen for SIGHUP: {:?}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            // A reload that is pending already picks up the changes.
+            let _ = reload_sender.try_send("received SIGHUP");
+        }
+    });
+}
+
+fn watch_config_file(config_file: PathBuf, reload_sender: mpsc::Sender<&'static str>) {
+    tokio::spawn(async move {
+        let mut modified = modified_time(&config_file);
+        let mut interval = tokio::time::interval(CONFIG_WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let new_modified = modified_time(&config_file);
+            if new_modified != modified {
+                modified = new_modified;
+                let _ = reload_sender.try_send("the configuration file changed");
+            }
+        }
+    });
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(args: &[&str]) -> ArgMatches {
+        PyrsiaNodeArgs::command()
+            .try_get_matches_from(std::iter::once("pyrsia_node").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reload_report() {
+        let started = matches(&["--rate-limit", "60"]);
+        let current = matches(&["--rate-limit", "120", "--port", "8888"]);
+        let reloaded = matches(&[
+            "--rate-limit",
+            "120",
+            "--api-token",
+            "token",
+            "--port",
+            "8888",
+        ]);
+
+        assert_eq!(
+            ReloadReport::new(&started, &current, &reloaded),
+            ReloadReport {
+                applied: vec![String::from("api_token")],
+                requires_restart: vec![String::from("port")],
+            }
+        );
+        assert_eq!(
+            ReloadReport::new(&started, &started, &started),
+            ReloadReport::default()
+        );
+    }
+}
@@ -0,0 +1,217 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! Runs the node as a Windows service: installs and uninstalls the service,
+//! answers the service control manager and logs to the Windows event log.
+
+use crate::args::parser::PyrsiaNodeArgs;
+use anyhow::{bail, Context};
+use futures::future::FutureExt;
+use log::error;
+use pyrsia::logging::buffer::LogEntry;
+use std::env;
+use std::ffi::OsString;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+/// The name of the service, which is also the source of its event log entries.
+pub const SERVICE_NAME: &str = "pyrsia_node";
+const SERVICE_DISPLAY_NAME: &str = "Pyrsia Node";
+const SERVICE_DESCRIPTION: &str =
+    "Connects to and participates in the Pyrsia network to distribute artifacts";
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+// The arguments of the node and the runtime to run it on, handed over to the
+// service main function that the service control manager calls.
+static SERVICE_CONTEXT: Mutex<Option<(PyrsiaNodeArgs, Handle)>> = Mutex::new(None);
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Install the node as a service that starts automatically, with the
+/// options that preceded `service install` on the command line. The service
+/// runs in the system directory, so the data directory is made absolute.
+pub fn install(args: &PyrsiaNodeArgs) -> anyhow::Result<()> {
+    let command_line: Vec<OsString> = env::args_os().skip(1).collect();
+    let mut launch_arguments: Vec<OsString> = match command_line
+        .windows(2)
+        .position(|window| window[0] == "service" && window[1] == "install")
+    {
+        Some(position) => command_line[..position].to_vec(),
+        None => bail!("Unable to find the service install subcommand"),
+    };
+    if args.data_dir.is_none() {
+        launch_arguments.push(OsString::from("--data-dir"));
+        launch_arguments.push(env::current_dir()?.join(args.data_dir()).into_os_string());
+    }
+    launch_arguments.extend([OsString::from("service"), OsString::from("run")]);
+
+    let service_manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+    let service_info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: env::current_exe()?,
+        launch_arguments,
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    let service = service_manager
+        .create_service(&service_info, ServiceAccess::CHANGE_CONFIG)
+        .context("Unable to create the service, which requires an administrator")?;
+    service.set_description(SERVICE_DESCRIPTION)?;
+    eventlog::register(SERVICE_NAME).context("Unable to register the event log source")?;
+    Ok(())
+}
+
+/// Stop the service if it runs and remove it.
+pub fn uninstall() -> anyhow::Result<()> {
+    let service_manager =
+        ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = service_manager
+        .open_service(
+            SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .context("Unable to open the service, is it installed?")?;
+
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+        let started_at = Instant::now();
+        while service.query_status()?.current_state != ServiceState::Stopped {
+            if started_at.elapsed() > STOP_TIMEOUT {
+                bail!("The service did not stop within {:?}", STOP_TIMEOUT);
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+    service.delete()?;
+    if let Err(e) = eventlog::deregister(SERVICE_NAME) {
+        error!("Unable to deregister the event log source: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Run the node on the specified runtime for the service control manager.
+/// Blocks until the service is stopped.
+pub fn run(args: PyrsiaNodeArgs, runtime: Handle) -> anyhow::Result<()> {
+    *SERVICE_CONTEXT.lock().unwrap() = Some((args, runtime));
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Unable to connect to the service control manager")
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    let context = SERVICE_CONTEXT.lock().unwrap().take();
+    if let Some((args, runtime)) = context {
+        if let Err(e) = run_service(args, runtime) {
+            error!("The service failed: {:?}", e);
+        }
+    }
+}
+
+fn run_service(args: PyrsiaNodeArgs, runtime: Handle) -> windows_service::Result<()> {
+    let (stop_sender, stop_receiver) = oneshot::channel();
+    let mut stop_sender = Some(stop_sender);
+    let status_handle =
+        service_control_handler::register(SERVICE_NAME, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                if let Some(stop_sender) = stop_sender.take() {
+                    let _ = stop_sender.send(());
+                }
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })?;
+
+    let status = |current_state, controls_accepted, exit_code| ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    };
+    status_handle.set_service_status(status(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        ServiceExitCode::Win32(0),
+    ))?;
+
+    let result = runtime.block_on(crate::run(args, stop_receiver.map(|_| ()).boxed()));
+    let exit_code = match result {
+        Ok(()) => ServiceExitCode::Win32(0),
+        Err(e) => {
+            error!("{}", e);
+            ServiceExitCode::ServiceSpecific(crate::startup::exit_code(e.as_ref()) as u32)
+        }
+    };
+    status_handle.set_service_status(status(
+        ServiceState::Stopped,
+        ServiceControlAccept::empty(),
+        exit_code,
+    ))
+}
+
+/// Writes the log to the Windows event log, under the source that is
+/// registered when the service is installed.
+pub struct EventLogLayer {
+    event_log: eventlog::EventLog,
+}
+
+impl EventLogLayer {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(EventLogLayer {
+            event_log: eventlog::EventLog::new(SERVICE_NAME, log::Level::Trace)
+                .map_err(|e| anyhow::anyhow!("Unable to open the event log: {:?}", e))?,
+        })
+    }
+}
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let entry = LogEntry::from(event);
+        let level = log::Level::from_str(&entry.level).unwrap_or(log::Level::Info);
+        log::Log::log(
+            &self.event_log,
+            &log::Record::builder()
+                .level(level)
+                .target(&entry.target)
+                .args(format_args!("{}", entry.message))
+                .build(),
+        );
+    }
+}
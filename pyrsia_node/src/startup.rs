@@ -0,0 +1,228 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, SocketAddr, TcpListener, ToSocketAddrs};
+use std::path::Path;
+
+/// The exit code of errors that aren't a startup error.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The classes of errors that prevent the node from starting, each with its
+/// own exit code. Exit code 2 is used by clap for invalid arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartupErrorKind {
+    /// The host or port of the HTTP APIs is not valid or can't be resolved.
+    InvalidAddress,
+    /// The port of the HTTP APIs is in use or may not be bound to.
+    PortUnavailable,
+    /// The data directory can't be created or written to.
+    DataDirectory,
+    /// The key pair of the node can't be loaded.
+    KeyPair,
+}
+
+impl StartupErrorKind {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            StartupErrorKind::InvalidAddress => 3,
+            StartupErrorKind::PortUnavailable => 4,
+            StartupErrorKind::DataDirectory => 5,
+            StartupErrorKind::KeyPair => 6,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StartupError {
+    pub kind: StartupErrorKind,
+    message: String,
+}
+
+impl StartupError {
+    pub fn new(kind: StartupErrorKind, message: impl Into<String>) -> Self {
+        StartupError {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for StartupError {}
+
+/// The exit code of the node when it stops with the specified error.
+pub fn exit_code(error: &(dyn Error + 'static)) -> i32 {
+    error
+        .downcast_ref::<StartupError>()
+        .map_or(EXIT_FAILURE, |e| e.kind.exit_code())
+}
+
+/// The socket addresses to serve the HTTP APIs on. Each host is either an IP
+/// address, optionally in brackets for IPv6, or a host name that is resolved
+/// to all of its addresses.
+pub fn http_addresses(hosts: &[String], port: &str) -> Result<Vec<SocketAddr>, StartupError> {
+    let port = port.parse::<u16>().map_err(|_| {
+        StartupError::new(
+            StartupErrorKind::InvalidAddress,
+            format!("Invalid port '{}': expected a number from 0 to 65535", port),
+        )
+    })?;
+    let mut addresses: Vec<SocketAddr> = Vec::new();
+    for host in hosts {
+        for address in resolve_host(host, port)? {
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+    }
+    if addresses.is_empty() {
+        return Err(StartupError::new(
+            StartupErrorKind::InvalidAddress,
+            "No host to bind the HTTP APIs to",
+        ));
+    }
+    Ok(addresses)
+}
+
+fn resolve_host(host: &str, port: u16) -> Result<Vec<SocketAddr>, StartupError> {
+    let host = host.trim();
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip_address) = unbracketed.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip_address, port)]);
+    }
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map(|addresses| addresses.collect::<Vec<_>>())
+        .map_err(|e| {
+            StartupError::new(
+                StartupErrorKind::InvalidAddress,
+                format!("Unable to resolve host '{}': {}", host, e),
+            )
+        })?;
+    if addresses.is_empty() {
+        return Err(StartupError::new(
+            StartupErrorKind::InvalidAddress,
+            format!("Host '{}' does not resolve to any address", host),
+        ));
+    }
+    Ok(addresses)
+}
+
+/// Check that the HTTP APIs can be served on the specified address, before
+/// any of the services of the node are started.
+pub fn check_port_available(address: SocketAddr) -> Result<(), StartupError> {
+    TcpListener::bind(address).map(|_| ()).map_err(|e| {
+        StartupError::new(
+            StartupErrorKind::PortUnavailable,
+            format!(
+                "Unable to bind to {}: {}. Is another Pyrsia Node running?",
+                address, e
+            ),
+        )
+    })
+}
+
+/// Check that files can be written to the data directory.
+pub fn check_data_dir_writable(data_dir: &Path) -> Result<(), StartupError> {
+    let probe_path = data_dir.join(".write_check");
+    fs::write(&probe_path, [])
+        .and_then(|_| fs::remove_file(&probe_path))
+        .map_err(|e| {
+            StartupError::new(
+                StartupErrorKind::DataDirectory,
+                format!(
+                    "The data directory {} is not writable: {}",
+                    data_dir.display(),
+                    e
+                ),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(hosts: &[&str]) -> Vec<String> {
+        hosts.iter().map(|host| host.to_string()).collect()
+    }
+
+    #[test]
+    fn test_http_addresses() {
+        assert_eq!(
+            http_addresses(&hosts(&["127.0.0.1"]), "7888").unwrap(),
+            vec!["127.0.0.1:7888".parse().unwrap()]
+        );
+        assert_eq!(
+            http_addresses(&hosts(&["::1", "[::]", "127.0.0.1", "::1"]), "7888").unwrap(),
+            vec![
+                "[::1]:7888".parse().unwrap(),
+                "[::]:7888".parse().unwrap(),
+                "127.0.0.1:7888".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            http_addresses(&hosts(&["127.0.0.1"]), "78888")
+                .unwrap_err()
+                .kind,
+            StartupErrorKind::InvalidAddress
+        );
+        assert_eq!(
+            http_addresses(&[], "7888").unwrap_err().kind,
+            StartupErrorKind::InvalidAddress
+        );
+    }
+
+    #[test]
+    fn test_http_addresses_resolves_host_names() {
+        let addresses = http_addresses(&hosts(&["localhost"]), "7888").unwrap();
+        assert!(!addresses.is_empty());
+        assert!(addresses
+            .iter()
+            .all(|address| address.ip().is_loopback() && address.port() == 7888));
+
+        assert_eq!(
+            http_addresses(&hosts(&["unknown-host.invalid"]), "7888")
+                .unwrap_err()
+                .kind,
+            StartupErrorKind::InvalidAddress
+        );
+    }
+
+    #[test]
+    fn test_check_port_available() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let error = check_port_available(address).unwrap_err();
+        assert_eq!(error.kind, StartupErrorKind::PortUnavailable);
+        assert_eq!(exit_code(&error), 4);
+
+        drop(listener);
+        assert!(check_port_available(address).is_ok());
+    }
+}
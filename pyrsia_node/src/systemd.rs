@@ -0,0 +1,93 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! Support for `Type=notify` systemd units: the node reports when it is ready
+//! and when it stops, and pings the watchdog while its p2p event loop
+//! responds, so that systemd restarts a node that hangs.
+
+use log::{debug, warn};
+use pyrsia::network::client::Client;
+use std::env;
+use std::process;
+use std::time::Duration;
+
+/// Tells systemd that the node has started up.
+pub fn notify_ready() {
+    notify("READY=1");
+}
+
+/// Tells systemd that the node is shutting down.
+pub fn notify_stopping() {
+    notify("STOPPING=1");
+}
+
+/// Pings the watchdog of systemd at half of the watchdog interval, as long as
+/// the p2p event loop responds within that time. Does nothing when the
+/// watchdog is not enabled for the node.
+pub fn start_watchdog(mut p2p_client: Client) {
+    let interval = match watchdog_interval() {
+        Some(watchdog_interval) => watchdog_interval / 2,
+        None => return,
+    };
+    debug!("Pinging the systemd watchdog every {:?}", interval);
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            match tokio::time::timeout(interval, p2p_client.status()).await {
+                Ok(Ok(_)) => notify("WATCHDOG=1"),
+                Ok(Err(e)) => warn!(
+                    "Skipping the watchdog ping, the p2p event loop failed: {:?}",
+                    e
+                ),
+                Err(_) => warn!("Skipping the watchdog ping, the p2p event loop did not respond"),
+            }
+        }
+    });
+}
+
+// The watchdog interval that systemd set for this process, if any.
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(watchdog_pid) = env::var("WATCHDOG_PID") {
+        if watchdog_pid.parse::<u32>().ok() != Some(process::id()) {
+            return None;
+        }
+    }
+    let watchdog_usec = env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    (watchdog_usec > 0).then(|| Duration::from_micros(watchdog_usec))
+}
+
+#[cfg(unix)]
+fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket_path = match env::var_os("NOTIFY_SOCKET") {
+        Some(socket_path) => socket_path,
+        None => return,
+    };
+    if socket_path.to_string_lossy().starts_with('@') {
+        warn!("Abstract systemd notify sockets are not supported");
+        return;
+    }
+    let result =
+        UnixDatagram::unbound().and_then(|socket| socket.send_to(state.as_bytes(), &socket_path));
+    if let Err(e) = result {
+        warn!("Failed to notify systemd of {}: {}", state, e);
+    }
+}
+
+#[cfg(not(unix))]
+fn notify(_state: &str) {}
@@ -0,0 +1,110 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::args::parser::PyrsiaNodeArgs;
+use opentelemetry::sdk::trace::{self, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use pyrsia::logging::buffer::LogBufferLayer;
+use pyrsia::logging::rotating_file::{RotatingFile, Rotation};
+use std::error::Error;
+use std::io;
+use std::sync::Mutex;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::{self, writer::BoxMakeWriter};
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Registry};
+
+// What is logged when RUST_LOG is not set, like pretty_env_logger did.
+const DEFAULT_LOG_FILTER: &str = "error";
+// The name of the service that traces are exported under.
+const OTLP_SERVICE_NAME: &str = "pyrsia_node";
+
+/// Installs the global tracing subscriber, which also receives everything
+/// that is logged with the `log` macros. It writes the log as text or JSON
+/// to standard error or to a rotating log file, and keeps it in the log
+/// buffer so it can be followed through the node API. A Windows service also
+/// logs to the Windows event log. With an OTLP endpoint, the spans are
+/// exported as traces too, independent of RUST_LOG.
+pub fn init_tracing(args: &PyrsiaNodeArgs) -> Result<(), Box<dyn Error>> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let writer = match &args.log_file {
+        Some(log_file) => BoxMakeWriter::new(Mutex::new(RotatingFile::open(
+            log_file,
+            args.log_rotation.parse::<Rotation>()?,
+            args.log_max_size,
+            args.log_max_files as usize,
+        )?)),
+        None => BoxMakeWriter::new(io::stderr),
+    };
+    let fmt_layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(args.log_file.is_none());
+    let fmt_layer: Box<dyn Layer<Registry> + Send + Sync> = match args.log_format.as_str() {
+        "json" => Box::new(fmt_layer.json()),
+        _ => Box::new(fmt_layer),
+    };
+    #[cfg(windows)]
+    let fmt_layer: Box<dyn Layer<Registry> + Send + Sync> = if args.is_windows_service() {
+        Box::new(fmt_layer.and_then(crate::service::EventLogLayer::new()?))
+    } else {
+        fmt_layer
+    };
+    let log_layer = fmt_layer.and_then(LogBufferLayer).with_filter(filter);
+
+    let otlp_layer = match &args.otlp_endpoint {
+        Some(otlp_endpoint) => Some(
+            tracing_opentelemetry::layer()
+                .with_tracer(otlp_tracer(otlp_endpoint)?)
+                .with_filter(LevelFilter::INFO),
+        ),
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(log_layer)
+        .with(otlp_layer)
+        .try_init()?;
+    Ok(())
+}
+
+/// Exports the traces that have not been exported yet.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+// Exports spans in batches to the OTLP collector at `endpoint` over gRPC.
+fn otlp_tracer(endpoint: &str) -> Result<Tracer, TraceError> {
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                OTLP_SERVICE_NAME,
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+}
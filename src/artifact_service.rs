@@ -14,6 +14,18 @@
    limitations under the License.
 */
 
+pub mod archive;
+pub mod attestation;
+pub mod audit_log;
+pub mod compression;
+pub mod events;
+pub mod federation;
+pub mod hashing;
+pub mod metadata_index;
 pub mod model;
+pub mod policy;
+pub mod sbom;
 pub mod service;
 pub mod storage;
+pub mod storage_backend;
+pub mod trust_store;
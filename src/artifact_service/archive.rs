@@ -0,0 +1,318 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::model::PackageType;
+use super::storage::ArtifactStorage;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+const METADATA_ENTRY: &str = "metadata.json";
+const ARTIFACT_ENTRY_PREFIX: &str = "artifacts/";
+
+/// The metadata of an artifact in an exported archive.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ArchivedArtifact {
+    pub artifact_id: String,
+    pub artifact_hash: Option<String>,
+    pub size: u64,
+    pub media_type: Option<String>,
+    pub package_type: Option<PackageType>,
+    pub package_specific_artifact_id: Option<String>,
+    pub pinned: bool,
+}
+
+/// Write all artifacts of the storage and their metadata to a tar archive.
+/// The hash of each artifact, as known by its transparency log, is looked
+/// up with `artifact_hash`. Returns the number of exported artifacts.
+pub fn export_artifacts<W: Write>(
+    artifact_storage: &ArtifactStorage,
+    artifact_hash: impl Fn(&str) -> Option<String>,
+    writer: W,
+) -> io::Result<usize> {
+    let archived_artifacts: Vec<ArchivedArtifact> = artifact_storage
+        .list_artifact_metadata()?
+        .into_iter()
+        .map(|metadata| ArchivedArtifact {
+            artifact_hash: artifact_hash(&metadata.artifact_id),
+            pinned: artifact_storage.is_pinned(&metadata.artifact_id),
+            artifact_id: metadata.artifact_id,
+            size: metadata.size,
+            media_type: metadata.media_type,
+            package_type: metadata.package_type,
+            package_specific_artifact_id: metadata.package_specific_artifact_id,
+        })
+        .collect();
+
+    let mut builder = tar::Builder::new(writer);
+    let metadata = serde_json::to_vec(&archived_artifacts)?;
+    append_entry(
+        &mut builder,
+        METADATA_ENTRY,
+        metadata.len() as u64,
+        &metadata[..],
+    )?;
+    for archived_artifact in archived_artifacts.iter() {
+        debug!("Exporting artifact {}", archived_artifact.artifact_id);
+        append_entry(
+            &mut builder,
+            &format!("{}{}", ARTIFACT_ENTRY_PREFIX, archived_artifact.artifact_id),
+            archived_artifact.size,
+            artifact_storage.read_artifact(&archived_artifact.artifact_id)?,
+        )?;
+    }
+    builder.into_inner()?.flush()?;
+
+    info!("Exported {} artifacts", archived_artifacts.len());
+    Ok(archived_artifacts.len())
+}
+
+/// The artifacts of an archive that were imported and the ones that were not.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportedArchive {
+    pub imported_artifacts: Vec<String>,
+    /// The artifacts that are not added by a transparency log of this node,
+    /// so their content can't be verified.
+    pub rejected_artifacts: Vec<String>,
+}
+
+/// Restore the artifacts of an archive written by [`export_artifacts`] into
+/// the storage. Only artifacts for which `artifact_hash` returns the hash of
+/// their transparency log are imported, and they are verified against it
+/// while they are stored. The hashes in the archive itself are not trusted.
+/// Artifacts that are already stored are kept.
+pub fn import_artifacts<R: Read>(
+    artifact_storage: &ArtifactStorage,
+    artifact_hash: impl Fn(&str) -> Option<String>,
+    reader: R,
+) -> io::Result<ImportedArchive> {
+    let mut archive = tar::Archive::new(reader);
+    let mut archived_artifacts: HashMap<String, ArchivedArtifact> = HashMap::new();
+    let mut imported_archive = ImportedArchive::default();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        if path == METADATA_ENTRY {
+            let metadata: Vec<ArchivedArtifact> = serde_json::from_reader(&mut entry)?;
+            archived_artifacts = metadata
+                .into_iter()
+                .map(|archived_artifact| (archived_artifact.artifact_id.clone(), archived_artifact))
+                .collect();
+            continue;
+        }
+
+        let archived_artifact = path
+            .strip_prefix(ARTIFACT_ENTRY_PREFIX)
+            .filter(|artifact_id| is_valid_artifact_id(artifact_id))
+            .and_then(|artifact_id| archived_artifacts.get(artifact_id))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unexpected entry {} in artifact archive", path),
+                )
+            })?;
+
+        let artifact_id = &archived_artifact.artifact_id;
+        let expected_hash = match artifact_hash(artifact_id) {
+            Some(expected_hash) => expected_hash,
+            None => {
+                warn!(
+                    "Not importing artifact {}, it has no transparency log on this node",
+                    artifact_id
+                );
+                imported_archive
+                    .rejected_artifacts
+                    .push(artifact_id.clone());
+                continue;
+            }
+        };
+        debug!("Importing artifact {}", artifact_id);
+        let _reservation = artifact_storage.reserve_space(artifact_id, entry.header().size()?)?;
+        artifact_storage.push_verified_artifact(&mut entry, artifact_id, &expected_hash)?;
+        if let (Some(package_type), Some(package_specific_artifact_id)) = (
+            archived_artifact.package_type,
+            &archived_artifact.package_specific_artifact_id,
+        ) {
+            artifact_storage.associate_artifact(
+                artifact_id,
+                package_type,
+                package_specific_artifact_id,
+                archived_artifact.media_type.as_deref(),
+            )?;
+        }
+        if archived_artifact.pinned {
+            artifact_storage.pin_artifact(artifact_id)?;
+        }
+        imported_archive
+            .imported_artifacts
+            .push(artifact_id.clone());
+    }
+
+    info!(
+        "Imported {} artifacts, rejected {} artifacts",
+        imported_archive.imported_artifacts.len(),
+        imported_archive.rejected_artifacts.len()
+    );
+    Ok(imported_archive)
+}
+
+fn append_entry<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    size: u64,
+    data: impl Read,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)
+}
+
+// Artifact ids become storage keys, so they must not contain path separators.
+fn is_valid_artifact_id(artifact_id: &str) -> bool {
+    !artifact_id.is_empty()
+        && !artifact_id.starts_with('.')
+        && !artifact_id.contains(|c: char| c == '/' || c == '\\')
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::artifact_service::hashing::HashAlgorithm;
+    use crate::util::test_util;
+
+    #[test]
+    fn test_export_and_import_artifacts() {
+        let tmp_dir = test_util::tests::setup();
+        let source_dir = tmp_dir.join("source");
+        let target_dir = tmp_dir.join("target");
+        std::fs::create_dir_all(&source_dir).unwrap();
+        std::fs::create_dir_all(&target_dir).unwrap();
+
+        let source_storage = ArtifactStorage::new(&source_dir).unwrap();
+        source_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "artifact_a")
+            .unwrap();
+        source_storage
+            .associate_artifact("artifact_a", PackageType::Docker, "alpine:3.16", None)
+            .unwrap();
+        source_storage
+            .push_artifact(&mut &b"OTHER_DATA"[..], "artifact_b")
+            .unwrap();
+        source_storage.pin_artifact("artifact_b").unwrap();
+
+        let mut archive = vec![];
+        let exported = export_artifacts(
+            &source_storage,
+            |artifact_id| {
+                (artifact_id == "artifact_a").then(|| HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"))
+            },
+            &mut archive,
+        )
+        .unwrap();
+        assert_eq!(exported, 2);
+
+        let target_storage = ArtifactStorage::new(&target_dir).unwrap();
+        let imported = import_artifacts(
+            &target_storage,
+            |artifact_id| match artifact_id {
+                "artifact_a" => Some(HashAlgorithm::Sha256.hash(b"SAMPLE_DATA")),
+                _ => Some(HashAlgorithm::Sha256.hash(b"OTHER_DATA")),
+            },
+            &archive[..],
+        )
+        .unwrap();
+        assert_eq!(
+            imported.imported_artifacts,
+            vec!["artifact_a", "artifact_b"]
+        );
+        assert!(imported.rejected_artifacts.is_empty());
+
+        let metadata = target_storage
+            .artifact_metadata("artifact_a")
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.size, 11);
+        assert_eq!(metadata.package_type, Some(PackageType::Docker));
+        assert!(target_storage.is_pinned("artifact_b"));
+        let mut content = String::new();
+        target_storage
+            .pull_artifact("artifact_b")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "OTHER_DATA");
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_import_artifacts_with_invalid_hash() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage = ArtifactStorage::new(&tmp_dir).unwrap();
+        artifact_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "artifact_id")
+            .unwrap();
+        let mut archive = vec![];
+        export_artifacts(&artifact_storage, |_| None, &mut archive).unwrap();
+
+        let other_dir = tmp_dir.join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        let other_storage = ArtifactStorage::new(&other_dir).unwrap();
+        let result = import_artifacts(
+            &other_storage,
+            |_| Some(HashAlgorithm::Sha256.hash(b"OTHER_DATA")),
+            &archive[..],
+        );
+        assert!(result.is_err());
+        assert!(other_storage.list_artifacts().unwrap().is_empty());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_import_artifacts_without_transparency_log() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage = ArtifactStorage::new(&tmp_dir).unwrap();
+        artifact_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "artifact_id")
+            .unwrap();
+        let mut archive = vec![];
+        // the hash in the archive is not trusted by the importing node
+        export_artifacts(
+            &artifact_storage,
+            |_| Some(HashAlgorithm::Sha256.hash(b"SAMPLE_DATA")),
+            &mut archive,
+        )
+        .unwrap();
+
+        let other_dir = tmp_dir.join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        let other_storage = ArtifactStorage::new(&other_dir).unwrap();
+        let imported = import_artifacts(&other_storage, |_| None, &archive[..]).unwrap();
+        assert!(imported.imported_artifacts.is_empty());
+        assert_eq!(imported.rejected_artifacts, vec!["artifact_id"]);
+        assert!(other_storage.list_artifacts().unwrap().is_empty());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
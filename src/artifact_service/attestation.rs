@@ -0,0 +1,369 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::hashing::ArtifactDigest;
+use crate::network::signed_document::public_key_of;
+use crate::network::signer::Signer;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The payload type of DSSE envelopes that carry an in-toto statement.
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+#[derive(Debug, Error)]
+pub enum AttestationError {
+    #[error("Invalid attestation envelope: {0}")]
+    InvalidEnvelope(String),
+    #[error("Unsupported attestation payload type: {0}")]
+    UnsupportedPayloadType(String),
+    #[error("Attestation is not a valid in-toto statement: {0}")]
+    InvalidStatement(#[from] serde_json::Error),
+    #[error("Attestation has no subject with a supported digest")]
+    NoSubject,
+    #[error("Attestation is not signed by a trusted key")]
+    Untrusted,
+}
+
+/// A DSSE envelope, the signature wrapper of in-toto attestations. The key
+/// id of a signature is the peer id of the key that created it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub payload_type: String,
+    /// The base64 encoded payload.
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct EnvelopeSignature {
+    pub keyid: String,
+    /// The base64 encoded signature.
+    pub sig: String,
+}
+
+impl Envelope {
+    /// Creates an envelope with the specified payload that is signed with
+    /// the specified signer, e.g. a keypair.
+    pub fn sign<S: Signer + ?Sized>(
+        signer: &S,
+        payload_type: &str,
+        payload: &[u8],
+    ) -> anyhow::Result<Self> {
+        let sig = signer.sign_message(&pae(payload_type, payload))?;
+        Ok(Envelope {
+            payload_type: payload_type.to_owned(),
+            payload: BASE64.encode(payload),
+            signatures: vec![EnvelopeSignature {
+                keyid: signer.public_key().to_peer_id().to_string(),
+                sig: BASE64.encode(sig),
+            }],
+        })
+    }
+
+    /// Adds the signature of another party, like an auditor, to the envelope.
+    /// The signature replaces an earlier one of the same key.
+    pub fn cosign<S: Signer + ?Sized>(&mut self, signer: &S) -> anyhow::Result<()> {
+        let sig = signer.sign_message(&pae(&self.payload_type, &self.payload()?))?;
+        let keyid = signer.public_key().to_peer_id().to_string();
+        self.signatures.retain(|signature| signature.keyid != keyid);
+        self.signatures.push(EnvelopeSignature {
+            keyid,
+            sig: BASE64.encode(sig),
+        });
+        Ok(())
+    }
+
+    /// Validates every signature of the envelope and returns the peers that
+    /// signed it, in the order of their signatures.
+    pub fn signers(&self) -> Result<Vec<PeerId>, AttestationError> {
+        let message = pae(&self.payload_type, &self.payload()?);
+        self.signatures
+            .iter()
+            .map(|signature| {
+                let invalid = || {
+                    AttestationError::InvalidEnvelope(format!(
+                        "invalid signature of key {}",
+                        signature.keyid
+                    ))
+                };
+                let peer_id = PeerId::from_str(&signature.keyid).map_err(|_| invalid())?;
+                match (public_key_of(&peer_id), BASE64.decode(&signature.sig)) {
+                    (Some(public_key), Ok(sig)) if public_key.verify(&message, &sig) => Ok(peer_id),
+                    _ => Err(invalid()),
+                }
+            })
+            .collect()
+    }
+
+    /// The decoded payload of the envelope.
+    pub fn payload(&self) -> Result<Vec<u8>, AttestationError> {
+        BASE64
+            .decode(&self.payload)
+            .map_err(|e| AttestationError::InvalidEnvelope(e.to_string()))
+    }
+
+    /// The trusted peers that have a valid signature on the envelope.
+    pub fn verified_signers(
+        &self,
+        trusted_keys: &[PeerId],
+    ) -> Result<Vec<PeerId>, AttestationError> {
+        let message = pae(&self.payload_type, &self.payload()?);
+        Ok(trusted_keys
+            .iter()
+            .filter(|peer_id| {
+                self.signatures.iter().any(|signature| {
+                    signature.keyid == peer_id.to_string()
+                        && match (public_key_of(peer_id), BASE64.decode(&signature.sig)) {
+                            (Some(public_key), Ok(sig)) => public_key.verify(&message, &sig),
+                            _ => false,
+                        }
+                })
+            })
+            .copied()
+            .collect())
+    }
+}
+
+/// The pre-authentication encoding of DSSE, which is what is actually signed.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+#[derive(Deserialize)]
+struct Statement {
+    #[serde(rename = "predicateType")]
+    predicate_type: String,
+    subject: Vec<Subject>,
+    #[serde(default)]
+    predicate: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Subject {
+    digest: BTreeMap<String, String>,
+}
+
+/// A verified in-toto attestation, like SLSA provenance, about an artifact.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Attestation {
+    /// The digest of the artifact the attestation is about, e.g. `sha256:...`.
+    pub artifact_digest: String,
+    pub predicate_type: String,
+    /// The id of the builder that produced the artifact, for provenance.
+    pub builder_id: Option<String>,
+    /// The digests of the materials the artifact was built from, for provenance.
+    pub materials: Vec<String>,
+    /// The trusted peers that signed the attestation.
+    pub signers: Vec<String>,
+    pub envelope: Envelope,
+}
+
+impl Attestation {
+    /// Verifies an envelope with an in-toto statement against the trusted
+    /// keys and returns an attestation for each subject of the statement.
+    pub fn verify(
+        envelope: Envelope,
+        trusted_keys: &[PeerId],
+    ) -> Result<Vec<Self>, AttestationError> {
+        if envelope.payload_type != IN_TOTO_PAYLOAD_TYPE {
+            return Err(AttestationError::UnsupportedPayloadType(
+                envelope.payload_type,
+            ));
+        }
+        let signers = envelope.verified_signers(trusted_keys)?;
+        if signers.is_empty() {
+            return Err(AttestationError::Untrusted);
+        }
+        let statement: Statement = serde_json::from_slice(&envelope.payload()?)?;
+
+        let subjects: Vec<String> = statement
+            .subject
+            .iter()
+            .filter_map(|subject| normalized_digest(&subject.digest))
+            .collect();
+        if subjects.is_empty() {
+            return Err(AttestationError::NoSubject);
+        }
+        let builder_id = builder_id(&statement.predicate);
+        let materials = materials(&statement.predicate);
+        let signers: Vec<String> = signers.iter().map(|signer| signer.to_string()).collect();
+
+        Ok(subjects
+            .into_iter()
+            .map(|artifact_digest| Attestation {
+                artifact_digest,
+                predicate_type: statement.predicate_type.clone(),
+                builder_id: builder_id.clone(),
+                materials: materials.clone(),
+                signers: signers.clone(),
+                envelope: envelope.clone(),
+            })
+            .collect())
+    }
+}
+
+/// The digest of an in-toto digest set, preferring sha256.
+fn normalized_digest(digest_set: &BTreeMap<String, String>) -> Option<String> {
+    digest_set
+        .get("sha256")
+        .map(|hex| ("sha256", hex))
+        .into_iter()
+        .chain(
+            digest_set
+                .iter()
+                .map(|(algorithm, hex)| (algorithm.as_str(), hex)),
+        )
+        .find_map(|(algorithm, hex)| {
+            ArtifactDigest::from_str(&format!("{}:{}", algorithm, hex))
+                .ok()
+                .map(|digest| digest.to_string())
+        })
+}
+
+/// The builder id of SLSA provenance v0.2 or v1.
+fn builder_id(predicate: &serde_json::Value) -> Option<String> {
+    predicate
+        .pointer("/builder/id")
+        .or_else(|| predicate.pointer("/runDetails/builder/id"))
+        .and_then(|id| id.as_str())
+        .map(String::from)
+}
+
+/// The digests of the materials of SLSA provenance v0.2 or the resolved
+/// dependencies of SLSA provenance v1.
+fn materials(predicate: &serde_json::Value) -> Vec<String> {
+    predicate
+        .pointer("/materials")
+        .or_else(|| predicate.pointer("/buildDefinition/resolvedDependencies"))
+        .and_then(|materials| materials.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|material| {
+            serde_json::from_value::<BTreeMap<String, String>>(material.get("digest")?.clone()).ok()
+        })
+        .filter_map(|digest_set| normalized_digest(&digest_set))
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+    use serde_json::json;
+
+    const ARTIFACT_HEX: &str = "865c8d988be4669f3e48f73b98f9bc2507be0246ea35e0098cf6054d3644c14f";
+    const SOURCE_HEX: &str = "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a";
+
+    fn provenance() -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "subject": [{ "name": "alpine", "digest": { "sha256": ARTIFACT_HEX } }],
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "predicate": {
+                "builder": { "id": "https://pyrsia.io/build" },
+                "materials": [{ "uri": "git+https://github.com/alpinelinux/docker-alpine", "digest": { "sha256": SOURCE_HEX } }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_provenance_signed_by_trusted_key() {
+        let keypair = Keypair::generate_ed25519();
+        let envelope = Envelope::sign(&keypair, IN_TOTO_PAYLOAD_TYPE, &provenance()).unwrap();
+
+        let attestations = Attestation::verify(envelope, &[keypair.public().to_peer_id()]).unwrap();
+
+        assert_eq!(attestations.len(), 1);
+        assert_eq!(
+            attestations[0].artifact_digest,
+            format!("sha256:{}", ARTIFACT_HEX)
+        );
+        assert_eq!(
+            attestations[0].builder_id.as_deref(),
+            Some("https://pyrsia.io/build")
+        );
+        assert_eq!(
+            attestations[0].materials,
+            vec![format!("sha256:{}", SOURCE_HEX)]
+        );
+        assert_eq!(
+            attestations[0].signers,
+            vec![keypair.public().to_peer_id().to_string()]
+        );
+    }
+
+    #[test]
+    fn test_cosigned_envelope() {
+        let builder_keypair = Keypair::generate_ed25519();
+        let auditor_keypair = Keypair::generate_ed25519();
+        let mut envelope =
+            Envelope::sign(&builder_keypair, IN_TOTO_PAYLOAD_TYPE, &provenance()).unwrap();
+        envelope.cosign(&auditor_keypair).unwrap();
+        envelope.cosign(&auditor_keypair).unwrap();
+
+        let builder = builder_keypair.public().to_peer_id();
+        let auditor = auditor_keypair.public().to_peer_id();
+        assert_eq!(envelope.signatures.len(), 2);
+        assert_eq!(envelope.signers().unwrap(), vec![builder, auditor]);
+
+        let attestations = Attestation::verify(envelope.clone(), &[builder, auditor]).unwrap();
+        assert_eq!(
+            attestations[0].signers,
+            vec![builder.to_string(), auditor.to_string()]
+        );
+
+        envelope.signatures[1].sig = envelope.signatures[0].sig.clone();
+        assert!(matches!(
+            envelope.signers(),
+            Err(AttestationError::InvalidEnvelope(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_untrusted_or_tampered_envelope() {
+        let keypair = Keypair::generate_ed25519();
+        let other_keypair = Keypair::generate_ed25519();
+        let envelope = Envelope::sign(&keypair, IN_TOTO_PAYLOAD_TYPE, &provenance()).unwrap();
+
+        assert!(matches!(
+            Attestation::verify(envelope.clone(), &[other_keypair.public().to_peer_id()]),
+            Err(AttestationError::Untrusted)
+        ));
+
+        let mut tampered = envelope;
+        tampered.payload = BASE64.encode(b"{}");
+        assert!(matches!(
+            Attestation::verify(tampered, &[keypair.public().to_peer_id()]),
+            Err(AttestationError::Untrusted)
+        ));
+    }
+}
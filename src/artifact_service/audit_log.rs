@@ -0,0 +1,258 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::network::signed_document::public_key_of;
+use crate::network::signer::Signer;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use libp2p::PeerId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The kinds of operations that are recorded in the audit log.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Serialize,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditAction {
+    /// An artifact was pulled by a local package manager.
+    Pull,
+    /// An artifact was served to another peer.
+    Serve,
+    /// An artifact was added to the local storage.
+    Push,
+    /// An artifact was evicted from the local storage.
+    Evict,
+    /// An artifact was moved to quarantine.
+    Quarantine,
+    /// The configuration of the node was changed.
+    ConfigChange,
+    /// An administrative action, like authorizing a node, was performed.
+    Admin,
+}
+
+/// A single entry of the audit log. When the log is signed, the entry holds
+/// the id of the signing node and the signature over its other fields.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The time of the operation, in seconds since the epoch.
+    pub timestamp: u64,
+    pub action: AuditAction,
+    /// What the operation was performed on, like an artifact id.
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl AuditEntry {
+    /// Verifies the signature of a signed entry. Unsigned entries never verify.
+    pub fn verify(&self) -> bool {
+        let public_key = match self
+            .signer
+            .as_deref()
+            .and_then(|signer| PeerId::from_str(signer).ok())
+            .and_then(|peer_id| public_key_of(&peer_id))
+        {
+            Some(public_key) => public_key,
+            None => return false,
+        };
+        match self
+            .signature
+            .as_deref()
+            .map(|signature| BASE64.decode(signature))
+        {
+            Some(Ok(signature)) => public_key.verify(&self.signed_message(), &signature),
+            _ => false,
+        }
+    }
+
+    fn signed_message(&self) -> Vec<u8> {
+        serde_json::to_vec(&(
+            self.timestamp,
+            self.action,
+            &self.subject,
+            &self.details,
+            &self.signer,
+        ))
+        .unwrap_or_default()
+    }
+}
+
+/// An append-only log of the artifact and administrative operations of the
+/// node, written as one JSON object per line.
+#[derive(Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    signer: Option<Arc<dyn Signer>>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl AuditLog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        AuditLog {
+            path: path.as_ref().to_path_buf(),
+            signer: None,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Sign every new entry with the specified signer, e.g. the keypair of
+    /// the node.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record an operation. A failure to write the audit log is logged, but
+    /// never fails the operation itself.
+    pub fn record(&self, action: AuditAction, subject: &str, details: Option<String>) {
+        if let Err(e) = self.append(action, subject, details) {
+            warn!(
+                "Unable to write {} of {} to the audit log: {}",
+                action, subject, e
+            );
+        }
+    }
+
+    fn append(
+        &self,
+        action: AuditAction,
+        subject: &str,
+        details: Option<String>,
+    ) -> anyhow::Result<AuditEntry> {
+        let mut entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            action,
+            subject: subject.to_owned(),
+            details,
+            signer: None,
+            signature: None,
+        };
+        if let Some(signer) = &self.signer {
+            entry.signer = Some(signer.public_key().to_peer_id().to_string());
+            entry.signature = Some(BASE64.encode(signer.sign_message(&entry.signed_message())?));
+        }
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(entry)
+    }
+
+    /// The most recent entries of the audit log, oldest first. Lines that
+    /// cannot be parsed are skipped.
+    pub fn recent(&self, limit: usize) -> io::Result<Vec<AuditEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = vec![];
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) {
+                entries.push(entry);
+            }
+        }
+        let skip = entries.len().saturating_sub(limit);
+        Ok(entries.split_off(skip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_recent_returns_latest_entries() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let audit_log = AuditLog::new(tmp_dir.path().join("audit.log"));
+        assert!(audit_log.recent(10).unwrap().is_empty());
+
+        audit_log.record(AuditAction::Push, "artifact-1", None);
+        audit_log.record(AuditAction::Pull, "artifact-1", None);
+        audit_log.record(
+            AuditAction::Evict,
+            "artifact-1",
+            Some(String::from("garbage collection")),
+        );
+
+        let entries = audit_log.recent(2).unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.action).collect::<Vec<_>>(),
+            vec![AuditAction::Pull, AuditAction::Evict]
+        );
+        assert_eq!(entries[1].details.as_deref(), Some("garbage collection"));
+        assert!(!entries[1].verify());
+    }
+
+    #[test]
+    fn test_signed_entries_verify() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let keypair = Keypair::generate_ed25519();
+        let audit_log =
+            AuditLog::new(tmp_dir.path().join("audit.log")).with_signer(Arc::new(keypair.clone()));
+
+        audit_log.record(AuditAction::Admin, "add_authorized_node", None);
+
+        let mut entry = audit_log.recent(1).unwrap().remove(0);
+        assert_eq!(
+            entry.signer,
+            Some(keypair.public().to_peer_id().to_string())
+        );
+        assert!(entry.verify());
+
+        entry.subject = String::from("tampered");
+        assert!(!entry.verify());
+    }
+}
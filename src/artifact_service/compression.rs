@@ -0,0 +1,142 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::io::{self, BufRead, Read};
+
+/// The extension of the storage keys of compressed artifacts, which marks
+/// them as compressed even when the metadata index has to be rebuilt.
+pub const COMPRESSED_KEY_EXTENSION: &str = "zst";
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+// The magic bytes of formats that are compressed already, like docker layers
+// (gzip), jars (zip) and other archives, and gain nothing from compression.
+const COMPRESSED_FORMAT_MAGIC_BYTES: [&[u8]; 5] = [
+    b"\x1f\x8b",
+    b"PK\x03\x04",
+    b"\x28\xb5\x2f\xfd",
+    b"\xfd7zXZ\x00",
+    b"BZh",
+];
+
+/// The storage key of a compressed artifact.
+pub fn compressed_key(artifact_id: &str) -> String {
+    format!("{}.{}", artifact_id, COMPRESSED_KEY_EXTENSION)
+}
+
+/// The id of the artifact that is stored compressed under `key`.
+pub fn artifact_id_of_compressed_key(key: &str) -> Option<&str> {
+    key.strip_suffix(COMPRESSED_KEY_EXTENSION)
+        .and_then(|artifact_id| artifact_id.strip_suffix('.'))
+}
+
+/// Decide whether an artifact is worth compressing by the first bytes of its
+/// content. The media type of an artifact is not known yet when it is stored,
+/// so compressed media types are recognized by the magic bytes of their format.
+/// Empty artifacts are never compressed.
+pub fn should_compress(head: &[u8]) -> bool {
+    !head.is_empty()
+        && !COMPRESSED_FORMAT_MAGIC_BYTES
+            .iter()
+            .any(|magic_bytes| head.starts_with(magic_bytes))
+}
+
+/// A reader that compresses the bytes read from `reader`.
+pub fn compressing_reader<R: BufRead>(reader: R) -> io::Result<impl Read> {
+    zstd::stream::read::Encoder::with_buffer(reader, COMPRESSION_LEVEL)
+}
+
+/// A reader that decompresses the bytes read from `reader`.
+pub fn decompressing_reader<R: Read + Send>(reader: R) -> io::Result<impl Read + Send> {
+    zstd::stream::read::Decoder::new(reader)
+}
+
+/// The size of the content of a compressed artifact.
+pub fn uncompressed_size<R: Read + Send>(reader: R) -> io::Result<u64> {
+    io::copy(&mut decompressing_reader(reader)?, &mut io::sink())
+}
+
+/// A reader that counts the bytes that are read through it.
+pub struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub fn new(inner: R) -> Self {
+        CountingReader { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn test_should_compress() {
+        assert!(should_compress(b"{\"schemaVersion\": 2}"));
+        assert!(!should_compress(b"\x1f\x8b\x08\x00"));
+        assert!(!should_compress(b"PK\x03\x04\x14\x00"));
+        assert!(!should_compress(b""));
+    }
+
+    #[test]
+    fn test_compress_and_decompress() {
+        let content = b"SAMPLE_DATA SAMPLE_DATA SAMPLE_DATA SAMPLE_DATA".repeat(10);
+
+        let mut compressed = vec![];
+        compressing_reader(BufReader::new(&content[..]))
+            .unwrap()
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert!(compressed.len() < content.len());
+        assert_eq!(
+            uncompressed_size(&compressed[..]).unwrap(),
+            content.len() as u64
+        );
+
+        let mut decompressed = vec![];
+        decompressing_reader(&compressed[..])
+            .unwrap()
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, content);
+    }
+
+    #[test]
+    fn test_compressed_key() {
+        assert_eq!(compressed_key("artifact_id"), "artifact_id.zst");
+        assert_eq!(
+            artifact_id_of_compressed_key("artifact_id.zst"),
+            Some("artifact_id")
+        );
+        assert_eq!(artifact_id_of_compressed_key("artifact_id"), None);
+    }
+}
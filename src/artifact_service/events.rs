@@ -0,0 +1,122 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// The number of events that are kept for subscribers that fall behind.
+const NODE_EVENTS_CAPACITY: usize = 1024;
+
+/// The activity of a node that can be followed in real time.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NodeEventKind {
+    /// An artifact was added to the local storage.
+    ArtifactStored { artifact_id: String },
+    /// An artifact was served to another peer.
+    ArtifactServed { artifact_id: String },
+    /// An artifact was evicted from the local storage.
+    ArtifactEvicted { artifact_id: String },
+    /// A stored artifact was verified against its transparency log.
+    ArtifactVerified { artifact_id: String, valid: bool },
+    /// A connection to a peer was established.
+    PeerConnected { peer_id: String },
+    /// The last connection to a peer was closed.
+    PeerDisconnected { peer_id: String },
+}
+
+impl NodeEventKind {
+    /// The name of the event, as used for server-sent events.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeEventKind::ArtifactStored { .. } => "artifact_stored",
+            NodeEventKind::ArtifactServed { .. } => "artifact_served",
+            NodeEventKind::ArtifactEvicted { .. } => "artifact_evicted",
+            NodeEventKind::ArtifactVerified { .. } => "artifact_verified",
+            NodeEventKind::PeerConnected { .. } => "peer_connected",
+            NodeEventKind::PeerDisconnected { .. } => "peer_disconnected",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NodeEvent {
+    /// The time of the event, in seconds since the epoch.
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: NodeEventKind,
+}
+
+/// Broadcasts the activity of the node to every subscriber, like the event
+/// stream of the node API. Events that are published while nobody is
+/// subscribed are dropped.
+#[derive(Clone, Debug)]
+pub struct NodeEvents {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl Default for NodeEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(NODE_EVENTS_CAPACITY);
+        NodeEvents { sender }
+    }
+}
+
+impl NodeEvents {
+    pub fn publish(&self, kind: NodeEventKind) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let _ = self.sender.send(NodeEvent { timestamp, kind });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        let node_events = NodeEvents::default();
+        node_events.publish(NodeEventKind::PeerConnected {
+            peer_id: String::from("peer_1"),
+        });
+
+        let mut receiver = node_events.clone().subscribe();
+        node_events.publish(NodeEventKind::ArtifactStored {
+            artifact_id: String::from("artifact_1"),
+        });
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(
+            event.kind,
+            NodeEventKind::ArtifactStored {
+                artifact_id: String::from("artifact_1")
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["type"],
+            "artifact_stored"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::model::MetadataBlockPayload;
+use super::service::ArtifactService;
+use crate::network::signed_document::SignedDocument;
+use anyhow::bail;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// The time to wait for a federation peer to respond.
+const FEDERATION_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The maximum number of transparency logs and of signed package metadata
+/// that a node includes in one sync.
+pub const FEDERATION_SYNC_PAGE_SIZE: usize = 500;
+
+/// The transparency logs and signed package metadata that a node serves to
+/// other nodes that cannot reach it over libp2p, e.g. because only HTTPS is
+/// allowed between them. Artifacts themselves are not included.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FederationSync {
+    /// The JSON list of transparency logs, signed by the serving node so the
+    /// receiving node can check that it is an authorized node.
+    pub transparency_logs: SignedDocument,
+    /// The sequence number of the last included transparency log, or of
+    /// the requested one when none are included.
+    pub log_sequence: u64,
+    pub metadata: Vec<MetadataBlockPayload>,
+    /// The sequence number of the last included package metadata, or of
+    /// the requested one when none are included.
+    pub metadata_sequence: u64,
+    /// Whether more entries are available after the included ones.
+    pub has_more: bool,
+}
+
+/// The query parameters of `GET /federation/sync`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FederationSyncQuery {
+    /// Only include transparency logs that were stored after the one with
+    /// this sequence number.
+    #[serde(default)]
+    pub log_sequence: u64,
+    /// Only include package metadata that was stored after the metadata
+    /// with this sequence number.
+    #[serde(default)]
+    pub metadata_sequence: u64,
+}
+
+/// Another node that metadata is pulled from over HTTP(S), as a fallback
+/// for when it can't be reached over libp2p.
+#[derive(Clone, Debug)]
+pub struct FederationPeer {
+    url: String,
+    token: Option<String>,
+    query: FederationSyncQuery,
+    client: reqwest::Client,
+}
+
+impl FederationPeer {
+    /// A peer with the specified base URL of its node API, e.g.
+    /// https://node.example.com:7888, and an optional bearer token. The URL
+    /// must use https, unless `allow_http` is set.
+    pub fn new(url: &str, token: Option<String>, allow_http: bool) -> anyhow::Result<Self> {
+        let parsed_url = reqwest::Url::parse(url)?;
+        match parsed_url.scheme() {
+            "https" => {}
+            "http" if allow_http => {}
+            scheme => bail!(
+                "The federation peer {} uses {} instead of https, which must be allowed explicitly",
+                url,
+                scheme
+            ),
+        }
+        Ok(FederationPeer {
+            url: url.trim_end_matches('/').to_owned(),
+            token,
+            query: FederationSyncQuery::default(),
+            client: reqwest::Client::builder()
+                .timeout(FEDERATION_REQUEST_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Change the bearer token that is sent to the peer.
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.token = token;
+    }
+
+    /// Pull the entries that the peer stored since the previous sync and
+    /// store them. Returns the number of stored entries.
+    pub async fn sync(&mut self, artifact_service: &ArtifactService) -> anyhow::Result<usize> {
+        let mut applied = 0;
+        loop {
+            debug!("Pulling metadata from {} after {:?}", self.url, self.query);
+            let mut request = self
+                .client
+                .get(format!("{}/federation/sync", self.url))
+                .query(&self.query);
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let sync: FederationSync = request.send().await?.error_for_status()?.json().await?;
+
+            let next_query = FederationSyncQuery {
+                log_sequence: sync.log_sequence,
+                metadata_sequence: sync.metadata_sequence,
+            };
+            // Stop when the peer does not make progress, so a misbehaving
+            // peer can't keep the sync busy.
+            let has_more = sync.has_more && next_query != self.query;
+            applied += artifact_service.apply_federation_sync(sync)?;
+            self.query = next_query;
+            if !has_more {
+                break;
+            }
+        }
+        if applied > 0 {
+            info!("Stored {} entries pulled from {}", applied, self.url);
+        }
+        Ok(applied)
+    }
+}
@@ -0,0 +1,292 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use multihash::Hasher;
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DigestError {
+    #[error("Unsupported hash algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid {algorithm} digest: {hex}")]
+    InvalidHex {
+        algorithm: HashAlgorithm,
+        hex: String,
+    },
+}
+
+/// The hash algorithms that can be used to identify and verify artifacts.
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, PartialEq, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Calculate the hex encoded hash of the specified bytes.
+    pub fn hash(&self, bytes: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut sha256 = multihash::Sha2_256::default();
+                sha256.update(bytes);
+                hex::encode(sha256.finalize())
+            }
+            HashAlgorithm::Sha512 => hex::encode(Sha512::digest(bytes)),
+            HashAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        }
+    }
+
+    fn hex_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 64,
+            HashAlgorithm::Sha512 => 128,
+        }
+    }
+}
+
+/// A digest of an artifact, written as `<algorithm>:<hex>`, e.g.
+/// `sha512:ee26b0dd...`. A digest without an algorithm prefix is a
+/// SHA-256 digest, which is how artifact hashes were written before
+/// other algorithms were supported.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ArtifactDigest {
+    pub algorithm: HashAlgorithm,
+    pub hex: String,
+}
+
+impl ArtifactDigest {
+    pub fn calculate(algorithm: HashAlgorithm, bytes: &[u8]) -> Self {
+        ArtifactDigest {
+            algorithm,
+            hex: algorithm.hash(bytes),
+        }
+    }
+}
+
+impl fmt::Display for ArtifactDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hex)
+    }
+}
+
+impl FromStr for ArtifactDigest {
+    type Err = DigestError;
+
+    fn from_str(digest: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hex) = match digest.split_once(':') {
+            Some((algorithm, hex)) => (
+                HashAlgorithm::from_str(algorithm)
+                    .map_err(|_| DigestError::UnsupportedAlgorithm(algorithm.to_owned()))?,
+                hex,
+            ),
+            None => (HashAlgorithm::Sha256, digest),
+        };
+
+        if hex.len() != algorithm.hex_len() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DigestError::InvalidHex {
+                algorithm,
+                hex: hex.to_owned(),
+            });
+        }
+
+        Ok(ArtifactDigest {
+            algorithm,
+            hex: hex.to_ascii_lowercase(),
+        })
+    }
+}
+
+/// Returns true when the specified digest starts with the prefix of a
+/// supported hash algorithm, e.g. `sha256:`.
+pub fn has_algorithm_prefix(digest: &str) -> bool {
+    match digest.split_once(':') {
+        Some((algorithm, _)) => HashAlgorithm::from_str(algorithm).is_ok(),
+        None => false,
+    }
+}
+
+/// Calculate the hash of the specified bytes with the algorithm and in the
+/// notation of `expected_hash`, so that both can be compared as strings.
+/// When `expected_hash` is not a valid digest, the SHA-256 hash is returned,
+/// which never matches it.
+pub fn calculate_hash_like(expected_hash: &str, bytes: &[u8]) -> String {
+    match prefixed_algorithm(expected_hash) {
+        Some(algorithm) => ArtifactDigest::calculate(algorithm, bytes).to_string(),
+        None => HashAlgorithm::Sha256.hash(bytes),
+    }
+}
+
+// The algorithm of a valid digest that is written with an algorithm prefix.
+fn prefixed_algorithm(digest: &str) -> Option<HashAlgorithm> {
+    match ArtifactDigest::from_str(digest) {
+        Ok(artifact_digest) if has_algorithm_prefix(digest) => Some(artifact_digest.algorithm),
+        _ => None,
+    }
+}
+
+enum IncrementalHasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// A reader that calculates the hash of all bytes read through it, so that
+/// an artifact can be verified while it is being written to the storage.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: IncrementalHasher,
+    prefixed_algorithm: Option<HashAlgorithm>,
+}
+
+impl<R: Read> HashingReader<R> {
+    /// Hash the bytes read from `inner` with the algorithm and in the notation
+    /// of `expected_hash`, like [`calculate_hash_like`] does.
+    pub fn new_like(expected_hash: &str, inner: R) -> Self {
+        let prefixed_algorithm = prefixed_algorithm(expected_hash);
+        let hasher = match prefixed_algorithm.unwrap_or(HashAlgorithm::Sha256) {
+            HashAlgorithm::Sha256 => IncrementalHasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => IncrementalHasher::Sha512(Sha512::new()),
+            HashAlgorithm::Blake3 => IncrementalHasher::Blake3(Box::new(blake3::Hasher::new())),
+        };
+        HashingReader {
+            inner,
+            hasher,
+            prefixed_algorithm,
+        }
+    }
+
+    /// The hash of the bytes that were read so far.
+    pub fn hash(self) -> String {
+        let hex = match self.hasher {
+            IncrementalHasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+            IncrementalHasher::Sha512(hasher) => hex::encode(hasher.finalize()),
+            IncrementalHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        };
+        match self.prefixed_algorithm {
+            Some(algorithm) => ArtifactDigest { algorithm, hex }.to_string(),
+            None => hex,
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        match &mut self.hasher {
+            IncrementalHasher::Sha256(hasher) => hasher.update(&buf[..read]),
+            IncrementalHasher::Sha512(hasher) => hasher.update(&buf[..read]),
+            IncrementalHasher::Blake3(hasher) => {
+                hasher.update(&buf[..read]);
+            }
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    const SAMPLE_SHA256: &str = "fc9e0e7f7da17b7a2e62fc2b4eee0c94fea5f6ae0b78ad5bea8b0b0e7c6d1d7e";
+
+    #[test]
+    fn test_parse_digest_with_algorithm_prefix() {
+        let sha512 = HashAlgorithm::Sha512.hash(b"SAMPLE_DATA");
+        let digest = ArtifactDigest::from_str(&format!("sha512:{}", sha512)).unwrap();
+
+        assert_eq!(digest.algorithm, HashAlgorithm::Sha512);
+        assert_eq!(digest.hex, sha512);
+        assert_eq!(digest.to_string(), format!("sha512:{}", sha512));
+    }
+
+    #[test]
+    fn test_parse_digest_without_algorithm_prefix() {
+        let digest = ArtifactDigest::from_str(SAMPLE_SHA256).unwrap();
+
+        assert_eq!(digest.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(digest.hex, SAMPLE_SHA256);
+    }
+
+    #[test]
+    fn test_parse_invalid_digest() {
+        assert_eq!(
+            ArtifactDigest::from_str(&format!("md5:{}", SAMPLE_SHA256)),
+            Err(DigestError::UnsupportedAlgorithm("md5".to_owned()))
+        );
+        assert_eq!(
+            ArtifactDigest::from_str(&format!("sha512:{}", SAMPLE_SHA256)),
+            Err(DigestError::InvalidHex {
+                algorithm: HashAlgorithm::Sha512,
+                hex: SAMPLE_SHA256.to_owned()
+            })
+        );
+        assert!(ArtifactDigest::from_str("sha256:not-a-hex-digest").is_err());
+    }
+
+    #[test]
+    fn test_hash_lengths() {
+        for algorithm in [
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha512,
+            HashAlgorithm::Blake3,
+        ] {
+            assert_eq!(algorithm.hash(b"SAMPLE_DATA").len(), algorithm.hex_len());
+        }
+    }
+
+    #[test]
+    fn test_calculate_hash_like() {
+        let blake3 = format!("blake3:{}", HashAlgorithm::Blake3.hash(b"SAMPLE_DATA"));
+        assert_eq!(calculate_hash_like(&blake3, b"SAMPLE_DATA"), blake3);
+
+        let sha256 = HashAlgorithm::Sha256.hash(b"SAMPLE_DATA");
+        assert_eq!(calculate_hash_like(&sha256, b"SAMPLE_DATA"), sha256);
+        assert_ne!(calculate_hash_like(&sha256, b"OTHER_DATA"), sha256);
+
+        assert_ne!(calculate_hash_like("invalid", b"SAMPLE_DATA"), "invalid");
+    }
+
+    #[test]
+    fn test_hashing_reader() {
+        for expected_hash in [
+            HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"),
+            format!("sha512:{}", HashAlgorithm::Sha512.hash(b"SAMPLE_DATA")),
+            format!("blake3:{}", HashAlgorithm::Blake3.hash(b"SAMPLE_DATA")),
+        ] {
+            let mut hashing_reader = HashingReader::new_like(&expected_hash, &b"SAMPLE_DATA"[..]);
+            io::copy(&mut hashing_reader, &mut io::sink()).unwrap();
+            assert_eq!(hashing_reader.hash(), expected_hash);
+        }
+    }
+
+    #[test]
+    fn test_has_algorithm_prefix() {
+        assert!(has_algorithm_prefix("sha256:1234"));
+        assert!(has_algorithm_prefix("sha512:1234"));
+        assert!(has_algorithm_prefix("blake3:1234"));
+        assert!(!has_algorithm_prefix("latest"));
+        assert!(!has_algorithm_prefix("md5:1234"));
+    }
+}
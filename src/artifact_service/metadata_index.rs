@@ -0,0 +1,1178 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::attestation::Attestation;
+use super::compression;
+use super::model::{PackageType, QuarantinedArtifact, RepositoryUsage};
+use super::sbom::{Sbom, SbomFormat};
+use super::storage_backend::StorageBackend;
+use crate::network::signed_document::SignedDocument;
+use log::{debug, info};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// The columns that were added to the artifact metadata table after its first
+// version, in the order of the table definition, with the statement that fills
+// them in for the artifacts that were already indexed.
+const ADDED_COLUMNS: [(&str, &str, Option<&str>); 6] = [
+    ("local_pulls", "INTEGER NOT NULL DEFAULT 0", None),
+    ("peer_serves", "INTEGER NOT NULL DEFAULT 0", None),
+    (
+        "stored_size",
+        "INTEGER NOT NULL DEFAULT 0",
+        Some("UPDATE ARTIFACTMETADATA SET stored_size = size"),
+    ),
+    ("compressed", "INTEGER NOT NULL DEFAULT 0", None),
+    ("blob_id", "TEXT", None),
+    ("artifact_hash", "TEXT", None),
+];
+
+/// The metadata of an artifact in the local storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArtifactMetadata {
+    pub artifact_id: String,
+    pub size: u64,
+    pub media_type: Option<String>,
+    pub package_type: Option<PackageType>,
+    pub package_specific_artifact_id: Option<String>,
+    pub last_access: SystemTime,
+    /// The number of artifacts that share the stored bytes of this artifact.
+    pub ref_count: u64,
+    pub local_pulls: u64,
+    pub peer_serves: u64,
+    /// The number of bytes the artifact takes up in the storage backend.
+    pub stored_size: u64,
+    /// Whether the artifact is stored compressed.
+    pub compressed: bool,
+    /// The id of the artifact under which the bytes of this artifact are
+    /// stored. Artifacts with the same content, e.g. the same blob pulled
+    /// through two repositories, share the bytes of the first one stored.
+    pub blob_id: String,
+    /// The verified hash of the content of the artifact, if known.
+    pub artifact_hash: Option<String>,
+}
+
+impl ArtifactMetadata {
+    /// The key under which the bytes of the artifact are kept in the backend.
+    pub fn blob_key(&self) -> String {
+        if self.compressed {
+            compression::compressed_key(&self.blob_id)
+        } else {
+            self.blob_id.clone()
+        }
+    }
+}
+
+/// An embedded index of the metadata of stored artifacts, so that listing,
+/// garbage collection and status queries don't need to walk the storage.
+/// The index is rebuilt from the storage backend when it doesn't exist and
+/// reconciled with it when it is opened.
+pub struct MetadataIndex {
+    db_path: PathBuf,
+}
+
+impl MetadataIndex {
+    pub fn open(index_path: PathBuf, backend: &dyn StorageBackend) -> io::Result<Self> {
+        fs::create_dir_all(&index_path)?;
+        let db_path = index_path.join("artifact_index.db");
+        let exists = db_path.is_file();
+
+        let metadata_index = MetadataIndex { db_path };
+        metadata_index.migrate()?;
+        if exists {
+            metadata_index.reconcile(backend)?;
+        } else {
+            metadata_index.rebuild(backend)?;
+        }
+        Ok(metadata_index)
+    }
+
+    // Add the columns that an index created by an older version of the node
+    // lacks, so that queries can rely on all columns being present.
+    fn migrate(&self) -> io::Result<()> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT name FROM pragma_table_info('ARTIFACTMETADATA')")
+            .map_err(to_io_error)?;
+        let columns = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(to_io_error)?
+            .collect::<rusqlite::Result<HashSet<String>>>()
+            .map_err(to_io_error)?;
+        for (column, definition, fill) in ADDED_COLUMNS {
+            if columns.contains(column) {
+                continue;
+            }
+            info!("Adding column {} to the artifact index", column);
+            conn.execute(
+                &format!(
+                    "ALTER TABLE ARTIFACTMETADATA ADD COLUMN {} {}",
+                    column, definition
+                ),
+                [],
+            )
+            .map_err(to_io_error)?;
+            if let Some(fill) = fill {
+                conn.execute(fill, []).map_err(to_io_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open_db(&self) -> io::Result<Connection> {
+        let conn = Connection::open(&self.db_path).map_err(to_io_error)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ARTIFACTMETADATA (
+                artifact_id TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                media_type TEXT,
+                package_type TEXT,
+                package_specific_artifact_id TEXT,
+                last_access INTEGER NOT NULL,
+                ref_count INTEGER NOT NULL DEFAULT 0,
+                local_pulls INTEGER NOT NULL DEFAULT 0,
+                peer_serves INTEGER NOT NULL DEFAULT 0,
+                stored_size INTEGER NOT NULL DEFAULT 0,
+                compressed INTEGER NOT NULL DEFAULT 0,
+                blob_id TEXT,
+                artifact_hash TEXT
+            )",
+            [],
+        )
+        .map_err(|err| {
+            debug!("Error creating artifact index database table: {:?}", err);
+            to_io_error(err)
+        })?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS QUARANTINE (
+                quarantine_key TEXT PRIMARY KEY,
+                artifact_id TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|err| {
+            debug!("Error creating quarantine database table: {:?}", err);
+            to_io_error(err)
+        })?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS SBOM (
+                artifact_digest TEXT NOT NULL,
+                format TEXT NOT NULL,
+                digest TEXT NOT NULL,
+                manifest_digest TEXT NOT NULL,
+                document TEXT NOT NULL,
+                PRIMARY KEY (artifact_digest, format)
+            )",
+            [],
+        )
+        .map_err(|err| {
+            debug!("Error creating SBOM database table: {:?}", err);
+            to_io_error(err)
+        })?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ATTESTATION (
+                artifact_digest TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attestation TEXT NOT NULL,
+                PRIMARY KEY (artifact_digest, payload)
+            )",
+            [],
+        )
+        .map_err(|err| {
+            debug!("Error creating attestation database table: {:?}", err);
+            to_io_error(err)
+        })?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS SIGNED_METADATA (
+                key TEXT PRIMARY KEY,
+                document TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(|err| {
+            debug!("Error creating signed metadata database table: {:?}", err);
+            to_io_error(err)
+        })?;
+        Ok(conn)
+    }
+
+    /// Replace the contents of the index with the artifacts that are
+    /// currently stored in the backend.
+    pub fn rebuild(&self, backend: &dyn StorageBackend) -> io::Result<()> {
+        let mut conn = self.open_db()?;
+        let tx = conn.transaction().map_err(to_io_error)?;
+        tx.execute("DELETE FROM ARTIFACTMETADATA", [])
+            .map_err(to_io_error)?;
+        let objects = backend.list_with_sizes()?;
+        for (key, stored_size) in objects.iter() {
+            insert_object(&tx, backend, key, *stored_size)?;
+        }
+        tx.commit().map_err(to_io_error)?;
+
+        info!(
+            "Rebuilt the artifact index with {} artifacts",
+            objects.len()
+        );
+        Ok(())
+    }
+
+    /// Bring the index in line with the artifacts that are currently stored
+    /// in the backend, which may have changed while the node was stopped.
+    /// Artifacts that are no longer stored are removed from the index and
+    /// stored artifacts that are missing from it are added.
+    pub fn reconcile(&self, backend: &dyn StorageBackend) -> io::Result<()> {
+        let objects = backend.list_with_sizes()?;
+        let indexed = self.list()?;
+        let stored_keys: HashSet<&str> = objects.iter().map(|(key, _)| key.as_str()).collect();
+        let indexed_keys: HashSet<String> =
+            indexed.iter().map(ArtifactMetadata::blob_key).collect();
+
+        let mut conn = self.open_db()?;
+        let tx = conn.transaction().map_err(to_io_error)?;
+        let mut removed = 0;
+        for metadata in indexed
+            .iter()
+            .filter(|metadata| !stored_keys.contains(metadata.blob_key().as_str()))
+        {
+            tx.execute(
+                "DELETE FROM ARTIFACTMETADATA WHERE artifact_id = ?1",
+                params![metadata.artifact_id],
+            )
+            .map_err(to_io_error)?;
+            removed += 1;
+        }
+        let mut added = 0;
+        for (key, stored_size) in objects
+            .iter()
+            .filter(|(key, _)| !indexed_keys.contains(key))
+        {
+            insert_object(&tx, backend, key, *stored_size)?;
+            added += 1;
+        }
+        tx.commit().map_err(to_io_error)?;
+
+        if removed > 0 || added > 0 {
+            info!(
+                "Reconciled the artifact index with the storage, removed {} and added {} artifacts",
+                removed, added
+            );
+        }
+        Ok(())
+    }
+
+    pub fn insert(&self, artifact_id: &str, size: u64, time: SystemTime) -> io::Result<()> {
+        self.insert_stored(artifact_id, size, size, false, None, time)
+    }
+
+    /// Insert an artifact that takes up `stored_size` bytes in the storage
+    /// backend, which differs from its `size` when it is stored compressed.
+    pub fn insert_stored(
+        &self,
+        artifact_id: &str,
+        size: u64,
+        stored_size: u64,
+        compressed: bool,
+        artifact_hash: Option<&str>,
+        time: SystemTime,
+    ) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "INSERT OR REPLACE INTO ARTIFACTMETADATA (artifact_id, size, last_access, ref_count, stored_size, compressed, blob_id, artifact_hash) VALUES (?1, ?2, ?3, 1, ?4, ?5, ?1, ?6)",
+                params![artifact_id, size, to_millis(time), stored_size, compressed, artifact_hash],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// Insert an artifact that shares the stored bytes of `blob`, an artifact
+    /// with the same content, and count the extra reference to them.
+    pub fn insert_reference(
+        &self,
+        artifact_id: &str,
+        blob: &ArtifactMetadata,
+        time: SystemTime,
+    ) -> io::Result<()> {
+        let mut conn = self.open_db()?;
+        let tx = conn.transaction().map_err(to_io_error)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO ARTIFACTMETADATA (artifact_id, size, last_access, stored_size, compressed, blob_id, artifact_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                artifact_id,
+                blob.size,
+                to_millis(time),
+                blob.stored_size,
+                blob.compressed,
+                blob.blob_id,
+                blob.artifact_hash
+            ],
+        )
+        .map_err(to_io_error)?;
+        update_ref_count(&tx, &blob.blob_id)?;
+        tx.commit().map_err(to_io_error)
+    }
+
+    /// Remove an artifact and return the number of artifacts that still
+    /// reference its stored bytes. The bytes may only be deleted when no
+    /// references remain.
+    pub fn remove(&self, artifact_id: &str) -> io::Result<u64> {
+        let mut conn = self.open_db()?;
+        let tx = conn.transaction().map_err(to_io_error)?;
+        let blob_id: Option<String> = tx
+            .query_row(
+                "SELECT COALESCE(blob_id, artifact_id) FROM ARTIFACTMETADATA WHERE artifact_id = ?1",
+                params![artifact_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_io_error)?;
+        let ref_count = match blob_id {
+            Some(blob_id) => {
+                tx.execute(
+                    "DELETE FROM ARTIFACTMETADATA WHERE artifact_id = ?1",
+                    params![artifact_id],
+                )
+                .map_err(to_io_error)?;
+                update_ref_count(&tx, &blob_id)?
+            }
+            None => 0,
+        };
+        tx.commit().map_err(to_io_error)?;
+        Ok(ref_count)
+    }
+
+    /// Remove all artifacts that share the stored bytes with id `blob_id`
+    /// and return their ids.
+    pub fn remove_blob(&self, blob_id: &str) -> io::Result<Vec<String>> {
+        let mut conn = self.open_db()?;
+        let tx = conn.transaction().map_err(to_io_error)?;
+        let artifact_ids = {
+            let mut stmt = tx
+                .prepare("SELECT artifact_id FROM ARTIFACTMETADATA WHERE COALESCE(blob_id, artifact_id) = ?1")
+                .map_err(to_io_error)?;
+            let rows = stmt
+                .query_map(params![blob_id], |row| row.get(0))
+                .map_err(to_io_error)?;
+            rows.collect::<Result<Vec<String>, _>>()
+                .map_err(to_io_error)?
+        };
+        tx.execute(
+            "DELETE FROM ARTIFACTMETADATA WHERE COALESCE(blob_id, artifact_id) = ?1",
+            params![blob_id],
+        )
+        .map_err(to_io_error)?;
+        tx.commit().map_err(to_io_error)?;
+        Ok(artifact_ids)
+    }
+
+    /// Find a stored artifact with the specified verified content hash.
+    pub fn find_by_hash(&self, artifact_hash: &str) -> io::Result<Option<ArtifactMetadata>> {
+        self.open_db()?
+            .query_row(
+                "SELECT * FROM ARTIFACTMETADATA WHERE artifact_hash = ?1 LIMIT 1",
+                params![artifact_hash],
+                read_artifact_metadata,
+            )
+            .optional()
+            .map_err(to_io_error)
+    }
+
+    pub fn record_access(&self, artifact_id: &str, time: SystemTime) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "UPDATE ARTIFACTMETADATA SET last_access = ?2 WHERE artifact_id = ?1",
+                params![artifact_id, to_millis(time)],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// Count a pull of the artifact by a local package manager.
+    pub fn record_local_pull(&self, artifact_id: &str) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "UPDATE ARTIFACTMETADATA SET local_pulls = local_pulls + 1 WHERE artifact_id = ?1",
+                params![artifact_id],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// Count a serve of the artifact to another peer.
+    pub fn record_peer_serve(&self, artifact_id: &str) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "UPDATE ARTIFACTMETADATA SET peer_serves = peer_serves + 1 WHERE artifact_id = ?1",
+                params![artifact_id],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// Associate an artifact with the package it belongs to.
+    pub fn associate(
+        &self,
+        artifact_id: &str,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+        media_type: Option<&str>,
+    ) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "UPDATE ARTIFACTMETADATA SET package_type = ?2, package_specific_artifact_id = ?3, media_type = COALESCE(?4, media_type) WHERE artifact_id = ?1",
+                params![artifact_id, package_type, package_specific_artifact_id, media_type],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    pub fn get(&self, artifact_id: &str) -> io::Result<Option<ArtifactMetadata>> {
+        self.open_db()?
+            .query_row(
+                "SELECT * FROM ARTIFACTMETADATA WHERE artifact_id = ?1",
+                params![artifact_id],
+                read_artifact_metadata,
+            )
+            .optional()
+            .map_err(to_io_error)
+    }
+
+    /// List the metadata of all artifacts, ordered by artifact id.
+    pub fn list(&self) -> io::Result<Vec<ArtifactMetadata>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT * FROM ARTIFACTMETADATA ORDER BY artifact_id")
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map([], read_artifact_metadata)
+            .map_err(to_io_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_io_error)
+    }
+
+    /// List the metadata of at most `limit` artifacts, ordered by artifact id,
+    /// skipping the first `offset` artifacts.
+    pub fn list_page(&self, offset: usize, limit: usize) -> io::Result<Vec<ArtifactMetadata>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT * FROM ARTIFACTMETADATA ORDER BY artifact_id LIMIT ?1 OFFSET ?2")
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map(params![limit as i64, offset as i64], read_artifact_metadata)
+            .map_err(to_io_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_io_error)
+    }
+
+    /// The number of artifacts in the index.
+    pub fn count(&self) -> io::Result<usize> {
+        self.open_db()?
+            .query_row("SELECT COUNT(*) FROM ARTIFACTMETADATA", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count as usize)
+            .map_err(to_io_error)
+    }
+
+    /// The usage statistics of the artifacts, aggregated per repository.
+    pub fn usage_by_repository(&self) -> io::Result<Vec<RepositoryUsage>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT package_type, package_specific_artifact_id, size, local_pulls, peer_serves FROM ARTIFACTMETADATA",
+            )
+            .map_err(to_io_error)?;
+        let mut rows = stmt.query([]).map_err(to_io_error)?;
+
+        let mut usage_by_repository = BTreeMap::new();
+        while let Some(row) = rows.next().map_err(to_io_error)? {
+            let package_type: Option<String> = row.get(0).map_err(to_io_error)?;
+            let package_type = package_type.and_then(|pt| PackageType::from_str(&pt).ok());
+            let package_specific_artifact_id: Option<String> = row.get(1).map_err(to_io_error)?;
+            let repository = package_type
+                .zip(package_specific_artifact_id)
+                .map(|(package_type, id)| package_type.repository(&id).to_owned());
+
+            let usage = usage_by_repository
+                .entry((package_type.map(|pt| pt.to_string()), repository.clone()))
+                .or_insert_with(|| RepositoryUsage {
+                    package_type,
+                    repository,
+                    ..Default::default()
+                });
+            usage.artifacts += 1;
+            usage.size_bytes += row.get::<_, u64>(2).map_err(to_io_error)?;
+            usage.local_pulls += row.get::<_, u64>(3).map_err(to_io_error)?;
+            usage.peer_serves += row.get::<_, u64>(4).map_err(to_io_error)?;
+        }
+        Ok(usage_by_repository.into_values().collect())
+    }
+
+    /// Record why an artifact was moved into the quarantine.
+    pub fn insert_quarantined(&self, quarantined_artifact: &QuarantinedArtifact) -> io::Result<()> {
+        self.open_db()?
+            .execute(
+                "INSERT OR REPLACE INTO QUARANTINE (quarantine_key, artifact_id, reason, quarantined_at) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    quarantined_artifact.quarantine_key,
+                    quarantined_artifact.artifact_id,
+                    quarantined_artifact.reason,
+                    quarantined_artifact.quarantined_at
+                ],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// List the quarantined artifacts, most recently quarantined first.
+    pub fn list_quarantined(&self) -> io::Result<Vec<QuarantinedArtifact>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT quarantine_key, artifact_id, reason, quarantined_at FROM QUARANTINE ORDER BY quarantined_at DESC, quarantine_key")
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(QuarantinedArtifact {
+                    quarantine_key: row.get(0)?,
+                    artifact_id: row.get(1)?,
+                    reason: row.get(2)?,
+                    quarantined_at: row.get(3)?,
+                })
+            })
+            .map_err(to_io_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_io_error)
+    }
+
+    /// Store an SBOM, replacing an SBOM of the same format for the same artifact.
+    pub fn insert_sbom(&self, sbom: &Sbom) -> io::Result<()> {
+        let document = serde_json::to_string(&sbom.document)?;
+        self.open_db()?
+            .execute(
+                "INSERT OR REPLACE INTO SBOM (artifact_digest, format, digest, manifest_digest, document) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    sbom.artifact_digest,
+                    sbom.format.to_string(),
+                    sbom.digest,
+                    sbom.referrer_manifest_digest(),
+                    document
+                ],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// List the SBOMs of the artifact with the specified digest.
+    pub fn list_sboms(&self, artifact_digest: &str) -> io::Result<Vec<Sbom>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT artifact_digest, format, digest, document FROM SBOM WHERE artifact_digest = ?1 ORDER BY format")
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map(params![artifact_digest], read_sbom)
+            .map_err(to_io_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_io_error)
+    }
+
+    /// Find the SBOM of which either the content or the referrer manifest
+    /// has the specified digest.
+    pub fn find_sbom(&self, digest: &str) -> io::Result<Option<Sbom>> {
+        self.open_db()?
+            .query_row(
+                "SELECT artifact_digest, format, digest, document FROM SBOM WHERE digest = ?1 OR manifest_digest = ?1 LIMIT 1",
+                params![digest],
+                read_sbom,
+            )
+            .optional()
+            .map_err(to_io_error)
+    }
+
+    /// Store an attestation, replacing the same attestation that was stored before.
+    pub fn insert_attestation(&self, attestation: &Attestation) -> io::Result<()> {
+        let json = serde_json::to_string(attestation)?;
+        self.open_db()?
+            .execute(
+                "INSERT OR REPLACE INTO ATTESTATION (artifact_digest, payload, attestation) VALUES (?1, ?2, ?3)",
+                params![attestation.artifact_digest, attestation.envelope.payload, json],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// List the attestations about the artifact with the specified digest.
+    pub fn list_attestations(&self, artifact_digest: &str) -> io::Result<Vec<Attestation>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT attestation FROM ATTESTATION WHERE artifact_digest = ?1 ORDER BY rowid",
+            )
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map(params![artifact_digest], |row| {
+                let json: String = row.get(0)?;
+                serde_json::from_str(&json).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        0,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })
+            })
+            .map_err(to_io_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(to_io_error)
+    }
+
+    /// Store the signed document with package metadata under the specified
+    /// key. The stored document gets a sequence number that is higher than
+    /// that of every other document, also when it replaces one.
+    pub fn insert_signed_metadata(&self, key: &str, document: &SignedDocument) -> io::Result<()> {
+        let json = serde_json::to_string(document)?;
+        self.open_db()?
+            .execute(
+                "INSERT OR REPLACE INTO SIGNED_METADATA (rowid, key, document) SELECT COALESCE(MAX(rowid), 0) + 1, ?1, ?2 FROM SIGNED_METADATA",
+                params![key, json],
+            )
+            .map(|_| ())
+            .map_err(to_io_error)
+    }
+
+    /// Find the signed document with package metadata under the specified key.
+    pub fn find_signed_metadata(&self, key: &str) -> io::Result<Option<SignedDocument>> {
+        let json: Option<String> = self
+            .open_db()?
+            .query_row(
+                "SELECT document FROM SIGNED_METADATA WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_io_error)?;
+        json.map(|json| serde_json::from_str(&json).map_err(io::Error::from))
+            .transpose()
+    }
+
+    /// List the keys and signed documents of all package metadata.
+    pub fn list_signed_metadata(&self) -> io::Result<Vec<(String, SignedDocument)>> {
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare("SELECT key, document FROM SIGNED_METADATA ORDER BY key")
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(to_io_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_io_error)?;
+        rows.into_iter()
+            .map(|(key, json)| Ok((key, serde_json::from_str::<SignedDocument>(&json)?)))
+            .collect::<io::Result<Vec<_>>>()
+    }
+
+    /// List at most `limit` signed documents with package metadata that were
+    /// stored after the specified sequence number, in the order they were
+    /// stored, with their sequence numbers and keys.
+    pub fn list_signed_metadata_after_sequence(
+        &self,
+        sequence: u64,
+        limit: usize,
+    ) -> io::Result<Vec<(u64, String, SignedDocument)>> {
+        // sqlite stores signed 64-bit integers
+        let sequence = sequence.min(i64::MAX as u64);
+        let conn = self.open_db()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT rowid, key, document FROM SIGNED_METADATA WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+            )
+            .map_err(to_io_error)?;
+        let rows = stmt
+            .query_map(params![sequence, limit], |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(to_io_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_io_error)?;
+        rows.into_iter()
+            .map(|(sequence, key, json)| {
+                Ok((
+                    sequence,
+                    key,
+                    serde_json::from_str::<SignedDocument>(&json)?,
+                ))
+            })
+            .collect::<io::Result<Vec<_>>>()
+    }
+
+    /// The total number of bytes all artifacts take up in the storage backend.
+    /// Bytes that are shared by several artifacts are counted once.
+    pub fn total_size(&self) -> io::Result<u64> {
+        self.open_db()?
+            .query_row(
+                "SELECT COALESCE(SUM(stored_size), 0) FROM (SELECT MAX(stored_size) AS stored_size FROM ARTIFACTMETADATA GROUP BY COALESCE(blob_id, artifact_id))",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(to_io_error)
+    }
+
+    /// Write all changes of the index to the database file, so none are
+    /// lost when the node is stopped.
+    pub fn flush(&self) -> io::Result<()> {
+        self.open_db()?
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(to_io_error)
+    }
+}
+
+// Set the reference count of all artifacts that share the stored bytes with
+// id `blob_id` and return it.
+fn update_ref_count(conn: &Connection, blob_id: &str) -> io::Result<u64> {
+    let ref_count: u64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ARTIFACTMETADATA WHERE COALESCE(blob_id, artifact_id) = ?1",
+            params![blob_id],
+            |row| row.get(0),
+        )
+        .map_err(to_io_error)?;
+    conn.execute(
+        "UPDATE ARTIFACTMETADATA SET ref_count = ?2 WHERE COALESCE(blob_id, artifact_id) = ?1",
+        params![blob_id, ref_count],
+    )
+    .map_err(to_io_error)?;
+    Ok(ref_count)
+}
+
+fn read_artifact_metadata(row: &Row) -> rusqlite::Result<ArtifactMetadata> {
+    let artifact_id: String = row.get(0)?;
+    let package_type: Option<String> = row.get(3)?;
+    let last_access: u64 = row.get(5)?;
+    let blob_id: Option<String> = row.get(11)?;
+    Ok(ArtifactMetadata {
+        blob_id: blob_id.unwrap_or_else(|| artifact_id.clone()),
+        artifact_id,
+        size: row.get(1)?,
+        media_type: row.get(2)?,
+        package_type: package_type.and_then(|pt| PackageType::from_str(&pt).ok()),
+        package_specific_artifact_id: row.get(4)?,
+        last_access: UNIX_EPOCH + Duration::from_millis(last_access),
+        ref_count: row.get(6)?,
+        local_pulls: row.get(7)?,
+        peer_serves: row.get(8)?,
+        stored_size: row.get(9)?,
+        compressed: row.get(10)?,
+        artifact_hash: row.get(12)?,
+    })
+}
+
+fn read_sbom(row: &Row) -> rusqlite::Result<Sbom> {
+    let format: String = row.get(1)?;
+    let document: String = row.get(3)?;
+    Ok(Sbom {
+        artifact_digest: row.get(0)?,
+        format: SbomFormat::from_str(&format).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        digest: row.get(2)?,
+        document: serde_json::from_str(&document).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn to_io_error(error: rusqlite::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error)
+}
+
+// Insert a stored object into the index, with the metadata that can be
+// derived from the backend.
+fn insert_object(
+    conn: &Connection,
+    backend: &dyn StorageBackend,
+    key: &str,
+    stored_size: u64,
+) -> io::Result<()> {
+    let (artifact_id, size, compressed) = match compression::artifact_id_of_compressed_key(key) {
+        Some(artifact_id) => (
+            artifact_id,
+            compression::uncompressed_size(backend.get(key)?)?,
+            true,
+        ),
+        None => (key, stored_size, false),
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO ARTIFACTMETADATA (artifact_id, size, last_access, ref_count, stored_size, compressed, blob_id) VALUES (?1, ?2, ?3, 1, ?4, ?5, ?1)",
+        params![
+            artifact_id,
+            size,
+            to_millis(backend.modified(key)?),
+            stored_size,
+            compressed
+        ],
+    )
+    .map(|_| ())
+    .map_err(to_io_error)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::artifact_service::storage_backend::filesystem::FileSystemBackend;
+    use crate::util::test_util;
+
+    #[test]
+    fn test_insert_associate_and_remove() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+        assert!(metadata_index.list().unwrap().is_empty());
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_000_123);
+        metadata_index.insert("artifact_id", 11, time).unwrap();
+        metadata_index
+            .associate(
+                "artifact_id",
+                PackageType::Docker,
+                "alpine@sha256:1234",
+                Some("application/octet-stream"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            metadata_index.get("artifact_id").unwrap(),
+            Some(ArtifactMetadata {
+                artifact_id: "artifact_id".to_owned(),
+                size: 11,
+                media_type: Some("application/octet-stream".to_owned()),
+                package_type: Some(PackageType::Docker),
+                package_specific_artifact_id: Some("alpine@sha256:1234".to_owned()),
+                last_access: time,
+                ref_count: 1,
+                local_pulls: 0,
+                peer_serves: 0,
+                stored_size: 11,
+                compressed: false,
+                blob_id: "artifact_id".to_owned(),
+                artifact_hash: None,
+            })
+        );
+        assert_eq!(metadata_index.total_size().unwrap(), 11);
+
+        metadata_index.insert("other_artifact_id", 5, time).unwrap();
+        assert_eq!(metadata_index.count().unwrap(), 2);
+        let page = metadata_index.list_page(1, 10).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].artifact_id, "other_artifact_id");
+        metadata_index.remove("other_artifact_id").unwrap();
+
+        metadata_index.remove("artifact_id").unwrap();
+        assert_eq!(metadata_index.get("artifact_id").unwrap(), None);
+        assert_eq!(metadata_index.total_size().unwrap(), 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_insert_compressed() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+
+        metadata_index
+            .insert_stored("artifact_id", 100, 40, true, None, SystemTime::now())
+            .unwrap();
+
+        let metadata = metadata_index.get("artifact_id").unwrap().unwrap();
+        assert_eq!(metadata.size, 100);
+        assert_eq!(metadata.stored_size, 40);
+        assert!(metadata.compressed);
+        assert_eq!(metadata_index.total_size().unwrap(), 40);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_flush() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+
+        metadata_index
+            .insert("artifact_id", 100, SystemTime::now())
+            .unwrap();
+        metadata_index.flush().unwrap();
+
+        let reopened_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+        assert_eq!(reopened_index.count().unwrap(), 1);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_list_signed_metadata_after_sequence() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        let document = SignedDocument::new(&keypair, "{}").unwrap();
+
+        metadata_index
+            .insert_signed_metadata("first_key", &document)
+            .unwrap();
+        metadata_index
+            .insert_signed_metadata("second_key", &document)
+            .unwrap();
+        let all = metadata_index
+            .list_signed_metadata_after_sequence(0, 10)
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].1, "first_key");
+        assert_eq!(all[1].1, "second_key");
+
+        // A replaced document gets a higher sequence number than all others,
+        // including the one it replaces when that was the last one.
+        metadata_index
+            .insert_signed_metadata("second_key", &document)
+            .unwrap();
+        metadata_index
+            .insert_signed_metadata("first_key", &document)
+            .unwrap();
+        let replaced = metadata_index
+            .list_signed_metadata_after_sequence(all[1].0, 10)
+            .unwrap();
+        assert_eq!(replaced.len(), 2);
+        assert_eq!(replaced[0].1, "second_key");
+        assert_eq!(replaced[1].1, "first_key");
+        assert!(replaced[0].0 > all[1].0);
+        assert_eq!(metadata_index.list_signed_metadata().unwrap().len(), 2);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_shared_blob_reference_counting() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+        let time = SystemTime::now();
+
+        metadata_index
+            .insert_stored("first", 100, 40, true, Some("sha256:1234"), time)
+            .unwrap();
+        let blob = metadata_index.find_by_hash("sha256:1234").unwrap().unwrap();
+        assert_eq!(blob.artifact_id, "first");
+        metadata_index
+            .insert_reference("second", &blob, time)
+            .unwrap();
+
+        let second = metadata_index.get("second").unwrap().unwrap();
+        assert_eq!(second.blob_id, "first");
+        assert_eq!(second.size, 100);
+        assert!(second.compressed);
+        assert_eq!(second.ref_count, 2);
+        assert_eq!(metadata_index.get("first").unwrap().unwrap().ref_count, 2);
+        assert_eq!(metadata_index.total_size().unwrap(), 40);
+
+        assert_eq!(metadata_index.remove("first").unwrap(), 1);
+        assert_eq!(metadata_index.get("second").unwrap().unwrap().ref_count, 1);
+        assert_eq!(metadata_index.total_size().unwrap(), 40);
+        assert_eq!(metadata_index.remove("second").unwrap(), 0);
+        assert_eq!(metadata_index.total_size().unwrap(), 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_usage_by_repository() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+        let time = SystemTime::now();
+        for (artifact_id, size) in [
+            ("docker_a", 10),
+            ("docker_b", 20),
+            ("docker_c", 7),
+            ("unknown", 5),
+        ] {
+            metadata_index.insert(artifact_id, size, time).unwrap();
+        }
+        for (artifact_id, package_specific_artifact_id) in [
+            ("docker_a", "library/alpine@sha256:1234"),
+            ("docker_b", "library/alpine:3.16"),
+            ("docker_c", "library/busybox:latest"),
+        ] {
+            metadata_index
+                .associate(
+                    artifact_id,
+                    PackageType::Docker,
+                    package_specific_artifact_id,
+                    None,
+                )
+                .unwrap();
+        }
+        metadata_index.record_local_pull("docker_a").unwrap();
+        metadata_index.record_local_pull("docker_a").unwrap();
+        metadata_index.record_peer_serve("docker_b").unwrap();
+        metadata_index.record_peer_serve("unknown").unwrap();
+
+        assert_eq!(
+            metadata_index.get("docker_a").unwrap().unwrap().local_pulls,
+            2
+        );
+        assert_eq!(
+            metadata_index.usage_by_repository().unwrap(),
+            vec![
+                RepositoryUsage {
+                    package_type: None,
+                    repository: None,
+                    artifacts: 1,
+                    size_bytes: 5,
+                    local_pulls: 0,
+                    peer_serves: 1,
+                },
+                RepositoryUsage {
+                    package_type: Some(PackageType::Docker),
+                    repository: Some("library/alpine".to_owned()),
+                    artifacts: 2,
+                    size_bytes: 30,
+                    local_pulls: 2,
+                    peer_serves: 1,
+                },
+                RepositoryUsage {
+                    package_type: Some(PackageType::Docker),
+                    repository: Some("library/busybox".to_owned()),
+                    artifacts: 1,
+                    size_bytes: 7,
+                    local_pulls: 0,
+                    peer_serves: 0,
+                },
+            ]
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_rebuild_when_index_is_missing() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        backend.put("first", &mut &b"SAMPLE_DATA"[..]).unwrap();
+        backend.put("second", &mut &b"DATA"[..]).unwrap();
+
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+
+        let artifact_ids: Vec<String> = metadata_index
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|metadata| metadata.artifact_id)
+            .collect();
+        assert_eq!(artifact_ids, vec!["first", "second"]);
+        assert_eq!(metadata_index.total_size().unwrap(), 15);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_reconcile_when_index_exists() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        backend.put("first", &mut &b"SAMPLE_DATA"[..]).unwrap();
+        backend.put("second", &mut &b"DATA"[..]).unwrap();
+        MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+
+        // the storage changes while the node is stopped
+        backend.delete("first").unwrap();
+        backend.put("third", &mut &b"MORE_DATA"[..]).unwrap();
+
+        let metadata_index = MetadataIndex::open(tmp_dir.join("index"), &backend).unwrap();
+
+        let artifact_ids: Vec<String> = metadata_index
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|metadata| metadata.artifact_id)
+            .collect();
+        assert_eq!(artifact_ids, vec!["second", "third"]);
+        assert_eq!(metadata_index.total_size().unwrap(), 13);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_migrate_index_of_older_version() {
+        let tmp_dir = test_util::tests::setup();
+
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+        backend
+            .put("artifact_id", &mut &b"SAMPLE_DATA"[..])
+            .unwrap();
+        let index_path = tmp_dir.join("index");
+        fs::create_dir_all(&index_path).unwrap();
+        let conn = Connection::open(index_path.join("artifact_index.db")).unwrap();
+        conn.execute(
+            "CREATE TABLE ARTIFACTMETADATA (
+                artifact_id TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                media_type TEXT,
+                package_type TEXT,
+                package_specific_artifact_id TEXT,
+                last_access INTEGER NOT NULL,
+                ref_count INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO ARTIFACTMETADATA (artifact_id, size, last_access, ref_count) VALUES ('artifact_id', 11, 0, 1)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let metadata_index = MetadataIndex::open(index_path, &backend).unwrap();
+        metadata_index.record_local_pull("artifact_id").unwrap();
+
+        let metadata = metadata_index.get("artifact_id").unwrap().unwrap();
+        assert_eq!(metadata.local_pulls, 1);
+        assert_eq!(metadata.stored_size, 11);
+        assert_eq!(metadata.blob_id, "artifact_id");
+        assert_eq!(metadata_index.total_size().unwrap(), 11);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
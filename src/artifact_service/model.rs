@@ -14,6 +14,9 @@
    limitations under the License.
 */
 
+use crate::artifact_service::trust_store::TrustRole;
+use crate::network::signed_document::{Signed, SignedDocument};
+use anyhow::bail;
 use rusqlite::types::ToSqlOutput;
 use rusqlite::ToSql;
 use serde::{Deserialize, Serialize};
@@ -33,6 +36,37 @@ use serde::{Deserialize, Serialize};
 pub enum PackageType {
     Docker,
     Maven2,
+    /// Files that don't belong to a package ecosystem, pushed directly to a
+    /// node under a name chosen by the user.
+    Generic,
+}
+
+impl PackageType {
+    /// The repository that an artifact of this package type belongs to, e.g.
+    /// `library/alpine` for the Docker artifact `library/alpine@sha256:...`.
+    pub fn repository<'a>(&self, package_specific_artifact_id: &'a str) -> &'a str {
+        match self {
+            PackageType::Docker => {
+                let name = package_specific_artifact_id
+                    .split('@')
+                    .next()
+                    .unwrap_or_default();
+                match name.rfind(':') {
+                    Some(index) if !name[index..].contains('/') => &name[..index],
+                    _ => name,
+                }
+            }
+            // a maven artifact id consists of the group, artifact, version and file name
+            PackageType::Maven2 => match package_specific_artifact_id.match_indices('/').nth(1) {
+                Some((index, _)) => &package_specific_artifact_id[..index],
+                None => package_specific_artifact_id,
+            },
+            PackageType::Generic => match package_specific_artifact_id.rfind('/') {
+                Some(index) => &package_specific_artifact_id[..index],
+                None => package_specific_artifact_id,
+            },
+        }
+    }
 }
 
 impl ToSql for PackageType {
@@ -40,3 +74,311 @@ impl ToSql for PackageType {
         Ok(ToSqlOutput::from(self.to_string()))
     }
 }
+
+/// The role of a node, which determines the operations it performs in the
+/// network. Every role includes the operations of the roles before it.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum NodeRole {
+    /// Retrieves, caches and serves artifacts.
+    #[default]
+    Regular,
+    /// Builds artifacts from source and publishes the verified builds.
+    Build,
+    /// Authorizes other nodes to build and publish artifacts.
+    Authority,
+}
+
+impl NodeRole {
+    /// Whether a node with this role may perform the operations of `role`.
+    pub fn includes(&self, role: NodeRole) -> bool {
+        match self {
+            NodeRole::Regular => role == NodeRole::Regular,
+            NodeRole::Build => role != NodeRole::Authority,
+            NodeRole::Authority => true,
+        }
+    }
+}
+
+/// The usage statistics of the artifacts of one repository, e.g. the Docker
+/// repository `library/alpine`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct RepositoryUsage {
+    /// The package type of the repository, unknown for artifacts that are
+    /// not associated with a package.
+    pub package_type: Option<PackageType>,
+    /// The repository, unknown for artifacts that are not associated with a package.
+    #[serde(default)]
+    pub repository: Option<String>,
+    pub artifacts: u64,
+    pub size_bytes: u64,
+    /// The number of times artifacts were pulled by local package managers.
+    pub local_pulls: u64,
+    /// The number of times artifacts were served to other peers.
+    pub peer_serves: u64,
+}
+
+/// An artifact that was moved into the quarantine because it failed verification.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct QuarantinedArtifact {
+    pub artifact_id: String,
+    /// The key under which the quarantined bytes are kept in the storage backend.
+    pub quarantine_key: String,
+    /// Why the artifact was quarantined, e.g. the hash mismatch that was found.
+    pub reason: String,
+    /// The time the artifact was quarantined, in seconds since the epoch.
+    pub quarantined_at: u64,
+}
+
+/// The results of the last integrity scrub of the artifacts in the local storage.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ScrubReport {
+    /// The time at which the scrub finished, in seconds since the unix epoch.
+    pub finished_at: u64,
+    pub artifacts_checked: u64,
+    pub artifacts_corrupted: u64,
+    pub artifacts_refetched: u64,
+    /// Artifacts that could not be verified because no transparency log was found.
+    pub artifacts_unverifiable: u64,
+}
+
+/// A peer that signed or recorded something that was verified, and what
+/// the local trust store trusts it for.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct VerifiedSigner {
+    pub peer_id: String,
+    /// What the peer signed, e.g. the document, an attestation or a
+    /// transparency log entry.
+    pub signed: String,
+    pub valid: bool,
+    pub trusted_roles: Vec<TrustRole>,
+}
+
+/// The outcome of verifying a signed document or the evidence about an
+/// artifact against the local trust store and admission policy.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct VerificationReport {
+    /// Whether every signature is valid and, for an artifact, whether it
+    /// has a transparency log and is not revoked.
+    pub valid: bool,
+    pub signers: Vec<VerifiedSigner>,
+    pub transparency_logs: usize,
+    /// The reason the artifact was revoked, if it was.
+    pub revocation: Option<String>,
+    /// The reason the admission policy would refuse the artifact, if any.
+    pub policy_violation: Option<String>,
+}
+
+/// A package of an ecosystem, like a docker image repository or a maven
+/// artifact, independent of its versions.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Package {
+    pub package_type: PackageType,
+    /// The name of the package, e.g. `library/alpine` or `com.company:library`.
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl Signed for Package {
+    fn key(&self) -> String {
+        package_key(self.package_type, &self.name)
+    }
+}
+
+/// A version of a package, which maps the version to the artifacts it consists of.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct PackageVersion {
+    pub package_type: PackageType,
+    pub name: String,
+    /// The version of the package, e.g. a docker tag or a maven version.
+    pub version: String,
+    pub artifacts: Vec<ArtifactReference>,
+}
+
+impl Signed for PackageVersion {
+    fn key(&self) -> String {
+        package_version_key(self.package_type, &self.name, &self.version)
+    }
+}
+
+/// A reference to an artifact by its content digest.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ArtifactReference {
+    /// The digest of the artifact, e.g. `sha256:...`.
+    pub artifact_digest: String,
+    pub media_type: Option<String>,
+}
+
+impl Signed for ArtifactReference {
+    fn key(&self) -> String {
+        format!("artifacts/{}", self.artifact_digest)
+    }
+}
+
+/// Signed package metadata as it is committed to the blockchain, the ordered
+/// ledger through which all nodes converge on the same published metadata.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct MetadataBlockPayload {
+    pub metadata_key: String,
+    pub document: SignedDocument,
+}
+
+impl MetadataBlockPayload {
+    /// Verifies the signature of the document and that the metadata key is
+    /// the one derived from the signed content, so a document can't be
+    /// replayed under the key of other metadata.
+    pub fn verify_key(&self) -> anyhow::Result<()> {
+        let key = signed_metadata_key(&self.metadata_key, &self.document)?;
+        if key != self.metadata_key {
+            bail!(
+                "The signed metadata under {} belongs under {}",
+                self.metadata_key,
+                key
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The key of the signed package metadata in the document, which is derived
+/// from its content. The key that the document was stored or found under
+/// selects the kind of metadata that the content is read as.
+pub fn signed_metadata_key(key: &str, document: &SignedDocument) -> anyhow::Result<String> {
+    if key.starts_with("artifacts/") {
+        Ok(ArtifactReference::from_signed_document(document)?.key())
+    } else if key.contains("/versions/") {
+        Ok(PackageVersion::from_signed_document(document)?.key())
+    } else {
+        Ok(Package::from_signed_document(document)?.key())
+    }
+}
+
+/// The key of the signed metadata of a package.
+pub fn package_key(package_type: PackageType, name: &str) -> String {
+    format!("packages/{}/{}", package_type, name)
+}
+
+/// The key of the signed metadata of a version of a package.
+pub fn package_version_key(package_type: PackageType, name: &str, version: &str) -> String {
+    format!("{}/versions/{}", package_key(package_type, name), version)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_node_role() {
+        assert_eq!(NodeRole::from_str("build").unwrap(), NodeRole::Build);
+        assert_eq!(NodeRole::Authority.to_string(), "authority");
+        assert!(NodeRole::Authority.includes(NodeRole::Build));
+        assert!(NodeRole::Build.includes(NodeRole::Regular));
+        assert!(!NodeRole::Build.includes(NodeRole::Authority));
+        assert!(!NodeRole::Regular.includes(NodeRole::Build));
+    }
+
+    #[test]
+    fn test_package_type_repository() {
+        assert_eq!(
+            PackageType::Docker.repository("library/alpine@sha256:1234"),
+            "library/alpine"
+        );
+        assert_eq!(
+            PackageType::Docker.repository("library/alpine:3.16"),
+            "library/alpine"
+        );
+        assert_eq!(
+            PackageType::Docker.repository("localhost:5000/alpine"),
+            "localhost:5000/alpine"
+        );
+        assert_eq!(
+            PackageType::Maven2.repository("com.company/artifact/1.8/artifact-1.8.pom"),
+            "com.company/artifact"
+        );
+        assert_eq!(PackageType::Generic.repository("tools/cli.tar.gz"), "tools");
+    }
+
+    #[test]
+    fn test_signed_package_version() {
+        let keypair = Keypair::generate_ed25519();
+        let package_version = PackageVersion {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            version: String::from("3.17"),
+            artifacts: vec![ArtifactReference {
+                artifact_digest: String::from(
+                    "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a",
+                ),
+                media_type: None,
+            }],
+        };
+
+        let mut document = package_version.sign(&keypair).unwrap();
+
+        assert_eq!(
+            package_version.key(),
+            "packages/Docker/library/alpine/versions/3.17"
+        );
+        assert_eq!(
+            PackageVersion::from_signed_document(&document).unwrap(),
+            package_version
+        );
+
+        document.content = document.content.replace("3.17", "3.18");
+        assert!(PackageVersion::from_signed_document(&document).is_err());
+    }
+
+    #[test]
+    fn test_metadata_block_payload_key() {
+        let keypair = Keypair::generate_ed25519();
+        let package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            description: None,
+        };
+        let package_version = PackageVersion {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            version: String::from("3.17"),
+            artifacts: vec![],
+        };
+
+        let payload = MetadataBlockPayload {
+            metadata_key: package.key(),
+            document: package.sign(&keypair).unwrap(),
+        };
+        assert!(payload.verify_key().is_ok());
+        let payload = MetadataBlockPayload {
+            metadata_key: package_version.key(),
+            document: package_version.sign(&keypair).unwrap(),
+        };
+        assert!(payload.verify_key().is_ok());
+
+        // A document that is replayed under the key of other metadata.
+        let payload = MetadataBlockPayload {
+            metadata_key: package_key(PackageType::Docker, "library/busybox"),
+            document: package.sign(&keypair).unwrap(),
+        };
+        assert!(payload.verify_key().is_err());
+        let payload = MetadataBlockPayload {
+            metadata_key: package.key(),
+            document: package_version.sign(&keypair).unwrap(),
+        };
+        assert!(payload.verify_key().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::model::PackageType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// The reasons for which the admission policy refuses to serve an artifact.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[error("Artifact {artifact} is denied by policy: a transparency log is required")]
+    MissingTransparencyLog { artifact: String },
+    #[error("Artifact {artifact} is denied by policy: {required} independent verifications are required, found {found}")]
+    InsufficientVerifications {
+        artifact: String,
+        required: usize,
+        found: usize,
+    },
+}
+
+/// A rule of the admission policy, which applies to the artifacts of the
+/// matching package type and namespace.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct PolicyRule {
+    /// The package type the rule applies to, all package types when not set.
+    pub package_type: Option<PackageType>,
+    /// The prefix of the package specific artifact ids the rule applies to,
+    /// e.g. `library/`. All artifacts when not set.
+    pub namespace: Option<String>,
+    /// Only serve artifacts that have a valid transparency log.
+    pub require_transparency_log: bool,
+    /// The number of independent nodes that must have verified the artifact,
+    /// either by building it or by signing an attestation about it.
+    pub min_verifications: usize,
+    /// Only count the verifications of nodes whose keys are in the trust
+    /// store with the `verification` role.
+    pub trusted_verifiers_only: bool,
+}
+
+impl PolicyRule {
+    fn applies_to(
+        &self,
+        package_type: Option<PackageType>,
+        package_specific_artifact_id: &str,
+    ) -> bool {
+        self.package_type.map_or(true, |rule_package_type| {
+            package_type == Some(rule_package_type)
+        }) && self.namespace.as_deref().map_or(true, |namespace| {
+            package_specific_artifact_id.starts_with(namespace)
+        })
+    }
+}
+
+/// What is known about an artifact when the policy is evaluated.
+#[derive(Debug, Default)]
+pub struct Evidence {
+    pub has_transparency_log: bool,
+    /// The ids of the nodes that independently verified the artifact.
+    pub verifiers: HashSet<String>,
+    /// The ids of the nodes that are trusted to verify artifacts.
+    pub trusted_verifiers: HashSet<String>,
+}
+
+impl Evidence {
+    fn verification_count(&self, trusted_verifiers_only: bool) -> usize {
+        if trusted_verifiers_only {
+            self.verifiers.intersection(&self.trusted_verifiers).count()
+        } else {
+            self.verifiers.len()
+        }
+    }
+}
+
+/// The admission policy declares which artifacts a node is allowed to
+/// serve, to local package managers and to other peers. A policy without
+/// rules admits every artifact.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    /// Read the policy from a TOML file with a `[[rules]]` table per rule.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Check the evidence about an artifact against every rule that applies to it.
+    pub fn check(
+        &self,
+        package_type: Option<PackageType>,
+        package_specific_artifact_id: &str,
+        evidence: &Evidence,
+    ) -> Result<(), PolicyViolation> {
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.applies_to(package_type, package_specific_artifact_id))
+        {
+            if rule.require_transparency_log && !evidence.has_transparency_log {
+                return Err(PolicyViolation::MissingTransparencyLog {
+                    artifact: package_specific_artifact_id.to_owned(),
+                });
+            }
+            let verification_count = evidence.verification_count(rule.trusted_verifiers_only);
+            if verification_count < rule.min_verifications {
+                return Err(PolicyViolation::InsufficientVerifications {
+                    artifact: package_specific_artifact_id.to_owned(),
+                    required: rule.min_verifications,
+                    found: verification_count,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_from_toml() {
+        let policy: Policy = toml::from_str(
+            r#"
+            [[rules]]
+            require_transparency_log = true
+
+            [[rules]]
+            package_type = "Docker"
+            namespace = "library/"
+            min_verifications = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.rules,
+            vec![
+                PolicyRule {
+                    require_transparency_log: true,
+                    ..Default::default()
+                },
+                PolicyRule {
+                    package_type: Some(PackageType::Docker),
+                    namespace: Some(String::from("library/")),
+                    min_verifications: 2,
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let policy = Policy {
+            rules: vec![PolicyRule {
+                package_type: Some(PackageType::Docker),
+                namespace: Some(String::from("library/")),
+                require_transparency_log: true,
+                min_verifications: 2,
+                ..Default::default()
+            }],
+        };
+        let mut evidence = Evidence {
+            has_transparency_log: true,
+            verifiers: HashSet::from([String::from("node_1")]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.check(Some(PackageType::Docker), "library/alpine:3.17", &evidence),
+            Err(PolicyViolation::InsufficientVerifications {
+                artifact: String::from("library/alpine:3.17"),
+                required: 2,
+                found: 1
+            })
+        );
+        assert!(policy
+            .check(Some(PackageType::Docker), "company/image:1.0", &evidence)
+            .is_ok());
+        assert!(policy
+            .check(Some(PackageType::Maven2), "library/alpine:3.17", &evidence)
+            .is_ok());
+
+        evidence.verifiers.insert(String::from("node_2"));
+        assert!(policy
+            .check(Some(PackageType::Docker), "library/alpine:3.17", &evidence)
+            .is_ok());
+
+        evidence.has_transparency_log = false;
+        assert_eq!(
+            policy.check(Some(PackageType::Docker), "library/alpine:3.17", &evidence),
+            Err(PolicyViolation::MissingTransparencyLog {
+                artifact: String::from("library/alpine:3.17"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_policy_check_trusted_verifiers_only() {
+        let policy = Policy {
+            rules: vec![PolicyRule {
+                min_verifications: 1,
+                trusted_verifiers_only: true,
+                ..Default::default()
+            }],
+        };
+        let mut evidence = Evidence {
+            has_transparency_log: true,
+            verifiers: HashSet::from([String::from("node_1")]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.check(None, "library/alpine:3.17", &evidence),
+            Err(PolicyViolation::InsufficientVerifications {
+                artifact: String::from("library/alpine:3.17"),
+                required: 1,
+                found: 0
+            })
+        );
+
+        evidence.trusted_verifiers.insert(String::from("node_1"));
+        assert!(policy.check(None, "library/alpine:3.17", &evidence).is_ok());
+    }
+}
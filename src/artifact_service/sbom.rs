@@ -0,0 +1,192 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::hashing::{ArtifactDigest, DigestError, HashAlgorithm};
+use crate::network::signed_document::SignedDocument;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+/// The media type of OCI image manifests, used for the referrer manifests of SBOMs.
+pub const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// The media type of the empty config of OCI artifact manifests.
+pub const OCI_EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+/// The digest of the empty config `{}` of OCI artifact manifests.
+pub const OCI_EMPTY_DIGEST: &str =
+    "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a";
+/// The content of the empty config of OCI artifact manifests.
+pub const OCI_EMPTY_CONTENT: &str = "{}";
+/// The base64 encoded empty config, embedded in the referrer manifests so
+/// that clients never need to fetch it as a blob.
+pub const OCI_EMPTY_DATA: &str = "e30=";
+
+#[derive(Debug, Error)]
+pub enum SbomError {
+    #[error("Invalid artifact digest: {0}")]
+    InvalidArtifactDigest(#[from] DigestError),
+    #[error("SBOM is not a valid JSON document: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("SBOM is neither an SPDX nor a CycloneDX JSON document")]
+    UnknownFormat,
+}
+
+/// The formats of the software bills of materials that can be attached to artifacts.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    /// Detect the format of an SBOM from its JSON content.
+    pub fn detect(content: &str) -> Result<Self, SbomError> {
+        let document: serde_json::Value = serde_json::from_str(content)?;
+        if document.get("spdxVersion").is_some() {
+            Ok(SbomFormat::Spdx)
+        } else if document.get("bomFormat").and_then(|format| format.as_str()) == Some("CycloneDX")
+        {
+            Ok(SbomFormat::CycloneDx)
+        } else {
+            Err(SbomError::UnknownFormat)
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "application/spdx+json",
+            SbomFormat::CycloneDx => "application/vnd.cyclonedx+json",
+        }
+    }
+}
+
+/// A software bill of materials that is attached to an artifact. The SBOM
+/// is kept as a document that is signed by the node that stored it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Sbom {
+    /// The digest of the artifact that the SBOM describes, e.g. `sha256:...`.
+    pub artifact_digest: String,
+    pub format: SbomFormat,
+    /// The digest of the content of the SBOM.
+    pub digest: String,
+    pub document: SignedDocument,
+}
+
+impl Sbom {
+    pub fn new(
+        artifact_digest: &ArtifactDigest,
+        format: SbomFormat,
+        document: SignedDocument,
+    ) -> Self {
+        Sbom {
+            artifact_digest: artifact_digest.to_string(),
+            format,
+            digest: ArtifactDigest::calculate(HashAlgorithm::Sha256, document.content.as_bytes())
+                .to_string(),
+            document,
+        }
+    }
+
+    /// The OCI artifact manifest through which the SBOM is exposed as a
+    /// referrer of the artifact it describes. The SBOM is its only layer.
+    pub fn referrer_manifest(&self) -> Vec<u8> {
+        let manifest = json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+            "artifactType": self.format.media_type(),
+            "config": {
+                "mediaType": OCI_EMPTY_MEDIA_TYPE,
+                "digest": OCI_EMPTY_DIGEST,
+                "size": OCI_EMPTY_CONTENT.len(),
+                "data": OCI_EMPTY_DATA,
+            },
+            "layers": [{
+                "mediaType": self.format.media_type(),
+                "digest": self.digest,
+                "size": self.document.content.len(),
+            }],
+        });
+        serde_json::to_vec(&manifest).unwrap()
+    }
+
+    /// The digest of the referrer manifest of the SBOM.
+    pub fn referrer_manifest_digest(&self) -> String {
+        ArtifactDigest::calculate(HashAlgorithm::Sha256, &self.referrer_manifest()).to_string()
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+    use std::str::FromStr;
+
+    const CYCLONEDX_SBOM: &str = r#"{"bomFormat":"CycloneDX","specVersion":"1.4","components":[]}"#;
+
+    #[test]
+    fn test_detect_sbom_format() {
+        assert_eq!(
+            SbomFormat::detect(r#"{"spdxVersion":"SPDX-2.3","packages":[]}"#).unwrap(),
+            SbomFormat::Spdx
+        );
+        assert_eq!(
+            SbomFormat::detect(CYCLONEDX_SBOM).unwrap(),
+            SbomFormat::CycloneDx
+        );
+        assert!(matches!(
+            SbomFormat::detect(r#"{"schemaVersion":2}"#),
+            Err(SbomError::UnknownFormat)
+        ));
+        assert!(matches!(
+            SbomFormat::detect("not json"),
+            Err(SbomError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_referrer_manifest() {
+        let artifact_digest = ArtifactDigest::from_str(OCI_EMPTY_DIGEST).unwrap();
+        let document = SignedDocument::new(&Keypair::generate_ed25519(), CYCLONEDX_SBOM).unwrap();
+        let sbom = Sbom::new(&artifact_digest, SbomFormat::CycloneDx, document);
+
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&sbom.referrer_manifest()).unwrap();
+        assert_eq!(manifest["artifactType"], "application/vnd.cyclonedx+json");
+        assert_eq!(manifest["layers"][0]["digest"], sbom.digest.as_str());
+        assert_eq!(manifest["layers"][0]["size"], CYCLONEDX_SBOM.len());
+        assert_eq!(
+            sbom.referrer_manifest_digest(),
+            sbom.referrer_manifest_digest()
+        );
+        assert_eq!(
+            OCI_EMPTY_DIGEST,
+            format!(
+                "sha256:{}",
+                HashAlgorithm::Sha256.hash(OCI_EMPTY_CONTENT.as_bytes())
+            )
+        );
+    }
+}
@@ -14,25 +14,58 @@
    limitations under the License.
 */
 
-use super::model::PackageType;
-use super::storage::ArtifactStorage;
+use super::archive::{self, ImportedArchive};
+use super::attestation::{Attestation, Envelope};
+use super::audit_log::{AuditAction, AuditLog};
+use super::events::{NodeEventKind, NodeEvents};
+use super::federation::{FederationSync, FederationSyncQuery, FEDERATION_SYNC_PAGE_SIZE};
+use super::hashing::{calculate_hash_like, ArtifactDigest, HashAlgorithm};
+use super::model::{
+    MetadataBlockPayload, NodeRole, PackageType, ScrubReport, VerificationReport, VerifiedSigner,
+};
+use super::policy::{Evidence, Policy, PolicyViolation};
+use super::sbom::{Sbom, SbomFormat};
+use super::storage::{ArtifactStorage, StorageError};
+use super::trust_store::{TrustRole, TrustStore};
 use crate::blockchain_service::event::BlockchainEventClient;
 use crate::build_service::error::BuildError;
 use crate::build_service::event::BuildEventClient;
 use crate::build_service::model::BuildResult;
+use crate::network::artifact_protocol::PeerBusy;
 use crate::network::client::Client;
+use crate::network::fair_share::FairShare;
+use crate::network::public_keys::{
+    public_key_record_key, Jwk, Jwks, PublicKeyCache, PublicKeyRecord,
+};
+use crate::network::signed_document::{public_key_of, Signed, SignedDocument};
+use crate::node_api::api_tokens::ApiTokens;
+use crate::peer_metrics::history::MetricsHistory;
+use crate::peer_metrics::load_shedding::LoadShedding;
+use crate::transparency_log::key_rotation::{
+    KeyRotation, KeyRotationBlockPayload, KeyRotationCertificate,
+};
 use crate::transparency_log::log::{
-    AddArtifactRequest, TransparencyLog, TransparencyLogError, TransparencyLogService,
+    AddArtifactRequest, Operation, Revocation, RevocationBlockPayload, TransparencyLog,
+    TransparencyLogError, TransparencyLogService,
 };
-use anyhow::{bail, Context};
+use crate::util::rate_limit::RateLimits;
+use anyhow::{anyhow, bail, Context};
 use itertools::Itertools;
 use libp2p::PeerId;
-use log::{debug, info, warn};
-use multihash::Hasher;
+use log::{debug, error, info, warn};
+use pyrsia_blockchain_network::structures::header::Address;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read, Write};
 use std::path::Path;
-use std::str;
+use std::str::{self, FromStr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{instrument, Instrument};
+
+/// How long a provider may take to answer whether it has an artifact.
+const AVAILABILITY_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// The artifact service is the component that handles everything related to
 /// pyrsia artifacts. It allows artifacts to be retrieved and added to the
@@ -40,9 +73,43 @@ use std::str;
 #[derive(Clone)]
 pub struct ArtifactService {
     pub artifact_storage: ArtifactStorage,
+    blockchain_event_client: BlockchainEventClient,
     build_event_client: BuildEventClient,
     pub transparency_log_service: TransparencyLogService,
     pub p2p_client: Client,
+    /// The number of other peers an artifact is pushed to after it was
+    /// built on this node. Replication is disabled when set to 0.
+    pub replication_factor: usize,
+    /// Whether artifacts read from the local storage are verified against
+    /// their transparency log before they are returned. Artifacts that are
+    /// served to other peers are always verified.
+    pub verify_on_read: bool,
+    /// The peers whose keys are trusted to sign in-toto attestations.
+    pub trusted_attestation_keys: Vec<PeerId>,
+    /// The admission policy that decides which artifacts are served,
+    /// shared by all clones so that it can be replaced while the node runs.
+    policy: Arc<RwLock<Policy>>,
+    /// The role the node is configured with, see `has_role`.
+    pub role: NodeRole,
+    /// The append-only log of the artifact and administrative operations.
+    pub audit_log: AuditLog,
+    /// The activity of the node, for subscribers like the event stream.
+    pub events: NodeEvents,
+    /// The keys that are trusted to sign attestations and verify artifacts.
+    pub trust_store: TrustStore,
+    pub api_tokens: ApiTokens,
+    pub rate_limits: RateLimits,
+    /// Refuses artifact requests while the node is overloaded.
+    pub load_shedding: LoadShedding,
+    /// Throttles peers that are served a disproportionate share.
+    pub fair_share: FairShare,
+    /// The metrics of the node over the last day.
+    pub metrics_history: MetricsHistory,
+    /// The public keys of other peers that were looked up in the DHT.
+    pub public_key_cache: PublicKeyCache,
+    last_scrub_report: Arc<Mutex<Option<ScrubReport>>>,
+    build_fallbacks: Arc<AtomicU64>,
+    started_at: Instant,
 }
 
 impl ArtifactService {
@@ -53,14 +120,53 @@ impl ArtifactService {
         p2p_client: Client,
     ) -> anyhow::Result<Self> {
         let artifact_storage = ArtifactStorage::new(&artifact_path)?;
+        Self::new_with_storage(
+            artifact_path,
+            artifact_storage,
+            blockchain_event_client,
+            build_event_client,
+            p2p_client,
+        )
+    }
+
+    /// Create an artifact service that keeps the artifacts in the specified
+    /// storage, instead of in the artifact path itself.
+    pub fn new_with_storage<P: AsRef<Path>>(
+        artifact_path: P,
+        artifact_storage: ArtifactStorage,
+        blockchain_event_client: BlockchainEventClient,
+        build_event_client: BuildEventClient,
+        p2p_client: Client,
+    ) -> anyhow::Result<Self> {
+        let audit_log = AuditLog::new(artifact_path.as_ref().join("audit.log"));
+        let trust_store = TrustStore::new(artifact_path.as_ref().join("trust_store.json"));
+        let api_tokens = ApiTokens::new(artifact_path.as_ref().join("api_tokens.json"));
         Ok(ArtifactService {
             artifact_storage,
+            blockchain_event_client: blockchain_event_client.clone(),
             build_event_client,
             transparency_log_service: TransparencyLogService::new(
                 artifact_path,
                 blockchain_event_client,
             )?,
             p2p_client,
+            replication_factor: 0,
+            verify_on_read: true,
+            trusted_attestation_keys: vec![],
+            policy: Arc::new(RwLock::new(Policy::default())),
+            role: NodeRole::default(),
+            audit_log,
+            events: NodeEvents::default(),
+            trust_store,
+            api_tokens,
+            rate_limits: RateLimits::default(),
+            load_shedding: LoadShedding::default(),
+            fair_share: FairShare::default(),
+            metrics_history: MetricsHistory::default(),
+            public_key_cache: PublicKeyCache::default(),
+            last_scrub_report: Arc::new(Mutex::new(None)),
+            build_fallbacks: Arc::new(AtomicU64::new(0)),
+            started_at: Instant::now(),
         })
     }
 
@@ -128,6 +234,7 @@ impl ArtifactService {
         build_id: &str,
         build_result: BuildResult,
     ) -> Result<(), anyhow::Error> {
+        self.ensure_role(NodeRole::Build)?;
         let package_specific_id = build_result.package_specific_id.as_str();
 
         info!(
@@ -164,17 +271,75 @@ impl ArtifactService {
             self.put_artifact_from_build_result(
                 &artifact.artifact_location,
                 &add_artifact_transparency_log.artifact_id,
+                &add_artifact_transparency_log.artifact_hash,
             )
             .await?;
+            // artifacts built on this node are never evicted from its storage
+            self.artifact_storage
+                .pin_artifact(&add_artifact_transparency_log.artifact_id)?;
+            self.associate_artifact(&add_artifact_transparency_log)?;
 
             self.p2p_client
                 .provide(&add_artifact_transparency_log.artifact_id)
                 .await?;
+
+            self.publish_name_record(&add_artifact_transparency_log);
+            self.replicate_artifact(&add_artifact_transparency_log);
         }
 
         Ok(())
     }
 
+    /// Publish a generic artifact under the specified name: record it in the
+    /// transparency log, store and pin it on this node and announce it to
+    /// the network.
+    pub async fn push_generic_artifact(
+        &mut self,
+        name: &str,
+        artifact: &[u8],
+    ) -> Result<TransparencyLog, anyhow::Error> {
+        self.ensure_role(NodeRole::Build)?;
+        self.transparency_log_service
+            .verify_package_can_be_added_to_transparency_logs(&PackageType::Generic, name)?;
+
+        let add_artifact_request = AddArtifactRequest {
+            package_type: PackageType::Generic,
+            package_specific_id: name.to_owned(),
+            num_artifacts: 1,
+            package_specific_artifact_id: name.to_owned(),
+            artifact_hash: HashAlgorithm::Sha256.hash(artifact),
+        };
+        info!("Pushing generic artifact: {:?}", add_artifact_request);
+
+        let transparency_log = self
+            .transparency_log_service
+            .add_artifact(add_artifact_request)
+            .await?;
+        self.transparency_log_service
+            .write_transparency_log(&transparency_log)?;
+
+        self.put_verified_artifact(
+            &mut BufReader::new(artifact),
+            artifact.len() as u64,
+            &transparency_log.artifact_id,
+            &transparency_log.artifact_hash,
+            "push",
+        )?;
+        // pushed artifacts are never evicted from the storage of their publisher
+        self.artifact_storage
+            .pin_artifact(&transparency_log.artifact_id)?;
+        self.associate_artifact(&transparency_log)?;
+
+        self.p2p_client
+            .provide(&transparency_log.artifact_id)
+            .await?;
+
+        self.publish_name_record(&transparency_log);
+        self.replicate_artifact(&transparency_log);
+
+        Ok(transparency_log)
+    }
+
     pub async fn get_build_status(&mut self, build_id: &str) -> Result<String, BuildError> {
         let local_peer_id = self.p2p_client.local_peer_id;
         debug!("Got local node with peer_id: {:?}", local_peer_id.clone());
@@ -216,6 +381,17 @@ impl ArtifactService {
         payloads: Vec<Vec<u8>>,
     ) -> Result<(), anyhow::Error> {
         if payloads.len() == 1 {
+            if let Ok(metadata) = serde_json::from_slice::<MetadataBlockPayload>(&payloads[0]) {
+                return self.apply_committed_metadata(metadata);
+            }
+            if let Ok(payload) = serde_json::from_slice::<RevocationBlockPayload>(&payloads[0]) {
+                return self.apply_revocation(&payload.revocation).await;
+            }
+            if let Ok(payload) = serde_json::from_slice::<KeyRotationBlockPayload>(&payloads[0]) {
+                self.transparency_log_service
+                    .write_key_rotation(&payload.key_rotation)?;
+                return Ok(());
+            }
             let transparency_log: TransparencyLog = serde_json::from_slice(&payloads[0])?;
             if let Err(TransparencyLogError::LogNotFound { .. }) = self
                 .transparency_log_service
@@ -229,286 +405,3411 @@ impl ArtifactService {
         Ok(())
     }
 
-    async fn put_artifact_from_build_result(
-        &self,
-        artifact_location: &Path,
-        artifact_id: &str,
-    ) -> Result<(), anyhow::Error> {
-        let artifact_file = File::open(artifact_location)?;
-        let mut artifact_reader = BufReader::new(artifact_file);
-        self.put_artifact(artifact_id, &mut artifact_reader)
+    /// Replace the admission policy of the node and all of its clones.
+    pub fn set_policy(&self, policy: Policy) {
+        *self.policy.write().unwrap() = policy;
     }
 
-    /// Given artifact_id & reader, push artifact to artifact_storage
-    fn put_artifact(&self, artifact_id: &str, reader: &mut impl Read) -> Result<(), anyhow::Error> {
-        info!("put_artifact with id: {}", artifact_id);
-        self.artifact_storage
-            .push_artifact(reader, artifact_id)
-            .context("Error from put_artifact")
+    /// Whether the local node may act in the specified role. Besides being
+    /// configured with the role, the node must be in the authority list that
+    /// is committed to the blockchain, unless no node was authorized yet.
+    pub fn has_role(&self, role: NodeRole) -> bool {
+        self.role.includes(role)
+            && (role == NodeRole::Regular || self.is_authorized(&self.p2p_client.local_peer_id))
     }
 
-    /// Retrieve the artifact data for the specified package. If the artifact
-    /// is not available locally, the service will try to fetch the artifact
-    /// from the p2p network.
-    pub async fn get_artifact(
-        &mut self,
-        package_type: PackageType,
-        package_specific_artifact_id: &str,
-    ) -> anyhow::Result<Vec<u8>> {
-        let transparency_log = self
-            .transparency_log_service
-            .get_artifact(&package_type, package_specific_artifact_id)?;
+    fn ensure_role(&self, role: NodeRole) -> anyhow::Result<()> {
+        if !self.has_role(role) {
+            bail!(
+                "This node is not authorized to act as a {} node (configured role: {})",
+                role,
+                self.role
+            );
+        }
+        Ok(())
+    }
 
-        let artifact = match self
-            .get_artifact_locally(&transparency_log.artifact_id)
-            .await
-        {
-            Ok(artifact) => Ok(artifact),
-            Err(_) => {
-                self.get_artifact_from_peers(&transparency_log.artifact_id)
-                    .await
+    /// Whether the specified node is in the authority list, or whether no
+    /// node was authorized yet while the network is being bootstrapped.
+    pub fn is_authorized(&self, peer_id: &PeerId) -> bool {
+        match self.transparency_log_service.get_authorized_nodes() {
+            Ok(nodes) => nodes.is_empty() || nodes.contains(peer_id),
+            Err(e) => {
+                warn!("Unable to read the authorized nodes: {}", e);
+                false
             }
-        }?;
-
-        self.verify_artifact(&transparency_log, &artifact).await?;
+        }
+    }
 
-        Ok(artifact)
+    /// Whether a block that was committed by the node with the specified
+    /// address is accepted. Only authorized nodes may commit blocks.
+    pub fn accepts_block_from(&self, committer: &Address) -> bool {
+        match self.transparency_log_service.get_authorized_nodes() {
+            Ok(nodes) => {
+                nodes.is_empty()
+                    || nodes
+                        .into_iter()
+                        .any(|peer_id| Address::from(peer_id) == *committer)
+            }
+            Err(e) => {
+                warn!("Unable to read the authorized nodes: {}", e);
+                false
+            }
+        }
     }
 
-    /// Retrieve the artifact data for the specified package. If the artifact
-    /// is not found, the service start a request to build it on an authorized
-    /// node.
-    pub async fn get_artifact_or_build(
+    /// Revoke the artifacts with the specified hash, e.g. because they turned
+    /// out to be malicious. The revocation is signed by the local node and
+    /// committed to the blockchain, so that all nodes stop serving them.
+    pub async fn revoke_artifact(
         &mut self,
-        package_type: PackageType,
-        package_specific_id: &str,
-        package_specific_artifact_id: &str,
-    ) -> anyhow::Result<Vec<u8>> {
-        self.get_artifact(package_type, package_specific_artifact_id).await.map_err(|e| {
-                warn!("Error looking for artifact: {:?}. A new build will be started. Try again later", e);
-                let new_artifact_service = self.clone();
-                let new_package_specific_id = package_specific_id.to_string();
-                tokio::spawn(async move {
-                    debug!("Spawning a build...");
-                    let build_result = new_artifact_service.clone().request_build(package_type, new_package_specific_id).await;
-                    debug!("Build result {:?}", build_result);
-                });
-                // in any case, return the error
-                e
-            })
+        artifact_hash: &str,
+        reason: &str,
+    ) -> anyhow::Result<Revocation> {
+        self.ensure_role(NodeRole::Authority)?;
+        let revocation = Revocation {
+            artifact_hash: artifact_hash.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let document = self
+            .p2p_client
+            .sign_document(&serde_json::to_string(&revocation)?)
+            .await?;
+        self.transparency_log_service
+            .revoke_artifact(&document)
+            .await?;
+        self.audit_log.record(
+            AuditAction::Admin,
+            artifact_hash,
+            Some(format!("revoked: {}", reason)),
+        );
+        self.quarantine_revoked_artifacts(&revocation).await?;
+        Ok(revocation)
     }
 
-    /// Retrieve the artifact data specified by `artifact_id` from the local storage.
-    pub async fn get_artifact_locally(
-        &mut self,
-        artifact_id: &str,
-    ) -> Result<Vec<u8>, anyhow::Error> {
-        let artifact = self.artifact_storage.pull_artifact(artifact_id)?;
-        let mut buf_reader = BufReader::new(artifact);
-        let mut blob_content = Vec::new();
-        buf_reader.read_to_end(&mut blob_content)?;
-        Ok(blob_content)
+    // Apply a revocation that was committed to the blockchain. Only
+    // revocations issued by authorized nodes are accepted.
+    async fn apply_revocation(&mut self, document: &SignedDocument) -> anyhow::Result<()> {
+        match document.publisher() {
+            Some(publisher) if self.is_authorized(&publisher) => {}
+            _ => bail!("Revocation was not issued by an authorized node"),
+        }
+        let revocation = self.transparency_log_service.write_revocation(document)?;
+        warn!(
+            "Artifact hash {} was revoked: {}",
+            revocation.artifact_hash, revocation.reason
+        );
+        self.quarantine_revoked_artifacts(&revocation).await
     }
 
-    /// Retrieve the artifact logs for the specified package.
-    pub async fn get_logs_for_artifact(
+    // Move the locally stored artifacts with a revoked hash to quarantine and
+    // stop providing them on the p2p network.
+    async fn quarantine_revoked_artifacts(
         &mut self,
-        package_type: PackageType,
-        package_specific_id: &str,
-    ) -> anyhow::Result<Vec<TransparencyLog>> {
+        revocation: &Revocation,
+    ) -> anyhow::Result<()> {
         let transparency_logs = self
             .transparency_log_service
-            .search_transparency_logs(&package_type, package_specific_id)?;
-
-        Ok(transparency_logs)
-    }
-
-    pub async fn provide_local_artifacts(&self) -> anyhow::Result<()> {
-        for path in self.artifact_storage.list_artifacts()? {
-            if let Some(artifact_id) = path.file_stem() {
-                debug!("Providing artifact_id: {:?}", artifact_id);
-                self.p2p_client
-                    .clone()
-                    .provide(artifact_id.to_str().expect("error getting artifact_id"))
-                    .await?
+            .find_transparency_logs_by_artifact_hash(&revocation.artifact_hash)?;
+        for transparency_log in transparency_logs
+            .iter()
+            .filter(|transparency_log| transparency_log.operation == Operation::AddArtifact)
+        {
+            if self
+                .artifact_storage
+                .artifact_size(&transparency_log.artifact_id)
+                .is_err()
+            {
+                continue;
             }
+            let reason = format!("Artifact was revoked: {}", revocation.reason);
+            self.artifact_storage
+                .quarantine_artifact(&transparency_log.artifact_id, &reason)?;
+            self.audit_log.record(
+                AuditAction::Quarantine,
+                &transparency_log.artifact_id,
+                Some(reason),
+            );
+            self.p2p_client
+                .stop_providing(&transparency_log.artifact_id)
+                .await?;
         }
         Ok(())
     }
 
-    async fn get_artifact_from_peers(
-        &mut self,
-        artifact_id: &str,
-    ) -> Result<Vec<u8>, anyhow::Error> {
-        let providers = self.p2p_client.list_providers(artifact_id).await?;
+    /// Publish the rotation of a key to its successor. Documents signed by the
+    /// retired key remain verifiable, and the successor of an authorized key
+    /// is authorized as well.
+    pub async fn add_key_rotation(
+        &self,
+        certificate: &KeyRotationCertificate,
+    ) -> anyhow::Result<KeyRotation> {
+        let key_rotation = self
+            .transparency_log_service
+            .add_key_rotation(certificate)
+            .await?;
+        self.audit_log.record(
+            AuditAction::Admin,
+            &key_rotation.previous_key,
+            Some(format!("rotated key to {}", key_rotation.next_key)),
+        );
+        Ok(key_rotation)
+    }
 
-        match self.p2p_client.get_idle_peer(providers).await? {
-            Some(peer_id) => self.get_artifact_from_peer(&peer_id, artifact_id).await,
-            None => {
-                bail!(
-                    "Artifact with id {} is not available on the p2p network.",
-                    artifact_id
-                )
-            }
+    /// The revocation list: all signed revocations known to this node.
+    pub fn revocations(&self) -> anyhow::Result<Vec<SignedDocument>> {
+        Ok(self.transparency_log_service.find_revocations()?)
+    }
+
+    /// Evict the least recently used artifacts from the local storage when
+    /// it approaches its allocated disk space, and stop providing them.
+    /// Returns the ids of the evicted artifacts.
+    pub async fn collect_garbage(&mut self) -> Result<Vec<String>, anyhow::Error> {
+        let evicted_artifact_ids = self.artifact_storage.collect_garbage()?;
+        for artifact_id in evicted_artifact_ids.iter() {
+            self.artifact_evicted(artifact_id, None).await?;
         }
+
+        Ok(evicted_artifact_ids)
     }
 
-    async fn get_artifact_from_peer(
+    /// Evict the locally stored artifacts with the specified hash on request
+    /// of an operator, also when they are pinned, and stop providing them.
+    /// Returns the ids of the evicted artifacts.
+    pub async fn evict_artifacts_by_hash(
         &mut self,
-        peer_id: &PeerId,
-        artifact_id: &str,
-    ) -> Result<Vec<u8>, anyhow::Error> {
-        let artifact = self
-            .p2p_client
-            .request_artifact(peer_id, artifact_id)
+        artifact_hash: &str,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let transparency_logs = self
+            .transparency_log_service
+            .find_transparency_logs_by_artifact_hash(artifact_hash)?;
+        let mut evicted_artifact_ids = vec![];
+        for transparency_log in transparency_logs
+            .iter()
+            .filter(|transparency_log| transparency_log.operation == Operation::AddArtifact)
+        {
+            match self
+                .artifact_storage
+                .evict_artifact(&transparency_log.artifact_id)
+            {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+            self.artifact_evicted(
+                &transparency_log.artifact_id,
+                Some(String::from("evicted by operator")),
+            )
             .await?;
+            evicted_artifact_ids.push(transparency_log.artifact_id.clone());
+        }
 
-        let mut buf_reader = BufReader::new(artifact.as_slice());
+        Ok(evicted_artifact_ids)
+    }
 
-        self.put_artifact(artifact_id, &mut buf_reader)?;
-        self.get_artifact_locally(artifact_id).await
+    // Record the eviction of an artifact and stop providing it.
+    async fn artifact_evicted(
+        &mut self,
+        artifact_id: &str,
+        details: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.audit_log
+            .record(AuditAction::Evict, artifact_id, details);
+        self.events.publish(NodeEventKind::ArtifactEvicted {
+            artifact_id: artifact_id.to_owned(),
+        });
+        self.p2p_client.stop_providing(artifact_id).await
     }
 
-    async fn verify_artifact(
+    /// Attach an SBOM in SPDX or CycloneDX JSON format to the artifact with
+    /// the specified digest. The SBOM is signed by the local node.
+    pub async fn attach_sbom(
         &mut self,
-        transparency_log: &TransparencyLog,
-        artifact: &[u8],
-    ) -> Result<(), TransparencyLogError> {
-        let mut sha256 = multihash::Sha2_256::default();
-        sha256.update(artifact);
-        let calculated_hash = hex::encode(sha256.finalize());
+        artifact_digest: &str,
+        content: &str,
+    ) -> anyhow::Result<Sbom> {
+        let artifact_digest = ArtifactDigest::from_str(artifact_digest)?;
+        let format = SbomFormat::detect(content)?;
+        let document = self.p2p_client.sign_document(content).await?;
+
+        let sbom = Sbom::new(&artifact_digest, format, document);
+        self.artifact_storage.store_sbom(&sbom)?;
+        self.audit_log.record(
+            AuditAction::Admin,
+            &artifact_digest.to_string(),
+            Some(format!("attached {} SBOM", format)),
+        );
+        info!("Attached {} SBOM to artifact {}", format, artifact_digest);
+        Ok(sbom)
+    }
 
-        if transparency_log.artifact_hash == calculated_hash {
-            Ok(())
-        } else {
-            Err(TransparencyLogError::InvalidHash {
-                id: transparency_log.package_specific_artifact_id.clone(),
-                invalid_hash: calculated_hash,
-                actual_hash: transparency_log.artifact_hash.clone(),
-            })
-        }
+    /// The SBOMs attached to the artifact with the specified digest.
+    pub fn sboms(&self, artifact_digest: &str) -> anyhow::Result<Vec<Sbom>> {
+        let artifact_digest = ArtifactDigest::from_str(artifact_digest)?;
+        Ok(self.artifact_storage.sboms(&artifact_digest.to_string())?)
     }
-}
 
-#[cfg(test)]
-#[cfg(not(tarpaulin_include))]
-mod tests {
-    use super::*;
-    use crate::blockchain_service::event::BlockchainEvent;
-    use crate::build_service::event::BuildEvent;
-    use crate::network::client::command::Command;
-    use crate::network::idle_metric_protocol::PeerMetrics;
-    use crate::util::test_util;
-    use libp2p::identity::ed25519::Keypair;
-    use libp2p::identity::PublicKey;
-    use sha2::{Digest, Sha256};
-    use std::collections::HashSet;
-    use std::env;
-    use std::path::PathBuf;
-    use tokio::task;
+    /// Verify an in-toto attestation, like SLSA provenance, against the
+    /// trusted keys and store it for each artifact it is about. Both the
+    /// configured keys and the keys in the trust store with the
+    /// `attestation` role are trusted.
+    pub fn ingest_attestation(&self, envelope: Envelope) -> anyhow::Result<Vec<Attestation>> {
+        let attestations = Attestation::verify(envelope, &self.attestation_signers())?;
+        for attestation in &attestations {
+            self.artifact_storage.store_attestation(attestation)?;
+            self.audit_log.record(
+                AuditAction::Admin,
+                &attestation.artifact_digest,
+                Some(format!("stored {} attestation", attestation.predicate_type)),
+            );
+            info!(
+                "Stored {} attestation about artifact {}",
+                attestation.predicate_type, attestation.artifact_digest
+            );
+        }
+        Ok(attestations)
+    }
 
-    const VALID_ARTIFACT_HASH: [u8; 32] = [
-        0x86, 0x5c, 0x8d, 0x98, 0x8b, 0xe4, 0x66, 0x9f, 0x3e, 0x48, 0xf7, 0x3b, 0x98, 0xf9, 0xbc,
-        0x25, 0x7, 0xbe, 0x2, 0x46, 0xea, 0x35, 0xe0, 0x9, 0x8c, 0xf6, 0x5, 0x4d, 0x36, 0x44, 0xc1,
-        0x4f,
-    ];
+    /// Whether the envelope of a stored attestation is still signed by a
+    /// trusted key, which is no longer the case when the key of its signer
+    /// was removed from the trust store since it was ingested.
+    pub fn attestation_is_valid(&self, attestation: &Attestation) -> bool {
+        Attestation::verify(attestation.envelope.clone(), &self.attestation_signers()).is_ok()
+    }
 
-    #[tokio::test]
-    async fn test_put_and_get_artifact() {
-        let tmp_dir = test_util::tests::setup();
+    // The keys that are trusted to sign attestations.
+    fn attestation_signers(&self) -> Vec<PeerId> {
+        let mut trusted_keys = self.trusted_attestation_keys.clone();
+        trusted_keys.extend(self.trust_store.trusted_peers(TrustRole::Attestation));
+        trusted_keys
+    }
 
-        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
-            test_util::tests::create_artifact_service(&tmp_dir);
+    /// The attestations about the artifact with the specified digest.
+    pub fn attestations(&self, artifact_digest: &str) -> anyhow::Result<Vec<Attestation>> {
+        let artifact_digest = ArtifactDigest::from_str(artifact_digest)?;
+        Ok(self
+            .artifact_storage
+            .attestations(&artifact_digest.to_string())?)
+    }
 
-        tokio::spawn(async move {
-            loop {
-                match p2p_command_receiver.recv().await {
-                    Some(Command::ListPeers { sender, .. }) => {
-                        let _ = sender.send(HashSet::new());
+    /// The provenance chain of the artifact with the specified digest: its
+    /// attestations, followed by the attestations about the materials it was
+    /// built from, and so on.
+    pub fn provenance(&self, artifact_digest: &str) -> anyhow::Result<Vec<Attestation>> {
+        let artifact_digest = ArtifactDigest::from_str(artifact_digest)?.to_string();
+        let mut chain = vec![];
+        let mut visited = HashSet::from([artifact_digest.clone()]);
+        let mut pending = VecDeque::from([artifact_digest]);
+        while let Some(digest) = pending.pop_front() {
+            for attestation in self.artifact_storage.attestations(&digest)? {
+                for material in &attestation.materials {
+                    if visited.insert(material.clone()) {
+                        pending.push_back(material.clone());
                     }
-                    _ => panic!("Command must match Command::ListPeers"),
                 }
+                chain.push(attestation);
             }
-        });
+        }
+        Ok(chain)
+    }
 
-        tokio::spawn(async move {
-            loop {
-                match blockchain_event_receiver.recv().await {
-                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
-                        let _ = sender.send(Ok(()));
-                    }
-                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
-                }
-            }
-        });
+    /// Publish package metadata, like a version of a package, as a document
+    /// that is signed by the local node. The document is committed to the
+    /// blockchain, which orders the updates of all nodes, and distributed
+    /// over the DHT.
+    pub async fn publish_metadata<T: Signed>(&mut self, metadata: &T) -> anyhow::Result<()> {
+        self.ensure_role(NodeRole::Build)?;
+        let key = metadata.key();
+        let document = self
+            .p2p_client
+            .sign_document(&serde_json::to_string(metadata)?)
+            .await?;
+        let payload = serde_json::to_vec(&MetadataBlockPayload {
+            metadata_key: key.clone(),
+            document: document.clone(),
+        })?;
+        self.blockchain_event_client.add_block(payload).await?;
+        self.artifact_storage
+            .store_signed_metadata(&key, &document)?;
+        if let Err(e) = self.p2p_client.put_signed_record(&key, &document).await {
+            warn!(
+                "Failed to publish signed metadata {} in the DHT: {}",
+                key, e
+            );
+        }
+        Ok(())
+    }
 
-        let package_type = PackageType::Docker;
-        let package_specific_id = "package_specific_id";
-        let package_specific_artifact_id = "package_specific_artifact_id";
-        let transparency_log = artifact_service
-            .transparency_log_service
-            .add_artifact(AddArtifactRequest {
-                package_type,
-                package_specific_id: package_specific_id.to_owned(),
-                num_artifacts: 8,
-                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
-                artifact_hash: hex::encode(VALID_ARTIFACT_HASH),
-            })
+    // Store signed metadata that was committed to the blockchain by another
+    // node. Only metadata that an authorized node published under the key
+    // derived from its content is accepted. Blocks are applied in order, so
+    // a later update of the same metadata replaces the earlier one.
+    fn apply_committed_metadata(&self, metadata: MetadataBlockPayload) -> anyhow::Result<()> {
+        if !metadata.document.verify() {
+            bail!(
+                "The signature of the committed metadata {} is invalid",
+                metadata.metadata_key
+            );
+        }
+        match metadata.document.publisher() {
+            Some(publisher) if self.is_authorized(&publisher) => {}
+            _ => bail!(
+                "The committed metadata {} was not published by an authorized node",
+                metadata.metadata_key
+            ),
+        }
+        metadata.verify_key()?;
+        self.artifact_storage
+            .store_signed_metadata(&metadata.metadata_key, &metadata.document)?;
+        Ok(())
+    }
+
+    /// The transparency logs and signed package metadata that were stored
+    /// after the specified sequence numbers, for nodes that sync over HTTP
+    /// instead of libp2p. The transparency logs are signed by the local node.
+    pub async fn federation_sync(
+        &mut self,
+        query: &FederationSyncQuery,
+    ) -> anyhow::Result<FederationSync> {
+        let transparency_logs = self
+            .transparency_log_service
+            .find_transparency_logs_after_sequence(query.log_sequence, FEDERATION_SYNC_PAGE_SIZE)?;
+        let metadata = self
+            .artifact_storage
+            .signed_metadata_after_sequence(query.metadata_sequence, FEDERATION_SYNC_PAGE_SIZE)?;
+        let has_more = transparency_logs.len() == FEDERATION_SYNC_PAGE_SIZE
+            || metadata.len() == FEDERATION_SYNC_PAGE_SIZE;
+
+        let log_sequence = transparency_logs
+            .last()
+            .map_or(query.log_sequence, |(sequence, _)| *sequence);
+        let transparency_logs: Vec<TransparencyLog> = transparency_logs
+            .into_iter()
+            .map(|(_, transparency_log)| transparency_log)
+            .collect();
+        let transparency_logs = self
+            .p2p_client
+            .sign_document(&serde_json::to_string(&transparency_logs)?)
+            .await?;
+
+        let metadata_sequence = metadata
+            .last()
+            .map_or(query.metadata_sequence, |(sequence, ..)| *sequence);
+        let metadata = metadata
+            .into_iter()
+            .map(|(_, metadata_key, document)| MetadataBlockPayload {
+                metadata_key,
+                document,
+            })
+            .collect();
+
+        Ok(FederationSync {
+            transparency_logs,
+            log_sequence,
+            metadata,
+            metadata_sequence,
+            has_more,
+        })
+    }
+
+    /// Store the transparency logs and signed package metadata that were
+    /// pulled from another node over HTTP. Transparency logs are only stored
+    /// when that node signed them and is an authorized node, and metadata
+    /// only when it was published by an authorized node or a key in the
+    /// trust store. Known transparency logs and metadata that is older than
+    /// the local copy are skipped. Returns the number of stored entries.
+    pub fn apply_federation_sync(&self, sync: FederationSync) -> anyhow::Result<usize> {
+        if !sync.transparency_logs.verify() {
+            bail!("The signature of the synced transparency logs is invalid");
+        }
+        let logs_signed_by_authorized_node = match sync.transparency_logs.publisher() {
+            Some(publisher) => self.is_authorized(&publisher),
+            None => false,
+        };
+        let transparency_logs: Vec<TransparencyLog> =
+            serde_json::from_str(&sync.transparency_logs.content)?;
+
+        let mut applied = 0;
+        for transparency_log in transparency_logs {
+            match self
+                .transparency_log_service
+                .find_transparency_log(&transparency_log.id)
+            {
+                Ok(_) => continue,
+                Err(TransparencyLogError::LogNotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+            if !logs_signed_by_authorized_node {
+                warn!(
+                    "Skipping synced transparency log {} that was not signed by an authorized node",
+                    transparency_log.id
+                );
+                continue;
+            }
+            self.transparency_log_service
+                .write_transparency_log(&transparency_log)?;
+            applied += 1;
+        }
+
+        let trusted_peers = self.trust_store.trusted_peers(TrustRole::Verification);
+        for metadata in sync.metadata {
+            if !metadata.document.verify() {
+                warn!(
+                    "Skipping synced metadata {} with an invalid signature",
+                    metadata.metadata_key
+                );
+                continue;
+            }
+            let is_trusted = match metadata.document.publisher() {
+                Some(publisher) => {
+                    self.is_authorized(&publisher) || trusted_peers.contains(&publisher)
+                }
+                None => false,
+            };
+            if !is_trusted {
+                warn!(
+                    "Skipping synced metadata {} that was not published by an authorized node or a trusted key",
+                    metadata.metadata_key
+                );
+                continue;
+            }
+            if let Err(e) = metadata.verify_key() {
+                warn!("Skipping synced metadata: {}", e);
+                continue;
+            }
+            let is_newer = match self
+                .artifact_storage
+                .signed_metadata(&metadata.metadata_key)?
+            {
+                Some(local) => local.signed_at < metadata.document.signed_at,
+                None => true,
+            };
+            if is_newer {
+                self.artifact_storage
+                    .store_signed_metadata(&metadata.metadata_key, &metadata.document)?;
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Retrieve the package metadata under the specified key, which is looked
+    /// up in the DHT when it is not available locally. Only metadata with a
+    /// valid signature is returned. Metadata from the DHT is only accepted
+    /// when it belongs under the key and its publisher is authorized or
+    /// trusted, like name records.
+    pub async fn find_metadata<T: Signed>(&mut self, key: &str) -> anyhow::Result<Option<T>> {
+        if let Some(document) = self.artifact_storage.signed_metadata(key)? {
+            return T::from_signed_document(&document).map(Some);
+        }
+        match self.p2p_client.get_signed_record(key).await? {
+            Some(document) => {
+                let metadata = T::from_signed_document(&document)?;
+                if metadata.key() != key {
+                    bail!(
+                        "The signed metadata found under {} belongs under {}",
+                        key,
+                        metadata.key()
+                    );
+                }
+                let is_accepted = match (self.name_record_publishers()?, document.publisher()) {
+                    (None, _) => true,
+                    (Some(publishers), Some(publisher)) => publishers.contains(&publisher),
+                    (Some(_), None) => false,
+                };
+                if !is_accepted {
+                    bail!(
+                        "The signed metadata under {} of publisher {:?} is not accepted, the publisher is neither authorized nor trusted",
+                        key,
+                        document.publisher()
+                    );
+                }
+                self.artifact_storage
+                    .store_signed_metadata(key, &document)?;
+                Ok(Some(metadata))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The public keys that documents signed by this node are verified with.
+    pub fn local_public_keys(&self) -> Jwks {
+        Jwks {
+            keys: public_key_of(&self.p2p_client.local_peer_id)
+                .and_then(|public_key| Jwk::from_public_key(&public_key))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Publish the public keys of this node in the DHT, as a record that is
+    /// signed by the node itself. DHT records expire, so the keys have to
+    /// be published again periodically.
+    pub async fn publish_public_keys(&mut self) -> anyhow::Result<()> {
+        let record = PublicKeyRecord {
+            peer_id: self.p2p_client.local_peer_id.to_string(),
+            jwks: self.local_public_keys(),
+        };
+        let document = self
+            .p2p_client
+            .sign_document(&serde_json::to_string(&record)?)
+            .await?;
+        self.p2p_client
+            .put_signed_record(&record.key(), &document)
+            .await
+    }
+
+    /// Retrieve the public keys of the specified peer, from the cache or
+    /// from the DHT. Only records that are signed by the peer itself are
+    /// accepted.
+    pub async fn find_public_keys(&mut self, peer_id: &PeerId) -> anyhow::Result<Option<Jwks>> {
+        if *peer_id == self.p2p_client.local_peer_id {
+            return Ok(Some(self.local_public_keys()));
+        }
+        if let Some(jwks) = self.public_key_cache.get(peer_id) {
+            return Ok(Some(jwks));
+        }
+        let key = public_key_record_key(&peer_id.to_string());
+        match self.p2p_client.get_signed_record(&key).await? {
+            Some(document) => {
+                if document.publisher().as_ref() != Some(peer_id) {
+                    bail!(
+                        "The public key record of {} is not signed by the peer itself",
+                        peer_id
+                    );
+                }
+                let record = PublicKeyRecord::from_signed_document(&document)?;
+                self.public_key_cache.insert(*peer_id, record.jwks.clone());
+                Ok(Some(record.jwks))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Re-hash all artifacts in the local storage and verify them against
+    /// their transparency log. Corrupted artifacts are quarantined and fetched
+    /// again from other peers. The scrub yields after every artifact so that
+    /// it doesn't starve other tasks.
+    pub async fn scrub_artifacts(&mut self) -> Result<ScrubReport, anyhow::Error> {
+        let mut scrub_report = ScrubReport::default();
+        for artifact_id in self.artifact_storage.list_artifacts()? {
+            scrub_report.artifacts_checked += 1;
+            self.scrub_artifact(&artifact_id, &mut scrub_report).await;
+            tokio::task::yield_now().await;
+        }
+
+        scrub_report.finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        info!("Finished scrub of stored artifacts: {:?}", scrub_report);
+        *self.last_scrub_report.lock().unwrap() = Some(scrub_report.clone());
+
+        Ok(scrub_report)
+    }
+
+    /// The results of the last completed scrub, if any.
+    pub fn last_scrub_report(&self) -> Option<ScrubReport> {
+        self.last_scrub_report.lock().unwrap().clone()
+    }
+
+    /// The time since the artifact service, and with it the node, was started.
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// The number of requested artifacts that were not available and fell
+    /// back to a build from source.
+    pub fn build_fallback_count(&self) -> u64 {
+        self.build_fallbacks.load(Ordering::Relaxed)
+    }
+
+    // Verify a single stored artifact and update the scrub report.
+    async fn scrub_artifact(&mut self, artifact_id: &str, scrub_report: &mut ScrubReport) {
+        let transparency_log = match self
+            .transparency_log_service
+            .find_artifact_transparency_log(artifact_id)
+        {
+            Ok(transparency_log) => transparency_log,
+            Err(_) => {
+                scrub_report.artifacts_unverifiable += 1;
+                return;
+            }
+        };
+
+        let artifact = match self.get_artifact_locally(artifact_id).await {
+            Ok(artifact) => artifact,
+            Err(e) => {
+                warn!("Unable to read artifact {} for scrub: {:?}", artifact_id, e);
+                return;
+            }
+        };
+
+        if let Err(e) = self
+            .verify_stored_artifact(&transparency_log, &artifact)
+            .await
+        {
+            warn!("Scrub found a corrupted artifact: {}", e);
+            scrub_report.artifacts_corrupted += 1;
+            match self.refetch_artifact(&transparency_log).await {
+                Ok(()) => scrub_report.artifacts_refetched += 1,
+                Err(e) => warn!("Unable to fetch artifact {} again: {:?}", artifact_id, e),
+            }
+        }
+    }
+
+    // Fetch a quarantined artifact again from other peers and provide it
+    // once its hash has been verified.
+    async fn refetch_artifact(&mut self, transparency_log: &TransparencyLog) -> anyhow::Result<()> {
+        let artifact = self
+            .get_artifact_from_peers(
+                &transparency_log.artifact_id,
+                &transparency_log.artifact_hash,
+            )
+            .await?;
+        self.verify_stored_artifact(transparency_log, &artifact)
+            .await?;
+        self.p2p_client.provide(&transparency_log.artifact_id).await
+    }
+
+    async fn put_artifact_from_build_result(
+        &self,
+        artifact_location: &Path,
+        artifact_id: &str,
+        artifact_hash: &str,
+    ) -> Result<(), anyhow::Error> {
+        let artifact_file = File::open(artifact_location)?;
+        let size = artifact_file.metadata()?.len();
+        self.put_verified_artifact(
+            &mut BufReader::new(artifact_file),
+            size,
+            artifact_id,
+            artifact_hash,
+            "build result",
+        )
+        .context("Error from put_artifact_from_build_result")
+    }
+
+    // Push an artifact that is published by this node to the storage, after
+    // reserving space for it and verifying it against its hash.
+    fn put_verified_artifact(
+        &self,
+        reader: &mut impl Read,
+        size: u64,
+        artifact_id: &str,
+        artifact_hash: &str,
+        origin: &str,
+    ) -> Result<(), anyhow::Error> {
+        let _reservation = self.artifact_storage.reserve_space(artifact_id, size)?;
+        self.artifact_storage
+            .push_verified_artifact(reader, artifact_id, artifact_hash)?;
+        self.audit_log
+            .record(AuditAction::Push, artifact_id, Some(String::from(origin)));
+        self.events.publish(NodeEventKind::ArtifactStored {
+            artifact_id: artifact_id.to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Given artifact_id & reader, push artifact to artifact_storage
+    fn put_artifact(&self, artifact_id: &str, reader: &mut impl Read) -> Result<(), anyhow::Error> {
+        info!("put_artifact with id: {}", artifact_id);
+        self.artifact_storage
+            .push_artifact(reader, artifact_id)
+            .context("Error from put_artifact")?;
+        self.audit_log.record(AuditAction::Push, artifact_id, None);
+        self.events.publish(NodeEventKind::ArtifactStored {
+            artifact_id: artifact_id.to_owned(),
+        });
+        Ok(())
+    }
+
+    // Count the pull of an artifact by a local package manager in its usage statistics.
+    fn record_local_pull(&self, artifact_id: &str) {
+        if let Err(e) = self.artifact_storage.record_local_pull(artifact_id) {
+            warn!("Unable to record pull of artifact {}: {}", artifact_id, e);
+        }
+        self.audit_log.record(AuditAction::Pull, artifact_id, None);
+    }
+
+    // Record the package of a stored artifact in the metadata index of the storage.
+    fn associate_artifact(&self, transparency_log: &TransparencyLog) -> io::Result<()> {
+        match transparency_log.package_type {
+            Some(package_type) => self.artifact_storage.associate_artifact(
+                &transparency_log.artifact_id,
+                package_type,
+                &transparency_log.package_specific_artifact_id,
+                None,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Retrieve the artifact data for the specified package. If the artifact
+    /// is not available locally, the service will try to fetch the artifact
+    /// from the p2p network.
+    #[instrument(skip(self))]
+    pub async fn get_artifact(
+        &mut self,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let transparency_log = match self
+            .transparency_log_service
+            .get_artifact(&package_type, package_specific_artifact_id)
+        {
+            Ok(transparency_log) => transparency_log,
+            Err(e) => {
+                self.enforce_policy(Some(package_type), package_specific_artifact_id, None)?;
+                return self
+                    .get_artifact_by_name_record(package_type, package_specific_artifact_id)
+                    .await
+                    .map_err(|name_record_error| {
+                        debug!(
+                            "Unable to resolve artifact {} from the p2p network: {:?}",
+                            package_specific_artifact_id, name_record_error
+                        );
+                        e.into()
+                    });
+            }
+        };
+        self.transparency_log_service
+            .verify_artifact_not_revoked(&transparency_log)?;
+        self.enforce_policy(
+            Some(package_type),
+            package_specific_artifact_id,
+            Some(&transparency_log),
+        )?;
+
+        let artifact = match self
+            .get_artifact_locally(&transparency_log.artifact_id)
+            .await
+        {
+            Ok(artifact) if !self.verify_on_read => {
+                self.record_local_pull(&transparency_log.artifact_id);
+                return Ok(artifact);
+            }
+            Ok(artifact) => match self
+                .verify_stored_artifact(&transparency_log, &artifact)
+                .await
+            {
+                Ok(()) => {
+                    self.record_local_pull(&transparency_log.artifact_id);
+                    return Ok(artifact);
+                }
+                Err(e) => {
+                    warn!(
+                        "Fetching artifact {} from the p2p network instead: {}",
+                        transparency_log.artifact_id, e
+                    );
+                    self.get_artifact_from_peers(
+                        &transparency_log.artifact_id,
+                        &transparency_log.artifact_hash,
+                    )
+                    .await
+                }
+            },
+            Err(_) => {
+                self.get_artifact_from_peers(
+                    &transparency_log.artifact_id,
+                    &transparency_log.artifact_hash,
+                )
+                .await
+            }
+        }?;
+
+        self.verify_artifact(&transparency_log, &artifact).await?;
+        self.associate_artifact(&transparency_log)?;
+        self.record_local_pull(&transparency_log.artifact_id);
+
+        Ok(artifact)
+    }
+
+    /// Retrieve the artifact data from the local storage for serving it to
+    /// other peers. The artifact is always verified against its transparency
+    /// log, so that corrupted artifacts are never spread over the network.
+    pub async fn get_verified_artifact_locally(
+        &mut self,
+        artifact_id: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let transparency_log = self
+            .transparency_log_service
+            .find_artifact_transparency_log(artifact_id)?;
+        self.transparency_log_service
+            .verify_artifact_not_revoked(&transparency_log)?;
+        self.enforce_policy(
+            transparency_log.package_type,
+            &transparency_log.package_specific_artifact_id,
+            Some(&transparency_log),
+        )?;
+        let artifact = self.get_artifact_locally(artifact_id).await?;
+
+        self.verify_stored_artifact(&transparency_log, &artifact)
+            .await?;
+        if let Err(e) = self.artifact_storage.record_peer_serve(artifact_id) {
+            warn!("Unable to record serve of artifact {}: {}", artifact_id, e);
+        }
+        self.audit_log.record(AuditAction::Serve, artifact_id, None);
+        self.events.publish(NodeEventKind::ArtifactServed {
+            artifact_id: artifact_id.to_owned(),
+        });
+
+        Ok(artifact)
+    }
+
+    // Check the admission policy for an artifact. The nodes that added a
+    // transparency log for the same hash and the trusted signers of
+    // attestations about it count as independent verifications.
+    fn enforce_policy(
+        &self,
+        package_type: Option<PackageType>,
+        package_specific_artifact_id: &str,
+        transparency_log: Option<&TransparencyLog>,
+    ) -> Result<(), PolicyViolation> {
+        self.check_policy(package_type, package_specific_artifact_id, transparency_log)
+            .map_err(|violation| {
+                warn!("{}", violation);
+                violation
+            })
+    }
+
+    fn check_policy(
+        &self,
+        package_type: Option<PackageType>,
+        package_specific_artifact_id: &str,
+        transparency_log: Option<&TransparencyLog>,
+    ) -> Result<(), PolicyViolation> {
+        let policy = self.policy.read().unwrap().clone();
+        if policy.is_empty() {
+            return Ok(());
+        }
+
+        let mut evidence = Evidence {
+            has_transparency_log: transparency_log.is_some(),
+            trusted_verifiers: self
+                .trust_store
+                .trusted_peers(TrustRole::Verification)
+                .iter()
+                .map(PeerId::to_string)
+                .collect(),
+            ..Default::default()
+        };
+        if let Some(transparency_log) = transparency_log {
+            let transparency_logs = self
+                .transparency_log_service
+                .find_transparency_logs_by_artifact_hash(&transparency_log.artifact_hash)
+                .unwrap_or_default();
+            evidence.verifiers.extend(
+                transparency_logs
+                    .into_iter()
+                    .filter(|log| log.operation == Operation::AddArtifact)
+                    .map(|log| log.node_id),
+            );
+            if let Ok(attestations) = self.attestations(&transparency_log.artifact_hash) {
+                evidence.verifiers.extend(
+                    attestations
+                        .into_iter()
+                        .flat_map(|attestation| attestation.signers),
+                );
+            }
+        }
+
+        policy.check(package_type, package_specific_artifact_id, &evidence)
+    }
+
+    /// Verify the signature of a document and look up what its signer is
+    /// trusted for in the trust store.
+    pub fn verify_document(&self, document: &SignedDocument) -> VerificationReport {
+        let valid = document.verify();
+        VerificationReport {
+            valid,
+            signers: document
+                .publisher()
+                .map(|peer_id| self.verified_signer(&peer_id.to_string(), "document", valid))
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Verify the evidence about the artifacts with the specified hash: the
+    /// nodes that recorded a transparency log for them, the signers of
+    /// attestations about them and whether the admission policy would
+    /// accept them.
+    pub fn verify_artifact_hash(&self, artifact_hash: &str) -> anyhow::Result<VerificationReport> {
+        let transparency_logs: Vec<TransparencyLog> = self
+            .transparency_log_service
+            .find_transparency_logs_by_artifact_hash(artifact_hash)?
+            .into_iter()
+            .filter(|transparency_log| transparency_log.operation == Operation::AddArtifact)
+            .collect();
+        let mut signers: Vec<VerifiedSigner> = transparency_logs
+            .iter()
+            .map(|transparency_log| transparency_log.node_id.clone())
+            .unique()
+            .map(|node_id| self.verified_signer(&node_id, "transparency log", true))
+            .collect();
+        for attestation in self.attestations(artifact_hash).unwrap_or_default() {
+            let valid = self.attestation_is_valid(&attestation);
+            for signer in &attestation.signers {
+                signers.push(self.verified_signer(
+                    signer,
+                    &format!("{} attestation", attestation.predicate_type),
+                    valid,
+                ));
+            }
+        }
+        let revocation = self
+            .transparency_log_service
+            .find_revocation(artifact_hash)?
+            .map(|revocation| revocation.reason);
+        let transparency_log = transparency_logs.first();
+        let policy_violation = self
+            .check_policy(
+                transparency_log.and_then(|transparency_log| transparency_log.package_type),
+                transparency_log
+                    .map(|transparency_log| transparency_log.package_specific_artifact_id.as_str())
+                    .unwrap_or(artifact_hash),
+                transparency_log,
+            )
+            .err()
+            .map(|violation| violation.to_string());
+
+        Ok(VerificationReport {
+            valid: !transparency_logs.is_empty()
+                && revocation.is_none()
+                && signers.iter().all(|signer| signer.valid),
+            signers,
+            transparency_logs: transparency_logs.len(),
+            revocation,
+            policy_violation,
+        })
+    }
+
+    fn verified_signer(&self, peer_id: &str, signed: &str, valid: bool) -> VerifiedSigner {
+        VerifiedSigner {
+            peer_id: peer_id.to_owned(),
+            signed: signed.to_owned(),
+            valid,
+            trusted_roles: self
+                .trust_store
+                .list()
+                .unwrap_or_default()
+                .into_iter()
+                .find(|trusted_key| trusted_key.peer_id == peer_id)
+                .map(|trusted_key| trusted_key.roles)
+                .unwrap_or_default(),
+        }
+    }
+
+    // Verify an artifact read from the local storage against the hash in its
+    // transparency log. A corrupted artifact is moved to quarantine and no
+    // longer provided on the p2p network.
+    async fn verify_stored_artifact(
+        &mut self,
+        transparency_log: &TransparencyLog,
+        artifact: &[u8],
+    ) -> anyhow::Result<()> {
+        let calculated_hash = calculate_hash_like(&transparency_log.artifact_hash, artifact);
+        let valid = transparency_log.artifact_hash == calculated_hash;
+        self.events.publish(NodeEventKind::ArtifactVerified {
+            artifact_id: transparency_log.artifact_id.clone(),
+            valid,
+        });
+        if valid {
+            return Ok(());
+        }
+
+        let storage_error = StorageError::Corrupted {
+            artifact_id: transparency_log.artifact_id.clone(),
+            expected_hash: transparency_log.artifact_hash.clone(),
+            actual_hash: calculated_hash,
+        };
+        error!("{}", storage_error);
+
+        self.artifact_storage
+            .quarantine_artifact(&transparency_log.artifact_id, &storage_error.to_string())?;
+        self.audit_log.record(
+            AuditAction::Quarantine,
+            &transparency_log.artifact_id,
+            Some(storage_error.to_string()),
+        );
+        self.p2p_client
+            .stop_providing(&transparency_log.artifact_id)
+            .await?;
+
+        Err(storage_error.into())
+    }
+
+    // Retrieve the artifact data for the specified package by resolving
+    // its name with a signed name record from the p2p network. Used when
+    // no transparency log for the package is available locally. Only name
+    // records of authorized or trusted publishers are accepted, and revoked
+    // artifacts are refused.
+    #[instrument(skip(self))]
+    async fn get_artifact_by_name_record(
+        &mut self,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let name = artifact_name(&package_type, package_specific_artifact_id);
+        let publishers = self.name_record_publishers()?;
+        let name_record = self
+            .p2p_client
+            .resolve_name(&name, publishers.clone())
+            .await?
+            .ok_or_else(|| anyhow!("No name record found for {}", name))?;
+
+        if !name_record.is_published_by(&publishers) || !name_record.verify() {
+            bail!(
+                "The name record for {} of publisher {:?} is not accepted, the publisher is neither authorized nor trusted",
+                name,
+                name_record.publisher()
+            );
+        }
+        if let Some(revocation) = self
+            .transparency_log_service
+            .find_revocation(&name_record.artifact_hash)?
+        {
+            bail!(TransparencyLogError::ArtifactRevoked {
+                artifact_hash: revocation.artifact_hash,
+                reason: revocation.reason,
+            });
+        }
+
+        let artifact = match self.get_artifact_locally(&name_record.artifact_id).await {
+            Ok(artifact) => artifact,
+            Err(_) => {
+                self.get_artifact_from_peers(&name_record.artifact_id, &name_record.artifact_hash)
+                    .await?
+            }
+        };
+
+        let calculated_hash = calculate_hash_like(&name_record.artifact_hash, &artifact);
+        if name_record.artifact_hash != calculated_hash {
+            bail!(TransparencyLogError::InvalidHash {
+                id: package_specific_artifact_id.to_owned(),
+                invalid_hash: calculated_hash,
+                actual_hash: name_record.artifact_hash,
+            });
+        }
+
+        Ok(artifact)
+    }
+
+    /// The publishers whose name records are accepted: the authorized nodes
+    /// and the nodes trusted for verification. Like `is_authorized`, any
+    /// publisher is accepted while no node was authorized yet.
+    pub fn name_record_publishers(&self) -> anyhow::Result<Option<HashSet<PeerId>>> {
+        let authorized_nodes = self.transparency_log_service.get_authorized_nodes()?;
+        if authorized_nodes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            authorized_nodes
+                .into_iter()
+                .chain(self.trust_store.trusted_peers(TrustRole::Verification))
+                .collect(),
+        ))
+    }
+
+    // Publish a signed name record for the artifact in the background,
+    // so other nodes can resolve its name through the p2p network.
+    fn publish_name_record(&self, transparency_log: &TransparencyLog) {
+        let package_type = match transparency_log.package_type {
+            Some(package_type) => package_type,
+            None => return,
+        };
+
+        let name = artifact_name(
+            &package_type,
+            &transparency_log.package_specific_artifact_id,
+        );
+        let artifact_id = transparency_log.artifact_id.clone();
+        let artifact_hash = transparency_log.artifact_hash.clone();
+        let mut p2p_client = self.p2p_client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = p2p_client
+                .put_name_record(&name, &artifact_id, &artifact_hash)
+                .await
+            {
+                warn!("Failed to publish name record for {}: {:?}", name, e);
+            }
+        });
+    }
+
+    // Push the artifact in the background to the most idle connected peers
+    // that don't provide it yet, until `replication_factor` peers store it.
+    fn replicate_artifact(&self, transparency_log: &TransparencyLog) {
+        if self.replication_factor == 0 {
+            return;
+        }
+
+        let package_type = match transparency_log.package_type {
+            Some(package_type) => package_type,
+            None => return,
+        };
+
+        let mut artifact_service = self.clone();
+        let artifact_id = transparency_log.artifact_id.clone();
+        let package_specific_artifact_id = transparency_log.package_specific_artifact_id.clone();
+        tokio::spawn(async move {
+            match artifact_service
+                .push_artifact_to_peers(package_type, &package_specific_artifact_id, &artifact_id)
+                .await
+            {
+                Ok(replicas) => info!(
+                    "Artifact {} replicated to {} peer(s)",
+                    package_specific_artifact_id, replicas
+                ),
+                Err(e) => warn!(
+                    "Failed to replicate artifact {}: {:?}",
+                    package_specific_artifact_id, e
+                ),
+            }
+        });
+    }
+
+    async fn push_artifact_to_peers(
+        &mut self,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+        artifact_id: &str,
+    ) -> anyhow::Result<usize> {
+        let local_peer_id = self.p2p_client.local_peer_id;
+        let providers = self.p2p_client.list_providers(artifact_id).await?;
+        let existing_replicas = providers
+            .iter()
+            .filter(|peer_id| **peer_id != local_peer_id)
+            .count();
+        let missing_replicas = self.replication_factor.saturating_sub(existing_replicas);
+        if missing_replicas == 0 {
+            return Ok(existing_replicas);
+        }
+
+        let candidates: HashSet<PeerId> = self
+            .p2p_client
+            .list_peers()
+            .await?
+            .into_iter()
+            .filter(|peer_id| *peer_id != local_peer_id && !providers.contains(peer_id))
+            .collect();
+        let candidates = self
+            .p2p_client
+            .get_idle_peers(candidates.clone(), candidates.len())
+            .await?;
+
+        let artifact = self.get_artifact_locally(artifact_id).await?;
+        let mut replicas = 0;
+        for peer_id in candidates {
+            if replicas == missing_replicas {
+                break;
+            }
+
+            match self
+                .p2p_client
+                .request_artifact_replication(
+                    &peer_id,
+                    package_type,
+                    package_specific_artifact_id,
+                    artifact.clone(),
+                )
+                .await
+            {
+                Ok(true) => replicas += 1,
+                Ok(false) => debug!(
+                    "Peer {} refused replica of artifact {}",
+                    peer_id, package_specific_artifact_id
+                ),
+                Err(e) => debug!(
+                    "Unable to replicate artifact {} to peer {}: {}",
+                    package_specific_artifact_id, peer_id, e
+                ),
+            }
+        }
+
+        Ok(existing_replicas + replicas)
+    }
+
+    /// Store a replica of an artifact that was pushed by another peer. The
+    /// artifact is only accepted when a transparency log for the package
+    /// exists and the hash of the artifact matches the one in the log.
+    pub async fn store_replicated_artifact(
+        &mut self,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+        artifact: &[u8],
+    ) -> anyhow::Result<bool> {
+        let transparency_log = match self
+            .transparency_log_service
+            .get_artifact(&package_type, package_specific_artifact_id)
+        {
+            Ok(transparency_log) => transparency_log,
+            Err(e) => {
+                debug!(
+                    "Refusing replica of unknown artifact {}: {:?}",
+                    package_specific_artifact_id, e
+                );
+                return Ok(false);
+            }
+        };
+
+        if let Err(e) = self
+            .transparency_log_service
+            .verify_artifact_not_revoked(&transparency_log)
+        {
+            warn!(
+                "Refusing replica of artifact {}: {}",
+                package_specific_artifact_id, e
+            );
+            return Ok(false);
+        }
+
+        if let Err(e) = self.verify_artifact(&transparency_log, artifact).await {
+            warn!(
+                "Refusing replica of artifact {}: {:?}",
+                package_specific_artifact_id, e
+            );
+            let reason = format!("Replica failed verification: {}", e);
+            match self.artifact_storage.quarantine_data(
+                &mut BufReader::new(artifact),
+                &transparency_log.artifact_id,
+                &reason,
+            ) {
+                Ok(()) => self.audit_log.record(
+                    AuditAction::Quarantine,
+                    &transparency_log.artifact_id,
+                    Some(reason),
+                ),
+                Err(e) => warn!(
+                    "Unable to quarantine replica of artifact {}: {:?}",
+                    package_specific_artifact_id, e
+                ),
+            }
+            return Ok(false);
+        }
+
+        if self
+            .artifact_storage
+            .artifact_size(&transparency_log.artifact_id)
+            .is_err()
+        {
+            self.put_artifact(&transparency_log.artifact_id, &mut BufReader::new(artifact))?;
+            self.associate_artifact(&transparency_log)?;
+            self.p2p_client
+                .provide(&transparency_log.artifact_id)
+                .await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Retrieve the artifact data for the specified package. If the artifact
+    /// is not found, the service start a request to build it on an authorized
+    /// node.
+    #[instrument(skip(self, package_specific_artifact_id))]
+    pub async fn get_artifact_or_build(
+        &mut self,
+        package_type: PackageType,
+        package_specific_id: &str,
+        package_specific_artifact_id: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.get_artifact(package_type, package_specific_artifact_id).await.map_err(|e| {
+                if e.is::<PolicyViolation>()
+                    || matches!(
+                        e.downcast_ref::<TransparencyLogError>(),
+                        Some(TransparencyLogError::ArtifactRevoked { .. })
+                    )
+                {
+                    return e;
+                }
+                warn!("Error looking for artifact: {:?}. A new build will be started. Try again later", e);
+                self.build_fallbacks.fetch_add(1, Ordering::Relaxed);
+                let new_artifact_service = self.clone();
+                let new_package_specific_id = package_specific_id.to_string();
+                tokio::spawn(async move {
+                    debug!("Spawning a build...");
+                    let build_result = new_artifact_service.clone().request_build(package_type, new_package_specific_id).await;
+                    debug!("Build result {:?}", build_result);
+                }.in_current_span());
+                // in any case, return the error
+                e
+            })
+    }
+
+    /// Retrieve the artifact data specified by `artifact_id` from the local storage.
+    #[instrument(skip(self))]
+    pub async fn get_artifact_locally(
+        &mut self,
+        artifact_id: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let artifact = self.artifact_storage.pull_artifact(artifact_id)?;
+        let mut buf_reader = BufReader::new(artifact);
+        let mut blob_content = Vec::new();
+        buf_reader.read_to_end(&mut blob_content)?;
+        Ok(blob_content)
+    }
+
+    /// Retrieve the artifact logs for the specified package.
+    pub async fn get_logs_for_artifact(
+        &mut self,
+        package_type: PackageType,
+        package_specific_id: &str,
+    ) -> anyhow::Result<Vec<TransparencyLog>> {
+        let transparency_logs = self
+            .transparency_log_service
+            .search_transparency_logs(&package_type, package_specific_id)?;
+
+        Ok(transparency_logs)
+    }
+
+    /// Export all artifacts of the local storage and their metadata to a tar archive.
+    /// The archive is written on a blocking thread, so the writer may block until
+    /// the archive is consumed. Returns the number of exported artifacts.
+    pub async fn export_artifacts(
+        &self,
+        writer: impl Write + Send + 'static,
+    ) -> anyhow::Result<usize> {
+        let artifact_storage = self.artifact_storage.clone();
+        let transparency_log_service = self.transparency_log_service.clone();
+        let exported_artifacts = tokio::task::spawn_blocking(move || {
+            archive::export_artifacts(
+                &artifact_storage,
+                |artifact_id| known_artifact_hash(&transparency_log_service, artifact_id),
+                writer,
+            )
+        })
+        .await??;
+        self.audit_log.record(
+            AuditAction::Admin,
+            "export",
+            Some(format!("exported {} artifacts", exported_artifacts)),
+        );
+        Ok(exported_artifacts)
+    }
+
+    /// Import the artifacts of an archive that was created by `export_artifacts`
+    /// and provide them on the p2p network. Only artifacts that were added by a
+    /// transparency log of this node are imported, after their content was
+    /// verified against it. The archive is read on a blocking thread.
+    pub async fn import_artifacts(
+        &mut self,
+        reader: impl Read + Send + 'static,
+    ) -> anyhow::Result<ImportedArchive> {
+        let artifact_storage = self.artifact_storage.clone();
+        let transparency_log_service = self.transparency_log_service.clone();
+        let imported_archive = tokio::task::spawn_blocking(move || {
+            archive::import_artifacts(
+                &artifact_storage,
+                |artifact_id| known_artifact_hash(&transparency_log_service, artifact_id),
+                reader,
+            )
+        })
+        .await??;
+        for artifact_id in imported_archive.imported_artifacts.iter() {
+            self.audit_log
+                .record(AuditAction::Push, artifact_id, Some(String::from("import")));
+            self.p2p_client.provide(artifact_id).await?;
+        }
+        Ok(imported_archive)
+    }
+
+    pub async fn provide_local_artifacts(&self) -> anyhow::Result<()> {
+        for artifact_id in self.artifact_storage.list_artifacts()? {
+            debug!("Providing artifact_id: {:?}", artifact_id);
+            self.p2p_client.clone().provide(&artifact_id).await?
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_artifact_from_peers(
+        &mut self,
+        artifact_id: &str,
+        artifact_hash: &str,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let providers = self.p2p_client.list_providers(artifact_id).await?;
+        let availability = self.get_provider_availability(providers, artifact_id).await;
+
+        // reserve the space for the artifact before it is transferred, using
+        // the size announced by the providers that have it
+        let reservation = match availability.available.values().max() {
+            Some(size) => Some(self.artifact_storage.reserve_space(artifact_id, *size)?),
+            None => None,
+        };
+
+        // the providers that confirmed they have the artifact are preferred
+        // over the providers that couldn't tell
+        let confirmed_providers = availability.available.keys().copied().collect();
+        for mut providers in [confirmed_providers, availability.unknown] {
+            // overloaded peers refuse the transfer, try the next idle peer then
+            while let Some(peer_id) = self.p2p_client.get_idle_peer(providers.clone()).await? {
+                match self
+                    .get_artifact_from_peer(
+                        &peer_id,
+                        artifact_id,
+                        artifact_hash,
+                        reservation.is_some(),
+                    )
+                    .await
+                {
+                    Err(e) if e.is::<PeerBusy>() => {
+                        debug!(
+                            "Peer {} is busy, requesting artifact {} elsewhere",
+                            peer_id, artifact_id
+                        );
+                        providers.remove(&peer_id);
+                    }
+                    result => return result,
+                }
+            }
+        }
+        bail!(
+            "Artifact with id {} is not available on the p2p network.",
+            artifact_id
+        )
+    }
+
+    // Query all providers concurrently whether they still have the artifact,
+    // so that stale provider records don't lead to failing transfers.
+    // Providers that don't support the availability protocol, or that don't
+    // answer in time, might still have it.
+    async fn get_provider_availability(
+        &self,
+        providers: HashSet<PeerId>,
+        artifact_id: &str,
+    ) -> ProviderAvailability {
+        let queries = providers.into_iter().map(|peer_id| {
+            let mut p2p_client = self.p2p_client.clone();
+            async move {
+                let availability = tokio::time::timeout(
+                    AVAILABILITY_QUERY_TIMEOUT,
+                    p2p_client.request_artifact_availability(&peer_id, artifact_id),
+                )
+                .await;
+                (peer_id, availability)
+            }
+        });
+
+        let mut provider_availability = ProviderAvailability::default();
+        for (peer_id, availability) in futures::future::join_all(queries).await {
+            match availability {
+                Ok(Ok(Some(size))) => {
+                    debug!(
+                        "Peer {} has artifact {} with size {}",
+                        peer_id, artifact_id, size
+                    );
+                    provider_availability.available.insert(peer_id, size);
+                }
+                Ok(Ok(None)) => {
+                    debug!(
+                        "Peer {} no longer provides artifact {}",
+                        peer_id, artifact_id
+                    );
+                }
+                Ok(Err(e)) => {
+                    debug!(
+                        "Unable to query availability of artifact {} on peer {}: {}",
+                        artifact_id, peer_id, e
+                    );
+                    provider_availability.unknown.insert(peer_id);
+                }
+                Err(_) => {
+                    debug!(
+                        "Peer {} did not answer the availability query of artifact {} in time",
+                        peer_id, artifact_id
+                    );
+                    provider_availability.unknown.insert(peer_id);
+                }
+            }
+        }
+        provider_availability
+    }
+
+    // Space for the artifact is reserved once it was transferred, unless the
+    // caller reserved it before already.
+    #[instrument(skip(self, peer_id), fields(peer_id = %peer_id))]
+    async fn get_artifact_from_peer(
+        &mut self,
+        peer_id: &PeerId,
+        artifact_id: &str,
+        artifact_hash: &str,
+        space_reserved: bool,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let artifact = self
+            .p2p_client
+            .request_artifact(peer_id, artifact_id)
+            .await?;
+
+        let _reservation = if space_reserved {
+            None
+        } else {
+            Some(
+                self.artifact_storage
+                    .reserve_space(artifact_id, artifact.len() as u64)?,
+            )
+        };
+        let mut buf_reader = BufReader::new(artifact.as_slice());
+
+        self.artifact_storage
+            .push_verified_artifact(&mut buf_reader, artifact_id, artifact_hash)
+            .context("Error from get_artifact_from_peer")?;
+        self.get_artifact_locally(artifact_id).await
+    }
+
+    async fn verify_artifact(
+        &mut self,
+        transparency_log: &TransparencyLog,
+        artifact: &[u8],
+    ) -> Result<(), TransparencyLogError> {
+        let calculated_hash = calculate_hash_like(&transparency_log.artifact_hash, artifact);
+
+        if transparency_log.artifact_hash == calculated_hash {
+            Ok(())
+        } else {
+            Err(TransparencyLogError::InvalidHash {
+                id: transparency_log.package_specific_artifact_id.clone(),
+                invalid_hash: calculated_hash,
+                actual_hash: transparency_log.artifact_hash.clone(),
+            })
+        }
+    }
+}
+
+// The providers of an artifact, by whether they confirmed they have it.
+#[derive(Default)]
+struct ProviderAvailability {
+    // the providers that have the artifact, with its size
+    available: HashMap<PeerId, u64>,
+    // the providers that couldn't tell whether they have the artifact
+    unknown: HashSet<PeerId>,
+}
+
+// The name under which the artifact of a package is published in the
+// p2p network, e.g. `Docker/library/alpine:3.16`.
+pub(crate) fn artifact_name(
+    package_type: &PackageType,
+    package_specific_artifact_id: &str,
+) -> String {
+    format!("{}/{}", package_type, package_specific_artifact_id)
+}
+
+// The hash of an artifact as recorded by its transparency log on this node.
+fn known_artifact_hash(
+    transparency_log_service: &TransparencyLogService,
+    artifact_id: &str,
+) -> Option<String> {
+    transparency_log_service
+        .find_artifact_transparency_log(artifact_id)
+        .ok()
+        .map(|transparency_log| transparency_log.artifact_hash)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::artifact_service::attestation::IN_TOTO_PAYLOAD_TYPE;
+    use crate::artifact_service::model::{ArtifactReference, Package, PackageVersion};
+    use crate::artifact_service::policy::PolicyRule;
+    use crate::blockchain_service::event::BlockchainEvent;
+    use crate::build_service::event::BuildEvent;
+    use crate::network::client::command::Command;
+    use crate::network::idle_metric_protocol::PeerMetrics;
+    use crate::network::name_record::NameRecord;
+    use crate::util::test_util;
+    use libp2p::identity;
+    use libp2p::identity::ed25519::Keypair;
+    use libp2p::identity::PublicKey;
+    use sha2::{Digest, Sha256};
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicBool;
+    use tokio::task;
+
+    const VALID_ARTIFACT_HASH: [u8; 32] = [
+        0x86, 0x5c, 0x8d, 0x98, 0x8b, 0xe4, 0x66, 0x9f, 0x3e, 0x48, 0xf7, 0x3b, 0x98, 0xf9, 0xbc,
+        0x25, 0x7, 0xbe, 0x2, 0x46, 0xea, 0x35, 0xe0, 0x9, 0x8c, 0xf6, 0x5, 0x4d, 0x36, 0x44, 0xc1,
+        0x4f,
+    ];
+
+    #[tokio::test]
+    async fn test_ingest_attestations_and_provenance() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, ..) = test_util::tests::create_artifact_service(&tmp_dir);
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let artifact_hex = hex::encode(VALID_ARTIFACT_HASH);
+        let source_hex = hex::encode(Sha256::digest(b"source"));
+        let provenance = |subject: &str, material: Option<&str>| {
+            let materials: Vec<serde_json::Value> = material
+                .iter()
+                .map(|material| serde_json::json!({ "digest": { "sha256": material } }))
+                .collect();
+            let statement = serde_json::json!({
+                "_type": "https://in-toto.io/Statement/v0.1",
+                "subject": [{ "digest": { "sha256": subject } }],
+                "predicateType": "https://slsa.dev/provenance/v0.2",
+                "predicate": { "builder": { "id": "builder" }, "materials": materials }
+            });
+            Envelope::sign(
+                &keypair,
+                IN_TOTO_PAYLOAD_TYPE,
+                &serde_json::to_vec(&statement).unwrap(),
+            )
+            .unwrap()
+        };
+
+        assert!(artifact_service
+            .ingest_attestation(provenance(&artifact_hex, Some(&source_hex)))
+            .is_err());
+
+        artifact_service.trusted_attestation_keys = vec![keypair.public().to_peer_id()];
+        artifact_service
+            .ingest_attestation(provenance(&artifact_hex, Some(&source_hex)))
+            .unwrap();
+        artifact_service
+            .ingest_attestation(provenance(&source_hex, Some(&artifact_hex)))
+            .unwrap();
+
+        let chain = artifact_service.provenance(&artifact_hex).unwrap();
+        assert_eq!(
+            chain
+                .iter()
+                .map(|attestation| attestation.artifact_digest.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                format!("sha256:{}", artifact_hex),
+                format!("sha256:{}", source_hex)
+            ]
+        );
+        assert_eq!(
+            artifact_service
+                .attestations(&format!("sha256:{}", source_hex))
+                .unwrap(),
+            vec![chain[1].clone()]
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_attestation_trusted_by_trust_store() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (artifact_service, ..) = test_util::tests::create_artifact_service(&tmp_dir);
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let statement = serde_json::json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "subject": [{ "digest": { "sha256": hex::encode(VALID_ARTIFACT_HASH) } }],
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "predicate": { "builder": { "id": "builder" } }
+        });
+        let envelope = Envelope::sign(
+            &keypair,
+            IN_TOTO_PAYLOAD_TYPE,
+            &serde_json::to_vec(&statement).unwrap(),
+        )
+        .unwrap();
+
+        artifact_service
+            .trust_store
+            .add(
+                &keypair.public().to_peer_id(),
+                vec![TrustRole::Verification],
+            )
+            .unwrap();
+        assert!(artifact_service
+            .ingest_attestation(envelope.clone())
+            .is_err());
+
+        artifact_service
+            .trust_store
+            .add(&keypair.public().to_peer_id(), vec![TrustRole::Attestation])
+            .unwrap();
+        assert_eq!(
+            artifact_service.ingest_attestation(envelope).unwrap().len(),
+            1
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_verify_document() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (artifact_service, ..) = test_util::tests::create_artifact_service(&tmp_dir);
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+        artifact_service
+            .trust_store
+            .add(&peer_id, vec![TrustRole::Verification])
+            .unwrap();
+
+        let mut document = SignedDocument::new(&keypair, "{}").unwrap();
+        let report = artifact_service.verify_document(&document);
+        assert!(report.valid);
+        assert_eq!(
+            report.signers,
+            vec![VerifiedSigner {
+                peer_id: peer_id.to_string(),
+                signed: "document".to_owned(),
+                valid: true,
+                trusted_roles: vec![TrustRole::Verification],
+            }]
+        );
+
+        document.content = "{\"tampered\":true}".to_owned();
+        let report = artifact_service.verify_document(&document);
+        assert!(!report.valid);
+        assert!(!report.signers[0].valid);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_node_roles() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, ..) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let local_peer_id = artifact_service.p2p_client.local_peer_id;
+        let other_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+
+        assert!(artifact_service.has_role(NodeRole::Regular));
+        assert!(!artifact_service.has_role(NodeRole::Build));
+        artifact_service.role = NodeRole::Build;
+        assert!(artifact_service.has_role(NodeRole::Build));
+        assert!(!artifact_service.has_role(NodeRole::Authority));
+        assert!(artifact_service.accepts_block_from(&Address::from(local_peer_id)));
+
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(other_peer_id)
+            .await
+            .unwrap();
+
+        assert!(!artifact_service.has_role(NodeRole::Build));
+        assert!(artifact_service.accepts_block_from(&Address::from(other_peer_id)));
+        assert!(!artifact_service.accepts_block_from(&Address::from(local_peer_id)));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_find_metadata() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let remote_keypair = identity::Keypair::generate_ed25519();
+        let remote_package = Package {
+            package_type: PackageType::Maven2,
+            name: String::from("com.company:library"),
+            description: None,
+        };
+        let remote_document = remote_package.sign(&remote_keypair).unwrap();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&keypair, &content));
+                    }
+                    Some(Command::PutSignedRecord { sender, .. }) => {
+                        let _ = sender.send(Err(anyhow!("no peers")));
+                    }
+                    Some(Command::GetSignedRecord { key, sender }) => {
+                        assert_eq!(key, "packages/Maven2/com.company:library");
+                        let _ = sender.send(Some(remote_document.clone()));
+                    }
+                    _ => panic!("Command must match Command::SignDocument, Command::PutSignedRecord or Command::GetSignedRecord"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { payload, sender }) => {
+                        let metadata: MetadataBlockPayload =
+                            serde_json::from_slice(&payload).unwrap();
+                        assert_eq!(
+                            metadata.metadata_key,
+                            "packages/Docker/library/alpine/versions/3.17"
+                        );
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_version = PackageVersion {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            version: String::from("3.17"),
+            artifacts: vec![ArtifactReference {
+                artifact_digest: format!("sha256:{}", hex::encode(VALID_ARTIFACT_HASH)),
+                media_type: None,
+            }],
+        };
+        assert!(artifact_service
+            .publish_metadata(&package_version)
+            .await
+            .is_err());
+        artifact_service.role = NodeRole::Build;
+        artifact_service
+            .publish_metadata(&package_version)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            artifact_service
+                .find_metadata::<PackageVersion>(&package_version.key())
+                .await
+                .unwrap(),
+            Some(package_version)
+        );
+        assert_eq!(
+            artifact_service
+                .find_metadata::<Package>(&remote_package.key())
+                .await
+                .unwrap(),
+            Some(remote_package)
+        );
+
+        let committed_package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/busybox"),
+            description: None,
+        };
+        let payload = serde_json::to_vec(&MetadataBlockPayload {
+            metadata_key: committed_package.key(),
+            document: committed_package.sign(&remote_keypair).unwrap(),
+        })
+        .unwrap();
+        artifact_service
+            .handle_block_added(vec![payload])
+            .await
+            .unwrap();
+        assert_eq!(
+            artifact_service
+                .artifact_storage
+                .signed_metadata(&committed_package.key())
+                .unwrap()
+                .map(|document| Package::from_signed_document(&document).unwrap()),
+            Some(committed_package)
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_committed_metadata_requires_authorized_publisher_and_matching_key() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, ..) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let authorized_keypair = identity::Keypair::generate_ed25519();
+        let package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            description: None,
+        };
+        let other_package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/busybox"),
+            description: None,
+        };
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(authorized_keypair.public().to_peer_id())
+            .await
+            .unwrap();
+
+        // A document of an authorized node that is replayed under the key of
+        // other metadata.
+        let payload = serde_json::to_vec(&MetadataBlockPayload {
+            metadata_key: other_package.key(),
+            document: package.sign(&authorized_keypair).unwrap(),
+        })
+        .unwrap();
+        assert!(artifact_service
+            .handle_block_added(vec![payload])
+            .await
+            .is_err());
+
+        // A document of a node that is not authorized.
+        let payload = serde_json::to_vec(&MetadataBlockPayload {
+            metadata_key: package.key(),
+            document: package
+                .sign(&identity::Keypair::generate_ed25519())
+                .unwrap(),
+        })
+        .unwrap();
+        assert!(artifact_service
+            .handle_block_added(vec![payload])
+            .await
+            .is_err());
+        assert!(artifact_service
+            .artifact_storage
+            .all_signed_metadata()
+            .unwrap()
+            .is_empty());
+
+        let payload = serde_json::to_vec(&MetadataBlockPayload {
+            metadata_key: package.key(),
+            document: package.sign(&authorized_keypair).unwrap(),
+        })
+        .unwrap();
+        artifact_service
+            .handle_block_added(vec![payload])
+            .await
+            .unwrap();
+        assert!(artifact_service
+            .artifact_storage
+            .signed_metadata(&package.key())
+            .unwrap()
+            .is_some());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_find_metadata_rejects_misplaced_and_untrusted_records() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        let remote_keypair = identity::Keypair::generate_ed25519();
+        let package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            description: None,
+        };
+        let other_package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/busybox"),
+            description: None,
+        };
+        let document = package.sign(&remote_keypair).unwrap();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::GetSignedRecord { sender, .. }) => {
+                        let _ = sender.send(Some(document.clone()));
+                    }
+                    _ => panic!("Command must match Command::GetSignedRecord"),
+                }
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        // A document that is replayed under the key of other metadata.
+        let result = artifact_service
+            .find_metadata::<Package>(&other_package.key())
+            .await;
+        assert!(result.unwrap_err().to_string().contains("belongs under"));
+
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(identity::Keypair::generate_ed25519().public().to_peer_id())
+            .await
+            .unwrap();
+        let result = artifact_service
+            .find_metadata::<Package>(&package.key())
+            .await;
+        assert!(result.unwrap_err().to_string().contains("is not accepted"));
+        assert!(artifact_service
+            .artifact_storage
+            .all_signed_metadata()
+            .unwrap()
+            .is_empty());
+
+        artifact_service
+            .trust_store
+            .add(
+                &remote_keypair.public().to_peer_id(),
+                vec![TrustRole::Verification],
+            )
+            .unwrap();
+        assert_eq!(
+            artifact_service
+                .find_metadata::<Package>(&package.key())
+                .await
+                .unwrap(),
+            Some(package)
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_federation_sync() {
+        let tmp_dir = test_util::tests::setup();
+        let source_dir = tmp_dir.join("source");
+        std::fs::create_dir_all(&source_dir).unwrap();
+
+        let (artifact_service, mut blockchain_event_receiver, ..) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+        let (mut source_service, _, _, mut source_p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&source_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+        let source_keypair = identity::Keypair::generate_ed25519();
+        let source_peer_id = source_keypair.public().to_peer_id();
+        tokio::spawn(async move {
+            loop {
+                match source_p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&source_keypair, &content));
+                    }
+                    _ => panic!("Command must match Command::SignDocument"),
+                }
+            }
+        });
+
+        let transparency_log = TransparencyLog::from(AddArtifactRequest {
+            package_type: PackageType::Docker,
+            package_specific_id: String::from("library/alpine:3.16"),
+            num_artifacts: 1,
+            package_specific_artifact_id: String::from("library/alpine:3.16"),
+            artifact_hash: String::from("test_hash"),
+        });
+        source_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+        let keypair = identity::Keypair::generate_ed25519();
+        let package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            description: None,
+        };
+        source_service
+            .artifact_storage
+            .store_signed_metadata(&package.key(), &package.sign(&keypair).unwrap())
+            .unwrap();
+
+        let sync = source_service
+            .federation_sync(&FederationSyncQuery::default())
+            .await
+            .unwrap();
+        assert!(!sync.has_more);
+        assert_eq!(sync.metadata.len(), 1);
+        let next_query = FederationSyncQuery {
+            log_sequence: sync.log_sequence,
+            metadata_sequence: sync.metadata_sequence,
+        };
+
+        // Entries of a node that is not authorized and metadata of a
+        // publisher that is not trusted are not stored.
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(identity::Keypair::generate_ed25519().public().to_peer_id())
+            .await
+            .unwrap();
+        assert_eq!(artifact_service.apply_federation_sync(sync).unwrap(), 0);
+        assert!(artifact_service
+            .transparency_log_service
+            .find_transparency_log(&transparency_log.id)
+            .is_err());
+
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(source_peer_id)
+            .await
+            .unwrap();
+        artifact_service
+            .trust_store
+            .add(
+                &keypair.public().to_peer_id(),
+                vec![TrustRole::Verification],
+            )
+            .unwrap();
+        let sync = source_service
+            .federation_sync(&FederationSyncQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(artifact_service.apply_federation_sync(sync).unwrap(), 2);
+        assert_eq!(
+            artifact_service
+                .transparency_log_service
+                .find_transparency_log(&transparency_log.id)
+                .unwrap()
+                .package_specific_id,
+            "library/alpine:3.16"
+        );
+        assert!(artifact_service
+            .artifact_storage
+            .signed_metadata(&package.key())
+            .unwrap()
+            .is_some());
+
+        // Entries that are already known are not stored again.
+        let sync = source_service
+            .federation_sync(&FederationSyncQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(artifact_service.apply_federation_sync(sync).unwrap(), 0);
+
+        // Metadata that is replayed under the key of other metadata is not
+        // stored.
+        let other_package = Package {
+            package_type: PackageType::Docker,
+            name: String::from("library/busybox"),
+            description: None,
+        };
+        let mut sync = source_service
+            .federation_sync(&FederationSyncQuery::default())
+            .await
+            .unwrap();
+        sync.metadata[0].metadata_key = other_package.key();
+        assert_eq!(artifact_service.apply_federation_sync(sync).unwrap(), 0);
+        assert!(artifact_service
+            .artifact_storage
+            .signed_metadata(&other_package.key())
+            .unwrap()
+            .is_none());
+
+        // A sync continues after the entries of the previous one.
+        let sync = source_service.federation_sync(&next_query).await.unwrap();
+        assert_eq!(sync.transparency_logs.content, "[]");
+        assert!(sync.metadata.is_empty());
+        assert_eq!(sync.log_sequence, next_query.log_sequence);
+        assert_eq!(sync.metadata_sequence, next_query.metadata_sequence);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_attach_sbom() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, _, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let local_peer_id = keypair.public().to_peer_id();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&keypair, &content));
+                    }
+                    _ => panic!("Command must match Command::SignDocument"),
+                }
+            }
+        });
+
+        let artifact_digest = hex::encode(VALID_ARTIFACT_HASH);
+        let content = r#"{"bomFormat":"CycloneDX","specVersion":"1.4"}"#;
+        assert!(artifact_service
+            .attach_sbom(&artifact_digest, "{}")
+            .await
+            .is_err());
+        let sbom = artifact_service
+            .attach_sbom(&artifact_digest, content)
+            .await
+            .unwrap();
+
+        assert_eq!(sbom.artifact_digest, format!("sha256:{}", artifact_digest));
+        assert_eq!(sbom.format, SbomFormat::CycloneDx);
+        assert!(sbom.document.verify());
+        assert_eq!(sbom.document.publisher(), Some(local_peer_id));
+        assert_eq!(
+            artifact_service
+                .sboms(&format!("sha256:{}", artifact_digest))
+                .unwrap(),
+            vec![sbom.clone()]
+        );
+        assert_eq!(
+            artifact_service
+                .artifact_storage
+                .find_sbom(&sbom.referrer_manifest_digest())
+                .unwrap(),
+            Some(sbom)
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_artifact_enforces_policy() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let _ = sender.send(HashSet::new());
+                    }
+                    _ => panic!("Command must match Command::ListPeers"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_type = PackageType::Docker;
+        let package_specific_artifact_id = "library/alpine:3.17";
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: String::from("library/alpine:3.17"),
+                num_artifacts: 8,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: hex::encode(VALID_ARTIFACT_HASH),
+            })
+            .await
+            .unwrap();
+        artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+        artifact_service
+            .put_artifact(
+                &transparency_log.artifact_id,
+                &mut get_file_reader().unwrap(),
+            )
+            .unwrap();
+
+        artifact_service.set_policy(Policy {
+            rules: vec![PolicyRule {
+                namespace: Some(String::from("library/")),
+                min_verifications: 2,
+                ..Default::default()
+            }],
+        });
+        let error = artifact_service
+            .get_artifact(package_type, package_specific_artifact_id)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<PolicyViolation>(),
+            Some(&PolicyViolation::InsufficientVerifications {
+                artifact: package_specific_artifact_id.to_owned(),
+                required: 2,
+                found: 1
+            })
+        );
+
+        let keypair = identity::Keypair::generate_ed25519();
+        let statement = serde_json::json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "subject": [{ "digest": { "sha256": hex::encode(VALID_ARTIFACT_HASH) } }],
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "predicate": {}
+        });
+        artifact_service.trusted_attestation_keys = vec![keypair.public().to_peer_id()];
+        artifact_service
+            .ingest_attestation(
+                Envelope::sign(
+                    &keypair,
+                    IN_TOTO_PAYLOAD_TYPE,
+                    &serde_json::to_vec(&statement).unwrap(),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(artifact_service
+            .get_artifact(package_type, package_specific_artifact_id)
+            .await
+            .is_ok());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+        artifact_service.role = NodeRole::Authority;
+
+        let keypair = identity::Keypair::generate_ed25519();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&keypair, &content));
+                    }
+                    Some(Command::StopProviding { sender, .. }) => {
+                        let _ = sender.send(());
+                    }
+                    _ => {
+                        panic!("Command must match Command::SignDocument or Command::StopProviding")
+                    }
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_type = PackageType::Docker;
+        let package_specific_artifact_id = "library/alpine:3.17";
+        let artifact_hash = hex::encode(VALID_ARTIFACT_HASH);
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: String::from("library/alpine:3.17"),
+                num_artifacts: 8,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: artifact_hash.clone(),
+            })
+            .await
+            .unwrap();
+        artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+        artifact_service
+            .put_artifact(
+                &transparency_log.artifact_id,
+                &mut get_file_reader().unwrap(),
+            )
+            .unwrap();
+
+        artifact_service
+            .revoke_artifact(&artifact_hash, "malicious")
+            .await
+            .unwrap();
+
+        let quarantined_artifacts = artifact_service
+            .artifact_storage
+            .list_quarantined()
+            .unwrap();
+        assert_eq!(quarantined_artifacts.len(), 1);
+        assert_eq!(
+            quarantined_artifacts[0].artifact_id,
+            transparency_log.artifact_id
+        );
+        assert_eq!(artifact_service.revocations().unwrap().len(), 1);
+
+        let error = artifact_service
+            .get_artifact(package_type, package_specific_artifact_id)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TransparencyLogError>(),
+            Some(TransparencyLogError::ArtifactRevoked { .. })
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let _ = sender.send(HashSet::new());
+                    }
+                    _ => panic!("Command must match Command::ListPeers"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_type = PackageType::Docker;
+        let package_specific_id = "package_specific_id";
+        let package_specific_artifact_id = "package_specific_artifact_id";
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: package_specific_id.to_owned(),
+                num_artifacts: 8,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: hex::encode(VALID_ARTIFACT_HASH),
+            })
+            .await
+            .unwrap();
+        artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+
+        //put the artifact
+        artifact_service
+            .put_artifact(
+                &transparency_log.artifact_id,
+                &mut get_file_reader().unwrap(),
+            )
+            .context("Error from put_artifact")
+            .unwrap();
+
+        // pull artifact
+        let future = {
+            artifact_service
+                .get_artifact(package_type, package_specific_artifact_id)
+                .await
+                .context("Error from get_artifact")
+        };
+        let file = task::spawn_blocking(|| future).await.unwrap().unwrap();
+
+        //validate pulled artifact with the actual data
+        let mut s = String::new();
+        get_file_reader().unwrap().read_to_string(&mut s).unwrap();
+
+        let s1 = match str::from_utf8(file.as_slice()) {
+            Ok(v) => v,
+            Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
+        };
+        assert_eq!(s, s1);
+
+        let metadata = artifact_service
+            .artifact_storage
+            .artifact_metadata(&transparency_log.artifact_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.local_pulls, 1);
+        assert_eq!(metadata.peer_serves, 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_verified_artifact_locally_quarantines_corrupted_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_type = PackageType::Docker;
+        let package_specific_artifact_id = "package_specific_artifact_id";
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: "package_specific_id".to_owned(),
+                num_artifacts: 1,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"),
+            })
+            .await
+            .unwrap();
+        artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+        artifact_service
+            .put_artifact(&transparency_log.artifact_id, &mut &b"OTHER_DATA"[..])
+            .unwrap();
+
+        artifact_service.verify_on_read = false;
+        let artifact = artifact_service
+            .get_artifact(package_type, package_specific_artifact_id)
+            .await
+            .unwrap();
+        assert_eq!(artifact, b"OTHER_DATA");
+
+        let artifact_id = transparency_log.artifact_id.clone();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::StopProviding {
+                        artifact_id: stopped_artifact_id,
+                        sender,
+                    }) => {
+                        assert_eq!(stopped_artifact_id, artifact_id);
+                        let _ = sender.send(());
+                    }
+                    _ => panic!("Command must match Command::StopProviding"),
+                }
+            }
+        });
+
+        let error = artifact_service
+            .get_verified_artifact_locally(&transparency_log.artifact_id)
+            .await
+            .expect_err("Corrupted artifact must not be served");
+        assert_eq!(
+            error.downcast::<StorageError>().unwrap(),
+            StorageError::Corrupted {
+                artifact_id: transparency_log.artifact_id.clone(),
+                expected_hash: HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"),
+                actual_hash: HashAlgorithm::Sha256.hash(b"OTHER_DATA"),
+            }
+        );
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size(&transparency_log.artifact_id)
+            .is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_scrub_artifacts() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::StopProviding { sender, .. }) => {
+                        let _ = sender.send(());
+                    }
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let _ = sender.send(Default::default());
+                    }
+                    _ => panic!(
+                        "Command must match Command::StopProviding or Command::ListProviders"
+                    ),
+                }
+            }
+        });
+
+        let mut artifact_ids = vec![];
+        for (package_specific_artifact_id, stored_data) in [
+            ("valid_artifact_id", b"SAMPLE_DATA"),
+            ("corrupted_artifact_id", b"OTHER_DATA_"),
+        ] {
+            let transparency_log = artifact_service
+                .transparency_log_service
+                .add_artifact(AddArtifactRequest {
+                    package_type: PackageType::Docker,
+                    package_specific_id: package_specific_artifact_id.to_owned(),
+                    num_artifacts: 1,
+                    package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                    artifact_hash: HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"),
+                })
+                .await
+                .unwrap();
+            artifact_service
+                .transparency_log_service
+                .write_transparency_log(&transparency_log)
+                .unwrap();
+            artifact_service
+                .put_artifact(&transparency_log.artifact_id, &mut &stored_data[..])
+                .unwrap();
+            artifact_ids.push(transparency_log.artifact_id);
+        }
+        artifact_service
+            .put_artifact("unknown_artifact_id", &mut &b"SAMPLE_DATA"[..])
+            .unwrap();
+
+        assert_eq!(artifact_service.last_scrub_report(), None);
+
+        let scrub_report = artifact_service.scrub_artifacts().await.unwrap();
+        assert_eq!(scrub_report.artifacts_checked, 3);
+        assert_eq!(scrub_report.artifacts_corrupted, 1);
+        assert_eq!(scrub_report.artifacts_refetched, 0);
+        assert_eq!(scrub_report.artifacts_unverifiable, 1);
+        assert_eq!(artifact_service.last_scrub_report(), Some(scrub_report));
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size(&artifact_ids[0])
+            .is_ok());
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size(&artifact_ids[1])
+            .is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_put_and_list_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let _ = sender.send(HashSet::new());
+                    }
+                    Some(Command::Provide { sender, .. }) => {
+                        let _ = sender.send(());
+                    }
+                    _ => panic!("Command must match Command::ListPeers or Command::Provide"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_type = PackageType::Docker;
+        let package_specific_id = "package_specific_id";
+        let package_specific_artifact_id = "package_specific_artifact_id";
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: package_specific_id.to_owned(),
+                num_artifacts: 8,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: hex::encode(VALID_ARTIFACT_HASH),
+            })
+            .await
+            .unwrap();
+        artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+
+        //put the artifact
+        artifact_service
+            .put_artifact(
+                &transparency_log.artifact_id,
+                &mut get_file_reader().unwrap(),
+            )
+            .context("Error from put_artifact")
+            .unwrap();
+
+        // provide artifacts
+        let future = {
+            artifact_service
+                .provide_local_artifacts()
+                .await
+                .context("Error from provide_local_artifacts")
+        };
+        let files = task::spawn_blocking(|| future).await.unwrap();
+        assert!(files.is_ok());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, mut blockchain_event_receiver, _) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let _ = sender.send(HashSet::new());
+                    },
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(p2p_client.local_peer_id);
+                        let _ = sender.send(set);
+                    },
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(Some(11)));
+                    },
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: (0.1_f64).to_le_bytes()
+                        }));
+                    },
+                    Some(Command::RequestArtifact { sender, .. }) => {
+                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                    },
+                    _ => panic!("Command must match Command::ListPeers, Command::ListProviders, Command::RequestArtifactAvailability, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let random_hash = hex::encode(hasher.finalize());
+
+        let package_type = PackageType::Docker;
+        let package_specific_id = "package_specific_id";
+        let package_specific_artifact_id = "package_specific_artifact_id";
+        let transparency_log = artifact_service
+            .transparency_log_service
+            .add_artifact(AddArtifactRequest {
+                package_type,
+                package_specific_id: package_specific_id.to_owned(),
+                num_artifacts: 8,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact_hash: random_hash.clone(),
+            })
             .await
             .unwrap();
         artifact_service
             .transparency_log_service
-            .write_transparency_log(&transparency_log)
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+
+        let future = {
+            artifact_service
+                .get_artifact(package_type, package_specific_artifact_id)
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert!(result.is_ok());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers_with_no_providers() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, _, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            tokio::select! {
+                command = p2p_command_receiver.recv() => {
+                    match command {
+                        Some(Command::ListProviders { sender, .. }) => {
+                            let _ = sender.send(Default::default());
+                        },
+                        _ => panic!("Command must match Command::ListProviders"),
+                    }
+                }
+            }
+        });
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let hash_bytes = hasher.finalize();
+        let artifact_id = hex::encode(hash_bytes);
+
+        let future = {
+            artifact_service
+                .get_artifact_from_peers(&artifact_id, &artifact_id)
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert!(result.is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers_with_only_stale_providers() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(p2p_client.local_peer_id);
+                        let _ = sender.send(set);
+                    }
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(None));
+                    }
+                    _ => panic!("Command must match Command::ListProviders, Command::RequestArtifactAvailability"),
+                }
+            }
+        });
+
+        let future = {
+            artifact_service
+                .get_artifact_from_peers("artifact_id", "artifact_hash")
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert!(result.is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers_reserves_space_before_transfer() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        artifact_service.artifact_storage.disk_allocated = Some(10);
+
+        let transferred = Arc::new(AtomicBool::new(false));
+        let artifact_transferred = transferred.clone();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(p2p_client.local_peer_id);
+                        let _ = sender.send(set);
+                    }
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(Some(11)));
+                    }
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(HashMap::new());
+                    }
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: 0.1_f64.to_le_bytes(),
+                        }));
+                    }
+                    Some(Command::RequestArtifact { sender, .. }) => {
+                        artifact_transferred.store(true, Ordering::SeqCst);
+                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                    }
+                    _ => panic!("Command must match Command::ListProviders, Command::RequestArtifactAvailability, Command::ListPeerLatencies, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        let future = {
+            artifact_service
+                .get_artifact_from_peers("artifact_id", "artifact_hash")
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert!(result.is_err());
+        // the artifact is not transferred when it doesn't fit
+        assert!(!transferred.load(Ordering::SeqCst));
+        assert_eq!(artifact_service.artifact_storage.reserved_space(), 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers_retries_when_peer_is_busy() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let busy_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let other_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let _ = sender.send(HashSet::from([busy_peer_id, other_peer_id]));
+                    }
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(Some(11)));
+                    }
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(HashMap::new());
+                    }
+                    Some(Command::RequestIdleMetric { peer, sender }) => {
+                        // the busy peer advertised a lower metric before it got busy
+                        let idle_metric = if peer == busy_peer_id { 0.1_f64 } else { 0.9_f64 };
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: idle_metric.to_le_bytes(),
+                        }));
+                    }
+                    Some(Command::RequestArtifact { peer, sender, .. }) => {
+                        if peer == busy_peer_id {
+                            let _ = sender.send(Err(PeerBusy.into()));
+                        } else {
+                            let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                        }
+                    }
+                    _ => panic!("Command must match Command::ListProviders, Command::RequestArtifactAvailability, Command::ListPeerLatencies, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let artifact_id = hex::encode(hasher.finalize());
+
+        let future = {
+            artifact_service
+                .get_artifact_from_peers(&artifact_id, &artifact_id)
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert_eq!(result.unwrap(), b"SAMPLE_DATA".to_vec());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_from_peers_falls_back_to_providers_with_unknown_availability() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let stale_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let old_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let _ = sender.send(HashSet::from([stale_peer_id, old_peer_id]));
+                    }
+                    Some(Command::RequestArtifactAvailability { peer, sender, .. }) => {
+                        if peer == stale_peer_id {
+                            let _ = sender.send(Ok(None));
+                        } else {
+                            // the old peer doesn't support the availability protocol
+                            let _ = sender.send(Err(anyhow!("unsupported protocol")));
+                        }
+                    }
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: (0.1_f64).to_le_bytes(),
+                        }));
+                    }
+                    Some(Command::RequestArtifact { peer, sender, .. }) => {
+                        assert_eq!(peer, old_peer_id);
+                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                    }
+                    _ => panic!("Command must match Command::ListProviders, Command::RequestArtifactAvailability, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let artifact_id = hex::encode(hasher.finalize());
+
+        let future = {
+            artifact_service
+                .get_artifact_from_peers(&artifact_id, &artifact_id)
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert_eq!(result.unwrap(), b"SAMPLE_DATA".to_vec());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_artifact_resolved_from_name_record() {
+        let tmp_dir = test_util::tests::setup();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let artifact_hash = hex::encode(hasher.finalize());
+        let name_record = NameRecord::new(
+            &identity::Keypair::generate_ed25519(),
+            "Docker/package_specific_artifact_id",
+            "artifact_id",
+            &artifact_hash,
+        )
+        .unwrap();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ResolveName { name, sender, .. }) => {
+                        assert_eq!(name, "Docker/package_specific_artifact_id");
+                        let _ = sender.send(Some(name_record.clone()));
+                    }
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(p2p_client.local_peer_id);
+                        let _ = sender.send(set);
+                    }
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(Some(11)));
+                    }
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: (0.1_f64).to_le_bytes(),
+                        }));
+                    }
+                    Some(Command::RequestArtifact { artifact_id, sender, .. }) => {
+                        assert_eq!(artifact_id, "artifact_id");
+                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                    }
+                    _ => panic!("Command must match Command::ResolveName, Command::ListProviders, Command::RequestArtifactAvailability, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        let future = {
+            artifact_service
+                .get_artifact(PackageType::Docker, "package_specific_artifact_id")
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert_eq!(result.unwrap(), b"SAMPLE_DATA".to_vec());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_artifact_from_name_record_fails_when_hashes_differ() {
+        let tmp_dir = test_util::tests::setup();
+
+        let name_record = NameRecord::new(
+            &identity::Keypair::generate_ed25519(),
+            "Docker/package_specific_artifact_id",
+            "artifact_id",
+            "other_hash",
+        )
+        .unwrap();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ResolveName { sender, .. }) => {
+                        let _ = sender.send(Some(name_record.clone()));
+                    }
+                    Some(Command::ListProviders { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(p2p_client.local_peer_id);
+                        let _ = sender.send(set);
+                    }
+                    Some(Command::RequestArtifactAvailability { sender, .. }) => {
+                        let _ = sender.send(Ok(Some(11)));
+                    }
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: (0.1_f64).to_le_bytes(),
+                        }));
+                    }
+                    Some(Command::RequestArtifact { sender, .. }) => {
+                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
+                    }
+                    _ => panic!("Command must match Command::ResolveName, Command::ListProviders, Command::RequestArtifactAvailability, Command::RequestIdleMetric, Command::RequestArtifact"),
+                }
+            }
+        });
+
+        let future = {
+            artifact_service
+                .get_artifact(PackageType::Docker, "package_specific_artifact_id")
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert!(result.is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_artifact_from_name_record_of_unauthorized_publisher_fails() {
+        let tmp_dir = test_util::tests::setup();
+
+        let name_record = NameRecord::new(
+            &identity::Keypair::generate_ed25519(),
+            "Docker/package_specific_artifact_id",
+            "artifact_id",
+            "artifact_hash",
+        )
+        .unwrap();
+        let authorized_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, mut blockchain_event_receiver, _) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ResolveName {
+                        publishers, sender, ..
+                    }) => {
+                        assert_eq!(publishers, Some(HashSet::from([authorized_peer_id])));
+                        let _ = sender.send(Some(name_record.clone()));
+                    }
+                    _ => panic!("Command must match Command::ResolveName"),
+                }
+            }
+        });
+
+        artifact_service
+            .transparency_log_service
+            .add_authorized_node(authorized_peer_id)
+            .await
             .unwrap();
 
-        //put the artifact
+        let result = artifact_service
+            .get_artifact_by_name_record(PackageType::Docker, "package_specific_artifact_id")
+            .await;
+        assert!(result.unwrap_err().to_string().contains("is not accepted"));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_artifact_from_name_record_of_revoked_artifact_fails() {
+        let tmp_dir = test_util::tests::setup();
+
+        let name_record = NameRecord::new(
+            &identity::Keypair::generate_ed25519(),
+            "Docker/package_specific_artifact_id",
+            "artifact_id",
+            "artifact_hash",
+        )
+        .unwrap();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ResolveName { sender, .. }) => {
+                        let _ = sender.send(Some(name_record.clone()));
+                    }
+                    _ => panic!("Command must match Command::ResolveName"),
+                }
+            }
+        });
+
+        let revocation = Revocation {
+            artifact_hash: "artifact_hash".to_owned(),
+            reason: "malicious".to_owned(),
+        };
         artifact_service
-            .put_artifact(
-                &transparency_log.artifact_id,
-                &mut get_file_reader().unwrap(),
+            .transparency_log_service
+            .write_revocation(
+                &SignedDocument::new(
+                    &identity::Keypair::generate_ed25519(),
+                    &serde_json::to_string(&revocation).unwrap(),
+                )
+                .unwrap(),
             )
-            .context("Error from put_artifact")
             .unwrap();
 
-        // pull artifact
-        let future = {
-            artifact_service
-                .get_artifact(package_type, package_specific_artifact_id)
-                .await
-                .context("Error from get_artifact")
-        };
-        let file = task::spawn_blocking(|| future).await.unwrap().unwrap();
+        let result = artifact_service
+            .get_artifact_by_name_record(PackageType::Docker, "package_specific_artifact_id")
+            .await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<TransparencyLogError>(),
+            Some(TransparencyLogError::ArtifactRevoked { .. })
+        ));
 
-        //validate pulled artifact with the actual data
-        let mut s = String::new();
-        get_file_reader().unwrap().read_to_string(&mut s).unwrap();
+        test_util::tests::teardown(tmp_dir);
+    }
 
-        let s1 = match str::from_utf8(file.as_slice()) {
-            Ok(v) => v,
-            Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
-        };
-        assert_eq!(s, s1);
+    #[tokio::test]
+    async fn test_push_generic_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, mut blockchain_event_receiver, _) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::Provide { sender, .. }) => {
+                        let _ = sender.send(());
+                    }
+                    Some(Command::PutNameRecord { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("Command must match Command::Provide or Command::PutNameRecord"),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        assert!(artifact_service
+            .push_generic_artifact("release.tar.gz", b"SAMPLE_DATA")
+            .await
+            .is_err());
+
+        artifact_service.role = NodeRole::Build;
+        let transparency_log = artifact_service
+            .push_generic_artifact("release.tar.gz", b"SAMPLE_DATA")
+            .await
+            .unwrap();
+        assert_eq!(transparency_log.package_type, Some(PackageType::Generic));
+        assert_eq!(
+            transparency_log.artifact_hash,
+            HashAlgorithm::Sha256.hash(b"SAMPLE_DATA")
+        );
+
+        let artifact = artifact_service
+            .get_artifact(PackageType::Generic, "release.tar.gz")
+            .await
+            .unwrap();
+        assert_eq!(artifact, b"SAMPLE_DATA");
+
+        assert!(artifact_service
+            .push_generic_artifact("release.tar.gz", b"OTHER_DATA")
+            .await
+            .is_err());
 
         test_util::tests::teardown(tmp_dir);
     }
 
     #[tokio::test]
-    async fn test_put_and_list_artifact() {
+    async fn test_store_replicated_artifact() {
         let tmp_dir = test_util::tests::setup();
 
-        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
-            test_util::tests::create_artifact_service(&tmp_dir);
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, mut blockchain_event_receiver, _) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client);
 
         tokio::spawn(async move {
             loop {
                 match p2p_command_receiver.recv().await {
-                    Some(Command::ListPeers { sender, .. }) => {
-                        let _ = sender.send(HashSet::new());
-                    }
                     Some(Command::Provide { sender, .. }) => {
                         let _ = sender.send(());
                     }
-                    _ => panic!("Command must match Command::ListPeers or Command::Provide"),
+                    _ => panic!("Command must match Command::Provide"),
                 }
             }
         });
@@ -524,17 +3825,20 @@ mod tests {
             }
         });
 
+        let mut hasher = Sha256::new();
+        hasher.update(b"SAMPLE_DATA");
+        let artifact_hash = hex::encode(hasher.finalize());
+
         let package_type = PackageType::Docker;
-        let package_specific_id = "package_specific_id";
         let package_specific_artifact_id = "package_specific_artifact_id";
         let transparency_log = artifact_service
             .transparency_log_service
             .add_artifact(AddArtifactRequest {
                 package_type,
-                package_specific_id: package_specific_id.to_owned(),
-                num_artifacts: 8,
+                package_specific_id: "package_specific_id".to_owned(),
+                num_artifacts: 1,
                 package_specific_artifact_id: package_specific_artifact_id.to_owned(),
-                artifact_hash: hex::encode(VALID_ARTIFACT_HASH),
+                artifact_hash,
             })
             .await
             .unwrap();
@@ -543,60 +3847,106 @@ mod tests {
             .write_transparency_log(&transparency_log)
             .unwrap();
 
-        //put the artifact
-        artifact_service
-            .put_artifact(
-                &transparency_log.artifact_id,
-                &mut get_file_reader().unwrap(),
-            )
-            .context("Error from put_artifact")
-            .unwrap();
+        let result = artifact_service
+            .store_replicated_artifact(package_type, package_specific_artifact_id, b"OTHER_DATA")
+            .await;
+        assert!(!result.unwrap());
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size(&transparency_log.artifact_id)
+            .is_err());
 
-        // provide artifacts
-        let future = {
+        let result = artifact_service
+            .store_replicated_artifact(package_type, package_specific_artifact_id, b"SAMPLE_DATA")
+            .await;
+        assert!(result.unwrap());
+        assert_eq!(
             artifact_service
-                .provide_local_artifacts()
-                .await
-                .context("Error from provide_local_artifacts")
-        };
-        let files = task::spawn_blocking(|| future).await.unwrap();
-        assert!(files.is_ok());
+                .artifact_storage
+                .artifact_size(&transparency_log.artifact_id)
+                .unwrap(),
+            11
+        );
+
+        let result = artifact_service
+            .store_replicated_artifact(package_type, "unknown_artifact_id", b"SAMPLE_DATA")
+            .await;
+        assert!(!result.unwrap());
 
         test_util::tests::teardown(tmp_dir);
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_get_from_peers() {
+    async fn test_push_artifact_to_peers() {
         let tmp_dir = test_util::tests::setup();
 
+        let provider_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let accepting_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+        let refusing_peer_id = identity::Keypair::generate_ed25519().public().to_peer_id();
+
         let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
-        let (mut artifact_service, mut blockchain_event_receiver, _) =
+        let (mut artifact_service, ..) =
             test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        artifact_service.replication_factor = 2;
 
+        let local_peer_id = p2p_client.local_peer_id;
         tokio::spawn(async move {
             loop {
                 match p2p_command_receiver.recv().await {
-                    Some(Command::ListPeers { sender, .. }) => {
-                        let _ = sender.send(HashSet::new());
-                    },
                     Some(Command::ListProviders { sender, .. }) => {
-                        let mut set = HashSet::new();
-                        set.insert(p2p_client.local_peer_id);
-                        let _ = sender.send(set);
-                    },
-                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(HashSet::from([local_peer_id, provider_peer_id]));
+                    }
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let _ = sender.send(HashSet::from([
+                            provider_peer_id,
+                            accepting_peer_id,
+                            refusing_peer_id,
+                        ]));
+                    }
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(Default::default());
+                    }
+                    Some(Command::RequestIdleMetric { peer, sender }) => {
+                        assert_ne!(peer, provider_peer_id);
                         let _ = sender.send(Ok(PeerMetrics {
-                            idle_metric: (0.1_f64).to_le_bytes()
+                            idle_metric: (0.1_f64).to_le_bytes(),
                         }));
-                    },
-                    Some(Command::RequestArtifact { sender, .. }) => {
-                        let _ = sender.send(Ok(b"SAMPLE_DATA".to_vec()));
-                    },
-                    _ => panic!("Command must match Command::ListPeers, Command::ListProviders, Command::RequestIdleMetric, Command::RequestArtifact"),
+                    }
+                    Some(Command::RequestArtifactReplication { peer, artifact, sender, .. }) => {
+                        assert_eq!(artifact, b"SAMPLE_DATA".to_vec());
+                        let _ = sender.send(Ok(peer == accepting_peer_id));
+                    }
+                    _ => panic!("Command must match Command::ListProviders, Command::ListPeers, Command::ListPeerLatencies, Command::RequestIdleMetric, Command::RequestArtifactReplication"),
                 }
             }
         });
 
+        artifact_service
+            .put_artifact("artifact_id", &mut b"SAMPLE_DATA".as_slice())
+            .unwrap();
+
+        let future = {
+            artifact_service
+                .push_artifact_to_peers(
+                    PackageType::Docker,
+                    "package_specific_artifact_id",
+                    "artifact_id",
+                )
+                .await
+        };
+        let result = task::spawn_blocking(|| future).await.unwrap();
+        assert_eq!(result.unwrap(), 2);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_evict_artifacts_by_hash() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, mut blockchain_event_receiver, _, mut p2p_command_receiver) =
+            test_util::tests::create_artifact_service(&tmp_dir);
+
         tokio::spawn(async move {
             loop {
                 match blockchain_event_receiver.recv().await {
@@ -608,21 +3958,15 @@ mod tests {
             }
         });
 
-        let mut hasher = Sha256::new();
-        hasher.update(b"SAMPLE_DATA");
-        let random_hash = hex::encode(hasher.finalize());
-
-        let package_type = PackageType::Docker;
-        let package_specific_id = "package_specific_id";
-        let package_specific_artifact_id = "package_specific_artifact_id";
+        let artifact_hash = hex::encode(VALID_ARTIFACT_HASH);
         let transparency_log = artifact_service
             .transparency_log_service
             .add_artifact(AddArtifactRequest {
-                package_type,
-                package_specific_id: package_specific_id.to_owned(),
+                package_type: PackageType::Docker,
+                package_specific_id: String::from("library/alpine:3.17"),
                 num_artifacts: 8,
-                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
-                artifact_hash: random_hash.clone(),
+                package_specific_artifact_id: String::from("library/alpine:3.17"),
+                artifact_hash: artifact_hash.clone(),
             })
             .await
             .unwrap();
@@ -630,46 +3974,109 @@ mod tests {
             .transparency_log_service
             .write_transparency_log(&transparency_log)
             .unwrap();
+        artifact_service
+            .put_artifact(
+                &transparency_log.artifact_id,
+                &mut get_file_reader().unwrap(),
+            )
+            .unwrap();
+        artifact_service
+            .artifact_storage
+            .pin_artifact(&transparency_log.artifact_id)
+            .unwrap();
 
-        let future = {
-            artifact_service
-                .get_artifact(package_type, package_specific_artifact_id)
-                .await
-        };
-        let result = task::spawn_blocking(|| future).await.unwrap();
-        assert!(result.is_ok());
+        let expected_artifact_id = transparency_log.artifact_id.clone();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::StopProviding {
+                        artifact_id,
+                        sender,
+                    }) => {
+                        assert_eq!(artifact_id, expected_artifact_id);
+                        let _ = sender.send(());
+                    }
+                    _ => panic!("Command must match Command::StopProviding"),
+                }
+            }
+        });
+
+        let evicted_artifact_ids = artifact_service
+            .evict_artifacts_by_hash(&artifact_hash)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            evicted_artifact_ids,
+            vec![transparency_log.artifact_id.clone()]
+        );
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size(&transparency_log.artifact_id)
+            .is_err());
+        assert!(artifact_service
+            .evict_artifacts_by_hash(&artifact_hash)
+            .await
+            .unwrap()
+            .is_empty());
 
         test_util::tests::teardown(tmp_dir);
     }
 
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_get_from_peers_with_no_providers() {
+    #[tokio::test]
+    async fn test_collect_garbage_stops_providing_evicted_artifacts() {
         let tmp_dir = test_util::tests::setup();
 
-        let (mut artifact_service, _, _, mut p2p_command_receiver) =
-            test_util::tests::create_artifact_service(&tmp_dir);
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, _, _) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client);
+
+        artifact_service
+            .artifact_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "evictable_artifact_id")
+            .unwrap();
+        artifact_service
+            .artifact_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "pinned_artifact_id")
+            .unwrap();
+        artifact_service
+            .artifact_storage
+            .pin_artifact("pinned_artifact_id")
+            .unwrap();
+        artifact_service.artifact_storage.disk_allocated = Some(22);
 
         tokio::spawn(async move {
-            tokio::select! {
-                command = p2p_command_receiver.recv() => {
-                    match command {
-                        Some(Command::ListProviders { sender, .. }) => {
-                            let _ = sender.send(Default::default());
-                        },
-                        _ => panic!("Command must match Command::ListProviders"),
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::StopProviding {
+                        artifact_id,
+                        sender,
+                    }) => {
+                        assert_eq!(artifact_id, "evictable_artifact_id");
+                        let _ = sender.send(());
                     }
+                    _ => panic!("Command must match Command::StopProviding"),
                 }
             }
         });
 
-        let mut hasher = Sha256::new();
-        hasher.update(b"SAMPLE_DATA");
-        let hash_bytes = hasher.finalize();
-        let artifact_id = hex::encode(hash_bytes);
+        artifact_service.collect_garbage().await.unwrap();
 
-        let future = { artifact_service.get_artifact_from_peers(&artifact_id).await };
-        let result = task::spawn_blocking(|| future).await.unwrap();
-        assert!(result.is_err());
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size("evictable_artifact_id")
+            .is_err());
+        assert!(artifact_service
+            .artifact_storage
+            .artifact_size("pinned_artifact_id")
+            .is_ok());
+        assert_eq!(
+            artifact_service
+                .artifact_storage
+                .eviction_stats()
+                .evicted_artifacts,
+            1
+        );
 
         test_util::tests::teardown(tmp_dir);
     }
@@ -739,6 +4146,34 @@ mod tests {
         test_util::tests::teardown(tmp_dir);
     }
 
+    #[tokio::test]
+    async fn test_verify_artifact_with_other_hash_algorithms() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (mut artifact_service, ..) = test_util::tests::create_artifact_service(&tmp_dir);
+
+        for algorithm in [HashAlgorithm::Sha512, HashAlgorithm::Blake3] {
+            let transparency_log = TransparencyLog::from(AddArtifactRequest {
+                package_type: PackageType::Docker,
+                package_specific_id: "package_specific_id".to_owned(),
+                num_artifacts: 1,
+                package_specific_artifact_id: "package_specific_artifact_id".to_owned(),
+                artifact_hash: format!("{}:{}", algorithm, algorithm.hash(b"SAMPLE_DATA")),
+            });
+
+            assert!(artifact_service
+                .verify_artifact(&transparency_log, b"SAMPLE_DATA")
+                .await
+                .is_ok());
+            assert!(artifact_service
+                .verify_artifact(&transparency_log, b"OTHER_DATA")
+                .await
+                .is_err());
+        }
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     #[tokio::test]
     async fn test_verify_artifact_fails_when_hashes_differ() {
         let tmp_dir = test_util::tests::setup();
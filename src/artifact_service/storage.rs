@@ -14,16 +14,53 @@
    limitations under the License.
 */
 
+use super::attestation::Attestation;
+use super::compression::{self, CountingReader};
+use super::hashing::HashingReader;
+use super::metadata_index::{ArtifactMetadata, MetadataIndex};
+use super::model::{PackageType, QuarantinedArtifact, RepositoryUsage};
+use super::sbom::Sbom;
+use super::storage_backend::filesystem::FileSystemBackend;
+use super::storage_backend::StorageBackend;
+use crate::network::signed_document::SignedDocument;
 use crate::util::env_util::read_var;
 use anyhow::{anyhow, Context, Result};
 use lazy_static::lazy_static;
-use log::{debug, error, info};
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufWriter, Read, Write};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
 use std::panic::UnwindSafe;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::instrument;
+use uuid::Uuid;
 
-const FILE_EXTENSION: &str = "file";
+const PIN_EXTENSION: &str = "pin";
+const QUARANTINE_DIR: &str = "quarantine";
+const INDEX_DIR: &str = "artifact_index";
+const STAGING_DIR: &str = "staging";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StorageError {
+    #[error("Artifact {artifact_id} is corrupted in storage: expected hash {expected_hash} but found {actual_hash}")]
+    Corrupted {
+        artifact_id: String,
+        expected_hash: String,
+        actual_hash: String,
+    },
+    #[error("Artifact {artifact_id} does not match its expected hash {expected_hash}: found {actual_hash}")]
+    DigestMismatch {
+        artifact_id: String,
+        expected_hash: String,
+        actual_hash: String,
+    },
+}
 
 lazy_static! {
     pub static ref ARTIFACTS_DIR: String = {
@@ -65,17 +102,205 @@ fn log_static_initialization_failure<T: UnwindSafe>(
     }
 }
 
+/// The percentages of the allocated disk space at which garbage collection
+/// starts (high) and stops (low) evicting artifacts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GcWatermarks {
+    pub high: u8,
+    pub low: u8,
+}
+
+impl Default for GcWatermarks {
+    fn default() -> Self {
+        GcWatermarks { high: 90, low: 75 }
+    }
+}
+
+/// Counters of the artifacts that were evicted by garbage collection.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct EvictionStats {
+    pub evicted_artifacts: u64,
+    pub evicted_bytes: u64,
+}
+
+// The artifacts that are being written to the storage, so that concurrent
+// writers of the same artifact wait for each other instead of both writing it.
+#[derive(Default)]
+struct ActiveWriters {
+    artifact_ids: Mutex<HashSet<String>>,
+    finished: Condvar,
+}
+
+impl ActiveWriters {
+    // Wait until no other writer is storing the artifact and register this writer.
+    fn register(self: &Arc<Self>, artifact_id: &str) -> ActiveWriter {
+        let wait_for_writers = || {
+            let mut artifact_ids = self.artifact_ids.lock().unwrap();
+            while artifact_ids.contains(artifact_id) {
+                artifact_ids = self.finished.wait(artifact_ids).unwrap();
+            }
+            artifact_ids.insert(artifact_id.to_owned());
+        };
+        let contended = self.artifact_ids.lock().unwrap().contains(artifact_id);
+        match Handle::try_current() {
+            // a worker of the async runtime hands off its other tasks before
+            // it waits for the other writer to finish
+            Ok(handle) if contended && handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(wait_for_writers)
+            }
+            _ => wait_for_writers(),
+        }
+        ActiveWriter {
+            writers: self.clone(),
+            artifact_id: artifact_id.to_owned(),
+        }
+    }
+}
+
+struct ActiveWriter {
+    writers: Arc<ActiveWriters>,
+    artifact_id: String,
+}
+
+impl Drop for ActiveWriter {
+    fn drop(&mut self) {
+        self.writers
+            .artifact_ids
+            .lock()
+            .unwrap()
+            .remove(&self.artifact_id);
+        self.writers.finished.notify_all();
+    }
+}
+
+// The disk space that is reserved for artifacts that are about to be
+// written, so that concurrent writers can't collectively exceed the quota.
+// Each reservation has its own key, as the same artifact may be reserved
+// by concurrent transfers.
+#[derive(Default)]
+struct SpaceReservations {
+    next_key: AtomicU64,
+    reserved_bytes: Mutex<HashMap<u64, ReservedSpace>>,
+}
+
+struct ReservedSpace {
+    artifact_id: String,
+    size: u64,
+}
+
+impl SpaceReservations {
+    fn total(&self) -> u64 {
+        self.reserved_bytes
+            .lock()
+            .unwrap()
+            .values()
+            .map(|reserved_space| reserved_space.size)
+            .sum()
+    }
+
+    fn total_except(&self, artifact_id: &str) -> u64 {
+        reserved_except(&self.reserved_bytes.lock().unwrap(), artifact_id)
+    }
+}
+
+// The space reserved for all artifacts except the specified one.
+fn reserved_except(reserved_bytes: &HashMap<u64, ReservedSpace>, artifact_id: &str) -> u64 {
+    reserved_bytes
+        .values()
+        .filter(|reserved_space| reserved_space.artifact_id != artifact_id)
+        .map(|reserved_space| reserved_space.size)
+        .sum()
+}
+
+/// Disk space reserved for an artifact that is about to be written. The
+/// reservation is released when it is dropped, whether the artifact was
+/// written or the transfer was aborted.
+pub struct SpaceReservation {
+    reservations: Arc<SpaceReservations>,
+    key: u64,
+}
+
+impl Drop for SpaceReservation {
+    fn drop(&mut self) {
+        self.reservations
+            .reserved_bytes
+            .lock()
+            .unwrap()
+            .remove(&self.key);
+    }
+}
+
 #[derive(Clone)]
 pub struct ArtifactStorage {
     repository_path: PathBuf,
+    backend: Arc<dyn StorageBackend>,
+    index: Arc<MetadataIndex>,
+    /// The maximum number of bytes that stored artifacts may occupy.
+    /// No quota is enforced when not set.
+    pub disk_allocated: Option<u64>,
+    /// The thresholds used by garbage collection when `disk_allocated` is set.
+    pub gc_watermarks: GcWatermarks,
+    /// Whether artifacts are compressed when they are stored. Artifacts in
+    /// formats that are compressed already are always stored as is.
+    pub compress_artifacts: bool,
+    eviction_stats: Arc<Mutex<EvictionStats>>,
+    active_writers: Arc<ActiveWriters>,
+    space_reservations: Arc<SpaceReservations>,
+    io_bytes: Arc<AtomicU64>,
+}
+
+// Counts the bytes read from the backend as I/O of the storage.
+struct IoCountingReader {
+    inner: Box<dyn Read + Send>,
+    io_bytes: Arc<AtomicU64>,
+}
+
+impl Read for IoCountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.io_bytes.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+// The sizes of an artifact that was written to a staging key.
+struct StagedArtifact {
+    size: u64,
+    stored_size: u64,
+    compressed: bool,
 }
 
 impl ArtifactStorage {
     pub fn new<P: AsRef<Path>>(repository_path: P) -> Result<ArtifactStorage, anyhow::Error> {
+        let absolute_path = repository_path.as_ref().to_path_buf().canonicalize()?;
+        let backend = Arc::new(FileSystemBackend::new(absolute_path.clone()));
+        ArtifactStorage::new_with_backend(absolute_path, backend)
+    }
+
+    /// Create an artifact storage that keeps the bytes of artifacts in the
+    /// specified backend. The repository path holds the local state of the
+    /// storage, like the pinned artifacts.
+    pub fn new_with_backend<P: AsRef<Path>>(
+        repository_path: P,
+        backend: Arc<dyn StorageBackend>,
+    ) -> Result<ArtifactStorage, anyhow::Error> {
         let absolute_path = repository_path.as_ref().to_path_buf().canonicalize()?;
         if absolute_path.is_dir() {
+            let index = MetadataIndex::open(
+                absolute_path.join(INDEX_DIR).join(backend.name()),
+                backend.as_ref(),
+            )?;
             Ok(ArtifactStorage {
                 repository_path: absolute_path,
+                backend,
+                index: Arc::new(index),
+                disk_allocated: None,
+                gc_watermarks: GcWatermarks::default(),
+                compress_artifacts: false,
+                eviction_stats: Arc::new(Mutex::new(EvictionStats::default())),
+                active_writers: Arc::new(ActiveWriters::default()),
+                space_reservations: Arc::new(SpaceReservations::default()),
+                io_bytes: Arc::new(AtomicU64::new(0)),
             })
         } else {
             error!(
@@ -86,31 +311,17 @@ impl ArtifactStorage {
         }
     }
 
-    // The base file path (no extension on the file name) that will correspond to this artifact id.
-    // The structure of the path is: `repo_root_dir/artifact_id`. This consists of the artifact
-    // repository root directory and a file name that is the artifact id. For example:
-    // `pyrsia-artifacts/e131322a-0c72-454d-b7a0-dcdb53c1bbdf
-    //
-    // TODO To support nodes that will store many files, we need a scheme that will start separating
-    // files by subdirectories based on the first n bytes of the artifact id.
-    fn base_file_path(&self, artifact_id: &str) -> PathBuf {
-        let mut path: PathBuf = PathBuf::from(&self.repository_path);
-        path.push(artifact_id);
-        path
-    }
-
-    fn artifact_file_path(&self, artifact_id: &str) -> io::Result<PathBuf> {
-        let mut base_file_path: PathBuf = self.base_file_path(artifact_id);
-        base_file_path.set_extension(FILE_EXTENSION);
-        Ok(base_file_path)
+    fn pin_file_path(&self, artifact_id: &str) -> PathBuf {
+        self.repository_path
+            .join(format!("{}.{}", artifact_id, PIN_EXTENSION))
     }
 
-    fn create_artifact_file(&self, artifact_id: &str) -> io::Result<File> {
-        let artifact_file_path = self.artifact_file_path(artifact_id)?;
-        OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(artifact_file_path)
+    // The key under which the bytes of an artifact are kept in the backend.
+    fn backend_key(&self, artifact_id: &str) -> io::Result<String> {
+        match self.index.get(artifact_id)? {
+            Some(metadata) => Ok(metadata.blob_key()),
+            None => Ok(artifact_id.to_owned()),
+        }
     }
 
     /// Push an artifact to this node's local repository.
@@ -123,11 +334,237 @@ impl ArtifactStorage {
             "An artifact is being pushed to the artifact manager {}",
             artifact_id
         );
+        self.store_artifact(reader, artifact_id, None)
+    }
 
-        let artifact_file = self.create_artifact_file(artifact_id)?;
-        let mut writer = BufWriter::new(artifact_file);
-        io::copy(reader, &mut writer)?;
-        writer.flush()
+    /// Push an artifact to this node's local repository and verify its hash
+    /// while it is being written. The artifact is not stored when its hash
+    /// doesn't match `expected_hash`.
+    #[instrument(skip(self, reader))]
+    pub fn push_verified_artifact(
+        &self,
+        reader: &mut impl Read,
+        artifact_id: &str,
+        expected_hash: &str,
+    ) -> io::Result<()> {
+        info!(
+            "A verified artifact is being pushed to the artifact manager {}",
+            artifact_id
+        );
+        self.store_artifact(reader, artifact_id, Some(expected_hash))
+    }
+
+    // Write the artifact to a staging key first and only move it into place
+    // when it is complete, so that readers never see a partially written
+    // artifact. Concurrent writers of the same verified artifact are
+    // deduplicated, while an unverified artifact can only be pushed once.
+    fn store_artifact(
+        &self,
+        reader: &mut impl Read,
+        artifact_id: &str,
+        expected_hash: Option<&str>,
+    ) -> io::Result<()> {
+        let _active_writer = self.active_writers.register(artifact_id);
+        if let Some(metadata) = self.index.get(artifact_id)? {
+            if self.backend.size(&metadata.blob_key()).is_ok() {
+                // a verified artifact has the same content as the stored one,
+                // e.g. when it was written by a concurrent writer, so it
+                // doesn't need to be written again
+                return match expected_hash {
+                    Some(_) => {
+                        debug!("Artifact {} is already stored", artifact_id);
+                        Ok(())
+                    }
+                    None => Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("Artifact {} is already stored", artifact_id),
+                    )),
+                };
+            }
+        }
+        // the same content may already be stored under another artifact id,
+        // e.g. when a blob is pulled through two repositories
+        if let Some(expected_hash) = expected_hash {
+            if let Some(blob) = self.index.find_by_hash(expected_hash)? {
+                if self.backend.size(&blob.blob_key()).is_ok() {
+                    debug!(
+                        "Artifact {} shares the stored bytes of artifact {}",
+                        artifact_id, blob.blob_id
+                    );
+                    return self
+                        .index
+                        .insert_reference(artifact_id, &blob, SystemTime::now());
+                }
+            }
+        }
+
+        let staging_key = format!("{}/{}", STAGING_DIR, Uuid::new_v4());
+        let staged = match expected_hash {
+            Some(expected_hash) => {
+                let mut hashing_reader = HashingReader::new_like(expected_hash, reader);
+                self.stage_artifact(&mut hashing_reader, artifact_id, &staging_key)
+                    .and_then(|staged| {
+                        let actual_hash = hashing_reader.hash();
+                        if actual_hash == expected_hash {
+                            Ok(staged)
+                        } else {
+                            Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                StorageError::DigestMismatch {
+                                    artifact_id: artifact_id.to_owned(),
+                                    expected_hash: expected_hash.to_owned(),
+                                    actual_hash,
+                                },
+                            ))
+                        }
+                    })
+            }
+            None => self.stage_artifact(reader, artifact_id, &staging_key),
+        };
+
+        let staged = match staged {
+            Ok(staged) => staged,
+            Err(e) => {
+                // keep artifacts that fail verification for investigation
+                let cleanup_result = match e
+                    .get_ref()
+                    .and_then(|inner| inner.downcast_ref::<StorageError>())
+                {
+                    Some(StorageError::DigestMismatch { .. }) => {
+                        self.quarantine_key(&staging_key, artifact_id, &e.to_string())
+                    }
+                    _ => self.backend.delete(&staging_key),
+                };
+                if let Err(cleanup_error) = cleanup_result {
+                    debug!(
+                        "Unable to clean up staged artifact {}: {}",
+                        staging_key, cleanup_error
+                    );
+                }
+                return Err(e);
+            }
+        };
+        let key = if staged.compressed {
+            compression::compressed_key(artifact_id)
+        } else {
+            artifact_id.to_owned()
+        };
+        self.backend.rename(&staging_key, &key)?;
+        self.index.insert_stored(
+            artifact_id,
+            staged.size,
+            staged.stored_size,
+            staged.compressed,
+            expected_hash,
+            SystemTime::now(),
+        )
+    }
+
+    fn stage_artifact(
+        &self,
+        reader: &mut dyn Read,
+        artifact_id: &str,
+        staging_key: &str,
+    ) -> io::Result<StagedArtifact> {
+        let mut counting_reader = CountingReader::new(reader);
+        let (stored_size, compressed) = {
+            let mut buffered_reader = BufReader::new(&mut counting_reader);
+            let compressed = self.compress_artifacts
+                && compression::should_compress(buffered_reader.fill_buf()?);
+            let stored_size = if compressed {
+                let mut compressing_reader = compression::compressing_reader(buffered_reader)?;
+                self.put_within_quota(&mut compressing_reader, artifact_id, staging_key)?
+            } else {
+                self.put_within_quota(&mut buffered_reader, artifact_id, staging_key)?
+            };
+            (stored_size, compressed)
+        };
+        self.io_bytes.fetch_add(stored_size, Ordering::Relaxed);
+        Ok(StagedArtifact {
+            size: counting_reader.count(),
+            stored_size,
+            compressed,
+        })
+    }
+
+    fn put_within_quota(
+        &self,
+        reader: &mut dyn Read,
+        artifact_id: &str,
+        staging_key: &str,
+    ) -> io::Result<u64> {
+        match self.disk_allocated {
+            Some(disk_allocated) => {
+                // the space reserved for this artifact is available to it
+                let available_space = disk_allocated.saturating_sub(
+                    self.disk_usage()? + self.space_reservations.total_except(artifact_id),
+                );
+                // read one byte more than available to detect artifacts that don't fit
+                let copied = self
+                    .backend
+                    .put(staging_key, &mut reader.take(available_space + 1))?;
+                if copied > available_space {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!(
+                            "Storing artifact {} exceeds the allocated disk space of {} bytes",
+                            artifact_id, disk_allocated
+                        ),
+                    ));
+                }
+                Ok(copied)
+            }
+            None => self.backend.put(staging_key, reader),
+        }
+    }
+
+    /// Reserve disk space for an artifact of the announced size before it is
+    /// transferred, e.g. based on a Content-Length header or the size of a
+    /// file. Fails upfront when the artifact doesn't fit in the allocated
+    /// disk space next to the stored artifacts and the other reservations.
+    /// Writers of other artifacts can't use the reserved space until the
+    /// returned reservation is dropped. Artifacts that are stored already
+    /// don't need any space.
+    pub fn reserve_space(&self, artifact_id: &str, size: u64) -> io::Result<SpaceReservation> {
+        let size = match self.index.get(artifact_id)? {
+            Some(_) => 0,
+            None => size,
+        };
+        let mut reserved_bytes = self.space_reservations.reserved_bytes.lock().unwrap();
+        if let Some(disk_allocated) = self.disk_allocated {
+            let available_space = disk_allocated
+                .saturating_sub(self.disk_usage()? + reserved_except(&reserved_bytes, artifact_id));
+            if size > available_space {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "Unable to reserve {} bytes for artifact {}, only {} of the allocated {} bytes are available",
+                        size, artifact_id, available_space, disk_allocated
+                    ),
+                ));
+            }
+        }
+        let key = self
+            .space_reservations
+            .next_key
+            .fetch_add(1, Ordering::Relaxed);
+        reserved_bytes.insert(
+            key,
+            ReservedSpace {
+                artifact_id: artifact_id.to_owned(),
+                size,
+            },
+        );
+        Ok(SpaceReservation {
+            reservations: self.space_reservations.clone(),
+            key,
+        })
+    }
+
+    /// The number of bytes that are currently reserved for artifacts that
+    /// are being transferred.
+    pub fn reserved_space(&self) -> u64 {
+        self.space_reservations.total()
     }
 
     /// Pull an artifact. The current implementation only looks in the local node's repository.
@@ -136,29 +573,383 @@ impl ArtifactStorage {
             "An artifact is being pulled from the artifact manager {}",
             artifact_id
         );
-        let artifact_file_path = self.artifact_file_path(artifact_id)?;
-        File::open(artifact_file_path)
+        let artifact = self.read_artifact(artifact_id)?;
+        // a missed access only affects the order of garbage collection
+        if let Err(e) = self.index.record_access(artifact_id, SystemTime::now()) {
+            warn!(
+                "Unable to record the access of artifact {}: {}",
+                artifact_id, e
+            );
+        }
+        Ok(artifact)
     }
 
-    /// List all artifacts found in the repository path.
-    /// The current implementation only looks in the local node's repository.
-    pub fn list_artifacts(&self) -> Result<Vec<PathBuf>> {
-        let root: PathBuf = PathBuf::from(&self.repository_path);
-        debug!("Finding stored artifacts");
-        if root.is_dir() {
-            let vec: Vec<PathBuf> = std::fs::read_dir(root)?
-                .filter_map(|entry| {
-                    let path = entry.unwrap().path();
-                    match path.extension() {
-                        Some(ext) if ext.eq(FILE_EXTENSION) => Some(path),
-                        _ => None,
-                    }
-                })
-                .collect();
-            debug!("There are {} stored artifacts ", vec.len());
-            return Ok(vec);
+    /// Read an artifact without counting it as an access, e.g. to back it up.
+    /// Compressed artifacts are decompressed while they are read.
+    pub fn read_artifact(&self, artifact_id: &str) -> io::Result<impl Read> {
+        match self.index.get(artifact_id)? {
+            Some(metadata) if metadata.compressed => {
+                let artifact = self.get_counted(&metadata.blob_key())?;
+                Ok(Box::new(compression::decompressing_reader(artifact)?) as Box<dyn Read + Send>)
+            }
+            Some(metadata) => self.get_counted(&metadata.blob_id),
+            None => self.get_counted(artifact_id),
+        }
+    }
+
+    fn get_counted(&self, key: &str) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(IoCountingReader {
+            inner: self.backend.get(key)?,
+            io_bytes: self.io_bytes.clone(),
+        }))
+    }
+
+    /// The number of bytes of artifacts that were read from and written to
+    /// the backend since the storage was created. Compressed artifacts count
+    /// with their stored size. Shared by all clones of the storage.
+    pub fn io_bytes(&self) -> u64 {
+        self.io_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The local directory of the storage, which holds the artifacts when
+    /// they are kept on the file system.
+    pub fn repository_path(&self) -> &Path {
+        &self.repository_path
+    }
+
+    /// The metadata of an artifact in the local node's repository.
+    pub fn artifact_metadata(&self, artifact_id: &str) -> io::Result<Option<ArtifactMetadata>> {
+        self.index.get(artifact_id)
+    }
+
+    /// The metadata of all artifacts in the local node's repository.
+    pub fn list_artifact_metadata(&self) -> io::Result<Vec<ArtifactMetadata>> {
+        self.index.list()
+    }
+
+    /// The metadata of at most `limit` artifacts in the local node's repository,
+    /// ordered by artifact id and skipping the first `offset` artifacts.
+    pub fn artifact_metadata_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> io::Result<Vec<ArtifactMetadata>> {
+        self.index.list_page(offset, limit)
+    }
+
+    /// The number of artifacts in the local node's repository.
+    pub fn artifact_count(&self) -> io::Result<usize> {
+        self.index.count()
+    }
+
+    /// Count a pull of an artifact by a local package manager.
+    pub fn record_local_pull(&self, artifact_id: &str) -> io::Result<()> {
+        self.index.record_local_pull(artifact_id)
+    }
+
+    /// Count a serve of an artifact to another peer.
+    pub fn record_peer_serve(&self, artifact_id: &str) -> io::Result<()> {
+        self.index.record_peer_serve(artifact_id)
+    }
+
+    /// Write all changes of the artifact index to disk, before the node
+    /// shuts down.
+    pub fn flush_index(&self) -> io::Result<()> {
+        self.index.flush()
+    }
+
+    /// The usage statistics of the stored artifacts, aggregated per repository.
+    pub fn repository_usage(&self) -> io::Result<Vec<RepositoryUsage>> {
+        self.index.usage_by_repository()
+    }
+
+    /// Record the package that a stored artifact belongs to.
+    pub fn associate_artifact(
+        &self,
+        artifact_id: &str,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+        media_type: Option<&str>,
+    ) -> io::Result<()> {
+        self.index.associate(
+            artifact_id,
+            package_type,
+            package_specific_artifact_id,
+            media_type,
+        )
+    }
+
+    /// Store an SBOM that is attached to an artifact.
+    pub fn store_sbom(&self, sbom: &Sbom) -> io::Result<()> {
+        self.index.insert_sbom(sbom)
+    }
+
+    /// The SBOMs attached to the artifact with the specified digest.
+    pub fn sboms(&self, artifact_digest: &str) -> io::Result<Vec<Sbom>> {
+        self.index.list_sboms(artifact_digest)
+    }
+
+    /// Find the SBOM of which either the content or the referrer manifest
+    /// has the specified digest.
+    pub fn find_sbom(&self, digest: &str) -> io::Result<Option<Sbom>> {
+        self.index.find_sbom(digest)
+    }
+
+    /// Store a verified attestation about an artifact.
+    pub fn store_attestation(&self, attestation: &Attestation) -> io::Result<()> {
+        self.index.insert_attestation(attestation)
+    }
+
+    /// The attestations about the artifact with the specified digest.
+    pub fn attestations(&self, artifact_digest: &str) -> io::Result<Vec<Attestation>> {
+        self.index.list_attestations(artifact_digest)
+    }
+
+    /// Store the signed document with package metadata under the specified key.
+    pub fn store_signed_metadata(&self, key: &str, document: &SignedDocument) -> io::Result<()> {
+        self.index.insert_signed_metadata(key, document)
+    }
+
+    /// The signed document with package metadata under the specified key.
+    pub fn signed_metadata(&self, key: &str) -> io::Result<Option<SignedDocument>> {
+        self.index.find_signed_metadata(key)
+    }
+
+    /// The keys and signed documents of all package metadata.
+    pub fn all_signed_metadata(&self) -> io::Result<Vec<(String, SignedDocument)>> {
+        self.index.list_signed_metadata()
+    }
+
+    /// At most `limit` signed documents with package metadata that were
+    /// stored after the specified sequence number, with their sequence
+    /// numbers and keys, in the order they were stored.
+    pub fn signed_metadata_after_sequence(
+        &self,
+        sequence: u64,
+        limit: usize,
+    ) -> io::Result<Vec<(u64, String, SignedDocument)>> {
+        self.index
+            .list_signed_metadata_after_sequence(sequence, limit)
+    }
+
+    /// Pin an artifact so that it is never evicted by garbage collection.
+    pub fn pin_artifact(&self, artifact_id: &str) -> io::Result<()> {
+        File::create(self.pin_file_path(artifact_id)).map(|_| ())
+    }
+
+    /// Unpin an artifact so that it can be evicted by garbage collection again.
+    pub fn unpin_artifact(&self, artifact_id: &str) -> io::Result<()> {
+        match std::fs::remove_file(self.pin_file_path(artifact_id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_pinned(&self, artifact_id: &str) -> bool {
+        self.pin_file_path(artifact_id).exists()
+    }
+
+    /// The number of artifacts and bytes that were evicted by garbage collection.
+    pub fn eviction_stats(&self) -> EvictionStats {
+        *self.eviction_stats.lock().unwrap()
+    }
+
+    /// Evict the least recently used, unpinned artifacts when the disk usage
+    /// exceeds the high watermark, until it drops below the low watermark.
+    /// Returns the ids of the evicted artifacts.
+    pub fn collect_garbage(&self) -> io::Result<Vec<String>> {
+        let disk_allocated = match self.disk_allocated {
+            Some(disk_allocated) => disk_allocated,
+            None => return Ok(vec![]),
+        };
+        let high_watermark = disk_allocated.saturating_mul(self.gc_watermarks.high as u64) / 100;
+        let low_watermark = disk_allocated.saturating_mul(self.gc_watermarks.low as u64) / 100;
+
+        let mut disk_usage = self.disk_usage()?;
+        if disk_usage <= high_watermark {
+            return Ok(vec![]);
+        }
+        info!(
+            "Disk usage of {} bytes exceeds the high watermark of {} bytes, collecting garbage",
+            disk_usage, high_watermark
+        );
+
+        let mut candidates: Vec<ArtifactMetadata> = self
+            .index
+            .list()?
+            .into_iter()
+            .filter(|metadata| !self.is_pinned(&metadata.artifact_id))
+            .collect();
+        candidates.sort_by_key(|metadata| metadata.last_access);
+
+        let mut evicted = vec![];
+        for ArtifactMetadata {
+            artifact_id,
+            stored_size,
+            ..
+        } in candidates
+        {
+            if disk_usage <= low_watermark {
+                break;
+            }
+            let freed_size = self.remove_artifact(&artifact_id, stored_size)?;
+            disk_usage = disk_usage.saturating_sub(freed_size);
+
+            let mut eviction_stats = self.eviction_stats.lock().unwrap();
+            eviction_stats.evicted_artifacts += 1;
+            eviction_stats.evicted_bytes += freed_size;
+            info!(
+                "Evicted artifact {} freeing {} bytes, {} artifacts evicted so far",
+                artifact_id, freed_size, eviction_stats.evicted_artifacts
+            );
+            evicted.push(artifact_id);
         }
-        Ok(Vec::new())
+
+        if disk_usage > low_watermark {
+            warn!(
+                "Disk usage of {} bytes remains above the low watermark of {} bytes, all remaining artifacts are pinned",
+                disk_usage, low_watermark
+            );
+        }
+        Ok(evicted)
+    }
+
+    /// Evict a single artifact on request of an operator, regardless of the
+    /// disk usage and whether it is pinned. Returns the number of bytes freed.
+    pub fn evict_artifact(&self, artifact_id: &str) -> io::Result<u64> {
+        let stored_size = match self.index.get(artifact_id)? {
+            Some(metadata) => metadata.stored_size,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Artifact {} is not stored", artifact_id),
+                ))
+            }
+        };
+        let freed_size = self.remove_artifact(artifact_id, stored_size)?;
+        self.unpin_artifact(artifact_id)?;
+
+        let mut eviction_stats = self.eviction_stats.lock().unwrap();
+        eviction_stats.evicted_artifacts += 1;
+        eviction_stats.evicted_bytes += freed_size;
+        info!(
+            "Evicted artifact {} on request freeing {} bytes",
+            artifact_id, freed_size
+        );
+        Ok(freed_size)
+    }
+
+    // Remove an artifact from the index and delete its stored bytes when no
+    // other artifact references them. Returns the number of bytes freed.
+    fn remove_artifact(&self, artifact_id: &str, stored_size: u64) -> io::Result<u64> {
+        let key = self.backend_key(artifact_id)?;
+        if self.index.remove(artifact_id)? > 0 {
+            debug!(
+                "The stored bytes of artifact {} are still referenced",
+                artifact_id
+            );
+            return Ok(0);
+        }
+        self.backend.delete(&key)?;
+        Ok(stored_size)
+    }
+
+    /// Move an artifact out of the local node's repository into the
+    /// quarantine, so that it is no longer served, provided or listed.
+    /// Artifacts that share its stored bytes are removed from the repository
+    /// as well. The reason is recorded for investigation.
+    pub fn quarantine_artifact(&self, artifact_id: &str, reason: &str) -> io::Result<()> {
+        let (key, blob_id) = match self.index.get(artifact_id)? {
+            Some(metadata) => (metadata.blob_key(), metadata.blob_id),
+            None => (artifact_id.to_owned(), artifact_id.to_owned()),
+        };
+        self.quarantine_key(&key, artifact_id, reason)?;
+        let artifact_ids = self.index.remove_blob(&blob_id)?;
+
+        warn!(
+            "Artifact {} was quarantined together with the artifacts sharing its bytes: {:?}",
+            artifact_id, artifact_ids
+        );
+        Ok(())
+    }
+
+    /// Write data that failed verification, e.g. a replica received from
+    /// another peer, directly into the quarantine for investigation.
+    pub fn quarantine_data(
+        &self,
+        reader: &mut impl Read,
+        artifact_id: &str,
+        reason: &str,
+    ) -> io::Result<()> {
+        let quarantine_key = format!("{}/{}", QUARANTINE_DIR, Uuid::new_v4());
+        self.backend.put(&quarantine_key, reader)?;
+        self.record_quarantine(artifact_id, &quarantine_key, reason)
+    }
+
+    /// The artifacts in the quarantine, most recently quarantined first.
+    pub fn list_quarantined(&self) -> io::Result<Vec<QuarantinedArtifact>> {
+        self.index.list_quarantined()
+    }
+
+    // Move the bytes under `key` into the quarantine and record the reason.
+    // Every quarantine gets its own file, so an earlier one of the same
+    // artifact is not overwritten.
+    fn quarantine_key(&self, key: &str, artifact_id: &str, reason: &str) -> io::Result<()> {
+        let quarantine_key = format!(
+            "{}/{}-{}",
+            QUARANTINE_DIR,
+            key.rsplit('/').next().unwrap_or(key),
+            Uuid::new_v4()
+        );
+        self.backend.rename(key, &quarantine_key)?;
+        self.record_quarantine(artifact_id, &quarantine_key, reason)
+    }
+
+    fn record_quarantine(
+        &self,
+        artifact_id: &str,
+        quarantine_key: &str,
+        reason: &str,
+    ) -> io::Result<()> {
+        warn!(
+            "Artifact {} was quarantined to {}: {}",
+            artifact_id, quarantine_key, reason
+        );
+        self.index.insert_quarantined(&QuarantinedArtifact {
+            artifact_id: artifact_id.to_owned(),
+            quarantine_key: quarantine_key.to_owned(),
+            reason: reason.to_owned(),
+            quarantined_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        })
+    }
+
+    /// The size in bytes of an artifact in the local node's repository.
+    /// This is the size of its content, also when it is stored compressed.
+    pub fn artifact_size(&self, artifact_id: &str) -> io::Result<u64> {
+        match self.index.get(artifact_id)? {
+            Some(metadata) => Ok(metadata.size),
+            None => self.backend.size(artifact_id),
+        }
+    }
+
+    /// The total number of bytes that all artifacts in the local node's
+    /// repository take up in the storage.
+    pub fn disk_usage(&self) -> io::Result<u64> {
+        self.index.total_size()
+    }
+
+    /// List the ids of all artifacts found in the repository.
+    /// The current implementation only looks in the local node's repository.
+    pub fn list_artifacts(&self) -> Result<Vec<String>> {
+        Ok(self
+            .index
+            .list()?
+            .into_iter()
+            .map(|metadata| metadata.artifact_id)
+            .collect())
     }
 }
 
@@ -166,6 +957,7 @@ impl ArtifactStorage {
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
+    use crate::artifact_service::hashing::HashAlgorithm;
     use crate::util::test_util;
     use std::path::PathBuf;
     use stringreader::StringReader;
@@ -219,10 +1011,73 @@ mod tests {
         test_util::tests::teardown(tmp_dir);
     }
 
+    #[test]
+    pub fn push_compressed_artifact_then_pull_it() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        artifact_storage.compress_artifacts = true;
+
+        let content = TEST_ARTIFACT_DATA.repeat(10);
+        artifact_storage
+            .push_artifact(&mut StringReader::new(&content), &artifact_id)
+            .unwrap();
+
+        let metadata = artifact_storage
+            .artifact_metadata(&artifact_id)
+            .unwrap()
+            .unwrap();
+        assert!(metadata.compressed);
+        assert_eq!(metadata.size, content.len() as u64);
+        assert!(metadata.stored_size < metadata.size);
+        assert!(tmp_dir.join(format!("{}.zst.file", artifact_id)).is_file());
+        assert_eq!(
+            artifact_storage.artifact_size(&artifact_id).unwrap(),
+            content.len() as u64
+        );
+        assert_eq!(artifact_storage.disk_usage().unwrap(), metadata.stored_size);
+
+        let mut read_buffer = String::new();
+        artifact_storage
+            .pull_artifact(&artifact_id)
+            .unwrap()
+            .read_to_string(&mut read_buffer)
+            .unwrap();
+        assert_eq!(content, read_buffer);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_already_compressed_artifact_is_stored_as_is() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        artifact_storage.compress_artifacts = true;
+
+        let content = b"\x1f\x8b\x08\x00 gzipped layer";
+        artifact_storage
+            .push_artifact(&mut &content[..], &artifact_id)
+            .unwrap();
+
+        let metadata = artifact_storage
+            .artifact_metadata(&artifact_id)
+            .unwrap()
+            .unwrap();
+        assert!(!metadata.compressed);
+        assert_eq!(metadata.stored_size, content.len() as u64);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     fn check_artifact_is_written_correctly(dir_name: &Path, artifact_id: &str) -> Result<()> {
         let mut dir_name = dir_name.to_path_buf();
         dir_name.push(artifact_id);
-        dir_name.set_extension(FILE_EXTENSION);
+        dir_name.set_extension("file");
         let content_vec = std::fs::read(dir_name.as_path())
             .context("reading pushed file")
             .unwrap();
@@ -257,6 +1112,31 @@ mod tests {
         test_util::tests::teardown(tmp_dir);
     }
 
+    #[test]
+    pub fn artifact_size_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let mut string_reader = StringReader::new(TEST_ARTIFACT_DATA);
+        let artifact_id = Uuid::new_v4().to_string();
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+
+        artifact_storage
+            .push_artifact(&mut string_reader, &artifact_id)
+            .context("Error from push_artifact")
+            .unwrap();
+
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.artifact_size(&artifact_id).unwrap()
+        );
+        assert!(artifact_storage
+            .artifact_size(&Uuid::new_v4().to_string())
+            .is_err());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     #[test]
     pub fn list_artifacts_test() {
         let tmp_dir = test_util::tests::setup();
@@ -277,4 +1157,495 @@ mod tests {
 
         test_util::tests::teardown(tmp_dir);
     }
+
+    #[test]
+    pub fn disk_usage_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        assert_eq!(0, artifact_storage.disk_usage().unwrap());
+
+        for _ in 0..2 {
+            artifact_storage
+                .push_artifact(
+                    &mut StringReader::new(TEST_ARTIFACT_DATA),
+                    &Uuid::new_v4().to_string(),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            2 * TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.disk_usage().unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn io_bytes_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        assert_eq!(0, artifact_storage.io_bytes());
+
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.clone().io_bytes()
+        );
+
+        let mut content = Vec::new();
+        artifact_storage
+            .pull_artifact(&artifact_id)
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(
+            2 * TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.io_bytes()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn artifact_metadata_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        assert_eq!(
+            None,
+            artifact_storage.artifact_metadata(&artifact_id).unwrap()
+        );
+
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+        artifact_storage
+            .associate_artifact(&artifact_id, PackageType::Docker, "alpine:3.15.2", None)
+            .unwrap();
+
+        let metadata = artifact_storage
+            .artifact_metadata(&artifact_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(TEST_ARTIFACT_DATA.len() as u64, metadata.size);
+        assert_eq!(Some(PackageType::Docker), metadata.package_type);
+        assert_eq!(
+            Some("alpine:3.15.2".to_owned()),
+            metadata.package_specific_artifact_id
+        );
+
+        // a reopened storage keeps using the persisted index
+        let reopened_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        assert_eq!(
+            Some(metadata),
+            reopened_storage.artifact_metadata(&artifact_id).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_verified_artifact_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let expected_hash = HashAlgorithm::Sha256.hash(TEST_ARTIFACT_DATA.as_bytes());
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let result = artifact_storage.push_verified_artifact(
+            &mut StringReader::new("OTHER_DATA"),
+            &artifact_id,
+            &expected_hash,
+        );
+        assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+        assert!(artifact_storage.pull_artifact(&artifact_id).is_err());
+        assert!(artifact_storage.list_artifacts().unwrap().is_empty());
+        let quarantined = artifact_storage.list_quarantined().unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].artifact_id, artifact_id);
+        assert!(quarantined[0].reason.contains(&expected_hash));
+
+        artifact_storage
+            .push_verified_artifact(
+                &mut StringReader::new(TEST_ARTIFACT_DATA),
+                &artifact_id,
+                &expected_hash,
+            )
+            .unwrap();
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.artifact_size(&artifact_id).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_artifact_with_same_content_shares_bytes_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let expected_hash = HashAlgorithm::Sha256.hash(TEST_ARTIFACT_DATA.as_bytes());
+        let size = TEST_ARTIFACT_DATA.len() as u64;
+
+        let first_id = Uuid::new_v4().to_string();
+        let second_id = Uuid::new_v4().to_string();
+        for artifact_id in [&first_id, &second_id] {
+            artifact_storage
+                .push_verified_artifact(
+                    &mut StringReader::new(TEST_ARTIFACT_DATA),
+                    artifact_id,
+                    &expected_hash,
+                )
+                .unwrap();
+        }
+
+        assert!(!tmp_dir.join(format!("{}.file", second_id)).exists());
+        assert_eq!(artifact_storage.disk_usage().unwrap(), size);
+        assert_eq!(artifact_storage.list_artifacts().unwrap().len(), 2);
+        let second = artifact_storage
+            .artifact_metadata(&second_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.blob_id, first_id);
+        assert_eq!(second.ref_count, 2);
+
+        assert_eq!(
+            artifact_storage.remove_artifact(&first_id, size).unwrap(),
+            0
+        );
+        check_able_to_pull_artifact(&second_id, &artifact_storage).unwrap();
+        assert_eq!(artifact_storage.disk_usage().unwrap(), size);
+
+        assert_eq!(
+            artifact_storage.remove_artifact(&second_id, size).unwrap(),
+            size
+        );
+        assert!(!tmp_dir.join(format!("{}.file", first_id)).exists());
+        assert_eq!(artifact_storage.disk_usage().unwrap(), 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_artifact_concurrently_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let artifact_storage = artifact_storage.clone();
+                let artifact_id = artifact_id.clone();
+                std::thread::spawn(move || {
+                    artifact_storage.push_verified_artifact(
+                        &mut StringReader::new(TEST_ARTIFACT_DATA),
+                        &artifact_id,
+                        &HashAlgorithm::Sha256.hash(TEST_ARTIFACT_DATA.as_bytes()),
+                    )
+                })
+            })
+            .collect();
+        for writer in writers {
+            assert!(writer.join().unwrap().is_ok());
+        }
+
+        assert_eq!(
+            vec![artifact_id],
+            artifact_storage.list_artifacts().unwrap()
+        );
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.disk_usage().unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_existing_artifact_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+
+        let result =
+            artifact_storage.push_artifact(&mut StringReader::new("OTHER_DATA"), &artifact_id);
+        assert_eq!(io::ErrorKind::AlreadyExists, result.unwrap_err().kind());
+        check_able_to_pull_artifact(&artifact_id, &artifact_storage).unwrap();
+
+        artifact_storage
+            .push_verified_artifact(
+                &mut StringReader::new(TEST_ARTIFACT_DATA),
+                &artifact_id,
+                &HashAlgorithm::Sha256.hash(TEST_ARTIFACT_DATA.as_bytes()),
+            )
+            .unwrap();
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.disk_usage().unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn push_artifact_exceeding_disk_allocated_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        artifact_storage.disk_allocated = Some(TEST_ARTIFACT_DATA.len() as u64 + 10);
+
+        let artifact_id = Uuid::new_v4().to_string();
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+
+        let other_artifact_id = Uuid::new_v4().to_string();
+        assert!(artifact_storage
+            .push_artifact(
+                &mut StringReader::new(TEST_ARTIFACT_DATA),
+                &other_artifact_id
+            )
+            .is_err());
+        assert!(artifact_storage.pull_artifact(&other_artifact_id).is_err());
+        assert_eq!(
+            TEST_ARTIFACT_DATA.len() as u64,
+            artifact_storage.disk_usage().unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn reserve_space_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let size = TEST_ARTIFACT_DATA.len() as u64;
+        artifact_storage.disk_allocated = Some(size + 10);
+
+        let artifact_id = Uuid::new_v4().to_string();
+        let other_artifact_id = Uuid::new_v4().to_string();
+        let reservation = artifact_storage.reserve_space(&artifact_id, size).unwrap();
+        assert_eq!(artifact_storage.reserved_space(), size);
+
+        // concurrent transfers can't use the reserved space
+        assert!(artifact_storage
+            .reserve_space(&other_artifact_id, size)
+            .is_err());
+        assert!(artifact_storage
+            .push_artifact(
+                &mut StringReader::new(TEST_ARTIFACT_DATA),
+                &other_artifact_id
+            )
+            .is_err());
+
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+        drop(reservation);
+        assert_eq!(artifact_storage.reserved_space(), 0);
+
+        // a stored artifact doesn't need any space
+        let reservation = artifact_storage.reserve_space(&artifact_id, size).unwrap();
+        assert_eq!(artifact_storage.reserved_space(), 0);
+        drop(reservation);
+
+        // concurrent reservations of the same artifact are released separately
+        let reservation = artifact_storage
+            .reserve_space(&other_artifact_id, 4)
+            .unwrap();
+        let other_reservation = artifact_storage
+            .reserve_space(&other_artifact_id, 4)
+            .unwrap();
+        assert_eq!(artifact_storage.reserved_space(), 8);
+        drop(reservation);
+        assert_eq!(artifact_storage.reserved_space(), 4);
+        drop(other_reservation);
+        assert_eq!(artifact_storage.reserved_space(), 0);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn collect_garbage_evicts_least_recently_used_unpinned_artifacts_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_size = TEST_ARTIFACT_DATA.len() as u64;
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        artifact_storage.disk_allocated = Some(4 * artifact_size);
+        artifact_storage.gc_watermarks = GcWatermarks { high: 70, low: 50 };
+
+        let artifact_ids: Vec<String> = (0..4).map(|_| Uuid::new_v4().to_string()).collect();
+        for artifact_id in &artifact_ids {
+            artifact_storage
+                .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), artifact_id)
+                .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        artifact_storage.pin_artifact(&artifact_ids[0]).unwrap();
+        artifact_storage.pull_artifact(&artifact_ids[1]).unwrap();
+
+        let evicted = artifact_storage.collect_garbage().unwrap();
+
+        assert_eq!(
+            evicted,
+            vec![artifact_ids[2].clone(), artifact_ids[3].clone()]
+        );
+        assert!(artifact_storage.pull_artifact(&artifact_ids[0]).is_ok());
+        assert!(artifact_storage.pull_artifact(&artifact_ids[1]).is_ok());
+        assert!(artifact_storage.pull_artifact(&artifact_ids[2]).is_err());
+        assert!(artifact_storage.pull_artifact(&artifact_ids[3]).is_err());
+        assert_eq!(
+            EvictionStats {
+                evicted_artifacts: 2,
+                evicted_bytes: 2 * artifact_size,
+            },
+            artifact_storage.eviction_stats()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn collect_garbage_below_high_watermark_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let mut artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+
+        assert!(artifact_storage.collect_garbage().unwrap().is_empty());
+
+        artifact_storage.disk_allocated = Some(2 * TEST_ARTIFACT_DATA.len() as u64);
+        assert!(artifact_storage.collect_garbage().unwrap().is_empty());
+        assert!(artifact_storage.pull_artifact(&artifact_id).is_ok());
+        assert_eq!(EvictionStats::default(), artifact_storage.eviction_stats());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn evict_pinned_artifact_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+        artifact_storage.pin_artifact(&artifact_id).unwrap();
+
+        let freed_size = artifact_storage.evict_artifact(&artifact_id).unwrap();
+
+        assert_eq!(freed_size, TEST_ARTIFACT_DATA.len() as u64);
+        assert!(artifact_storage.pull_artifact(&artifact_id).is_err());
+        assert!(!artifact_storage.is_pinned(&artifact_id));
+        assert_eq!(
+            artifact_storage
+                .evict_artifact(&artifact_id)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn pin_and_unpin_artifact_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+
+        assert!(!artifact_storage.is_pinned(&artifact_id));
+        artifact_storage.pin_artifact(&artifact_id).unwrap();
+        assert!(artifact_storage.is_pinned(&artifact_id));
+        artifact_storage.unpin_artifact(&artifact_id).unwrap();
+        assert!(!artifact_storage.is_pinned(&artifact_id));
+        artifact_storage.unpin_artifact(&artifact_id).unwrap();
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    pub fn quarantine_artifact_test() {
+        let tmp_dir = test_util::tests::setup();
+
+        let artifact_storage =
+            ArtifactStorage::new(&tmp_dir).expect("Error creating ArtifactManager");
+        let artifact_id = Uuid::new_v4().to_string();
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+
+        artifact_storage
+            .quarantine_artifact(&artifact_id, "hash mismatch")
+            .unwrap();
+
+        assert!(artifact_storage.pull_artifact(&artifact_id).is_err());
+        assert!(artifact_storage.list_artifacts().unwrap().is_empty());
+        let quarantined = artifact_storage.list_quarantined().unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].artifact_id, artifact_id);
+        assert_eq!(quarantined[0].reason, "hash mismatch");
+        assert!(quarantined[0]
+            .quarantine_key
+            .starts_with(&format!("{}/{}-", QUARANTINE_DIR, artifact_id)));
+        assert_eq!(
+            std::fs::read(tmp_dir.join(format!("{}.file", quarantined[0].quarantine_key))).unwrap(),
+            TEST_ARTIFACT_DATA.as_bytes()
+        );
+        assert!(artifact_storage
+            .quarantine_artifact(&artifact_id, "hash mismatch")
+            .is_err());
+
+        // Quarantining the same artifact again keeps the earlier quarantined
+        // bytes.
+        artifact_storage
+            .push_artifact(&mut StringReader::new(TEST_ARTIFACT_DATA), &artifact_id)
+            .unwrap();
+        artifact_storage
+            .quarantine_artifact(&artifact_id, "hash mismatch")
+            .unwrap();
+        let quarantined = artifact_storage.list_quarantined().unwrap();
+        assert_eq!(quarantined.len(), 2);
+        assert_ne!(quarantined[0].quarantine_key, quarantined[1].quarantine_key);
+        for quarantined_artifact in quarantined {
+            assert!(tmp_dir
+                .join(format!("{}.file", quarantined_artifact.quarantine_key))
+                .exists());
+        }
+
+        test_util::tests::teardown(tmp_dir);
+    }
 }
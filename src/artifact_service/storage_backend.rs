@@ -0,0 +1,70 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+pub mod filesystem;
+pub mod s3;
+
+use std::io::{self, Read};
+use std::time::SystemTime;
+
+/// A store for the bytes of artifacts, which are identified by a key. Keys
+/// that contain a `/` live in a nested namespace, e.g. `quarantine/<id>`.
+pub trait StorageBackend: Send + Sync {
+    /// A name that identifies where this backend stores the bytes, so that
+    /// local state can be kept apart for each backend.
+    fn name(&self) -> String;
+
+    /// Store the bytes read from `reader` under the specified key. Fails when
+    /// the key already exists. Returns the number of bytes that were stored.
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<u64>;
+
+    /// Read the bytes stored under the specified key.
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Delete the bytes stored under the specified key.
+    fn delete(&self, key: &str) -> io::Result<()>;
+
+    /// List all keys, except for the keys in nested namespaces.
+    fn list(&self) -> io::Result<Vec<String>>;
+
+    /// List all keys together with the number of bytes stored under them,
+    /// except for the keys in nested namespaces.
+    fn list_with_sizes(&self) -> io::Result<Vec<(String, u64)>> {
+        self.list()?
+            .into_iter()
+            .map(|key| {
+                let size = self.size(&key)?;
+                Ok((key, size))
+            })
+            .collect()
+    }
+
+    /// The number of bytes stored under the specified key.
+    fn size(&self, key: &str) -> io::Result<u64>;
+
+    /// The time at which the bytes under the specified key were stored.
+    /// Backends that don't keep track of it return the unix epoch.
+    fn modified(&self, _key: &str) -> io::Result<SystemTime> {
+        Ok(SystemTime::UNIX_EPOCH)
+    }
+
+    /// Move the bytes stored under `from` to the key `to`.
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut reader = self.get(from)?;
+        self.put(to, &mut reader)?;
+        self.delete(from)
+    }
+}
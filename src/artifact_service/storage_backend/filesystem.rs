@@ -0,0 +1,160 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::StorageBackend;
+use log::debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+const FILE_EXTENSION: &str = "file";
+
+/// Stores artifacts as files in a directory on the local disk.
+pub struct FileSystemBackend {
+    root: PathBuf,
+}
+
+impl FileSystemBackend {
+    pub fn new(root: PathBuf) -> Self {
+        FileSystemBackend { root }
+    }
+
+    // The file path that corresponds to a key. The structure of the path is:
+    // `root_dir/key.file`. For example:
+    // `pyrsia-artifacts/e131322a-0c72-454d-b7a0-dcdb53c1bbdf.file`
+    //
+    // TODO To support nodes that will store many files, we need a scheme that will start separating
+    // files by subdirectories based on the first n bytes of the artifact id.
+    fn file_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", key, FILE_EXTENSION))
+    }
+}
+
+impl StorageBackend for FileSystemBackend {
+    fn name(&self) -> String {
+        "filesystem".to_owned()
+    }
+
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<u64> {
+        let file_path = self.file_path(key);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(file_path)?;
+        let mut writer = BufWriter::new(file);
+        let copied = io::copy(reader, &mut writer)?;
+        writer.flush()?;
+        Ok(copied)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(File::open(self.file_path(key))?))
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        fs::remove_file(self.file_path(key))
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        debug!("Finding stored artifacts");
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut keys = vec![];
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            match path.extension() {
+                Some(ext) if ext.eq(FILE_EXTENSION) && path.is_file() => {
+                    if let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) {
+                        keys.push(key.to_owned());
+                    }
+                }
+                _ => {}
+            }
+        }
+        debug!("There are {} stored artifacts ", keys.len());
+        Ok(keys)
+    }
+
+    fn size(&self, key: &str) -> io::Result<u64> {
+        Ok(fs::metadata(self.file_path(key))?.len())
+    }
+
+    fn modified(&self, key: &str) -> io::Result<SystemTime> {
+        fs::metadata(self.file_path(key))?.modified()
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let to_file_path = self.file_path(to);
+        if let Some(parent) = to_file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(self.file_path(from), to_file_path)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::util::test_util;
+
+    #[test]
+    fn test_put_get_and_delete() {
+        let tmp_dir = test_util::tests::setup();
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+
+        assert_eq!(backend.put("key", &mut &b"SAMPLE_DATA"[..]).unwrap(), 11);
+        assert!(backend.put("key", &mut &b"OTHER_DATA"[..]).is_err());
+
+        let mut content = vec![];
+        backend
+            .get("key")
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, b"SAMPLE_DATA");
+        assert_eq!(backend.size("key").unwrap(), 11);
+        assert_eq!(backend.list().unwrap(), vec!["key".to_owned()]);
+
+        backend.delete("key").unwrap();
+        assert!(backend.get("key").is_err());
+        assert!(backend.list().unwrap().is_empty());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_rename_to_nested_namespace() {
+        let tmp_dir = test_util::tests::setup();
+        let backend = FileSystemBackend::new(tmp_dir.clone());
+
+        backend.put("key", &mut &b"SAMPLE_DATA"[..]).unwrap();
+        backend.rename("key", "nested/key").unwrap();
+
+        assert!(backend.list().unwrap().is_empty());
+        assert!(backend.get("key").is_err());
+        assert_eq!(backend.size("nested/key").unwrap(), 11);
+        assert!(tmp_dir.join("nested").join("key.file").is_file());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
@@ -0,0 +1,641 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::StorageBackend;
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+use reqwest::{Client, Method, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::io::{self, Read};
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+use url::Url;
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+/// Objects larger than this are uploaded in parts of this size, so they are
+/// never held in memory as a whole. S3 requires all parts but the last one
+/// to be at least 5 MiB.
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Stores artifacts as objects in a bucket of an S3 compatible object store.
+/// Requests are path-style and signed with AWS Signature Version 4.
+pub struct S3Backend {
+    client: Client,
+    // Requests run on a runtime of their own, so they can be waited for from
+    // synchronous code on any kind of async runtime. It is only taken when
+    // the backend is dropped.
+    runtime: Option<Runtime>,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    #[serde(default)]
+    contents: Vec<ListedObject>,
+    is_truncated: bool,
+    next_continuation_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedObject {
+    key: String,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InitiateMultipartUploadResult {
+    upload_id: String,
+}
+
+// A failed completion is reported with a success status and an error
+// document, which lacks the ETag of the object.
+#[derive(Deserialize)]
+struct CompleteMultipartUploadResult {
+    #[serde(rename = "ETag")]
+    _e_tag: String,
+}
+
+impl S3Backend {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+    ) -> anyhow::Result<Self> {
+        let endpoint = Url::parse(endpoint)?;
+        if endpoint.host_str().is_none() {
+            anyhow::bail!("S3 endpoint {} has no host", endpoint);
+        }
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("pyrsia-s3")
+            .enable_all()
+            .build()?;
+
+        Ok(S3Backend {
+            client: Client::new(),
+            runtime: Some(runtime),
+            endpoint,
+            bucket: bucket.to_owned(),
+            region: region.to_owned(),
+            access_key_id: access_key_id.to_owned(),
+            secret_access_key: secret_access_key.to_owned(),
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!(
+            "/{}/{}",
+            uri_encode(&self.bucket, true),
+            uri_encode(key, false)
+        )
+    }
+
+    fn host(&self) -> String {
+        let host = self.endpoint.host_str().unwrap_or_default();
+        match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_owned(),
+        }
+    }
+
+    fn authorization(
+        &self,
+        method: &Method,
+        path: &str,
+        canonical_query: &str,
+        amz_date: &str,
+    ) -> String {
+        let date = &amz_date[..8];
+        let canonical_headers = format!(
+            "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
+            self.host(),
+            UNSIGNED_PAYLOAD,
+            amz_date
+        );
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, canonical_query, canonical_headers, SIGNED_HEADERS, UNSIGNED_PAYLOAD
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key = signing_key(&self.secret_access_key, date, &self.region, "s3");
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, SIGNED_HEADERS, signature
+        )
+    }
+
+    // Run a future on the runtime of this backend and wait for its output.
+    fn run<F>(&self, future: F) -> io::Result<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("the runtime is only taken when the backend is dropped");
+        wait(runtime.spawn(future)).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<Vec<u8>>,
+    ) -> io::Result<Response> {
+        let mut query: Vec<String> = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        query.sort();
+        let canonical_query = query.join("&");
+
+        let mut url = self.endpoint.clone();
+        url.set_path(path);
+        if !canonical_query.is_empty() {
+            url.set_query(Some(&canonical_query));
+        }
+
+        let amz_date = format_amz_date(Utc::now());
+        let authorization = self.authorization(&method, path, &canonical_query, &amz_date);
+        debug!("Sending {} request to {}", method, url);
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization);
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = self.run(request.send())?.map_err(to_io_error)?;
+        if response.status().is_success() {
+            Ok(response)
+        } else if response.status() == reqwest::StatusCode::NOT_FOUND {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Object {} not found", path),
+            ))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} request for {} failed", response.status(), path),
+            ))
+        }
+    }
+
+    fn read_xml<T: DeserializeOwned>(&self, response: Response) -> io::Result<T> {
+        let body = self.run(response.bytes())?.map_err(to_io_error)?;
+        parse_xml(&body)
+    }
+
+    // Upload an object in parts, starting with the part that was already
+    // read from the reader.
+    fn put_multipart(
+        &self,
+        path: &str,
+        first_part: Vec<u8>,
+        reader: &mut dyn Read,
+    ) -> io::Result<u64> {
+        let response = self.send(Method::POST, path, &[("uploads", "")], None)?;
+        let upload_id = self
+            .read_xml::<InitiateMultipartUploadResult>(response)?
+            .upload_id;
+
+        let result = self.upload_parts(path, &upload_id, first_part, reader);
+        if result.is_err() {
+            // free the parts that were already uploaded
+            if let Err(err) = self.send(Method::DELETE, path, &[("uploadId", &upload_id)], None) {
+                warn!("Unable to abort the upload of {}: {}", path, err);
+            }
+        }
+        result
+    }
+
+    fn upload_parts(
+        &self,
+        path: &str,
+        upload_id: &str,
+        first_part: Vec<u8>,
+        reader: &mut dyn Read,
+    ) -> io::Result<u64> {
+        let mut etags = vec![];
+        let mut len = 0;
+        let mut part = first_part;
+        while !part.is_empty() {
+            len += part.len() as u64;
+            let part_number = (etags.len() + 1).to_string();
+            let response = self.send(
+                Method::PUT,
+                path,
+                &[("partNumber", &part_number), ("uploadId", upload_id)],
+                Some(part),
+            )?;
+            let etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Part {} of {} has no ETag", part_number, path),
+                    )
+                })?;
+            etags.push(etag.to_owned());
+            part = read_part(reader)?;
+        }
+
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(index, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    index + 1,
+                    etag
+                )
+            })
+            .collect();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+        let response = self.send(
+            Method::POST,
+            path,
+            &[("uploadId", upload_id)],
+            Some(body.into_bytes()),
+        )?;
+        self.read_xml::<CompleteMultipartUploadResult>(response)?;
+        Ok(len)
+    }
+}
+
+impl Drop for S3Backend {
+    fn drop(&mut self) {
+        // dropping a runtime blocks, which is not allowed in an async context
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl StorageBackend for S3Backend {
+    fn name(&self) -> String {
+        format!("s3-{}", self.bucket)
+    }
+
+    fn put(&self, key: &str, reader: &mut dyn Read) -> io::Result<u64> {
+        if self.size(key).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Object {} already exists", key),
+            ));
+        }
+
+        let path = self.object_path(key);
+        let first_part = read_part(reader)?;
+        if (first_part.len() as u64) < PART_SIZE {
+            let len = first_part.len() as u64;
+            self.send(Method::PUT, &path, &[], Some(first_part))?;
+            Ok(len)
+        } else {
+            self.put_multipart(&path, first_part, reader)
+        }
+    }
+
+    fn get(&self, key: &str) -> io::Result<Box<dyn Read + Send>> {
+        let response = self.send(Method::GET, &self.object_path(key), &[], None)?;
+        Ok(Box::new(ObjectReader::new(response.bytes_stream().boxed())))
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        self.send(Method::DELETE, &self.object_path(key), &[], None)
+            .map(|_| ())
+    }
+
+    fn list(&self) -> io::Result<Vec<String>> {
+        Ok(self
+            .list_with_sizes()?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    fn list_with_sizes(&self) -> io::Result<Vec<(String, u64)>> {
+        let bucket_path = format!("/{}", uri_encode(&self.bucket, true));
+        let mut objects = vec![];
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("delimiter", "/")];
+            if let Some(token) = continuation_token.as_deref() {
+                query.push(("continuation-token", token));
+            }
+            let response = self.send(Method::GET, &bucket_path, &query, None)?;
+            let result: ListBucketResult = self.read_xml(response)?;
+
+            objects.extend(
+                result
+                    .contents
+                    .into_iter()
+                    .map(|object| (object.key, object.size)),
+            );
+            continuation_token = if result.is_truncated {
+                result.next_continuation_token
+            } else {
+                None
+            };
+            if continuation_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn size(&self, key: &str) -> io::Result<u64> {
+        let response = self.send(Method::HEAD, &self.object_path(key), &[], None)?;
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Object {} has no content length", key),
+                )
+            })
+    }
+}
+
+/// Reads the body of an object while it is being received.
+struct ObjectReader {
+    chunks: BoxStream<'static, reqwest::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl ObjectReader {
+    fn new(chunks: BoxStream<'static, reqwest::Result<Bytes>>) -> Self {
+        ObjectReader {
+            chunks,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl Read for ObjectReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match wait(self.chunks.next()) {
+                Some(chunk) => self.chunk = chunk.map_err(to_io_error)?,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Ok(len)
+    }
+}
+
+// Wait for a future from synchronous code. A worker of a multi-threaded
+// runtime hands off its other tasks first, so they keep running.
+fn wait<F: Future>(future: F) -> F::Output {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| futures::executor::block_on(future))
+        }
+        _ => futures::executor::block_on(future),
+    }
+}
+
+// Read the next part of an upload, which is shorter than the part size only
+// when the end of the reader was reached.
+fn read_part(reader: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut part = Vec::new();
+    reader.take(PART_SIZE).read_to_end(&mut part)?;
+    Ok(part)
+}
+
+fn parse_xml<T: DeserializeOwned>(xml: &[u8]) -> io::Result<T> {
+    quick_xml::de::from_str(&String::from_utf8_lossy(xml))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn to_io_error(error: reqwest::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error)
+}
+
+// URI encode a value as specified for AWS Signature Version 4.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let date_key = hmac_sha256(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    let region_key = hmac_sha256(&date_key, region.as_bytes());
+    let service_key = hmac_sha256(&region_key, service.as_bytes());
+    hmac_sha256(&service_key, b"aws4_request")
+}
+
+fn format_amz_date(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_signing_key() {
+        // example from the AWS Signature Version 4 documentation
+        assert_eq!(
+            hex::encode(signing_key(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20150830",
+                "us-east-1",
+                "iam"
+            )),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+    }
+
+    #[test]
+    fn test_format_amz_date() {
+        assert_eq!(
+            format_amz_date(Utc.timestamp_opt(0, 0).unwrap()),
+            "19700101T000000Z"
+        );
+        assert_eq!(
+            format_amz_date(Utc.timestamp_opt(951827696, 0).unwrap()),
+            "20000229T123456Z"
+        );
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("quarantine/a b", false), "quarantine/a%20b");
+        assert_eq!(uri_encode("quarantine/a b", true), "quarantine%2Fa%20b");
+        assert_eq!(uri_encode("A-Z_a.z~0", true), "A-Z_a.z~0");
+    }
+
+    #[test]
+    fn test_parse_list_bucket_result() {
+        let xml = "<ListBucketResult><Name>pyrsia</Name><IsTruncated>true</IsTruncated>\
+            <Contents><Key>first</Key><Size>3</Size></Contents>\
+            <Contents><Key>a&amp;b</Key><Size>5</Size></Contents>\
+            <CommonPrefixes><Prefix>quarantine/</Prefix></CommonPrefixes>\
+            <NextContinuationToken>token</NextContinuationToken></ListBucketResult>";
+
+        let result: ListBucketResult = parse_xml(xml.as_bytes()).unwrap();
+        assert!(result.is_truncated);
+        assert_eq!(result.next_continuation_token, Some("token".to_owned()));
+        let objects: Vec<(String, u64)> = result
+            .contents
+            .into_iter()
+            .map(|object| (object.key, object.size))
+            .collect();
+        assert_eq!(
+            objects,
+            vec![("first".to_owned(), 3), ("a&b".to_owned(), 5)]
+        );
+
+        let empty: ListBucketResult =
+            parse_xml(b"<ListBucketResult><IsTruncated>false</IsTruncated></ListBucketResult>")
+                .unwrap();
+        assert!(empty.contents.is_empty());
+    }
+
+    #[test]
+    fn test_complete_multipart_upload_error() {
+        assert!(parse_xml::<CompleteMultipartUploadResult>(
+            b"<CompleteMultipartUploadResult><ETag>\"etag\"</ETag></CompleteMultipartUploadResult>"
+        )
+        .is_ok());
+        assert!(parse_xml::<CompleteMultipartUploadResult>(
+            b"<Error><Code>InternalError</Code></Error>"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_part() {
+        let data = vec![7u8; PART_SIZE as usize + 10];
+        let mut reader: &[u8] = &data;
+
+        assert_eq!(read_part(&mut reader).unwrap().len() as u64, PART_SIZE);
+        assert_eq!(read_part(&mut reader).unwrap(), vec![7u8; 10]);
+        assert!(read_part(&mut reader).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_object_reader() {
+        let chunks = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"ab")),
+            Ok(Bytes::new()),
+            Ok(Bytes::from_static(b"cde")),
+        ]);
+        let mut reader = ObjectReader::new(chunks.boxed());
+
+        let mut first = [0u8; 1];
+        assert_eq!(reader.read(&mut first).unwrap(), 1);
+        assert_eq!(&first, b"a");
+        let mut rest = vec![];
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"bcde");
+    }
+
+    #[test]
+    fn test_authorization() {
+        let backend = S3Backend::new(
+            "http://localhost:9000",
+            "pyrsia",
+            "us-east-1",
+            "ACCESS_KEY",
+            "SECRET_KEY",
+        )
+        .unwrap();
+
+        assert_eq!(backend.host(), "localhost:9000");
+        assert_eq!(
+            backend.object_path("quarantine/key"),
+            "/pyrsia/quarantine/key"
+        );
+
+        let authorization =
+            backend.authorization(&Method::GET, "/pyrsia/key", "", "20130524T000000Z");
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=ACCESS_KEY/20130524/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+        assert_eq!(
+            authorization,
+            backend.authorization(&Method::GET, "/pyrsia/key", "", "20130524T000000Z")
+        );
+        assert_ne!(
+            authorization,
+            backend.authorization(&Method::PUT, "/pyrsia/key", "", "20130524T000000Z")
+        );
+    }
+}
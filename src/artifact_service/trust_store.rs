@@ -0,0 +1,235 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::network::signed_document::public_key_of;
+use libp2p::identity::PublicKey;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TrustStoreError {
+    #[error("The public key of peer {0} can not be derived from its id")]
+    UnsupportedKey(String),
+    #[error("No trusted key with fingerprint {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// What a trusted key is trusted for.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Serialize,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TrustRole {
+    /// Signs in-toto attestations, like SLSA provenance, that are ingested.
+    Attestation,
+    /// Verifies artifacts, by building them or attesting to them, in a way
+    /// that counts towards the admission policy.
+    Verification,
+}
+
+/// The stable fingerprint of a public key: the SHA-256 hash of its protobuf
+/// encoding, e.g. `sha256:9f86d0...`.
+pub fn fingerprint(public_key: &PublicKey) -> String {
+    format!(
+        "sha256:{}",
+        hex::encode(Sha256::digest(public_key.to_protobuf_encoding()))
+    )
+}
+
+/// The fingerprint of the public key of a peer, for the peers whose public
+/// key is inlined in their id.
+pub fn fingerprint_of(peer_id: &PeerId) -> Option<String> {
+    public_key_of(peer_id).map(|public_key| fingerprint(&public_key))
+}
+
+/// A key in the trust store with the roles it is trusted for.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TrustedKey {
+    pub fingerprint: String,
+    pub peer_id: String,
+    pub roles: Vec<TrustRole>,
+}
+
+/// The local store of trusted keys, kept as a JSON file. The store is read
+/// from disk on every lookup, so changes are picked up by all clones.
+#[derive(Clone, Debug)]
+pub struct TrustStore {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl TrustStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        TrustStore {
+            path: path.as_ref().to_path_buf(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All trusted keys.
+    pub fn list(&self) -> Result<Vec<TrustedKey>, TrustStoreError> {
+        let _guard = self.lock.lock().unwrap();
+        self.read()
+    }
+
+    /// Trust the key of a peer for the specified roles. The roles of a key
+    /// that is already trusted are replaced.
+    pub fn add(
+        &self,
+        peer_id: &PeerId,
+        roles: Vec<TrustRole>,
+    ) -> Result<TrustedKey, TrustStoreError> {
+        let fingerprint = fingerprint_of(peer_id)
+            .ok_or_else(|| TrustStoreError::UnsupportedKey(peer_id.to_string()))?;
+        let trusted_key = TrustedKey {
+            fingerprint,
+            peer_id: peer_id.to_string(),
+            roles,
+        };
+
+        let _guard = self.lock.lock().unwrap();
+        let mut trusted_keys = self.read()?;
+        trusted_keys.retain(|key| key.fingerprint != trusted_key.fingerprint);
+        trusted_keys.push(trusted_key.clone());
+        self.write(&trusted_keys)?;
+        Ok(trusted_key)
+    }
+
+    /// Stop trusting the key with the specified fingerprint.
+    pub fn remove(&self, fingerprint: &str) -> Result<TrustedKey, TrustStoreError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut trusted_keys = self.read()?;
+        let position = trusted_keys
+            .iter()
+            .position(|key| key.fingerprint == fingerprint)
+            .ok_or_else(|| TrustStoreError::NotFound(fingerprint.to_owned()))?;
+        let trusted_key = trusted_keys.remove(position);
+        self.write(&trusted_keys)?;
+        Ok(trusted_key)
+    }
+
+    /// Whether the key of the peer is trusted for the specified role.
+    pub fn is_trusted(&self, peer_id: &PeerId, role: TrustRole) -> bool {
+        self.trusted_peers(role).contains(peer_id)
+    }
+
+    /// The peers whose keys are trusted for the specified role. A store
+    /// that can't be read trusts nobody.
+    pub fn trusted_peers(&self, role: TrustRole) -> Vec<PeerId> {
+        self.list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|key| key.roles.contains(&role))
+            .filter_map(|key| PeerId::from_str(&key.peer_id).ok())
+            .filter(|peer_id| fingerprint_of(peer_id).is_some())
+            .collect()
+    }
+
+    fn read(&self) -> Result<Vec<TrustedKey>, TrustStoreError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, trusted_keys: &[TrustedKey]) -> Result<(), TrustStoreError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(trusted_keys)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::util::test_util;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+
+        let fingerprint = fingerprint(&keypair.public());
+
+        assert!(fingerprint.starts_with("sha256:"));
+        assert_eq!(fingerprint.len(), "sha256:".len() + 64);
+        assert_eq!(fingerprint_of(&peer_id), Some(fingerprint));
+    }
+
+    #[test]
+    fn test_add_and_remove_trusted_keys() {
+        let tmp_dir = test_util::tests::setup();
+        let trust_store = TrustStore::new(tmp_dir.join("trust_store.json"));
+        let attester = Keypair::generate_ed25519().public().to_peer_id();
+        let verifier = Keypair::generate_ed25519().public().to_peer_id();
+
+        trust_store
+            .add(&attester, vec![TrustRole::Attestation])
+            .unwrap();
+        let trusted_verifier = trust_store
+            .add(&verifier, vec![TrustRole::Verification])
+            .unwrap();
+
+        assert_eq!(trust_store.list().unwrap().len(), 2);
+        assert!(trust_store.is_trusted(&attester, TrustRole::Attestation));
+        assert!(!trust_store.is_trusted(&attester, TrustRole::Verification));
+        assert_eq!(
+            trust_store.trusted_peers(TrustRole::Verification),
+            vec![verifier]
+        );
+
+        trust_store.remove(&trusted_verifier.fingerprint).unwrap();
+        assert!(trust_store
+            .trusted_peers(TrustRole::Verification)
+            .is_empty());
+        assert!(matches!(
+            trust_store.remove(&trusted_verifier.fingerprint),
+            Err(TrustStoreError::NotFound(_))
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
@@ -225,7 +225,19 @@ impl BlockchainEventLoop {
                 debug!("Handling broadcast blocks");
 
                 let payloads = block.fetch_payload();
-                if let Err(e) = self
+                if !self
+                    .artifact_service
+                    .accepts_block_from(&block.header.committer)
+                {
+                    sender
+                        .send(Err(anyhow::anyhow!(
+                            "Block #{} was committed by a node that is not authorized",
+                            block_ordinal
+                        )))
+                        .unwrap_or_else(|e| {
+                            error!("block broadcast error. {:#?}", e);
+                        });
+                } else if let Err(e) = self
                     .blockchain_service
                     .add_block(block_ordinal, block)
                     .await
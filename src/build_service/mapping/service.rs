@@ -51,6 +51,11 @@ impl MappingService {
                 build_spec_url: None,
             }),
             PackageType::Maven2 => self.get_maven_mapping(package_specific_id).await,
+            // generic artifacts are pushed by users, not built from source
+            PackageType::Generic => Err(BuildError::MappingNotFound {
+                package_type,
+                package_specific_id: package_specific_id.to_owned(),
+            }),
         }
     }
 
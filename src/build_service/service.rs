@@ -220,6 +220,7 @@ impl BuildService {
                     };
                     vec![format!("{}/{}", prefix, artifact_filename)]
                 }
+                PackageType::Generic => vec![package_specific_id.to_owned()],
             };
 
             debug!(
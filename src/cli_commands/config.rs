@@ -14,21 +14,35 @@
    limitations under the License.
 */
 
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
-use std::net::Ipv4Addr;
-use std::path::PathBuf;
+use std::fs;
+use std::io::Write;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+use crate::util::disk_space_util::parse_disk_space;
+
 const CONF_FILE: &str = "pyrsia-cli";
 
 /// The name of the environment variable to use for hardcoding the location
 /// of the configuration file during testing.
 const PYRSIA_CONFIG_LOCATION_FOR_TEST: &str = "PYRSIA_CONFIG_LOCATION_FOR_TEST";
 
+/// The name of the environment variable that selects the configuration
+/// profile when no profile is selected with [`set_profile`].
+pub const PYRSIA_PROFILE: &str = "PYRSIA_PROFILE";
+
+lazy_static! {
+    static ref SELECTED_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+}
+
 /// Gets the path of the configuration file. We always use [`confy::load_path`] and
 /// [`confy::store_path`] (instead of [`confy::load`] and [`confy::store`] respectively).
 /// That way we have full control over the exact location of the configuration file.
@@ -51,6 +65,17 @@ pub struct CliConfig {
     pub host: String,
     pub port: String,
     pub disk_allocated: String,
+    /// The scheme of the node API, http or https.
+    #[serde(default = "default_scheme")]
+    pub scheme: String,
+    /// The path of a PEM file with the CA certificates to trust in addition
+    /// to the system roots, for nodes with a certificate of a private CA.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ca_cert: String,
+}
+
+fn default_scheme() -> String {
+    "http".to_string()
 }
 
 impl Default for CliConfig {
@@ -59,6 +84,8 @@ impl Default for CliConfig {
             host: "localhost".to_string(),
             port: "7888".to_string(),
             disk_allocated: "10 GB".to_string(),
+            scheme: default_scheme(),
+            ca_cert: String::new(),
         }
     }
 }
@@ -75,31 +102,135 @@ impl PartialEq for CliConfig {
         self.host.as_str() == other.host.as_str()
             && self.port.as_str() == other.port.as_str()
             && self.disk_allocated.as_str() == other.disk_allocated.as_str()
+            && self.scheme.as_str() == other.scheme.as_str()
+            && self.ca_cert.as_str() == other.ca_cert.as_str()
     }
 }
 
+/// The contents of the configuration file: the default configuration and
+/// the named profiles, e.g. of a local node and a shared team node.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CliConfigFile {
+    #[serde(flatten)]
+    default: CliConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, CliConfig>,
+}
+
+/// Selects the profile that is used by all configuration functions,
+/// instead of the one in the [`PYRSIA_PROFILE`] environment variable.
+pub fn set_profile(profile: Option<String>) {
+    *SELECTED_PROFILE.lock().unwrap() = profile;
+}
+
+/// Returns the selected profile, or `None` when the default configuration
+/// is used.
+pub fn active_profile() -> Option<String> {
+    SELECTED_PROFILE
+        .lock()
+        .unwrap()
+        .clone()
+        .or_else(|| std::env::var(PYRSIA_PROFILE).ok())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Returns the names of the profiles in the configuration file.
+pub fn get_profiles() -> Result<Vec<String>> {
+    let config_file: CliConfigFile = confy::load_path(get_config_path()?)?;
+    Ok(config_file.profiles.into_keys().collect())
+}
+
+/// Updates the configuration of the active profile, which is created
+/// when it doesn't exist yet.
 pub fn add_config(new_cfg: CliConfig) -> Result<()> {
     let config_path = get_config_path()?;
 
-    let mut cfg: CliConfig = confy::load_path(&config_path)?;
+    let mut config_file: CliConfigFile = confy::load_path(&config_path)?;
+    let profile = active_profile();
+    let mut cfg = match &profile {
+        Some(profile) => config_file
+            .profiles
+            .get(profile)
+            .cloned()
+            .unwrap_or_default(),
+        None => config_file.default.clone(),
+    };
+    let mut errors: Vec<String> = Vec::new();
     if !new_cfg.host.is_empty() {
-        cfg.host = new_cfg.host
+        match valid_host_name(new_cfg.host) {
+            Ok(host) => cfg.host = host,
+            Err(description) => errors.push(description),
+        }
     }
 
     if !new_cfg.port.is_empty() {
-        cfg.port = new_cfg.port
+        match valid_port(new_cfg.port) {
+            Ok(port) => cfg.port = port,
+            Err(description) => errors.push(description),
+        }
     }
-    // need more validation for checking units
+
     if !new_cfg.disk_allocated.is_empty() {
-        cfg.disk_allocated = new_cfg.disk_allocated
+        match valid_disk_space(new_cfg.disk_allocated) {
+            Ok(disk_allocated) => cfg.disk_allocated = disk_allocated,
+            Err(description) => errors.push(description),
+        }
+    }
+
+    if !new_cfg.scheme.is_empty() {
+        match valid_scheme(new_cfg.scheme) {
+            Ok(scheme) => cfg.scheme = scheme,
+            Err(description) => errors.push(description),
+        }
+    }
+
+    // an empty CA certificate path means that only the system roots are
+    // trusted, so it is always taken over
+    match valid_ca_cert(new_cfg.ca_cert) {
+        Ok(ca_cert) => cfg.ca_cert = ca_cert,
+        Err(description) => errors.push(description),
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid pyrsia config: {}", errors.join(", "));
     }
 
-    confy::store_path(&config_path, &cfg)?;
+    match profile {
+        Some(profile) => {
+            config_file.profiles.insert(profile, cfg);
+        }
+        None => config_file.default = cfg,
+    }
+    store_config(&config_path, &config_file)
+}
+
+/// Writes the configuration to a temporary file next to the configuration
+/// file and renames it, so that an interrupted write never leaves a
+/// truncated configuration file behind.
+fn store_config(config_path: &Path, config_file: &CliConfigFile) -> Result<()> {
+    if let Some(config_dir) = config_path.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
+    let tmp_path = config_path.with_extension("tmp");
+    fs::write(&tmp_path, toml::to_string_pretty(config_file)?)?;
+    fs::rename(&tmp_path, config_path)?;
 
     Ok(())
 }
 
+/// Removes the active profile, or the whole configuration file when no
+/// profile is selected.
 pub fn config_remove() -> Result<()> {
+    remove_api_token()?;
+    if let Some(profile) = active_profile() {
+        let config_path = get_config_path()?;
+        let mut config_file: CliConfigFile = confy::load_path(&config_path)?;
+        if config_file.profiles.remove(&profile).is_none() {
+            bail!("Profile {} does not exist", profile);
+        }
+        return store_config(&config_path, &config_file);
+    }
+
     let cfg_patch = confy::get_configuration_file_path(CONF_FILE, None)?;
     if cfg_patch.exists() {
         std::fs::remove_file(cfg_patch)?;
@@ -111,6 +242,8 @@ pub fn config_edit(
     host_name: Option<String>,
     port: Option<String>,
     disk_space: Option<String>,
+    scheme: Option<String>,
+    ca_cert: Option<String>,
 ) -> Result<()> {
     let mut cli_config = get_config()?;
 
@@ -137,6 +270,20 @@ pub fn config_edit(
         }
     }
 
+    if let Some(validation_result) = scheme.map(valid_scheme) {
+        match validation_result {
+            Ok(scheme) => cli_config.scheme = scheme,
+            Err(description) => errors.push(description),
+        }
+    }
+
+    if let Some(validation_result) = ca_cert.map(valid_ca_cert) {
+        match validation_result {
+            Ok(ca_cert) => cli_config.ca_cert = ca_cert,
+            Err(description) => errors.push(description),
+        }
+    }
+
     if errors.is_empty() {
         add_config(cli_config)
     } else {
@@ -145,7 +292,8 @@ pub fn config_edit(
     }
 }
 
-/// Returns true if input is a valid hostname or a valid IPv4 address
+/// Returns the input if it is a valid IP address, or a valid hostname that
+/// can be resolved, otherwise a description of the problem.
 pub fn valid_host_name(input: String) -> Result<String, String> {
     /// Returns true if input is a valid hostname as per the definition
     /// at https://man7.org/linux/man-pages/man7/hostname.7.html, otherwise false
@@ -158,54 +306,161 @@ pub fn valid_host_name(input: String) -> Result<String, String> {
         }
         HOSTNAME_REGEX.is_match(input)
     }
-    /// Returns true if input is a valid IPv4 address, otherwise false
-    fn valid_ipv4_address(input: &str) -> bool {
-        input.parse::<Ipv4Addr>().is_ok()
-    }
 
-    if valid_ipv4_address(&input) || valid_hostname(&input) {
-        Ok(input)
-    } else {
-        Err("Invalid value for Hostname".to_owned())
+    if input.parse::<IpAddr>().is_ok() {
+        return Ok(input);
+    }
+    if !valid_hostname(&input) {
+        return Err(format!(
+            "Invalid value for Hostname: '{}' is neither a valid hostname nor an IP address",
+            input
+        ));
+    }
+    match (input.as_str(), 0).to_socket_addrs() {
+        Ok(mut addresses) if addresses.next().is_some() => Ok(input),
+        Ok(_) => Err(format!(
+            "Invalid value for Hostname: '{}' does not resolve to an address",
+            input
+        )),
+        Err(e) => Err(format!(
+            "Invalid value for Hostname: '{}' could not be resolved: {}",
+            input, e
+        )),
     }
 }
 
 pub fn valid_port(input: String) -> Result<String, String> {
     match input.parse::<u16>() {
         Ok(_) => Ok(input),
-        Err(_) => Err("Invalid value for Port Number".to_owned()),
+        Err(_) => Err(format!(
+            "Invalid value for Port Number: '{}' is not a number between 0 and {}",
+            input,
+            u16::MAX
+        )),
     }
 }
 
-/// Disk space will only accept integer values. Currently we it will accept value greater than 0 GB till 4096 GB
+pub fn valid_scheme(input: String) -> Result<String, String> {
+    match input.as_str() {
+        "http" | "https" => Ok(input),
+        _ => Err(format!(
+            "Invalid value for Scheme: '{}' must be http or https",
+            input
+        )),
+    }
+}
+
+/// Returns the input if it is empty, i.e. no custom CA, or the path of a
+/// PEM file with at least one certificate.
+pub fn valid_ca_cert(input: String) -> Result<String, String> {
+    if input.is_empty() {
+        return Ok(input);
+    }
+    match fs::read(&input) {
+        Ok(pem) if reqwest::Certificate::from_pem(&pem).is_ok() => Ok(input),
+        Ok(_) => Err(format!(
+            "Invalid value for CA Certificate: '{}' is not a PEM encoded certificate",
+            input
+        )),
+        Err(e) => Err(format!(
+            "Invalid value for CA Certificate: '{}' cannot be read: {}",
+            input, e
+        )),
+    }
+}
+
+/// Disk space will only accept integer values with a unit of MB, GB or TB,
+/// like `512 MB` or `10 GB`. Currently it will accept values greater than
+/// 0 till 4096 GB.
 pub fn valid_disk_space(input: String) -> Result<String, String> {
-    const DISK_SPACE_NUM_MIN: u16 = 0;
-    const DISK_SPACE_NUM_MAX: u16 = 4096;
+    const DISK_SPACE_MAX: &str = "4096 GB";
     lazy_static! {
-        static ref DISK_SPACE_RE: Regex = Regex::new(r"^([0-9,\.]+)\s+(GB)$").unwrap();
-    }
-    if DISK_SPACE_RE.is_match(&input) {
-        let captured_groups = DISK_SPACE_RE.captures(&input).unwrap();
-        //Group 1 is numeric part including decimal & Group 2 is metric part
-        if let Ok(disk_space_num) = captured_groups.get(1).unwrap().as_str().parse::<u16>() {
-            if DISK_SPACE_NUM_MIN < disk_space_num && disk_space_num <= DISK_SPACE_NUM_MAX {
-                return Ok(format!(
-                    "{} {}",
-                    disk_space_num,
-                    captured_groups.get(2).unwrap().as_str()
-                ));
-            }
+        static ref DISK_SPACE_RE: Regex = Regex::new(r"^([0-9]+)\s+(MB|GB|TB)$").unwrap();
+    }
+    let invalid = |reason: &str| {
+        Err(format!(
+            "Invalid value for Disk Allocation: '{}' {}",
+            input, reason
+        ))
+    };
+    if !DISK_SPACE_RE.is_match(&input) {
+        return invalid(
+            "must be a whole number followed by a space and a unit of MB, GB or TB, like 10 GB",
+        );
+    }
+    match parse_disk_space(&input) {
+        Ok(0) => invalid("must be greater than 0"),
+        Ok(bytes) if bytes > parse_disk_space(DISK_SPACE_MAX).unwrap() => {
+            invalid(&format!("must not be more than {}", DISK_SPACE_MAX))
         }
+        Ok(_) => Ok(input.clone()),
+        Err(e) => invalid(&e),
     }
-    Err("Invalid value for Disk Allocation".to_owned())
 }
 
+/// Returns the configuration of the active profile.
 pub fn get_config() -> Result<CliConfig> {
     let config_path = get_config_path()?;
 
-    let cfg: CliConfig = confy::load_path(config_path)?;
+    let mut config_file: CliConfigFile = confy::load_path(config_path)?;
+    match active_profile() {
+        Some(profile) => config_file.profiles.remove(&profile).ok_or_else(|| {
+            anyhow!(
+                "Profile {} does not exist, create it with 'pyrsia config --profile {} --edit'",
+                profile,
+                profile
+            )
+        }),
+        None => Ok(config_file.default),
+    }
+}
 
-    Ok(cfg)
+// The API token of each profile is stored in its own file next to the
+// configuration file, which only the user can read, so that the
+// configuration itself can be shared.
+fn api_token_path() -> Result<PathBuf> {
+    let config_path = get_config_path()?;
+    let config_dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow!("Invalid config file path"))?;
+    let profile = active_profile().unwrap_or_else(|| "default".to_string());
+    Ok(config_dir.join("api_tokens").join(profile))
+}
+
+/// Stores the API token that the CLI presents to the node of the active
+/// profile.
+pub fn store_api_token(api_token: &str) -> Result<()> {
+    let api_token_path = api_token_path()?;
+    if let Some(api_tokens_dir) = api_token_path.parent() {
+        fs::create_dir_all(api_tokens_dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&api_token_path)?
+        .write_all(api_token.as_bytes())?;
+
+    Ok(())
+}
+
+/// Returns the stored API token of the active profile, if any.
+pub fn api_token() -> Option<String> {
+    api_token_path()
+        .ok()
+        .and_then(|api_token_path| fs::read_to_string(api_token_path).ok())
+        .map(|api_token| api_token.trim().to_string())
+        .filter(|api_token| !api_token.is_empty())
+}
+
+/// Removes the stored API token of the active profile.
+pub fn remove_api_token() -> Result<()> {
+    let api_token_path = api_token_path()?;
+    if api_token_path.exists() {
+        fs::remove_file(api_token_path)?;
+    }
+    Ok(())
 }
 
 pub fn get_config_file_path() -> Result<PathBuf> {
@@ -239,6 +494,9 @@ mod tests {
             add_config(cli_config_2.clone()).expect("add_config failed");
             let current_cli_config = get_config().expect("get_config failed");
             assert_eq!(current_cli_config.port, cli_config_2.port);
+
+            let config_path = get_config_path().expect("cannot get config file path");
+            assert!(!config_path.with_extension("tmp").exists());
         });
     }
 
@@ -259,6 +517,95 @@ mod tests {
         });
     }
 
+    #[test]
+    #[serial]
+    fn test_config_profiles() {
+        setup_temp_home_dir_and_execute(|| {
+            add_config(CliConfig::default()).expect("add_config failed");
+
+            set_profile(Some("staging".to_string()));
+            assert!(get_config().is_err());
+            add_config(CliConfig {
+                host: "127.0.0.1".to_string(),
+                port: "7889".to_string(),
+                ..Default::default()
+            })
+            .expect("add_config failed");
+            assert_eq!(get_config().unwrap().port, "7889");
+            assert_eq!(get_profiles().unwrap(), vec!["staging".to_string()]);
+
+            set_profile(None);
+            assert_eq!(get_config().unwrap(), CliConfig::default());
+
+            set_profile(Some("staging".to_string()));
+            config_remove().expect("config_remove failed");
+            assert!(get_config().is_err());
+            set_profile(None);
+            assert!(get_profiles().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn test_add_config_rejects_invalid_values() {
+        setup_temp_home_dir_and_execute(|| {
+            let result = add_config(CliConfig {
+                host: "-pyrsia.io".to_string(),
+                port: "65536".to_string(),
+                disk_allocated: "10 XB".to_string(),
+                scheme: "ftp".to_string(),
+                ca_cert: "/nonexistent/ca.pem".to_string(),
+            });
+            let error = result.expect_err("add_config must fail").to_string();
+            assert!(error.contains("Hostname"));
+            assert!(error.contains("Port Number"));
+            assert!(error.contains("Disk Allocation"));
+            assert!(error.contains("Scheme"));
+            assert!(error.contains("CA Certificate"));
+            assert_eq!(get_config().unwrap(), CliConfig::default());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_scheme() {
+        setup_temp_home_dir_and_execute(|| {
+            config_edit(None, None, None, Some("https".to_string()), None)
+                .expect("config_edit failed");
+            assert_eq!(get_config().unwrap().scheme, "https");
+            assert!(valid_scheme("ftp".to_string()).is_err());
+            assert!(valid_ca_cert(String::new()).is_ok());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn test_api_token_per_profile() {
+        setup_temp_home_dir_and_execute(|| {
+            assert_eq!(api_token(), None);
+            store_api_token("default_token").expect("store_api_token failed");
+
+            set_profile(Some("staging".to_string()));
+            assert_eq!(api_token(), None);
+            store_api_token("staging_token").expect("store_api_token failed");
+            assert_eq!(api_token(), Some("staging_token".to_string()));
+            remove_api_token().expect("remove_api_token failed");
+            assert_eq!(api_token(), None);
+
+            set_profile(None);
+            assert_eq!(api_token(), Some("default_token".to_string()));
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = fs::metadata(api_token_path().unwrap())
+                    .unwrap()
+                    .permissions()
+                    .mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        });
+    }
+
     #[test]
     #[serial]
     fn test_remove_not_existed_config_file() {
@@ -290,8 +637,13 @@ mod tests {
         disk_allocated: Option<String>,
     ) {
         let existing_cli_config = get_config().unwrap();
-        let config_edit_result =
-            config_edit(host_name.clone(), port.clone(), disk_allocated.clone());
+        let config_edit_result = config_edit(
+            host_name.clone(),
+            port.clone(),
+            disk_allocated.clone(),
+            None,
+            None,
+        );
         let updated_cli_config = get_config().unwrap();
         if config_edit_result.is_ok() {
             //restore the config to original state after test
@@ -302,6 +654,7 @@ mod tests {
                 host: host_name.unwrap_or(existing_cli_config.host),
                 port: port.unwrap_or(existing_cli_config.port),
                 disk_allocated: disk_allocated.unwrap_or(existing_cli_config.disk_allocated),
+                ..existing_cli_config
             },
             updated_cli_config
         );
@@ -310,7 +663,7 @@ mod tests {
     #[test]
     #[serial]
     fn test_config_edit_only_with_valid_host_name() {
-        test_common_valid_config_edit(Some("some.localhost".to_string()), None, None);
+        test_common_valid_config_edit(Some("localhost".to_string()), None, None);
     }
 
     #[test]
@@ -329,7 +682,7 @@ mod tests {
     #[serial]
     fn test_config_edit_with_all_valid_attributes() {
         test_common_valid_config_edit(
-            Some("some.localhost".to_string()),
+            Some("localhost".to_string()),
             Some(u16::MAX.to_string()),
             Some("10 GB".to_string()),
         );
@@ -346,17 +699,20 @@ mod tests {
             Some(host_name.to_owned()),
             Some(port.clone()),
             Some(disk_space.to_owned()),
+            None,
+            None,
         );
         let updated_cli_config = get_config().unwrap();
         if config_edit_result.is_ok() {
             //restore the config to original state after test
-            let _restore_config = add_config(existing_cli_config);
+            let _restore_config = add_config(existing_cli_config.clone());
         }
         assert_ne!(
             CliConfig {
                 host: host_name.to_owned(),
                 port,
-                disk_allocated: disk_space.to_owned()
+                disk_allocated: disk_space.to_owned(),
+                ..existing_cli_config
             },
             updated_cli_config
         );
@@ -370,7 +726,7 @@ mod tests {
 
     #[test]
     fn test_valid_host() {
-        let valid_hosts = vec!["pyrsia.io", "localhost", "10.10.10.255"];
+        let valid_hosts = vec!["localhost", "10.10.10.255", "::1"];
         assert!(valid_hosts
             .into_iter()
             .all(|x| valid_host_name(x.to_owned()).is_ok()));
@@ -382,6 +738,7 @@ mod tests {
             "-pyrsia.io",
             "@localhost",
             "%*%*%*%*NO_SENSE_AS_HOST@#$*@#$*@#$*",
+            "unresolvable.invalid",
         ];
         assert!(!invalid_hosts
             .into_iter()
@@ -406,7 +763,7 @@ mod tests {
 
     #[test]
     fn test_valid_disk_space() {
-        let valid_disk_space_list = vec!["100 GB", "1 GB", "4096 GB"];
+        let valid_disk_space_list = vec!["100 GB", "1 GB", "4096 GB", "512 MB", "4 TB"];
         assert!(valid_disk_space_list
             .into_iter()
             .all(|x| valid_disk_space(x.to_owned()).is_ok()));
@@ -423,6 +780,9 @@ mod tests {
             "5..84 GB",
             "5..84 GB",
             "5.84.22 GB",
+            "0 MB",
+            "5 TB",
+            "10 KB",
         ];
         assert!(!invalid_disk_space_list
             .into_iter()
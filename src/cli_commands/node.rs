@@ -16,69 +16,417 @@
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use reqwest::Response;
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::{Body, Certificate, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
+use std::fs;
+use std::io::{Cursor, Read, Write};
 
+use crate::artifact_service::events::NodeEvent;
+use crate::artifact_service::model::VerificationReport;
+use crate::artifact_service::trust_store::TrustedKey;
+use crate::docker::prefetch::PrefetchedImage;
+use crate::logging::buffer::LogEntry;
+use crate::network::bench::BenchReport;
+use crate::node_api::api_tokens::{IssuedApiToken, NewApiToken};
 use crate::node_api::model::cli::{
-    RequestAddAuthorizedNode, RequestBuildStatus, RequestDockerBuild, RequestDockerLog,
-    RequestMavenBuild, RequestMavenLog, Status,
+    ArtifactInspection, ArtifactList, ConnectedPeer, ImportedArtifacts, InspectQuery, LogQuery,
+    NodePing, PushQuery, PushedArtifact, RequestAddAuthorizedNode, RequestBench,
+    RequestBuildStatus, RequestConnectPeer, RequestDisconnectPeer, RequestDockerBuild,
+    RequestDockerLog, RequestMavenBuild, RequestMavenLog, RequestPrefetchImage, RequestTrustKey,
+    RequestVerify, SearchQuery, SearchResult, Status,
 };
+use crate::node_api::pagination::{ListQuery, Page, MAX_PAGE_SIZE};
+use crate::node_api::response_signing::{verify_signature, SIGNATURE_HEADER};
+use crate::transparency_log::key_rotation::KeyRotationCertificate;
+use crate::util::env_util::read_var;
 
-use super::config::get_config;
+use super::config::{api_token, get_config};
 
-pub async fn ping() -> Result<String> {
-    //TODO: implement ping api in Node
-    let node_url = format!("http://{}/v2", get_url());
-    let response = reqwest::get(node_url).await?.text().await?;
-    Ok(response)
+/// Receives the number of bytes transferred so far, and the total number of
+/// bytes when it is known, while a request moves artifacts to or from the
+/// node.
+pub type OnProgress = Box<dyn FnMut(u64, Option<u64>) + Send + Sync>;
+
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+pub async fn ping() -> Result<NodePing> {
+    let node_url = format!("{}/ping", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<NodePing>(&response)?)
+}
+
+pub async fn peers_connected() -> Result<Vec<ConnectedPeer>> {
+    let node_url = format!("{}/peers", get_url());
+    let query = ListQuery {
+        limit: MAX_PAGE_SIZE,
+        ..Default::default()
+    };
+    let response = node_client()?
+        .get(node_url)
+        .query(&query)
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Page<ConnectedPeer>>(&response)?.items)
+}
+
+pub async fn list_artifacts(query: ListQuery) -> Result<ArtifactList> {
+    let node_url = format!("{}/artifacts", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<ArtifactList>(&response)?)
+}
+
+pub async fn inspect_artifact(reference: &str) -> Result<Vec<ArtifactInspection>> {
+    let node_url = format!("{}/inspect", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .query(&InspectQuery {
+            reference: reference.to_owned(),
+        })
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Vec<ArtifactInspection>>(
+        &response,
+    )?)
+}
+
+pub async fn search_artifacts(query: &SearchQuery) -> Result<Vec<SearchResult>> {
+    let node_url = format!("{}/search", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .query(query)
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Vec<SearchResult>>(&response)?)
 }
 
-pub async fn peers_connected() -> Result<String> {
-    let node_url = format!("http://{}/peers", get_url());
-    let response = reqwest::get(node_url).await?.text().await?;
-    Ok(response)
+/// Let the node fetch an image and all its layers ahead of time.
+pub async fn prefetch_image(image: &str) -> Result<PrefetchedImage> {
+    let node_url = format!("{}/admin/prefetch", get_url());
+    let response = node_client()?
+        .post(node_url)
+        .json(&RequestPrefetchImage {
+            image: image.to_owned(),
+        })
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<PrefetchedImage>(&response)?)
+}
+
+/// Write the archive of the artifacts stored by the node to `writer` while it
+/// is downloaded. Returns the size of the archive.
+pub async fn export_artifacts(
+    mut writer: impl Write + Send,
+    mut on_progress: OnProgress,
+) -> Result<u64> {
+    let node_url = format!("{}/admin/artifacts/export", get_url());
+    let mut response = node_client()?
+        .get(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?;
+    let total = response.content_length();
+    let mut exported = 0;
+    while let Some(chunk) = response.chunk().await? {
+        writer.write_all(&chunk)?;
+        exported += chunk.len() as u64;
+        on_progress(exported, total);
+    }
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Upload an archive of `size` bytes that was created by `export_artifacts`
+/// to the node while it is read.
+pub async fn import_artifacts(
+    archive: impl Read + Send + Sync + 'static,
+    size: u64,
+    on_progress: OnProgress,
+) -> Result<ImportedArtifacts> {
+    let node_url = format!("{}/admin/artifacts/import", get_url());
+    let response = node_client()?
+        .post(node_url)
+        .header(CONTENT_LENGTH, size)
+        .body(upload_body(archive, size, on_progress))
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<ImportedArtifacts>(&response)?)
+}
+
+pub async fn push_artifact(
+    name: &str,
+    artifact: Vec<u8>,
+    on_progress: OnProgress,
+) -> Result<PushedArtifact> {
+    let size = artifact.len() as u64;
+    let node_url = format!("{}/admin/artifacts/generic", get_url());
+    let response = node_client()?
+        .post(node_url)
+        .query(&PushQuery {
+            name: name.to_owned(),
+        })
+        .header(CONTENT_LENGTH, size)
+        .body(upload_body(Cursor::new(artifact), size, on_progress))
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<PushedArtifact>(&response)?)
 }
 
 pub async fn status() -> Result<Status> {
-    let node_url = format!("http://{}/status", get_url());
+    let node_url = format!("{}/status", get_url());
 
-    let response = reqwest::get(node_url).await?.json::<Status>().await?;
-    Ok(response)
+    let response = node_client()?
+        .get(node_url)
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Status>(&response)?)
+}
+
+/// Follow the event stream of the node and pass every event to `on_event`,
+/// until the node closes the stream.
+pub async fn follow_events<F: FnMut(NodeEvent)>(on_event: F) -> Result<()> {
+    let node_url = format!("{}/node/events", get_url());
+    follow_server_sent_events(node_client()?.get(node_url), on_event).await
+}
+
+/// Fetch the most recent entries of the node log.
+pub async fn node_logs(query: &LogQuery) -> Result<Vec<LogEntry>> {
+    let node_url = format!("{}/node/logs", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .query(query)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .bytes()
+        .await?;
+    Ok(serde_json::from_slice(&response)?)
+}
+
+/// Follow the node log and pass every entry to `on_entry`, starting with the
+/// most recent entries, until the node closes the stream.
+pub async fn follow_logs<F: FnMut(LogEntry)>(query: &LogQuery, on_entry: F) -> Result<()> {
+    let node_url = format!("{}/node/logs/follow", get_url());
+    follow_server_sent_events(node_client()?.get(node_url).query(query), on_entry).await
+}
+
+async fn follow_server_sent_events<T: DeserializeOwned, F: FnMut(T)>(
+    request: RequestBuilder,
+    mut on_item: F,
+) -> Result<()> {
+    let mut response = request.send().await?.error_for_status_with_body().await?;
+    let mut buffer: Vec<u8> = vec![];
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+            let message: Vec<u8> = buffer.drain(..end + 2).collect();
+            for data in String::from_utf8(message)?
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+            {
+                on_item(serde_json::from_str(data.trim())?);
+            }
+        }
+    }
+    Ok(())
 }
 
 pub async fn add_authorized_node(request: RequestAddAuthorizedNode) -> Result<()> {
-    post_and_parse_result_as_text(format!("http://{}/authorized_node", get_url()), request)
+    post_and_parse_result_as_text(format!("{}/admin/authorized_node", get_url()), request)
+        .await
+        .map(|_| ())
+}
+
+pub async fn trusted_keys() -> Result<Vec<TrustedKey>> {
+    let node_url = format!("{}/trust_store", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Vec<TrustedKey>>(&response)?)
+}
+
+pub async fn add_trusted_key(request: RequestTrustKey) -> Result<TrustedKey> {
+    let response =
+        post_and_parse_result_as_text(format!("{}/admin/trust_store", get_url()), request).await?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+pub async fn verify(request: RequestVerify) -> Result<VerificationReport> {
+    let response = post_and_parse_result_as_text(format!("{}/verify", get_url()), request).await?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+pub async fn remove_trusted_key(fingerprint: &str) -> Result<()> {
+    let node_url = format!("{}/admin/trust_store/{}", get_url(), fingerprint);
+    node_client()?
+        .delete(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?;
+    Ok(())
+}
+
+pub async fn admin_evict_artifact(artifact_hash: &str) -> Result<Vec<String>> {
+    let node_url = format!("{}/admin/artifacts/{}", get_url(), artifact_hash);
+    let response = node_client()?
+        .delete(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Vec<String>>(&response)?)
+}
+
+pub async fn admin_connect_peer(address: &str) -> Result<String> {
+    post_and_parse_result_as_text(
+        format!("{}/admin/peers/connect", get_url()),
+        RequestConnectPeer {
+            address: address.to_owned(),
+        },
+    )
+    .await
+}
+
+pub async fn admin_bench(request: &RequestBench) -> Result<BenchReport> {
+    let node_url = format!("{}/admin/bench", get_url());
+    let response = node_client()?
+        .post(node_url)
+        .json(request)
+        .send()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<BenchReport>(&response)?)
+}
+
+pub async fn admin_disconnect_peer(peer_id: &str, ban: bool) -> Result<()> {
+    post_and_parse_result_as_text(
+        format!("{}/admin/peers/{}/disconnect", get_url(), peer_id),
+        RequestDisconnectPeer { ban },
+    )
+    .await
+    .map(|_| ())
+}
+
+pub async fn admin_collect_garbage() -> Result<Vec<String>> {
+    let response = post_and_parse_result_as_text(format!("{}/admin/gc", get_url()), ()).await?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+pub async fn admin_scrub() -> Result<()> {
+    post_and_parse_result_as_text(format!("{}/admin/scrub", get_url()), ())
+        .await
+        .map(|_| ())
+}
+
+pub async fn admin_issue_api_token() -> Result<NewApiToken> {
+    let response = post_and_parse_result_as_text(format!("{}/admin/tokens", get_url()), ()).await?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+pub async fn admin_api_tokens() -> Result<Vec<IssuedApiToken>> {
+    let node_url = format!("{}/admin/tokens", get_url());
+    let response = node_client()?
+        .get(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?
+        .verified_body()
+        .await?;
+    Ok(serde_json::from_slice::<Vec<IssuedApiToken>>(&response)?)
+}
+
+pub async fn admin_revoke_api_token(id: &str) -> Result<()> {
+    let node_url = format!("{}/admin/tokens/{}", get_url(), id);
+    node_client()?
+        .delete(node_url)
+        .send()
+        .await?
+        .error_for_status_with_body()
+        .await?;
+    Ok(())
+}
+
+pub async fn add_key_rotation(certificate: KeyRotationCertificate) -> Result<()> {
+    post_and_parse_result_as_text(format!("{}/admin/key_rotations", get_url()), certificate)
         .await
         .map(|_| ())
 }
 
 pub async fn request_docker_build(request: RequestDockerBuild) -> Result<String> {
-    post_and_parse_result_as_json(format!("http://{}/build/docker", get_url()), request).await
+    post_and_parse_result_as_json(format!("{}/build/docker", get_url()), request).await
 }
 
 pub async fn request_build_status(request: RequestBuildStatus) -> Result<String> {
-    post_and_parse_result_as_json(format!("http://{}/build/status", get_url()), request).await
+    post_and_parse_result_as_json(format!("{}/build/status", get_url()), request).await
 }
 
 pub async fn request_maven_build(request: RequestMavenBuild) -> Result<String> {
-    post_and_parse_result_as_json(format!("http://{}/build/maven", get_url()), request).await
+    post_and_parse_result_as_json(format!("{}/build/maven", get_url()), request).await
 }
 
 pub async fn inspect_docker_transparency_log(request: RequestDockerLog) -> Result<String> {
-    post_and_parse_result_as_text(format!("http://{}/inspect/docker", get_url()), request).await
+    post_and_parse_result_as_text(format!("{}/inspect/docker", get_url()), request).await
 }
 
 pub async fn inspect_maven_transparency_log(request: RequestMavenLog) -> Result<String> {
-    post_and_parse_result_as_text(format!("http://{}/inspect/maven", get_url()), request).await
+    post_and_parse_result_as_text(format!("{}/inspect/maven", get_url()), request).await
 }
 
+/// The base URL of the node API, e.g. http://localhost:7888.
 pub fn get_url() -> String {
     let result = get_config();
+    let mut scheme = String::from("http");
     let mut host = String::new();
     let mut port = String::new();
     match result {
         Ok(data) => {
+            scheme = data.scheme;
             host = data.host;
             port = data.port;
         }
@@ -87,14 +435,14 @@ pub fn get_url() -> String {
         }
     };
 
-    format!("{}:{}", host, port)
+    format!("{}://{}:{}", scheme, host, port)
 }
 
 async fn post_and_parse_result_as_json<T: Serialize>(
     node_url: String,
     request: T,
 ) -> Result<String> {
-    let client = reqwest::Client::new();
+    let client = node_client()?;
     client
         .post(node_url)
         .json(&request)
@@ -108,7 +456,7 @@ async fn post_and_parse_result_as_text<T: Serialize>(
     node_url: String,
     request: T,
 ) -> Result<String> {
-    let client = reqwest::Client::new();
+    let client = node_client()?;
     client
         .post(node_url)
         .json(&request)
@@ -118,25 +466,78 @@ async fn post_and_parse_result_as_text<T: Serialize>(
         .await
 }
 
+/// The HTTP client for requests to the node. It presents the API token from
+/// PYRSIA_API_TOKEN, or else the one stored with 'pyrsia login', as bearer
+/// token, and trusts the CA certificate of the configuration.
+fn node_client() -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    let bearer_token = match read_var("PYRSIA_API_TOKEN", "") {
+        bearer_token if bearer_token.is_empty() => api_token().unwrap_or_default(),
+        bearer_token => bearer_token,
+    };
+    if !bearer_token.is_empty() {
+        if let Ok(authorization) = HeaderValue::from_str(&format!("Bearer {}", bearer_token)) {
+            headers.insert(AUTHORIZATION, authorization);
+        }
+    }
+    let mut client_builder = reqwest::Client::builder().default_headers(headers);
+    if let Ok(config) = get_config() {
+        if !config.ca_cert.is_empty() {
+            let pem = fs::read(&config.ca_cert)
+                .map_err(|e| anyhow!("Cannot read CA certificate {}: {}", config.ca_cert, e))?;
+            client_builder = client_builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+    }
+    Ok(client_builder.build()?)
+}
+
+// Send the data in chunks, so the progress of the upload can be reported.
+// The node requires the length of the body up front, so the request must
+// set the Content-Length header itself.
+// Upload what is read from `reader` in chunks, reporting the progress
+// against the `total` size.
+fn upload_body(
+    mut reader: impl Read + Send + Sync + 'static,
+    total: u64,
+    mut on_progress: OnProgress,
+) -> Body {
+    let mut uploaded = 0;
+    let chunks = std::iter::from_fn(move || {
+        let mut chunk = vec![0; UPLOAD_CHUNK_SIZE];
+        match reader.read(&mut chunk) {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                uploaded += read as u64;
+                on_progress(uploaded, Some(total));
+                Some(Ok(Bytes::from(chunk)))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    });
+    Body::wrap_stream(futures::stream::iter(chunks))
+}
+
 #[async_trait]
 trait ErrorResponseWithBody {
     async fn json_or_error_with_body(self) -> Result<String>;
     async fn text_or_error_with_body(self) -> Result<String>;
     async fn error_for_status_with_body(self) -> Result<Response>;
+    async fn verified_body(self) -> Result<Vec<u8>>;
 }
 
 #[async_trait]
 impl ErrorResponseWithBody for Response {
     async fn json_or_error_with_body(self) -> Result<String> {
         match self.error_for_status_with_body().await {
-            Ok(r) => Ok(r.json::<String>().await?),
+            Ok(r) => Ok(serde_json::from_slice::<String>(&r.verified_body().await?)?),
             Err(e) => Err(e),
         }
     }
 
     async fn text_or_error_with_body(self) -> Result<String> {
         match self.error_for_status_with_body().await {
-            Ok(r) => Ok(r.text().await?),
+            Ok(r) => Ok(String::from_utf8(r.verified_body().await?)?),
             Err(e) => Err(e),
         }
     }
@@ -155,4 +556,19 @@ impl ErrorResponseWithBody for Response {
         }
         Ok(self)
     }
+
+    // Read the body of the response. When the node signed the response, the
+    // body is only returned if it matches the signature.
+    async fn verified_body(self) -> Result<Vec<u8>> {
+        let signature = self
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .map(|value| value.to_str().map(str::to_owned))
+            .transpose()?;
+        let body = self.bytes().await?.to_vec();
+        if let Some(signature) = signature {
+            verify_signature(&signature, &body)?;
+        }
+        Ok(body)
+    }
 }
@@ -16,4 +16,5 @@
 
 pub mod constants;
 pub mod error_util;
+pub mod prefetch;
 pub mod v2;
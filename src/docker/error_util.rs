@@ -14,13 +14,17 @@
    limitations under the License.
 */
 
+use crate::artifact_service::policy::PolicyViolation;
 use crate::build_service::error::BuildError;
+use crate::peer_metrics::load_shedding::Overloaded;
 use crate::transparency_log::log::TransparencyLogError;
+use crate::util::rate_limit::TooManyRequests;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
 use std::error::Error;
 use std::string::FromUtf8Error;
+use warp::http::header::{HeaderValue, RETRY_AFTER};
 use warp::http::StatusCode;
 use warp::reject::Reject;
 use warp::{Rejection, Reply};
@@ -39,7 +43,13 @@ pub struct ErrorMessages {
 pub enum RegistryErrorCode {
     BlobUnknown,
     ManifestUnknown,
+    NotFound(String),
     BadRequest(String),
+    Denied(String),
+    Revoked(String),
+    Unauthorized(String),
+    TooManyRequests(String),
+    Unavailable(String),
     Unknown(String),
 }
 
@@ -48,6 +58,25 @@ pub struct RegistryError {
     pub code: RegistryErrorCode,
 }
 
+impl RegistryError {
+    /// Maps an error that occurred while retrieving an artifact to the
+    /// specified code, unless the artifact was denied by the admission policy
+    /// or revoked.
+    pub fn from_artifact_error(err: anyhow::Error, code: RegistryErrorCode) -> RegistryError {
+        if let Some(violation) = err.downcast_ref::<PolicyViolation>() {
+            return RegistryError {
+                code: RegistryErrorCode::Denied(violation.to_string()),
+            };
+        }
+        match err.downcast_ref::<TransparencyLogError>() {
+            Some(revoked @ TransparencyLogError::ArtifactRevoked { .. }) => RegistryError {
+                code: RegistryErrorCode::Revoked(revoked.to_string()),
+            },
+            _ => RegistryError { code },
+        }
+    }
+}
+
 impl From<anyhow::Error> for RegistryError {
     fn from(err: anyhow::Error) -> RegistryError {
         RegistryError {
@@ -75,6 +104,9 @@ impl From<TransparencyLogError> for RegistryError {
             TransparencyLogError::NodeAlreadyExists { .. } => RegistryError {
                 code: RegistryErrorCode::BadRequest(err.to_string()),
             },
+            TransparencyLogError::ArtifactRevoked { .. } => RegistryError {
+                code: RegistryErrorCode::Revoked(err.to_string()),
+            },
             _ => RegistryError {
                 code: RegistryErrorCode::Unknown(err.to_string()),
             },
@@ -166,6 +198,7 @@ impl Reject for RegistryError {}
 
 pub async fn custom_recover(err: Rejection) -> Result<impl Reply, Infallible> {
     let mut status_code = StatusCode::INTERNAL_SERVER_ERROR;
+    let mut retry_after_secs = None;
     let mut error_message = ErrorMessage {
         code: RegistryErrorCode::Unknown("".to_string()),
         message: "".to_string(),
@@ -182,11 +215,41 @@ pub async fn custom_recover(err: Rejection) -> Result<impl Reply, Infallible> {
                 status_code = StatusCode::NOT_FOUND;
                 error_message.code = RegistryErrorCode::ManifestUnknown;
             }
+            RegistryErrorCode::NotFound(m) => {
+                status_code = StatusCode::NOT_FOUND;
+                error_message.code = RegistryErrorCode::NotFound(m.clone());
+                error_message.message = m.clone();
+            }
             RegistryErrorCode::BadRequest(m) => {
                 status_code = StatusCode::BAD_REQUEST;
                 error_message.code = RegistryErrorCode::BadRequest(m.clone());
                 error_message.message = m.clone();
             }
+            RegistryErrorCode::Denied(m) => {
+                status_code = StatusCode::FORBIDDEN;
+                error_message.code = RegistryErrorCode::Denied(m.clone());
+                error_message.message = m.clone();
+            }
+            RegistryErrorCode::Revoked(m) => {
+                status_code = StatusCode::GONE;
+                error_message.code = RegistryErrorCode::Revoked(m.clone());
+                error_message.message = m.clone();
+            }
+            RegistryErrorCode::Unauthorized(m) => {
+                status_code = StatusCode::UNAUTHORIZED;
+                error_message.code = RegistryErrorCode::Unauthorized(m.clone());
+                error_message.message = m.clone();
+            }
+            RegistryErrorCode::TooManyRequests(m) => {
+                status_code = StatusCode::TOO_MANY_REQUESTS;
+                error_message.code = RegistryErrorCode::TooManyRequests(m.clone());
+                error_message.message = m.clone();
+            }
+            RegistryErrorCode::Unavailable(m) => {
+                status_code = StatusCode::SERVICE_UNAVAILABLE;
+                error_message.code = RegistryErrorCode::Unavailable(m.clone());
+                error_message.message = m.clone();
+            }
             RegistryErrorCode::Unknown(m) => {
                 error_message.message = m.clone();
             }
@@ -194,16 +257,40 @@ pub async fn custom_recover(err: Rejection) -> Result<impl Reply, Infallible> {
     } else if let Some(e) = err.find::<warp::reject::InvalidHeader>() {
         status_code = StatusCode::BAD_REQUEST;
         error_message.message = format!("{}", e);
+    } else if let Some(e) = err.find::<TooManyRequests>() {
+        status_code = StatusCode::TOO_MANY_REQUESTS;
+        let message = format!(
+            "Too many requests, retry after {} seconds",
+            e.retry_after_secs
+        );
+        error_message.code = RegistryErrorCode::TooManyRequests(message.clone());
+        error_message.message = message;
+        retry_after_secs = Some(e.retry_after_secs);
+    } else if let Some(e) = err.find::<Overloaded>() {
+        status_code = StatusCode::SERVICE_UNAVAILABLE;
+        let message = format!(
+            "The node is overloaded, retry after {} seconds",
+            e.retry_after_secs
+        );
+        error_message.code = RegistryErrorCode::Unavailable(message.clone());
+        error_message.message = message;
+        retry_after_secs = Some(e.retry_after_secs);
     }
 
     debug!("ErrorMessage: {:?}", error_message);
-    Ok(warp::reply::with_status(
+    let mut response = warp::reply::with_status(
         warp::reply::json(&ErrorMessages {
             errors: vec![error_message],
         }),
         status_code,
     )
-    .into_response())
+    .into_response();
+    if let Some(retry_after_secs) = retry_after_secs {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    }
+    Ok(response)
 }
 
 #[cfg(test)]
@@ -327,6 +414,28 @@ mod tests {
         verify_recover_response(response, expected_body, StatusCode::NOT_FOUND).await;
     }
 
+    #[tokio::test]
+    async fn custom_recover_from_registry_error_not_found() {
+        let registry_error = RegistryError {
+            code: RegistryErrorCode::NotFound(String::from("not_found")),
+        };
+
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::NotFound("not_found".to_string()),
+                message: String::from("not_found"),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(registry_error.into())
+            .await
+            .expect("Reply should be created.")
+            .into_response();
+
+        verify_recover_response(response, expected_body, StatusCode::NOT_FOUND).await;
+    }
+
     #[tokio::test]
     async fn custom_recover_from_registry_error_bad_request() {
         let registry_error = RegistryError {
@@ -349,6 +458,130 @@ mod tests {
         verify_recover_response(response, expected_body, StatusCode::BAD_REQUEST).await;
     }
 
+    #[tokio::test]
+    async fn custom_recover_from_registry_error_denied() {
+        let registry_error = RegistryError::from_artifact_error(
+            PolicyViolation::MissingTransparencyLog {
+                artifact: String::from("library/alpine:3.17"),
+            }
+            .into(),
+            RegistryErrorCode::ManifestUnknown,
+        );
+        let message =
+            "Artifact library/alpine:3.17 is denied by policy: a transparency log is required";
+
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::Denied(message.to_string()),
+                message: String::from(message),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(registry_error.into())
+            .await
+            .expect("Reply should be created.")
+            .into_response();
+
+        verify_recover_response(response, expected_body, StatusCode::FORBIDDEN).await;
+    }
+
+    #[tokio::test]
+    async fn custom_recover_from_registry_error_revoked() {
+        let registry_error = RegistryError::from_artifact_error(
+            TransparencyLogError::ArtifactRevoked {
+                artifact_hash: String::from("sha256:abcd"),
+                reason: String::from("malicious"),
+            }
+            .into(),
+            RegistryErrorCode::BlobUnknown,
+        );
+        let message = "Artifact with hash sha256:abcd was revoked: malicious";
+
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::Revoked(message.to_string()),
+                message: String::from(message),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(registry_error.into())
+            .await
+            .expect("Reply should be created.")
+            .into_response();
+
+        verify_recover_response(response, expected_body, StatusCode::GONE).await;
+    }
+
+    #[tokio::test]
+    async fn custom_recover_from_registry_error_unauthorized() {
+        let message = "The node API requires a bearer token";
+        let registry_error = RegistryError {
+            code: RegistryErrorCode::Unauthorized(String::from(message)),
+        };
+
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::Unauthorized(message.to_string()),
+                message: String::from(message),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(registry_error.into())
+            .await
+            .expect("Reply should be created.")
+            .into_response();
+
+        verify_recover_response(response, expected_body, StatusCode::UNAUTHORIZED).await;
+    }
+
+    #[tokio::test]
+    async fn custom_recover_from_too_many_requests() {
+        let message = "Too many requests, retry after 30 seconds";
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::TooManyRequests(message.to_string()),
+                message: String::from(message),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(warp::reject::custom(TooManyRequests {
+            retry_after_secs: 30,
+        }))
+        .await
+        .expect("Reply should be created.")
+        .into_response();
+
+        assert_eq!(response.headers()[RETRY_AFTER], "30");
+        verify_recover_response(response, expected_body, StatusCode::TOO_MANY_REQUESTS).await;
+    }
+
+    #[tokio::test]
+    async fn custom_recover_from_overloaded() {
+        let message = "The node is overloaded, retry after 10 seconds";
+        let expected_body = serde_json::to_string(&ErrorMessages {
+            errors: vec![ErrorMessage {
+                code: RegistryErrorCode::Unavailable(message.to_string()),
+                message: String::from(message),
+            }],
+        })
+        .expect("Generating JSON body should not fail.");
+
+        let response = custom_recover(warp::reject::custom(Overloaded {
+            quality_metric: 0.95,
+            retry_after_secs: 10,
+        }))
+        .await
+        .expect("Reply should be created.")
+        .into_response();
+
+        assert_eq!(response.headers()[RETRY_AFTER], "10");
+        verify_recover_response(response, expected_body, StatusCode::SERVICE_UNAVAILABLE).await;
+    }
+
     #[tokio::test]
     async fn custom_recover_from_registry_error_for_unknown() {
         let registry_error = RegistryError {
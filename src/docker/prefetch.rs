@@ -0,0 +1,289 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::artifact_service::hashing::has_algorithm_prefix;
+use crate::artifact_service::model::PackageType;
+use crate::artifact_service::service::ArtifactService;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+// The registry hosts that name Docker Hub in an image reference.
+const DOCKER_HUB_HOSTS: [&str; 3] = ["docker.io", "index.docker.io", "registry-1.docker.io"];
+
+/// A Docker image reference, split in the repository name and the tag or
+/// digest, e.g. `library/alpine` and `3.16`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageReference {
+    pub name: String,
+    pub reference: String,
+}
+
+impl ImageReference {
+    /// Parses an image reference like `docker pull` accepts it, e.g. `alpine`,
+    /// `docker.io/library/alpine:3.16` or `alpine@sha256:...`. Only images of
+    /// Docker Hub are supported.
+    pub fn parse(image: &str) -> anyhow::Result<ImageReference> {
+        let mut remainder = image.trim();
+        if let Some((host, path)) = remainder.split_once('/') {
+            if host.contains('.') || host.contains(':') || host == "localhost" {
+                if !DOCKER_HUB_HOSTS.contains(&host) {
+                    bail!("Only images of Docker Hub are supported: {}", image);
+                }
+                remainder = path;
+            }
+        }
+        let (name, reference) = match remainder.split_once('@') {
+            Some((name_and_tag, digest)) => {
+                (name_and_tag.split(':').next().unwrap_or_default(), digest)
+            }
+            None => remainder.split_once(':').unwrap_or((remainder, "latest")),
+        };
+        if name.is_empty() || reference.is_empty() {
+            bail!("Invalid image reference: {}", image);
+        }
+        let name = if name.contains('/') {
+            name.to_owned()
+        } else {
+            format!("library/{}", name)
+        };
+        Ok(ImageReference {
+            name,
+            reference: reference.to_owned(),
+        })
+    }
+
+    fn with_reference(&self, reference: &str) -> ImageReference {
+        ImageReference {
+            name: self.name.clone(),
+            reference: reference.to_owned(),
+        }
+    }
+}
+
+/// Formats the reference as the package specific artifact id of the Docker
+/// artifact, e.g. `library/alpine:3.16` or `library/alpine@sha256:...`.
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if has_algorithm_prefix(&self.reference) {
+            write!(f, "{}@{}", self.name, self.reference)
+        } else {
+            write!(f, "{}:{}", self.name, self.reference)
+        }
+    }
+}
+
+/// The role of an artifact in an image.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImagePart {
+    Manifest,
+    Config,
+    Layer,
+}
+
+impl fmt::Display for ImagePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImagePart::Manifest => write!(f, "manifest"),
+            ImagePart::Config => write!(f, "config"),
+            ImagePart::Layer => write!(f, "layer"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrefetchedArtifact {
+    pub part: ImagePart,
+    /// The tag or digest of the artifact.
+    pub reference: String,
+    pub size: u64,
+    /// True if the artifact was already stored on the node.
+    pub cached: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PrefetchedImage {
+    pub image: String,
+    pub artifacts: Vec<PrefetchedArtifact>,
+}
+
+#[derive(Deserialize)]
+struct Descriptor {
+    digest: String,
+}
+
+// The parts of an image manifest or a manifest list that refer to other
+// artifacts.
+#[derive(Deserialize)]
+struct Manifest {
+    config: Option<Descriptor>,
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+// Returns the artifacts that a manifest refers to, in the order a client
+// would fetch them.
+fn referenced_artifacts(manifest: &[u8]) -> anyhow::Result<Vec<(ImagePart, String)>> {
+    let manifest: Manifest = serde_json::from_slice(manifest)?;
+    Ok(manifest
+        .manifests
+        .into_iter()
+        .map(|descriptor| (ImagePart::Manifest, descriptor.digest))
+        .chain(
+            manifest
+                .config
+                .map(|descriptor| (ImagePart::Config, descriptor.digest)),
+        )
+        .chain(
+            manifest
+                .layers
+                .into_iter()
+                .map(|descriptor| (ImagePart::Layer, descriptor.digest)),
+        )
+        .collect())
+}
+
+fn is_stored_locally(artifact_service: &mut ArtifactService, artifact_id: &str) -> bool {
+    match artifact_service
+        .transparency_log_service
+        .get_artifact(&PackageType::Docker, artifact_id)
+    {
+        Ok(transparency_log) => artifact_service
+            .artifact_storage
+            .artifact_size(&transparency_log.artifact_id)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Fetches the manifest of an image and every manifest, config and layer it
+/// refers to, the same way a pull would, so later pulls are served from the
+/// local storage. As with a pull, a build is requested for artifacts that are
+/// not available on the p2p network yet.
+pub async fn prefetch_image(
+    artifact_service: &mut ArtifactService,
+    image: &ImageReference,
+) -> anyhow::Result<PrefetchedImage> {
+    let mut prefetched_image = PrefetchedImage {
+        image: image.to_string(),
+        artifacts: vec![],
+    };
+    let mut seen = HashSet::new();
+    let mut pending = VecDeque::from([(ImagePart::Manifest, image.reference.clone())]);
+    while let Some((part, reference)) = pending.pop_front() {
+        if !seen.insert(reference.clone()) {
+            continue;
+        }
+        let artifact_id = image.with_reference(&reference).to_string();
+        let cached = is_stored_locally(artifact_service, &artifact_id);
+        let content = artifact_service
+            .get_artifact_or_build(PackageType::Docker, &artifact_id, &artifact_id)
+            .await
+            .with_context(|| format!("Unable to fetch {} {}", part, artifact_id))?;
+        if part == ImagePart::Manifest {
+            pending.extend(
+                referenced_artifacts(&content)
+                    .with_context(|| format!("Invalid manifest {}", artifact_id))?,
+            );
+        }
+        prefetched_image.artifacts.push(PrefetchedArtifact {
+            part,
+            reference,
+            size: content.len() as u64,
+            cached,
+        });
+    }
+
+    Ok(prefetched_image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:1e014f84205d569a5cc3be4e108ca614055f7e21d11928946113ab3f36054801";
+
+    #[test]
+    fn test_parse_official_image() {
+        assert_eq!(
+            ImageReference::parse("alpine").unwrap().to_string(),
+            "library/alpine:latest"
+        );
+        assert_eq!(
+            ImageReference::parse("docker.io/alpine:3.16")
+                .unwrap()
+                .to_string(),
+            "library/alpine:3.16"
+        );
+    }
+
+    #[test]
+    fn test_parse_image_with_digest() {
+        let image = ImageReference::parse(&format!("bitnami/redis:7.0@{}", DIGEST)).unwrap();
+
+        assert_eq!(image.name, "bitnami/redis");
+        assert_eq!(image.reference, DIGEST);
+        assert_eq!(image.to_string(), format!("bitnami/redis@{}", DIGEST));
+    }
+
+    #[test]
+    fn test_parse_image_of_other_registry() {
+        assert!(ImageReference::parse("ghcr.io/pyrsia/pyrsia:latest").is_err());
+        assert!(ImageReference::parse("localhost:5000/alpine").is_err());
+        assert!(ImageReference::parse("alpine:").is_err());
+    }
+
+    #[test]
+    fn test_referenced_artifacts() {
+        let manifest = format!(
+            r#"{{
+                "schemaVersion": 2,
+                "config": {{ "digest": "sha256:config" }},
+                "layers": [{{ "digest": "sha256:layer1" }}, {{ "digest": "{}" }}]
+            }}"#,
+            DIGEST
+        );
+
+        assert_eq!(
+            referenced_artifacts(manifest.as_bytes()).unwrap(),
+            vec![
+                (ImagePart::Config, "sha256:config".to_owned()),
+                (ImagePart::Layer, "sha256:layer1".to_owned()),
+                (ImagePart::Layer, DIGEST.to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_referenced_artifacts_of_manifest_list() {
+        let manifest_list = br#"{
+            "schemaVersion": 2,
+            "manifests": [{ "digest": "sha256:amd64" }, { "digest": "sha256:arm64" }]
+        }"#;
+
+        assert_eq!(
+            referenced_artifacts(manifest_list).unwrap(),
+            vec![
+                (ImagePart::Manifest, "sha256:amd64".to_owned()),
+                (ImagePart::Manifest, "sha256:arm64".to_owned()),
+            ]
+        );
+    }
+}
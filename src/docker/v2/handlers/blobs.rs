@@ -17,6 +17,9 @@
 use crate::artifact_service::model::PackageType;
 use crate::artifact_service::service::ArtifactService;
 use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::docker::v2::handlers::referrers::find_referrer_blob;
+use crate::logging::http::record_artifact_hash;
+use crate::util::rate_limit::download_body;
 use log::debug;
 use std::result::Result;
 use warp::{http::StatusCode, Rejection, Reply};
@@ -26,11 +29,28 @@ pub async fn handle_get_blobs(
     digest: String,
     mut artifact_service: ArtifactService,
 ) -> Result<impl Reply, Rejection> {
+    record_artifact_hash(&digest);
     debug!(
         "Getting blob with digest: {:?}. If not found, a build will be requested",
         &get_package_specific_artifact_id(&name, &digest)
     );
 
+    if let Some((media_type, blob_content)) = find_referrer_blob(&artifact_service, &digest) {
+        return Ok(warp::http::response::Builder::new()
+            .header("Content-Type", media_type)
+            .status(StatusCode::OK)
+            .body(blob_content)
+            .unwrap());
+    }
+
+    artifact_service
+        .load_shedding
+        .check()
+        .map_err(warp::reject::custom)?;
+    let download_permit = artifact_service
+        .rate_limits
+        .acquire_download()
+        .map_err(warp::reject::custom)?;
     let blob_content = artifact_service
         .get_artifact_or_build(
             PackageType::Docker,
@@ -38,16 +58,18 @@ pub async fn handle_get_blobs(
             &get_package_specific_artifact_id(&name, &digest),
         )
         .await
-        .map_err(|_| {
-            warp::reject::custom(RegistryError {
-                code: RegistryErrorCode::BlobUnknown,
-            })
+        .map_err(|e| {
+            warp::reject::custom(RegistryError::from_artifact_error(
+                e,
+                RegistryErrorCode::BlobUnknown,
+            ))
         })?;
 
     Ok(warp::http::response::Builder::new()
         .header("Content-Type", "application/octet-stream")
+        .header("Content-Length", blob_content.len())
         .status(StatusCode::OK)
-        .body(blob_content.to_vec())
+        .body(download_body(blob_content.to_vec(), download_permit))
         .unwrap())
 }
 
@@ -14,9 +14,12 @@
    limitations under the License.
 */
 
+use crate::artifact_service::hashing::has_algorithm_prefix;
 use crate::artifact_service::model::PackageType;
 use crate::artifact_service::service::ArtifactService;
 use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::docker::v2::handlers::referrers::{find_referrer_manifest, referrer_manifest_response};
+use crate::logging::http::record_artifact_hash;
 use log::debug;
 use warp::http::StatusCode;
 use warp::{Rejection, Reply};
@@ -27,10 +30,16 @@ pub async fn fetch_manifest(
     tag: String,
     mut artifact_service: ArtifactService,
 ) -> Result<impl Reply, Rejection> {
+    if has_algorithm_prefix(&tag) {
+        record_artifact_hash(&tag);
+    }
     debug!(
         "Fetching manifest for {}",
         &get_package_specific_artifact_id(&name, &tag)
     );
+    if let Some(manifest) = find_referrer_manifest(&artifact_service, &tag) {
+        return Ok(referrer_manifest_response(manifest));
+    }
 
     let manifest_content = artifact_service
         .get_artifact(
@@ -38,10 +47,11 @@ pub async fn fetch_manifest(
             &get_package_specific_artifact_id(&name, &tag),
         )
         .await
-        .map_err(|_| {
-            warp::reject::custom(RegistryError {
-                code: RegistryErrorCode::ManifestUnknown,
-            })
+        .map_err(|e| {
+            warp::reject::custom(RegistryError::from_artifact_error(
+                e,
+                RegistryErrorCode::ManifestUnknown,
+            ))
         })?;
 
     let len = manifest_content.len();
@@ -62,10 +72,16 @@ pub async fn fetch_manifest_or_build(
     tag: String,
     mut artifact_service: ArtifactService,
 ) -> Result<impl Reply, Rejection> {
+    if has_algorithm_prefix(&tag) {
+        record_artifact_hash(&tag);
+    }
     debug!(
         "Fetching manifest for {}. If not found, a build will be requested",
         &get_package_specific_artifact_id(&name, &tag)
     );
+    if let Some(manifest) = find_referrer_manifest(&artifact_service, &tag) {
+        return Ok(referrer_manifest_response(manifest));
+    }
     let manifest_content = artifact_service
         .get_artifact_or_build(
             PackageType::Docker,
@@ -73,10 +89,11 @@ pub async fn fetch_manifest_or_build(
             &get_package_specific_artifact_id(&name, &tag),
         )
         .await
-        .map_err(|_| {
-            warp::reject::custom(RegistryError {
-                code: RegistryErrorCode::ManifestUnknown,
-            })
+        .map_err(|e| {
+            warp::reject::custom(RegistryError::from_artifact_error(
+                e,
+                RegistryErrorCode::ManifestUnknown,
+            ))
         })?;
 
     let len = manifest_content.len();
@@ -93,7 +110,7 @@ pub async fn fetch_manifest_or_build(
 }
 
 fn get_package_specific_artifact_id(name: &str, tag: &str) -> String {
-    let combined_tag = if tag.starts_with("sha256:") {
+    let combined_tag = if has_algorithm_prefix(tag) {
         format!("{}@{}", name, tag)
     } else {
         format!("{}:{}", name, tag)
@@ -144,6 +161,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_package_specific_artifact_id_from_sha512_digest() {
+        let name = "library/alpine";
+        let tag = "sha512:0e9d5d4a3b2a1c81ef2d6a3f1f64cbb3a0e1f0f4a6f5b8c2d0b1a6e5f4c3d2b1a0e1f0f4a6f5b8c2d0b1a6e5f4c3d2b1a0e1f0f4a6f5b8c2d0b1a6e5f4c3d2b1";
+
+        assert_eq!(
+            get_package_specific_artifact_id(name, tag),
+            format!("{}@{}", name, tag)
+        );
+    }
+
     #[test]
     fn test_get_package_specific_artifact_id_from_tag() {
         let name = "library/alpine";
@@ -0,0 +1,98 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::artifact_service::sbom::OCI_MANIFEST_MEDIA_TYPE;
+use crate::artifact_service::service::ArtifactService;
+use crate::docker::error_util::RegistryError;
+use log::debug;
+use serde_json::json;
+use warp::http::StatusCode;
+use warp::{Rejection, Reply};
+
+const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+
+// Handles GET endpoint of the OCI referrers API, which lists the SBOMs that
+// are attached to the artifact with the specified digest.
+pub async fn handle_get_referrers(
+    name: String,
+    digest: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    debug!("Fetching referrers of {}@{}", name, digest);
+
+    let manifests: Vec<serde_json::Value> = artifact_service
+        .sboms(&digest)
+        .map_err(RegistryError::from)?
+        .iter()
+        .map(|sbom| {
+            json!({
+                "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+                "digest": sbom.referrer_manifest_digest(),
+                "size": sbom.referrer_manifest().len(),
+                "artifactType": sbom.format.media_type(),
+            })
+        })
+        .collect();
+    let index = serde_json::to_vec(&json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_INDEX_MEDIA_TYPE,
+        "manifests": manifests,
+    }))
+    .unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", OCI_INDEX_MEDIA_TYPE)
+        .header("Content-Length", index.len())
+        .status(StatusCode::OK)
+        .body(index)
+        .unwrap())
+}
+
+/// The referrer manifest of an SBOM with the specified manifest digest.
+pub fn find_referrer_manifest(artifact_service: &ArtifactService, digest: &str) -> Option<Vec<u8>> {
+    artifact_service
+        .artifact_storage
+        .find_sbom(digest)
+        .ok()
+        .flatten()
+        .filter(|sbom| sbom.referrer_manifest_digest() == digest)
+        .map(|sbom| sbom.referrer_manifest())
+}
+
+/// The media type and content of an SBOM, which is the only layer of its
+/// referrer manifest.
+pub fn find_referrer_blob(
+    artifact_service: &ArtifactService,
+    digest: &str,
+) -> Option<(&'static str, Vec<u8>)> {
+    artifact_service
+        .artifact_storage
+        .find_sbom(digest)
+        .ok()
+        .flatten()
+        .filter(|sbom| sbom.digest == digest)
+        .map(|sbom| (sbom.format.media_type(), sbom.document.content.into_bytes()))
+}
+
+/// The response that serves the referrer manifest of an SBOM.
+pub fn referrer_manifest_response(manifest: Vec<u8>) -> warp::http::Response<Vec<u8>> {
+    warp::http::response::Builder::new()
+        .header("Content-Type", OCI_MANIFEST_MEDIA_TYPE)
+        .header("Content-Length", manifest.len())
+        .status(StatusCode::OK)
+        .body(manifest)
+        .unwrap()
+}
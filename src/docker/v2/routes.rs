@@ -19,6 +19,7 @@ use crate::artifact_service::service::ArtifactService;
 
 use super::handlers::blobs::*;
 use super::handlers::manifests::*;
+use super::handlers::referrers::*;
 use warp::Filter;
 
 pub fn make_docker_routes(
@@ -50,6 +51,12 @@ pub fn make_docker_routes(
         .and(artifact_service_filter.clone())
         .and_then(fetch_manifest);
 
+    let v2_referrers = warp::path!("v2" / "library" / String / "referrers" / String)
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_referrers);
+
     let v2_blobs = warp::path!("v2" / "library" / String / "blobs" / String)
         .and(warp::get())
         .and(warp::path::end())
@@ -60,6 +67,7 @@ pub fn make_docker_routes(
         v2_base
             .or(v2_manifests_get)
             .or(v2_manifests_head)
+            .or(v2_referrers)
             .or(v2_blobs),
     )
 }
@@ -134,4 +142,24 @@ mod tests {
 
         test_util::tests::teardown(tmp_dir);
     }
+
+    #[tokio::test]
+    async fn docker_routes_referrers() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (artifact_service, ..) = test_util::tests::create_artifact_service(&tmp_dir);
+
+        let filter = make_docker_routes(artifact_service);
+        let response = warp::test::request()
+            .path("/v2/library/alpine/referrers/sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a")
+            .reply(&filter)
+            .await;
+
+        let expected_body = r#"{"manifests":[],"mediaType":"application/vnd.oci.image.index.v1+json","schemaVersion":2}"#;
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(expected_body, str::from_utf8(response.body()).unwrap());
+
+        test_util::tests::teardown(tmp_dir);
+    }
 }
@@ -17,6 +17,7 @@
 use crate::artifact_service::model::PackageType;
 use crate::artifact_service::service::ArtifactService;
 use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::util::rate_limit::download_body;
 use anyhow::{anyhow, bail};
 use log::debug;
 use warp::{http::StatusCode, Rejection, Reply};
@@ -50,6 +51,14 @@ pub async fn handle_get_maven_artifact(
         package_specific_id, package_specific_artifact_id
     );
 
+    artifact_service
+        .load_shedding
+        .check()
+        .map_err(warp::reject::custom)?;
+    let download_permit = artifact_service
+        .rate_limits
+        .acquire_download()
+        .map_err(warp::reject::custom)?;
     let artifact_content = artifact_service
         .get_artifact_or_build(
             PackageType::Maven2,
@@ -66,8 +75,9 @@ pub async fn handle_get_maven_artifact(
 
     Ok(warp::http::response::Builder::new()
         .header("Content-Type", "application/octet-stream")
+        .header("Content-Length", artifact_content.len())
         .status(StatusCode::OK)
-        .body(artifact_content)
+        .body(download_body(artifact_content, download_permit))
         .unwrap())
 }
 
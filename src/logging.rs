@@ -14,4 +14,6 @@
    limitations under the License.
 */
 
+pub mod buffer;
 pub mod http;
+pub mod rotating_file;
@@ -0,0 +1,233 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::layer::{Context, Layer};
+
+// The number of log entries that the node keeps in memory.
+const LOG_BUFFER_CAPACITY: usize = 1000;
+
+lazy_static! {
+    static ref LOG_BUFFER: LogBuffer = LogBuffer::new(LOG_BUFFER_CAPACITY);
+}
+
+/// A single log record as it is exposed through the node API.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Returns true if this entry is at least as severe as `level`.
+    pub fn is_enabled(&self, level: LevelFilter) -> bool {
+        Level::from_str(&self.level)
+            .map(|entry_level| entry_level <= level)
+            .unwrap_or(false)
+    }
+}
+
+impl From<&Event<'_>> for LogEntry {
+    fn from(event: &Event) -> Self {
+        // Events of the `log` macros carry their actual target in fields.
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata
+            .as_ref()
+            .unwrap_or_else(|| event.metadata());
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        LogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or_default(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_owned(),
+            message: visitor.message,
+        }
+    }
+}
+
+// Collects the message of an event, followed by its other fields as
+// key=value pairs.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl MessageVisitor {
+    fn record_field(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message.insert_str(0, &value),
+            name if name.starts_with("log.") => {}
+            name => self.message.push_str(&format!(" {}={}", name, value)),
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_field(field, value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_field(field, format!("{:?}", value));
+    }
+}
+
+/// Keeps the most recent log entries of the node and broadcasts new entries
+/// to everyone following the log.
+pub struct LogBuffer {
+    capacity: usize,
+    entries: Mutex<VecDeque<LogEntry>>,
+    sender: broadcast::Sender<LogEntry>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        LogBuffer {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            sender,
+        }
+    }
+
+    pub fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry.clone());
+        // Sending only fails when nobody follows the log.
+        let _ = self.sender.send(entry);
+    }
+
+    /// Returns the last `lines` entries that are at least as severe as `level`,
+    /// oldest first.
+    pub fn tail(&self, level: LevelFilter, lines: usize) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        let mut tail: Vec<LogEntry> = entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.is_enabled(level))
+            .take(lines)
+            .cloned()
+            .collect();
+        tail.reverse();
+        tail
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender.subscribe()
+    }
+}
+
+/// The log buffer of this process.
+pub fn log_buffer() -> &'static LogBuffer {
+    &LOG_BUFFER
+}
+
+/// A tracing layer that keeps every event that passes the filters of the
+/// subscriber in the log buffer, so the log can be read through the node API.
+pub struct LogBufferLayer;
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        log_buffer().push(LogEntry::from(event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: 0,
+            level: level.to_string(),
+            target: "pyrsia".to_owned(),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_buffer_keeps_most_recent_entries() {
+        let buffer = LogBuffer::new(2);
+        buffer.push(entry(Level::Info, "first"));
+        buffer.push(entry(Level::Info, "second"));
+        buffer.push(entry(Level::Info, "third"));
+
+        assert_eq!(
+            buffer.tail(LevelFilter::Trace, 10),
+            vec![entry(Level::Info, "second"), entry(Level::Info, "third")]
+        );
+        assert_eq!(
+            buffer.tail(LevelFilter::Trace, 1),
+            vec![entry(Level::Info, "third")]
+        );
+    }
+
+    #[test]
+    fn test_buffer_filters_on_level() {
+        let buffer = LogBuffer::new(10);
+        buffer.push(entry(Level::Debug, "debug"));
+        buffer.push(entry(Level::Warn, "warn"));
+        buffer.push(entry(Level::Error, "error"));
+
+        assert_eq!(
+            buffer.tail(LevelFilter::Warn, 10),
+            vec![entry(Level::Warn, "warn"), entry(Level::Error, "error")]
+        );
+        assert!(buffer.tail(LevelFilter::Off, 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_buffer_broadcasts_new_entries() {
+        let buffer = LogBuffer::new(10);
+        let mut receiver = buffer.subscribe();
+        buffer.push(entry(Level::Info, "hello"));
+
+        assert_eq!(receiver.recv().await.unwrap(), entry(Level::Info, "hello"));
+    }
+
+    #[test]
+    fn test_layer_keeps_events_in_log_buffer() {
+        let subscriber = tracing_subscriber::registry().with(LogBufferLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(artifact_hash = "sha256:1234", "Kept by the layer");
+        });
+
+        let entries = log_buffer().tail(LevelFilter::Warn, LOG_BUFFER_CAPACITY);
+        assert!(entries.iter().any(|entry| entry.level == "WARN"
+            && entry.message == "Kept by the layer artifact_hash=sha256:1234"));
+    }
+}
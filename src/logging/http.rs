@@ -16,9 +16,33 @@
 
 use log::trace;
 use std::convert::Infallible;
+use tracing::field::Empty;
+use tracing::Span;
+use uuid::Uuid;
 use warp::http::HeaderMap;
+use warp::trace::{Info, Trace};
 use warp::Filter;
 
+/// Wraps the handling of every request in a span with a unique request id,
+/// so that everything that is logged for a request can be correlated. The
+/// handlers of artifacts record the hash of the artifact in the span.
+pub fn trace_requests() -> Trace<impl Fn(Info<'_>) -> Span + Clone> {
+    warp::trace(|info: Info| {
+        tracing::info_span!(
+            "request",
+            request_id = %Uuid::new_v4(),
+            method = %info.method(),
+            path = info.path(),
+            artifact_hash = Empty,
+        )
+    })
+}
+
+/// Records the hash of the requested artifact in the span of the request.
+pub fn record_artifact_hash(artifact_hash: &str) {
+    Span::current().record("artifact_hash", artifact_hash);
+}
+
 pub fn log_headers() -> impl Filter<Extract = (), Error = Infallible> + Copy {
     warp::header::headers_cloned()
         .map(|headers: HeaderMap| {
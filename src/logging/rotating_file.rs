@@ -0,0 +1,190 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How often a log file is rotated, besides when it reaches its maximum size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl Rotation {
+    // The number of the rotation period that `time` falls in.
+    fn period_of(&self, time: SystemTime) -> u64 {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        match self {
+            Rotation::Never => 0,
+            Rotation::Hourly => seconds / (60 * 60),
+            Rotation::Daily => seconds / (24 * 60 * 60),
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(rotation: &str) -> Result<Self, Self::Err> {
+        match rotation {
+            "never" => Ok(Rotation::Never),
+            "hourly" => Ok(Rotation::Hourly),
+            "daily" => Ok(Rotation::Daily),
+            _ => Err(format!("Unknown log rotation: {}", rotation)),
+        }
+    }
+}
+
+/// A log file that is rotated when it would grow beyond `max_size` bytes or
+/// when a new rotation period starts. The rotated files are suffixed with .1
+/// for the most recent one up to .`max_files`, older files are removed.
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+    period: u64,
+}
+
+impl RotatingFile {
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        rotation: Rotation,
+        max_size: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+        let period = rotation.period_of(metadata.modified().unwrap_or_else(|_| SystemTime::now()));
+        Ok(RotatingFile {
+            path,
+            rotation,
+            max_size,
+            max_files: max_files.max(1),
+            file,
+            size: metadata.len(),
+            period,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let oldest_path = self.rotated_path(self.max_files);
+        if oldest_path.exists() {
+            fs::remove_file(oldest_path)?;
+        }
+        for index in (1..self.max_files).rev() {
+            let rotated_path = self.rotated_path(index);
+            if rotated_path.exists() {
+                fs::rename(rotated_path, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let period = self.rotation.period_of(SystemTime::now());
+        let too_large = self.size + buf.len() as u64 > self.max_size;
+        if self.size > 0 && (too_large || period != self.period) {
+            self.rotate()?;
+        }
+        self.period = period;
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_on_size() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("logs").join("pyrsia.log");
+
+        let mut rotating_file = RotatingFile::open(&path, Rotation::Never, 10, 2).unwrap();
+        rotating_file.write_all(b"first\n").unwrap();
+        rotating_file.write_all(b"second\n").unwrap();
+        rotating_file.write_all(b"third\n").unwrap();
+        rotating_file.write_all(b"fourth\n").unwrap();
+        rotating_file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("logs/pyrsia.log.1")).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(tmp_dir.path().join("logs/pyrsia.log.2")).unwrap(),
+            "second\n"
+        );
+        assert!(!tmp_dir.path().join("logs/pyrsia.log.3").exists());
+    }
+
+    #[test]
+    fn test_append_to_existing_file() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("pyrsia.log");
+        fs::write(&path, "existing\n").unwrap();
+
+        let mut rotating_file = RotatingFile::open(&path, Rotation::Daily, 1024, 5).unwrap();
+        rotating_file.write_all(b"appended\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "existing\nappended\n");
+    }
+
+    #[test]
+    fn test_rotation_from_str() {
+        assert_eq!(Rotation::from_str("hourly").unwrap(), Rotation::Hourly);
+        assert!(Rotation::from_str("weekly").is_err());
+    }
+}
@@ -14,12 +14,25 @@
    limitations under the License.
 */
 
+pub mod artifact_availability_protocol;
 pub mod artifact_protocol;
+pub mod artifact_replication_protocol;
 pub mod behaviour;
+pub mod bench;
+pub mod bench_protocol;
 pub mod blockchain_protocol;
 pub mod build_protocol;
 pub mod build_status_protocol;
 pub mod client;
 pub mod event_loop;
+pub mod fair_share;
 pub mod idle_metric_protocol;
+pub mod latency;
+pub mod name_record;
 pub mod p2p;
+pub mod peer_identity;
+pub mod public_keys;
+pub mod reconnect;
+pub mod signed_document;
+pub mod signer;
+pub mod transfer_metrics;
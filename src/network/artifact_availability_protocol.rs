@@ -0,0 +1,129 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed, ProtocolName};
+use libp2p::request_response::RequestResponseCodec;
+use log::debug;
+use std::io;
+
+/// The `ArtifactAvailabilityExchangeCodec` defines the request and response
+/// types for the [`RequestResponse`](crate::RequestResponse) protocol for
+/// asking a provider if it still has the artifact with a specific id, without
+/// transferring the artifact itself. The response contains the size of the
+/// artifact in bytes, or nothing when the peer does not have the artifact.
+#[derive(Debug, Clone)]
+pub struct ArtifactAvailabilityExchangeProtocol();
+#[derive(Clone)]
+pub struct ArtifactAvailabilityExchangeCodec();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactAvailabilityRequest(pub String);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactAvailabilityResponse(pub Option<u64>);
+
+impl ProtocolName for ArtifactAvailabilityExchangeProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        "/artifact-availability/1".as_bytes()
+    }
+}
+
+#[async_trait]
+impl RequestResponseCodec for ArtifactAvailabilityExchangeCodec {
+    type Protocol = ArtifactAvailabilityExchangeProtocol;
+    type Request = ArtifactAvailabilityRequest;
+    type Response = ArtifactAvailabilityResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &ArtifactAvailabilityExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let artifact_id_vec = read_length_prefixed(io, 1_000_000).await?;
+        if artifact_id_vec.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let artifact_id = String::from_utf8(artifact_id_vec)
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        debug!("Reading ArtifactAvailabilityRequest: {}", artifact_id);
+
+        Ok(ArtifactAvailabilityRequest(artifact_id))
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &ArtifactAvailabilityExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        // an empty response means the artifact is not available, otherwise
+        // the response contains the size of the artifact as little endian
+        let size_vec = read_length_prefixed(io, 8).await?;
+        let size = match size_vec.len() {
+            0 => None,
+            8 => {
+                let mut size_bytes = [0u8; 8];
+                size_bytes.copy_from_slice(&size_vec);
+                Some(u64::from_le_bytes(size_bytes))
+            }
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        debug!("Reading ArtifactAvailabilityResponse: {:?}", size);
+
+        Ok(ArtifactAvailabilityResponse(size))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &ArtifactAvailabilityExchangeProtocol,
+        io: &mut T,
+        ArtifactAvailabilityRequest(artifact_id): ArtifactAvailabilityRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        debug!("Write ArtifactAvailabilityRequest: {}", artifact_id);
+
+        write_length_prefixed(io, artifact_id).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &ArtifactAvailabilityExchangeProtocol,
+        io: &mut T,
+        ArtifactAvailabilityResponse(size): ArtifactAvailabilityResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        debug!("Write ArtifactAvailabilityResponse: {:?}", size);
+
+        let size_vec = size.map(|s| s.to_le_bytes().to_vec()).unwrap_or_default();
+        write_length_prefixed(io, size_vec).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+}
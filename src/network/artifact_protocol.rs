@@ -20,6 +20,7 @@ use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed, Protoco
 use libp2p::request_response::RequestResponseCodec;
 use log::debug;
 use std::io;
+use thiserror::Error;
 
 #[derive(Debug, Clone)]
 pub struct ArtifactExchangeProtocol();
@@ -34,6 +35,24 @@ pub struct ArtifactRequest(pub String);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ArtifactResponse(pub Vec<u8>);
 
+/// An overloaded peer responds with an empty artifact to ask for the
+/// artifact to be requested from another peer. Peers that don't know
+/// about this response treat it as a failed request.
+impl ArtifactResponse {
+    pub fn busy() -> Self {
+        ArtifactResponse(Vec::new())
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The peer refused to send an artifact because it is overloaded.
+#[derive(Debug, Error)]
+#[error("Peer is busy, retry elsewhere")]
+pub struct PeerBusy;
+
 impl ProtocolName for ArtifactExchangeProtocol {
     fn protocol_name(&self) -> &[u8] {
         "/artifact-exchange/1".as_bytes()
@@ -75,10 +94,6 @@ impl RequestResponseCodec for ArtifactExchangeCodec {
     {
         let vec = read_length_prefixed(io, 100_000_000).await?;
 
-        if vec.is_empty() {
-            return Err(io::ErrorKind::UnexpectedEof.into());
-        }
-
         Ok(ArtifactResponse(vec))
     }
 
@@ -0,0 +1,135 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::artifact_service::model::PackageType;
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed, ProtocolName};
+use libp2p::request_response::RequestResponseCodec;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// The `ArtifactReplicationExchangeCodec` defines the request and response
+/// types for the [`RequestResponse`](crate::RequestResponse) protocol for
+/// pushing an artifact to another peer, asking it to store a replica. The
+/// response tells if the peer accepted and stored the artifact.
+#[derive(Debug, Clone)]
+pub struct ArtifactReplicationExchangeProtocol();
+#[derive(Clone)]
+pub struct ArtifactReplicationExchangeCodec();
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ArtifactReplicationRequest {
+    pub package_type: PackageType,
+    pub package_specific_artifact_id: String,
+    pub artifact: Vec<u8>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactReplicationResponse(pub bool);
+
+impl ProtocolName for ArtifactReplicationExchangeProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        "/artifact-replication/1".as_bytes()
+    }
+}
+
+#[async_trait]
+impl RequestResponseCodec for ArtifactReplicationExchangeCodec {
+    type Protocol = ArtifactReplicationExchangeProtocol;
+    type Request = ArtifactReplicationRequest;
+    type Response = ArtifactReplicationResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &ArtifactReplicationExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let request_vec = read_length_prefixed(io, 100_000_000).await?;
+        if request_vec.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let request: ArtifactReplicationRequest = bincode::deserialize(&request_vec)
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        debug!(
+            "Reading ArtifactReplicationRequest: {}",
+            request.package_specific_artifact_id
+        );
+
+        Ok(request)
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &ArtifactReplicationExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let accepted_vec = read_length_prefixed(io, 1).await?;
+        let accepted = match accepted_vec.as_slice() {
+            [0] => false,
+            [1] => true,
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        debug!("Reading ArtifactReplicationResponse: {}", accepted);
+
+        Ok(ArtifactReplicationResponse(accepted))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &ArtifactReplicationExchangeProtocol,
+        io: &mut T,
+        request: ArtifactReplicationRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        debug!(
+            "Write ArtifactReplicationRequest: {}",
+            request.package_specific_artifact_id
+        );
+
+        let request_vec = bincode::serialize(&request)
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        write_length_prefixed(io, request_vec).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &ArtifactReplicationExchangeProtocol,
+        io: &mut T,
+        ArtifactReplicationResponse(accepted): ArtifactReplicationResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        debug!("Write ArtifactReplicationResponse: {}", accepted);
+
+        write_length_prefixed(io, [accepted as u8]).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+}
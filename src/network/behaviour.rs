@@ -14,7 +14,14 @@
    limitations under the License.
 */
 
+use crate::network::artifact_availability_protocol::{
+    ArtifactAvailabilityExchangeCodec, ArtifactAvailabilityRequest, ArtifactAvailabilityResponse,
+};
 use crate::network::artifact_protocol::{ArtifactExchangeCodec, ArtifactRequest, ArtifactResponse};
+use crate::network::artifact_replication_protocol::{
+    ArtifactReplicationExchangeCodec, ArtifactReplicationRequest, ArtifactReplicationResponse,
+};
+use crate::network::bench_protocol::{BenchExchangeCodec, BenchRequest, BenchResponse};
 use crate::network::blockchain_protocol::{
     BlockchainExchangeCodec, BlockchainRequest, BlockchainResponse,
 };
@@ -31,6 +38,7 @@ use libp2p::gossipsub;
 use libp2p::identify;
 use libp2p::kad::record::store::MemoryStore;
 use libp2p::kad::{Kademlia, KademliaEvent};
+use libp2p::ping;
 use libp2p::request_response::{RequestResponse, RequestResponseEvent};
 use libp2p::swarm::NetworkBehaviour;
 
@@ -40,8 +48,9 @@ use libp2p::swarm::NetworkBehaviour;
 ///
 /// * [`Identify`]
 /// * [`Kademlia`]
-/// * [`RequestResponse`] for exchanging artifacts, idle metrics and
-/// blockchain updates
+/// * [`Ping`] for measuring the latency to connected peers
+/// * [`RequestResponse`] for exchanging artifacts, artifact availability,
+/// artifact replicas, idle metrics, blockchain updates and benchmarks
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "PyrsiaNetworkEvent")]
 pub struct PyrsiaNetworkBehaviour {
@@ -49,11 +58,15 @@ pub struct PyrsiaNetworkBehaviour {
     pub gossipsub: gossipsub::Gossipsub,
     pub identify: identify::Behaviour,
     pub kademlia: Kademlia<MemoryStore>,
+    pub ping: ping::Behaviour,
     pub request_response: RequestResponse<ArtifactExchangeCodec>,
+    pub artifact_availability_request_response: RequestResponse<ArtifactAvailabilityExchangeCodec>,
+    pub artifact_replication_request_response: RequestResponse<ArtifactReplicationExchangeCodec>,
     pub build_request_response: RequestResponse<BuildExchangeCodec>,
     pub idle_metric_request_response: RequestResponse<IdleMetricExchangeCodec>,
     pub blockchain_request_response: RequestResponse<BlockchainExchangeCodec>,
     pub build_status_request_response: RequestResponse<BuildStatusExchangeCodec>,
+    pub bench_request_response: RequestResponse<BenchExchangeCodec>,
 }
 
 /// Each event in the `PyrsiaNetworkBehaviour` is wrapped in a
@@ -64,11 +77,19 @@ pub enum PyrsiaNetworkEvent {
     Gossipsub(gossipsub::GossipsubEvent),
     Identify(Box<identify::Event>),
     Kademlia(Box<KademliaEvent>),
+    Ping(ping::Event),
     RequestResponse(RequestResponseEvent<ArtifactRequest, ArtifactResponse>),
+    ArtifactAvailabilityRequestResponse(
+        RequestResponseEvent<ArtifactAvailabilityRequest, ArtifactAvailabilityResponse>,
+    ),
+    ArtifactReplicationRequestResponse(
+        RequestResponseEvent<ArtifactReplicationRequest, ArtifactReplicationResponse>,
+    ),
     BuildRequestResponse(RequestResponseEvent<BuildRequest, BuildResponse>),
     IdleMetricRequestResponse(RequestResponseEvent<IdleMetricRequest, IdleMetricResponse>),
     BlockchainRequestResponse(RequestResponseEvent<BlockchainRequest, BlockchainResponse>),
     BuildStatusRequestResponse(RequestResponseEvent<BuildStatusRequest, BuildStatusResponse>),
+    BenchRequestResponse(RequestResponseEvent<BenchRequest, BenchResponse>),
 }
 
 impl From<autonat::Event> for PyrsiaNetworkEvent {
@@ -95,12 +116,38 @@ impl From<KademliaEvent> for PyrsiaNetworkEvent {
     }
 }
 
+impl From<ping::Event> for PyrsiaNetworkEvent {
+    fn from(event: ping::Event) -> Self {
+        PyrsiaNetworkEvent::Ping(event)
+    }
+}
+
 impl From<RequestResponseEvent<ArtifactRequest, ArtifactResponse>> for PyrsiaNetworkEvent {
     fn from(event: RequestResponseEvent<ArtifactRequest, ArtifactResponse>) -> Self {
         PyrsiaNetworkEvent::RequestResponse(event)
     }
 }
 
+impl From<RequestResponseEvent<ArtifactAvailabilityRequest, ArtifactAvailabilityResponse>>
+    for PyrsiaNetworkEvent
+{
+    fn from(
+        event: RequestResponseEvent<ArtifactAvailabilityRequest, ArtifactAvailabilityResponse>,
+    ) -> Self {
+        PyrsiaNetworkEvent::ArtifactAvailabilityRequestResponse(event)
+    }
+}
+
+impl From<RequestResponseEvent<ArtifactReplicationRequest, ArtifactReplicationResponse>>
+    for PyrsiaNetworkEvent
+{
+    fn from(
+        event: RequestResponseEvent<ArtifactReplicationRequest, ArtifactReplicationResponse>,
+    ) -> Self {
+        PyrsiaNetworkEvent::ArtifactReplicationRequestResponse(event)
+    }
+}
+
 impl From<RequestResponseEvent<BuildRequest, BuildResponse>> for PyrsiaNetworkEvent {
     fn from(event: RequestResponseEvent<BuildRequest, BuildResponse>) -> Self {
         PyrsiaNetworkEvent::BuildRequestResponse(event)
@@ -124,3 +171,9 @@ impl From<RequestResponseEvent<BuildStatusRequest, BuildStatusResponse>> for Pyr
         PyrsiaNetworkEvent::BuildStatusRequestResponse(event)
     }
 }
+
+impl From<RequestResponseEvent<BenchRequest, BenchResponse>> for PyrsiaNetworkEvent {
+    fn from(event: RequestResponseEvent<BenchRequest, BenchResponse>) -> Self {
+        PyrsiaNetworkEvent::BenchRequestResponse(event)
+    }
+}
@@ -0,0 +1,205 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::network::bench_protocol::MAX_BENCH_PAYLOAD_SIZE;
+use crate::network::client::Client;
+use anyhow::{anyhow, bail};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// The number of bytes that are uploaded and downloaded in every round
+/// when no size is given.
+pub const DEFAULT_BENCH_SIZE: u64 = 4 * 1024 * 1024;
+pub const DEFAULT_BENCH_ROUNDS: u32 = 3;
+pub const MAX_BENCH_ROUNDS: u32 = 100;
+
+/// The outcome of a network benchmark against a single peer.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BenchReport {
+    pub peer_id: String,
+    /// The number of bytes that were uploaded and downloaded in every round.
+    pub size: u64,
+    pub rounds: u32,
+    /// The average round-trip time of an empty request.
+    pub latency_ms: f64,
+    pub upload_bytes_per_sec: u64,
+    pub download_bytes_per_sec: u64,
+}
+
+/// The connected peer with the lowest latency, as measured by the ping
+/// protocol.
+pub async fn fastest_peer(p2p_client: &mut Client) -> anyhow::Result<PeerId> {
+    p2p_client
+        .list_peer_latencies()
+        .await?
+        .into_iter()
+        .min_by_key(|(_, latency)| *latency)
+        .map(|(peer_id, _)| peer_id)
+        .ok_or_else(|| anyhow!("No connected peer to benchmark against"))
+}
+
+/// Check that the size and the number of rounds of a benchmark are within
+/// the limits.
+pub fn check_bench_parameters(size: u64, rounds: u32) -> anyhow::Result<()> {
+    if size == 0 || size > MAX_BENCH_PAYLOAD_SIZE {
+        bail!(
+            "The size must be between 1 and {} bytes",
+            MAX_BENCH_PAYLOAD_SIZE
+        );
+    }
+    if rounds == 0 || rounds > MAX_BENCH_ROUNDS {
+        bail!(
+            "The number of rounds must be between 1 and {}",
+            MAX_BENCH_ROUNDS
+        );
+    }
+    Ok(())
+}
+
+/// Measure the latency and the upload and download throughput to a peer
+/// by exchanging `size` bytes of synthetic data in both directions, in
+/// each of the `rounds`.
+pub async fn run_bench(
+    p2p_client: &mut Client,
+    peer_id: &PeerId,
+    size: u64,
+    rounds: u32,
+) -> anyhow::Result<BenchReport> {
+    check_bench_parameters(size, rounds)?;
+
+    let mut latency = Duration::ZERO;
+    let mut upload = Duration::ZERO;
+    let mut download = Duration::ZERO;
+    for _ in 0..rounds {
+        let started = Instant::now();
+        p2p_client.request_bench(peer_id, 0, 0).await?;
+        latency += started.elapsed();
+
+        let started = Instant::now();
+        p2p_client.request_bench(peer_id, size, 0).await?;
+        upload += started.elapsed();
+
+        let started = Instant::now();
+        let received = p2p_client.request_bench(peer_id, 0, size).await?;
+        download += started.elapsed();
+        if received != size {
+            bail!(
+                "Peer {} returned {} bytes instead of {}",
+                peer_id,
+                received,
+                size
+            );
+        }
+    }
+
+    Ok(BenchReport {
+        peer_id: peer_id.to_string(),
+        size,
+        rounds,
+        latency_ms: latency.as_secs_f64() * 1000.0 / rounds as f64,
+        upload_bytes_per_sec: bytes_per_sec(size * rounds as u64, upload),
+        download_bytes_per_sec: bytes_per_sec(size * rounds as u64, download),
+    })
+}
+
+fn bytes_per_sec(bytes: u64, duration: Duration) -> u64 {
+    if duration.is_zero() {
+        0
+    } else {
+        (bytes as f64 / duration.as_secs_f64()) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::client::command::Command;
+    use crate::util::test_util;
+    use libp2p::identity::Keypair;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_bytes_per_sec() {
+        assert_eq!(bytes_per_sec(1000, Duration::from_millis(500)), 2000);
+        assert_eq!(bytes_per_sec(1000, Duration::ZERO), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fastest_peer() {
+        let (mut p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let slow_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let fast_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        tokio::spawn(async move {
+            match p2p_command_receiver.recv().await {
+                Some(Command::ListPeerLatencies { sender }) => {
+                    let _ = sender.send(HashMap::from([
+                        (slow_peer_id, Duration::from_millis(80)),
+                        (fast_peer_id, Duration::from_millis(5)),
+                    ]));
+                }
+                _ => panic!("Command must match Command::ListPeerLatencies"),
+            }
+        });
+
+        assert_eq!(fastest_peer(&mut p2p_client).await.unwrap(), fast_peer_id);
+    }
+
+    #[tokio::test]
+    async fn test_run_bench() {
+        let (mut p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::RequestBench {
+                        peer,
+                        download_size,
+                        sender,
+                        ..
+                    }) => {
+                        assert_eq!(peer, peer_id);
+                        let _ = sender.send(Ok(download_size));
+                    }
+                    None => break,
+                    _ => panic!("Command must match Command::RequestBench"),
+                }
+            }
+        });
+
+        let report = run_bench(&mut p2p_client, &peer_id, 1024, 2).await.unwrap();
+
+        assert_eq!(report.peer_id, peer_id.to_string());
+        assert_eq!(report.size, 1024);
+        assert_eq!(report.rounds, 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_bench_with_invalid_size() {
+        let (mut p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        assert!(run_bench(&mut p2p_client, &peer_id, 0, 1).await.is_err());
+        assert!(
+            run_bench(&mut p2p_client, &peer_id, MAX_BENCH_PAYLOAD_SIZE + 1, 1)
+                .await
+                .is_err()
+        );
+        assert!(run_bench(&mut p2p_client, &peer_id, 1024, 0).await.is_err());
+    }
+}
@@ -0,0 +1,135 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed, ProtocolName};
+use libp2p::request_response::RequestResponseCodec;
+use log::debug;
+use std::io;
+
+/// The largest payload that is uploaded or downloaded with a single
+/// benchmark request.
+pub const MAX_BENCH_PAYLOAD_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct BenchExchangeProtocol();
+/// The `BenchExchangeCodec` defines the request and response types
+/// for the [`RequestResponse`](crate::RequestResponse) protocol for
+/// measuring the throughput and latency to a peer. A request uploads
+/// a synthetic payload and asks the peer to return `download_size`
+/// bytes of synthetic data.
+#[derive(Clone)]
+pub struct BenchExchangeCodec();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchRequest {
+    pub download_size: u64,
+    pub payload: Vec<u8>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchResponse(pub Vec<u8>);
+
+impl ProtocolName for BenchExchangeProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        "/bench/1".as_bytes()
+    }
+}
+
+#[async_trait]
+impl RequestResponseCodec for BenchExchangeCodec {
+    type Protocol = BenchExchangeProtocol;
+    type Request = BenchRequest;
+    type Response = BenchResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &BenchExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let download_size_bytes: [u8; 8] = read_length_prefixed(io, 8)
+            .await?
+            .try_into()
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        let payload = read_length_prefixed(io, MAX_BENCH_PAYLOAD_SIZE as usize).await?;
+        let download_size = u64::from_be_bytes(download_size_bytes);
+        debug!(
+            "Read BenchRequest: upload {} bytes, download {} bytes",
+            payload.len(),
+            download_size
+        );
+
+        Ok(BenchRequest {
+            download_size,
+            payload,
+        })
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &BenchExchangeProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let data = read_length_prefixed(io, MAX_BENCH_PAYLOAD_SIZE as usize).await?;
+
+        Ok(BenchResponse(data))
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &BenchExchangeProtocol,
+        io: &mut T,
+        BenchRequest {
+            download_size,
+            payload,
+        }: BenchRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        debug!(
+            "Write BenchRequest: upload {} bytes, download {} bytes",
+            payload.len(),
+            download_size
+        );
+
+        write_length_prefixed(io, download_size.to_be_bytes()).await?;
+        write_length_prefixed(io, payload).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &BenchExchangeProtocol,
+        io: &mut T,
+        BenchResponse(data): BenchResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_length_prefixed(io, data).await?;
+        io.close().await?;
+
+        Ok(())
+    }
+}
@@ -17,19 +17,25 @@
 pub mod command;
 
 use crate::artifact_service::model::PackageType;
+use crate::network::artifact_availability_protocol::ArtifactAvailabilityResponse;
 use crate::network::artifact_protocol::ArtifactResponse;
+use crate::network::artifact_replication_protocol::ArtifactReplicationResponse;
 use crate::network::blockchain_protocol::BlockchainResponse;
 use crate::network::build_protocol::BuildResponse;
 use crate::network::build_status_protocol::BuildStatusResponse;
 use crate::network::client::command::Command;
 use crate::network::idle_metric_protocol::{IdleMetricResponse, PeerMetrics};
-use crate::node_api::model::cli::Status;
+use crate::network::name_record::NameRecord;
+use crate::network::signed_document::SignedDocument;
+use crate::node_api::model::cli::{PeerDetails, Status};
 use libp2p::core::{Multiaddr, PeerId};
 use libp2p::gossipsub;
 use libp2p::request_response::ResponseChannel;
 use log::debug;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
+use tracing::instrument;
 
 /* peer metrics support */
 const PEER_METRIC_THRESHOLD: f64 = 0.5_f64;
@@ -37,6 +43,7 @@ const PEER_METRIC_THRESHOLD: f64 = 0.5_f64;
 struct IdleMetric {
     pub peer: PeerId,
     pub metric: f64,
+    pub latency: Duration,
 }
 /* peer metric support */
 
@@ -161,6 +168,43 @@ impl Client {
         bootstrap_receiver.await?
     }
 
+    /// Instruct the swarm to stay connected to the peer with the specified
+    /// address. Whenever a dial attempt to the peer fails or the connection
+    /// is lost, the swarm retries to connect with an exponential backoff.
+    pub async fn keep_connected(
+        &mut self,
+        peer_id: &PeerId,
+        peer_addr: &Multiaddr,
+    ) -> anyhow::Result<()> {
+        debug!("p2p::Client::keep_connected {:?} {:?}", peer_id, peer_addr);
+
+        self.sender
+            .send(Command::KeepConnected {
+                peer_id: *peer_id,
+                peer_addr: peer_addr.clone(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Close the connections to the specified peer and stop reconnecting to
+    /// it. A banned peer is refused any new connection until the node is
+    /// restarted.
+    pub async fn disconnect_peer(&mut self, peer_id: &PeerId, ban: bool) -> anyhow::Result<()> {
+        debug!("p2p::Client::disconnect_peer {:?} ban: {}", peer_id, ban);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::DisconnectPeer {
+                peer_id: *peer_id,
+                ban,
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
     /// List the peers that this node is connected to.
     pub async fn list_peers(&mut self) -> anyhow::Result<HashSet<PeerId>> {
         let (sender, receiver) = oneshot::channel();
@@ -168,6 +212,82 @@ impl Client {
         Ok(receiver.await?)
     }
 
+    /// List the average round-trip time, as measured by the ping
+    /// protocol, of each connected peer. Peers for which no
+    /// round-trip time was measured yet are not included.
+    pub async fn list_peer_latencies(&mut self) -> anyhow::Result<HashMap<PeerId, Duration>> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ListPeerLatencies { sender })
+            .await?;
+        Ok(receiver.await?)
+    }
+
+    /// Get the details of a connected peer, like the information it
+    /// advertised with the identify protocol. Returns `None` when this
+    /// node is not connected to the peer.
+    pub async fn get_peer_details(
+        &mut self,
+        peer_id: &PeerId,
+    ) -> anyhow::Result<Option<PeerDetails>> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::GetPeerDetails {
+                peer_id: *peer_id,
+                sender,
+            })
+            .await?;
+        Ok(receiver.await?)
+    }
+
+    /// Get the details of every connected peer.
+    pub async fn list_peer_details(&mut self) -> anyhow::Result<Vec<PeerDetails>> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ListPeerDetails { sender })
+            .await?;
+        Ok(receiver.await?)
+    }
+
+    /// Ask a peer for its idle metric, which is lower when the peer is less busy.
+    pub async fn request_idle_metric(&mut self, peer_id: &PeerId) -> anyhow::Result<f64> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::RequestIdleMetric {
+                peer: *peer_id,
+                sender,
+            })
+            .await?;
+        let peer_metrics = receiver.await??;
+        Ok(f64::from_le_bytes(peer_metrics.idle_metric))
+    }
+
+    /// Upload `upload_size` bytes of synthetic data to a peer and let it
+    /// return `download_size` bytes of synthetic data. Returns the number
+    /// of bytes that were received.
+    pub async fn request_bench(
+        &mut self,
+        peer_id: &PeerId,
+        upload_size: u64,
+        download_size: u64,
+    ) -> anyhow::Result<u64> {
+        debug!(
+            "p2p::Client::request_bench {:?}: upload {} bytes, download {} bytes",
+            peer_id, upload_size, download_size
+        );
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::RequestBench {
+                peer: *peer_id,
+                download_size,
+                payload: vec![0; upload_size as usize],
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
     /// Get the status of the node including nearby peers cnt and my peer addrs
     pub async fn status(&mut self) -> anyhow::Result<Status> {
         let (sender, receiver) = oneshot::channel();
@@ -175,6 +295,18 @@ impl Client {
         Ok(receiver.await?)
     }
 
+    /// Gracefully shut down the p2p network of this node. The node stops
+    /// providing its artifacts, announces to the other peers that it is
+    /// leaving and closes all connections. The event loop is stopped
+    /// afterwards, so the client can no longer be used.
+    pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        debug!("p2p::Client::shutdown");
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender.send(Command::Shutdown { sender }).await?;
+        Ok(receiver.await?)
+    }
+
     /// Inform the swarm that this node is currently a provider
     /// of the artifact with the specified `artifact_id`.
     pub async fn provide(&mut self, artifact_id: &str) -> anyhow::Result<()> {
@@ -190,8 +322,24 @@ impl Client {
         Ok(receiver.await?)
     }
 
+    /// Inform the swarm that this node is no longer a provider
+    /// of the artifact with the specified `artifact_id`.
+    pub async fn stop_providing(&mut self, artifact_id: &str) -> anyhow::Result<()> {
+        debug!("p2p::Client::stop_providing {:?}", artifact_id);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::StopProviding {
+                artifact_id: artifact_id.to_owned(),
+                sender,
+            })
+            .await?;
+        Ok(receiver.await?)
+    }
+
     /// List all peers in the swarm that are providing
     /// the artifact with the specified `artifact_id`.
+    #[instrument(skip(self))]
     pub async fn list_providers(&mut self, artifact_id: &str) -> anyhow::Result<HashSet<PeerId>> {
         debug!("p2p::Client::list_providers {:?}", artifact_id);
 
@@ -205,6 +353,99 @@ impl Client {
         Ok(receiver.await?)
     }
 
+    /// Publish a record in the DHT that maps the specified `name` to
+    /// the artifact with `artifact_id` and `artifact_hash`. The record is
+    /// signed with the keypair of the local node.
+    pub async fn put_name_record(
+        &mut self,
+        name: &str,
+        artifact_id: &str,
+        artifact_hash: &str,
+    ) -> anyhow::Result<()> {
+        debug!("p2p::Client::put_name_record {:?}", name);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::PutNameRecord {
+                name: name.to_owned(),
+                artifact_id: artifact_id.to_owned(),
+                artifact_hash: artifact_hash.to_owned(),
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
+    /// Resolve the specified `name` to an artifact by looking up its name
+    /// record in the DHT. Only records with a valid signature are returned,
+    /// and only records of the specified `publishers` when they are set.
+    pub async fn resolve_name(
+        &mut self,
+        name: &str,
+        publishers: Option<HashSet<PeerId>>,
+    ) -> anyhow::Result<Option<NameRecord>> {
+        debug!("p2p::Client::resolve_name {:?}", name);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ResolveName {
+                name: name.to_owned(),
+                publishers,
+                sender,
+            })
+            .await?;
+        Ok(receiver.await?)
+    }
+
+    /// Sign a document, like an SBOM, with the keypair of the local node.
+    pub async fn sign_document(&mut self, content: &str) -> anyhow::Result<SignedDocument> {
+        debug!("p2p::Client::sign_document");
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::SignDocument {
+                content: content.to_owned(),
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
+    /// Publish a signed document, like package metadata, in the DHT under
+    /// the specified `key`.
+    pub async fn put_signed_record(
+        &mut self,
+        key: &str,
+        document: &SignedDocument,
+    ) -> anyhow::Result<()> {
+        debug!("p2p::Client::put_signed_record {:?}", key);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::PutSignedRecord {
+                key: key.to_owned(),
+                document: document.clone(),
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
+    /// Look up the signed document under the specified `key` in the DHT.
+    /// Only documents with a valid signature are returned.
+    pub async fn get_signed_record(&mut self, key: &str) -> anyhow::Result<Option<SignedDocument>> {
+        debug!("p2p::Client::get_signed_record {:?}", key);
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::GetSignedRecord {
+                key: key.to_owned(),
+                sender,
+            })
+            .await?;
+        Ok(receiver.await?)
+    }
+
     /// Request a build to a peer with the specified address.
     pub async fn request_build(
         &mut self,
@@ -250,6 +491,7 @@ impl Client {
 
     /// Request an artifact with the specified `artifact_id`
     /// from the swarm.
+    #[instrument(skip(self, peer), fields(peer_id = %peer))]
     pub async fn request_artifact(
         &mut self,
         peer: &PeerId,
@@ -272,16 +514,122 @@ impl Client {
     }
 
     /// Put the artifact as a response to an incoming artifact
-    /// request.
+    /// request from the specified `peer`.
     pub async fn respond_artifact(
         &mut self,
+        peer: &PeerId,
         artifact: Vec<u8>,
         channel: ResponseChannel<ArtifactResponse>,
     ) -> anyhow::Result<()> {
         debug!("p2p::Client::respond_artifact size={:?}", artifact.len());
 
         self.sender
-            .send(Command::RespondArtifact { artifact, channel })
+            .send(Command::RespondArtifact {
+                peer: *peer,
+                artifact,
+                channel,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Refuse an incoming artifact request because this node is
+    /// overloaded, so that the peer requests the artifact elsewhere.
+    pub async fn respond_artifact_busy(
+        &mut self,
+        channel: ResponseChannel<ArtifactResponse>,
+    ) -> anyhow::Result<()> {
+        debug!("p2p::Client::respond_artifact_busy");
+
+        self.sender
+            .send(Command::RespondArtifactBusy { channel })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Ask a peer if it still has the artifact with the specified
+    /// `artifact_id`, without transferring the artifact itself. Returns
+    /// the size of the artifact, or `None` when the peer does not have it.
+    pub async fn request_artifact_availability(
+        &mut self,
+        peer: &PeerId,
+        artifact_id: &str,
+    ) -> anyhow::Result<Option<u64>> {
+        debug!(
+            "p2p::Client::request_artifact_availability {:?}: {:?}",
+            peer, artifact_id
+        );
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::RequestArtifactAvailability {
+                artifact_id: artifact_id.to_owned(),
+                peer: *peer,
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
+    /// Put the size of the artifact, if it is available on this node,
+    /// as a response to an incoming artifact availability request.
+    pub async fn respond_artifact_availability(
+        &mut self,
+        size: Option<u64>,
+        channel: ResponseChannel<ArtifactAvailabilityResponse>,
+    ) -> anyhow::Result<()> {
+        debug!("p2p::Client::respond_artifact_availability size={:?}", size);
+
+        self.sender
+            .send(Command::RespondArtifactAvailability { size, channel })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Push the artifact of the specified package to a peer, asking it
+    /// to store a replica. Returns whether the peer accepted the artifact.
+    pub async fn request_artifact_replication(
+        &mut self,
+        peer: &PeerId,
+        package_type: PackageType,
+        package_specific_artifact_id: &str,
+        artifact: Vec<u8>,
+    ) -> anyhow::Result<bool> {
+        debug!(
+            "p2p::Client::request_artifact_replication {:?} to peer {:?}",
+            package_specific_artifact_id, peer
+        );
+
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::RequestArtifactReplication {
+                peer: *peer,
+                package_type,
+                package_specific_artifact_id: package_specific_artifact_id.to_owned(),
+                artifact,
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
+    /// Respond to an incoming artifact replication request, telling
+    /// the peer whether the artifact was accepted.
+    pub async fn respond_artifact_replication(
+        &mut self,
+        accepted: bool,
+        channel: ResponseChannel<ArtifactReplicationResponse>,
+    ) -> anyhow::Result<()> {
+        debug!(
+            "p2p::Client::respond_artifact_replication accepted={:?}",
+            accepted
+        );
+
+        self.sender
+            .send(Command::RespondArtifactReplication { accepted, channel })
             .await?;
 
         Ok(())
@@ -297,6 +645,16 @@ impl Client {
             "p2p::Client::get_idle_peer() entered with {} peers",
             providers.len()
         );
+        // peers are queried in order of their latency, so that the closest
+        // peer wins when multiple peers are below the threshold
+        let latencies = if providers.len() > 1 {
+            self.list_peer_latencies().await?
+        } else {
+            HashMap::new()
+        };
+        let mut providers: Vec<PeerId> = providers.into_iter().collect();
+        providers.sort_by_key(|peer| latencies.get(peer).copied().unwrap_or(Duration::MAX));
+
         let mut idle_metrics: Vec<IdleMetric> = Vec::new();
         for peer in providers.iter() {
             let (sender, receiver) = oneshot::channel();
@@ -313,6 +671,7 @@ impl Client {
                     let idle_metric = IdleMetric {
                         peer: *peer,
                         metric,
+                        latency: latencies.get(peer).copied().unwrap_or(Duration::MAX),
                     };
                     if idle_metric.metric < PEER_METRIC_THRESHOLD {
                         debug!(
@@ -337,11 +696,69 @@ impl Client {
             };
         }
 
-        //sort the peers in ascending order according to their idle metric and return top of list
-        idle_metrics.sort_by(|a, b| a.metric.partial_cmp(&b.metric).unwrap());
+        //sort the peers in ascending order according to their idle metric, using the latency
+        //as a tiebreaker, and return top of list
+        idle_metrics.sort_by(|a, b| {
+            a.metric
+                .partial_cmp(&b.metric)
+                .unwrap()
+                .then_with(|| a.latency.cmp(&b.latency))
+        });
         Ok(idle_metrics.first().map(|idle_metric| idle_metric.peer))
     }
 
+    /// Returns at most `count` of the specified peers, ordered from the
+    /// most idle to the least idle peer. Peers for which the idle metric
+    /// could not be retrieved are skipped.
+    pub async fn get_idle_peers(
+        &mut self,
+        peers: HashSet<PeerId>,
+        count: usize,
+    ) -> anyhow::Result<Vec<PeerId>> {
+        debug!(
+            "p2p::Client::get_idle_peers() entered with {} peers",
+            peers.len()
+        );
+        let latencies = self.list_peer_latencies().await?;
+
+        let mut idle_metrics: Vec<IdleMetric> = Vec::new();
+        for peer in peers.iter() {
+            let (sender, receiver) = oneshot::channel();
+            self.sender
+                .send(Command::RequestIdleMetric {
+                    peer: *peer,
+                    sender,
+                })
+                .await?;
+
+            match receiver.await? {
+                Ok(peer_metric) => idle_metrics.push(IdleMetric {
+                    peer: *peer,
+                    metric: f64::from_le_bytes(peer_metric.idle_metric),
+                    latency: latencies.get(peer).copied().unwrap_or(Duration::MAX),
+                }),
+                Err(e) => {
+                    debug!(
+                        "p2p::Client::get_idle_peers() Unable to get peer metric for peer {} error {}",
+                        peer, e
+                    );
+                }
+            }
+        }
+
+        idle_metrics.sort_by(|a, b| {
+            a.metric
+                .partial_cmp(&b.metric)
+                .unwrap()
+                .then_with(|| a.latency.cmp(&b.latency))
+        });
+        Ok(idle_metrics
+            .into_iter()
+            .take(count)
+            .map(|idle_metric| idle_metric.peer)
+            .collect())
+    }
+
     pub async fn respond_idle_metric(
         &mut self,
         metric: PeerMetrics,
@@ -391,6 +808,19 @@ impl Client {
         Ok(())
     }
 
+    /// Advertise the quality metric of this node to all peers, which use it
+    /// instead of requesting the metric while it is recent.
+    pub async fn advertise_quality_metric(&mut self, quality_metric: f64) -> anyhow::Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::AdvertiseQualityMetric {
+                quality_metric,
+                sender,
+            })
+            .await?;
+        receiver.await?
+    }
+
     pub async fn broadcast_block(&mut self, block: Vec<u8>) -> anyhow::Result<()> {
         debug!("p2p::Client::broadcast_block sent");
 
@@ -509,7 +939,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_list_peers() {
+    async fn test_disconnect_peer() {
         let (sender, mut receiver) = mpsc::channel(1);
 
         let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
@@ -519,20 +949,23 @@ mod tests {
             pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
         };
 
-        tokio::spawn(async move { client.list_peers().await });
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        tokio::spawn(async move { client.disconnect_peer(&other_peer_id, true).await });
 
         tokio::select! {
             command = receiver.recv() => match command {
-                Some(Command::ListPeers { sender }) => {
-                    let _ = sender.send(Default::default());
+                Some(Command::DisconnectPeer { peer_id, ban, sender }) => {
+                    assert_eq!(peer_id, other_peer_id);
+                    assert!(ban);
+                    let _ = sender.send(Ok(()));
                 },
-                _ => panic!("Command must match Command::ListPeers")
+                _ => panic!("Command must match Command::DisconnectPeer")
             }
         }
     }
 
     #[tokio::test]
-    async fn test_status() {
+    async fn test_keep_connected() {
         let (sender, mut receiver) = mpsc::channel(1);
 
         let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
@@ -542,20 +975,23 @@ mod tests {
             pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
         };
 
-        tokio::spawn(async move { client.status().await });
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/44000".parse().unwrap();
+        let cloned_address = address.clone();
+        tokio::spawn(async move { client.keep_connected(&local_peer_id, &address).await });
 
         tokio::select! {
             command = receiver.recv() => match command {
-                Some(Command::Status { sender }) => {
-                    let _ = sender.send(Default::default());
+                Some(Command::KeepConnected { peer_id, peer_addr }) => {
+                    assert_eq!(peer_id, local_peer_id);
+                    assert_eq!(peer_addr, cloned_address);
                 },
-                _ => panic!("Command must match Command::Status")
+                _ => panic!("Command must match Command::KeepConnected")
             }
         }
     }
 
     #[tokio::test]
-    async fn test_get_idle_metric() {
+    async fn test_list_peers() {
         let (sender, mut receiver) = mpsc::channel(1);
 
         let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
@@ -565,8 +1001,125 @@ mod tests {
             pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
         };
 
-        let mut peers: HashSet<PeerId> = HashSet::new();
-        peers.insert(client.local_peer_id);
+        tokio::spawn(async move { client.list_peers().await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::ListPeers { sender }) => {
+                    let _ = sender.send(Default::default());
+                },
+                _ => panic!("Command must match Command::ListPeers")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_peer_latencies() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let mut client = Client {
+            sender,
+            local_peer_id,
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        tokio::spawn(async move { client.list_peer_latencies().await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::ListPeerLatencies { sender }) => {
+                    let _ = sender.send(Default::default());
+                },
+                _ => panic!("Command must match Command::ListPeerLatencies")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_peer_details() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let mut client = Client {
+            sender,
+            local_peer_id,
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        tokio::spawn(async move { client.get_peer_details(&other_peer_id).await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::GetPeerDetails { peer_id, sender }) => {
+                    assert_eq!(peer_id, other_peer_id);
+                    let _ = sender.send(None);
+                },
+                _ => panic!("Command must match Command::GetPeerDetails")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_peer_details() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let mut client = Client {
+            sender,
+            local_peer_id,
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        tokio::spawn(async move { client.list_peer_details().await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::ListPeerDetails { sender }) => {
+                    let _ = sender.send(vec![]);
+                },
+                _ => panic!("Command must match Command::ListPeerDetails")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let mut client = Client {
+            sender,
+            local_peer_id,
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        tokio::spawn(async move { client.status().await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::Status { sender }) => {
+                    let _ = sender.send(Default::default());
+                },
+                _ => panic!("Command must match Command::Status")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_idle_metric() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let local_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let mut client = Client {
+            sender,
+            local_peer_id,
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let mut peers: HashSet<PeerId> = HashSet::new();
+        peers.insert(client.local_peer_id);
         tokio::spawn(async move { client.get_idle_peer(peers).await });
 
         tokio::select! {
@@ -584,6 +1137,132 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_idle_peer_uses_latency_as_tiebreaker() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let slow_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let fast_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let peers = HashSet::from([slow_peer_id, fast_peer_id]);
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(HashMap::from([
+                            (slow_peer_id, Duration::from_millis(50)),
+                            (fast_peer_id, Duration::from_millis(5)),
+                        ]));
+                    }
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: 1f64.to_le_bytes(),
+                        }));
+                    }
+                    None => break,
+                    _ => panic!(
+                        "Command must match Command::ListPeerLatencies, Command::RequestIdleMetric"
+                    ),
+                }
+            }
+        });
+
+        let result = client.get_idle_peer(peers).await;
+        assert_eq!(Some(fast_peer_id), result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_idle_peers() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let busy_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let idle_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let unreachable_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let peers = HashSet::from([busy_peer_id, idle_peer_id, unreachable_peer_id]);
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(HashMap::new());
+                    }
+                    Some(Command::RequestIdleMetric { peer, sender }) => {
+                        let metric = if peer == busy_peer_id {
+                            Ok(PeerMetrics {
+                                idle_metric: 100f64.to_le_bytes(),
+                            })
+                        } else if peer == idle_peer_id {
+                            Ok(PeerMetrics {
+                                idle_metric: 1f64.to_le_bytes(),
+                            })
+                        } else {
+                            Err(anyhow::anyhow!("peer unreachable"))
+                        };
+                        let _ = sender.send(metric);
+                    }
+                    None => break,
+                    _ => panic!(
+                        "Command must match Command::ListPeerLatencies, Command::RequestIdleMetric"
+                    ),
+                }
+            }
+        });
+
+        let result = client.get_idle_peers(peers.clone(), 3).await;
+        assert_eq!(vec![idle_peer_id, busy_peer_id], result.unwrap());
+
+        let result = client.get_idle_peers(peers, 1).await;
+        assert_eq!(vec![idle_peer_id], result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_artifact_replication() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        tokio::spawn(async move {
+            client
+                .request_artifact_replication(
+                    &other_peer_id,
+                    PackageType::Docker,
+                    "alpine@sha256:1234",
+                    b"SAMPLE_DATA".to_vec(),
+                )
+                .await
+        });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::RequestArtifactReplication { peer, package_type, package_specific_artifact_id, artifact, sender }) => {
+                    assert_eq!(peer, other_peer_id);
+                    assert_eq!(package_type, PackageType::Docker);
+                    assert_eq!(package_specific_artifact_id, "alpine@sha256:1234");
+                    assert_eq!(artifact, b"SAMPLE_DATA".to_vec());
+                    let _ = sender.send(Ok(true));
+                },
+                _ => panic!("Command must match Command::RequestArtifactReplication")
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_provide() {
         let (sender, mut receiver) = mpsc::channel(1);
@@ -613,6 +1292,35 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_stop_providing() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let random_artifact_id: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(30)
+            .map(char::from)
+            .collect();
+        let cloned_random_artifact_id = random_artifact_id.clone();
+        tokio::spawn(async move { client.stop_providing(&random_artifact_id).await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::StopProviding { artifact_id, sender }) => {
+                    assert_eq!(artifact_id, cloned_random_artifact_id);
+                    let _ = sender.send(());
+                },
+                _ => panic!("Command must match Command::StopProviding")
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_list_providers() {
         let (sender, mut receiver) = mpsc::channel(1);
@@ -642,6 +1350,129 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_put_name_record() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        tokio::spawn(async move {
+            client
+                .put_name_record("Docker/alpine:3.16", "artifact_id", "artifact_hash")
+                .await
+        });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::PutNameRecord { name, artifact_id, artifact_hash, sender }) => {
+                    assert_eq!(name, "Docker/alpine:3.16");
+                    assert_eq!(artifact_id, "artifact_id");
+                    assert_eq!(artifact_hash, "artifact_hash");
+                    let _ = sender.send(Ok(()));
+                },
+                _ => panic!("Command must match Command::PutNameRecord")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get_signed_record() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let keypair = Keypair::generate_ed25519();
+        let mut client = Client {
+            sender,
+            local_peer_id: keypair.public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+        let document = SignedDocument::new(&keypair, "{}").unwrap();
+        let expected_document = document.clone();
+
+        tokio::spawn(async move {
+            client
+                .put_signed_record("packages/Docker/alpine", &document)
+                .await?;
+            client.get_signed_record("packages/Docker/alpine").await
+        });
+
+        match receiver.recv().await {
+            Some(Command::PutSignedRecord {
+                key,
+                document,
+                sender,
+            }) => {
+                assert_eq!(key, "packages/Docker/alpine");
+                assert_eq!(document, expected_document);
+                let _ = sender.send(Ok(()));
+            }
+            _ => panic!("Command must match Command::PutSignedRecord"),
+        }
+        match receiver.recv().await {
+            Some(Command::GetSignedRecord { key, sender }) => {
+                assert_eq!(key, "packages/Docker/alpine");
+                let _ = sender.send(Some(expected_document));
+            }
+            _ => panic!("Command must match Command::GetSignedRecord"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_name() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let publisher = PeerId::random();
+        tokio::spawn(async move {
+            client
+                .resolve_name("Docker/alpine:3.16", Some(HashSet::from([publisher])))
+                .await
+        });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::ResolveName { name, publishers, sender }) => {
+                    assert_eq!(name, "Docker/alpine:3.16");
+                    assert_eq!(publishers, Some(HashSet::from([publisher])));
+                    let _ = sender.send(None);
+                },
+                _ => panic!("Command must match Command::ResolveName")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_document() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let keypair = Keypair::generate_ed25519();
+        let mut client = Client {
+            sender,
+            local_peer_id: keypair.public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        tokio::spawn(async move { client.sign_document("{}").await });
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::SignDocument { content, sender }) => {
+                    assert_eq!(content, "{}");
+                    let _ = sender.send(SignedDocument::new(&keypair, &content));
+                },
+                _ => panic!("Command must match Command::SignDocument")
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_request_artifact() {
         let (sender, mut receiver) = mpsc::channel(1);
@@ -677,6 +1508,47 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_request_artifact_availability() {
+        let (sender, mut receiver) = mpsc::channel(1);
+
+        let mut client = Client {
+            sender,
+            local_peer_id: Keypair::generate_ed25519().public().to_peer_id(),
+            pyrsia_topic: IdentTopic::new("pyrsia-blockchain-topic"),
+        };
+
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let random_artifact_id: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(30)
+            .map(char::from)
+            .collect();
+        let cloned_random_artifact_id = random_artifact_id.clone();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Some(Command::RequestArtifactAvailability {
+                        peer,
+                        artifact_id,
+                        sender,
+                    }) => {
+                        assert_eq!(peer, other_peer_id);
+                        assert_eq!(artifact_id, cloned_random_artifact_id);
+                        let _ = sender.send(Ok(Some(42)));
+                    }
+                    None => break,
+                    _ => panic!("Command must match Command::RequestArtifactAvailability"),
+                }
+            }
+        });
+
+        let result = client
+            .request_artifact_availability(&other_peer_id, &random_artifact_id)
+            .await;
+        assert_eq!(Some(42), result.unwrap());
+    }
+
     #[tokio::test]
     async fn test_request_docker_build() {
         let (sender, mut receiver) = mpsc::channel(1);
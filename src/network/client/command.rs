@@ -15,16 +15,21 @@
 */
 
 use crate::artifact_service::model::PackageType;
+use crate::network::artifact_availability_protocol::ArtifactAvailabilityResponse;
 use crate::network::artifact_protocol::ArtifactResponse;
+use crate::network::artifact_replication_protocol::ArtifactReplicationResponse;
 use crate::network::blockchain_protocol::BlockchainResponse;
 use crate::network::build_protocol::BuildResponse;
 use crate::network::build_status_protocol::BuildStatusResponse;
 use crate::network::idle_metric_protocol::{IdleMetricResponse, PeerMetrics};
-use crate::node_api::model::cli::Status;
+use crate::network::name_record::NameRecord;
+use crate::network::signed_document::SignedDocument;
+use crate::node_api::model::cli::{PeerDetails, Status};
 use libp2p::core::{Multiaddr, PeerId};
 use libp2p::gossipsub;
 use libp2p::request_response::ResponseChannel;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use strum_macros::Display;
 use tokio::sync::oneshot;
 
@@ -38,6 +43,10 @@ pub enum Command {
         probe_addr: Multiaddr,
         sender: oneshot::Sender<anyhow::Result<()>>,
     },
+    AdvertiseQualityMetric {
+        quality_metric: f64,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
     BroadcastBlock {
         topic: gossipsub::IdentTopic,
         block: Vec<u8>,
@@ -55,20 +64,70 @@ pub enum Command {
         peer_addr: Multiaddr,
         sender: oneshot::Sender<anyhow::Result<()>>,
     },
+    KeepConnected {
+        peer_id: PeerId,
+        peer_addr: Multiaddr,
+    },
+    DisconnectPeer {
+        peer_id: PeerId,
+        ban: bool,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
     ListPeers {
         sender: oneshot::Sender<HashSet<PeerId>>,
     },
+    ListPeerLatencies {
+        sender: oneshot::Sender<HashMap<PeerId, Duration>>,
+    },
+    GetPeerDetails {
+        peer_id: PeerId,
+        sender: oneshot::Sender<Option<PeerDetails>>,
+    },
+    ListPeerDetails {
+        sender: oneshot::Sender<Vec<PeerDetails>>,
+    },
     Status {
         sender: oneshot::Sender<Status>,
     },
+    Shutdown {
+        sender: oneshot::Sender<()>,
+    },
     Provide {
         artifact_id: String,
         sender: oneshot::Sender<()>,
     },
+    StopProviding {
+        artifact_id: String,
+        sender: oneshot::Sender<()>,
+    },
     ListProviders {
         artifact_id: String,
         sender: oneshot::Sender<HashSet<PeerId>>,
     },
+    PutNameRecord {
+        name: String,
+        artifact_id: String,
+        artifact_hash: String,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    ResolveName {
+        name: String,
+        publishers: Option<HashSet<PeerId>>,
+        sender: oneshot::Sender<Option<NameRecord>>,
+    },
+    SignDocument {
+        content: String,
+        sender: oneshot::Sender<anyhow::Result<SignedDocument>>,
+    },
+    PutSignedRecord {
+        key: String,
+        document: SignedDocument,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    GetSignedRecord {
+        key: String,
+        sender: oneshot::Sender<Option<SignedDocument>>,
+    },
     RequestBuild {
         peer: PeerId,
         package_type: PackageType,
@@ -85,9 +144,33 @@ pub enum Command {
         sender: oneshot::Sender<anyhow::Result<Vec<u8>>>,
     },
     RespondArtifact {
+        peer: PeerId,
         artifact: Vec<u8>,
         channel: ResponseChannel<ArtifactResponse>,
     },
+    RespondArtifactBusy {
+        channel: ResponseChannel<ArtifactResponse>,
+    },
+    RequestArtifactAvailability {
+        artifact_id: String,
+        peer: PeerId,
+        sender: oneshot::Sender<anyhow::Result<Option<u64>>>,
+    },
+    RequestArtifactReplication {
+        peer: PeerId,
+        package_type: PackageType,
+        package_specific_artifact_id: String,
+        artifact: Vec<u8>,
+        sender: oneshot::Sender<anyhow::Result<bool>>,
+    },
+    RespondArtifactReplication {
+        accepted: bool,
+        channel: ResponseChannel<ArtifactReplicationResponse>,
+    },
+    RespondArtifactAvailability {
+        size: Option<u64>,
+        channel: ResponseChannel<ArtifactAvailabilityResponse>,
+    },
     RequestIdleMetric {
         peer: PeerId,
         sender: oneshot::Sender<anyhow::Result<PeerMetrics>>,
@@ -96,6 +179,12 @@ pub enum Command {
         metric: PeerMetrics,
         channel: ResponseChannel<IdleMetricResponse>,
     },
+    RequestBench {
+        peer: PeerId,
+        download_size: u64,
+        payload: Vec<u8>,
+        sender: oneshot::Sender<anyhow::Result<u64>>,
+    },
     RequestBlockchain {
         data: Vec<u8>,
         peer: PeerId,
@@ -15,41 +15,98 @@
 */
 
 use crate::artifact_service::model::PackageType;
-use crate::network::artifact_protocol::{ArtifactRequest, ArtifactResponse};
+use crate::network::artifact_availability_protocol::{
+    ArtifactAvailabilityRequest, ArtifactAvailabilityResponse,
+};
+use crate::network::artifact_protocol::{ArtifactRequest, ArtifactResponse, PeerBusy};
+use crate::network::artifact_replication_protocol::{
+    ArtifactReplicationRequest, ArtifactReplicationResponse,
+};
 use crate::network::behaviour::{PyrsiaNetworkBehaviour, PyrsiaNetworkEvent};
+use crate::network::bench_protocol::{BenchRequest, BenchResponse, MAX_BENCH_PAYLOAD_SIZE};
 use crate::network::blockchain_protocol::{BlockchainRequest, BlockchainResponse};
 use crate::network::build_protocol::{BuildRequest, BuildResponse};
 use crate::network::build_status_protocol::{BuildStatusRequest, BuildStatusResponse};
 use crate::network::client::command::Command;
 use crate::network::idle_metric_protocol::{IdleMetricRequest, IdleMetricResponse, PeerMetrics};
-use crate::node_api::model::cli::Status;
+use crate::network::latency::RollingRtt;
+use crate::network::name_record::NameRecord;
+use crate::network::peer_identity::{ConnectionDirection, PeerIdentity, ProtocolCompatibility};
+use crate::network::reconnect::Reconnect;
+use crate::network::signed_document::SignedDocument;
+use crate::network::signer::Signer;
+use crate::network::transfer_metrics::TransferMetrics;
+use crate::node_api::model::cli::{PeerDetails, Status};
 use crate::util::env_util::read_var;
 use libp2p::autonat::{Event as AutonatEvent, NatStatus};
 use libp2p::core::PeerId;
 use libp2p::futures::StreamExt;
 use libp2p::gossipsub;
 use libp2p::identify;
-use libp2p::kad::{BootstrapOk, GetProvidersOk, KademliaEvent, QueryId, QueryResult};
+use libp2p::kad::record::store::RecordStore;
+use libp2p::kad::{
+    BootstrapOk, GetProvidersOk, GetRecordOk, KademliaEvent, PeerRecord, QueryId, QueryResult,
+    Quorum, Record,
+};
 use libp2p::multiaddr::Protocol;
+use libp2p::ping;
 use libp2p::request_response::{
     RequestId, RequestResponseEvent, RequestResponseMessage, ResponseChannel,
 };
+use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::SwarmEvent;
+use libp2p::Multiaddr;
 use libp2p::Swarm;
 use log::{debug, error, info, trace, warn};
 use std::collections::{hash_map::Entry, HashMap, HashSet};
 use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+/// The interval at which the event loop checks if a reconnection
+/// attempt to one of the peers it must stay connected to is due.
+const RECONNECT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time the event loop keeps driving the swarm after publishing
+/// the leave notice on shutdown, so the notice can reach other peers.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// The gossipsub topic on which nodes announce that they are leaving
+/// the p2p network.
+pub const LEAVE_TOPIC: &str = "pyrsia-leave-topic";
+
+/// The gossipsub topic on which nodes periodically advertise their quality
+/// metric. Gossipsub signs every message with the key of its source, so a
+/// node can only advertise its own metric.
+pub const METRICS_TOPIC: &str = "pyrsia-metrics-topic";
+
+/// The interval at which nodes advertise their quality metric.
+pub const METRIC_ADVERTISEMENT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The age after which an advertised quality metric is no longer used,
+/// so a few advertisements may be lost before a peer has to be asked.
+const ADVERTISED_METRIC_MAX_AGE: Duration = Duration::from_secs(90);
 
 type PendingBootstrapMap = HashMap<QueryId, oneshot::Sender<anyhow::Result<()>>>;
 type PendingDialMap = HashMap<PeerId, oneshot::Sender<anyhow::Result<()>>>;
 type PendingListProvidersMap = HashMap<QueryId, PendingListProviders>;
 type PendingStartProvidingMap = HashMap<QueryId, oneshot::Sender<()>>;
+type PendingPutRecordMap = HashMap<QueryId, oneshot::Sender<anyhow::Result<()>>>;
+type PendingResolveNameMap = HashMap<QueryId, PendingResolveName>;
+type PendingGetSignedRecordMap = HashMap<QueryId, PendingGetSignedRecord>;
 type PendingRequestArtifactMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<Vec<u8>>>>;
+type PendingRequestArtifactAvailabilityMap =
+    HashMap<RequestId, oneshot::Sender<anyhow::Result<Option<u64>>>>;
+type PendingRequestArtifactReplicationMap =
+    HashMap<RequestId, oneshot::Sender<anyhow::Result<bool>>>;
 type PendingRequestBuildMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<String>>>;
 type PendingRequestIdleMetricMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<PeerMetrics>>>;
 type PendingRequestBlockchainMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<Vec<u8>>>>;
 type PendingBuildStatusMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<String>>>;
+type PendingBenchMap = HashMap<RequestId, oneshot::Sender<anyhow::Result<u64>>>;
 
 struct PendingListProviders {
     sender: oneshot::Sender<HashSet<PeerId>>,
@@ -65,12 +122,24 @@ impl PendingListProviders {
     }
 }
 
+struct PendingResolveName {
+    name: String,
+    publishers: Option<HashSet<PeerId>>,
+    sender: oneshot::Sender<Option<NameRecord>>,
+}
+
+struct PendingGetSignedRecord {
+    key: String,
+    sender: oneshot::Sender<Option<SignedDocument>>,
+}
+
 /// The `PyrsiaEventLoop` is responsible for taking care of incoming
 /// events from the libp2p [`Swarm`] itself, the different network
 /// behaviours that exist inside the `Swarm` and incoming commands
 /// from the [`Client`].
 pub struct PyrsiaEventLoop {
     swarm: Swarm<PyrsiaNetworkBehaviour>,
+    signer: Arc<dyn Signer>,
     command_receiver: mpsc::Receiver<Command>,
     event_sender: mpsc::Sender<PyrsiaEvent>,
     bootstrapped: bool,
@@ -78,21 +147,36 @@ pub struct PyrsiaEventLoop {
     pending_dial: PendingDialMap,
     pending_start_providing: PendingStartProvidingMap,
     pending_list_providers: PendingListProvidersMap,
+    pending_put_record: PendingPutRecordMap,
+    pending_resolve_name: PendingResolveNameMap,
+    pending_get_signed_record: PendingGetSignedRecordMap,
     pending_request_artifact: PendingRequestArtifactMap,
+    pending_request_artifact_availability: PendingRequestArtifactAvailabilityMap,
+    pending_request_artifact_replication: PendingRequestArtifactReplicationMap,
     pending_request_build: PendingRequestBuildMap,
     pending_idle_metric_requests: PendingRequestIdleMetricMap,
     pending_blockchain_requests: PendingRequestBlockchainMap,
     pending_build_status_requests: PendingBuildStatusMap,
+    pending_bench_requests: PendingBenchMap,
+    peer_latencies: HashMap<PeerId, RollingRtt>,
+    peer_identities: HashMap<PeerId, PeerIdentity>,
+    advertised_metrics: HashMap<PeerId, (f64, Instant)>,
+    peer_connections: HashMap<PeerId, (ConnectionDirection, Multiaddr)>,
+    reconnect_peers: HashMap<PeerId, Reconnect>,
+    transfer_metrics: TransferMetrics,
+    stopped: bool,
 }
 
 impl PyrsiaEventLoop {
     pub fn new(
         swarm: Swarm<PyrsiaNetworkBehaviour>,
+        signer: Arc<dyn Signer>,
         command_receiver: mpsc::Receiver<Command>,
         event_sender: mpsc::Sender<PyrsiaEvent>,
     ) -> Self {
         Self {
             swarm,
+            signer,
             command_receiver,
             event_sender,
             bootstrapped: false,
@@ -100,38 +184,149 @@ impl PyrsiaEventLoop {
             pending_dial: Default::default(),
             pending_start_providing: Default::default(),
             pending_list_providers: Default::default(),
+            pending_put_record: Default::default(),
+            pending_resolve_name: Default::default(),
+            pending_get_signed_record: Default::default(),
             pending_request_artifact: Default::default(),
+            pending_request_artifact_availability: Default::default(),
+            pending_request_artifact_replication: Default::default(),
             pending_request_build: Default::default(),
             pending_idle_metric_requests: Default::default(),
             pending_blockchain_requests: Default::default(),
             pending_build_status_requests: Default::default(),
+            pending_bench_requests: Default::default(),
+            peer_latencies: Default::default(),
+            peer_identities: Default::default(),
+            advertised_metrics: Default::default(),
+            peer_connections: Default::default(),
+            reconnect_peers: Default::default(),
+            transfer_metrics: Default::default(),
+            stopped: false,
         }
     }
 
     /// Creates the actual event loop to begin listening for
     /// incoming events on the swarm and command channels.
     pub async fn run(mut self) {
+        let mut reconnect_interval = tokio::time::interval(RECONNECT_CHECK_INTERVAL);
         loop {
             tokio::select! {
-                event = self.swarm.select_next_some() => match event {
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::AutoNat(autonat_event)) => self.handle_autonat_event(autonat_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::Gossipsub(gossipsub_event)) => self.handle_gossipsub_event(gossipsub_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::Identify(identify_event)) => self.handle_identify_event(*identify_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::Kademlia(kademlia_event)) => self.handle_kademlia_event(*kademlia_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::RequestResponse(request_response_event)) => self.handle_request_response_event(request_response_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::BuildRequestResponse(build_request_response_event)) => self.handle_build_request_response_event(build_request_response_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::IdleMetricRequestResponse(request_response_event)) => self.handle_idle_metric_request_response_event(request_response_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::BlockchainRequestResponse(request_response_event)) => self.handle_blockchain_request_response_event(request_response_event).await,
-                    SwarmEvent::Behaviour(PyrsiaNetworkEvent::BuildStatusRequestResponse(build_status_request_response_event)) => self.handle_build_status_request_response_event(build_status_request_response_event).await,
-                    swarm_event => self.handle_swarm_event(swarm_event).await,
+                event = self.swarm.select_next_some() => {
+                    let span = swarm_event_span(&event);
+                    self.handle_event(event).instrument(span).await
                 },
                 command = self.command_receiver.recv() => match command {
                     Some(c) => {
                         self.handle_command(c).await;
+                        if self.stopped {
+                            info!("p2p event loop stopped");
+                            return;
+                        }
                     },
                     // Command channel closed, thus shutting down the network event loop.
                     None => { warn!("Got empty command"); return },
                 },
+                _ = reconnect_interval.tick() => self.handle_reconnects(),
+            }
+        }
+    }
+
+    // Handles an event of the swarm, in the span of the peer it concerns.
+    async fn handle_event(&mut self, event: SwarmEvent<PyrsiaNetworkEvent, impl Error>) {
+        match event {
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::AutoNat(autonat_event)) => {
+                self.handle_autonat_event(autonat_event).await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::Gossipsub(gossipsub_event)) => {
+                self.handle_gossipsub_event(gossipsub_event).await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::Identify(identify_event)) => {
+                self.handle_identify_event(*identify_event).await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::Kademlia(kademlia_event)) => {
+                self.handle_kademlia_event(*kademlia_event).await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::Ping(ping_event)) => {
+                self.handle_ping_event(ping_event).await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::RequestResponse(request_response_event)) => {
+                self.handle_request_response_event(request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::ArtifactAvailabilityRequestResponse(
+                request_response_event,
+            )) => {
+                self.handle_artifact_availability_request_response_event(request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::ArtifactReplicationRequestResponse(
+                request_response_event,
+            )) => {
+                self.handle_artifact_replication_request_response_event(request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::BuildRequestResponse(
+                build_request_response_event,
+            )) => {
+                self.handle_build_request_response_event(build_request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::IdleMetricRequestResponse(
+                request_response_event,
+            )) => {
+                self.handle_idle_metric_request_response_event(request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::BlockchainRequestResponse(
+                request_response_event,
+            )) => {
+                self.handle_blockchain_request_response_event(request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::BuildStatusRequestResponse(
+                build_status_request_response_event,
+            )) => {
+                self.handle_build_status_request_response_event(build_status_request_response_event)
+                    .await
+            }
+            SwarmEvent::Behaviour(PyrsiaNetworkEvent::BenchRequestResponse(
+                request_response_event,
+            )) => self.handle_bench_request_response_event(request_response_event),
+            swarm_event => self.handle_swarm_event(swarm_event).await,
+        }
+    }
+
+    // Dials the peers that this node must stay connected to and for
+    // which the backoff delay of the next reconnection attempt expired.
+    fn handle_reconnects(&mut self) {
+        let now = Instant::now();
+        let due_peers: Vec<(PeerId, Multiaddr)> = self
+            .reconnect_peers
+            .iter_mut()
+            .filter_map(|(peer_id, reconnect)| {
+                reconnect
+                    .take_due(now)
+                    .then(|| (*peer_id, reconnect.peer_addr.clone()))
+            })
+            .collect();
+
+        for (peer_id, peer_addr) in due_peers {
+            if self.swarm.is_connected(&peer_id) {
+                if let Some(reconnect) = self.reconnect_peers.get_mut(&peer_id) {
+                    reconnect.connected();
+                }
+                continue;
+            }
+
+            info!("Reconnecting to peer {} at {}", peer_id, peer_addr);
+            let dial_opts = DialOpts::peer_id(peer_id)
+                .addresses(vec![peer_addr])
+                .build();
+            if let Err(e) = self.swarm.dial(dial_opts) {
+                warn!("Failed to reconnect to peer {}: {}", peer_id, e);
+                if let Some(reconnect) = self.reconnect_peers.get_mut(&peer_id) {
+                    reconnect.schedule(now);
+                }
             }
         }
     }
@@ -161,10 +356,147 @@ impl PyrsiaEventLoop {
         }
     }
 
+    // Stops providing the local artifacts, announces to the other peers that
+    // this node is leaving and closes all connections.
+    async fn shutdown(&mut self) {
+        info!("Shutting down p2p network");
+
+        let provided_keys: Vec<_> = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .provided()
+            .map(|provider_record| provider_record.key.clone())
+            .collect();
+        for key in provided_keys.iter() {
+            self.swarm.behaviour_mut().kademlia.stop_providing(key);
+        }
+        debug!("Stopped providing {} artifact(s)", provided_keys.len());
+
+        let local_peer_id = *self.swarm.local_peer_id();
+        if let Err(e) = self.swarm.behaviour_mut().gossipsub.publish(
+            gossipsub::IdentTopic::new(LEAVE_TOPIC),
+            local_peer_id.to_bytes(),
+        ) {
+            debug!("Unable to publish leave notice: {:?}", e);
+        }
+
+        let grace_period = tokio::time::sleep(SHUTDOWN_GRACE_PERIOD);
+        tokio::pin!(grace_period);
+        loop {
+            tokio::select! {
+                _ = &mut grace_period => break,
+                event = self.swarm.select_next_some() => {
+                    trace!("Ignoring swarm event during shutdown: {:?}", event);
+                }
+            }
+        }
+
+        self.reconnect_peers.clear();
+        let connected_peers: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        for peer_id in connected_peers {
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+
+        self.stopped = true;
+    }
+
+    // Handles a leave notice of a peer that is shutting down, so that it
+    // is no longer selected for new queries.
+    fn handle_leave_notice(&mut self, source: Option<PeerId>, data: &[u8]) {
+        match (source, PeerId::from_bytes(data)) {
+            (Some(source), Ok(peer_id)) if source == peer_id => {
+                info!("Peer {} is leaving the p2p network", peer_id);
+                self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
+                self.advertised_metrics.remove(&peer_id);
+            }
+            _ => warn!("Ignoring invalid leave notice from {:?}", source),
+        }
+    }
+
+    // Handles a quality metric that a peer advertised, which is encoded
+    // like the idle metric of the idle metric exchange protocol.
+    fn handle_metric_advertisement(&mut self, source: Option<PeerId>, data: &[u8]) {
+        let quality_metric = <[u8; 8]>::try_from(data).map(f64::from_le_bytes);
+        match (source, quality_metric) {
+            (Some(peer_id), Ok(quality_metric))
+                if quality_metric.is_finite() && quality_metric >= 0_f64 =>
+            {
+                trace!(
+                    "Peer {} advertised quality metric {}",
+                    peer_id,
+                    quality_metric
+                );
+                self.advertised_metrics
+                    .insert(peer_id, (quality_metric, Instant::now()));
+            }
+            _ => warn!("Ignoring invalid metric advertisement from {:?}", source),
+        }
+    }
+
+    // The quality metric that a peer advertised, unless it is too old.
+    fn advertised_metric(&self, peer_id: &PeerId) -> Option<f64> {
+        self.advertised_metrics
+            .get(peer_id)
+            .filter(|(_, received_at)| received_at.elapsed() < ADVERTISED_METRIC_MAX_AGE)
+            .map(|(quality_metric, _)| *quality_metric)
+    }
+
+    // The details of a connected peer from what was learned about it on
+    // this node, including the quality metric it advertised last.
+    fn peer_details(&self, peer_id: &PeerId) -> PeerDetails {
+        let peer_identity = self.peer_identities.get(peer_id);
+        let peer_connection = self.peer_connections.get(peer_id);
+        PeerDetails {
+            peer_id: peer_id.to_string(),
+            agent_version: peer_identity.map(|i| i.agent_version.clone()),
+            protocol_version: peer_identity.map(|i| i.protocol_version.clone()),
+            protocols: peer_identity
+                .map(|i| i.protocols.clone())
+                .unwrap_or_default(),
+            listen_addrs: peer_identity
+                .map(|i| i.listen_addrs.iter().map(|a| a.to_string()).collect())
+                .unwrap_or_default(),
+            compatibility: peer_identity.map(|i| i.compatibility),
+            latency_ms: self
+                .peer_latencies
+                .get(peer_id)
+                .and_then(|rolling_rtt| rolling_rtt.average())
+                .map(|rtt| rtt.as_millis() as u64),
+            direction: peer_connection.map(|(direction, _)| *direction),
+            remote_addr: peer_connection.map(|(_, remote_addr)| remote_addr.to_string()),
+            transfer: self
+                .transfer_metrics
+                .peers
+                .get(&peer_id.to_string())
+                .cloned()
+                .unwrap_or_default(),
+            quality_metric: self.advertised_metric(peer_id),
+        }
+    }
+
+    // Send an informational event without waiting for the receiver. The
+    // event is dropped when the receiver is busy or gone.
+    fn notify(&self, event: PyrsiaEvent) {
+        if let Err(e) = self.event_sender.try_send(event) {
+            debug!("Dropped event: {}", e);
+        }
+    }
+
     // Handles events from the `GossipSub` network behaviour.
     async fn handle_gossipsub_event(&mut self, event: gossipsub::GossipsubEvent) {
         trace!("Handle GossipsubEvent: {:?}", event);
         if let gossipsub::GossipsubEvent::Message { message, .. } = event {
+            if message.topic == gossipsub::IdentTopic::new(LEAVE_TOPIC).hash() {
+                self.handle_leave_notice(message.source, &message.data);
+                return;
+            }
+            if message.topic == gossipsub::IdentTopic::new(METRICS_TOPIC).hash() {
+                self.handle_metric_advertisement(message.source, &message.data);
+                return;
+            }
+
             self.event_sender
                 .send(PyrsiaEvent::BlockchainRequest {
                     data: message.data,
@@ -180,7 +512,29 @@ impl PyrsiaEventLoop {
         trace!("Handle IdentifyEvent: {:?}", event);
         match event {
             identify::Event::Pushed { .. } => {}
-            identify::Event::Received { .. } => {}
+            identify::Event::Received { peer_id, info } => {
+                let peer_identity = PeerIdentity::from(info);
+                match peer_identity.compatibility {
+                    ProtocolCompatibility::Compatible => {}
+                    ProtocolCompatibility::Unknown => {
+                        warn!(
+                            "Peer {} ({}) does not advertise a pyrsia protocol version: {}",
+                            peer_id, peer_identity.agent_version, peer_identity.protocol_version
+                        );
+                    }
+                    ProtocolCompatibility::Incompatible => {
+                        warn!(
+                            "Disconnecting peer {} ({}) because it runs incompatible protocol version {}",
+                            peer_id, peer_identity.agent_version, peer_identity.protocol_version
+                        );
+                        self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
+                        if self.swarm.disconnect_peer_id(peer_id).is_err() {
+                            debug!("Peer {} was already disconnected", peer_id);
+                        }
+                    }
+                }
+                self.peer_identities.insert(peer_id, peer_identity);
+            }
             identify::Event::Sent { .. } => {}
             identify::Event::Error { .. } => {}
         }
@@ -243,6 +597,60 @@ impl PyrsiaEventLoop {
                         );
                     });
             }
+            KademliaEvent::OutboundQueryProgressed {
+                id,
+                result: QueryResult::PutRecord(result),
+                ..
+            } => {
+                if let Some(sender) = self.pending_put_record.remove(&id) {
+                    sender
+                        .send(result.map(|_| ()).map_err(|e| e.into()))
+                        .unwrap_or_else(|e| {
+                            error!(
+                                "Handle KademliaEvent match arm: {}. Error: {:?}",
+                                event_str, e
+                            );
+                        });
+                }
+            }
+            KademliaEvent::OutboundQueryProgressed {
+                id,
+                result:
+                    QueryResult::GetRecord(Ok(GetRecordOk::FoundRecord(PeerRecord { record, .. }))),
+                ..
+            } => {
+                if self.pending_get_signed_record.contains_key(&id) {
+                    self.handle_found_signed_record(id, record);
+                } else {
+                    self.handle_found_name_record(id, record);
+                }
+            }
+            KademliaEvent::OutboundQueryProgressed {
+                id,
+                result: QueryResult::GetRecord(_),
+                ..
+            } => {
+                if let Some(pending_resolve_name) = self.pending_resolve_name.remove(&id) {
+                    pending_resolve_name.sender.send(None).unwrap_or_else(|e| {
+                        error!(
+                            "Handle KademliaEvent match arm: {}. Error: {:?}",
+                            event_str, e
+                        );
+                    });
+                }
+                if let Some(pending_get_signed_record) = self.pending_get_signed_record.remove(&id)
+                {
+                    pending_get_signed_record
+                        .sender
+                        .send(None)
+                        .unwrap_or_else(|e| {
+                            error!(
+                                "Handle KademliaEvent match arm: {}. Error: {:?}",
+                                event_str, e
+                            );
+                        });
+                }
+            }
             KademliaEvent::OutboundQueryProgressed {
                 id,
                 result: QueryResult::Bootstrap(Ok(BootstrapOk { num_remaining, .. })),
@@ -281,6 +689,109 @@ impl PyrsiaEventLoop {
         }
     }
 
+    // Handles events from the `Ping` network behaviour.
+    async fn handle_ping_event(&mut self, event: ping::Event) {
+        trace!("Handle PingEvent: {:?}", event);
+        match event.result {
+            Ok(ping::Success::Ping { rtt }) => {
+                self.peer_latencies
+                    .entry(event.peer)
+                    .or_default()
+                    .add_sample(rtt);
+            }
+            Ok(ping::Success::Pong) => {}
+            Err(e) => {
+                debug!("Ping to peer {} failed: {}", event.peer, e);
+            }
+        }
+    }
+
+    // Handles a name record that was found while resolving a name. Records
+    // with an invalid signature, for another name or of a publisher that is
+    // not accepted are ignored, the first valid record finishes the query.
+    fn handle_found_name_record(&mut self, id: QueryId, record: Record) {
+        let (pending_name, publishers) = match self.pending_resolve_name.get(&id) {
+            Some(pending_resolve_name) => (
+                pending_resolve_name.name.clone(),
+                pending_resolve_name.publishers.clone(),
+            ),
+            None => return,
+        };
+
+        match NameRecord::from_bytes(&record.value) {
+            Ok(name_record) if !name_record.is_published_by(&publishers) => {
+                warn!(
+                    "Ignoring name record for {} published by {:?}: the publisher is not authorized",
+                    pending_name,
+                    name_record.publisher()
+                );
+            }
+            Ok(name_record) if name_record.name == pending_name && name_record.verify() => {
+                if let Some(pending_resolve_name) = self.pending_resolve_name.remove(&id) {
+                    pending_resolve_name
+                        .sender
+                        .send(Some(name_record))
+                        .unwrap_or_else(|e| {
+                            error!("Send resolved name record {:?}", e);
+                        });
+                }
+                if let Some(mut query) = self.swarm.behaviour_mut().kademlia.query_mut(&id) {
+                    query.finish();
+                }
+            }
+            Ok(name_record) => {
+                warn!(
+                    "Ignoring name record for {} published by {:?}: signature verification failed",
+                    pending_name,
+                    name_record.publisher()
+                );
+            }
+            Err(e) => {
+                warn!("Ignoring invalid name record for {}: {:?}", pending_name, e);
+            }
+        }
+    }
+
+    // Handles a signed document that was found while looking up a signed
+    // record. Documents with an invalid signature are ignored, the first
+    // valid document finishes the query.
+    fn handle_found_signed_record(&mut self, id: QueryId, record: Record) {
+        let pending_key = match self.pending_get_signed_record.get(&id) {
+            Some(pending_get_signed_record) => pending_get_signed_record.key.clone(),
+            None => return,
+        };
+
+        match SignedDocument::from_bytes(&record.value) {
+            Ok(document) if document.verify() => {
+                if let Some(pending_get_signed_record) = self.pending_get_signed_record.remove(&id)
+                {
+                    pending_get_signed_record
+                        .sender
+                        .send(Some(document))
+                        .unwrap_or_else(|e| {
+                            error!("Send signed record {:?}", e);
+                        });
+                }
+                if let Some(mut query) = self.swarm.behaviour_mut().kademlia.query_mut(&id) {
+                    query.finish();
+                }
+            }
+            Ok(document) => {
+                warn!(
+                    "Ignoring signed record for {} published by {:?}: signature verification failed",
+                    pending_key,
+                    document.publisher()
+                );
+            }
+            Err(e) => {
+                warn!(
+                    "Ignoring invalid signed record for {}: {:?}",
+                    pending_key, e
+                );
+            }
+        }
+    }
+
     // Handles events from the `RequestResponse` for artifact exchange
     // network behaviour.
     async fn handle_request_response_event(
@@ -288,15 +799,16 @@ impl PyrsiaEventLoop {
         event: RequestResponseEvent<ArtifactRequest, ArtifactResponse>,
     ) {
         trace!("Handle RequestResponseEvent: {:?}", event);
-        let event_str = format!("{:#?}", event);
         match event {
-            RequestResponseEvent::Message { message, .. } => match message {
+            RequestResponseEvent::Message { peer, message } => match message {
                 RequestResponseMessage::Request {
                     request, channel, ..
                 } => {
+                    Span::current().record("artifact_id", request.0.as_str());
                     self.event_sender
                         .send(PyrsiaEvent::RequestArtifact {
                             artifact_id: request.0,
+                            peer,
                             channel,
                         })
                         .await
@@ -306,28 +818,155 @@ impl PyrsiaEventLoop {
                     request_id,
                     response,
                 } => {
+                    let result = if response.is_busy() {
+                        debug!("Peer {} is too busy to send the artifact", peer);
+                        Err(PeerBusy.into())
+                    } else {
+                        self.transfer_metrics
+                            .record_fetched(&peer, response.0.len() as u64);
+                        Ok(response.0)
+                    };
+                    // the artifact is left out of the error, it can be large
                     self.pending_request_artifact
                         .remove(&request_id)
                         .expect("Request to still be pending.")
-                        .send(Ok(response.0))
-                        .unwrap_or_else(|e| {
+                        .send(result)
+                        .unwrap_or_else(|_| {
                             error!(
-                                "Handle RequestResponseEvent match arm: {}. Error: {:?}",
-                                event_str, e
+                                "Handle RequestResponseEvent match arm: the requester of artifact request {:?} to peer {} is gone",
+                                request_id, peer
                             );
                         });
                 }
             },
+            RequestResponseEvent::InboundFailure { peer, .. } => {
+                self.transfer_metrics.record_serve_failure(&peer);
+            }
+            RequestResponseEvent::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                self.transfer_metrics.record_fetch_failure(&peer);
+                self.pending_request_artifact
+                    .remove(&request_id)
+                    .expect("Request to still be pending.")
+                    .send(Err(error.into()))
+                    .unwrap_or_else(|e| {
+                        error!(
+                            "Handle RequestResponseEvent match arm: the requester of artifact request {:?} to peer {} is gone: {:?}",
+                            request_id, peer, e
+                        );
+                    });
+            }
+            RequestResponseEvent::ResponseSent { .. } => {}
+        }
+    }
+
+    // Handles events from the `RequestResponse` for artifact availability
+    // exchange network behaviour.
+    async fn handle_artifact_availability_request_response_event(
+        &mut self,
+        event: RequestResponseEvent<ArtifactAvailabilityRequest, ArtifactAvailabilityResponse>,
+    ) {
+        trace!(
+            "Handle ArtifactAvailabilityRequestResponseEvent: {:?}",
+            event
+        );
+        let event_str = format!("{:#?}", event);
+        match event {
+            RequestResponseEvent::Message { message, .. } => match message {
+                RequestResponseMessage::Request {
+                    request, channel, ..
+                } => {
+                    self.event_sender
+                        .send(PyrsiaEvent::RequestArtifactAvailability {
+                            artifact_id: request.0,
+                            channel,
+                        })
+                        .await
+                        .expect("Event receiver not to be dropped.");
+                }
+                RequestResponseMessage::Response {
+                    request_id,
+                    response,
+                } => {
+                    self.pending_request_artifact_availability
+                        .remove(&request_id)
+                        .expect("Request to still be pending.")
+                        .send(Ok(response.0))
+                        .unwrap_or_else(|e| {
+                            error!("Handle RequestResponseEvent match arm: {}. pending_request_artifact_availability: {:?}", event_str, e);
+                        });
+                }
+            },
             RequestResponseEvent::InboundFailure { .. } => {}
             RequestResponseEvent::OutboundFailure {
                 request_id, error, ..
             } => {
-                self.pending_request_artifact
+                self.pending_request_artifact_availability
                     .remove(&request_id)
                     .expect("Request to still be pending.")
                     .send(Err(error.into()))
                     .unwrap_or_else(|e| {
-                        error!("Handle RequestResponseEvent match arm: {}. pending_request_artifact: {:?}", event_str, e);
+                        error!("Handle RequestResponseEvent match arm: {}. pending_request_artifact_availability: {:?}", event_str, e);
+                    });
+            }
+            RequestResponseEvent::ResponseSent { .. } => {}
+        }
+    }
+
+    // Handles events from the `RequestResponse` for artifact replication
+    // exchange network behaviour.
+    async fn handle_artifact_replication_request_response_event(
+        &mut self,
+        event: RequestResponseEvent<ArtifactReplicationRequest, ArtifactReplicationResponse>,
+    ) {
+        trace!(
+            "Handle ArtifactReplicationRequestResponseEvent: {:?}",
+            event
+        );
+        match event {
+            RequestResponseEvent::Message { peer, message } => match message {
+                RequestResponseMessage::Request {
+                    request, channel, ..
+                } => {
+                    self.event_sender
+                        .send(PyrsiaEvent::RequestArtifactReplication {
+                            peer,
+                            package_type: request.package_type,
+                            package_specific_artifact_id: request.package_specific_artifact_id,
+                            artifact: request.artifact,
+                            channel,
+                        })
+                        .await
+                        .expect("Event receiver not to be dropped.");
+                }
+                RequestResponseMessage::Response {
+                    request_id,
+                    response,
+                } => {
+                    self.pending_request_artifact_replication
+                        .remove(&request_id)
+                        .expect("Request to still be pending.")
+                        .send(Ok(response.0))
+                        .unwrap_or_else(|e| {
+                            error!("Handle RequestResponseEvent match arm: the requester of replication request {:?} to peer {} is gone: {:?}", request_id, peer, e);
+                        });
+                }
+            },
+            RequestResponseEvent::InboundFailure { .. } => {}
+            RequestResponseEvent::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                self.pending_request_artifact_replication
+                    .remove(&request_id)
+                    .expect("Request to still be pending.")
+                    .send(Err(error.into()))
+                    .unwrap_or_else(|e| {
+                        error!("Handle RequestResponseEvent match arm: the requester of replication request {:?} to peer {} is gone: {:?}", request_id, peer, e);
                     });
             }
             RequestResponseEvent::ResponseSent { .. } => {}
@@ -379,6 +1018,51 @@ impl PyrsiaEventLoop {
         }
     }
 
+    // Handles events from the `RequestResponse` for benchmark exchange
+    // network behaviour. Incoming requests are answered right away with
+    // synthetic data, without involving the rest of the node.
+    fn handle_bench_request_response_event(
+        &mut self,
+        event: RequestResponseEvent<BenchRequest, BenchResponse>,
+    ) {
+        trace!("Handle BenchRequestResponseEvent: {:?}", event);
+        match event {
+            RequestResponseEvent::Message { message, .. } => match message {
+                RequestResponseMessage::Request {
+                    request, channel, ..
+                } => {
+                    let download_size = request.download_size.min(MAX_BENCH_PAYLOAD_SIZE);
+                    if self
+                        .swarm
+                        .behaviour_mut()
+                        .bench_request_response
+                        .send_response(channel, BenchResponse(vec![0; download_size as usize]))
+                        .is_err()
+                    {
+                        debug!("Connection closed before the bench response could be sent");
+                    }
+                }
+                RequestResponseMessage::Response {
+                    request_id,
+                    response,
+                } => {
+                    if let Some(sender) = self.pending_bench_requests.remove(&request_id) {
+                        let _ = sender.send(Ok(response.0.len() as u64));
+                    }
+                }
+            },
+            RequestResponseEvent::InboundFailure { .. } => {}
+            RequestResponseEvent::OutboundFailure {
+                request_id, error, ..
+            } => {
+                if let Some(sender) = self.pending_bench_requests.remove(&request_id) {
+                    let _ = sender.send(Err(error.into()));
+                }
+            }
+            RequestResponseEvent::ResponseSent { .. } => {}
+        }
+    }
+
     // Handles events from the `RequestResponse` for build exchange
     // network behaviour.
     async fn handle_build_request_response_event(
@@ -562,8 +1246,26 @@ impl PyrsiaEventLoop {
                 );
             }
             SwarmEvent::ConnectionEstablished {
-                peer_id, endpoint, ..
+                peer_id,
+                endpoint,
+                num_established,
+                ..
             } => {
+                if let Some(reconnect) = self.reconnect_peers.get_mut(&peer_id) {
+                    reconnect.connected();
+                }
+                if num_established.get() == 1 {
+                    let direction = if endpoint.is_dialer() {
+                        ConnectionDirection::Outbound
+                    } else {
+                        ConnectionDirection::Inbound
+                    };
+                    self.peer_connections.insert(
+                        peer_id,
+                        (direction, endpoint.get_remote_address().to_owned()),
+                    );
+                    self.notify(PyrsiaEvent::PeerConnected { peer: peer_id });
+                }
                 if endpoint.is_dialer() {
                     if let Some(sender) = self.pending_dial.remove(&peer_id) {
                         self.swarm
@@ -577,12 +1279,39 @@ impl PyrsiaEventLoop {
                     }
                 }
             }
-            SwarmEvent::ConnectionClosed { .. } => {}
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established,
+                ..
+            } => {
+                if num_established == 0 {
+                    self.notify(PyrsiaEvent::PeerDisconnected { peer: peer_id });
+                    self.peer_connections.remove(&peer_id);
+                    self.peer_latencies.remove(&peer_id);
+                    self.peer_identities.remove(&peer_id);
+                    self.advertised_metrics.remove(&peer_id);
+                    self.transfer_metrics.remove_peer(&peer_id);
+                    if let Some(reconnect) = self.reconnect_peers.get_mut(&peer_id) {
+                        let delay = reconnect.schedule(Instant::now());
+                        info!(
+                            "Lost connection to peer {}, reconnecting in {:?}",
+                            peer_id, delay
+                        );
+                    }
+                }
+            }
             SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
                 if let Some(peer_id) = peer_id {
                     if peer_id == *self.swarm.local_peer_id() {
                         warn!("The dialed node has the same peer ID as the current node: '{}'. Please make sure that every node has a unique peer ID.", peer_id);
                     }
+                    if let Some(reconnect) = self.reconnect_peers.get_mut(&peer_id) {
+                        let delay = reconnect.schedule(Instant::now());
+                        info!(
+                            "Failed to connect to peer {}, retrying in {:?}",
+                            peer_id, delay
+                        );
+                    }
                     if let Some(sender) = self.pending_dial.remove(&peer_id) {
                         sender.send(Err(error.into())).unwrap_or_else(|_e| {
                             error!("Handle SwarmEvent match arm: {}", event_str);
@@ -669,12 +1398,72 @@ impl PyrsiaEventLoop {
                     }
                 }
             }
+            Command::KeepConnected { peer_id, peer_addr } => {
+                self.reconnect_peers
+                    .entry(peer_id)
+                    .or_insert_with(|| Reconnect::new(peer_addr));
+            }
+            Command::DisconnectPeer {
+                peer_id,
+                ban,
+                sender,
+            } => {
+                self.reconnect_peers.remove(&peer_id);
+                let result = if ban {
+                    info!("Banning peer {}", peer_id);
+                    self.swarm.ban_peer_id(peer_id);
+                    Ok(())
+                } else {
+                    self.swarm
+                        .disconnect_peer_id(peer_id)
+                        .map_err(|_| anyhow::anyhow!("Peer {} is not connected", peer_id))
+                };
+                sender.send(result).unwrap_or_else(|_e| {
+                    error!("Handle Command match arm: {}.", command_str);
+                });
+            }
             Command::ListPeers { sender } => {
                 let peers = HashSet::from_iter(self.swarm.connected_peers().copied());
                 sender.send(peers).unwrap_or_else(|_e| {
                     error!("Handle Command match arm: {}.", command_str);
                 });
             }
+            Command::ListPeerLatencies { sender } => {
+                let latencies = self
+                    .peer_latencies
+                    .iter()
+                    .filter_map(|(peer_id, rolling_rtt)| {
+                        rolling_rtt.average().map(|rtt| (*peer_id, rtt))
+                    })
+                    .collect();
+                sender.send(latencies).unwrap_or_else(|_e| {
+                    error!("Handle Command match arm: {}.", command_str);
+                });
+            }
+            Command::GetPeerDetails { peer_id, sender } => {
+                let peer_details = if self.swarm.is_connected(&peer_id) {
+                    Some(self.peer_details(&peer_id))
+                } else {
+                    None
+                };
+                sender.send(peer_details).unwrap_or_else(|_e| {
+                    error!("Handle Command match arm: {}.", command_str);
+                });
+            }
+            Command::ListPeerDetails { sender } => {
+                let peer_details = self
+                    .swarm
+                    .connected_peers()
+                    .map(|peer_id| self.peer_details(peer_id))
+                    .collect();
+                sender.send(peer_details).unwrap_or_else(|_e| {
+                    error!("Handle Command match arm: {}.", command_str);
+                });
+            }
+            Command::Shutdown { sender } => {
+                self.shutdown().await;
+                let _ = sender.send(());
+            }
             Command::Status { sender } => {
                 let swarm = &self.swarm;
                 let local_peer_id = *swarm.local_peer_id();
@@ -705,37 +1494,141 @@ impl PyrsiaEventLoop {
 
                 let peer_addrs = addr_map.into_iter().collect::<Vec<_>>();
 
+                let nat_status = match swarm.behaviour().auto_nat.nat_status() {
+                    NatStatus::Public(_) => "public",
+                    NatStatus::Private => "private",
+                    NatStatus::Unknown => "unknown",
+                };
+
                 let status = Status {
                     peers_count: swarm.connected_peers().count(),
                     peer_id: local_peer_id.to_string(),
                     peer_addrs,
+                    transfer_metrics: self.transfer_metrics.clone(),
+                    nat_status: nat_status.to_owned(),
+                    ..Default::default()
                 };
 
                 sender.send(status).unwrap();
             }
-            Command::Provide {
-                artifact_id,
+            Command::Provide {
+                artifact_id,
+                sender,
+            } => {
+                let query_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .start_providing(artifact_id.into_bytes().into())
+                    .expect("No store error.");
+                self.pending_start_providing.insert(query_id, sender);
+            }
+            Command::StopProviding {
+                artifact_id,
+                sender,
+            } => {
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .stop_providing(&artifact_id.into_bytes().into());
+                sender.send(()).unwrap_or_else(|_e| {
+                    error!("Handle Command match arm: {}.", command_str);
+                });
+            }
+            Command::ListProviders {
+                artifact_id,
+                sender,
+            } => {
+                let query_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .get_providers(artifact_id.into_bytes().into());
+                self.pending_list_providers
+                    .insert(query_id, PendingListProviders::new(sender));
+            }
+            Command::PutNameRecord {
+                name,
+                artifact_id,
+                artifact_hash,
+                sender,
+            } => {
+                let value =
+                    NameRecord::new(self.signer.as_ref(), &name, &artifact_id, &artifact_hash)
+                        .and_then(|name_record| name_record.to_bytes());
+                match value {
+                    Ok(value) => {
+                        match self
+                            .swarm
+                            .behaviour_mut()
+                            .kademlia
+                            .put_record(Record::new(NameRecord::key(&name), value), Quorum::One)
+                        {
+                            Ok(query_id) => {
+                                self.pending_put_record.insert(query_id, sender);
+                            }
+                            Err(e) => {
+                                let _ = sender.send(Err(e.into()));
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                    }
+                }
+            }
+            Command::SignDocument { content, sender } => {
+                let _ = sender.send(SignedDocument::new(self.signer.as_ref(), &content));
+            }
+            Command::PutSignedRecord {
+                key,
+                document,
                 sender,
-            } => {
+            } => match document.to_bytes() {
+                Ok(value) => {
+                    match self.swarm.behaviour_mut().kademlia.put_record(
+                        Record::new(SignedDocument::record_key(&key), value),
+                        Quorum::One,
+                    ) {
+                        Ok(query_id) => {
+                            self.pending_put_record.insert(query_id, sender);
+                        }
+                        Err(e) => {
+                            let _ = sender.send(Err(e.into()));
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                }
+            },
+            Command::GetSignedRecord { key, sender } => {
                 let query_id = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
-                    .start_providing(artifact_id.into_bytes().into())
-                    .expect("No store error.");
-                self.pending_start_providing.insert(query_id, sender);
+                    .get_record(SignedDocument::record_key(&key));
+                self.pending_get_signed_record
+                    .insert(query_id, PendingGetSignedRecord { key, sender });
             }
-            Command::ListProviders {
-                artifact_id,
+            Command::ResolveName {
+                name,
+                publishers,
                 sender,
             } => {
                 let query_id = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
-                    .get_providers(artifact_id.into_bytes().into());
-                self.pending_list_providers
-                    .insert(query_id, PendingListProviders::new(sender));
+                    .get_record(NameRecord::key(&name));
+                self.pending_resolve_name.insert(
+                    query_id,
+                    PendingResolveName {
+                        name,
+                        publishers,
+                        sender,
+                    },
+                );
             }
             Command::RequestBuild {
                 peer,
@@ -771,20 +1664,101 @@ impl PyrsiaEventLoop {
                     .send_request(&peer, ArtifactRequest(artifact_id));
                 self.pending_request_artifact.insert(request_id, sender);
             }
-            Command::RespondArtifact { artifact, channel } => {
-                self.swarm
+            Command::RespondArtifact {
+                peer,
+                artifact,
+                channel,
+            } => {
+                let bytes = artifact.len() as u64;
+                match self
+                    .swarm
                     .behaviour_mut()
                     .request_response
                     .send_response(channel, ArtifactResponse(artifact))
+                {
+                    Ok(()) => self.transfer_metrics.record_served(&peer, bytes),
+                    Err(_) => {
+                        warn!(
+                            "Unable to send artifact to peer {}, the connection was closed",
+                            peer
+                        );
+                        self.transfer_metrics.record_serve_failure(&peer);
+                    }
+                }
+            }
+            Command::RespondArtifactBusy { channel } => {
+                self.swarm
+                    .behaviour_mut()
+                    .request_response
+                    .send_response(channel, ArtifactResponse::busy())
                     .expect("Connection to peer to be still open.");
             }
-            Command::RequestIdleMetric { peer, sender } => {
+            Command::RequestArtifactAvailability {
+                artifact_id,
+                peer,
+                sender,
+            } => {
                 let request_id = self
                     .swarm
                     .behaviour_mut()
-                    .idle_metric_request_response
-                    .send_request(&peer, IdleMetricRequest());
-                self.pending_idle_metric_requests.insert(request_id, sender);
+                    .artifact_availability_request_response
+                    .send_request(&peer, ArtifactAvailabilityRequest(artifact_id));
+                self.pending_request_artifact_availability
+                    .insert(request_id, sender);
+            }
+            Command::RequestArtifactReplication {
+                peer,
+                package_type,
+                package_specific_artifact_id,
+                artifact,
+                sender,
+            } => {
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .artifact_replication_request_response
+                    .send_request(
+                        &peer,
+                        ArtifactReplicationRequest {
+                            package_type,
+                            package_specific_artifact_id,
+                            artifact,
+                        },
+                    );
+                self.pending_request_artifact_replication
+                    .insert(request_id, sender);
+            }
+            Command::RespondArtifactReplication { accepted, channel } => {
+                self.swarm
+                    .behaviour_mut()
+                    .artifact_replication_request_response
+                    .send_response(channel, ArtifactReplicationResponse(accepted))
+                    .expect("Connection to peer to be still open.");
+            }
+            Command::RespondArtifactAvailability { size, channel } => {
+                self.swarm
+                    .behaviour_mut()
+                    .artifact_availability_request_response
+                    .send_response(channel, ArtifactAvailabilityResponse(size))
+                    .expect("Connection to peer to be still open.");
+            }
+            Command::RequestIdleMetric { peer, sender } => {
+                // a recently advertised metric saves a round trip to the peer
+                if let Some(quality_metric) = self.advertised_metric(&peer) {
+                    let peer_metrics = PeerMetrics {
+                        idle_metric: quality_metric.to_le_bytes(),
+                    };
+                    sender.send(Ok(peer_metrics)).unwrap_or_else(|_e| {
+                        error!("Handle Command match arm: {}.", command_str);
+                    });
+                } else {
+                    let request_id = self
+                        .swarm
+                        .behaviour_mut()
+                        .idle_metric_request_response
+                        .send_request(&peer, IdleMetricRequest());
+                    self.pending_idle_metric_requests.insert(request_id, sender);
+                }
             }
             Command::RespondIdleMetric { metric, channel } => {
                 self.swarm
@@ -793,6 +1767,25 @@ impl PyrsiaEventLoop {
                     .send_response(channel, IdleMetricResponse(metric))
                     .expect("Connection to peer to be still open.");
             }
+            Command::RequestBench {
+                peer,
+                download_size,
+                payload,
+                sender,
+            } => {
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .bench_request_response
+                    .send_request(
+                        &peer,
+                        BenchRequest {
+                            download_size,
+                            payload,
+                        },
+                    );
+                self.pending_bench_requests.insert(request_id, sender);
+            }
             Command::RequestBlockchain { data, peer, sender } => {
                 let request_id = self
                     .swarm
@@ -808,6 +1801,26 @@ impl PyrsiaEventLoop {
                     .send_response(channel, BlockchainResponse(data))
                     .expect("Connection to peer to be still open.");
             }
+            Command::AdvertiseQualityMetric {
+                quality_metric,
+                sender,
+            } => {
+                sender
+                    .send(
+                        self.swarm
+                            .behaviour_mut()
+                            .gossipsub
+                            .publish(
+                                gossipsub::IdentTopic::new(METRICS_TOPIC),
+                                quality_metric.to_le_bytes(),
+                            )
+                            .map(|_| ())
+                            .map_err(|e| e.into()),
+                    )
+                    .unwrap_or_else(|_e| {
+                        error!("Handle Command match arm: {}.", command_str);
+                    });
+            }
             Command::BroadcastBlock {
                 topic,
                 block,
@@ -851,12 +1864,74 @@ impl PyrsiaEventLoop {
     }
 }
 
+// The span that an event of the swarm is handled in, which names the peer
+// that the event concerns.
+fn swarm_event_span<E>(event: &SwarmEvent<PyrsiaNetworkEvent, E>) -> Span {
+    let peer_id = match event {
+        SwarmEvent::Behaviour(behaviour_event) => match behaviour_event {
+            PyrsiaNetworkEvent::Identify(identify_event) => match identify_event.as_ref() {
+                identify::Event::Received { peer_id, .. } => Some(*peer_id),
+                _ => None,
+            },
+            PyrsiaNetworkEvent::Ping(ping_event) => Some(ping_event.peer),
+            PyrsiaNetworkEvent::RequestResponse(event) => Some(request_response_peer(event)),
+            PyrsiaNetworkEvent::ArtifactAvailabilityRequestResponse(event) => {
+                Some(request_response_peer(event))
+            }
+            PyrsiaNetworkEvent::ArtifactReplicationRequestResponse(event) => {
+                Some(request_response_peer(event))
+            }
+            PyrsiaNetworkEvent::BuildRequestResponse(event) => Some(request_response_peer(event)),
+            PyrsiaNetworkEvent::IdleMetricRequestResponse(event) => {
+                Some(request_response_peer(event))
+            }
+            PyrsiaNetworkEvent::BlockchainRequestResponse(event) => {
+                Some(request_response_peer(event))
+            }
+            PyrsiaNetworkEvent::BuildStatusRequestResponse(event) => {
+                Some(request_response_peer(event))
+            }
+            PyrsiaNetworkEvent::BenchRequestResponse(event) => Some(request_response_peer(event)),
+            _ => None,
+        },
+        SwarmEvent::ConnectionEstablished { peer_id, .. }
+        | SwarmEvent::ConnectionClosed { peer_id, .. } => Some(*peer_id),
+        SwarmEvent::OutgoingConnectionError { peer_id, .. } => *peer_id,
+        _ => None,
+    };
+    match peer_id {
+        Some(peer_id) => tracing::info_span!("p2p", peer_id = %peer_id, artifact_id = Empty),
+        None => Span::none(),
+    }
+}
+
+fn request_response_peer<Req, Res>(event: &RequestResponseEvent<Req, Res>) -> PeerId {
+    match event {
+        RequestResponseEvent::Message { peer, .. }
+        | RequestResponseEvent::OutboundFailure { peer, .. }
+        | RequestResponseEvent::InboundFailure { peer, .. }
+        | RequestResponseEvent::ResponseSent { peer, .. } => *peer,
+    }
+}
+
 #[derive(Debug)]
 pub enum PyrsiaEvent {
     RequestArtifact {
         artifact_id: String,
+        peer: PeerId,
         channel: ResponseChannel<ArtifactResponse>,
     },
+    RequestArtifactAvailability {
+        artifact_id: String,
+        channel: ResponseChannel<ArtifactAvailabilityResponse>,
+    },
+    RequestArtifactReplication {
+        peer: PeerId,
+        package_type: PackageType,
+        package_specific_artifact_id: String,
+        artifact: Vec<u8>,
+        channel: ResponseChannel<ArtifactReplicationResponse>,
+    },
     RequestBuild {
         package_type: PackageType,
         package_specific_id: String,
@@ -873,13 +1948,26 @@ pub enum PyrsiaEvent {
         build_id: String,
         channel: ResponseChannel<BuildStatusResponse>,
     },
+    PeerConnected {
+        peer: PeerId,
+    },
+    PeerDisconnected {
+        peer: PeerId,
+    },
 }
 
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
+    use crate::network::artifact_availability_protocol::{
+        ArtifactAvailabilityExchangeCodec, ArtifactAvailabilityExchangeProtocol,
+    };
     use crate::network::artifact_protocol::{ArtifactExchangeCodec, ArtifactExchangeProtocol};
+    use crate::network::artifact_replication_protocol::{
+        ArtifactReplicationExchangeCodec, ArtifactReplicationExchangeProtocol,
+    };
+    use crate::network::bench_protocol::{BenchExchangeCodec, BenchExchangeProtocol};
     use crate::network::blockchain_protocol::{
         BlockchainExchangeCodec, BlockchainExchangeProtocol,
     };
@@ -892,6 +1980,7 @@ mod tests {
     use crate::network::idle_metric_protocol::{
         IdleMetricExchangeCodec, IdleMetricExchangeProtocol,
     };
+    use crate::network::peer_identity::{pyrsia_agent_version, PYRSIA_PROTOCOL_VERSION};
     use libp2p::core::upgrade;
     use libp2p::core::Transport;
     use libp2p::dns::TokioDnsConfig;
@@ -899,7 +1988,7 @@ mod tests {
     use libp2p::identity::Keypair;
     use libp2p::swarm::SwarmBuilder;
     use libp2p::yamux::YamuxConfig;
-    use libp2p::{autonat, identify, kad, noise, request_response, tcp};
+    use libp2p::{autonat, identify, kad, noise, ping, request_response, tcp};
     use std::iter;
     use std::time::Duration;
     use tokio_stream::wrappers::ReceiverStream;
@@ -953,6 +2042,12 @@ mod tests {
         gossip_sub
             .subscribe(&pyrsia_topic)
             .expect("Could not connect to pyrsia blockchain topic");
+        gossip_sub
+            .subscribe(&Topic::new(LEAVE_TOPIC))
+            .expect("Could not connect to pyrsia leave topic");
+        gossip_sub
+            .subscribe(&Topic::new(METRICS_TOPIC))
+            .expect("Could not connect to pyrsia metrics topic");
 
         let behaviour = PyrsiaNetworkBehaviour {
             auto_nat: autonat::Behaviour::new(
@@ -966,11 +2061,12 @@ mod tests {
                 },
             ),
             gossipsub: gossip_sub,
-            identify: identify::Behaviour::new(identify::Config::new(
-                "ipfs/1.0.0".to_owned(),
-                id_keys.public(),
-            )),
+            identify: identify::Behaviour::new(
+                identify::Config::new(PYRSIA_PROTOCOL_VERSION.to_owned(), id_keys.public())
+                    .with_agent_version(pyrsia_agent_version()),
+            ),
             kademlia: kad::Kademlia::new(peer_id, kad::record::store::MemoryStore::new(peer_id)),
+            ping: ping::Behaviour::new(ping::Config::new()),
             request_response: request_response::RequestResponse::new(
                 ArtifactExchangeCodec(),
                 iter::once((
@@ -979,6 +2075,22 @@ mod tests {
                 )),
                 Default::default(),
             ),
+            artifact_availability_request_response: request_response::RequestResponse::new(
+                ArtifactAvailabilityExchangeCodec(),
+                iter::once((
+                    ArtifactAvailabilityExchangeProtocol(),
+                    request_response::ProtocolSupport::Full,
+                )),
+                Default::default(),
+            ),
+            artifact_replication_request_response: request_response::RequestResponse::new(
+                ArtifactReplicationExchangeCodec(),
+                iter::once((
+                    ArtifactReplicationExchangeProtocol(),
+                    request_response::ProtocolSupport::Full,
+                )),
+                Default::default(),
+            ),
             build_request_response: request_response::RequestResponse::new(
                 BuildExchangeCodec(),
                 iter::once((
@@ -1011,6 +2123,14 @@ mod tests {
                 )),
                 Default::default(),
             ),
+            bench_request_response: request_response::RequestResponse::new(
+                BenchExchangeCodec(),
+                iter::once((
+                    BenchExchangeProtocol(),
+                    request_response::ProtocolSupport::Full,
+                )),
+                Default::default(),
+            ),
         };
 
         let swarm = SwarmBuilder::with_tokio_executor(
@@ -1024,7 +2144,8 @@ mod tests {
         let (event_sender, event_receiver) = mpsc::channel(1);
 
         let p2p_client = Client::new(command_sender, peer_id, IdentTopic::new("pyrsia-topic"));
-        let event_loop = PyrsiaEventLoop::new(swarm, command_receiver, event_sender);
+        let event_loop =
+            PyrsiaEventLoop::new(swarm, Arc::new(id_keys), command_receiver, event_sender);
 
         (p2p_client, event_loop, ReceiverStream::new(event_receiver))
     }
@@ -1102,6 +2223,36 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_keep_connected_reconnects_after_failed_dial() {
+        let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
+        let (mut p2p_client_2, event_loop_2, _) = create_test_swarm();
+
+        tokio::spawn(event_loop_1.run());
+        tokio::spawn(event_loop_2.run());
+
+        p2p_client_2
+            .listen(&"/ip4/127.0.0.1/tcp/44161".parse().unwrap())
+            .await
+            .unwrap();
+
+        let peer_1_address: Multiaddr = "/ip4/127.0.0.1/tcp/44160".parse().unwrap();
+        p2p_client_2
+            .keep_connected(&p2p_client_1.local_peer_id, &peer_1_address)
+            .await
+            .unwrap();
+        let result_dial = p2p_client_2
+            .dial(&p2p_client_1.local_peer_id, &peer_1_address)
+            .await;
+        assert!(result_dial.is_err());
+
+        p2p_client_1.listen(&peer_1_address).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(4)).await;
+
+        let peers = p2p_client_2.list_peers().await.unwrap();
+        assert!(peers.contains(&p2p_client_1.local_peer_id));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_request_build_loop() {
         let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
@@ -1208,4 +2359,164 @@ mod tests {
         expected_providers.insert(p2p_client_1.local_peer_id);
         assert_eq!(expected_providers, result_list_providers.unwrap());
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolve_name_with_interconnected_peer() {
+        let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
+        let (mut p2p_client_2, event_loop_2, _) = create_test_swarm();
+
+        tokio::spawn(event_loop_1.run());
+        tokio::spawn(event_loop_2.run());
+
+        p2p_client_1
+            .listen(&"/ip4/127.0.0.1/tcp/44170".parse().unwrap())
+            .await
+            .unwrap();
+        p2p_client_2
+            .listen(&"/ip4/127.0.0.1/tcp/44171".parse().unwrap())
+            .await
+            .unwrap();
+
+        let result_peer_2_dial_peer_1 = p2p_client_2
+            .dial(
+                &p2p_client_1.local_peer_id,
+                &"/ip4/127.0.0.1/tcp/44170".parse().unwrap(),
+            )
+            .await;
+        assert!(result_peer_2_dial_peer_1.is_ok());
+
+        let result_put = p2p_client_1
+            .put_name_record("Docker/alpine:3.16", "artifact_id", "artifact_hash")
+            .await;
+        assert!(result_put.is_ok());
+
+        let name_record = p2p_client_2
+            .resolve_name("Docker/alpine:3.16")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(name_record.artifact_id, "artifact_id");
+        assert_eq!(name_record.artifact_hash, "artifact_hash");
+        assert_eq!(name_record.publisher(), Some(p2p_client_1.local_peer_id));
+
+        let unknown_name_record = p2p_client_2.resolve_name("Docker/unknown:1.0").await;
+        assert!(unknown_name_record.unwrap().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_request_bench_with_connected_peer() {
+        let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
+        let (mut p2p_client_2, event_loop_2, _) = create_test_swarm();
+
+        tokio::spawn(event_loop_1.run());
+        tokio::spawn(event_loop_2.run());
+
+        p2p_client_1
+            .listen(&"/ip4/127.0.0.1/tcp/44190".parse().unwrap())
+            .await
+            .unwrap();
+        p2p_client_2
+            .dial(
+                &p2p_client_1.local_peer_id,
+                &"/ip4/127.0.0.1/tcp/44190".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let received = p2p_client_2
+            .request_bench(&p2p_client_1.local_peer_id, 1024, 4096)
+            .await
+            .unwrap();
+        assert_eq!(received, 4096);
+
+        let received = p2p_client_2
+            .request_bench(
+                &p2p_client_1.local_peer_id,
+                0,
+                MAX_BENCH_PAYLOAD_SIZE + 1024,
+            )
+            .await
+            .unwrap();
+        assert_eq!(received, MAX_BENCH_PAYLOAD_SIZE);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_advertise_quality_metric_to_connected_peer() {
+        let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
+        let (mut p2p_client_2, event_loop_2, _) = create_test_swarm();
+
+        tokio::spawn(event_loop_1.run());
+        tokio::spawn(event_loop_2.run());
+
+        p2p_client_1
+            .listen(&"/ip4/127.0.0.1/tcp/44200".parse().unwrap())
+            .await
+            .unwrap();
+        p2p_client_2
+            .dial(
+                &p2p_client_1.local_peer_id,
+                &"/ip4/127.0.0.1/tcp/44200".parse().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // the advertisement fails until the peers exchanged their subscriptions
+        let started_at = Instant::now();
+        let quality_metric = loop {
+            assert!(started_at.elapsed() < Duration::from_secs(10));
+            let _ = p2p_client_1.advertise_quality_metric(0.25).await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let peer_details = p2p_client_2
+                .get_peer_details(&p2p_client_1.local_peer_id)
+                .await
+                .unwrap()
+                .unwrap();
+            if let Some(quality_metric) = peer_details.quality_metric {
+                break quality_metric;
+            }
+        };
+        assert_eq!(quality_metric, 0.25);
+
+        let idle_metric = p2p_client_2
+            .request_idle_metric(&p2p_client_1.local_peer_id)
+            .await
+            .unwrap();
+        assert_eq!(idle_metric, 0.25);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_stops_event_loop_and_disconnects_peers() {
+        let (mut p2p_client_1, event_loop_1, _) = create_test_swarm();
+        let (mut p2p_client_2, event_loop_2, _) = create_test_swarm();
+
+        tokio::spawn(event_loop_1.run());
+        tokio::spawn(event_loop_2.run());
+
+        p2p_client_1
+            .listen(&"/ip4/127.0.0.1/tcp/44180".parse().unwrap())
+            .await
+            .unwrap();
+        p2p_client_2
+            .listen(&"/ip4/127.0.0.1/tcp/44181".parse().unwrap())
+            .await
+            .unwrap();
+
+        let result_peer_2_dial_peer_1 = p2p_client_2
+            .dial(
+                &p2p_client_1.local_peer_id,
+                &"/ip4/127.0.0.1/tcp/44180".parse().unwrap(),
+            )
+            .await;
+        assert!(result_peer_2_dial_peer_1.is_ok());
+
+        p2p_client_1.provide("artifact_id").await.unwrap();
+
+        let result_shutdown = p2p_client_1.shutdown().await;
+        assert!(result_shutdown.is_ok());
+        assert!(p2p_client_1.status().await.is_err());
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let peers = p2p_client_2.list_peers().await.unwrap();
+        assert!(!peers.contains(&p2p_client_1.local_peer_id));
+    }
 }
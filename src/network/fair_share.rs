@@ -0,0 +1,194 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+//! Fair Share
+//!
+//! The bytes served to each requesting peer are tracked over a rolling
+//! window, so that one greedy peer, like a mirror that pulls everything,
+//! cannot starve the other peers. A peer that was served a disproportionate
+//! share is asked to request its artifacts elsewhere until its share drops.
+
+use libp2p::PeerId;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The period over which the bytes served to the peers are compared.
+pub const FAIR_SHARE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// The number of bytes a peer has to be served in the window before it is
+/// throttled, so that small transfers are never refused.
+pub const MIN_THROTTLED_BYTES: u64 = 64 * 1024 * 1024;
+
+// The bytes served to each peer in the window, oldest first.
+type ServedBytes = HashMap<PeerId, VecDeque<(Instant, u64)>>;
+
+/// Decides which artifact requests of other peers are served. A peer is
+/// throttled when it was served more than `factor` times the average of
+/// the other peers that were served in the window. Shared by all clones.
+#[derive(Clone, Default)]
+pub struct FairShare {
+    factor: f64,
+    served: Arc<Mutex<ServedBytes>>,
+}
+
+impl FairShare {
+    /// Throttle peers that get more than `factor` times the average share
+    /// of the other peers. Peers are never throttled when `factor` is 0.
+    pub fn new(factor: f64) -> Self {
+        FairShare {
+            factor,
+            ..Default::default()
+        }
+    }
+
+    /// Records that an artifact of `bytes` length was served to `peer`.
+    pub fn record_served(&self, peer: &PeerId, bytes: u64) {
+        self.record_served_at(peer, bytes, Instant::now());
+    }
+
+    /// Whether a request of `peer` should be served now.
+    pub fn admit(&self, peer: &PeerId) -> bool {
+        self.admit_at(peer, Instant::now())
+    }
+
+    /// The bytes served to each peer in the window.
+    pub fn served_in_window(&self) -> HashMap<PeerId, u64> {
+        let mut served = self.served.lock().unwrap();
+        prune(&mut served, Instant::now());
+        totals(&served)
+    }
+
+    fn record_served_at(&self, peer: &PeerId, bytes: u64, at: Instant) {
+        let mut served = self.served.lock().unwrap();
+        prune(&mut served, at);
+        served.entry(*peer).or_default().push_back((at, bytes));
+    }
+
+    fn admit_at(&self, peer: &PeerId, now: Instant) -> bool {
+        if self.factor <= 0_f64 {
+            return true;
+        }
+        let mut served = self.served.lock().unwrap();
+        prune(&mut served, now);
+        let totals = totals(&served);
+
+        let peer_bytes = totals.get(peer).copied().unwrap_or_default();
+        if peer_bytes < MIN_THROTTLED_BYTES {
+            return true;
+        }
+        let others: Vec<u64> = totals
+            .iter()
+            .filter(|(other, _)| *other != peer)
+            .map(|(_, bytes)| *bytes)
+            .collect();
+        if others.is_empty() {
+            // nobody else is waiting for this node
+            return true;
+        }
+        let average = others.iter().sum::<u64>() as f64 / others.len() as f64;
+        peer_bytes as f64 <= self.factor * average
+    }
+}
+
+// Forget what was served before the window.
+fn prune(served: &mut ServedBytes, now: Instant) {
+    served.retain(|_, transfers| {
+        while let Some((at, _)) = transfers.front() {
+            if now.duration_since(*at) < FAIR_SHARE_WINDOW {
+                break;
+            }
+            transfers.pop_front();
+        }
+        !transfers.is_empty()
+    });
+}
+
+fn totals(served: &ServedBytes) -> HashMap<PeerId, u64> {
+    served
+        .iter()
+        .map(|(peer, transfers)| (*peer, transfers.iter().map(|(_, bytes)| bytes).sum()))
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit_greedy_peer_without_other_peers() {
+        let fair_share = FairShare::new(2.0);
+        let greedy_peer = PeerId::random();
+        fair_share.record_served(&greedy_peer, 10 * MIN_THROTTLED_BYTES);
+
+        assert!(fair_share.admit(&greedy_peer));
+    }
+
+    #[test]
+    fn test_throttle_greedy_peer() {
+        let fair_share = FairShare::new(2.0);
+        let greedy_peer = PeerId::random();
+        let other_peer = PeerId::random();
+        fair_share.record_served(&greedy_peer, 3 * MIN_THROTTLED_BYTES);
+        fair_share.record_served(&other_peer, MIN_THROTTLED_BYTES);
+
+        assert!(!fair_share.admit(&greedy_peer));
+        assert!(fair_share.admit(&other_peer));
+        assert!(fair_share.admit(&PeerId::random()));
+
+        fair_share.record_served(&other_peer, MIN_THROTTLED_BYTES);
+        assert!(fair_share.admit(&greedy_peer));
+    }
+
+    #[test]
+    fn test_admit_small_transfers() {
+        let fair_share = FairShare::new(2.0);
+        let peer = PeerId::random();
+        fair_share.record_served(&peer, MIN_THROTTLED_BYTES - 1);
+        fair_share.record_served(&PeerId::random(), 1);
+
+        assert!(fair_share.admit(&peer));
+    }
+
+    #[test]
+    fn test_admit_after_window() {
+        let fair_share = FairShare::new(2.0);
+        let greedy_peer = PeerId::random();
+        let other_peer = PeerId::random();
+        let start = Instant::now();
+        fair_share.record_served_at(&greedy_peer, 3 * MIN_THROTTLED_BYTES, start);
+        fair_share.record_served_at(&other_peer, MIN_THROTTLED_BYTES, start);
+        assert!(!fair_share.admit_at(&greedy_peer, start));
+
+        let later = start + FAIR_SHARE_WINDOW;
+        assert!(fair_share.admit_at(&greedy_peer, later));
+        assert!(fair_share.served.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let fair_share = FairShare::new(0.0);
+        let greedy_peer = PeerId::random();
+        fair_share.record_served(&greedy_peer, 100 * MIN_THROTTLED_BYTES);
+        fair_share.record_served(&PeerId::random(), 1);
+
+        assert!(fair_share.admit(&greedy_peer));
+        assert_eq!(
+            fair_share.served_in_window().get(&greedy_peer),
+            Some(&(100 * MIN_THROTTLED_BYTES))
+        );
+    }
+}
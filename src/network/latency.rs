@@ -0,0 +1,83 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The number of ping round-trip times that are kept for each peer.
+const RTT_WINDOW_SIZE: usize = 10;
+
+/// Keeps track of the most recent round-trip times that were measured
+/// with the ping protocol for a single peer. The latency of the peer
+/// is the average of those round-trip times.
+#[derive(Debug, Default)]
+pub struct RollingRtt {
+    samples: VecDeque<Duration>,
+}
+
+impl RollingRtt {
+    /// Add a newly measured round-trip time, dropping the oldest
+    /// one when the window is full.
+    pub fn add_sample(&mut self, rtt: Duration) {
+        if self.samples.len() == RTT_WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rtt);
+    }
+
+    /// The average round-trip time over the current window, or `None`
+    /// when no round-trip time was measured yet.
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            Some(self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_without_samples() {
+        let rolling_rtt = RollingRtt::default();
+
+        assert_eq!(None, rolling_rtt.average());
+    }
+
+    #[test]
+    fn test_average_with_samples() {
+        let mut rolling_rtt = RollingRtt::default();
+        rolling_rtt.add_sample(Duration::from_millis(10));
+        rolling_rtt.add_sample(Duration::from_millis(20));
+
+        assert_eq!(Some(Duration::from_millis(15)), rolling_rtt.average());
+    }
+
+    #[test]
+    fn test_average_only_keeps_window() {
+        let mut rolling_rtt = RollingRtt::default();
+        rolling_rtt.add_sample(Duration::from_secs(60));
+        for _ in 0..RTT_WINDOW_SIZE {
+            rolling_rtt.add_sample(Duration::from_millis(5));
+        }
+
+        assert_eq!(Some(Duration::from_millis(5)), rolling_rtt.average());
+    }
+}
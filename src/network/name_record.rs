@@ -0,0 +1,167 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::signer::Signer;
+use libp2p::identity::PublicKey;
+use libp2p::kad::record::Key;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const NAME_RECORD_KEY_PREFIX: &str = "/pyrsia/names/";
+
+/// A signed record that maps the name of an artifact, like a docker tag or
+/// maven coordinates, to the id and hash of the artifact. Name records are
+/// stored in the DHT so that other nodes can resolve names without having
+/// the corresponding transparency log available locally.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NameRecord {
+    pub name: String,
+    pub artifact_id: String,
+    pub artifact_hash: String,
+    publisher: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl NameRecord {
+    /// Creates a new name record that is signed with the specified signer,
+    /// e.g. a keypair.
+    pub fn new<S: Signer + ?Sized>(
+        signer: &S,
+        name: &str,
+        artifact_id: &str,
+        artifact_hash: &str,
+    ) -> anyhow::Result<Self> {
+        let message = signed_message(name, artifact_id, artifact_hash)?;
+        let signature = signer.sign_message(&message)?;
+
+        Ok(NameRecord {
+            name: name.to_owned(),
+            artifact_id: artifact_id.to_owned(),
+            artifact_hash: artifact_hash.to_owned(),
+            publisher: signer.public_key().to_protobuf_encoding(),
+            signature,
+        })
+    }
+
+    /// Returns the DHT key under which the record for `name` is stored.
+    pub fn key(name: &str) -> Key {
+        Key::new(&format!("{}{}", NAME_RECORD_KEY_PREFIX, name))
+    }
+
+    /// Returns the id of the peer that published this record.
+    pub fn publisher(&self) -> Option<PeerId> {
+        PublicKey::from_protobuf_encoding(&self.publisher)
+            .ok()
+            .map(|public_key| public_key.to_peer_id())
+    }
+
+    /// Whether the record was published by one of `publishers`, or by
+    /// anyone when no publishers are specified.
+    pub fn is_published_by(&self, publishers: &Option<HashSet<PeerId>>) -> bool {
+        match (publishers, self.publisher()) {
+            (None, _) => true,
+            (Some(publishers), Some(publisher)) => publishers.contains(&publisher),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Verifies that the record was signed by its publisher and that
+    /// the signed content was not tampered with.
+    pub fn verify(&self) -> bool {
+        let public_key = match PublicKey::from_protobuf_encoding(&self.publisher) {
+            Ok(public_key) => public_key,
+            Err(_) => return false,
+        };
+
+        match signed_message(&self.name, &self.artifact_id, &self.artifact_hash) {
+            Ok(message) => public_key.verify(&message, &self.signature),
+            Err(_) => false,
+        }
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+fn signed_message(name: &str, artifact_id: &str, artifact_hash: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(bincode::serialize(&(name, artifact_id, artifact_hash))?)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_signed_record_verifies() {
+        let keypair = Keypair::generate_ed25519();
+
+        let record = NameRecord::new(&keypair, "Docker/alpine:3.16", "id", "hash").unwrap();
+
+        assert!(record.verify());
+        assert_eq!(record.publisher(), Some(keypair.public().to_peer_id()));
+    }
+
+    #[test]
+    fn test_tampered_record_does_not_verify() {
+        let keypair = Keypair::generate_ed25519();
+
+        let mut record = NameRecord::new(&keypair, "Docker/alpine:3.16", "id", "hash").unwrap();
+        record.artifact_hash = String::from("other_hash");
+
+        assert!(!record.verify());
+    }
+
+    #[test]
+    fn test_record_signed_by_other_key_does_not_verify() {
+        let keypair = Keypair::generate_ed25519();
+        let other_keypair = Keypair::generate_ed25519();
+
+        let mut record = NameRecord::new(&keypair, "Docker/alpine:3.16", "id", "hash").unwrap();
+        record.publisher = other_keypair.public().to_protobuf_encoding();
+
+        assert!(!record.verify());
+    }
+
+    #[test]
+    fn test_record_is_published_by() {
+        let keypair = Keypair::generate_ed25519();
+        let record = NameRecord::new(&keypair, "Docker/alpine:3.16", "id", "hash").unwrap();
+
+        assert!(record.is_published_by(&None));
+        assert!(record.is_published_by(&Some(HashSet::from([keypair.public().to_peer_id()]))));
+        assert!(!record.is_published_by(&Some(HashSet::from([PeerId::random()]))));
+        assert!(!record.is_published_by(&Some(HashSet::new())));
+    }
+
+    #[test]
+    fn test_record_roundtrip_bytes() {
+        let keypair = Keypair::generate_ed25519();
+
+        let record = NameRecord::new(&keypair, "Maven2/a:b:1.0", "id", "hash").unwrap();
+
+        let decoded = NameRecord::from_bytes(&record.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, record);
+        assert!(decoded.verify());
+    }
+}
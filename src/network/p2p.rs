@@ -14,14 +14,21 @@
    limitations under the License.
 */
 
+use crate::network::artifact_availability_protocol::{
+    ArtifactAvailabilityExchangeCodec, ArtifactAvailabilityExchangeProtocol,
+};
 use crate::network::artifact_protocol::{ArtifactExchangeCodec, ArtifactExchangeProtocol};
+use crate::network::artifact_replication_protocol::{
+    ArtifactReplicationExchangeCodec, ArtifactReplicationExchangeProtocol,
+};
 use crate::network::behaviour::PyrsiaNetworkBehaviour;
+use crate::network::bench_protocol::{BenchExchangeCodec, BenchExchangeProtocol};
 use crate::network::blockchain_protocol::{BlockchainExchangeCodec, BlockchainExchangeProtocol};
 use crate::network::client::Client;
-use crate::network::event_loop::{PyrsiaEvent, PyrsiaEventLoop};
+use crate::network::event_loop::{PyrsiaEvent, PyrsiaEventLoop, LEAVE_TOPIC, METRICS_TOPIC};
 use crate::network::idle_metric_protocol::{IdleMetricExchangeCodec, IdleMetricExchangeProtocol};
-use crate::util::keypair_util;
-use crate::util::keypair_util::KEYPAIR_FILENAME;
+use crate::network::peer_identity::{pyrsia_agent_version, PYRSIA_PROTOCOL_VERSION};
+use crate::network::signer::Signer;
 
 use crate::network::build_protocol::{BuildExchangeCodec, BuildExchangeProtocol};
 use crate::network::build_status_protocol::{
@@ -32,12 +39,14 @@ use libp2p::kad::record::store::{MemoryStore, MemoryStoreConfig};
 use libp2p::request_response::{ProtocolSupport, RequestResponse};
 use libp2p::swarm::{Swarm, SwarmBuilder};
 use libp2p::{
-    autonat, core, dns, gossipsub, identify, identity, kad, mplex, noise, tcp, yamux, Transport,
+    autonat, core, dns, gossipsub, identify, identity, kad, mplex, noise, ping, tcp, yamux,
+    Transport,
 };
 use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
 use std::hash::{Hash, Hasher};
 use std::iter;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
@@ -45,8 +54,7 @@ use tokio_stream::Stream;
 
 /// Sets up the libp2p [`Swarm`] with the necessary components, doing the following things:
 ///
-/// * load a keypair that is used for the libp2p identity
-/// * create a libp2p swarm
+/// * create a libp2p swarm with the keypair that is used for the libp2p identity
 /// * create a mpsc channel for sending and receiving client commands
 /// * create a mpsc channel for sending and receiving custom events
 /// * create a [`Client`] for sending client commands
@@ -57,8 +65,10 @@ use tokio_stream::Stream;
 /// components:
 ///
 /// * autonat: a protocol for establishing Network Address Translation function
-/// * Identify: a protocol for exchanging identity information between peers
+/// * Identify: a protocol for exchanging identity information between peers, like
+///   the pyrsia agent version and the supported protocols
 /// * Kademlia: a DHT to share information over the libp2p network
+/// * Ping: a protocol to measure the round-trip time to connected peers
 /// * RequestResponse: a generic request/response protocol implementation for
 /// the [`FileExchangeProtocol`]
 ///
@@ -110,6 +120,8 @@ use tokio_stream::Stream;
 ///  * the PyrsiaEventLoop
 pub fn setup_libp2p_swarm(
     max_provided_keys: usize,
+    local_keypair: Keypair,
+    signer: Arc<dyn Signer>,
 ) -> Result<
     (
         Client,
@@ -119,8 +131,6 @@ pub fn setup_libp2p_swarm(
     ),
     Box<dyn Error>,
 > {
-    let local_keypair = keypair_util::load_or_generate_ed25519(KEYPAIR_FILENAME.as_str());
-
     let (mut swarm, local_peer_id) = create_swarm(local_keypair.clone(), max_provided_keys)?;
     let (command_sender, command_receiver) = mpsc::channel(32);
     let (event_sender, event_receiver) = mpsc::channel(32);
@@ -130,12 +140,20 @@ pub fn setup_libp2p_swarm(
     // https://docs.rs/libp2p/latest/libp2p/gossipsub/type.Sha256Topic.html
     let pyrsia_topic = gossipsub::IdentTopic::new("pyrsia-topic");
     swarm.behaviour_mut().gossipsub.subscribe(&pyrsia_topic)?;
+    swarm
+        .behaviour_mut()
+        .gossipsub
+        .subscribe(&gossipsub::IdentTopic::new(LEAVE_TOPIC))?;
+    swarm
+        .behaviour_mut()
+        .gossipsub
+        .subscribe(&gossipsub::IdentTopic::new(METRICS_TOPIC))?;
 
     Ok((
         Client::new(command_sender, local_peer_id, pyrsia_topic),
-        local_keypair,
+        local_keypair.clone(),
         ReceiverStream::new(event_receiver),
-        PyrsiaEventLoop::new(swarm, command_receiver, event_sender),
+        PyrsiaEventLoop::new(swarm, signer, command_receiver, event_sender),
     ))
 }
 
@@ -168,7 +186,9 @@ fn create_swarm(
 ) -> Result<(Swarm<PyrsiaNetworkBehaviour>, core::PeerId), Box<dyn Error>> {
     let peer_id = keypair.public().to_peer_id();
 
-    let identify_config = identify::Config::new("ipfs/1.0.0".to_owned(), keypair.public());
+    let identify_config =
+        identify::Config::new(PYRSIA_PROTOCOL_VERSION.to_owned(), keypair.public())
+            .with_agent_version(pyrsia_agent_version());
 
     let memory_store_config = MemoryStoreConfig {
         max_provided_keys,
@@ -209,11 +229,25 @@ fn create_swarm(
                     peer_id,
                     MemoryStore::with_config(peer_id, memory_store_config),
                 ),
+                ping: ping::Behaviour::new(ping::Config::new()),
                 request_response: RequestResponse::new(
                     ArtifactExchangeCodec(),
                     iter::once((ArtifactExchangeProtocol(), ProtocolSupport::Full)),
                     Default::default(),
                 ),
+                artifact_availability_request_response: RequestResponse::new(
+                    ArtifactAvailabilityExchangeCodec(),
+                    iter::once((
+                        ArtifactAvailabilityExchangeProtocol(),
+                        ProtocolSupport::Full,
+                    )),
+                    Default::default(),
+                ),
+                artifact_replication_request_response: RequestResponse::new(
+                    ArtifactReplicationExchangeCodec(),
+                    iter::once((ArtifactReplicationExchangeProtocol(), ProtocolSupport::Full)),
+                    Default::default(),
+                ),
                 build_request_response: RequestResponse::new(
                     BuildExchangeCodec(),
                     iter::once((BuildExchangeProtocol(), ProtocolSupport::Full)),
@@ -234,6 +268,11 @@ fn create_swarm(
                     iter::once((BuildStatusExchangeProtocol(), ProtocolSupport::Full)),
                     Default::default(),
                 ),
+                bench_request_response: RequestResponse::new(
+                    BenchExchangeCodec(),
+                    iter::once((BenchExchangeProtocol(), ProtocolSupport::Full)),
+                    Default::default(),
+                ),
             },
             peer_id,
         )
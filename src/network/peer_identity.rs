@@ -0,0 +1,148 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use libp2p::identify;
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+
+/// The protocol version that is advertised by this node with the identify
+/// protocol. Peers that advertise a different major version are not able
+/// to exchange messages with this node.
+pub const PYRSIA_PROTOCOL_VERSION: &str = "/pyrsia/1.0.0";
+
+const PYRSIA_PROTOCOL_VERSION_PREFIX: &str = "/pyrsia/";
+
+/// The agent version that is advertised by this node with the identify
+/// protocol, in the form of `pyrsia/x.y.z`.
+pub fn pyrsia_agent_version() -> String {
+    format!("pyrsia/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Describes whether the protocol version of a peer matches with the
+/// protocol version of this node.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtocolCompatibility {
+    /// The peer runs the same major pyrsia protocol version.
+    Compatible,
+    /// The peer does not advertise a pyrsia protocol version at all.
+    Unknown,
+    /// The peer runs a different major pyrsia protocol version.
+    Incompatible,
+}
+
+impl ProtocolCompatibility {
+    pub fn of(protocol_version: &str) -> Self {
+        match (
+            major_version(protocol_version),
+            major_version(PYRSIA_PROTOCOL_VERSION),
+        ) {
+            (Some(peer_major), Some(local_major)) if peer_major == local_major => {
+                ProtocolCompatibility::Compatible
+            }
+            (Some(_), Some(_)) => ProtocolCompatibility::Incompatible,
+            _ => ProtocolCompatibility::Unknown,
+        }
+    }
+}
+
+fn major_version(protocol_version: &str) -> Option<u64> {
+    protocol_version
+        .strip_prefix(PYRSIA_PROTOCOL_VERSION_PREFIX)?
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Which side opened the connection to a peer.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionDirection {
+    /// The peer dialed this node.
+    Inbound,
+    /// This node dialed the peer.
+    Outbound,
+}
+
+/// The identity information that a peer advertised with the identify
+/// protocol.
+#[derive(Clone, Debug)]
+pub struct PeerIdentity {
+    pub agent_version: String,
+    pub protocol_version: String,
+    pub protocols: Vec<String>,
+    pub listen_addrs: Vec<Multiaddr>,
+    pub compatibility: ProtocolCompatibility,
+}
+
+impl From<identify::Info> for PeerIdentity {
+    fn from(info: identify::Info) -> Self {
+        PeerIdentity {
+            compatibility: ProtocolCompatibility::of(&info.protocol_version),
+            agent_version: info.agent_version,
+            protocol_version: info.protocol_version,
+            protocols: info.protocols,
+            listen_addrs: info.listen_addrs,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_version() {
+        assert_eq!(
+            format!("pyrsia/{}", env!("CARGO_PKG_VERSION")),
+            pyrsia_agent_version()
+        );
+    }
+
+    #[test]
+    fn test_same_protocol_version_is_compatible() {
+        assert_eq!(
+            ProtocolCompatibility::Compatible,
+            ProtocolCompatibility::of(PYRSIA_PROTOCOL_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_same_major_protocol_version_is_compatible() {
+        assert_eq!(
+            ProtocolCompatibility::Compatible,
+            ProtocolCompatibility::of("/pyrsia/1.3.0")
+        );
+    }
+
+    #[test]
+    fn test_other_major_protocol_version_is_incompatible() {
+        assert_eq!(
+            ProtocolCompatibility::Incompatible,
+            ProtocolCompatibility::of("/pyrsia/2.0.0")
+        );
+    }
+
+    #[test]
+    fn test_foreign_protocol_version_is_unknown() {
+        assert_eq!(
+            ProtocolCompatibility::Unknown,
+            ProtocolCompatibility::of("ipfs/1.0.0")
+        );
+    }
+}
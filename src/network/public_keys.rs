@@ -0,0 +1,169 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::network::signed_document::Signed;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL;
+use base64::Engine;
+use libp2p::identity::PublicKey;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the public keys of a peer are cached before they are looked up
+/// again in the DHT.
+pub const PUBLIC_KEY_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A public verification key in the JSON Web Key format (RFC 8037).
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    /// The id of the peer that owns the key.
+    pub kid: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg: String,
+}
+
+impl Jwk {
+    /// Creates the JWK for a public key. Only ed25519 keys, which pyrsia
+    /// nodes use, can be represented.
+    pub fn from_public_key(public_key: &PublicKey) -> Option<Self> {
+        match public_key {
+            PublicKey::Ed25519(ed25519_key) => Some(Jwk {
+                kty: "OKP".to_owned(),
+                crv: "Ed25519".to_owned(),
+                x: BASE64_URL.encode(ed25519_key.encode()),
+                kid: public_key.to_peer_id().to_string(),
+                key_use: "sig".to_owned(),
+                alg: "EdDSA".to_owned(),
+            }),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Decodes the public key, if it is an ed25519 key that belongs to `kid`.
+    pub fn to_public_key(&self) -> Option<PublicKey> {
+        if self.kty != "OKP" || self.crv != "Ed25519" {
+            return None;
+        }
+        let bytes = BASE64_URL.decode(&self.x).ok()?;
+        let public_key =
+            PublicKey::Ed25519(libp2p::identity::ed25519::PublicKey::decode(&bytes).ok()?);
+        if public_key.to_peer_id().to_string() != self.kid {
+            return None;
+        }
+        Some(public_key)
+    }
+}
+
+/// A JSON Web Key Set, as served at `/.well-known/jwks.json`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+impl Jwks {
+    pub fn public_keys(&self) -> Vec<PublicKey> {
+        self.keys.iter().filter_map(Jwk::to_public_key).collect()
+    }
+}
+
+/// The public keys of a peer, published in the DHT as a signed record
+/// under the id of the peer.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PublicKeyRecord {
+    pub peer_id: String,
+    pub jwks: Jwks,
+}
+
+impl Signed for PublicKeyRecord {
+    fn key(&self) -> String {
+        public_key_record_key(&self.peer_id)
+    }
+}
+
+pub fn public_key_record_key(peer_id: &str) -> String {
+    format!("keys/{}", peer_id)
+}
+
+/// A cache of the public keys of other peers, shared by all clones.
+/// Entries expire after [`PUBLIC_KEY_CACHE_TTL`].
+#[derive(Clone, Debug, Default)]
+pub struct PublicKeyCache {
+    entries: Arc<Mutex<HashMap<PeerId, (Jwks, Instant)>>>,
+}
+
+impl PublicKeyCache {
+    pub fn get(&self, peer_id: &PeerId) -> Option<Jwks> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(peer_id)
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < PUBLIC_KEY_CACHE_TTL)
+            .map(|(jwks, _)| jwks.clone())
+    }
+
+    pub fn insert(&self, peer_id: PeerId, jwks: Jwks) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(peer_id, (jwks, Instant::now()));
+    }
+
+    pub fn remove(&self, peer_id: &PeerId) {
+        self.entries.lock().unwrap().remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_jwk_roundtrip() {
+        let keypair = Keypair::generate_ed25519();
+
+        let jwk = Jwk::from_public_key(&keypair.public()).unwrap();
+
+        assert_eq!(jwk.kid, keypair.public().to_peer_id().to_string());
+        assert_eq!(jwk.to_public_key(), Some(keypair.public()));
+    }
+
+    #[test]
+    fn test_signed_public_key_record() {
+        let keypair = Keypair::generate_ed25519();
+        let record = PublicKeyRecord {
+            peer_id: keypair.public().to_peer_id().to_string(),
+            jwks: Jwks {
+                keys: vec![Jwk::from_public_key(&keypair.public()).unwrap()],
+            },
+        };
+
+        let document = record.sign(&keypair).unwrap();
+
+        assert_eq!(
+            PublicKeyRecord::from_signed_document(&document).unwrap(),
+            record
+        );
+        assert_eq!(document.publisher(), Some(keypair.public().to_peer_id()));
+    }
+}
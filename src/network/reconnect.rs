@@ -0,0 +1,124 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use libp2p::Multiaddr;
+use std::time::{Duration, Instant};
+
+/// The delay before the first reconnection attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// The maximum delay between two reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Keeps track of the reconnection attempts to a peer that the node
+/// must stay connected to, like the bootstrap node or a peer that was
+/// configured at startup. The delay between attempts grows exponentially
+/// until it reaches [`MAX_BACKOFF`].
+#[derive(Debug)]
+pub struct Reconnect {
+    pub peer_addr: Multiaddr,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+}
+
+impl Reconnect {
+    pub fn new(peer_addr: Multiaddr) -> Self {
+        Self {
+            peer_addr,
+            attempts: 0,
+            next_attempt: None,
+        }
+    }
+
+    /// Resets the backoff after the connection with the peer was
+    /// (re-)established.
+    pub fn connected(&mut self) {
+        self.attempts = 0;
+        self.next_attempt = None;
+    }
+
+    /// Schedules the next reconnection attempt after the connection was
+    /// lost or a dial attempt failed. Returns the delay until that attempt.
+    pub fn schedule(&mut self, now: Instant) -> Duration {
+        let delay = backoff(self.attempts);
+        self.attempts = self.attempts.saturating_add(1);
+        self.next_attempt = Some(now + delay);
+        delay
+    }
+
+    /// Returns true when the scheduled reconnection attempt is due. Calling
+    /// this clears the schedule, as the caller is expected to dial the peer.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        match self.next_attempt {
+            Some(next_attempt) if next_attempt <= now => {
+                self.next_attempt = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn backoff(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .checked_mul(2u32.saturating_pow(attempts))
+        .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_exponentially() {
+        assert_eq!(Duration::from_secs(1), backoff(0));
+        assert_eq!(Duration::from_secs(2), backoff(1));
+        assert_eq!(Duration::from_secs(4), backoff(2));
+        assert_eq!(Duration::from_secs(256), backoff(8));
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        assert_eq!(MAX_BACKOFF, backoff(9));
+        assert_eq!(MAX_BACKOFF, backoff(u32::MAX));
+    }
+
+    #[test]
+    fn test_schedule_and_take_due() {
+        let mut reconnect = Reconnect::new("/ip4/127.0.0.1/tcp/44000".parse().unwrap());
+        let now = Instant::now();
+
+        assert!(!reconnect.take_due(now));
+        assert_eq!(Duration::from_secs(1), reconnect.schedule(now));
+        assert!(!reconnect.take_due(now));
+        assert!(reconnect.take_due(now + Duration::from_secs(1)));
+        assert!(!reconnect.take_due(now + Duration::from_secs(1)));
+        assert_eq!(Duration::from_secs(2), reconnect.schedule(now));
+    }
+
+    #[test]
+    fn test_connected_resets_backoff() {
+        let mut reconnect = Reconnect::new("/ip4/127.0.0.1/tcp/44000".parse().unwrap());
+        let now = Instant::now();
+
+        reconnect.schedule(now);
+        reconnect.schedule(now);
+        reconnect.connected();
+
+        assert!(!reconnect.take_due(now + MAX_BACKOFF));
+        assert_eq!(Duration::from_secs(1), reconnect.schedule(now));
+    }
+}
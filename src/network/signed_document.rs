@@ -0,0 +1,153 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::signer::Signer;
+use anyhow::bail;
+use libp2p::identity::PublicKey;
+use libp2p::kad::record::Key;
+use libp2p::PeerId;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SIGNED_RECORD_KEY_PREFIX: &str = "/pyrsia/metadata/";
+
+/// The public key of a peer, which is inlined in the peer id of the small
+/// keys, like ed25519, that pyrsia nodes use.
+pub fn public_key_of(peer_id: &PeerId) -> Option<PublicKey> {
+    let multihash = multihash::Multihash::from_bytes(&peer_id.to_bytes()).ok()?;
+    if multihash.code() != 0 {
+        return None;
+    }
+    PublicKey::from_protobuf_encoding(multihash.digest()).ok()
+}
+
+/// A JSON document, like an SBOM, that is signed with the keypair of the node
+/// that stored it, so that its origin can be verified when it is served.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SignedDocument {
+    pub content: String,
+    /// The time the document was signed, in seconds since the epoch.
+    pub signed_at: u64,
+    publisher: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl SignedDocument {
+    /// Creates a new document with the specified content that is signed with
+    /// the specified signer, e.g. a keypair.
+    pub fn new<S: Signer + ?Sized>(signer: &S, content: &str) -> anyhow::Result<Self> {
+        let signed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let signature = signer.sign_message(&signed_message(content, signed_at)?)?;
+
+        Ok(SignedDocument {
+            content: content.to_owned(),
+            signed_at,
+            publisher: signer.public_key().to_protobuf_encoding(),
+            signature,
+        })
+    }
+
+    /// Returns the id of the peer that signed this document.
+    pub fn publisher(&self) -> Option<PeerId> {
+        PublicKey::from_protobuf_encoding(&self.publisher)
+            .ok()
+            .map(|public_key| public_key.to_peer_id())
+    }
+
+    /// Returns the DHT key under which the signed document for `key` is stored.
+    pub fn record_key(key: &str) -> Key {
+        Key::new(&format!("{}{}", SIGNED_RECORD_KEY_PREFIX, key))
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// Verifies that the document was signed by its publisher and that
+    /// its content was not tampered with.
+    pub fn verify(&self) -> bool {
+        let public_key = match PublicKey::from_protobuf_encoding(&self.publisher) {
+            Ok(public_key) => public_key,
+            Err(_) => return false,
+        };
+
+        match signed_message(&self.content, self.signed_at) {
+            Ok(message) => public_key.verify(&message, &self.signature),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Metadata that is published as a [`SignedDocument`] with its JSON
+/// representation as content, so that its origin can be verified by every
+/// node that retrieves it.
+pub trait Signed: Serialize + DeserializeOwned {
+    /// The key under which the metadata is stored, locally and in the DHT.
+    fn key(&self) -> String;
+
+    /// Creates a document with the metadata that is signed with the
+    /// specified signer, e.g. a keypair.
+    fn sign<S: Signer + ?Sized>(&self, signer: &S) -> anyhow::Result<SignedDocument> {
+        SignedDocument::new(signer, &serde_json::to_string(self)?)
+    }
+
+    /// Reads the metadata from a signed document, after verifying its signature.
+    fn from_signed_document(document: &SignedDocument) -> anyhow::Result<Self> {
+        if !document.verify() {
+            bail!("The signature of the document is invalid");
+        }
+        Ok(serde_json::from_str(&document.content)?)
+    }
+}
+
+fn signed_message(content: &str, signed_at: u64) -> anyhow::Result<Vec<u8>> {
+    Ok(bincode::serialize(&(content, signed_at))?)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_signed_document_verifies() {
+        let keypair = Keypair::generate_ed25519();
+
+        let document = SignedDocument::new(&keypair, "{\"bomFormat\":\"CycloneDX\"}").unwrap();
+
+        assert!(document.verify());
+        assert_eq!(document.publisher(), Some(keypair.public().to_peer_id()));
+    }
+
+    #[test]
+    fn test_tampered_document_does_not_verify() {
+        let keypair = Keypair::generate_ed25519();
+
+        let mut document = SignedDocument::new(&keypair, "{\"bomFormat\":\"CycloneDX\"}").unwrap();
+        document.content = String::from("{}");
+
+        assert!(!document.verify());
+    }
+}
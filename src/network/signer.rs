@@ -0,0 +1,59 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use libp2p::identity::{Keypair, PublicKey};
+
+/// Signs messages with the key of the node. The node signs documents, name
+/// records and audit log entries through a signer, so the key can be kept
+/// out of memory, e.g. in a hardware token that is accessed over PKCS#11,
+/// by implementing this trait for it.
+pub trait Signer: Send + Sync {
+    /// The public key that the signatures are verified with.
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs the message and returns the signature.
+    fn sign_message(&self, message: &[u8]) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Signs with a keypair that is held in memory, like the identity keypair
+/// of the node.
+impl Signer for Keypair {
+    fn public_key(&self) -> PublicKey {
+        self.public()
+    }
+
+    fn sign_message(&self, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(self.sign(message)?)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_keypair_signer() {
+        let keypair = Keypair::generate_ed25519();
+        let signer: Arc<dyn Signer> = Arc::new(keypair.clone());
+
+        let signature = signer.sign_message(b"message").unwrap();
+
+        assert_eq!(signer.public_key(), keypair.public());
+        assert!(keypair.public().verify(b"message", &signature));
+    }
+}
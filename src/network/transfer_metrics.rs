@@ -0,0 +1,171 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The maximum number of peers that traffic is tracked for individually.
+/// Traffic with other peers only counts in the total.
+pub const MAX_TRACKED_PEERS: usize = 256;
+
+/// Counters describing the artifact traffic exchanged with other peers.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TransferCounters {
+    /// Number of artifacts this node served to other peers.
+    pub artifacts_served: u64,
+    /// Number of artifacts this node fetched from other peers.
+    pub artifacts_fetched: u64,
+    /// Number of artifact bytes sent to other peers.
+    pub bytes_out: u64,
+    /// Number of artifact bytes received from other peers.
+    pub bytes_in: u64,
+    /// Number of artifact requests from other peers that could not be served.
+    pub serve_failures: u64,
+    /// Number of artifact requests to other peers that failed.
+    pub fetch_failures: u64,
+}
+
+/// Keeps track of the artifact traffic exchanged over the p2p network,
+/// both in total and per connected peer, so operators can quantify how
+/// much traffic is served by Pyrsia peers instead of upstream registries.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TransferMetrics {
+    pub total: TransferCounters,
+    pub peers: HashMap<String, TransferCounters>,
+}
+
+impl TransferMetrics {
+    /// Records that an artifact of `bytes` length was served to `peer`.
+    pub fn record_served(&mut self, peer: &PeerId, bytes: u64) {
+        self.update(peer, |counters| {
+            counters.artifacts_served += 1;
+            counters.bytes_out += bytes;
+        });
+    }
+
+    /// Records that an artifact of `bytes` length was fetched from `peer`.
+    pub fn record_fetched(&mut self, peer: &PeerId, bytes: u64) {
+        self.update(peer, |counters| {
+            counters.artifacts_fetched += 1;
+            counters.bytes_in += bytes;
+        });
+    }
+
+    /// Records that an artifact request from `peer` could not be served.
+    pub fn record_serve_failure(&mut self, peer: &PeerId) {
+        self.update(peer, |counters| counters.serve_failures += 1);
+    }
+
+    /// Records that an artifact request to `peer` failed.
+    pub fn record_fetch_failure(&mut self, peer: &PeerId) {
+        self.update(peer, |counters| counters.fetch_failures += 1);
+    }
+
+    /// Forgets the traffic with `peer`, e.g. when it disconnected. Its
+    /// traffic still counts in the total.
+    pub fn remove_peer(&mut self, peer: &PeerId) {
+        self.peers.remove(&peer.to_string());
+    }
+
+    fn update(&mut self, peer: &PeerId, f: impl Fn(&mut TransferCounters)) {
+        f(&mut self.total);
+        let peer = peer.to_string();
+        if let Some(counters) = self.peers.get_mut(&peer) {
+            f(counters);
+        } else if self.peers.len() < MAX_TRACKED_PEERS {
+            f(self.peers.entry(peer).or_default());
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_served_and_fetched() {
+        let peer_1 = PeerId::random();
+        let peer_2 = PeerId::random();
+
+        let mut metrics = TransferMetrics::default();
+        metrics.record_served(&peer_1, 100);
+        metrics.record_served(&peer_2, 50);
+        metrics.record_fetched(&peer_1, 20);
+
+        assert_eq!(
+            metrics.total,
+            TransferCounters {
+                artifacts_served: 2,
+                artifacts_fetched: 1,
+                bytes_out: 150,
+                bytes_in: 20,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics.peers.get(&peer_1.to_string()),
+            Some(&TransferCounters {
+                artifacts_served: 1,
+                artifacts_fetched: 1,
+                bytes_out: 100,
+                bytes_in: 20,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_record_failures() {
+        let peer = PeerId::random();
+
+        let mut metrics = TransferMetrics::default();
+        metrics.record_serve_failure(&peer);
+        metrics.record_fetch_failure(&peer);
+        metrics.record_fetch_failure(&peer);
+
+        assert_eq!(metrics.total.serve_failures, 1);
+        assert_eq!(metrics.total.fetch_failures, 2);
+        assert_eq!(metrics.peers.get(&peer.to_string()), Some(&metrics.total));
+    }
+
+    #[test]
+    fn test_remove_peer() {
+        let peer = PeerId::random();
+
+        let mut metrics = TransferMetrics::default();
+        metrics.record_served(&peer, 100);
+        metrics.remove_peer(&peer);
+
+        assert!(metrics.peers.is_empty());
+        assert_eq!(metrics.total.bytes_out, 100);
+    }
+
+    #[test]
+    fn test_tracked_peers_are_capped() {
+        let mut metrics = TransferMetrics::default();
+        for _ in 0..MAX_TRACKED_PEERS + 1 {
+            metrics.record_fetched(&PeerId::random(), 10);
+        }
+
+        assert_eq!(metrics.peers.len(), MAX_TRACKED_PEERS);
+        assert_eq!(
+            metrics.total.artifacts_fetched,
+            MAX_TRACKED_PEERS as u64 + 1
+        );
+    }
+}
@@ -14,6 +14,10 @@
    limitations under the License.
 */
 
+pub mod api_tokens;
+pub mod cors;
 pub mod handlers;
 pub mod model;
+pub mod pagination;
+pub mod response_signing;
 pub mod routes;
@@ -0,0 +1,348 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use warp::{Filter, Rejection};
+
+const API_TOKEN_PREFIX: &str = "pyrsia_";
+
+/// The errors of managing the API tokens issued by the node.
+#[derive(Debug, Error)]
+pub enum ApiTokenError {
+    /// No issued token has the specified id.
+    #[error("No API token with id {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// An API token that was issued by the node. Only the hash of the token is
+/// kept, the token itself is shown once when it is issued.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IssuedApiToken {
+    /// The first characters of the hash of the token, which identify it.
+    pub id: String,
+    /// The SHA-256 hash of the token, which is left out when tokens are listed.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub hash: String,
+    /// The time the token was issued, in seconds since the epoch.
+    pub issued_at: u64,
+}
+
+/// A newly issued API token together with its secret value.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NewApiToken {
+    /// The id under which the token is listed and revoked.
+    pub id: String,
+    /// The secret value of the token, to send as `Authorization: Bearer <token>`.
+    pub token: String,
+}
+
+/// The bearer tokens that grant access to the node API: the tokens that are
+/// configured on the command line of the node and the tokens issued by the
+/// node, which are kept as a JSON file. Authentication is only required
+/// once at least one token exists.
+///
+/// The issued tokens are read from the file once and kept in memory, the
+/// file is only written again when a token is issued or revoked.
+#[derive(Clone, Debug)]
+pub struct ApiTokens {
+    path: PathBuf,
+    configured: Arc<Mutex<HashSet<String>>>,
+    issued: Arc<Mutex<Option<Vec<IssuedApiToken>>>>,
+    remote_admin: Arc<AtomicBool>,
+}
+
+impl ApiTokens {
+    /// Keep the issued tokens in the JSON file at the specified path, which
+    /// is created once the first token is issued.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        ApiTokens {
+            path: path.as_ref().to_path_buf(),
+            configured: Arc::new(Mutex::new(HashSet::new())),
+            issued: Arc::new(Mutex::new(None)),
+            remote_admin: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Accept the specified tokens in addition to the issued tokens,
+    /// instead of the tokens that were configured before.
+    pub fn configure(&self, tokens: &[String]) {
+        *self.configured.lock().unwrap() = tokens.iter().map(|token| token_hash(token)).collect();
+    }
+
+    /// Allow clients on other hosts to use the admin endpoints while
+    /// authentication is not enabled, e.g. the CLI on the host of a
+    /// container that runs the node.
+    pub fn allow_remote_admin(&self, allowed: bool) {
+        self.remote_admin.store(allowed, Ordering::Relaxed);
+    }
+
+    /// Whether clients on other hosts may use the admin endpoints while
+    /// authentication is not enabled.
+    pub fn is_remote_admin_allowed(&self) -> bool {
+        self.remote_admin.load(Ordering::Relaxed)
+    }
+
+    /// Whether requests to the node API must present a token. A token file
+    /// that can't be read enables authentication, so that access is never
+    /// granted by accident.
+    pub fn is_enabled(&self) -> bool {
+        !self.configured.lock().unwrap().is_empty()
+            || self.list().map(|tokens| !tokens.is_empty()).unwrap_or(true)
+    }
+
+    /// Check the value of the `Authorization` header of a request, which
+    /// must hold a valid bearer token once authentication is enabled.
+    pub fn authorize(&self, authorization: Option<&str>) -> Result<(), RegistryError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        match authorization.and_then(|authorization| authorization.strip_prefix("Bearer ")) {
+            Some(token) if self.verify(token.trim()) => Ok(()),
+            Some(_) => Err(RegistryError {
+                code: RegistryErrorCode::Unauthorized(String::from("The API token is not valid")),
+            }),
+            None => Err(RegistryError {
+                code: RegistryErrorCode::Unauthorized(String::from(
+                    "The node API requires a bearer token",
+                )),
+            }),
+        }
+    }
+
+    /// Whether the token grants access to the node API.
+    pub fn verify(&self, token: &str) -> bool {
+        let hash = token_hash(token);
+        if self.configured.lock().unwrap().contains(&hash) {
+            return true;
+        }
+        let mut issued = self.issued.lock().unwrap();
+        self.issued_tokens(&mut issued)
+            .map(|issued_tokens| {
+                issued_tokens
+                    .iter()
+                    .any(|issued_token| issued_token.hash == hash)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Issue a new random token.
+    pub fn issue(&self) -> Result<NewApiToken, ApiTokenError> {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let token = format!("{}{}", API_TOKEN_PREFIX, hex::encode(secret));
+        let hash = token_hash(&token);
+        let issued_token = IssuedApiToken {
+            id: hash[..12].to_owned(),
+            hash,
+            issued_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        let mut issued = self.issued.lock().unwrap();
+        let mut issued_tokens = self.issued_tokens(&mut issued)?.clone();
+        issued_tokens.push(issued_token.clone());
+        self.write(&issued_tokens)?;
+        *issued = Some(issued_tokens);
+        Ok(NewApiToken {
+            id: issued_token.id,
+            token,
+        })
+    }
+
+    /// The issued tokens, without their hashes.
+    pub fn list(&self) -> Result<Vec<IssuedApiToken>, ApiTokenError> {
+        let mut issued = self.issued.lock().unwrap();
+        Ok(self
+            .issued_tokens(&mut issued)?
+            .iter()
+            .map(|issued_token| IssuedApiToken {
+                hash: String::new(),
+                ..issued_token.clone()
+            })
+            .collect())
+    }
+
+    /// Revoke the issued token with the specified id.
+    pub fn revoke(&self, id: &str) -> Result<(), ApiTokenError> {
+        let mut issued = self.issued.lock().unwrap();
+        let mut issued_tokens = self.issued_tokens(&mut issued)?.clone();
+        let count = issued_tokens.len();
+        issued_tokens.retain(|issued_token| issued_token.id != id);
+        if issued_tokens.len() == count {
+            return Err(ApiTokenError::NotFound(id.to_owned()));
+        }
+        self.write(&issued_tokens)?;
+        *issued = Some(issued_tokens);
+        Ok(())
+    }
+
+    // The issued tokens in memory, which are read from the file the first
+    // time. A file that can't be read is read again the next time.
+    fn issued_tokens<'a>(
+        &self,
+        issued: &'a mut Option<Vec<IssuedApiToken>>,
+    ) -> Result<&'a mut Vec<IssuedApiToken>, ApiTokenError> {
+        if issued.is_none() {
+            *issued = Some(self.read()?);
+        }
+        Ok(issued.as_mut().unwrap())
+    }
+
+    fn read(&self) -> Result<Vec<IssuedApiToken>, ApiTokenError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, issued_tokens: &[IssuedApiToken]) -> Result<(), ApiTokenError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(issued_tokens)?)?;
+        Ok(())
+    }
+}
+
+fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Reject requests without a valid `Authorization: Bearer <token>` header
+/// once authentication is enabled.
+pub fn require_api_token(
+    api_tokens: ApiTokens,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::any().map(move || api_tokens.clone()))
+        .and_then(check_api_token)
+        .untuple_one()
+}
+
+async fn check_api_token(
+    authorization: Option<String>,
+    api_tokens: ApiTokens,
+) -> Result<(), Rejection> {
+    Ok(api_tokens.authorize(authorization.as_deref())?)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::docker::error_util::custom_recover;
+    use crate::util::test_util;
+
+    #[test]
+    fn test_issue_and_revoke_api_tokens() {
+        let tmp_dir = test_util::tests::setup();
+        let api_tokens = ApiTokens::new(tmp_dir.join("api_tokens.json"));
+        assert!(!api_tokens.is_enabled());
+
+        let new_token = api_tokens.issue().unwrap();
+
+        assert!(api_tokens.is_enabled());
+        assert!(new_token.token.starts_with(API_TOKEN_PREFIX));
+        assert!(api_tokens.verify(&new_token.token));
+        assert!(!api_tokens.verify("pyrsia_invalid"));
+        let issued_tokens = api_tokens.list().unwrap();
+        assert_eq!(issued_tokens.len(), 1);
+        assert_eq!(issued_tokens[0].id, new_token.id);
+        assert!(issued_tokens[0].hash.is_empty());
+
+        api_tokens.revoke(&new_token.id).unwrap();
+        assert!(!api_tokens.verify(&new_token.token));
+        assert!(matches!(
+            api_tokens.revoke(&new_token.id),
+            Err(ApiTokenError::NotFound(_))
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_issued_api_tokens_are_kept_in_memory() {
+        let tmp_dir = test_util::tests::setup();
+        let api_tokens = ApiTokens::new(tmp_dir.join("api_tokens.json"));
+        let new_token = api_tokens.issue().unwrap();
+
+        // the tokens are not read from the file again for every request
+        fs::remove_file(tmp_dir.join("api_tokens.json")).unwrap();
+        assert!(api_tokens.is_enabled());
+        assert!(api_tokens.verify(&new_token.token));
+
+        // tokens that are issued or revoked are written to the file
+        let other_token = api_tokens.issue().unwrap();
+        api_tokens.revoke(&new_token.id).unwrap();
+        let reloaded_api_tokens = ApiTokens::new(tmp_dir.join("api_tokens.json"));
+        assert!(!reloaded_api_tokens.verify(&new_token.token));
+        assert!(reloaded_api_tokens.verify(&other_token.token));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_require_api_token() {
+        let tmp_dir = test_util::tests::setup();
+        let api_tokens = ApiTokens::new(tmp_dir.join("api_tokens.json"));
+        let filter = require_api_token(api_tokens.clone())
+            .map(warp::reply)
+            .recover(custom_recover);
+
+        let response = warp::test::request().reply(&filter).await;
+        assert_eq!(response.status(), 200);
+
+        api_tokens.configure(&[String::from("configured_token")]);
+
+        let response = warp::test::request().reply(&filter).await;
+        assert_eq!(response.status(), 401);
+        let response = warp::test::request()
+            .header("Authorization", "Bearer other_token")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 401);
+        let response = warp::test::request()
+            .header("Authorization", "Bearer configured_token")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        api_tokens.configure(&[]);
+
+        let response = warp::test::request().reply(&filter).await;
+        assert_eq!(response.status(), 200);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
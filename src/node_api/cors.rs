@@ -0,0 +1,161 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use thiserror::Error;
+use warp::cors::Builder;
+use warp::http::{Method, Uri};
+
+/// The methods that browsers may use when no methods are configured.
+pub const DEFAULT_CORS_METHODS: [&str; 4] = ["GET", "POST", "PUT", "DELETE"];
+
+// The request headers that browsers may send along: the bearer token and
+// the type of JSON request bodies.
+const CORS_HEADERS: [&str; 2] = ["authorization", "content-type"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CorsError {
+    #[error("Invalid CORS origin {0}, expected * or scheme://host[:port]")]
+    InvalidOrigin(String),
+    #[error("Invalid CORS method {0}")]
+    InvalidMethod(String),
+}
+
+/// Build the CORS configuration of the node API that allows browsers on the
+/// specified origins to call it, or `*` to allow any origin. Returns `None`
+/// when no origins are configured, in which case no CORS headers are added.
+pub fn node_cors(
+    allowed_origins: &[String],
+    allowed_methods: &[String],
+) -> Result<Option<Builder>, CorsError> {
+    if allowed_origins.is_empty() {
+        return Ok(None);
+    }
+
+    let methods = if allowed_methods.is_empty() {
+        DEFAULT_CORS_METHODS.iter().map(|m| m.to_string()).collect()
+    } else {
+        allowed_methods.to_vec()
+    };
+    let methods = methods
+        .iter()
+        .map(|method| {
+            Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| CorsError::InvalidMethod(method.clone()))
+        })
+        .collect::<Result<Vec<Method>, CorsError>>()?;
+
+    let cors = warp::cors()
+        .allow_methods(methods)
+        .allow_headers(CORS_HEADERS);
+
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        return Ok(Some(cors.allow_any_origin()));
+    }
+
+    for origin in allowed_origins {
+        if !is_valid_origin(origin) {
+            return Err(CorsError::InvalidOrigin(origin.clone()));
+        }
+    }
+    Ok(Some(
+        cors.allow_origins(
+            allowed_origins
+                .iter()
+                .map(|origin| origin.trim_end_matches('/')),
+        ),
+    ))
+}
+
+// warp panics on origins that are not a bare scheme://host[:port].
+fn is_valid_origin(origin: &str) -> bool {
+    match origin.trim_end_matches('/').parse::<Uri>() {
+        Ok(uri) => {
+            uri.scheme().is_some()
+                && uri.host().is_some()
+                && uri.path_and_query().map_or(true, |p| p.as_str() == "/")
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn node_cors_allows_configured_origins() {
+        let cors = node_cors(&["http://localhost:3000".to_owned()], &[])
+            .unwrap()
+            .unwrap();
+        let filter = warp::path!("status").map(warp::reply).with(cors);
+
+        let response = warp::test::request()
+            .method("OPTIONS")
+            .path("/status")
+            .header("origin", "http://localhost:3000")
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "authorization")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:3000"
+        );
+
+        let response = warp::test::request()
+            .method("GET")
+            .path("/status")
+            .header("origin", "http://localhost:3000")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:3000"
+        );
+
+        let response = warp::test::request()
+            .method("OPTIONS")
+            .path("/status")
+            .header("origin", "http://evil.example.com")
+            .header("access-control-request-method", "GET")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 403);
+    }
+
+    #[test]
+    fn node_cors_rejects_invalid_config() {
+        assert!(node_cors(&[], &[]).unwrap().is_none());
+        assert_eq!(
+            node_cors(&["localhost".to_owned()], &[]).err(),
+            Some(CorsError::InvalidOrigin("localhost".to_owned()))
+        );
+        assert_eq!(
+            node_cors(&["https://example.com/ui".to_owned()], &[]).err(),
+            Some(CorsError::InvalidOrigin(
+                "https://example.com/ui".to_owned()
+            ))
+        );
+        assert_eq!(
+            node_cors(&["*".to_owned()], &["GE T".to_owned()]).err(),
+            Some(CorsError::InvalidMethod("GE T".to_owned()))
+        );
+    }
+}
@@ -14,4 +14,5 @@
    limitations under the License.
 */
 
+pub mod admin;
 pub mod swarm;
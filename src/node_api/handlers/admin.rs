@@ -0,0 +1,279 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::artifact_service::audit_log::AuditAction;
+use crate::artifact_service::service::ArtifactService;
+use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::network::bench::{
+    check_bench_parameters, fastest_peer, run_bench, DEFAULT_BENCH_ROUNDS, DEFAULT_BENCH_SIZE,
+};
+use crate::node_api::api_tokens::{ApiTokenError, ApiTokens};
+use crate::node_api::model::cli::{RequestBench, RequestConnectPeer, RequestDisconnectPeer};
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use log::warn;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use warp::{http::StatusCode, Rejection, Reply};
+
+/// Only allow clients with a valid API token to use the admin endpoints.
+/// As long as no API token is configured or issued, the admin endpoints are
+/// only available to clients on the same host as the node, unless remote
+/// admin clients are allowed.
+pub async fn ensure_admin_client(
+    authorization: Option<String>,
+    remote_addr: Option<SocketAddr>,
+    api_tokens: ApiTokens,
+) -> Result<(), Rejection> {
+    if api_tokens.is_enabled() {
+        return Ok(api_tokens.authorize(authorization.as_deref())?);
+    }
+    match remote_addr {
+        _ if api_tokens.is_remote_admin_allowed() => Ok(()),
+        Some(remote_addr) if remote_addr.ip().is_loopback() => Ok(()),
+        _ => Err(RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "The admin endpoints are only available to local clients until an API token is configured or remote admin clients are allowed",
+            )),
+        }
+        .into()),
+    }
+}
+
+pub async fn handle_admin_evict_artifact(
+    artifact_hash: String,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let evicted_artifact_ids = artifact_service
+        .evict_artifacts_by_hash(&artifact_hash)
+        .await
+        .map_err(RegistryError::from)?;
+    if evicted_artifact_ids.is_empty() {
+        return Err(RegistryError {
+            code: RegistryErrorCode::NotFound(format!(
+                "No artifact with hash {} is stored on this node",
+                artifact_hash
+            )),
+        }
+        .into());
+    }
+    let evicted_artifact_ids_as_json = serde_json::to_string(&evicted_artifact_ids).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(evicted_artifact_ids_as_json)
+        .unwrap())
+}
+
+pub async fn handle_admin_connect_peer(
+    request_connect_peer: RequestConnectPeer,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let address =
+        Multiaddr::from_str(&request_connect_peer.address).map_err(|_| RegistryError {
+            code: RegistryErrorCode::BadRequest(format!(
+                "Multiaddress has invalid format: {}",
+                request_connect_peer.address
+            )),
+        })?;
+    let peer_id = match address.iter().last() {
+        Some(Protocol::P2p(hash)) => PeerId::from_multihash(hash).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| RegistryError {
+        code: RegistryErrorCode::BadRequest(format!(
+            "Multiaddress does not end with a peer ID: {}",
+            address
+        )),
+    })?;
+    artifact_service
+        .p2p_client
+        .dial(&peer_id, &address)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(format!("Dialing {} failed: {}", address, e)),
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &peer_id.to_string(),
+        Some(format!("connected to peer at {}", address)),
+    );
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::OK)
+        .body(peer_id.to_string()))
+}
+
+pub async fn handle_admin_disconnect_peer(
+    peer_id: String,
+    request_disconnect_peer: RequestDisconnectPeer,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let peer_id = PeerId::from_str(&peer_id).map_err(|_| RegistryError {
+        code: RegistryErrorCode::BadRequest(format!("PeerId has invalid format: {}", peer_id)),
+    })?;
+    artifact_service
+        .p2p_client
+        .disconnect_peer(&peer_id, request_disconnect_peer.ban)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::NotFound(e.to_string()),
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &peer_id.to_string(),
+        Some(String::from(if request_disconnect_peer.ban {
+            "banned peer"
+        } else {
+            "disconnected peer"
+        })),
+    );
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::OK)
+        .body(""))
+}
+
+pub async fn handle_admin_bench(
+    request_bench: RequestBench,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let size = request_bench.size.unwrap_or(DEFAULT_BENCH_SIZE);
+    let rounds = request_bench.rounds.unwrap_or(DEFAULT_BENCH_ROUNDS);
+    check_bench_parameters(size, rounds).map_err(|e| RegistryError {
+        code: RegistryErrorCode::BadRequest(e.to_string()),
+    })?;
+    let peer_id = match request_bench.peer_id {
+        Some(peer_id) => PeerId::from_str(&peer_id).map_err(|_| RegistryError {
+            code: RegistryErrorCode::BadRequest(format!("PeerId has invalid format: {}", peer_id)),
+        })?,
+        None => fastest_peer(&mut artifact_service.p2p_client)
+            .await
+            .map_err(|e| RegistryError {
+                code: RegistryErrorCode::NotFound(e.to_string()),
+            })?,
+    };
+    let report = run_bench(&mut artifact_service.p2p_client, &peer_id, size, rounds)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(format!(
+                "Benchmark against {} failed: {}",
+                peer_id, e
+            )),
+        })?;
+    let report_as_json = serde_json::to_string(&report).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(report_as_json))
+}
+
+pub async fn handle_admin_collect_garbage(
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let evicted_artifact_ids = artifact_service
+        .collect_garbage()
+        .await
+        .map_err(RegistryError::from)?;
+    let evicted_artifact_ids_as_json = serde_json::to_string(&evicted_artifact_ids).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(evicted_artifact_ids_as_json)
+        .unwrap())
+}
+
+pub async fn handle_admin_issue_api_token(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let new_token = artifact_service
+        .api_tokens
+        .issue()
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(e.to_string()),
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &new_token.id,
+        Some(String::from("issued API token")),
+    );
+    let new_token_as_json = serde_json::to_string(&new_token).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(new_token_as_json)
+        .unwrap())
+}
+
+pub async fn handle_admin_get_api_tokens(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let issued_tokens = artifact_service
+        .api_tokens
+        .list()
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(e.to_string()),
+        })?;
+    let issued_tokens_as_json = serde_json::to_string(&issued_tokens).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(issued_tokens_as_json)
+        .unwrap())
+}
+
+pub async fn handle_admin_revoke_api_token(
+    id: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    artifact_service
+        .api_tokens
+        .revoke(&id)
+        .map_err(|e| RegistryError {
+            code: match e {
+                ApiTokenError::NotFound(_) => RegistryErrorCode::NotFound(e.to_string()),
+                _ => RegistryErrorCode::Unknown(e.to_string()),
+            },
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &id,
+        Some(String::from("revoked API token")),
+    );
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::OK)
+        .body(""))
+}
+
+pub async fn handle_admin_scrub(
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    tokio::spawn(async move {
+        if let Err(e) = artifact_service.scrub_artifacts().await {
+            warn!("Scrub of stored artifacts failed: {:?}", e);
+        }
+    });
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::ACCEPTED)
+        .body(""))
+}
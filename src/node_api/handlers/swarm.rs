@@ -14,22 +14,61 @@
    limitations under the License.
 */
 
-use crate::artifact_service::model::PackageType;
+use crate::artifact_service::attestation::Envelope;
+use crate::artifact_service::audit_log::AuditAction;
+use crate::artifact_service::federation::FederationSyncQuery;
+use crate::artifact_service::hashing::{ArtifactDigest, HashAlgorithm};
+use crate::artifact_service::metadata_index::ArtifactMetadata;
+use crate::artifact_service::model::{
+    package_key, package_version_key, NodeRole, Package, PackageType, PackageVersion,
+};
+use crate::artifact_service::trust_store::TrustStoreError;
 use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::docker::prefetch::{prefetch_image, ImageReference};
+use crate::logging::buffer::{log_buffer, LogEntry};
 use crate::network::client::Client;
+use crate::network::signed_document::Signed;
 use crate::node_api::model::cli::{
-    RequestAddAuthorizedNode, RequestBuildStatus, RequestDockerBuild, RequestDockerLog,
-    RequestMavenBuild, RequestMavenLog,
+    ArtifactInspection, ArtifactList, AuditLogQuery, ConnectedPeer, DiskUsage, ImportedArtifacts,
+    InspectQuery, InspectedAttestation, LogQuery, MetricsHistoryQuery, NodePing, PackageQuery,
+    PushQuery, PushedArtifact, RequestAddAuthorizedNode, RequestBuildStatus, RequestDockerBuild,
+    RequestDockerLog, RequestMavenBuild, RequestMavenLog, RequestPrefetchImage,
+    RequestRevokeArtifact, RequestTrustKey, RequestVerify, SearchQuery, SearchResult,
+    StoredArtifact, TransparencyLogQuery,
 };
-use crate::transparency_log::log::TransparencyLog;
+use crate::node_api::pagination::{ListQuery, Page, PaginationError};
+use crate::peer_metrics::history::parse_window;
+use crate::transparency_log::key_rotation::KeyRotationCertificate;
+use crate::transparency_log::log::{Operation, TransparencyLog};
+use crate::util::channel_io::{ChannelReader, ChannelWriter};
 
-use crate::artifact_service::service::ArtifactService;
+use crate::artifact_service::service::{artifact_name, ArtifactService};
+use bytes::{Buf, Bytes};
+use futures::{Stream, StreamExt};
+use itertools::Itertools;
 use libp2p::PeerId;
-use log::debug;
+use log::{debug, warn, LevelFilter};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{self, BufWriter};
 use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use warp::hyper::Body;
+use warp::sse::Event;
 use warp::{http::StatusCode, Rejection, Reply};
 
+const DEFAULT_AUDIT_LOG_LIMIT: usize = 100;
+const DEFAULT_LOG_LINES: usize = 100;
+const DEFAULT_METRICS_HISTORY_WINDOW: Duration = Duration::from_secs(60 * 60);
+const OPENAPI_DOCUMENT: &str = include_str!("../openapi.json");
+// The number of archive chunks that are buffered between the request or
+// response body and the blocking thread that reads or writes the archive.
+const ARCHIVE_CHANNEL_CAPACITY: usize = 16;
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ContentType {
     JSON,
@@ -136,12 +175,25 @@ pub async fn handle_add_authorized_node(
                 request_add_authorized_node.peer_id
             )),
         })?;
+    if !artifact_service.has_role(NodeRole::Authority) {
+        return Err(RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "This node is not authorized to add authorized nodes",
+            )),
+        }
+        .into());
+    }
 
     artifact_service
         .transparency_log_service
         .add_authorized_node(peer_id)
         .await
         .map_err(RegistryError::from)?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &peer_id.to_string(),
+        Some(String::from("added authorized node")),
+    );
 
     Ok(warp::http::response::Builder::new()
         .status(StatusCode::CREATED)
@@ -203,12 +255,20 @@ pub async fn handle_build_status(
         .body(build_status))
 }
 
-pub async fn handle_get_peers(mut p2p_client: Client) -> Result<impl Reply, Rejection> {
-    let peers = p2p_client.list_peers().await.map_err(RegistryError::from)?;
-    debug!("Got received_peers: {:?}", peers);
+pub async fn handle_get_peers(
+    query: ListQuery,
+    mut p2p_client: Client,
+) -> Result<impl Reply, Rejection> {
+    let peer_details = p2p_client
+        .list_peer_details()
+        .await
+        .map_err(RegistryError::from)?;
+    debug!("Got received_peers: {:?}", peer_details);
 
-    let str_peers: Vec<String> = peers.into_iter().map(|p| p.to_string()).collect();
-    let str_peers_as_json = serde_json::to_string(&str_peers).unwrap();
+    let connected_peers: Vec<ConnectedPeer> =
+        peer_details.into_iter().map(ConnectedPeer::from).collect();
+    let connected_peers = query.page(connected_peers).map_err(bad_list_query)?;
+    let str_peers_as_json = serde_json::to_string(&connected_peers).unwrap();
 
     Ok(warp::http::response::Builder::new()
         .header("Content-Type", "application/octet-stream")
@@ -217,8 +277,71 @@ pub async fn handle_get_peers(mut p2p_client: Client) -> Result<impl Reply, Reje
         .unwrap())
 }
 
-pub async fn handle_get_status(mut p2p_client: Client) -> Result<impl Reply, Rejection> {
-    let status = p2p_client.status().await.map_err(RegistryError::from)?;
+pub async fn handle_get_peer_details(
+    peer_id: String,
+    mut p2p_client: Client,
+) -> Result<impl Reply, Rejection> {
+    let parsed_peer_id = PeerId::from_str(&peer_id).map_err(|_| RegistryError {
+        code: RegistryErrorCode::BadRequest(format!("PeerId has invalid format: {}", peer_id)),
+    })?;
+
+    let mut peer_details = p2p_client
+        .get_peer_details(&parsed_peer_id)
+        .await
+        .map_err(RegistryError::from)?
+        .ok_or_else(|| RegistryError {
+            code: RegistryErrorCode::NotFound(format!("Peer {} is not connected", peer_id)),
+        })?;
+    match p2p_client.request_idle_metric(&parsed_peer_id).await {
+        Ok(quality_metric) => peer_details.quality_metric = Some(quality_metric),
+        Err(e) => debug!("Peer {} did not report its idle metric: {}", peer_id, e),
+    }
+
+    let peer_details_as_json = serde_json::to_string(&peer_details).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(peer_details_as_json)
+        .unwrap())
+}
+
+pub async fn handle_ping(p2p_client: Client) -> Result<impl Reply, Rejection> {
+    let ping = NodePing {
+        peer_id: p2p_client.local_peer_id.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+    };
+    let ping_as_json = serde_json::to_string(&ping).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(ping_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_status(
+    artifact_service: ArtifactService,
+    mut p2p_client: Client,
+) -> Result<impl Reply, Rejection> {
+    let mut status = p2p_client.status().await.map_err(RegistryError::from)?;
+    status.disk_usage = DiskUsage {
+        used_bytes: artifact_service
+            .artifact_storage
+            .disk_usage()
+            .map_err(RegistryError::from)?,
+        allocated_bytes: artifact_service.artifact_storage.disk_allocated,
+        evictions: artifact_service.artifact_storage.eviction_stats(),
+    };
+    status.last_scrub = artifact_service.last_scrub_report();
+    status.version = env!("CARGO_PKG_VERSION").to_owned();
+    status.uptime_secs = artifact_service.uptime().as_secs();
+    status.build_fallbacks = artifact_service.build_fallback_count();
+    status.rate_limits = artifact_service.rate_limits.status();
+    status.repository_usage = artifact_service
+        .artifact_storage
+        .repository_usage()
+        .map_err(RegistryError::from)?;
 
     let status_as_json = serde_json::to_string(&status).unwrap();
 
@@ -229,6 +352,908 @@ pub async fn handle_get_status(mut p2p_client: Client) -> Result<impl Reply, Rej
         .unwrap())
 }
 
+pub async fn handle_get_artifacts(
+    query: ListQuery,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let artifact_storage = &artifact_service.artifact_storage;
+    let to_stored_artifact = |metadata| stored_artifact(&artifact_service, metadata);
+
+    // Only a page of the artifacts is read, unless they must be sorted or
+    // filtered first.
+    let artifact_list: ArtifactList = if query.is_selective() {
+        let artifacts = artifact_storage
+            .list_artifact_metadata()
+            .map_err(RegistryError::from)?
+            .into_iter()
+            .map(to_stored_artifact)
+            .collect();
+        query.page(artifacts).map_err(bad_list_query)?
+    } else {
+        Page {
+            total: artifact_storage
+                .artifact_count()
+                .map_err(RegistryError::from)?,
+            offset: query.offset,
+            limit: query.page_size(),
+            items: artifact_storage
+                .artifact_metadata_page(query.offset, query.page_size())
+                .map_err(RegistryError::from)?
+                .into_iter()
+                .map(to_stored_artifact)
+                .collect(),
+        }
+    };
+    let artifact_list_as_json = serde_json::to_string(&artifact_list).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(artifact_list_as_json)
+        .unwrap())
+}
+
+pub async fn handle_search_artifacts(
+    query: SearchQuery,
+    artifact_service: ArtifactService,
+    mut p2p_client: Client,
+) -> Result<impl Reply, Rejection> {
+    if query.q.is_empty() {
+        return Err(warp::reject::custom(RegistryError {
+            code: RegistryErrorCode::BadRequest(String::from("The search query must not be empty")),
+        }));
+    }
+
+    let needle = query.q.to_lowercase();
+    let matches_package_type = |package_type: &PackageType| {
+        query
+            .package_type
+            .map_or(true, |queried_type| queried_type == *package_type)
+    };
+    let mut results: Vec<SearchResult> = artifact_service
+        .artifact_storage
+        .list_artifact_metadata()
+        .map_err(RegistryError::from)?
+        .into_iter()
+        .filter_map(|metadata| {
+            match (metadata.package_type, metadata.package_specific_artifact_id) {
+                (Some(package_type), Some(name))
+                    if matches_package_type(&package_type)
+                        && name.to_lowercase().contains(&needle) =>
+                {
+                    Some(SearchResult {
+                        package_type,
+                        name,
+                        artifact_id: metadata.artifact_id,
+                        artifact_hash: metadata.artifact_hash,
+                        stored_locally: true,
+                        providers: vec![],
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    if query.network {
+        let publishers = artifact_service.name_record_publishers().map_err(|e| {
+            warp::reject::custom(RegistryError {
+                code: RegistryErrorCode::Unknown(e.to_string()),
+            })
+        })?;
+        for package_type in [
+            PackageType::Docker,
+            PackageType::Maven2,
+            PackageType::Generic,
+        ]
+        .into_iter()
+        .filter(matches_package_type)
+        {
+            let name = artifact_name(&package_type, &query.q);
+            match p2p_client.resolve_name(&name, publishers.clone()).await {
+                Ok(Some(name_record))
+                    if !results
+                        .iter()
+                        .any(|result| result.artifact_id == name_record.artifact_id) =>
+                {
+                    results.push(SearchResult {
+                        package_type,
+                        name: query.q.clone(),
+                        artifact_id: name_record.artifact_id,
+                        artifact_hash: Some(name_record.artifact_hash),
+                        stored_locally: false,
+                        providers: vec![],
+                    })
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Unable to resolve the name {}: {}", name, e),
+            }
+        }
+
+        let local_peer_id = p2p_client.local_peer_id;
+        for result in results.iter_mut() {
+            match p2p_client.list_providers(&result.artifact_id).await {
+                Ok(peer_ids) => {
+                    result.providers = peer_ids
+                        .into_iter()
+                        .filter(|peer_id| *peer_id != local_peer_id)
+                        .map(|peer_id| peer_id.to_string())
+                        .sorted()
+                        .collect()
+                }
+                Err(e) => debug!(
+                    "Unable to list the providers of {}: {}",
+                    result.artifact_id, e
+                ),
+            }
+        }
+    }
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let results_as_json = serde_json::to_string(&results).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(results_as_json)
+        .unwrap())
+}
+
+pub async fn handle_inspect_artifact(
+    query: InspectQuery,
+    artifact_service: ArtifactService,
+    mut p2p_client: Client,
+) -> Result<impl Reply, Rejection> {
+    let transparency_log_service = &artifact_service.transparency_log_service;
+    let transparency_logs = match ArtifactDigest::from_str(&query.reference) {
+        // Hashes are recorded both with and without the algorithm prefix.
+        Ok(digest) => [
+            query.reference.clone(),
+            digest.hex.clone(),
+            digest.to_string(),
+        ]
+        .into_iter()
+        .unique()
+        .map(|artifact_hash| {
+            transparency_log_service.find_transparency_logs_by_artifact_hash(&artifact_hash)
+        })
+        .flatten_ok()
+        .collect::<Result<Vec<_>, _>>(),
+        Err(_) => transparency_log_service.search_transparency_logs(
+            &PackageType::Docker,
+            &get_package_specific_id(&query.reference),
+        ),
+    }
+    .map_err(RegistryError::from)?;
+    if transparency_logs.is_empty() {
+        return Err(warp::reject::custom(RegistryError {
+            code: RegistryErrorCode::NotFound(format!(
+                "No artifact is known for {}",
+                query.reference
+            )),
+        }));
+    }
+
+    let mut transparency_logs_by_hash: Vec<(String, Vec<TransparencyLog>)> = vec![];
+    for transparency_log in transparency_logs {
+        match transparency_logs_by_hash
+            .iter_mut()
+            .find(|(artifact_hash, _)| *artifact_hash == transparency_log.artifact_hash)
+        {
+            Some((_, transparency_logs)) => transparency_logs.push(transparency_log),
+            None => transparency_logs_by_hash.push((
+                transparency_log.artifact_hash.clone(),
+                vec![transparency_log],
+            )),
+        }
+    }
+
+    let mut inspections = vec![];
+    for (artifact_hash, transparency_logs) in transparency_logs_by_hash {
+        let mut stored = vec![];
+        let mut providers = BTreeSet::new();
+        for artifact_id in transparency_logs
+            .iter()
+            .filter(|transparency_log| transparency_log.operation == Operation::AddArtifact)
+            .map(|transparency_log| transparency_log.artifact_id.clone())
+            .unique()
+        {
+            if let Some(metadata) = artifact_service
+                .artifact_storage
+                .artifact_metadata(&artifact_id)
+                .map_err(RegistryError::from)?
+            {
+                stored.push(stored_artifact(&artifact_service, metadata));
+            }
+            match p2p_client.list_providers(&artifact_id).await {
+                Ok(peer_ids) => providers.extend(peer_ids.iter().map(PeerId::to_string)),
+                Err(e) => debug!("Unable to list the providers of {}: {}", artifact_id, e),
+            }
+        }
+        let attestations = artifact_service
+            .attestations(&artifact_hash)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|attestation| InspectedAttestation {
+                valid: artifact_service.attestation_is_valid(&attestation),
+                predicate_type: attestation.predicate_type,
+                builder_id: attestation.builder_id,
+                signers: attestation.signers,
+            })
+            .collect();
+        let revocation = transparency_log_service
+            .find_revocation(&artifact_hash)
+            .map_err(RegistryError::from)?;
+
+        inspections.push(ArtifactInspection {
+            artifact_hash,
+            stored,
+            transparency_logs,
+            attestations,
+            providers: providers.into_iter().collect(),
+            revocation,
+        });
+    }
+    let inspections_as_json = serde_json::to_string(&inspections).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(inspections_as_json)
+        .unwrap())
+}
+
+// The metadata of a stored artifact as it is shown to clients.
+fn stored_artifact(
+    artifact_service: &ArtifactService,
+    metadata: ArtifactMetadata,
+) -> StoredArtifact {
+    StoredArtifact {
+        artifact_hash: artifact_service
+            .transparency_log_service
+            .find_artifact_transparency_log(&metadata.artifact_id)
+            .ok()
+            .map(|transparency_log| transparency_log.artifact_hash),
+        pinned: artifact_service
+            .artifact_storage
+            .is_pinned(&metadata.artifact_id),
+        last_access: metadata
+            .last_access
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        artifact_id: metadata.artifact_id,
+        size: metadata.size,
+        media_type: metadata.media_type,
+        package_type: metadata.package_type,
+        package_specific_artifact_id: metadata.package_specific_artifact_id,
+        local_pulls: metadata.local_pulls,
+        peer_serves: metadata.peer_serves,
+    }
+}
+
+pub async fn handle_get_quarantine(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let quarantined_artifacts = artifact_service
+        .artifact_storage
+        .list_quarantined()
+        .map_err(RegistryError::from)?;
+    let quarantined_artifacts_as_json = serde_json::to_string(&quarantined_artifacts).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(quarantined_artifacts_as_json)
+        .unwrap())
+}
+
+pub async fn handle_attach_sbom(
+    artifact_digest: String,
+    body: Bytes,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let content = std::str::from_utf8(&body).map_err(|e| RegistryError {
+        code: RegistryErrorCode::BadRequest(e.to_string()),
+    })?;
+    let sbom = artifact_service
+        .attach_sbom(&artifact_digest, content)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::BadRequest(e.to_string()),
+        })?;
+    let sbom_as_json = serde_json::to_string(&sbom).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(sbom_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_sboms(
+    artifact_digest: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let sboms = artifact_service
+        .sboms(&artifact_digest)
+        .map_err(RegistryError::from)?;
+    let sboms_as_json = serde_json::to_string(&sboms).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(sboms_as_json)
+        .unwrap())
+}
+
+pub async fn handle_ingest_attestation(
+    envelope: Envelope,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let attestations =
+        artifact_service
+            .ingest_attestation(envelope)
+            .map_err(|e| RegistryError {
+                code: RegistryErrorCode::BadRequest(e.to_string()),
+            })?;
+    let attestations_as_json = serde_json::to_string(&attestations).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(attestations_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_attestations(
+    artifact_digest: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let attestations = artifact_service
+        .attestations(&artifact_digest)
+        .map_err(RegistryError::from)?;
+    let attestations_as_json = serde_json::to_string(&attestations).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(attestations_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_provenance(
+    artifact_digest: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let chain = artifact_service
+        .provenance(&artifact_digest)
+        .map_err(RegistryError::from)?;
+    let chain_as_json = serde_json::to_string(&chain).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(chain_as_json)
+        .unwrap())
+}
+
+pub async fn handle_export_artifacts(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    // The archive is streamed while it is written, so a failure halfway
+    // aborts the response instead of returning an error status.
+    let (sender, receiver) = mpsc::channel(ARCHIVE_CHANNEL_CAPACITY);
+    let writer = BufWriter::with_capacity(ARCHIVE_CHUNK_SIZE, ChannelWriter::new(sender.clone()));
+    tokio::spawn(async move {
+        if let Err(e) = artifact_service.export_artifacts(writer).await {
+            warn!("Failed to export artifacts: {}", e);
+            let _ = sender
+                .send(Err(io::Error::new(io::ErrorKind::Other, e.to_string())))
+                .await;
+        }
+    });
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/x-tar")
+        .status(StatusCode::OK)
+        .body(Body::wrap_stream(ReceiverStream::new(receiver)))
+        .unwrap())
+}
+
+pub async fn handle_import_artifacts(
+    archive: impl Stream<Item = Result<impl Buf, warp::Error>>,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let (sender, receiver) = mpsc::channel(ARCHIVE_CHANNEL_CAPACITY);
+    let receive_archive = async move {
+        let mut archive = Box::pin(archive);
+        while let Some(chunk) = archive.next().await {
+            let chunk = chunk
+                .map(|mut chunk| chunk.copy_to_bytes(chunk.remaining()))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e));
+            // the import stopped reading when it failed
+            if sender.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    };
+    let (_, imported_archive) = futures::join!(
+        receive_archive,
+        artifact_service.import_artifacts(ChannelReader::new(receiver))
+    );
+    let imported_archive = imported_archive.map_err(|e| RegistryError {
+        code: RegistryErrorCode::BadRequest(format!("Unable to import artifacts: {}", e)),
+    })?;
+
+    let imported_artifacts_as_json = serde_json::to_string(&ImportedArtifacts {
+        artifact_ids: imported_archive.imported_artifacts,
+        rejected_artifact_ids: imported_archive.rejected_artifacts,
+    })
+    .unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(imported_artifacts_as_json)
+        .unwrap())
+}
+
+pub async fn handle_prefetch_image(
+    request_prefetch_image: RequestPrefetchImage,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let image =
+        ImageReference::parse(&request_prefetch_image.image).map_err(|e| RegistryError {
+            code: RegistryErrorCode::BadRequest(e.to_string()),
+        })?;
+    let prefetched_image = prefetch_image(&mut artifact_service, &image)
+        .await
+        .map_err(|e| {
+            let message = format!("{:#}", e);
+            RegistryError::from_artifact_error(e, RegistryErrorCode::NotFound(message))
+        })?;
+    let prefetched_image_as_json = serde_json::to_string(&prefetched_image).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(prefetched_image_as_json)
+        .unwrap())
+}
+
+pub async fn handle_push_artifact(
+    query: PushQuery,
+    artifact: Bytes,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    if !artifact_service.has_role(NodeRole::Build) {
+        return Err(RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "This node is not authorized to publish artifacts",
+            )),
+        }
+        .into());
+    }
+
+    let transparency_log = artifact_service
+        .push_generic_artifact(&query.name, artifact.as_ref())
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::BadRequest(format!("Unable to push artifact: {}", e)),
+        })?;
+
+    let pushed_artifact_as_json = serde_json::to_string(&PushedArtifact {
+        name: query.name,
+        artifact_id: transparency_log.artifact_id,
+        artifact_hash: ArtifactDigest {
+            algorithm: HashAlgorithm::Sha256,
+            hex: transparency_log.artifact_hash,
+        }
+        .to_string(),
+    })
+    .unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(pushed_artifact_as_json)
+        .unwrap())
+}
+
+pub async fn handle_revoke_artifact(
+    request_revoke_artifact: RequestRevokeArtifact,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    if !artifact_service.has_role(NodeRole::Authority) {
+        return Err(RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "This node is not authorized to revoke artifacts",
+            )),
+        }
+        .into());
+    }
+
+    let revocation = artifact_service
+        .revoke_artifact(
+            &request_revoke_artifact.artifact_hash,
+            &request_revoke_artifact.reason,
+        )
+        .await
+        .map_err(RegistryError::from)?;
+    let revocation_as_json = serde_json::to_string(&revocation).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(revocation_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_trusted_keys(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let trusted_keys = artifact_service
+        .trust_store
+        .list()
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(e.to_string()),
+        })?;
+    let trusted_keys_as_json = serde_json::to_string(&trusted_keys).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(trusted_keys_as_json)
+        .unwrap())
+}
+
+pub async fn handle_add_trusted_key(
+    request_trust_key: RequestTrustKey,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let peer_id = PeerId::from_str(&request_trust_key.peer_id).map_err(|_| RegistryError {
+        code: RegistryErrorCode::BadRequest(format!(
+            "PeerId has invalid format: {}",
+            request_trust_key.peer_id
+        )),
+    })?;
+
+    let trusted_key = artifact_service
+        .trust_store
+        .add(&peer_id, request_trust_key.roles)
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::BadRequest(e.to_string()),
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &trusted_key.fingerprint,
+        Some(format!("trusted key of {}", trusted_key.peer_id)),
+    );
+    let trusted_key_as_json = serde_json::to_string(&trusted_key).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(trusted_key_as_json)
+        .unwrap())
+}
+
+pub async fn handle_remove_trusted_key(
+    fingerprint: String,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let trusted_key = artifact_service
+        .trust_store
+        .remove(&fingerprint)
+        .map_err(|e| RegistryError {
+            code: match e {
+                TrustStoreError::NotFound(_) => RegistryErrorCode::NotFound(e.to_string()),
+                _ => RegistryErrorCode::Unknown(e.to_string()),
+            },
+        })?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &trusted_key.fingerprint,
+        Some(format!("removed trusted key of {}", trusted_key.peer_id)),
+    );
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::OK)
+        .body(""))
+}
+
+pub async fn handle_add_key_rotation(
+    certificate: KeyRotationCertificate,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let key_rotation = artifact_service
+        .add_key_rotation(&certificate)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::BadRequest(e.to_string()),
+        })?;
+    let key_rotation_as_json = serde_json::to_string(&key_rotation).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::CREATED)
+        .body(key_rotation_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_key_rotations(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let key_rotations = artifact_service
+        .transparency_log_service
+        .find_key_rotations()
+        .map_err(RegistryError::from)?;
+    let key_rotations_as_json = serde_json::to_string(&key_rotations).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(key_rotations_as_json)
+        .unwrap())
+}
+
+pub async fn handle_node_events(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let events = futures::stream::unfold(
+        artifact_service.events.subscribe(),
+        |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Event stream skipped {} events", skipped)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    )
+    .map(|event| Event::default().event(event.kind.name()).json_data(&event));
+
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(events)))
+}
+
+fn log_level_filter(query: &LogQuery) -> Result<LevelFilter, RegistryError> {
+    match &query.level {
+        Some(level) => LevelFilter::from_str(level).map_err(|_| RegistryError {
+            code: RegistryErrorCode::BadRequest(format!("Unknown log level: {}", level)),
+        }),
+        None => Ok(LevelFilter::Trace),
+    }
+}
+
+pub async fn handle_get_node_logs(query: LogQuery) -> Result<impl Reply, Rejection> {
+    let level = log_level_filter(&query)?;
+    let entries = log_buffer().tail(level, query.lines.unwrap_or(DEFAULT_LOG_LINES));
+    let entries_as_json = serde_json::to_string(&entries).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(entries_as_json)
+        .unwrap())
+}
+
+pub async fn handle_follow_node_logs(query: LogQuery) -> Result<impl Reply, Rejection> {
+    let level = log_level_filter(&query)?;
+    // Subscribe before reading the tail, so no entry is lost in between.
+    let receiver = log_buffer().subscribe();
+    let tail = log_buffer().tail(level, query.lines.unwrap_or(DEFAULT_LOG_LINES));
+    let entries = futures::stream::iter(tail)
+        .chain(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(entry) => return Some((entry, receiver)),
+                        // Logging here would feed the log we are following.
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        ))
+        .filter(move |entry: &LogEntry| futures::future::ready(entry.is_enabled(level)))
+        .map(|entry| Event::default().event("log").json_data(&entry));
+
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(entries)))
+}
+
+pub async fn handle_get_metrics_history(
+    query: MetricsHistoryQuery,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let window = match &query.window {
+        Some(window) => parse_window(window).map_err(|message| RegistryError {
+            code: RegistryErrorCode::BadRequest(message),
+        })?,
+        None => DEFAULT_METRICS_HISTORY_WINDOW,
+    };
+    let samples = artifact_service.metrics_history.window(window);
+    let samples_as_json = serde_json::to_string(&samples).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(samples_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_openapi() -> Result<impl Reply, Rejection> {
+    let mut openapi: serde_json::Value = serde_json::from_str(OPENAPI_DOCUMENT).unwrap();
+    openapi["info"]["version"] = serde_json::Value::from(env!("CARGO_PKG_VERSION"));
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(openapi.to_string())
+        .unwrap())
+}
+
+pub async fn handle_get_jwks(artifact_service: ArtifactService) -> Result<impl Reply, Rejection> {
+    let jwks_as_json = serde_json::to_string(&artifact_service.local_public_keys()).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(jwks_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_peer_public_keys(
+    peer_id: String,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let peer_id = PeerId::from_str(&peer_id).map_err(|e| RegistryError {
+        code: RegistryErrorCode::BadRequest(e.to_string()),
+    })?;
+    let jwks = artifact_service
+        .find_public_keys(&peer_id)
+        .await
+        .map_err(RegistryError::from)?
+        .ok_or(RegistryError {
+            code: RegistryErrorCode::NotFound(format!("No public keys found for peer {}", peer_id)),
+        })?;
+    let jwks_as_json = serde_json::to_string(&jwks).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(jwks_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_package_metadata(
+    query: PackageQuery,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let metadata_as_json = match &query.version {
+        Some(version) => {
+            let key = package_version_key(query.package_type, &query.name, version);
+            artifact_service
+                .find_metadata::<PackageVersion>(&key)
+                .await
+                .map_err(RegistryError::from)?
+                .map(|package_version| serde_json::to_string(&package_version).unwrap())
+        }
+        None => {
+            let key = package_key(query.package_type, &query.name);
+            artifact_service
+                .find_metadata::<Package>(&key)
+                .await
+                .map_err(RegistryError::from)?
+                .map(|package| serde_json::to_string(&package).unwrap())
+        }
+    }
+    .ok_or(RegistryError {
+        code: RegistryErrorCode::NotFound(format!(
+            "No package metadata found for {} {}",
+            query.package_type, query.name
+        )),
+    })?;
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(metadata_as_json)
+        .unwrap())
+}
+
+pub async fn handle_publish_metadata<T: Signed + Send>(
+    metadata: T,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    if !artifact_service.has_role(NodeRole::Build) {
+        return Err(RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "This node is not authorized to publish package metadata",
+            )),
+        }
+        .into());
+    }
+
+    artifact_service
+        .publish_metadata(&metadata)
+        .await
+        .map_err(RegistryError::from)?;
+    artifact_service.audit_log.record(
+        AuditAction::Admin,
+        &metadata.key(),
+        Some(String::from("published package metadata")),
+    );
+
+    Ok(warp::http::response::Builder::new()
+        .status(StatusCode::CREATED)
+        .body("")
+        .unwrap())
+}
+
+pub async fn handle_get_revocations(
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let revocations = artifact_service
+        .revocations()
+        .map_err(RegistryError::from)?;
+    let revocations_as_json = serde_json::to_string(&revocations).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(revocations_as_json)
+        .unwrap())
+}
+
+pub async fn handle_verify(
+    request_verify: RequestVerify,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let report = match (request_verify.document, request_verify.artifact_hash) {
+        (Some(document), _) => artifact_service.verify_document(&document),
+        (None, Some(artifact_hash)) => artifact_service
+            .verify_artifact_hash(&artifact_hash)
+            .map_err(RegistryError::from)?,
+        (None, None) => {
+            return Err(RegistryError {
+                code: RegistryErrorCode::BadRequest(String::from(
+                    "Either a document or an artifact hash must be specified",
+                )),
+            }
+            .into())
+        }
+    };
+    let report_as_json = serde_json::to_string(&report).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(report_as_json)
+        .unwrap())
+}
+
+pub async fn handle_get_audit_log(
+    query: AuditLogQuery,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let entries = artifact_service
+        .audit_log
+        .recent(query.limit.unwrap_or(DEFAULT_AUDIT_LOG_LIMIT))
+        .map_err(RegistryError::from)?;
+    let entries_as_json = serde_json::to_string(&entries).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(entries_as_json)
+        .unwrap())
+}
+
 pub async fn handle_inspect_log_docker(
     request_docker_log: RequestDockerLog,
     artifact_service: ArtifactService,
@@ -256,6 +1281,55 @@ pub async fn handle_inspect_log_maven(
     request_maven_log.format().create_response(result)
 }
 
+pub async fn handle_query_transparency_logs(
+    query: TransparencyLogQuery,
+    list_query: ListQuery,
+    artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let transparency_log_service = &artifact_service.transparency_log_service;
+    let transparency_logs = match query.artifact_hash {
+        Some(artifact_hash) => {
+            transparency_log_service.find_transparency_logs_by_artifact_hash(&artifact_hash)
+        }
+        None => transparency_log_service.find_transparency_logs_by_time_range(
+            query.from.unwrap_or(0),
+            query.to.unwrap_or(u64::MAX),
+        ),
+    }
+    .map_err(RegistryError::from)?;
+    let transparency_logs = list_query.page(transparency_logs).map_err(bad_list_query)?;
+    let transparency_logs_as_json = serde_json::to_string(&transparency_logs).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(transparency_logs_as_json)
+        .unwrap())
+}
+
+pub async fn handle_federation_sync(
+    query: FederationSyncQuery,
+    mut artifact_service: ArtifactService,
+) -> Result<impl Reply, Rejection> {
+    let sync = artifact_service
+        .federation_sync(&query)
+        .await
+        .map_err(RegistryError::from)?;
+    let sync_as_json = serde_json::to_string(&sync).unwrap();
+
+    Ok(warp::http::response::Builder::new()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(sync_as_json)
+        .unwrap())
+}
+
+fn bad_list_query(error: PaginationError) -> RegistryError {
+    RegistryError {
+        code: RegistryErrorCode::BadRequest(error.to_string()),
+    }
+}
+
 fn get_package_specific_id(package_specific_id: &str) -> String {
     match package_specific_id.contains('/') {
         true => package_specific_id.to_owned(),
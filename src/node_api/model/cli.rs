@@ -14,7 +14,16 @@
    limitations under the License.
 */
 
+use crate::artifact_service::model::{PackageType, RepositoryUsage, ScrubReport};
+use crate::artifact_service::storage::EvictionStats;
+use crate::artifact_service::trust_store::TrustRole;
+use crate::network::peer_identity::{ConnectionDirection, ProtocolCompatibility};
+use crate::network::signed_document::SignedDocument;
+use crate::network::transfer_metrics::{TransferCounters, TransferMetrics};
 use crate::node_api::handlers::swarm;
+use crate::node_api::pagination::Page;
+use crate::transparency_log::log::{Revocation, TransparencyLog};
+use crate::util::rate_limit::RateLimitStatus;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Default)]
@@ -22,6 +31,287 @@ pub struct Status {
     pub peers_count: usize,
     pub peer_id: String,
     pub peer_addrs: Vec<String>,
+    #[serde(default)]
+    pub transfer_metrics: TransferMetrics,
+    #[serde(default)]
+    pub disk_usage: DiskUsage,
+    #[serde(default)]
+    pub last_scrub: Option<ScrubReport>,
+    #[serde(default)]
+    pub repository_usage: Vec<RepositoryUsage>,
+    /// The version of the node software.
+    #[serde(default)]
+    pub version: String,
+    /// The number of seconds since the node was started.
+    #[serde(default)]
+    pub uptime_secs: u64,
+    /// The number of requested artifacts that were not available on the
+    /// p2p network and fell back to a build from source.
+    #[serde(default)]
+    pub build_fallbacks: u64,
+    #[serde(default)]
+    pub rate_limits: RateLimitStatus,
+    /// Whether other peers can dial the node, as probed by AutoNAT:
+    /// public, private or unknown.
+    #[serde(default)]
+    pub nat_status: String,
+}
+
+/// The response to a ping: the identity of the node, without the cost of
+/// collecting the full status.
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct NodePing {
+    pub peer_id: String,
+    /// The version of the node software.
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub used_bytes: u64,
+    pub allocated_bytes: Option<u64>,
+    #[serde(default)]
+    pub evictions: EvictionStats,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ConnectedPeer {
+    pub peer_id: String,
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub agent_version: Option<String>,
+    #[serde(default)]
+    pub direction: Option<ConnectionDirection>,
+    #[serde(default)]
+    pub remote_addr: Option<String>,
+    /// The quality metric the peer advertised last, lower is less busy.
+    #[serde(default)]
+    pub quality_metric: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct PeerDetails {
+    pub peer_id: String,
+    pub agent_version: Option<String>,
+    pub protocol_version: Option<String>,
+    pub protocols: Vec<String>,
+    pub listen_addrs: Vec<String>,
+    pub compatibility: Option<ProtocolCompatibility>,
+    pub latency_ms: Option<u64>,
+    /// Which side opened the first connection to the peer.
+    #[serde(default)]
+    pub direction: Option<ConnectionDirection>,
+    /// The address the peer is connected on.
+    #[serde(default)]
+    pub remote_addr: Option<String>,
+    /// The artifact traffic exchanged with the peer.
+    #[serde(default)]
+    pub transfer: TransferCounters,
+    /// The quality metric the peer advertised recently or when asked, lower
+    /// is less busy.
+    #[serde(default)]
+    pub quality_metric: Option<f64>,
+}
+
+impl From<PeerDetails> for ConnectedPeer {
+    fn from(peer_details: PeerDetails) -> Self {
+        ConnectedPeer {
+            peer_id: peer_details.peer_id,
+            latency_ms: peer_details.latency_ms,
+            agent_version: peer_details.agent_version,
+            direction: peer_details.direction,
+            remote_addr: peer_details.remote_addr,
+            quality_metric: peer_details.quality_metric,
+        }
+    }
+}
+
+/// An artifact that is stored on the node.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StoredArtifact {
+    pub artifact_id: String,
+    pub artifact_hash: Option<String>,
+    pub size: u64,
+    pub media_type: Option<String>,
+    pub package_type: Option<PackageType>,
+    pub package_specific_artifact_id: Option<String>,
+    /// The time of the last access, in seconds since the unix epoch.
+    pub last_access: u64,
+    pub pinned: bool,
+    #[serde(default)]
+    pub local_pulls: u64,
+    #[serde(default)]
+    pub peer_serves: u64,
+}
+
+/// A page of the artifacts that are stored on the node.
+pub type ArtifactList = Page<StoredArtifact>;
+
+/// The artifacts that were imported from an archive.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImportedArtifacts {
+    pub artifact_ids: Vec<String>,
+    /// The artifacts that were not imported because this node has no
+    /// transparency log to verify them against.
+    #[serde(default)]
+    pub rejected_artifact_ids: Vec<String>,
+}
+
+/// A package, or a version of it when the version is specified, whose
+/// signed metadata is retrieved.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PackageQuery {
+    pub package_type: PackageType,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// The name under which a generic artifact is pushed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PushQuery {
+    pub name: String,
+}
+
+/// A generic artifact that was pushed to the node, with its content address.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PushedArtifact {
+    pub name: String,
+    pub artifact_id: String,
+    /// The hash of the artifact, prefixed with its algorithm.
+    pub artifact_hash: String,
+}
+
+/// A query of the transparency logs, either by artifact hash or by the time
+/// range, in seconds since the epoch, in which they were recorded.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TransparencyLogQuery {
+    pub artifact_hash: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+/// A query of everything the node knows about an artifact, by its hash
+/// (e.g. sha256:...) or by a Docker image reference (e.g. alpine:3.16).
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct InspectQuery {
+    pub reference: String,
+}
+
+/// A search of artifacts by a part of their package specific name, e.g.
+/// alpine or commons-lang3.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub package_type: Option<PackageType>,
+    /// Also resolve the name records in the DHT and look up the peers that
+    /// provide the matches. Name records are only found by their exact name.
+    #[serde(default)]
+    pub network: bool,
+}
+
+/// An artifact that matches a search, with the places it can be retrieved
+/// from.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SearchResult {
+    pub package_type: PackageType,
+    pub name: String,
+    pub artifact_id: String,
+    pub artifact_hash: Option<String>,
+    /// Whether the artifact is stored on this node.
+    pub stored_locally: bool,
+    /// The other peers that provide the artifact on the p2p network.
+    pub providers: Vec<String>,
+}
+
+/// Everything the node knows about the artifacts with one hash.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArtifactInspection {
+    pub artifact_hash: String,
+    /// The copies of the artifact in the local storage.
+    pub stored: Vec<StoredArtifact>,
+    pub transparency_logs: Vec<TransparencyLog>,
+    pub attestations: Vec<InspectedAttestation>,
+    /// The peers that provide the artifact on the p2p network.
+    pub providers: Vec<String>,
+    pub revocation: Option<Revocation>,
+}
+
+/// An attestation about an artifact and whether its signature is still
+/// valid, i.e. signed by a key that is trusted today.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct InspectedAttestation {
+    pub predicate_type: String,
+    pub builder_id: Option<String>,
+    pub signers: Vec<String>,
+    pub valid: bool,
+}
+
+/// A query of the most recent entries of the audit log.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AuditLogQuery {
+    pub limit: Option<usize>,
+}
+
+/// A query of the node log: the minimum level of the entries, e.g. `warn`,
+/// and the number of recent entries to return.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LogQuery {
+    pub level: Option<String>,
+    pub lines: Option<usize>,
+}
+
+/// A query of the metrics history: the period to return, e.g. `1h`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MetricsHistoryQuery {
+    pub window: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RequestRevokeArtifact {
+    pub artifact_hash: String,
+    pub reason: String,
+}
+
+/// Either a signed document or the hash of a stored artifact to verify.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RequestVerify {
+    pub document: Option<SignedDocument>,
+    pub artifact_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RequestDisconnectPeer {
+    /// Refuse new connections of the peer until the node is restarted.
+    #[serde(default)]
+    pub ban: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RequestPrefetchImage {
+    /// The image reference as `docker pull` accepts it, e.g. alpine:3.16.
+    pub image: String,
+}
+
+/// A network benchmark against a peer, or against the connected peer with
+/// the lowest latency when no peer is given.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RequestBench {
+    pub peer_id: Option<String>,
+    /// The number of bytes to upload and download in every round.
+    pub size: Option<u64>,
+    pub rounds: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RequestConnectPeer {
+    /// The multiaddress of the peer, ending with its peer ID.
+    pub address: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RequestTrustKey {
+    pub peer_id: String,
+    pub roles: Vec<TrustRole>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
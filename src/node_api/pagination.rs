@@ -0,0 +1,233 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use thiserror::Error;
+
+/// The number of items in a page when no limit is specified.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// The maximum number of items in a page.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaginationError {
+    #[error("Invalid filter {0}, expected field:value")]
+    InvalidFilter(String),
+}
+
+/// The query parameters shared by all list endpoints, e.g.
+/// `?offset=100&limit=50&sort=-size&filter=package_type:Docker`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ListQuery {
+    /// The number of items to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// The maximum number of items to return, at most [`MAX_PAGE_SIZE`].
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
+    /// The field to sort by, prefixed with `-` to sort in descending order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Comma separated `field:value` filters that all must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        ListQuery {
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+            sort: None,
+            filter: None,
+        }
+    }
+}
+
+/// A page of the items of a list endpoint.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Page<T> {
+    /// The number of items that match the filters, on all pages.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<T>,
+}
+
+impl ListQuery {
+    /// The limit of the page, capped at [`MAX_PAGE_SIZE`].
+    pub fn page_size(&self) -> usize {
+        self.limit.min(MAX_PAGE_SIZE)
+    }
+
+    /// Whether the items must be sorted or filtered, which requires all of
+    /// them instead of only the requested page.
+    pub fn is_selective(&self) -> bool {
+        self.sort.is_some() || self.filter.is_some()
+    }
+
+    /// Filter, sort and page the items by the fields of their JSON
+    /// representation. Items without a filtered field don't match the filter.
+    pub fn page<T: Serialize>(&self, items: Vec<T>) -> Result<Page<T>, PaginationError> {
+        let filters = self.filters()?;
+        let mut items: Vec<(Value, T)> = items
+            .into_iter()
+            .map(|item| (serde_json::to_value(&item).unwrap_or(Value::Null), item))
+            .filter(|(value, _)| {
+                filters.iter().all(|(field, expected)| {
+                    field_as_string(value, field).as_deref() == Some(*expected)
+                })
+            })
+            .collect();
+
+        if let Some(sort) = &self.sort {
+            let (field, descending) = match sort.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (sort.as_str(), false),
+            };
+            items.sort_by(|(a, _), (b, _)| {
+                let ordering = compare_values(&a[field], &b[field]);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        Ok(Page {
+            total: items.len(),
+            offset: self.offset,
+            limit: self.page_size(),
+            items: items
+                .into_iter()
+                .skip(self.offset)
+                .take(self.page_size())
+                .map(|(_, item)| item)
+                .collect(),
+        })
+    }
+
+    fn filters(&self) -> Result<Vec<(&str, &str)>, PaginationError> {
+        match &self.filter {
+            Some(filter) => filter
+                .split(',')
+                .filter(|filter| !filter.is_empty())
+                .map(|filter| {
+                    filter
+                        .split_once(':')
+                        .ok_or_else(|| PaginationError::InvalidFilter(filter.to_owned()))
+                })
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+fn field_as_string(value: &Value, field: &str) -> Option<String> {
+    match value.get(field)? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+// Numbers are compared numerically, strings and booleans by value. Missing
+// fields sort first.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, PartialEq)]
+    struct Item {
+        name: &'static str,
+        kind: &'static str,
+        size: u64,
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "a",
+                kind: "docker",
+                size: 30,
+            },
+            Item {
+                name: "b",
+                kind: "maven",
+                size: 10,
+            },
+            Item {
+                name: "c",
+                kind: "docker",
+                size: 20,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_page_with_sort_and_filter() {
+        let query = ListQuery {
+            offset: 1,
+            limit: 1,
+            sort: Some(String::from("-size")),
+            filter: Some(String::from("kind:docker")),
+        };
+
+        let page = query.page(items()).unwrap();
+
+        assert_eq!(page.total, 2);
+        assert_eq!(page.offset, 1);
+        assert_eq!(page.limit, 1);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].name, "c");
+    }
+
+    #[test]
+    fn test_page_with_invalid_filter() {
+        let query = ListQuery {
+            filter: Some(String::from("docker")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.page(items()).unwrap_err(),
+            PaginationError::InvalidFilter(String::from("docker"))
+        );
+    }
+}
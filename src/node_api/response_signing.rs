@@ -0,0 +1,114 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+use crate::network::client::Client;
+use crate::network::signed_document::SignedDocument;
+use anyhow::bail;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use libp2p::PeerId;
+use log::warn;
+use sha2::{Digest, Sha256};
+use warp::http::HeaderValue;
+use warp::hyper::body::HttpBody;
+use warp::hyper::Body;
+use warp::reply::Response;
+use warp::{Rejection, Reply};
+
+/// The header that holds the detached signature of a node API response.
+pub const SIGNATURE_HEADER: &str = "x-pyrsia-signature";
+
+/// The content that is signed for a response: the SHA-256 digest of its body.
+pub fn body_digest(body: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(body)))
+}
+
+/// Encodes a signed body digest as the value of the signature header.
+pub fn encode_signature(document: &SignedDocument) -> anyhow::Result<String> {
+    Ok(BASE64.encode(document.to_bytes()?))
+}
+
+/// Verifies the value of the signature header against the response body and
+/// returns the id of the node that signed it.
+pub fn verify_signature(header_value: &str, body: &[u8]) -> anyhow::Result<PeerId> {
+    let document = SignedDocument::from_bytes(&BASE64.decode(header_value)?)?;
+    if !document.verify() {
+        bail!("The signature of the response is invalid");
+    }
+    if document.content != body_digest(body) {
+        bail!("The signature does not match the response body");
+    }
+    match document.publisher() {
+        Some(publisher) => Ok(publisher),
+        None => bail!("The signer of the response is unknown"),
+    }
+}
+
+/// Adds a detached signature of the body to a response, when a client to
+/// sign with is passed. The body is buffered to calculate its digest, so
+/// only responses whose size is known up front are signed. Streamed
+/// responses, like event streams and archives, are passed on as they are.
+pub async fn sign_response<R: Reply>(
+    reply: R,
+    p2p_client: Option<Client>,
+) -> Result<Response, Rejection> {
+    let response = reply.into_response();
+    let mut p2p_client = match p2p_client {
+        Some(p2p_client) if HttpBody::size_hint(response.body()).exact().is_some() => p2p_client,
+        _ => return Ok(response),
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body = warp::hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| RegistryError {
+            code: RegistryErrorCode::Unknown(e.to_string()),
+        })?;
+    let signature = p2p_client
+        .sign_document(&body_digest(&body))
+        .await
+        .and_then(|document| Ok(HeaderValue::from_str(&encode_signature(&document)?)?));
+    match signature {
+        Ok(header_value) => {
+            parts.headers.insert(SIGNATURE_HEADER, header_value);
+        }
+        Err(e) => warn!("Failed to sign the response: {}", e),
+    }
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn test_verify_signature() {
+        let keypair = Keypair::generate_ed25519();
+        let body = b"{\"peers_count\":1}";
+        let document = SignedDocument::new(&keypair, &body_digest(body)).unwrap();
+        let signature = encode_signature(&document).unwrap();
+
+        assert_eq!(
+            verify_signature(&signature, body).unwrap(),
+            keypair.public().to_peer_id()
+        );
+        assert!(verify_signature(&signature, b"{\"peers_count\":2}").is_err());
+        assert!(verify_signature("invalid", body).is_err());
+    }
+}
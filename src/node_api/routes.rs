@@ -14,25 +14,48 @@
    limitations under the License.
 */
 
+use super::api_tokens::require_api_token;
+use super::handlers::admin::*;
 use super::handlers::swarm::*;
 use super::model::cli::{RequestDockerBuild, RequestMavenBuild};
+use super::pagination::ListQuery;
+use crate::artifact_service::federation::FederationSyncQuery;
+use crate::artifact_service::model::{Package, PackageVersion};
 use crate::artifact_service::service::ArtifactService;
 use crate::network::client::Client;
 use crate::node_api::model::cli::{
-    RequestAddAuthorizedNode, RequestBuildStatus, RequestDockerLog, RequestMavenLog,
+    AuditLogQuery, InspectQuery, LogQuery, MetricsHistoryQuery, PackageQuery, PushQuery,
+    RequestAddAuthorizedNode, RequestBench, RequestBuildStatus, RequestConnectPeer,
+    RequestDisconnectPeer, RequestDockerLog, RequestMavenLog, RequestPrefetchImage,
+    RequestRevokeArtifact, RequestTrustKey, RequestVerify, SearchQuery, TransparencyLogQuery,
 };
+use crate::transparency_log::key_rotation::KeyRotationCertificate;
 use warp::Filter;
 
+// The largest archive that can be imported, the storage quota still applies.
+const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024 * 1024;
+
 pub fn make_node_routes(
     artifact_service: ArtifactService,
     p2p_client: Client,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let api_tokens = artifact_service.api_tokens.clone();
+    let api_tokens_filter = {
+        let api_tokens = api_tokens.clone();
+        warp::any().map(move || api_tokens.clone())
+    };
     let artifact_service_filter = warp::any().map(move || artifact_service.clone());
     let p2p_client_filter = warp::any().map(move || p2p_client.clone());
+    let admin_client = warp::header::optional::<String>("authorization")
+        .and(warp::addr::remote())
+        .and(api_tokens_filter)
+        .and_then(ensure_admin_client)
+        .untuple_one();
 
     let add_authorized_node = warp::path!("authorized_node")
         .and(warp::post())
         .and(warp::path::end())
+        .and(admin_client.clone())
         .and(warp::body::content_length_limit(1024 * 8))
         .and(warp::body::json::<RequestAddAuthorizedNode>())
         .and(artifact_service_filter.clone())
@@ -65,15 +88,385 @@ pub fn make_node_routes(
     let peers = warp::path!("peers")
         .and(warp::get())
         .and(warp::path::end())
+        .and(warp::query::<ListQuery>())
         .and(p2p_client_filter.clone())
         .and_then(handle_get_peers);
 
+    let peer_details = warp::path!("peers" / String)
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(p2p_client_filter.clone())
+        .and_then(handle_get_peer_details);
+
+    let ping = warp::path!("ping")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(p2p_client_filter.clone())
+        .and_then(handle_ping);
+
     let status = warp::path!("status")
         .and(warp::get())
         .and(warp::path::end())
-        .and(p2p_client_filter)
+        .and(artifact_service_filter.clone())
+        .and(p2p_client_filter.clone())
         .and_then(handle_get_status);
 
+    let artifacts = warp::path!("artifacts")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<ListQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_artifacts);
+
+    let search_artifacts = warp::path!("search")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<SearchQuery>())
+        .and(artifact_service_filter.clone())
+        .and(p2p_client_filter.clone())
+        .and_then(handle_search_artifacts);
+
+    let inspect_artifact = warp::path!("inspect")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<InspectQuery>())
+        .and(artifact_service_filter.clone())
+        .and(p2p_client_filter)
+        .and_then(handle_inspect_artifact);
+
+    let push_artifact = warp::path!("artifacts" / "generic")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::query::<PushQuery>())
+        .and(warp::body::content_length_limit(1024 * 1024 * 1024))
+        .and(warp::body::bytes())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_push_artifact);
+
+    let prefetch_image = warp::path!("prefetch")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestPrefetchImage>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_prefetch_image);
+
+    let quarantine = warp::path!("quarantine")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_quarantine);
+
+    let attach_sbom = warp::path!("sboms" / String)
+        .and(warp::put())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 1024 * 16))
+        .and(warp::body::bytes())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_attach_sbom);
+
+    let sboms = warp::path!("sboms" / String)
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_sboms);
+
+    let ingest_attestation = warp::path!("attestations")
+        .and(warp::put())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_ingest_attestation);
+
+    let attestations = warp::path!("attestations" / String)
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_attestations);
+
+    let provenance = warp::path!("attestations" / String / "provenance")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_provenance);
+
+    let transparency_logs = warp::path!("transparency_logs")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<TransparencyLogQuery>())
+        .and(warp::query::<ListQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_query_transparency_logs);
+
+    let federation_sync = warp::path!("federation" / "sync")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<FederationSyncQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_federation_sync);
+
+    let package_metadata = warp::path!("packages")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<PackageQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_package_metadata);
+
+    let publish_package = warp::path!("packages")
+        .and(warp::put())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 64))
+        .and(warp::body::json::<Package>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_publish_metadata::<Package>);
+
+    let publish_package_version = warp::path!("packages" / "versions")
+        .and(warp::put())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 64))
+        .and(warp::body::json::<PackageVersion>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_publish_metadata::<PackageVersion>);
+
+    let revoke_artifact = warp::path!("revocations")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestRevokeArtifact>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_revoke_artifact);
+
+    let revocations = warp::path!("revocations")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_revocations);
+
+    let verify = warp::path!("verify")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json::<RequestVerify>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_verify);
+
+    let add_key_rotation = warp::path!("key_rotations")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 16))
+        .and(warp::body::json::<KeyRotationCertificate>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_add_key_rotation);
+
+    let key_rotations = warp::path!("key_rotations")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_key_rotations);
+
+    let trusted_keys = warp::path!("trust_store")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_trusted_keys);
+
+    let add_trusted_key = warp::path!("trust_store")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestTrustKey>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_add_trusted_key);
+
+    let remove_trusted_key = warp::path!("trust_store" / String)
+        .and(warp::delete())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_remove_trusted_key);
+
+    let node_events = warp::path!("node" / "events")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_node_events);
+
+    let node_logs = warp::path!("node" / "logs")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<LogQuery>())
+        .and_then(handle_get_node_logs);
+
+    let follow_node_logs = warp::path!("node" / "logs" / "follow")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<LogQuery>())
+        .and_then(handle_follow_node_logs);
+
+    let metrics_history = warp::path!("node" / "metrics" / "history")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<MetricsHistoryQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_metrics_history);
+
+    let openapi = warp::path!("node" / "openapi.json")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and_then(handle_get_openapi);
+
+    let jwks = warp::path!(".well-known" / "jwks.json")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_jwks);
+
+    let peer_public_keys = warp::path!("keys" / String)
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_peer_public_keys);
+
+    let audit_log = warp::path!("audit_log")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(warp::query::<AuditLogQuery>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_get_audit_log);
+
+    let admin_export_artifacts = warp::path!("artifacts" / "export")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_export_artifacts);
+
+    let admin_import_artifacts = warp::path!("artifacts" / "import")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(MAX_ARCHIVE_SIZE))
+        .and(warp::body::stream())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_import_artifacts);
+
+    let admin_evict_artifact = warp::path!("artifacts" / String)
+        .and(warp::delete())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_evict_artifact);
+
+    let admin_connect_peer = warp::path!("peers" / "connect")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestConnectPeer>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_connect_peer);
+
+    let admin_bench = warp::path!("bench")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestBench>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_bench);
+
+    let admin_disconnect_peer = warp::path!("peers" / String / "disconnect")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(warp::body::content_length_limit(1024 * 8))
+        .and(warp::body::json::<RequestDisconnectPeer>())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_disconnect_peer);
+
+    let admin_collect_garbage = warp::path!("gc")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_collect_garbage);
+
+    let admin_scrub = warp::path!("scrub")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_scrub);
+
+    let admin_issue_api_token = warp::path!("tokens")
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_issue_api_token);
+
+    let admin_api_tokens = warp::path!("tokens")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_get_api_tokens);
+
+    let admin_revoke_api_token = warp::path!("tokens" / String)
+        .and(warp::delete())
+        .and(warp::path::end())
+        .and(admin_client.clone())
+        .and(artifact_service_filter.clone())
+        .and_then(handle_admin_revoke_api_token);
+
+    let admin = warp::path("admin").and(
+        admin_export_artifacts
+            .or(admin_import_artifacts)
+            .or(admin_evict_artifact)
+            .or(admin_connect_peer)
+            .or(admin_disconnect_peer)
+            .or(admin_bench)
+            .or(admin_collect_garbage)
+            .or(admin_scrub)
+            .or(admin_issue_api_token)
+            .or(admin_api_tokens)
+            .or(admin_revoke_api_token)
+            .or(add_authorized_node.clone())
+            .or(push_artifact.clone())
+            .or(prefetch_image.clone())
+            .or(revoke_artifact.clone())
+            .or(add_trusted_key.clone())
+            .or(remove_trusted_key.clone())
+            .or(add_key_rotation.clone())
+            .or(attach_sbom.clone())
+            .or(ingest_attestation.clone())
+            .or(publish_package)
+            .or(publish_package_version),
+    );
+
+    // These endpoints were served outside of the admin scope before. Their
+    // previous paths are deprecated aliases with the same access checks as
+    // the admin scope.
+    let deprecated_admin_aliases = add_authorized_node
+        .or(push_artifact)
+        .or(prefetch_image)
+        .or(revoke_artifact)
+        .or(add_trusted_key)
+        .or(remove_trusted_key)
+        .or(add_key_rotation)
+        .or(attach_sbom)
+        .or(ingest_attestation);
+
     let inspect_docker = warp::path!("inspect" / "docker")
         .and(warp::post())
         .and(warp::path::end())
@@ -90,44 +483,91 @@ pub fn make_node_routes(
         .and(artifact_service_filter)
         .and_then(handle_inspect_log_maven);
 
-    warp::any().and(
-        add_authorized_node
-            .or(build_docker)
+    // The OpenAPI document and the public keys of the node are needed to
+    // verify the node, before a client holds a token.
+    openapi.or(jwks).or(require_api_token(api_tokens).and(
+        build_docker
             .or(build_maven)
             .or(peers)
+            .or(peer_details)
+            .or(ping)
             .or(status)
+            .or(artifacts)
+            .or(search_artifacts)
+            .or(inspect_artifact)
+            .or(quarantine)
+            .or(transparency_logs)
+            .or(federation_sync)
+            .or(package_metadata)
+            .or(revocations)
+            .or(verify)
+            .or(trusted_keys)
+            .or(node_events)
+            .or(node_logs)
+            .or(follow_node_logs)
+            .or(metrics_history)
+            .or(peer_public_keys)
+            .or(key_rotations)
+            .or(audit_log)
+            .or(sboms)
+            .or(attestations)
+            .or(provenance)
             .or(inspect_docker)
             .or(inspect_maven)
+            .or(admin)
+            .or(deprecated_admin_aliases)
             .or(build_status),
-    )
+    ))
 }
 
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
-    use crate::artifact_service::model::PackageType;
+    use crate::artifact_service::audit_log::{AuditAction, AuditEntry};
+    use crate::artifact_service::federation::FederationSync;
+    use crate::artifact_service::hashing::HashAlgorithm;
+    use crate::artifact_service::model::{
+        NodeRole, PackageType, QuarantinedArtifact, VerificationReport,
+    };
+    use crate::artifact_service::trust_store::{fingerprint_of, TrustRole, TrustedKey};
     use crate::blockchain_service::event::BlockchainEvent;
     use crate::build_service::event::BuildEvent;
+    use crate::docker::error_util::{RegistryError, RegistryErrorCode};
+    use crate::logging::buffer::{log_buffer, LogEntry};
+    use crate::network::bench::BenchReport;
     use crate::network::client::command::Command;
-    use crate::node_api::model::cli::{Status, TransparencyLogOutputParams};
+    use crate::network::idle_metric_protocol::PeerMetrics;
+    use crate::network::peer_identity::{ConnectionDirection, ProtocolCompatibility};
+    use crate::network::public_keys::Jwks;
+    use crate::network::signed_document::SignedDocument;
+    use crate::node_api::api_tokens::NewApiToken;
+    use crate::node_api::model::cli::{
+        ArtifactInspection, ArtifactList, ConnectedPeer, ImportedArtifacts, NodePing, PeerDetails,
+        SearchResult, Status, TransparencyLogOutputParams,
+    };
+    use crate::node_api::pagination::Page;
+    use crate::peer_metrics::history::{now_millis, MetricsSample};
     use crate::transparency_log::log::{
         AddArtifactRequest, TransparencyLog, TransparencyLogService,
     };
     use crate::util::test_util;
     use csv;
     use httptest::http;
-    use std::collections::HashSet;
+    use libp2p::identity::Keypair;
+    use std::collections::{HashMap, HashSet};
     use std::future::Future;
     use std::str;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn node_routes_add_authorized_node() {
         let tmp_dir = test_util::tests::setup();
 
         let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
-        let (artifact_service, mut blockchain_event_receiver, ..) =
+        let (mut artifact_service, mut blockchain_event_receiver, ..) =
             test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        artifact_service.role = NodeRole::Authority;
 
         tokio::spawn(async move {
             loop {
@@ -157,7 +597,8 @@ mod tests {
         };
         let response = warp::test::request()
             .method("POST")
-            .path("/authorized_node")
+            .path("/admin/authorized_node")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
             .json(&request)
             .reply(&filter)
             .await;
@@ -314,15 +755,26 @@ mod tests {
         let (artifact_service, ..) =
             test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
 
+        let local_peer_id = p2p_client.local_peer_id;
         tokio::spawn(async move {
             loop {
                 match p2p_command_receiver.recv().await {
-                    Some(Command::ListPeers { sender, .. }) => {
-                        let mut set = HashSet::new();
-                        set.insert(p2p_client.local_peer_id);
-                        let _ = sender.send(set);
+                    Some(Command::ListPeerDetails { sender }) => {
+                        let _ = sender.send(vec![PeerDetails {
+                            peer_id: local_peer_id.to_string(),
+                            agent_version: Some("pyrsia/0.2.4".to_owned()),
+                            protocol_version: None,
+                            protocols: vec![],
+                            listen_addrs: vec![],
+                            compatibility: None,
+                            latency_ms: Some(12),
+                            direction: Some(ConnectionDirection::Outbound),
+                            remote_addr: Some("/ip4/127.0.0.1/tcp/44000".to_owned()),
+                            transfer: Default::default(),
+                            quality_metric: Some(0.25),
+                        }]);
                     }
-                    _ => panic!("Command must match Command::ListPeers"),
+                    _ => panic!("Command must match Command::ListPeerDetails"),
                 }
             }
         });
@@ -330,8 +782,22 @@ mod tests {
         let filter = make_node_routes(artifact_service, p2p_client.clone());
         let response = warp::test::request().path("/peers").reply(&filter).await;
 
-        let expected_body =
-            bytes::Bytes::from(serde_json::to_string(&vec![p2p_client.local_peer_id]).unwrap());
+        let expected_body = bytes::Bytes::from(
+            serde_json::to_string(&Page {
+                total: 1,
+                offset: 0,
+                limit: 100,
+                items: vec![ConnectedPeer {
+                    peer_id: p2p_client.local_peer_id.to_string(),
+                    latency_ms: Some(12),
+                    agent_version: Some("pyrsia/0.2.4".to_owned()),
+                    direction: Some(ConnectionDirection::Outbound),
+                    remote_addr: Some("/ip4/127.0.0.1/tcp/44000".to_owned()),
+                    quality_metric: Some(0.25),
+                }],
+            })
+            .unwrap(),
+        );
 
         assert_eq!(response.status(), 200);
         assert_eq!(expected_body, str::from_utf8(response.body()).unwrap());
@@ -340,7 +806,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn node_routes_status() {
+    async fn node_routes_peer_details() {
         let tmp_dir = test_util::tests::setup();
 
         let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
@@ -351,57 +817,942 @@ mod tests {
         tokio::spawn(async move {
             loop {
                 match p2p_command_receiver.recv().await {
-                    Some(Command::ListPeers { sender, .. }) => {
-                        let mut set = HashSet::new();
-                        set.insert(local_peer_id);
-                        let _ = sender.send(set);
+                    Some(Command::GetPeerDetails { peer_id, sender }) => {
+                        let _ = sender.send(Some(PeerDetails {
+                            peer_id: peer_id.to_string(),
+                            agent_version: Some("pyrsia/0.2.4".to_owned()),
+                            protocol_version: Some("/pyrsia/1.0.0".to_owned()),
+                            protocols: vec!["/artifact-exchange/1".to_owned()],
+                            listen_addrs: vec![],
+                            compatibility: Some(ProtocolCompatibility::Compatible),
+                            latency_ms: Some(3),
+                            direction: Some(ConnectionDirection::Inbound),
+                            remote_addr: None,
+                            transfer: Default::default(),
+                            quality_metric: None,
+                        }));
                     }
-                    Some(Command::Status { sender, .. }) => {
-                        let status = Status {
-                            peers_count: 0,
-                            peer_addrs: Vec::new(),
-                            peer_id: local_peer_id.to_string(),
-                        };
+                    Some(Command::RequestIdleMetric { sender, .. }) => {
+                        let _ = sender.send(Ok(PeerMetrics {
+                            idle_metric: 0.25_f64.to_le_bytes(),
+                        }));
+                    }
+                    _ => panic!(
+                        "Command must match Command::GetPeerDetails, Command::RequestIdleMetric"
+                    ),
+                }
+            }
+        });
 
-                        let _ = sender.send(status);
+        let filter = make_node_routes(artifact_service, p2p_client.clone());
+        let response = warp::test::request()
+            .path(&format!("/peers/{}", local_peer_id))
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let peer_details: PeerDetails = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(peer_details.peer_id, local_peer_id.to_string());
+        assert_eq!(
+            peer_details.compatibility,
+            Some(ProtocolCompatibility::Compatible)
+        );
+        assert_eq!(peer_details.direction, Some(ConnectionDirection::Inbound));
+        assert_eq!(peer_details.quality_metric, Some(0.25));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_peer_details_not_connected() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::GetPeerDetails { sender, .. }) => {
+                        let _ = sender.send(None);
                     }
-                    _ => panic!("Command must match Command::ListPeers or Command::Status"),
+                    _ => panic!("Command must match Command::GetPeerDetails"),
                 }
             }
         });
 
         let filter = make_node_routes(artifact_service, p2p_client.clone());
-        let response = warp::test::request().path("/status").reply(&filter).await;
+        let response = warp::test::request()
+            .path(&format!("/peers/{}", p2p_client.local_peer_id))
+            .reply(&filter)
+            .await;
 
-        let expected_status = Status {
-            peers_count: 0,
-            peer_id: p2p_client.local_peer_id.to_string(),
-            peer_addrs: Vec::new(),
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::NotFound(format!(
+                "Peer {} is not connected",
+                p2p_client.local_peer_id
+            )),
         };
+        let expected_body = format!("Unhandled rejection: {:?}", expected_error);
 
-        let expected_body = bytes::Bytes::from(serde_json::to_string(&expected_status).unwrap());
-
-        assert_eq!(response.status(), 200);
+        assert_eq!(response.status(), 500);
         assert_eq!(expected_body, str::from_utf8(response.body()).unwrap());
 
         test_util::tests::teardown(tmp_dir);
     }
 
-    // Inspect Transparency Log Tests
-
     #[tokio::test]
-    async fn inspect_log_docker_json() {
-        setup_and_execute(|ctx| async {
-            let ps_id = "library/artipie:0.0.7";
-            let transparency_log = add_artifact(&ctx.log, PackageType::Docker, ps_id);
-            let request = RequestDockerLog {
-                image: ps_id.to_string(),
-                output_params: Default::default(),
-            };
+    async fn node_routes_artifacts() {
+        let tmp_dir = test_util::tests::setup();
 
-            let filter = ctx.create_route();
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
 
-            let response = warp::test::request()
+        for artifact_id in ["artifact_a", "artifact_b"] {
+            artifact_service
+                .artifact_storage
+                .push_artifact(&mut &b"SAMPLE_DATA"[..], artifact_id)
+                .unwrap();
+        }
+        artifact_service
+            .artifact_storage
+            .pin_artifact("artifact_b")
+            .unwrap();
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/artifacts?offset=1&limit=10")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let artifact_list: ArtifactList = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(artifact_list.total, 2);
+        assert_eq!(artifact_list.offset, 1);
+        assert_eq!(artifact_list.items.len(), 1);
+        let artifact = &artifact_list.items[0];
+        assert_eq!(artifact.artifact_id, "artifact_b");
+        assert_eq!(artifact.size, 11);
+        assert_eq!(artifact.artifact_hash, None);
+        assert!(artifact.pinned);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_quarantine() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let expected_hash = HashAlgorithm::Sha256.hash(b"SAMPLE_DATA");
+        assert!(artifact_service
+            .artifact_storage
+            .push_verified_artifact(&mut &b"OTHER_DATA"[..], "artifact_id", &expected_hash)
+            .is_err());
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/quarantine")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let quarantined_artifacts: Vec<QuarantinedArtifact> =
+            serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(quarantined_artifacts.len(), 1);
+        assert_eq!(quarantined_artifacts[0].artifact_id, "artifact_id");
+        assert!(quarantined_artifacts[0]
+            .quarantine_key
+            .starts_with("quarantine/"));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_audit_log() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        artifact_service
+            .audit_log
+            .record(AuditAction::Push, "artifact_id_1", None);
+        artifact_service
+            .audit_log
+            .record(AuditAction::Pull, "artifact_id_2", None);
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/audit_log?limit=1")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let entries: Vec<AuditEntry> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::Pull);
+        assert_eq!(entries[0].subject, "artifact_id_2");
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_node_logs() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let warning = LogEntry {
+            timestamp: 1,
+            level: "WARN".to_owned(),
+            target: "node_routes_node_logs".to_owned(),
+            message: "disk almost full".to_owned(),
+        };
+        let debug_entry = LogEntry {
+            level: "DEBUG".to_owned(),
+            message: "checking disk".to_owned(),
+            ..warning.clone()
+        };
+        log_buffer().push(debug_entry.clone());
+        log_buffer().push(warning.clone());
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/node/logs?level=warn&lines=1000")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let entries: Vec<LogEntry> = serde_json::from_slice(response.body()).unwrap();
+        assert!(entries.contains(&warning));
+        assert!(!entries.contains(&debug_entry));
+
+        let response = warp::test::request()
+            .path("/node/logs?level=loud")
+            .reply(&filter)
+            .await;
+
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::BadRequest("Unknown log level: loud".to_owned()),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_prefetch_image_of_other_registry() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/prefetch")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestPrefetchImage {
+                image: String::from("ghcr.io/pyrsia/pyrsia:latest"),
+            })
+            .reply(&filter)
+            .await;
+
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::BadRequest(String::from(
+                "Only images of Docker Hub are supported: ghcr.io/pyrsia/pyrsia:latest",
+            )),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_admin_bench_fastest_peer() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let fast_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let slow_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeerLatencies { sender }) => {
+                        let _ = sender.send(HashMap::from([
+                            (fast_peer_id, Duration::from_millis(3)),
+                            (slow_peer_id, Duration::from_millis(300)),
+                        ]));
+                    }
+                    Some(Command::RequestBench {
+                        peer,
+                        download_size,
+                        sender,
+                        ..
+                    }) => {
+                        assert_eq!(peer, fast_peer_id);
+                        let _ = sender.send(Ok(download_size));
+                    }
+                    _ => panic!(
+                        "Command must match Command::ListPeerLatencies or Command::RequestBench"
+                    ),
+                }
+            }
+        });
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/bench")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestBench {
+                size: Some(1024),
+                rounds: Some(2),
+                ..Default::default()
+            })
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let report: BenchReport = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(report.peer_id, fast_peer_id.to_string());
+        assert_eq!(report.size, 1024);
+        assert_eq!(report.rounds, 2);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_trust_store() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/trust_store")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestTrustKey {
+                peer_id: peer_id.to_string(),
+                roles: vec![TrustRole::Attestation],
+            })
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 201);
+        let trusted_key: TrustedKey = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(trusted_key.fingerprint, fingerprint_of(&peer_id).unwrap());
+
+        let response = warp::test::request()
+            .path("/trust_store")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        let trusted_keys: Vec<TrustedKey> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(trusted_keys, vec![trusted_key.clone()]);
+
+        let response = warp::test::request()
+            .method("DELETE")
+            .path(&format!("/admin/trust_store/{}", trusted_key.fingerprint))
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .method("DELETE")
+            .path(&format!("/admin/trust_store/{}", trusted_key.fingerprint))
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 404);
+
+        // the previous path is a deprecated alias with the same access checks
+        let request = RequestTrustKey {
+            peer_id: peer_id.to_string(),
+            roles: vec![TrustRole::Attestation],
+        };
+        let response = warp::test::request()
+            .method("POST")
+            .path("/trust_store")
+            .remote_addr("10.0.0.1:8080".parse().unwrap())
+            .json(&request)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 500);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/trust_store")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&request)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 201);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_admin_connect_peer() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let address = format!("/ip4/10.0.0.2/tcp/44000/p2p/{}", other_peer_id);
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::Dial {
+                        peer_id, sender, ..
+                    }) => {
+                        assert_eq!(peer_id, other_peer_id);
+                        let _ = sender.send(Ok(()));
+                    }
+                    Some(Command::BootstrapDht { sender }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("Command must match Command::Dial or Command::BootstrapDht"),
+                }
+            }
+        });
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/peers/connect")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestConnectPeer {
+                address: address.clone(),
+            })
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            other_peer_id.to_string(),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/peers/connect")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestConnectPeer {
+                address: String::from("/ip4/10.0.0.2/tcp/44000"),
+            })
+            .reply(&filter)
+            .await;
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::BadRequest(String::from(
+                "Multiaddress does not end with a peer ID: /ip4/10.0.0.2/tcp/44000",
+            )),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_admin_disconnect_peer() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::DisconnectPeer {
+                        peer_id,
+                        ban,
+                        sender,
+                    }) => {
+                        assert_eq!(peer_id, other_peer_id);
+                        assert!(ban);
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("Command must match Command::DisconnectPeer"),
+                }
+            }
+        });
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("POST")
+            .path(&format!("/admin/peers/{}/disconnect", other_peer_id))
+            .remote_addr("10.0.0.1:8080".parse().unwrap())
+            .json(&RequestDisconnectPeer { ban: true })
+            .reply(&filter)
+            .await;
+
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::Denied(String::from(
+                "The admin endpoints are only available to local clients until an API token is configured or remote admin clients are allowed",
+            )),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        let response = warp::test::request()
+            .method("POST")
+            .path(&format!("/admin/peers/{}/disconnect", other_peer_id))
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&RequestDisconnectPeer { ban: true })
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_api_tokens() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let api_tokens = artifact_service.api_tokens.clone();
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+
+        // remote clients may use the admin endpoints without a token when
+        // that is allowed
+        api_tokens.allow_remote_admin(true);
+        let response = warp::test::request()
+            .path("/admin/tokens")
+            .remote_addr("10.0.0.1:8080".parse().unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        api_tokens.allow_remote_admin(false);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/tokens")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 201);
+        let new_token: NewApiToken = serde_json::from_slice(response.body()).unwrap();
+
+        let response = warp::test::request()
+            .path("/quarantine")
+            .reply(&filter)
+            .await;
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::Unauthorized(String::from(
+                "The node API requires a bearer token",
+            )),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        let response = warp::test::request()
+            .path("/quarantine")
+            .header("Authorization", format!("Bearer {}", new_token.token))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        // the documents that clients need to verify the node are public
+        for path in ["/node/openapi.json", "/.well-known/jwks.json"] {
+            let response = warp::test::request().path(path).reply(&filter).await;
+            assert_eq!(response.status(), 200);
+        }
+
+        // remote clients may use the admin endpoints with a token
+        let response = warp::test::request()
+            .path("/admin/tokens")
+            .remote_addr("10.0.0.1:8080".parse().unwrap())
+            .header("Authorization", format!("Bearer {}", new_token.token))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        // local clients need a token too once one exists
+        let response = warp::test::request()
+            .path("/admin/tokens")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        let response = warp::test::request()
+            .method("DELETE")
+            .path(&format!("/admin/tokens/{}", new_token.id))
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .header("Authorization", format!("Bearer {}", new_token.token))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/quarantine")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_openapi() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/node/openapi.json")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let openapi: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(openapi["openapi"], "3.0.3");
+        assert_eq!(openapi["info"]["version"], env!("CARGO_PKG_VERSION"));
+        for path in [
+            "/status",
+            "/peers/{peer_id}",
+            "/node/metrics/history",
+            "/v2/library/{name}/blobs/{digest}",
+        ] {
+            assert!(
+                openapi["paths"][path].is_object(),
+                "{} is not documented",
+                path
+            );
+        }
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_metrics_history() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let now = now_millis();
+        let old_sample = MetricsSample {
+            timestamp: now - 2 * 60 * 60 * 1000,
+            ..Default::default()
+        };
+        let recent_sample = MetricsSample {
+            timestamp: now - 60 * 1000,
+            cpu: 0.5,
+            bytes_served: 1024,
+            peers_count: 2,
+            ..Default::default()
+        };
+        artifact_service
+            .metrics_history
+            .push(old_sample.clone())
+            .unwrap();
+        artifact_service
+            .metrics_history
+            .push(recent_sample.clone())
+            .unwrap();
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/node/metrics/history")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let samples: Vec<MetricsSample> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(samples, vec![recent_sample.clone()]);
+
+        let response = warp::test::request()
+            .path("/node/metrics/history?window=3h")
+            .reply(&filter)
+            .await;
+
+        let samples: Vec<MetricsSample> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(samples, vec![old_sample, recent_sample]);
+
+        let response = warp::test::request()
+            .path("/node/metrics/history?window=1w")
+            .reply(&filter)
+            .await;
+
+        let expected_error = RegistryError {
+            code: RegistryErrorCode::BadRequest(
+                "Invalid window unit, expected s, m, h or d: 1w".to_owned(),
+            ),
+        };
+        assert_eq!(response.status(), 500);
+        assert_eq!(
+            format!("Unhandled rejection: {:?}", expected_error),
+            str::from_utf8(response.body()).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_jwks() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let filter = make_node_routes(artifact_service, p2p_client.clone());
+        let response = warp::test::request()
+            .path("/.well-known/jwks.json")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let jwks: Jwks = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(jwks.keys.len(), 1);
+        assert_eq!(jwks.keys[0].kid, p2p_client.local_peer_id.to_string());
+        assert_eq!(jwks.public_keys()[0].to_peer_id(), p2p_client.local_peer_id);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_export_and_import_artifacts() {
+        let tmp_dir = test_util::tests::setup();
+        let source_dir = tmp_dir.join("source");
+        let target_dir = tmp_dir.join("target");
+        std::fs::create_dir_all(&source_dir).unwrap();
+        std::fs::create_dir_all(&target_dir).unwrap();
+
+        let (source_p2p_client, _source_p2p_command_receiver) =
+            test_util::tests::create_p2p_client();
+        let (source_artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(
+                &source_dir,
+                source_p2p_client.clone(),
+            );
+        source_artifact_service
+            .artifact_storage
+            .push_artifact(&mut &b"SAMPLE_DATA"[..], "artifact_id")
+            .unwrap();
+
+        let source_filter = make_node_routes(source_artifact_service, source_p2p_client);
+        let export_response = warp::test::request()
+            .path("/admin/artifacts/export")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .reply(&source_filter)
+            .await;
+        assert_eq!(export_response.status(), 200);
+
+        let (target_p2p_client, mut target_p2p_command_receiver) =
+            test_util::tests::create_p2p_client();
+        let (target_artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(
+                &target_dir,
+                target_p2p_client.clone(),
+            );
+
+        tokio::spawn(async move {
+            loop {
+                match target_p2p_command_receiver.recv().await {
+                    Some(Command::Provide {
+                        artifact_id,
+                        sender,
+                    }) => {
+                        assert_eq!(artifact_id, "artifact_id");
+                        let _ = sender.send(());
+                    }
+                    _ => panic!("Command must match Command::Provide"),
+                }
+            }
+        });
+
+        // only artifacts with a transparency log on the target node are imported
+        let mut transparency_log = TransparencyLog::from(AddArtifactRequest {
+            package_type: PackageType::Docker,
+            package_specific_id: "alpine:3.16".to_owned(),
+            num_artifacts: 1,
+            package_specific_artifact_id: "alpine:3.16".to_owned(),
+            artifact_hash: HashAlgorithm::Sha256.hash(b"SAMPLE_DATA"),
+        });
+        transparency_log.artifact_id = "artifact_id".to_owned();
+        target_artifact_service
+            .transparency_log_service
+            .write_transparency_log(&transparency_log)
+            .unwrap();
+
+        let target_filter = make_node_routes(target_artifact_service.clone(), target_p2p_client);
+        let import_response = warp::test::request()
+            .method("POST")
+            .path("/admin/artifacts/import")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .body(export_response.body())
+            .reply(&target_filter)
+            .await;
+
+        assert_eq!(import_response.status(), 200);
+        let imported_artifacts: ImportedArtifacts =
+            serde_json::from_slice(import_response.body()).unwrap();
+        assert_eq!(imported_artifacts.artifact_ids, vec!["artifact_id"]);
+        assert!(imported_artifacts.rejected_artifact_ids.is_empty());
+        assert_eq!(
+            target_artifact_service
+                .artifact_storage
+                .artifact_size("artifact_id")
+                .unwrap(),
+            11
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_status() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let local_peer_id = p2p_client.local_peer_id;
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::ListPeers { sender, .. }) => {
+                        let mut set = HashSet::new();
+                        set.insert(local_peer_id);
+                        let _ = sender.send(set);
+                    }
+                    Some(Command::Status { sender, .. }) => {
+                        let status = Status {
+                            peers_count: 0,
+                            peer_addrs: Vec::new(),
+                            peer_id: local_peer_id.to_string(),
+                            transfer_metrics: Default::default(),
+                            disk_usage: Default::default(),
+                            last_scrub: None,
+                            repository_usage: Vec::new(),
+                            ..Default::default()
+                        };
+
+                        let _ = sender.send(status);
+                    }
+                    _ => panic!("Command must match Command::ListPeers or Command::Status"),
+                }
+            }
+        });
+
+        let filter = make_node_routes(artifact_service, p2p_client.clone());
+        let response = warp::test::request().path("/status").reply(&filter).await;
+
+        let expected_status = Status {
+            peers_count: 0,
+            peer_id: p2p_client.local_peer_id.to_string(),
+            peer_addrs: Vec::new(),
+            transfer_metrics: Default::default(),
+            disk_usage: Default::default(),
+            last_scrub: None,
+            repository_usage: Vec::new(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            uptime_secs: 0,
+            build_fallbacks: 0,
+            rate_limits: Default::default(),
+            nat_status: String::new(),
+        };
+
+        assert_eq!(response.status(), 200);
+        let mut status: Status = serde_json::from_slice(response.body()).unwrap();
+        assert!(status.uptime_secs < 60);
+        status.uptime_secs = 0;
+        assert_eq!(
+            serde_json::to_string(&expected_status).unwrap(),
+            serde_json::to_string(&status).unwrap()
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn node_routes_ping() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, _) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+
+        let filter = make_node_routes(artifact_service, p2p_client.clone());
+        let response = warp::test::request().path("/ping").reply(&filter).await;
+
+        assert_eq!(response.status(), 200);
+        let ping: NodePing = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            ping,
+            NodePing {
+                peer_id: p2p_client.local_peer_id.to_string(),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+            }
+        );
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    // Inspect Transparency Log Tests
+
+    #[tokio::test]
+    async fn inspect_log_docker_json() {
+        setup_and_execute(|ctx| async {
+            let ps_id = "library/artipie:0.0.7";
+            let transparency_log = add_artifact(&ctx.log, PackageType::Docker, ps_id);
+            let request = RequestDockerLog {
+                image: ps_id.to_string(),
+                output_params: Default::default(),
+            };
+
+            let filter = ctx.create_route();
+
+            let response = warp::test::request()
                 .method("POST")
                 .path("/inspect/docker")
                 .json(&request)
@@ -483,6 +1834,313 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn query_transparency_logs() {
+        setup_and_execute(|ctx| async {
+            let transparency_log =
+                add_artifact(&ctx.log, PackageType::Docker, "library/artipie:0.0.7");
+
+            let filter = ctx.create_route();
+
+            for path in [
+                "/transparency_logs?artifact_hash=test_hash",
+                "/transparency_logs?from=0",
+            ] {
+                let response = warp::test::request().path(path).reply(&filter).await;
+
+                assert_eq!(response.status(), 200);
+                let transparency_logs: Page<TransparencyLog> =
+                    serde_json::from_slice(response.body()).unwrap();
+                assert_eq!(transparency_logs.total, 1);
+                assert_eq!(transparency_logs.items[0].id, transparency_log.id);
+            }
+
+            let response = warp::test::request()
+                .path("/transparency_logs?artifact_hash=other_hash")
+                .reply(&filter)
+                .await;
+            let transparency_logs: Page<TransparencyLog> =
+                serde_json::from_slice(response.body()).unwrap();
+            assert!(transparency_logs.items.is_empty());
+
+            let response = warp::test::request()
+                .path("/transparency_logs?from=0&filter=package_type:Maven2")
+                .reply(&filter)
+                .await;
+            let transparency_logs: Page<TransparencyLog> =
+                serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(transparency_logs.total, 0);
+
+            let response = warp::test::request()
+                .path("/transparency_logs?from=0&filter=package_type")
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 500);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn search_artifacts() {
+        setup_and_execute(|ctx| async {
+            let artifact_storage = &ctx.artifact_service.artifact_storage;
+            for (artifact_id, package_type, name) in [
+                ("artifact_a", PackageType::Docker, "library/alpine:3.16"),
+                (
+                    "artifact_b",
+                    PackageType::Maven2,
+                    "org.alpine:alpine-core:1.0",
+                ),
+                ("artifact_c", PackageType::Docker, "library/artipie:0.0.7"),
+            ] {
+                artifact_storage
+                    .push_artifact(&mut &b"SAMPLE_DATA"[..], artifact_id)
+                    .unwrap();
+                artifact_storage
+                    .associate_artifact(artifact_id, package_type, name, None)
+                    .unwrap();
+            }
+
+            let filter = ctx.create_route();
+
+            let response = warp::test::request()
+                .path("/search?q=ALPINE")
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 200);
+            let results: Vec<SearchResult> = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].name, "library/alpine:3.16");
+            assert_eq!(results[0].artifact_id, "artifact_a");
+            assert!(results[0].stored_locally);
+            assert_eq!(results[1].package_type, PackageType::Maven2);
+
+            let response = warp::test::request()
+                .path("/search?q=alpine&package_type=Docker")
+                .reply(&filter)
+                .await;
+            let results: Vec<SearchResult> = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].artifact_id, "artifact_a");
+
+            let response = warp::test::request()
+                .path("/search?q=")
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 500);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn inspect_artifact() {
+        setup_and_execute(|ctx| async {
+            let transparency_log =
+                add_artifact(&ctx.log, PackageType::Docker, "library/artipie:0.0.7");
+
+            let filter = ctx.create_route();
+
+            for path in [
+                "/inspect?reference=artipie:0.0.7",
+                "/inspect?reference=library/artipie:0.0.7",
+            ] {
+                let response = warp::test::request().path(path).reply(&filter).await;
+
+                assert_eq!(response.status(), 200);
+                let inspections: Vec<ArtifactInspection> =
+                    serde_json::from_slice(response.body()).unwrap();
+                assert_eq!(inspections.len(), 1);
+                assert_eq!(inspections[0].artifact_hash, "test_hash");
+                assert_eq!(inspections[0].transparency_logs[0].id, transparency_log.id);
+                assert!(inspections[0].stored.is_empty());
+                assert!(inspections[0].revocation.is_none());
+            }
+
+            let response = warp::test::request()
+                .path("/inspect?reference=unknown:1.0")
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 500);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        setup_and_execute(|ctx| async {
+            let transparency_log =
+                add_artifact(&ctx.log, PackageType::Docker, "library/artipie:0.0.7");
+            let keypair = Keypair::generate_ed25519();
+
+            let filter = ctx.create_route();
+
+            let response = warp::test::request()
+                .method("POST")
+                .path("/verify")
+                .json(&RequestVerify {
+                    artifact_hash: Some(String::from("test_hash")),
+                    ..Default::default()
+                })
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 200);
+            let report: VerificationReport = serde_json::from_slice(response.body()).unwrap();
+            assert!(report.valid);
+            assert_eq!(report.transparency_logs, 1);
+            assert_eq!(report.signers[0].peer_id, transparency_log.node_id);
+
+            let response = warp::test::request()
+                .method("POST")
+                .path("/verify")
+                .json(&RequestVerify {
+                    document: Some(SignedDocument::new(&keypair, "{}").unwrap()),
+                    ..Default::default()
+                })
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 200);
+            let report: VerificationReport = serde_json::from_slice(response.body()).unwrap();
+            assert!(report.valid);
+            assert_eq!(
+                report.signers[0].peer_id,
+                keypair.public().to_peer_id().to_string()
+            );
+
+            let response = warp::test::request()
+                .method("POST")
+                .path("/verify")
+                .json(&RequestVerify::default())
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 500);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn publish_and_get_package_metadata() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (mut artifact_service, mut blockchain_event_receiver, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        artifact_service.role = NodeRole::Build;
+
+        let keypair = Keypair::generate_ed25519();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&keypair, &content));
+                    }
+                    Some(Command::PutSignedRecord { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    Some(Command::GetSignedRecord { sender, .. }) => {
+                        let _ = sender.send(None);
+                    }
+                    _ => panic!("Command must match Command::SignDocument, Command::PutSignedRecord or Command::GetSignedRecord"),
+                }
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let package_version = PackageVersion {
+            package_type: PackageType::Docker,
+            name: String::from("library/alpine"),
+            version: String::from("3.17"),
+            artifacts: vec![],
+        };
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .method("PUT")
+            .path("/admin/packages/versions")
+            .remote_addr("127.0.0.1:8080".parse().unwrap())
+            .json(&package_version)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 201);
+
+        let response = warp::test::request()
+            .path("/packages?package_type=Docker&name=library/alpine&version=3.17")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        let found: PackageVersion = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(found, package_version);
+
+        let response = warp::test::request()
+            .path("/packages?package_type=Docker&name=library/alpine")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 500);
+        assert!(str::from_utf8(response.body())
+            .unwrap()
+            .contains("No package metadata found"));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn federation_sync() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (p2p_client, mut p2p_command_receiver) = test_util::tests::create_p2p_client();
+        let (artifact_service, ..) =
+            test_util::tests::create_artifact_service_with_p2p_client(&tmp_dir, p2p_client.clone());
+        let (log, _) = test_util::tests::create_transparency_log_service(&tmp_dir);
+        let transparency_log = add_artifact(&log, PackageType::Docker, "library/artipie:0.0.7");
+
+        let keypair = Keypair::generate_ed25519();
+        let local_peer_id = keypair.public().to_peer_id();
+        tokio::spawn(async move {
+            loop {
+                match p2p_command_receiver.recv().await {
+                    Some(Command::SignDocument { content, sender }) => {
+                        let _ = sender.send(SignedDocument::new(&keypair, &content));
+                    }
+                    _ => panic!("Command must match Command::SignDocument"),
+                }
+            }
+        });
+
+        let filter = make_node_routes(artifact_service, p2p_client);
+        let response = warp::test::request()
+            .path("/federation/sync?log_sequence=0&metadata_sequence=0")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        let sync: FederationSync = serde_json::from_slice(response.body()).unwrap();
+        assert!(sync.transparency_logs.verify());
+        assert_eq!(sync.transparency_logs.publisher(), Some(local_peer_id));
+        let transparency_logs: Vec<TransparencyLog> =
+            serde_json::from_str(&sync.transparency_logs.content).unwrap();
+        assert_eq!(transparency_logs.len(), 1);
+        assert_eq!(transparency_logs[0].id, transparency_log.id);
+        assert!(!sync.has_more);
+
+        let response = warp::test::request()
+            .path(&format!(
+                "/federation/sync?log_sequence={}",
+                sync.log_sequence
+            ))
+            .reply(&filter)
+            .await;
+        let sync: FederationSync = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(sync.transparency_logs.content, "[]");
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     fn assert_response_csv(
         response: http::response::Response<bytes::Bytes>,
         transparency_log: TransparencyLog,
@@ -14,4 +14,7 @@
    limitations under the License.
 */
 
+pub mod calibration;
+pub mod history;
+pub mod load_shedding;
 pub mod metrics;
@@ -0,0 +1,344 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+//! Calibration
+//!
+//! The load of nodes on different hardware, like a Raspberry Pi and a 64-core
+//! server, is not comparable: the same raw CPU usage or disk throughput leaves
+//! very different headroom. A quick micro-benchmark at startup measures the
+//! CPU and disk throughput of the node, and is cached in the data directory.
+//! The calibrated stress of a component is the raw stress with its headroom
+//! scaled by the throughput of the node relative to a reference node, so a
+//! slow node advertises a higher stress than a fast node under the same load
+//! and advertised scores are roughly comparable across peers.
+
+use super::metrics::{Stress, DISK_CAPACITY};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The file in the data directory that the calibration is cached in.
+pub const CALIBRATION_FILE_NAME: &str = "calibration.json";
+/// The SHA-256 throughput of all cores together, in bytes per second, of the
+/// reference node: 8 cores that hash 500 MB/s each.
+pub const REFERENCE_CPU_THROUGHPUT: f64 = 4_000_000_000_f64;
+/// The synced write throughput, in bytes per second, of the reference node.
+pub const REFERENCE_DISK_THROUGHPUT: f64 = DISK_CAPACITY;
+
+const CPU_BENCHMARK_DURATION: Duration = Duration::from_millis(200);
+const DISK_BENCHMARK_SIZE: usize = 32 * 1024 * 1024;
+const BENCHMARK_BLOCK_SIZE: usize = 1024 * 1024;
+const DISK_BENCHMARK_FILE_NAME: &str = ".calibration.tmp";
+
+/// The units of the quality metric that the node advertises.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, strum_macros::Display, strum_macros::EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum MetricUnits {
+    /// Relative to the hardware of the node itself.
+    Raw,
+    /// Relative to the reference node, see [`Calibration`].
+    Calibrated,
+}
+
+/// The measured throughput of the node.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Calibration {
+    /// The number of CPUs that were available to the benchmark. The node is
+    /// calibrated again when it changes.
+    pub cpus: usize,
+    /// The SHA-256 throughput of all cores together, in bytes per second.
+    pub cpu_throughput: f64,
+    /// The synced write throughput to the artifact volume, in bytes per second.
+    pub disk_throughput: f64,
+}
+
+impl Default for Calibration {
+    /// The calibration of the reference node, which leaves the stress as is.
+    fn default() -> Self {
+        Calibration {
+            cpus: available_cpus(),
+            cpu_throughput: REFERENCE_CPU_THROUGHPUT,
+            disk_throughput: REFERENCE_DISK_THROUGHPUT,
+        }
+    }
+}
+
+impl Calibration {
+    /// Run the micro-benchmark. The disk throughput is measured by writing a
+    /// temporary file to `dir`, which takes at most a few seconds.
+    pub fn measure(dir: &Path) -> io::Result<Self> {
+        let cpus = available_cpus();
+        Ok(Calibration {
+            cpus,
+            cpu_throughput: measure_hashing_throughput() * cpus as f64,
+            disk_throughput: measure_disk_throughput(dir)?,
+        })
+    }
+
+    /// Load the calibration that is cached in `file`, or measure it in `dir`
+    /// and cache it when there is no valid calibration for the current number
+    /// of CPUs, or when `recalibrate` is set. Falls back to the reference
+    /// calibration when the benchmark fails.
+    pub fn load_or_measure(file: &Path, dir: &Path, recalibrate: bool) -> Self {
+        if !recalibrate {
+            if let Some(calibration) = load(file) {
+                debug!("Loaded the cached calibration {:?}", calibration);
+                return calibration;
+            }
+        }
+        match Calibration::measure(dir) {
+            Ok(calibration) => {
+                info!("Calibrated the peer metrics: {:?}", calibration);
+                if let Err(e) = calibration.save(file) {
+                    warn!("Failed to cache the calibration in {:?}: {}", file, e);
+                }
+                calibration
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to calibrate the peer metrics, the stress is not calibrated: {}",
+                    e
+                );
+                Calibration::default()
+            }
+        }
+    }
+
+    /// Scale the headroom of the CPU and disk stress by the throughput of the
+    /// node relative to the reference node. Raw disk stress is relative to
+    /// DISK_CAPACITY, so it is made relative to the measured disk throughput
+    /// first. Nodes that are faster than the reference node report the stress
+    /// relative to their own throughput.
+    pub fn calibrate(&self, stress: &Stress) -> Stress {
+        let relative_disk = self.disk_throughput / REFERENCE_DISK_THROUGHPUT;
+        Stress {
+            cpu: with_headroom(stress.cpu, self.cpu_throughput / REFERENCE_CPU_THROUGHPUT),
+            disk: with_headroom(own_stress(stress.disk, relative_disk), relative_disk),
+            ..*stress
+        }
+    }
+
+    fn save(&self, file: &Path) -> io::Result<()> {
+        fs::write(file, serde_json::to_vec(self)?)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.cpus == available_cpus()
+            && is_throughput(self.cpu_throughput)
+            && is_throughput(self.disk_throughput)
+    }
+}
+
+fn load(file: &Path) -> Option<Calibration> {
+    let calibration: Calibration = serde_json::from_slice(&fs::read(file).ok()?).ok()?;
+    calibration.is_valid().then_some(calibration)
+}
+
+// Only the part of the headroom that is left relative to the reference node
+// counts as headroom.
+fn with_headroom(stress: f64, relative_throughput: f64) -> f64 {
+    if !is_throughput(relative_throughput) {
+        return stress;
+    }
+    1_f64 - (1_f64 - stress) * relative_throughput.min(1_f64)
+}
+
+// The stress relative to the throughput of the node itself, from stress
+// that is relative to the reference node.
+fn own_stress(stress: f64, relative_throughput: f64) -> f64 {
+    if !is_throughput(relative_throughput) {
+        return stress;
+    }
+    (stress / relative_throughput).min(1_f64)
+}
+
+fn is_throughput(throughput: f64) -> bool {
+    throughput.is_finite() && throughput > 0_f64
+}
+
+fn available_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(|cpus| cpus.get())
+        .unwrap_or(1)
+}
+
+// The SHA-256 throughput of a single core, in bytes per second.
+fn measure_hashing_throughput() -> f64 {
+    let block = vec![0x5a_u8; BENCHMARK_BLOCK_SIZE];
+    let mut hasher = Sha256::new();
+    let mut hashed = 0_u64;
+    let started_at = Instant::now();
+    while started_at.elapsed() < CPU_BENCHMARK_DURATION {
+        hasher.update(black_box(&block));
+        hashed += block.len() as u64;
+    }
+    black_box(hasher.finalize());
+    hashed as f64 / started_at.elapsed().as_secs_f64()
+}
+
+// The throughput of writing a file to `dir` and syncing it to disk, in bytes
+// per second. Reading it back would only measure the page cache.
+fn measure_disk_throughput(dir: &Path) -> io::Result<f64> {
+    let path = dir.join(DISK_BENCHMARK_FILE_NAME);
+    let block = vec![0xa5_u8; BENCHMARK_BLOCK_SIZE];
+    let started_at = Instant::now();
+    let written = File::create(&path).and_then(|mut file| {
+        for _ in 0..DISK_BENCHMARK_SIZE / BENCHMARK_BLOCK_SIZE {
+            file.write_all(&block)?;
+        }
+        file.sync_all()
+    });
+    let elapsed = started_at.elapsed();
+    if let Err(e) = fs::remove_file(&path) {
+        debug!("Unable to remove the benchmark file {:?}: {}", path, e);
+    }
+    written?;
+    Ok(DISK_BENCHMARK_SIZE as f64 / elapsed.as_secs_f64().max(f64::EPSILON))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::util::test_util;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    fn saturated() -> Stress {
+        Stress {
+            cpu: 1_f64,
+            network: 1_f64,
+            disk: 1_f64,
+            quota: 1_f64,
+            memory: 1_f64,
+        }
+    }
+
+    #[test]
+    fn test_metric_units() {
+        assert_eq!(MetricUnits::from_str("raw").unwrap(), MetricUnits::Raw);
+        assert_eq!(MetricUnits::Calibrated.to_string(), "calibrated");
+        assert!(MetricUnits::from_str("bogus").is_err());
+    }
+
+    #[test]
+    fn test_with_headroom() {
+        assert_eq!(with_headroom(0_f64, 0.25_f64), 0.75_f64);
+        assert_eq!(with_headroom(0.5_f64, 0.5_f64), 0.75_f64);
+        assert_eq!(with_headroom(0.5_f64, 4_f64), 0.5_f64);
+        assert_eq!(with_headroom(1_f64, 0.25_f64), 1_f64);
+        assert_eq!(with_headroom(0.5_f64, 0_f64), 0.5_f64);
+        assert_eq!(with_headroom(0.5_f64, f64::NAN), 0.5_f64);
+    }
+
+    #[test]
+    fn test_own_stress() {
+        assert_eq!(own_stress(0.25_f64, 0.5_f64), 0.5_f64);
+        assert_eq!(own_stress(0.75_f64, 0.5_f64), 1_f64);
+        assert_eq!(own_stress(0.5_f64, 2_f64), 0.25_f64);
+        assert_eq!(own_stress(0.5_f64, 0_f64), 0.5_f64);
+    }
+
+    #[test]
+    fn test_reference_calibration_keeps_stress() {
+        let stress = Stress {
+            cpu: 0.5_f64,
+            network: 0.25_f64,
+            disk: 0.125_f64,
+            quota: 0.75_f64,
+            memory: 1_f64,
+        };
+
+        assert_eq!(Calibration::default().calibrate(&stress), stress);
+    }
+
+    #[test]
+    fn test_calibrate_slow_node() {
+        let calibration = Calibration {
+            cpus: 4,
+            cpu_throughput: REFERENCE_CPU_THROUGHPUT / 4_f64,
+            disk_throughput: REFERENCE_DISK_THROUGHPUT / 2_f64,
+        };
+
+        let idle = calibration.calibrate(&Stress::default());
+        assert_eq!(idle.cpu, 0.75_f64);
+        assert_eq!(idle.disk, 0.5_f64);
+        assert_eq!(idle.network, 0_f64);
+
+        // the disk of the slow node is saturated at half the reference throughput
+        let busy = calibration.calibrate(&Stress {
+            disk: 0.5_f64,
+            ..Stress::default()
+        });
+        assert_eq!(busy.disk, 1_f64);
+
+        assert_eq!(calibration.calibrate(&saturated()), saturated());
+    }
+
+    #[test]
+    fn test_load_or_measure_caches_calibration() {
+        let tmp_dir = test_util::tests::setup();
+        let dir = PathBuf::from(&tmp_dir);
+        let file = dir.join(CALIBRATION_FILE_NAME);
+
+        let calibration = Calibration::load_or_measure(&file, &dir, false);
+        assert!(calibration.is_valid());
+        assert!(!dir.join(DISK_BENCHMARK_FILE_NAME).exists());
+        assert!(load(&file).is_some());
+
+        let cached = Calibration {
+            cpu_throughput: 1_f64,
+            ..calibration
+        };
+        cached.save(&file).unwrap();
+        assert_eq!(Calibration::load_or_measure(&file, &dir, false), cached);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_load_ignores_invalid_calibration() {
+        let tmp_dir = test_util::tests::setup();
+        let file = PathBuf::from(&tmp_dir).join(CALIBRATION_FILE_NAME);
+
+        assert_eq!(load(&file), None);
+
+        fs::write(&file, "not json").unwrap();
+        assert_eq!(load(&file), None);
+
+        Calibration {
+            cpus: available_cpus() + 1,
+            ..Calibration::default()
+        }
+        .save(&file)
+        .unwrap();
+        assert_eq!(load(&file), None);
+
+        Calibration {
+            disk_throughput: 0_f64,
+            ..Calibration::default()
+        }
+        .save(&file)
+        .unwrap();
+        assert_eq!(load(&file), None);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
@@ -0,0 +1,291 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+//! Metrics History
+//!
+//! The node keeps the metrics it sampled over the last day, so that operators
+//! without a monitoring system can still see trends. The history is kept in
+//! memory and can be persisted in a file to survive restarts.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The interval at which the metrics are added to the history.
+pub const HISTORY_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// The number of samples in the history: a day at the sample interval.
+pub const HISTORY_CAPACITY: usize = 24 * 60;
+
+/// The metrics of the node at one point in time. The components of the
+/// quality metric are smoothed and range from 0 to 1.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct MetricsSample {
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub cpu: f64,
+    pub network: f64,
+    pub disk: f64,
+    pub quota: f64,
+    pub memory: f64,
+    pub quality_metric: f64,
+    /// The number of artifact bytes sent to other peers since the previous
+    /// sample.
+    pub bytes_served: u64,
+    /// The number of artifact bytes received from other peers since the
+    /// previous sample.
+    pub bytes_fetched: u64,
+    pub peers_count: usize,
+}
+
+// The samples, oldest first, and the file they are appended to.
+struct Samples {
+    capacity: usize,
+    samples: VecDeque<MetricsSample>,
+    file: Option<PathBuf>,
+    // The number of lines in the file, which is rewritten with only the
+    // samples in memory when it holds twice as many.
+    file_lines: usize,
+}
+
+/// The most recent samples of the node metrics, shared by all clones.
+#[derive(Clone)]
+pub struct MetricsHistory {
+    samples: Arc<Mutex<Samples>>,
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        MetricsHistory::new(HISTORY_CAPACITY)
+    }
+}
+
+impl MetricsHistory {
+    /// A history that is kept in memory only.
+    pub fn new(capacity: usize) -> Self {
+        MetricsHistory {
+            samples: Arc::new(Mutex::new(Samples {
+                capacity,
+                samples: VecDeque::with_capacity(capacity),
+                file: None,
+                file_lines: 0,
+            })),
+        }
+    }
+
+    /// A history that is persisted in `file`, one JSON sample per line. The
+    /// samples that are already in the file are loaded.
+    pub fn open<P: AsRef<Path>>(file: P, capacity: usize) -> io::Result<Self> {
+        let file = file.as_ref();
+        let mut samples = VecDeque::with_capacity(capacity);
+        let mut file_lines = 0;
+        match File::open(file) {
+            Ok(existing) => {
+                for line in BufReader::new(existing).lines() {
+                    let line = line?;
+                    file_lines += 1;
+                    match serde_json::from_str(&line) {
+                        Ok(sample) => {
+                            if samples.len() == capacity {
+                                samples.pop_front();
+                            }
+                            samples.push_back(sample);
+                        }
+                        Err(e) => warn!(
+                            "Skipping invalid sample in metrics history {}: {}",
+                            file.display(),
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(MetricsHistory {
+            samples: Arc::new(Mutex::new(Samples {
+                capacity,
+                samples,
+                file: Some(file.to_path_buf()),
+                file_lines,
+            })),
+        })
+    }
+
+    /// Add a sample, dropping the oldest sample when the history is full.
+    pub fn push(&self, sample: MetricsSample) -> io::Result<()> {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.samples.len() == samples.capacity {
+            samples.samples.pop_front();
+        }
+        samples.samples.push_back(sample.clone());
+
+        let file = match &samples.file {
+            Some(file) => file.clone(),
+            None => return Ok(()),
+        };
+        if samples.file_lines >= 2 * samples.capacity {
+            rewrite(&file, &samples.samples)?;
+            samples.file_lines = samples.samples.len();
+        } else {
+            let mut appended = OpenOptions::new().create(true).append(true).open(&file)?;
+            writeln!(appended, "{}", serde_json::to_string(&sample)?)?;
+            samples.file_lines += 1;
+        }
+        Ok(())
+    }
+
+    /// The samples of the last `window`, oldest first.
+    pub fn window(&self, window: Duration) -> Vec<MetricsSample> {
+        let since = now_millis().saturating_sub(window.as_millis() as u64);
+        self.samples
+            .lock()
+            .unwrap()
+            .samples
+            .iter()
+            .filter(|sample| sample.timestamp >= since)
+            .cloned()
+            .collect()
+    }
+}
+
+// Replace the file with the specified samples, through a temporary file so
+// that an interrupted write does not lose the history.
+fn rewrite(file: &Path, samples: &VecDeque<MetricsSample>) -> io::Result<()> {
+    let temporary_file = file.with_extension("tmp");
+    let mut rewritten = File::create(&temporary_file)?;
+    for sample in samples {
+        writeln!(rewritten, "{}", serde_json::to_string(sample)?)?;
+    }
+    rewritten.sync_all()?;
+    fs::rename(&temporary_file, file)
+}
+
+/// Milliseconds since the unix epoch, the timestamp of a new sample.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Parse a window like `90s`, `30m`, `1h` or `7d`.
+pub fn parse_window(window: &str) -> Result<Duration, String> {
+    let window = window.trim();
+    let unit_start = window
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(window.len());
+    let (amount, unit) = window.split_at(unit_start);
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid window: {}", window))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Invalid window unit, expected s, m, h or d: {}",
+                window
+            ))
+        }
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::util::test_util;
+
+    fn sample(timestamp: u64) -> MetricsSample {
+        MetricsSample {
+            timestamp,
+            cpu: 0.5,
+            peers_count: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_push_drops_oldest_samples() {
+        let history = MetricsHistory::new(2);
+        let now = now_millis();
+        for timestamp in [now - 2, now - 1, now] {
+            history.push(sample(timestamp)).unwrap();
+        }
+
+        assert_eq!(
+            history.window(Duration::from_secs(60)),
+            vec![sample(now - 1), sample(now)]
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let history = MetricsHistory::new(10);
+        let now = now_millis();
+        history.push(sample(now - 2 * 60 * 60 * 1000)).unwrap();
+        history.push(sample(now - 30 * 60 * 1000)).unwrap();
+        history.push(sample(now)).unwrap();
+
+        assert_eq!(history.window(Duration::from_secs(60 * 60)).len(), 2);
+        assert_eq!(history.window(Duration::from_secs(3 * 60 * 60)).len(), 3);
+    }
+
+    #[test]
+    fn test_open_loads_persisted_samples() {
+        let tmp_dir = test_util::tests::setup();
+        let file = tmp_dir.join("metrics_history.jsonl");
+        let now = now_millis();
+
+        let history = MetricsHistory::open(&file, 3).unwrap();
+        for timestamp in (0..10).map(|i| now - 10 + i) {
+            history.push(sample(timestamp)).unwrap();
+        }
+        // the file is rewritten instead of growing without bounds
+        let lines = fs::read_to_string(&file).unwrap().lines().count();
+        assert!(lines <= 6, "{} lines in the history file", lines);
+
+        let reopened = MetricsHistory::open(&file, 3).unwrap();
+        assert_eq!(
+            reopened.window(Duration::from_secs(60)),
+            history.window(Duration::from_secs(60))
+        );
+        assert_eq!(reopened.window(Duration::from_secs(60)).len(), 3);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_window("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_window("1h"), Ok(Duration::from_secs(60 * 60)));
+        assert_eq!(
+            parse_window("7d"),
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert!(parse_window("1w").is_err());
+        assert!(parse_window("h").is_err());
+        assert!(parse_window("").is_err());
+    }
+}
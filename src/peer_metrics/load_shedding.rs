@@ -0,0 +1,105 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+//! Load Shedding
+//!
+//! An overloaded node refuses artifact requests instead of slowing down every
+//! transfer. Peers retry the artifact elsewhere and HTTP clients are asked to
+//! retry later. Requests are served again as soon as the quality metric drops
+//! below the threshold.
+
+use crate::peer_metrics::metrics::PeerMetrics;
+use warp::reject::Reject;
+
+/// The number of seconds after which HTTP clients are asked to retry a
+/// request that was refused because the node is overloaded.
+pub const OVERLOADED_RETRY_AFTER_SECS: u64 = 10;
+
+/// A request was refused because the quality metric of the node exceeds the
+/// load shedding threshold.
+#[derive(Debug)]
+pub struct Overloaded {
+    pub quality_metric: f64,
+    pub retry_after_secs: u64,
+}
+
+impl Reject for Overloaded {}
+
+/// Decides whether artifact requests are refused, based on the quality
+/// metric of the node relative to its own hardware. Requests are never refused by default.
+#[derive(Clone, Default)]
+pub struct LoadShedding {
+    peer_metrics: Option<PeerMetrics>,
+    threshold: f64,
+}
+
+impl LoadShedding {
+    /// Refuse requests while the quality metric exceeds `threshold`, or never
+    /// when no threshold is set.
+    pub fn new(peer_metrics: PeerMetrics, threshold: Option<f64>) -> Self {
+        match threshold {
+            Some(threshold) => LoadShedding {
+                peer_metrics: Some(peer_metrics),
+                threshold,
+            },
+            None => LoadShedding::default(),
+        }
+    }
+
+    /// Check whether a request can be served.
+    pub fn check(&self) -> Result<(), Overloaded> {
+        let quality_metric = match &self.peer_metrics {
+            Some(peer_metrics) => peer_metrics.get_local_quality_metric(),
+            None => return Ok(()),
+        };
+        if quality_metric > self.threshold {
+            Err(Overloaded {
+                quality_metric,
+                retry_after_secs: OVERLOADED_RETRY_AFTER_SECS,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_without_threshold() {
+        let load_shedding = LoadShedding::new(PeerMetrics::default(), None);
+        assert!(load_shedding.check().is_ok());
+        assert!(LoadShedding::default().check().is_ok());
+    }
+
+    #[test]
+    fn test_check_with_threshold() {
+        // the quality metric is 0 until the system was sampled
+        let peer_metrics = PeerMetrics::default();
+        assert!(LoadShedding::new(peer_metrics.clone(), Some(0.5))
+            .check()
+            .is_ok());
+
+        // a negative threshold is exceeded by any quality metric
+        let overloaded = LoadShedding::new(peer_metrics, Some(-1.0))
+            .check()
+            .unwrap_err();
+        assert_eq!(overloaded.quality_metric, 0.0);
+        assert_eq!(overloaded.retry_after_secs, OVERLOADED_RETRY_AFTER_SECS);
+    }
+}
@@ -14,110 +14,680 @@
    limitations under the License.
 */
 
-/// Peer Quality Metrics
+//! Peer Quality Metrics
+//!
+//! The quality metric that a node advertises to its peers is a stress score
+//! from 0, for an idle node, to 1, for a node whose CPU, network, disk,
+//! artifact quota and memory are all exhausted. Peers with a lower score are
+//! preferred, e.g. to request builds from. Each component is normalized to
+//! the 0 to 1 range first: the CPU usage of all cores, the network and disk
+//! throughput relative to the throughput at which they are considered
+//! saturated, the used part of the allocated artifact disk space and the
+//! memory pressure, which is the used part of the memory or of the swap
+//! space, whichever is higher. The network stress is that of the busiest
+//! interface, relative to its link speed when it is known. The disk stress
+//! only counts the artifact I/O of the node itself, so that other processes
+//! on the machine don't skew it, unless the utilization of the artifact
+//! volume is measured too and is higher. The score is the
+//! weighted average of the components, so scores of nodes with different
+//! weights remain on the same scale. The components are sampled
+//! periodically and smoothed with exponentially weighted moving averages.
+//! The advertised score can be calibrated to the hardware of the node, see
+//! the calibration module.
+
+use super::calibration::Calibration;
+use log::warn;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
-use std::time;
-use sysinfo::{NetworkExt, ProcessExt, System, SystemExt};
+use std::time::{Duration, Instant};
+use sysinfo::{CpuExt, NetworkExt, System, SystemExt};
+
+/// The network throughput, in bytes per second, at which a network interface
+/// with an unknown link speed is considered saturated: 1 Gbit/s.
+pub const NETWORK_CAPACITY: f64 = 125_000_000_f64;
+/// The throughput of the artifact I/O of the node, in bytes per second, at
+/// which the disk is considered saturated.
+pub const DISK_CAPACITY: f64 = 500_000_000_f64;
+/// The default interval between two samples of the system.
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// The weight of a new sample in the moving averages of the components.
+pub const SMOOTHING_FACTOR: f64 = 0.3_f64;
 
-// peer metric constants
-const CPU_STRESS_WEIGHT: f64 = 2_f64;
-const NETWORK_STRESS_WEIGHT: f64 = 0.001_f64;
-const DISK_STRESS_WEIGHT: f64 = 0.001_f64;
+/// The weights of the components of the quality metric. Only the ratio
+/// between the weights matters. A component with weight 0 is ignored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetricWeights {
+    pub cpu: f64,
+    pub network: f64,
+    pub disk: f64,
+    pub quota: f64,
+    pub memory: f64,
+}
+
+impl Default for MetricWeights {
+    fn default() -> Self {
+        MetricWeights {
+            cpu: 2_f64,
+            network: 1_f64,
+            disk: 1_f64,
+            quota: 1_f64,
+            memory: 1_f64,
+        }
+    }
+}
+
+impl MetricWeights {
+    /// The weighted average of the normalized components, or 0 when all
+    /// weights are 0.
+    pub fn score(&self, stress: &Stress) -> f64 {
+        let total_weight = self.cpu + self.network + self.disk + self.quota + self.memory;
+        if total_weight <= 0_f64 {
+            return 0_f64;
+        }
+        (stress.cpu * self.cpu
+            + stress.network * self.network
+            + stress.disk * self.disk
+            + stress.quota * self.quota
+            + stress.memory * self.memory)
+            / total_weight
+    }
+}
 
+/// The components of the quality metric, each from 0 to 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stress {
+    pub cpu: f64,
+    pub network: f64,
+    pub disk: f64,
+    pub quota: f64,
+    pub memory: f64,
+}
+
+impl Stress {
+    // Move the exponentially weighted moving averages towards the sample.
+    fn smooth(&mut self, sample: Stress) {
+        self.cpu += SMOOTHING_FACTOR * (sample.cpu - self.cpu);
+        self.network += SMOOTHING_FACTOR * (sample.network - self.network);
+        self.disk += SMOOTHING_FACTOR * (sample.disk - self.disk);
+        self.quota += SMOOTHING_FACTOR * (sample.quota - self.quota);
+        self.memory += SMOOTHING_FACTOR * (sample.memory - self.memory);
+    }
+}
+
+/// Returns the used part of the allocated artifact disk space, from 0 to 1,
+/// or None when no disk space is allocated.
+pub type QuotaUsage = Box<dyn Fn() -> Option<f64> + Send>;
+
+/// Returns the number of bytes that the node read from and wrote to its
+/// artifact storage so far.
+pub type StorageIo = Box<dyn Fn() -> u64 + Send>;
+
+/// How the artifact storage of the node is measured. The disk and quota
+/// components are 0 without probes.
 #[derive(Default)]
+pub struct StorageProbes {
+    pub quota_usage: Option<QuotaUsage>,
+    pub storage_io: Option<StorageIo>,
+    /// A path on the volume that holds the artifacts. Its utilization,
+    /// which includes the I/O of other processes, is used as disk stress
+    /// when it is higher. Only measured on Linux.
+    pub volume: Option<PathBuf>,
+}
+
+// The smoothed components, or None until the first sample was taken.
+type SmoothedStress = Arc<Mutex<Option<Stress>>>;
+
+/// The quality metric of the local node. The system is sampled on a
+/// background thread, which keeps moving averages of the components so
+/// that reading the metric is instant and short spikes are smoothed out.
+/// The thread stops when the last clone is dropped.
+#[derive(Clone, Default)]
 pub struct PeerMetrics {
-    system: System,
+    weights: MetricWeights,
+    stress: SmoothedStress,
+    calibration: Option<Calibration>,
 }
 
 impl PeerMetrics {
     pub fn new() -> Self {
-        let mut peer_metrics = Self {
+        Self::start(
+            MetricWeights::default(),
+            DEFAULT_SAMPLE_INTERVAL,
+            StorageProbes::default(),
+        )
+    }
+
+    /// Start sampling the system every `interval`.
+    pub fn start(weights: MetricWeights, interval: Duration, probes: StorageProbes) -> Self {
+        let stress = SmoothedStress::default();
+        let weak_stress = Arc::downgrade(&stress);
+        let spawned = thread::Builder::new()
+            .name(String::from("peer-metrics"))
+            .spawn(move || sample_periodically(weak_stress, interval, probes));
+        if let Err(e) = spawned {
+            warn!("Failed to start sampling the peer metrics: {}", e);
+        }
+        PeerMetrics {
+            weights,
+            stress,
+            calibration: None,
+        }
+    }
+
+    /// Advertise the quality metric in calibrated units, so that it is
+    /// comparable with that of peers on different hardware.
+    pub fn calibrated(self, calibration: Calibration) -> Self {
+        PeerMetrics {
+            calibration: Some(calibration),
+            ..self
+        }
+    }
+
+    /// The smoothed components of the quality metric, or None until the
+    /// system was sampled.
+    pub fn stress(&self) -> Option<Stress> {
+        *self.stress.lock().unwrap()
+    }
+
+    /// Get the local stress metric to advertise to peers, from 0 to 1, in
+    /// calibrated units when the metrics are calibrated. It is 0 until the
+    /// first sample was taken.
+    pub fn get_quality_metric(&self) -> f64 {
+        match (self.stress(), &self.calibration) {
+            (Some(stress), Some(calibration)) => {
+                self.weights.score(&calibration.calibrate(&stress))
+            }
+            (Some(stress), None) => self.weights.score(&stress),
+            (None, _) => 0_f64,
+        }
+    }
+
+    /// Get the stress metric relative to the hardware of the node itself,
+    /// from 0 to 1, e.g. to decide whether the node is overloaded. It is 0
+    /// until the first sample was taken.
+    pub fn get_local_quality_metric(&self) -> f64 {
+        match self.stress() {
+            Some(stress) => self.weights.score(&stress),
+            None => 0_f64,
+        }
+    }
+}
+
+fn sample_periodically(
+    stress: Weak<Mutex<Option<Stress>>>,
+    interval: Duration,
+    probes: StorageProbes,
+) {
+    let mut sampler = Sampler::new(probes);
+    loop {
+        thread::sleep(interval);
+        let sample = sampler.sample();
+        match stress.upgrade() {
+            Some(stress) => {
+                let mut stress = stress.lock().unwrap();
+                match stress.as_mut() {
+                    Some(stress) => stress.smooth(sample),
+                    None => *stress = Some(sample),
+                }
+            }
+            None => return,
+        }
+    }
+}
+
+// Measures the components of the quality metric since the previous sample.
+struct Sampler {
+    system: System,
+    sampled_at: Instant,
+    probes: StorageProbes,
+    // the counters of the previous sample
+    storage_io: u64,
+    volume_busy: Option<u64>,
+}
+
+impl Sampler {
+    fn new(probes: StorageProbes) -> Self {
+        let storage_io = read_storage_io(&probes);
+        let volume_busy = probes.volume.as_deref().and_then(volume_busy_millis);
+        Sampler {
             system: System::new_all(),
+            sampled_at: Instant::now(),
+            probes,
+            storage_io,
+            volume_busy,
+        }
+    }
+
+    // Refresh only what the components are computed from, which is much
+    // cheaper than refreshing everything.
+    fn sample(&mut self) -> Stress {
+        self.system.refresh_cpu();
+        self.system.refresh_networks();
+        self.system.refresh_memory();
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.sampled_at);
+        self.sampled_at = now;
+        Stress {
+            cpu: get_cpu_stress(&self.system),
+            network: get_network_stress(&self.system, elapsed),
+            disk: self.sample_disk_stress(elapsed),
+            quota: normalize(
+                self.probes
+                    .quota_usage
+                    .as_ref()
+                    .and_then(|quota_usage| quota_usage())
+                    .unwrap_or_default(),
+            ),
+            memory: get_memory_stress(&self.system),
+        }
+    }
+
+    // The stress from the artifact I/O of the node since the previous
+    // sample, or the utilization of the artifact volume when it is higher.
+    fn sample_disk_stress(&mut self, elapsed: Duration) -> f64 {
+        let storage_io = read_storage_io(&self.probes);
+        let io_stress = get_disk_stress(storage_io.saturating_sub(self.storage_io), elapsed);
+        self.storage_io = storage_io;
+
+        let volume_busy = self.probes.volume.as_deref().and_then(volume_busy_millis);
+        let volume_stress = match (self.volume_busy, volume_busy) {
+            (Some(previous), Some(current)) => {
+                utilization(current.saturating_sub(previous), elapsed)
+            }
+            _ => 0_f64,
         };
-        peer_metrics.initialize();
-        peer_metrics
+        self.volume_busy = volume_busy;
+
+        io_stress.max(volume_stress)
     }
+}
+
+fn read_storage_io(probes: &StorageProbes) -> u64 {
+    match &probes.storage_io {
+        Some(storage_io) => storage_io(),
+        None => 0,
+    }
+}
 
-    fn initialize(&mut self) {
-        self.system.refresh_all();
-        thread::sleep(time::Duration::from_millis(500));
-        self.system.refresh_all();
+// The CPU usage of all cores since the previous refresh, from 0 to 1.
+fn get_cpu_stress(system: &System) -> f64 {
+    normalize(system.global_cpu_info().cpu_usage() as f64 / 100_f64)
+}
+
+// The bytes sent and received since the previous refresh over the busiest
+// network interface, relative to its link speed. The loopback interface is
+// left out, its traffic does not leave the node.
+fn get_network_stress(system: &System, elapsed: Duration) -> f64 {
+    system
+        .networks()
+        .into_iter()
+        .filter(|(interface_name, _network)| !is_loopback(interface_name))
+        .map(|(interface_name, network)| {
+            let capacity = link_capacity(interface_name).unwrap_or(NETWORK_CAPACITY);
+            throughput_stress(
+                network.received() + network.transmitted(),
+                elapsed,
+                capacity,
+            )
+        })
+        .fold(0_f64, f64::max)
+}
+
+fn is_loopback(interface_name: &str) -> bool {
+    interface_name == "lo" || interface_name == "lo0"
+}
+
+// The link speed of a network interface in bytes per second, which Linux
+// reports in Mbit/s. Virtual interfaces and interfaces that are down report
+// no speed or -1.
+#[cfg(target_os = "linux")]
+fn link_capacity(interface_name: &str) -> Option<f64> {
+    let speed = std::fs::read_to_string(format!("/sys/class/net/{}/speed", interface_name)).ok()?;
+    parse_link_speed(&speed)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn link_capacity(_interface_name: &str) -> Option<f64> {
+    None
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_link_speed(speed: &str) -> Option<f64> {
+    match speed.trim().parse::<i64>() {
+        Ok(megabits) if megabits > 0 => Some(megabits as f64 * 125_000_f64),
+        _ => None,
     }
+}
+
+// The artifact bytes that the node read and wrote in the elapsed time,
+// relative to DISK_CAPACITY.
+fn get_disk_stress(bytes: u64, elapsed: Duration) -> f64 {
+    throughput_stress(bytes, elapsed, DISK_CAPACITY)
+}
 
-    /// Get the local stress metric to advertise to peers
-    pub fn get_quality_metric(&mut self) -> f64 {
-        let mut qm = get_cpu_stress(&mut self.system) * CPU_STRESS_WEIGHT;
-        qm += get_network_stress(&mut self.system) * NETWORK_STRESS_WEIGHT;
-        qm + get_disk_stress(&mut self.system) * DISK_STRESS_WEIGHT
+// The part of the elapsed time that a volume was busy.
+fn utilization(busy_millis: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0_f64;
     }
+    normalize(busy_millis as f64 / (elapsed.as_secs_f64() * 1000_f64))
+}
+
+// The milliseconds that the block device holding the path spent doing I/O
+// since boot. Paths on virtual file systems, like tmpfs or overlays, have
+// no block device.
+#[cfg(target_os = "linux")]
+fn volume_busy_millis(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = std::fs::metadata(path).ok()?.dev();
+    let stat = std::fs::read_to_string(format!(
+        "/sys/dev/block/{}:{}/stat",
+        dev_major(dev),
+        dev_minor(dev)
+    ))
+    .ok()?;
+    parse_io_ticks(&stat)
 }
 
-// This function gets the current CPU load on the system.
-fn get_cpu_stress(system: &mut System) -> f64 {
-    system.refresh_all();
+#[cfg(not(target_os = "linux"))]
+fn volume_busy_millis(_path: &Path) -> Option<u64> {
+    None
+}
 
-    let load_avg = system.load_average();
-    load_avg.one //using the average over the last 1 minute
+// The major and minor numbers of a device id, as encoded by glibc.
+#[cfg(any(target_os = "linux", test))]
+fn dev_major(dev: u64) -> u64 {
+    ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff)
 }
 
-// This function gets the current network load on the system
-fn get_network_stress(system: &mut System) -> f64 {
-    system.refresh_all();
+#[cfg(any(target_os = "linux", test))]
+fn dev_minor(dev: u64) -> u64 {
+    (dev & 0xff) | ((dev >> 12) & !0xff)
+}
 
-    let networks = system.networks();
+// The io_ticks of a block device stat file, its tenth field.
+#[cfg(any(target_os = "linux", test))]
+fn parse_io_ticks(stat: &str) -> Option<u64> {
+    stat.split_whitespace().nth(9)?.parse().ok()
+}
+
+// The used part of the memory or of the swap space, whichever is higher,
+// since a node that swaps heavily is stressed even with memory available.
+fn get_memory_stress(system: &System) -> f64 {
+    let memory_stress = used_part(
+        system
+            .total_memory()
+            .saturating_sub(system.available_memory()),
+        system.total_memory(),
+    );
+    let swap_stress = used_part(system.used_swap(), system.total_swap());
+    normalize(memory_stress.max(swap_stress))
+}
 
-    let mut packets_in = 0;
-    let mut packets_out = 0;
-    for (_interface_name, network) in networks {
-        packets_in += network.received();
-        packets_out += network.transmitted();
+fn used_part(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0_f64;
     }
-    (packets_in as f64) + (packets_out as f64)
-    //TODO: add network card capabilities to the metric. cards with > network capacity should get a lower stress number.
+    used as f64 / total as f64
 }
 
-fn get_disk_stress(system: &mut System) -> f64 {
-    system.refresh_all();
+fn throughput_stress(bytes: u64, elapsed: Duration, capacity: f64) -> f64 {
+    if elapsed.is_zero() {
+        return 0_f64;
+    }
+    normalize(bytes as f64 / elapsed.as_secs_f64() / capacity)
+}
 
-    // Sum up the disk usage measured as total read and writes per process:
-    let mut total_usage = 0_u64;
-    for process in system.processes().values() {
-        let usage = process.disk_usage();
-        total_usage = total_usage + usage.total_written_bytes + usage.total_read_bytes;
+fn normalize(stress: f64) -> f64 {
+    if stress.is_nan() {
+        0_f64
+    } else {
+        stress.clamp(0_f64, 1_f64)
     }
-    total_usage as f64
 }
 
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
+    use crate::peer_metrics::calibration::{REFERENCE_CPU_THROUGHPUT, REFERENCE_DISK_THROUGHPUT};
+
+    fn assert_normalized(stress: f64) {
+        assert!(
+            (0_f64..=1_f64).contains(&stress),
+            "{} is not normalized",
+            stress
+        );
+    }
+
+    fn no_probes() -> StorageProbes {
+        StorageProbes::default()
+    }
 
     #[test]
     fn cpu_load_test() {
-        let mut peer_metrics = PeerMetrics::new();
+        let sampler = Sampler::new(no_probes());
 
-        get_cpu_stress(&mut peer_metrics.system);
+        assert_normalized(get_cpu_stress(&sampler.system));
     }
 
     #[test]
     fn network_load_test() {
-        let mut peer_metrics = PeerMetrics::new();
+        let sampler = Sampler::new(no_probes());
+
+        assert_normalized(get_network_stress(
+            &sampler.system,
+            Duration::from_millis(500),
+        ));
+    }
 
-        get_network_stress(&mut peer_metrics.system);
+    #[test]
+    fn parse_link_speed_test() {
+        assert_eq!(parse_link_speed("1000\n"), Some(NETWORK_CAPACITY));
+        assert_eq!(parse_link_speed("10000"), Some(10_f64 * NETWORK_CAPACITY));
+        assert_eq!(parse_link_speed("-1\n"), None);
+        assert_eq!(parse_link_speed("0"), None);
+        assert_eq!(parse_link_speed(""), None);
+    }
+
+    #[test]
+    fn is_loopback_test() {
+        assert!(is_loopback("lo"));
+        assert!(is_loopback("lo0"));
+        assert!(!is_loopback("eth0"));
+        assert!(!is_loopback("lowpan0"));
     }
 
     #[test]
     fn disk_load_test() {
-        let mut peer_metrics = PeerMetrics::new();
+        let storage_io = Arc::new(Mutex::new(0_u64));
+        let sampled_io = storage_io.clone();
+        let mut sampler = Sampler::new(StorageProbes {
+            storage_io: Some(Box::new(move || *sampled_io.lock().unwrap())),
+            ..StorageProbes::default()
+        });
 
-        get_disk_stress(&mut peer_metrics.system);
+        *storage_io.lock().unwrap() = DISK_CAPACITY as u64 / 2;
+        assert_eq!(sampler.sample_disk_stress(Duration::from_secs(1)), 0.5_f64);
+        assert_eq!(sampler.sample_disk_stress(Duration::from_secs(1)), 0_f64);
+    }
+
+    #[test]
+    fn volume_load_test() {
+        let mut sampler = Sampler::new(StorageProbes {
+            volume: Some(std::env::temp_dir()),
+            ..StorageProbes::default()
+        });
+
+        assert_normalized(sampler.sample_disk_stress(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn utilization_test() {
+        let second = Duration::from_secs(1);
+        assert_eq!(utilization(250, second), 0.25_f64);
+        assert_eq!(utilization(2000, second), 1_f64);
+        assert_eq!(utilization(250, Duration::ZERO), 0_f64);
+    }
+
+    #[test]
+    fn parse_io_ticks_test() {
+        assert_eq!(
+            parse_io_ticks("   1234  56  7890  12  345  6  789  10  0  4321  2345  0 0 0 0\n"),
+            Some(4321)
+        );
+        assert_eq!(parse_io_ticks("1 2 3"), None);
+        assert_eq!(parse_io_ticks(""), None);
+    }
+
+    #[test]
+    fn dev_numbers_test() {
+        // 8:1, e.g. /dev/sda1, and 259:3, e.g. /dev/nvme0n1p3
+        assert_eq!((dev_major(0x801), dev_minor(0x801)), (8, 1));
+        assert_eq!((dev_major(0x10303), dev_minor(0x10303)), (259, 3));
     }
 
     #[test]
     fn quality_metric_test() {
-        let mut peer_metrics = PeerMetrics::new();
+        let peer_metrics = PeerMetrics::start(
+            MetricWeights::default(),
+            Duration::from_millis(10),
+            no_probes(),
+        );
+        assert_eq!(peer_metrics.get_quality_metric(), 0_f64);
+
+        let started_at = Instant::now();
+        while peer_metrics.stress.lock().unwrap().is_none() {
+            assert!(started_at.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_normalized(peer_metrics.get_quality_metric());
+    }
 
-        peer_metrics.get_quality_metric();
+    #[test]
+    fn calibrated_quality_metric_test() {
+        let peer_metrics = PeerMetrics::default().calibrated(Calibration {
+            cpus: 1,
+            cpu_throughput: REFERENCE_CPU_THROUGHPUT / 2_f64,
+            disk_throughput: REFERENCE_DISK_THROUGHPUT,
+        });
+        assert_eq!(peer_metrics.get_quality_metric(), 0_f64);
+
+        *peer_metrics.stress.lock().unwrap() = Some(Stress::default());
+        assert!(peer_metrics.get_quality_metric() > 0_f64);
+        assert_eq!(peer_metrics.get_local_quality_metric(), 0_f64);
+    }
+
+    #[test]
+    fn memory_load_test() {
+        let mut sampler = Sampler::new(no_probes());
+        sampler.system.refresh_memory();
+
+        assert_normalized(get_memory_stress(&sampler.system));
+    }
+
+    #[test]
+    fn sample_test() {
+        let mut sampler = Sampler::new(StorageProbes {
+            quota_usage: Some(Box::new(|| Some(0.75_f64))),
+            ..StorageProbes::default()
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let stress = sampler.sample();
+        assert_normalized(stress.cpu);
+        assert_normalized(stress.network);
+        assert_normalized(stress.disk);
+        assert_eq!(stress.quota, 0.75_f64);
+        assert_normalized(stress.memory);
+    }
+
+    #[test]
+    fn used_part_test() {
+        assert_eq!(used_part(1, 4), 0.25_f64);
+        assert_eq!(used_part(1, 0), 0_f64);
+    }
+
+    #[test]
+    fn smooth_test() {
+        let mut stress = Stress::default();
+        stress.smooth(Stress {
+            cpu: 1_f64,
+            network: 0_f64,
+            disk: 0.5_f64,
+            quota: 1_f64,
+            memory: 0.5_f64,
+        });
+        assert_eq!(
+            stress,
+            Stress {
+                cpu: SMOOTHING_FACTOR,
+                network: 0_f64,
+                disk: 0.5_f64 * SMOOTHING_FACTOR,
+                quota: SMOOTHING_FACTOR,
+                memory: 0.5_f64 * SMOOTHING_FACTOR,
+            }
+        );
+    }
+
+    #[test]
+    fn throughput_stress_test() {
+        let second = Duration::from_secs(1);
+        assert_eq!(throughput_stress(50, second, 100_f64), 0.5_f64);
+        assert_eq!(throughput_stress(500, second, 100_f64), 1_f64);
+        assert_eq!(throughput_stress(500, Duration::ZERO, 100_f64), 0_f64);
+    }
+
+    #[test]
+    fn score_test() {
+        let idle = Stress::default();
+        let saturated = Stress {
+            cpu: 1_f64,
+            network: 1_f64,
+            disk: 1_f64,
+            quota: 1_f64,
+            memory: 1_f64,
+        };
+        let cpu_saturated = Stress {
+            cpu: 1_f64,
+            ..Stress::default()
+        };
+
+        let weights = MetricWeights::default();
+        assert_eq!(weights.score(&idle), 0_f64);
+        assert_eq!(weights.score(&saturated), 1_f64);
+        assert_eq!(weights.score(&cpu_saturated), 2_f64 / 6_f64);
+
+        let cpu_only = MetricWeights {
+            cpu: 1_f64,
+            network: 0_f64,
+            disk: 0_f64,
+            quota: 0_f64,
+            memory: 0_f64,
+        };
+        let quarter_cpu = Stress {
+            cpu: 0.25_f64,
+            ..saturated
+        };
+        assert_eq!(cpu_only.score(&quarter_cpu), 0.25_f64);
+
+        let memory_only = MetricWeights {
+            cpu: 0_f64,
+            memory: 1_f64,
+            ..cpu_only
+        };
+        let half_memory = Stress {
+            memory: 0.5_f64,
+            ..idle
+        };
+        assert_eq!(memory_only.score(&half_memory), 0.5_f64);
+
+        let no_weights = MetricWeights {
+            cpu: 0_f64,
+            ..cpu_only
+        };
+        assert_eq!(no_weights.score(&saturated), 0_f64);
     }
 }
@@ -14,4 +14,5 @@
    limitations under the License.
 */
 
+pub mod key_rotation;
 pub mod log;
@@ -0,0 +1,117 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use super::log::TransparencyLogError;
+use crate::network::signed_document::SignedDocument;
+use libp2p::identity::Keypair;
+use serde::{Deserialize, Serialize};
+
+/// The statement that a key, identified by its peer id, is retired in favor
+/// of a successor key.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub previous_key: String,
+    pub next_key: String,
+}
+
+/// A cross-certified key rotation: the retired key vouches for its successor
+/// and the successor proves that it holds the new private key, by both
+/// signing the same rotation statement.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyRotationCertificate {
+    pub previous_signature: SignedDocument,
+    pub next_signature: SignedDocument,
+}
+
+/// The payload of a block that commits a key rotation certificate.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KeyRotationBlockPayload {
+    pub key_rotation: KeyRotationCertificate,
+}
+
+impl KeyRotationCertificate {
+    /// Creates the certificate of the rotation from the previous to the
+    /// next keypair.
+    pub fn new(previous_keypair: &Keypair, next_keypair: &Keypair) -> anyhow::Result<Self> {
+        let content = serde_json::to_string(&KeyRotation {
+            previous_key: previous_keypair.public().to_peer_id().to_string(),
+            next_key: next_keypair.public().to_peer_id().to_string(),
+        })?;
+        Ok(KeyRotationCertificate {
+            previous_signature: SignedDocument::new(previous_keypair, &content)?,
+            next_signature: SignedDocument::new(next_keypair, &content)?,
+        })
+    }
+
+    /// Verifies that both keys signed the rotation and returns it.
+    pub fn verify(&self) -> Result<KeyRotation, TransparencyLogError> {
+        let invalid = |reason: &str| TransparencyLogError::InvalidKeyRotation(reason.to_owned());
+        if !self.previous_signature.verify() || !self.next_signature.verify() {
+            return Err(invalid("a signature is invalid"));
+        }
+        if self.previous_signature.content != self.next_signature.content {
+            return Err(invalid("the keys signed different statements"));
+        }
+
+        let key_rotation: KeyRotation = serde_json::from_str(&self.previous_signature.content)?;
+        if key_rotation.previous_key == key_rotation.next_key {
+            return Err(invalid("a key cannot succeed itself"));
+        }
+        let signed_by = |document: &SignedDocument, key: &str| {
+            document
+                .publisher()
+                .map(|publisher| publisher.to_string() == key)
+                .unwrap_or(false)
+        };
+        if !signed_by(&self.previous_signature, &key_rotation.previous_key)
+            || !signed_by(&self.next_signature, &key_rotation.next_key)
+        {
+            return Err(invalid("the signatures do not match the rotated keys"));
+        }
+        Ok(key_rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_key_rotation_certificate() {
+        let previous_keypair = Keypair::generate_ed25519();
+        let next_keypair = Keypair::generate_ed25519();
+
+        let certificate = KeyRotationCertificate::new(&previous_keypair, &next_keypair).unwrap();
+        assert_eq!(
+            certificate.verify().unwrap(),
+            KeyRotation {
+                previous_key: previous_keypair.public().to_peer_id().to_string(),
+                next_key: next_keypair.public().to_peer_id().to_string(),
+            }
+        );
+
+        let other_certificate =
+            KeyRotationCertificate::new(&previous_keypair, &Keypair::generate_ed25519()).unwrap();
+        let forged_certificate = KeyRotationCertificate {
+            previous_signature: other_certificate.previous_signature,
+            next_signature: certificate.next_signature,
+        };
+        assert!(matches!(
+            forged_certificate.verify(),
+            Err(TransparencyLogError::InvalidKeyRotation(_))
+        ));
+    }
+}
@@ -14,15 +14,18 @@
    limitations under the License.
 */
 
+use super::key_rotation::{KeyRotation, KeyRotationBlockPayload, KeyRotationCertificate};
 use crate::artifact_service::model::PackageType;
 use crate::blockchain_service::event::BlockchainEventClient;
+use crate::network::signed_document::SignedDocument;
 use libp2p::core::ParseError;
 use libp2p::PeerId;
 use log::{debug, error};
 use pyrsia_blockchain_network::error::BlockchainError;
 use rusqlite::types::{ToSqlOutput, Value};
-use rusqlite::{params, Connection, ToSql};
+use rusqlite::{params, Connection, OptionalExtension, Params, Row, ToSql};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -72,6 +75,15 @@ pub enum TransparencyLogError {
     BlockchainFailure(#[from] BlockchainError),
     #[error("Failure while generating JSON from transparency log: {0}")]
     SerdeJsonFailure(#[from] serde_json::error::Error),
+    #[error("Invalid revocation: {0}")]
+    InvalidRevocation(String),
+    #[error("Invalid key rotation: {0}")]
+    InvalidKeyRotation(String),
+    #[error("Artifact with hash {artifact_hash} was revoked: {reason}")]
+    ArtifactRevoked {
+        artifact_hash: String,
+        reason: String,
+    },
 }
 
 #[derive(
@@ -123,6 +135,33 @@ pub struct AddArtifactRequest {
     pub artifact_hash: String,
 }
 
+/// A statement that the artifacts with the specified hash must no longer be
+/// served, e.g. because they turned out to be malicious. Revocations are
+/// signed by the node that issued them and committed to the blockchain.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Revocation {
+    pub artifact_hash: String,
+    pub reason: String,
+}
+
+/// The payload of a block that commits a signed revocation.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RevocationBlockPayload {
+    pub revocation: SignedDocument,
+}
+
+impl Revocation {
+    /// Parses the revocation of a signed document, after verifying its signature.
+    pub fn from_signed_document(document: &SignedDocument) -> Result<Self, TransparencyLogError> {
+        if !document.verify() {
+            return Err(TransparencyLogError::InvalidRevocation(String::from(
+                "the signature is invalid",
+            )));
+        }
+        Ok(serde_json::from_str(&document.content)?)
+    }
+}
+
 pub struct AuthorizedNode {
     pub id: String,
     pub public_key: String,
@@ -133,6 +172,9 @@ pub struct AuthorizedNode {
 ///
 /// The transparency log itself depends on the blockchain component to retrieve
 /// transactions and to reach consensus on the publication of new transactions.
+/// Each transparency log is published in a block that is signed by the node
+/// that commits it and broadcast to the other peers, which is how the log is
+/// signed and replicated.
 ///
 /// It uses a local database to store and index transparency log information to simplify
 /// access.
@@ -238,6 +280,91 @@ impl TransparencyLogService {
         Ok(())
     }
 
+    /// Commits a signed revocation to the blockchain, which distributes it to
+    /// the other peers, and records it locally.
+    pub async fn revoke_artifact(
+        &self,
+        document: &SignedDocument,
+    ) -> Result<Revocation, TransparencyLogError> {
+        let revocation = Revocation::from_signed_document(document)?;
+
+        let payload = serde_json::to_vec(&RevocationBlockPayload {
+            revocation: document.clone(),
+        })?;
+        self.blockchain_event_client.add_block(payload).await?;
+
+        self.write_revocation(document)?;
+        Ok(revocation)
+    }
+
+    /// Records a signed revocation. The first revocation of an artifact hash
+    /// is kept.
+    pub fn write_revocation(
+        &self,
+        document: &SignedDocument,
+    ) -> Result<Revocation, TransparencyLogError> {
+        let revocation = Revocation::from_signed_document(document)?;
+        let conn = self.open_db()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO REVOCATION (artifact_hash, document) VALUES (?1, ?2)",
+            params![revocation.artifact_hash, serde_json::to_string(document)?],
+        )?;
+        debug!(
+            "Revocation of artifact hash {} recorded",
+            revocation.artifact_hash
+        );
+        Ok(revocation)
+    }
+
+    /// Find the revocation of the artifacts with the specified hash, if any.
+    pub fn find_revocation(
+        &self,
+        artifact_hash: &str,
+    ) -> Result<Option<Revocation>, TransparencyLogError> {
+        let conn = self.open_db()?;
+        let document: Option<String> = conn
+            .query_row(
+                "SELECT document FROM REVOCATION WHERE artifact_hash = ?1",
+                params![artifact_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match document {
+            Some(document) => Ok(Some(Revocation::from_signed_document(
+                &serde_json::from_str(&document)?,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The revocation list: all signed revocations known to this node.
+    pub fn find_revocations(&self) -> Result<Vec<SignedDocument>, TransparencyLogError> {
+        let conn = self.open_db()?;
+        let mut stmt = conn.prepare("SELECT document FROM REVOCATION ORDER BY artifact_hash")?;
+        let documents = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut revocations = vec![];
+        for document in documents {
+            revocations.push(serde_json::from_str(&document?)?);
+        }
+        Ok(revocations)
+    }
+
+    /// Verifies that the artifacts with the hash of the specified transparency
+    /// log were not revoked. Returns an ArtifactRevoked error otherwise.
+    pub fn verify_artifact_not_revoked(
+        &self,
+        transparency_log: &TransparencyLog,
+    ) -> Result<(), TransparencyLogError> {
+        match self.find_revocation(&transparency_log.artifact_hash)? {
+            Some(revocation) => Err(TransparencyLogError::ArtifactRevoked {
+                artifact_hash: revocation.artifact_hash,
+                reason: revocation.reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Gets the latest transparency log for the specified package of which the
     /// operation is either AddArtifact or RemoveArtifact. Returns an error
     /// when no transparency log could be found.
@@ -290,13 +417,105 @@ impl TransparencyLogService {
     }
 
     /// Get a list of auth node PeerID. Return an error when no PeerID could be found.
+    /// The successors of rotated keys of authorized nodes are authorized as well.
     pub fn get_authorized_nodes(&self) -> Result<Vec<PeerId>, TransparencyLogError> {
-        Ok(self
-            .find_added_nodes()?
-            .iter()
+        let mut nodes = vec![];
+        for node in self.find_added_nodes()? {
             //Get PeerId in the correct format, ignoring parsing errors
-            .flat_map(|node| PeerId::from_str(&node.node_id))
-            .collect::<Vec<PeerId>>())
+            if let Ok(peer_id) = PeerId::from_str(&node.node_id) {
+                for key in self.key_chain(&peer_id)? {
+                    if !nodes.contains(&key) {
+                        nodes.push(key);
+                    }
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Commits a cross-certified key rotation to the blockchain, which
+    /// distributes it to the other peers, and records it locally. A key can
+    /// only be rotated once.
+    pub async fn add_key_rotation(
+        &self,
+        certificate: &KeyRotationCertificate,
+    ) -> Result<KeyRotation, TransparencyLogError> {
+        let key_rotation = certificate.verify()?;
+        if self.find_successor(&key_rotation.previous_key)?.is_some() {
+            return Err(TransparencyLogError::InvalidKeyRotation(format!(
+                "key {} was rotated already",
+                key_rotation.previous_key
+            )));
+        }
+
+        let payload = serde_json::to_vec(&KeyRotationBlockPayload {
+            key_rotation: certificate.clone(),
+        })?;
+        self.blockchain_event_client.add_block(payload).await?;
+
+        self.write_key_rotation(certificate)
+    }
+
+    /// Records a cross-certified key rotation. The first rotation of a key is kept.
+    pub fn write_key_rotation(
+        &self,
+        certificate: &KeyRotationCertificate,
+    ) -> Result<KeyRotation, TransparencyLogError> {
+        let key_rotation = certificate.verify()?;
+        let conn = self.open_db()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO KEY_ROTATION (previous_key, next_key, certificate) VALUES (?1, ?2, ?3)",
+            params![
+                key_rotation.previous_key,
+                key_rotation.next_key,
+                serde_json::to_string(certificate)?
+            ],
+        )?;
+        debug!(
+            "Rotation of key {} to {} recorded",
+            key_rotation.previous_key, key_rotation.next_key
+        );
+        Ok(key_rotation)
+    }
+
+    /// Find the key that succeeded the specified key, if it was rotated.
+    pub fn find_successor(&self, peer_id: &str) -> Result<Option<String>, TransparencyLogError> {
+        let conn = self.open_db()?;
+        Ok(conn
+            .query_row(
+                "SELECT next_key FROM KEY_ROTATION WHERE previous_key = ?1",
+                params![peer_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// The specified key followed by all its successors, oldest first. The
+    /// last key of the chain is the current key.
+    pub fn key_chain(&self, peer_id: &PeerId) -> Result<Vec<PeerId>, TransparencyLogError> {
+        let mut chain = vec![*peer_id];
+        let mut visited = HashSet::from([*peer_id]);
+        while let Some(next_key) = self.find_successor(&chain[chain.len() - 1].to_string())? {
+            match PeerId::from_str(&next_key) {
+                Ok(next_key) if visited.insert(next_key) => chain.push(next_key),
+                _ => break,
+            }
+        }
+        Ok(chain)
+    }
+
+    /// All key rotation certificates known to this node.
+    pub fn find_key_rotations(&self) -> Result<Vec<KeyRotationCertificate>, TransparencyLogError> {
+        let conn = self.open_db()?;
+        let mut stmt =
+            conn.prepare("SELECT certificate FROM KEY_ROTATION ORDER BY previous_key")?;
+        let certificates = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut key_rotations = vec![];
+        for certificate in certificates {
+            key_rotations.push(serde_json::from_str(&certificate?)?);
+        }
+        Ok(key_rotations)
     }
 
     /// Verifies that a specified node can be added to the transparency log database.
@@ -330,7 +549,7 @@ impl TransparencyLogService {
         fs::create_dir_all(db_path.clone())?;
         db_path.push("transparency_log.db");
         let conn = Connection::open(db_path)?;
-        match conn.execute(
+        match conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS TRANSPARENCYLOG (
                 id TEXT PRIMARY KEY,
                 package_type TEXT,
@@ -345,8 +564,16 @@ impl TransparencyLogService {
                 operation TEXT NOT NULL,
                 node_id TEXT,
                 node_public_key TEXT
-            )",
-            [],
+            );
+            CREATE TABLE IF NOT EXISTS REVOCATION (
+                artifact_hash TEXT PRIMARY KEY,
+                document TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS KEY_ROTATION (
+                previous_key TEXT PRIMARY KEY,
+                next_key TEXT NOT NULL,
+                certificate TEXT NOT NULL
+            );",
         ) {
             Ok(_) => Ok(conn),
             Err(err) => {
@@ -357,9 +584,8 @@ impl TransparencyLogService {
     }
 
     pub fn find_transparency_log(&self, id: &str) -> Result<TransparencyLog, TransparencyLogError> {
-        let query = ["SELECT * FROM TRANSPARENCYLOG WHERE id = '", id, "';"];
-
-        let results = self.process_query(query.join("").as_str())?;
+        let results =
+            self.process_query("SELECT * FROM TRANSPARENCYLOG WHERE id = ?1;", params![id])?;
 
         if results.len() == 1 {
             Ok(results.into_iter().next().unwrap())
@@ -368,6 +594,72 @@ impl TransparencyLogService {
         }
     }
 
+    /// Find the transparency log that added the artifact with the specified
+    /// artifact id to the network.
+    pub fn find_artifact_transparency_log(
+        &self,
+        artifact_id: &str,
+    ) -> Result<TransparencyLog, TransparencyLogError> {
+        self.process_query(
+            "SELECT * FROM TRANSPARENCYLOG WHERE artifact_id = ?1;",
+            params![artifact_id],
+        )?
+        .into_iter()
+        .find(|record| record.operation == Operation::AddArtifact)
+        .ok_or_else(|| TransparencyLogError::LogNotFound {
+            id: artifact_id.to_owned(),
+        })
+    }
+
+    /// Find the transparency logs of the artifacts with the specified hash,
+    /// oldest first.
+    pub fn find_transparency_logs_by_artifact_hash(
+        &self,
+        artifact_hash: &str,
+    ) -> Result<Vec<TransparencyLog>, TransparencyLogError> {
+        self.process_query(
+            "SELECT * FROM TRANSPARENCYLOG WHERE artifact_hash = ?1 ORDER BY timestamp;",
+            params![artifact_hash],
+        )
+    }
+
+    /// Find the transparency logs that were recorded within the specified
+    /// time range, in seconds since the epoch and including both ends,
+    /// oldest first.
+    pub fn find_transparency_logs_by_time_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<TransparencyLog>, TransparencyLogError> {
+        // sqlite stores signed 64-bit integers
+        let to = to.min(i64::MAX as u64);
+        self.process_query(
+            "SELECT * FROM TRANSPARENCYLOG WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp;",
+            params![from.min(to), to],
+        )
+    }
+
+    /// Find at most `limit` transparency logs that were stored after the
+    /// specified sequence number, in the order they were stored. Every log
+    /// is returned with its sequence number, which only increases, so a
+    /// caller can continue from the last one it received.
+    pub fn find_transparency_logs_after_sequence(
+        &self,
+        sequence: u64,
+        limit: usize,
+    ) -> Result<Vec<(u64, TransparencyLog)>, TransparencyLogError> {
+        // sqlite stores signed 64-bit integers
+        let sequence = sequence.min(i64::MAX as u64);
+        let conn = self.open_db()?;
+        let mut stmt = conn.prepare(
+            "SELECT *, rowid FROM TRANSPARENCYLOG WHERE rowid > ?1 ORDER BY rowid LIMIT ?2;",
+        )?;
+        let rows = stmt.query_map(params![sequence, limit], |row| {
+            Ok((row.get(13)?, transparency_log_from_row(row)?))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
     pub fn write_transparency_log(
         &self,
         transparency_log: &TransparencyLog,
@@ -408,14 +700,10 @@ impl TransparencyLogService {
         package_type: &PackageType,
         package_specific_artifact_id: &str,
     ) -> Result<TransparencyLog, TransparencyLogError> {
-        let query = [
-            "SELECT * FROM TRANSPARENCYLOG WHERE package_type = '",
-            &*package_type.to_string(),
-            "' AND package_specific_artifact_id = '",
-            package_specific_artifact_id,
-            "';",
-        ];
-        let results = self.process_query(query.join("").as_str())?;
+        let results = self.process_query(
+            "SELECT * FROM TRANSPARENCYLOG WHERE package_type = ?1 AND package_specific_artifact_id = ?2;",
+            params![package_type.to_string(), package_specific_artifact_id],
+        )?;
 
         let mut vector: Vec<TransparencyLog> = Vec::new();
         for record in results {
@@ -458,7 +746,7 @@ impl TransparencyLogService {
             package_specific_id,
             "';",
         ];
-        let results = self.process_query(query.join("").as_str())?;
+        let results = self.process_query(query.join("").as_str(), [])?;
 
         let mut vector: Vec<TransparencyLog> = Vec::new();
         for record in results {
@@ -482,7 +770,7 @@ impl TransparencyLogService {
             &Operation::RemoveNode.to_string(),
             "';",
         ];
-        let results = self.process_query(query.join("").as_str())?;
+        let results = self.process_query(query.join("").as_str(), [])?;
 
         let mut vector_added: Vec<TransparencyLog> = Vec::new();
         let mut vector_removed: Vec<TransparencyLog> = Vec::new();
@@ -500,41 +788,15 @@ impl TransparencyLogService {
         Ok(vector_added)
     }
 
-    fn process_query(&self, query: &str) -> Result<Vec<TransparencyLog>, TransparencyLogError> {
+    fn process_query<P: Params>(
+        &self,
+        query: &str,
+        params: P,
+    ) -> Result<Vec<TransparencyLog>, TransparencyLogError> {
         let conn = self.open_db()?;
         let mut stmt = conn.prepare(query)?;
 
-        let transparency_log_records = stmt.query_map([], |row| {
-            Ok(TransparencyLog {
-                id: row.get(0)?,
-                package_type: {
-                    let value: Value = row.get(1)?;
-                    match value {
-                        Value::Text(pt) => Ok(Some(PackageType::from_str(&pt).unwrap())),
-                        Value::Null => Ok(None),
-                        _ => Err(rusqlite::Error::InvalidColumnType(
-                            1,
-                            "package_type".to_owned(),
-                            value.data_type(),
-                        )),
-                    }?
-                },
-                package_specific_id: row.get(2)?,
-                num_artifacts: row.get(3)?,
-                package_specific_artifact_id: row.get(4)?,
-                artifact_hash: row.get(5)?,
-                source_hash: row.get(6)?,
-                artifact_id: row.get(7)?,
-                source_id: row.get(8)?,
-                timestamp: row.get(9)?,
-                operation: {
-                    let op: String = row.get(10)?;
-                    Operation::from_str(&op).unwrap()
-                },
-                node_id: row.get(11)?,
-                node_public_key: row.get(12)?,
-            })
-        })?;
+        let transparency_log_records = stmt.query_map(params, transparency_log_from_row)?;
 
         let mut vector: Vec<TransparencyLog> = Vec::new();
         for transparency_log_record in transparency_log_records {
@@ -545,6 +807,40 @@ impl TransparencyLogService {
     }
 }
 
+// Read a transparency log from a row whose first columns are the columns of
+// the TRANSPARENCYLOG table.
+fn transparency_log_from_row(row: &Row<'_>) -> rusqlite::Result<TransparencyLog> {
+    Ok(TransparencyLog {
+        id: row.get(0)?,
+        package_type: {
+            let value: Value = row.get(1)?;
+            match value {
+                Value::Text(pt) => Ok(Some(PackageType::from_str(&pt).unwrap())),
+                Value::Null => Ok(None),
+                _ => Err(rusqlite::Error::InvalidColumnType(
+                    1,
+                    "package_type".to_owned(),
+                    value.data_type(),
+                )),
+            }?
+        },
+        package_specific_id: row.get(2)?,
+        num_artifacts: row.get(3)?,
+        package_specific_artifact_id: row.get(4)?,
+        artifact_hash: row.get(5)?,
+        source_hash: row.get(6)?,
+        artifact_id: row.get(7)?,
+        source_id: row.get(8)?,
+        timestamp: row.get(9)?,
+        operation: {
+            let op: String = row.get(10)?;
+            Operation::from_str(&op).unwrap()
+        },
+        node_id: row.get(11)?,
+        node_public_key: row.get(12)?,
+    })
+}
+
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
@@ -672,6 +968,112 @@ mod tests {
         test_util::tests::teardown(tmp_dir);
     }
 
+    #[tokio::test]
+    async fn test_find_transparency_logs_by_artifact_hash_and_time_range() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, _) = test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        let mut older_transparency_log = new_artifact_transparency_log_default();
+        older_transparency_log.artifact_hash = "older_hash".to_owned();
+        older_transparency_log.timestamp = 1000;
+        log.write_transparency_log(&older_transparency_log).unwrap();
+        let transparency_log = new_artifact_transparency_log_default();
+        log.write_transparency_log(&transparency_log).unwrap();
+
+        let by_hash = log
+            .find_transparency_logs_by_artifact_hash("older_hash")
+            .unwrap();
+        assert_eq!(by_hash.len(), 1);
+        assert_eq!(by_hash[0].id, older_transparency_log.id);
+        assert!(log
+            .find_transparency_logs_by_artifact_hash("unknown_hash")
+            .unwrap()
+            .is_empty());
+
+        let by_time_range = log.find_transparency_logs_by_time_range(0, 1000).unwrap();
+        assert_eq!(by_time_range.len(), 1);
+        assert_eq!(by_time_range[0].id, older_transparency_log.id);
+        let all = log
+            .find_transparency_logs_by_time_range(0, u64::MAX)
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].id, transparency_log.id);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_find_transparency_logs_after_sequence() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, _) = test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        let first_transparency_log = new_artifact_transparency_log_with_id("first_id");
+        log.write_transparency_log(&first_transparency_log).unwrap();
+        let mut second_transparency_log = new_artifact_transparency_log_with_id("second_id");
+        second_transparency_log.timestamp = 1000;
+        log.write_transparency_log(&second_transparency_log)
+            .unwrap();
+
+        let first_page = log.find_transparency_logs_after_sequence(0, 1).unwrap();
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].1.id, "first_id");
+
+        // Logs are returned in the order they were stored, regardless of
+        // their timestamp.
+        let second_page = log
+            .find_transparency_logs_after_sequence(first_page[0].0, 10)
+            .unwrap();
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].1.id, "second_id");
+        assert!(second_page[0].0 > first_page[0].0);
+
+        assert!(log
+            .find_transparency_logs_after_sequence(second_page[0].0, 10)
+            .unwrap()
+            .is_empty());
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_find_artifact_transparency_log() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, _) = test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        let transparency_log = new_artifact_transparency_log_default();
+
+        let result_write = log.write_transparency_log(&transparency_log);
+        assert!(result_write.is_ok());
+
+        let result_find = log
+            .find_artifact_transparency_log(&transparency_log.artifact_id)
+            .unwrap();
+        assert_eq!(result_find.id, transparency_log.id);
+
+        let find_error = log
+            .find_artifact_transparency_log("unknown_artifact_id")
+            .expect_err("Find artifact transparency log should have failed.");
+        match find_error {
+            TransparencyLogError::LogNotFound { id } => {
+                assert_eq!("unknown_artifact_id".to_owned(), id);
+            }
+            e => {
+                panic!("Invalid Error encountered: {:?}", e);
+            }
+        }
+
+        let injected_id = format!("' OR artifact_id = '{}", transparency_log.artifact_id);
+        assert!(matches!(
+            log.find_artifact_transparency_log(&injected_id),
+            Err(TransparencyLogError::LogNotFound { .. })
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     #[tokio::test]
     async fn test_find_transparency_log_not_found() {
         let tmp_dir = test_util::tests::setup();
@@ -984,6 +1386,125 @@ mod tests {
         test_util::tests::teardown(tmp_dir);
     }
 
+    #[tokio::test]
+    async fn test_revoke_artifact() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, mut blockchain_event_receiver) =
+            test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let revocation = Revocation {
+            artifact_hash: "test_artifact_hash".to_owned(),
+            reason: "malicious".to_owned(),
+        };
+        let document = SignedDocument::new(
+            &Keypair::generate_ed25519(),
+            &serde_json::to_string(&revocation).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(log.find_revocation("test_artifact_hash").unwrap(), None);
+        assert_eq!(log.revoke_artifact(&document).await.unwrap(), revocation);
+        assert_eq!(
+            log.find_revocation("test_artifact_hash").unwrap(),
+            Some(revocation)
+        );
+        assert_eq!(log.find_revocations().unwrap(), vec![document]);
+
+        let mut transparency_log = new_auth_node_transparency_log(Operation::AddArtifact, "");
+        transparency_log.artifact_hash = "test_artifact_hash".to_owned();
+        assert!(matches!(
+            log.verify_artifact_not_revoked(&transparency_log),
+            Err(TransparencyLogError::ArtifactRevoked { .. })
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_write_revocation_with_invalid_signature() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, _) = test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        let mut document = SignedDocument::new(
+            &Keypair::generate_ed25519(),
+            r#"{"artifact_hash":"test_artifact_hash","reason":"malicious"}"#,
+        )
+        .unwrap();
+        document.content = r#"{"artifact_hash":"other_hash","reason":"malicious"}"#.to_owned();
+
+        assert!(matches!(
+            log.write_revocation(&document),
+            Err(TransparencyLogError::InvalidRevocation(_))
+        ));
+        assert_eq!(log.find_revocation("other_hash").unwrap(), None);
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_add_key_rotation_authorizes_successor() {
+        let tmp_dir = test_util::tests::setup();
+
+        let (log, mut blockchain_event_receiver) =
+            test_util::tests::create_transparency_log_service(&tmp_dir);
+
+        tokio::spawn(async move {
+            loop {
+                match blockchain_event_receiver.recv().await {
+                    Some(BlockchainEvent::AddBlock { sender, .. }) => {
+                        let _ = sender.send(Ok(()));
+                    }
+                    _ => panic!("BlockchainEvent must match BlockchainEvent::AddBlock"),
+                }
+            }
+        });
+
+        let previous_keypair = Keypair::generate_ed25519();
+        let next_keypair = Keypair::generate_ed25519();
+        let previous_key = previous_keypair.public().to_peer_id();
+        let next_key = next_keypair.public().to_peer_id();
+        log.write_transparency_log(&new_auth_node_transparency_log(
+            Operation::AddNode,
+            &previous_key.to_string(),
+        ))
+        .unwrap();
+
+        let certificate = KeyRotationCertificate::new(&previous_keypair, &next_keypair).unwrap();
+        log.add_key_rotation(&certificate).await.unwrap();
+
+        assert_eq!(
+            log.get_authorized_nodes().unwrap(),
+            vec![previous_key, next_key]
+        );
+        assert_eq!(
+            log.key_chain(&previous_key).unwrap(),
+            vec![previous_key, next_key]
+        );
+        assert_eq!(log.find_key_rotations().unwrap(), vec![certificate]);
+
+        let other_certificate =
+            KeyRotationCertificate::new(&previous_keypair, &Keypair::generate_ed25519()).unwrap();
+        assert!(matches!(
+            log.add_key_rotation(&other_certificate).await,
+            Err(TransparencyLogError::InvalidKeyRotation(_))
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
     #[tokio::test]
     async fn test_get_authorized_nodes_add() {
         let tmp_dir = test_util::tests::setup();
@@ -14,6 +14,10 @@
    limitations under the License.
 */
 
+pub mod channel_io;
+pub mod disk_space_util;
 pub mod env_util;
 pub mod keypair_util;
+pub mod keystore;
+pub mod rate_limit;
 pub mod test_util;
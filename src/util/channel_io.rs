@@ -0,0 +1,133 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use bytes::Bytes;
+use std::io::{self, Read, Write};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+/// A blocking reader over the chunks that are sent to a channel, e.g. by the
+/// task that receives a request body. It must be used on a blocking thread.
+pub struct ChannelReader {
+    receiver: Receiver<io::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl ChannelReader {
+    pub fn new(receiver: Receiver<io::Result<Bytes>>) -> Self {
+        ChannelReader {
+            receiver,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Ok(0),
+            }
+        }
+        let len = usize::min(buf.len(), self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+/// A blocking writer that sends what is written as chunks to a channel, e.g.
+/// to stream a response body. It must be used on a blocking thread.
+pub struct ChannelWriter {
+    sender: Sender<io::Result<Bytes>>,
+}
+
+impl ChannelWriter {
+    pub fn new(sender: Sender<io::Result<Bytes>>) -> Self {
+        ChannelWriter { sender }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The channel was closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_write_and_read_over_channel() {
+        let (sender, receiver) = mpsc::channel(2);
+        let writer = tokio::task::spawn_blocking(move || {
+            let mut writer = ChannelWriter::new(sender);
+            writer.write_all(b"SAMPLE_")?;
+            writer.write_all(b"DATA")
+        });
+        let reader = tokio::task::spawn_blocking(move || {
+            let mut content = String::new();
+            ChannelReader::new(receiver).read_to_string(&mut content)?;
+            Ok::<_, io::Error>(content)
+        });
+
+        writer.await.unwrap().unwrap();
+        assert_eq!(reader.await.unwrap().unwrap(), "SAMPLE_DATA");
+    }
+
+    #[tokio::test]
+    async fn test_read_error_from_channel() {
+        let (sender, receiver) = mpsc::channel(2);
+        sender.send(Ok(Bytes::from("SAMPLE"))).await.unwrap();
+        sender
+            .send(Err(io::Error::new(io::ErrorKind::Other, "aborted")))
+            .await
+            .unwrap();
+        drop(sender);
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut content = vec![];
+            ChannelReader::new(receiver).read_to_end(&mut content)
+        })
+        .await
+        .unwrap();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_to_closed_channel() {
+        let (sender, receiver) = mpsc::channel(2);
+        drop(receiver);
+
+        let result =
+            tokio::task::spawn_blocking(move || ChannelWriter::new(sender).write_all(b"SAMPLE"))
+                .await
+                .unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,69 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use byte_unit::{Byte, ByteUnit};
+
+/// Parses a human readable disk space, like `10 GB` or `512MiB`, into
+/// a number of bytes. Units are case insensitive and the number may
+/// contain a fractional part. A number without unit is read as bytes.
+pub fn parse_disk_space(input: &str) -> Result<u64, String> {
+    Byte::from_str(input.trim())
+        .map(|byte| byte.get_bytes())
+        .map_err(|_| format!("Invalid disk space: {}", input))
+}
+
+/// Formats a number of bytes as a human readable disk space, using
+/// the largest decimal unit for which the value is at least 1.
+pub fn format_disk_space(bytes: u64) -> String {
+    let adjusted = Byte::from_bytes(bytes).get_appropriate_unit(false);
+    match adjusted.get_unit() {
+        ByteUnit::B => format!("{} {}", bytes, ByteUnit::B),
+        unit => format!("{:.2} {}", adjusted.get_value(), unit),
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_disk_space() {
+        assert_eq!(parse_disk_space("10 GB"), Ok(10_000_000_000));
+        assert_eq!(parse_disk_space("1.5gb"), Ok(1_500_000_000));
+        assert_eq!(parse_disk_space("512 MiB"), Ok(512 * 1024 * 1024));
+        assert_eq!(parse_disk_space("2 TB"), Ok(2_000_000_000_000));
+        assert_eq!(parse_disk_space("100"), Ok(100));
+        assert_eq!(parse_disk_space(" 3 kb "), Ok(3_000));
+    }
+
+    #[test]
+    fn test_parse_invalid_disk_space() {
+        assert!(parse_disk_space("").is_err());
+        assert!(parse_disk_space("GB").is_err());
+        assert!(parse_disk_space("10 XB").is_err());
+        assert!(parse_disk_space("-1 GB").is_err());
+        assert!(parse_disk_space("1,5 GB").is_err());
+    }
+
+    #[test]
+    fn test_format_disk_space() {
+        assert_eq!(format_disk_space(0), "0 B");
+        assert_eq!(format_disk_space(999), "999 B");
+        assert_eq!(format_disk_space(1_500), "1.50 KB");
+        assert_eq!(format_disk_space(10_000_000_000), "10.00 GB");
+    }
+}
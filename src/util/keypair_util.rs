@@ -87,6 +87,12 @@ pub fn load_or_generate_ed25519<P: AsRef<Path>>(storage_path: P) -> identity::Ke
     }
 }
 
+/// Load an existing ed25519 keypair from the specified path, failing when
+/// the file is not a valid keypair.
+pub fn load_ed25519_keypair<P: AsRef<Path>>(storage_path: P) -> io::Result<identity::Keypair> {
+    load_ed25519(storage_path.as_ref()).map(identity::Keypair::Ed25519)
+}
+
 // Load a keypair from the specified path. It only returns a Keypair if all
 // the following conditions are met:
 //
@@ -0,0 +1,449 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use crate::network::public_keys::Jwk;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use libp2p::identity::{self, PublicKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const KEY_FILE_EXTENSION: &str = "key";
+const PUBLIC_KEY_FILE_EXTENSION: &str = "pub";
+const KEY_FILE_VERSION: u8 = 1;
+const KDF_ARGON2ID: &str = "argon2id";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("Invalid key name: {0}")]
+    InvalidName(String),
+    #[error("Key {0} not found in the keystore")]
+    KeyNotFound(String),
+    #[error("Key {0} already exists in the keystore")]
+    KeyAlreadyExists(String),
+    #[error("Unable to decrypt key {0}: the passphrase is wrong or the key file is corrupted")]
+    DecryptionFailure(String),
+    #[error("Unable to encrypt key: {0}")]
+    EncryptionFailure(String),
+    #[error("Failure while accessing the keystore: {0}")]
+    StorageFailure(#[from] io::Error),
+    #[error("Invalid key file: {0}")]
+    InvalidKeyFile(#[from] serde_json::Error),
+    #[error("Unsupported public key: only Ed25519 keys can be exported and imported")]
+    UnsupportedPublicKey,
+}
+
+// The content of a key file: the private key, encrypted with AES-256-GCM
+// using a key that is derived from the passphrase with Argon2id.
+#[derive(Deserialize, Serialize)]
+struct EncryptedKey {
+    version: u8,
+    kdf: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    // The public key in the clear, so that it can be listed and exported
+    // without the passphrase. Absent in key files of older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<Jwk>,
+}
+
+/// A key in the keystore: either a keypair or the public key of another
+/// party that was imported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeystoreEntry {
+    pub name: String,
+    /// Whether the keystore holds the private key.
+    pub private: bool,
+    /// The public key, unknown for keypairs that were stored by an older
+    /// version until they are loaded with the passphrase.
+    pub public_key: Option<PublicKey>,
+}
+
+/// A directory of named private keys that are encrypted with a passphrase.
+/// It holds the identity key of the node and the keys of CLI users.
+#[derive(Clone, Debug)]
+pub struct Keystore {
+    path: PathBuf,
+}
+
+impl Keystore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Keystore {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The names of the keypairs in the keystore, sorted alphabetically.
+    pub fn names(&self) -> Result<Vec<String>, KeystoreError> {
+        self.file_names(KEY_FILE_EXTENSION)
+    }
+
+    /// The keypairs and imported public keys in the keystore, sorted by name.
+    pub fn entries(&self) -> Result<Vec<KeystoreEntry>, KeystoreError> {
+        let mut entries = vec![];
+        for name in self.names()? {
+            entries.push(KeystoreEntry {
+                public_key: self.public_key(&name)?,
+                private: true,
+                name,
+            });
+        }
+        for name in self.file_names(PUBLIC_KEY_FILE_EXTENSION)? {
+            entries.push(KeystoreEntry {
+                public_key: self.public_key(&name)?,
+                private: false,
+                name,
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// Whether the keystore holds a keypair or an imported public key with
+    /// the specified name.
+    pub fn contains(&self, name: &str) -> bool {
+        [self.key_path(name), self.public_key_path(name)]
+            .into_iter()
+            .any(|path| path.map(|path| path.exists()).unwrap_or(false))
+    }
+
+    /// The public key with the specified name, of a keypair or imported.
+    /// Returns `None` for keypairs of older versions that don't store their
+    /// public key in the clear.
+    pub fn public_key(&self, name: &str) -> Result<Option<PublicKey>, KeystoreError> {
+        let public_key_path = self.public_key_path(name)?;
+        if public_key_path.exists() {
+            let jwk: Jwk = serde_json::from_slice(&fs::read(public_key_path)?)?;
+            return jwk
+                .to_public_key()
+                .map(Some)
+                .ok_or(KeystoreError::UnsupportedPublicKey);
+        }
+        let content = match fs::read(self.key_path(name)?) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(KeystoreError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let encrypted_key: EncryptedKey = serde_json::from_slice(&content)?;
+        Ok(encrypted_key.public_key.and_then(|jwk| jwk.to_public_key()))
+    }
+
+    /// Store the public key of another party under the specified name, e.g.
+    /// to share it with the node as a trusted key later.
+    pub fn import_public_key(
+        &self,
+        name: &str,
+        public_key: &PublicKey,
+    ) -> Result<(), KeystoreError> {
+        let public_key_path = self.public_key_path(name)?;
+        if self.contains(name) {
+            return Err(KeystoreError::KeyAlreadyExists(name.to_owned()));
+        }
+        let jwk = Jwk::from_public_key(public_key).ok_or(KeystoreError::UnsupportedPublicKey)?;
+        fs::create_dir_all(&self.path)?;
+        fs::write(public_key_path, serde_json::to_vec_pretty(&jwk)?)?;
+        Ok(())
+    }
+
+    /// Store a keypair under the specified name, encrypted with the passphrase.
+    pub fn store(
+        &self,
+        name: &str,
+        keypair: &identity::Keypair,
+        passphrase: &str,
+    ) -> Result<(), KeystoreError> {
+        let key_path = self.key_path(name)?;
+        if self.contains(name) {
+            return Err(KeystoreError::KeyAlreadyExists(name.to_owned()));
+        }
+        let identity::Keypair::Ed25519(ed25519_keypair) = keypair;
+
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher(passphrase, &salt)?
+            .encrypt(Nonce::from_slice(&nonce), ed25519_keypair.encode().as_ref())
+            .map_err(|e| KeystoreError::EncryptionFailure(e.to_string()))?;
+
+        let encrypted_key = EncryptedKey {
+            version: KEY_FILE_VERSION,
+            kdf: KDF_ARGON2ID.to_owned(),
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+            public_key: Jwk::from_public_key(&keypair.public()),
+        };
+        fs::create_dir_all(&self.path)?;
+        write_private_file(&key_path, &serde_json::to_vec_pretty(&encrypted_key)?)?;
+        Ok(())
+    }
+
+    /// Load and decrypt the keypair with the specified name.
+    pub fn load(&self, name: &str, passphrase: &str) -> Result<identity::Keypair, KeystoreError> {
+        let key_path = self.key_path(name)?;
+        let content = match fs::read(&key_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(KeystoreError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let encrypted_key: EncryptedKey = serde_json::from_slice(&content)?;
+        let decryption_failure = || KeystoreError::DecryptionFailure(name.to_owned());
+        if encrypted_key.version != KEY_FILE_VERSION || encrypted_key.kdf != KDF_ARGON2ID {
+            return Err(decryption_failure());
+        }
+
+        let salt = BASE64
+            .decode(&encrypted_key.salt)
+            .map_err(|_| decryption_failure())?;
+        let nonce = BASE64
+            .decode(&encrypted_key.nonce)
+            .map_err(|_| decryption_failure())?;
+        let ciphertext = BASE64
+            .decode(&encrypted_key.ciphertext)
+            .map_err(|_| decryption_failure())?;
+        if nonce.len() != NONCE_LENGTH {
+            return Err(decryption_failure());
+        }
+        let mut plaintext = cipher(passphrase, &salt)?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| decryption_failure())?;
+
+        identity::ed25519::Keypair::decode(&mut plaintext)
+            .map(identity::Keypair::Ed25519)
+            .map_err(|_| decryption_failure())
+    }
+
+    /// Generate a new ed25519 keypair and store it under the specified name.
+    pub fn generate(
+        &self,
+        name: &str,
+        passphrase: &str,
+    ) -> Result<identity::Keypair, KeystoreError> {
+        let keypair = identity::Keypair::generate_ed25519();
+        self.store(name, &keypair, passphrase)?;
+        Ok(keypair)
+    }
+
+    /// Load the keypair with the specified name, or generate a new one when
+    /// the keystore does not contain it yet.
+    pub fn load_or_generate(
+        &self,
+        name: &str,
+        passphrase: &str,
+    ) -> Result<identity::Keypair, KeystoreError> {
+        match self.load(name, passphrase) {
+            Err(KeystoreError::KeyNotFound(_)) => self.generate(name, passphrase),
+            result => result,
+        }
+    }
+
+    /// Remove the keypair or imported public key with the specified name.
+    pub fn remove(&self, name: &str) -> Result<(), KeystoreError> {
+        let public_key_path = self.public_key_path(name)?;
+        if public_key_path.exists() {
+            return Ok(fs::remove_file(public_key_path)?);
+        }
+        match fs::remove_file(self.key_path(name)?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(KeystoreError::KeyNotFound(name.to_owned()))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    // The names of the files in the keystore with the specified extension.
+    fn file_names(&self, file_extension: &str) -> Result<Vec<String>, KeystoreError> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut names = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) == Some(file_extension) {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn key_path(&self, name: &str) -> Result<PathBuf, KeystoreError> {
+        self.file_path(name, KEY_FILE_EXTENSION)
+    }
+
+    fn public_key_path(&self, name: &str) -> Result<PathBuf, KeystoreError> {
+        self.file_path(name, PUBLIC_KEY_FILE_EXTENSION)
+    }
+
+    fn file_path(&self, name: &str, file_extension: &str) -> Result<PathBuf, KeystoreError> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+        if !valid {
+            return Err(KeystoreError::InvalidName(name.to_owned()));
+        }
+        Ok(self.path.join(format!("{}.{}", name, file_extension)))
+    }
+}
+
+// Derive the AES-256-GCM cipher from the passphrase and salt with Argon2id.
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, KeystoreError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| KeystoreError::EncryptionFailure(e.to_string()))?;
+    Ok(Aes256Gcm::new(Key::from_slice(&key)))
+}
+
+// Write a file that is only readable by the current user.
+fn write_private_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_util;
+
+    #[test]
+    fn test_store_and_load_keypair() {
+        let tmp_dir = test_util::tests::setup();
+        let keystore = Keystore::new(tmp_dir.join("keystore"));
+        assert!(keystore.names().unwrap().is_empty());
+
+        let keypair = identity::Keypair::generate_ed25519();
+        keystore.store("node", &keypair, "secret").unwrap();
+
+        assert!(keystore.contains("node"));
+        assert_eq!(keystore.names().unwrap(), vec!["node"]);
+        assert_eq!(
+            keystore
+                .load("node", "secret")
+                .unwrap()
+                .public()
+                .to_peer_id(),
+            keypair.public().to_peer_id()
+        );
+        assert!(matches!(
+            keystore.load("node", "wrong"),
+            Err(KeystoreError::DecryptionFailure(_))
+        ));
+        assert!(matches!(
+            keystore.store("node", &keypair, "secret"),
+            Err(KeystoreError::KeyAlreadyExists(_))
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_load_or_generate_and_remove_keypair() {
+        let tmp_dir = test_util::tests::setup();
+        let keystore = Keystore::new(tmp_dir.join("keystore"));
+
+        let keypair = keystore.load_or_generate("user-1", "secret").unwrap();
+        assert_eq!(
+            keystore
+                .load_or_generate("user-1", "secret")
+                .unwrap()
+                .public(),
+            keypair.public()
+        );
+
+        keystore.remove("user-1").unwrap();
+        assert!(matches!(
+            keystore.load("user-1", "secret"),
+            Err(KeystoreError::KeyNotFound(_))
+        ));
+        assert!(matches!(
+            keystore.load("../node", "secret"),
+            Err(KeystoreError::InvalidName(_))
+        ));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+
+    #[test]
+    fn test_import_and_list_public_keys() {
+        let tmp_dir = test_util::tests::setup();
+        let keystore = Keystore::new(tmp_dir.join("keystore"));
+
+        let keypair = keystore.generate("user-1", "secret").unwrap();
+        let other_keypair = identity::Keypair::generate_ed25519();
+        keystore
+            .import_public_key("partner", &other_keypair.public())
+            .unwrap();
+        assert!(matches!(
+            keystore.import_public_key("user-1", &other_keypair.public()),
+            Err(KeystoreError::KeyAlreadyExists(_))
+        ));
+
+        assert_eq!(keystore.names().unwrap(), vec!["user-1"]);
+        assert_eq!(
+            keystore.entries().unwrap(),
+            vec![
+                KeystoreEntry {
+                    name: String::from("partner"),
+                    private: false,
+                    public_key: Some(other_keypair.public()),
+                },
+                KeystoreEntry {
+                    name: String::from("user-1"),
+                    private: true,
+                    public_key: Some(keypair.public()),
+                },
+            ]
+        );
+        assert!(matches!(
+            keystore.load("partner", "secret"),
+            Err(KeystoreError::KeyNotFound(_))
+        ));
+
+        keystore.remove("partner").unwrap();
+        assert!(!keystore.contains("partner"));
+
+        test_util::tests::teardown(tmp_dir);
+    }
+}
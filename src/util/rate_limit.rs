@@ -0,0 +1,282 @@
+/*
+   Copyright 2021 JFrog Ltd
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use warp::hyper::Body;
+use warp::reject::Reject;
+use warp::{Filter, Rejection};
+
+// The number of clients above which idle clients are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// A request was rejected because a rate limit or the concurrency cap was
+/// reached. The client may try again after the specified number of seconds.
+#[derive(Debug)]
+pub struct TooManyRequests {
+    pub retry_after_secs: u64,
+}
+
+impl Reject for TooManyRequests {}
+
+/// The configured limits and how often they were hit, as shown in the status.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// The number of requests per minute that each client may send.
+    pub requests_per_minute: Option<u32>,
+    /// The number of artifact downloads that are served concurrently.
+    pub max_concurrent_downloads: Option<usize>,
+    pub active_downloads: usize,
+    pub rejected_requests: u64,
+}
+
+// A token bucket that holds up to `requests_per_minute` tokens and is
+// refilled continuously.
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// The configured limits, where a limit of 0 means unlimited.
+#[derive(Debug, Default)]
+struct Limits {
+    requests_per_minute: Option<u32>,
+    max_concurrent_downloads: Option<usize>,
+    downloads: Option<Arc<Semaphore>>,
+}
+
+impl Limits {
+    fn new(requests_per_minute: Option<u32>, max_concurrent_downloads: Option<usize>) -> Self {
+        let max_concurrent_downloads = max_concurrent_downloads.filter(|limit| *limit > 0);
+        Limits {
+            requests_per_minute: requests_per_minute.filter(|limit| *limit > 0),
+            max_concurrent_downloads,
+            downloads: max_concurrent_downloads.map(|limit| Arc::new(Semaphore::new(limit))),
+        }
+    }
+}
+
+/// Per-client request rate limits and a global cap on concurrent artifact
+/// downloads, shared by all clones. Both are unlimited by default.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimits {
+    limits: Arc<RwLock<Limits>>,
+    buckets: Arc<Mutex<HashMap<IpAddr, TokenBucket>>>,
+    rejected_requests: Arc<AtomicU64>,
+}
+
+impl RateLimits {
+    pub fn new(requests_per_minute: Option<u32>, max_concurrent_downloads: Option<usize>) -> Self {
+        RateLimits {
+            limits: Arc::new(RwLock::new(Limits::new(
+                requests_per_minute,
+                max_concurrent_downloads,
+            ))),
+            ..Default::default()
+        }
+    }
+
+    /// Change the limits of all clones while the node runs. Downloads that
+    /// are in progress don't count towards a changed download cap.
+    pub fn reconfigure(
+        &self,
+        requests_per_minute: Option<u32>,
+        max_concurrent_downloads: Option<usize>,
+    ) {
+        let new_limits = Limits::new(requests_per_minute, max_concurrent_downloads);
+        let mut limits = self.limits.write().unwrap();
+        limits.requests_per_minute = new_limits.requests_per_minute;
+        if limits.max_concurrent_downloads != new_limits.max_concurrent_downloads {
+            limits.max_concurrent_downloads = new_limits.max_concurrent_downloads;
+            limits.downloads = new_limits.downloads;
+        }
+    }
+
+    /// Take a token from the bucket of the client. Requests of which the
+    /// client address is unknown are not limited.
+    pub fn check_request(&self, client: Option<IpAddr>) -> Result<(), TooManyRequests> {
+        let requests_per_minute = self.limits.read().unwrap().requests_per_minute;
+        let (requests_per_minute, client) = match (requests_per_minute, client) {
+            (Some(requests_per_minute), Some(client)) => (requests_per_minute as f64, client),
+            _ => return Ok(()),
+        };
+        let tokens_per_sec = requests_per_minute / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated_at).as_secs_f64() * tokens_per_sec
+                    < requests_per_minute
+            });
+        }
+        let bucket = buckets.entry(client).or_insert(TokenBucket {
+            tokens: requests_per_minute,
+            updated_at: now,
+        });
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated_at).as_secs_f64() * tokens_per_sec)
+            .min(requests_per_minute);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            self.rejected_requests.fetch_add(1, Ordering::Relaxed);
+            Err(TooManyRequests {
+                retry_after_secs: ((1.0 - bucket.tokens) / tokens_per_sec).ceil() as u64,
+            })
+        }
+    }
+
+    /// Reserve a slot for an artifact download, which is released when the
+    /// returned permit is dropped.
+    pub fn acquire_download(&self) -> Result<Option<OwnedSemaphorePermit>, TooManyRequests> {
+        let downloads = self.limits.read().unwrap().downloads.clone();
+        match downloads {
+            Some(downloads) => match downloads.try_acquire_owned() {
+                Ok(permit) => Ok(Some(permit)),
+                Err(_) => {
+                    self.rejected_requests.fetch_add(1, Ordering::Relaxed);
+                    Err(TooManyRequests {
+                        retry_after_secs: 1,
+                    })
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let limits = self.limits.read().unwrap();
+        RateLimitStatus {
+            requests_per_minute: limits.requests_per_minute,
+            max_concurrent_downloads: limits.max_concurrent_downloads,
+            active_downloads: match (&limits.downloads, limits.max_concurrent_downloads) {
+                (Some(downloads), Some(limit)) => limit - downloads.available_permits(),
+                _ => 0,
+            },
+            rejected_requests: self.rejected_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The body of an artifact download, which holds the download permit until
+/// the body was sent to the client or the client went away. Warp sends the
+/// body after the handler returned, so a permit that is dropped by the
+/// handler would not limit the transfers.
+pub fn download_body(content: Vec<u8>, permit: Option<OwnedSemaphorePermit>) -> Body {
+    let chunks = futures::stream::unfold(
+        (Some(Bytes::from(content)), permit),
+        |(content, permit)| async move {
+            // the permit is dropped with the state once the content was sent
+            content.map(|content| (Ok::<_, Infallible>(content), (None, permit)))
+        },
+    );
+    Body::wrap_stream(chunks)
+}
+
+/// Reject requests of clients that exceed their rate limit.
+pub fn rate_limit(rate_limits: RateLimits) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and_then(move |remote_addr: Option<SocketAddr>| {
+            let result = rate_limits.check_request(remote_addr.map(|addr| addr.ip()));
+            async move { result.map_err(warp::reject::custom) }
+        })
+        .untuple_one()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_request_per_client() {
+        let rate_limits = RateLimits::new(Some(2), None);
+        let client = Some("10.0.0.1".parse().unwrap());
+        let other_client = Some("10.0.0.2".parse().unwrap());
+
+        assert!(rate_limits.check_request(client).is_ok());
+        assert!(rate_limits.check_request(client).is_ok());
+        let too_many_requests = rate_limits.check_request(client).unwrap_err();
+        assert_eq!(too_many_requests.retry_after_secs, 30);
+        assert!(rate_limits.check_request(other_client).is_ok());
+        assert!(rate_limits.check_request(None).is_ok());
+        assert_eq!(rate_limits.status().rejected_requests, 1);
+    }
+
+    #[test]
+    fn test_acquire_download() {
+        let rate_limits = RateLimits::new(None, Some(1));
+
+        let permit = rate_limits.acquire_download().unwrap();
+        assert!(permit.is_some());
+        assert_eq!(rate_limits.status().active_downloads, 1);
+        assert!(rate_limits.acquire_download().is_err());
+
+        drop(permit);
+        assert!(rate_limits.acquire_download().unwrap().is_some());
+        assert!(RateLimits::default().acquire_download().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let rate_limits = RateLimits::new(Some(1), None);
+        let clone = rate_limits.clone();
+        let client = Some("10.0.0.1".parse().unwrap());
+
+        assert!(rate_limits.check_request(client).is_ok());
+        assert!(rate_limits.check_request(client).is_err());
+
+        clone.reconfigure(None, Some(1));
+        assert!(rate_limits.check_request(client).is_ok());
+        let permit = rate_limits.acquire_download().unwrap();
+        assert!(permit.is_some());
+        assert!(rate_limits.acquire_download().is_err());
+        assert_eq!(
+            rate_limits.status(),
+            RateLimitStatus {
+                requests_per_minute: None,
+                max_concurrent_downloads: Some(1),
+                active_downloads: 1,
+                rejected_requests: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_body_holds_permit_until_sent() {
+        let rate_limits = RateLimits::new(None, Some(1));
+
+        let permit = rate_limits.acquire_download().unwrap();
+        let body = download_body(b"SAMPLE_DATA".to_vec(), permit);
+        assert_eq!(rate_limits.status().active_downloads, 1);
+
+        let content = warp::hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(&content[..], b"SAMPLE_DATA");
+        assert_eq!(rate_limits.status().active_downloads, 0);
+    }
+}